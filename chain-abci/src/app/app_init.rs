@@ -1,6 +1,7 @@
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
 use std::mem;
+use std::sync::{Arc, Mutex};
 
 use abci::*;
 use log::{info, warn};
@@ -8,8 +9,16 @@ use parity_scale_codec::{Decode, Encode};
 use protobuf::Message;
 use serde::{Deserialize, Serialize};
 
+use crate::app::commit_hook::CommitHook;
+use crate::app::halt::CircuitBreaker;
+use crate::app::invariants::{CoinBalances, CoinFlows, ReconciliationReport};
+use crate::app::metrics::AppMetrics;
+use crate::app::snapshot::SnapshotManager;
+use crate::app::tx_handler::TxRegistry;
+use crate::app::tx_query::TxQueryEndpoints;
+use crate::app::warmup::Readiness;
 use crate::enclave_bridge::EnclaveProxy;
-use crate::staking::StakingTable;
+use crate::staking::{LegacyStakingTable, StakingTable};
 use chain_core::common::MerkleTree;
 use chain_core::common::Timespec;
 use chain_core::common::{H256, HASH_SIZE_256};
@@ -21,14 +30,24 @@ use chain_core::init::config::NetworkParameters;
 use chain_core::state::account::StakedStateDestination;
 use chain_core::state::account::{CouncilNodeMeta, StakedStateAddress};
 use chain_core::state::tendermint::{BlockHeight, TendermintVotePower};
+use chain_core::state::tx_result::TxResult;
 use chain_core::state::{ChainState, RewardsPoolState};
+use chain_core::tx::data::TxId;
 use chain_core::tx::TxAux;
 use chain_core::ChainInfo;
 use chain_storage::buffer::{
-    flush_storage, GetStaking, KVBuffer, StakingBuffer, StoreKV, StoreStaking,
+    flush_storage, BufferStore, GetStaking, KVBuffer, StakingBuffer, StoreKV, StoreStaking,
 };
-use chain_storage::jellyfish::{compute_staking_root, sum_staking_coins, StakingGetter, Version};
-use chain_storage::{Storage, StoredChainState};
+use chain_storage::jellyfish::{
+    compute_staking_root, root_hash, sum_staking_coins, StakingGetter, Version,
+};
+use chain_storage::scrub::ScrubMetrics;
+use chain_storage::snapshot::SnapshotRestorer;
+use chain_storage::{LookupItem, Storage, StoredChainState, RECENT_STATES_KEPT};
+
+/// Format of the encoded `ChainNodeState` stored by this version
+/// (format 0 is the legacy layout stored without a recorded format, see `LegacyChainNodeState`)
+pub const CHAIN_NODE_STATE_FORMAT: u32 = 1;
 
 /// ABCI app state snapshot
 #[derive(Serialize, Deserialize, Clone, Encode, Decode)]
@@ -55,6 +74,9 @@ pub struct ChainNodeState {
     /// Record the biggest enclave ISVSVN (Security Version Number of the Enclave) we've seen in
     /// keypackage so far
     pub enclave_isv_svn: u16,
+    /// app version (state machine rules) the current block is executed with,
+    /// set from the genesis configuration and raised at the scheduled upgrade heights
+    pub app_version: u64,
 
     /// The parts of states which involved in computing app_hash
     pub top_level: ChainState,
@@ -76,6 +98,78 @@ impl StoredChainState for ChainNodeState {
     fn get_staking_version(&self) -> Version {
         self.staking_version
     }
+
+    fn get_format_version(&self) -> u32 {
+        CHAIN_NODE_STATE_FORMAT
+    }
+}
+
+/// `ChainNodeState` stored in the format 0, before the staking table remembered
+/// the processed evidences
+#[derive(Decode)]
+struct LegacyChainNodeState {
+    last_block_height: BlockHeight,
+    last_apphash: H256,
+    block_time: Timespec,
+    block_height: BlockHeight,
+    staking_table: LegacyStakingTable,
+    genesis_time: Timespec,
+    max_evidence_age: Timespec,
+    staking_version: Version,
+    utxo_coins: Coin,
+    enclave_isv_svn: u16,
+    top_level: ChainState,
+}
+
+impl From<LegacyChainNodeState> for ChainNodeState {
+    fn from(legacy: LegacyChainNodeState) -> Self {
+        ChainNodeState {
+            last_block_height: legacy.last_block_height,
+            last_apphash: legacy.last_apphash,
+            block_time: legacy.block_time,
+            block_height: legacy.block_height,
+            staking_table: legacy.staking_table.into(),
+            genesis_time: legacy.genesis_time,
+            max_evidence_age: legacy.max_evidence_age,
+            staking_version: legacy.staking_version,
+            utxo_coins: legacy.utxo_coins,
+            enclave_isv_svn: legacy.enclave_isv_svn,
+            // the legacy binaries only ran the initial rules
+            app_version: chain_core::INITIAL_APP_VERSION,
+            top_level: legacy.top_level,
+        }
+    }
+}
+
+/// Rewrites the last committed state if it was stored in an older format,
+/// so the rest of the app only decodes the current one
+fn migrate_stored_state(storage: &mut Storage) {
+    let format = storage.get_state_format();
+    if format >= CHAIN_NODE_STATE_FORMAT {
+        return;
+    }
+    let data = match storage.get_last_app_state() {
+        Some(data) => data,
+        None => return,
+    };
+    info!(
+        "migrating the last app state from format {} to {}",
+        format, CHAIN_NODE_STATE_FORMAT
+    );
+    let state: ChainNodeState = LegacyChainNodeState::decode(&mut data.as_slice())
+        .expect("deserialize legacy app state")
+        .into();
+    let write_history_states = storage
+        .get_historical_state(state.last_block_height)
+        .is_some();
+    let mut buffer = KVBuffer::new();
+    chain_storage::store_chain_state(
+        &mut BufferStore::new(&*storage, &mut buffer),
+        &state,
+        state.last_block_height,
+        write_history_states,
+    );
+    flush_storage(storage, buffer).expect("write migrated app state");
 }
 
 impl ChainNodeState {
@@ -89,6 +183,7 @@ impl ChainNodeState {
         network_params: NetworkParameters,
         staking_table: StakingTable,
         enclave_isv_svn: u16,
+        app_version: u64,
     ) -> Self {
         ChainNodeState {
             last_block_height: BlockHeight::genesis(),
@@ -101,6 +196,7 @@ impl ChainNodeState {
             staking_version: 0,
             utxo_coins: Coin::zero(),
             enclave_isv_svn,
+            app_version,
             top_level: ChainState {
                 account_root,
                 rewards_pool,
@@ -136,12 +232,56 @@ pub struct ChainNodeApp<T: EnclaveProxy> {
     pub mempool_state: Option<ChainNodeState>,
     /// proxy for processing transaction validation requests
     pub tx_validator: T,
+    /// handlers of the accepted transaction kinds
+    pub tx_registry: TxRegistry<T>,
     /// was rewards pool updated in the current block?
     pub rewards_pool_updated: bool,
+    /// raw block filter obtained in EndBlock (included in the compact block on Commit)
+    pub block_filter: Option<Vec<u8>>,
     /// address of tx query enclave to supply to clients (if any)
     pub tx_query_address: Option<String>,
+    /// health-checked tx query enclave endpoints to supply to clients instead of
+    /// `tx_query_address` (if set)
+    pub tx_query_endpoints: Option<Arc<TxQueryEndpoints>>,
     /// Address of TDBE to supply to clients
     pub tdbe_address: String,
+    /// plugins invoked after each successful commit
+    pub commit_hooks: Vec<Box<dyn CommitHook>>,
+    /// events emitted in the current block (only collected if there are commit hooks)
+    pub block_events: Vec<Event>,
+    /// execution results of transactions delivered in the current block (stored on Commit)
+    pub tx_results: Vec<(TxId, TxResult)>,
+    /// number of transactions delivered in the current block (including rejected ones)
+    pub delivered_tx_count: u32,
+    /// coins moved by the current block (reconciled on Commit)
+    pub block_flows: CoinFlows,
+    /// balances before the current block (set on BeginBlock)
+    pub block_start_balances: Option<CoinBalances>,
+    /// reconciliation of the last committed block (returned by the "reconciliation" query)
+    pub last_reconciliation: Option<ReconciliationReport>,
+    /// counters of the trie scrubber (returned by the "scrub" query, if it runs)
+    pub scrub_metrics: Option<Arc<ScrubMetrics>>,
+    /// metrics exposed to Prometheus (if enabled)
+    pub metrics: Option<Arc<AppMetrics>>,
+    /// exported state snapshots (if enabled)
+    pub snapshots: Option<SnapshotManager>,
+    /// snapshot being restored (state sync of a new node)
+    pub snapshot_restore: Option<SnapshotRestorer>,
+    /// number of the latest staking trie versions kept when pruning (pruning disabled if not set)
+    pub prune_keep_recent: Option<u64>,
+    /// maximum size of transactions accepted in the mempool (not limited if not set)
+    pub max_mempool_tx_size: Option<usize>,
+    /// index committed transactions by the staked state addresses they affect
+    /// (returned by the "txs_by_address" query)
+    pub address_index: bool,
+    /// block height => app version the state machine rules are upgraded to from that block
+    pub app_version_upgrades: BTreeMap<BlockHeight, u64>,
+    /// held while a block is committed (the shutdown waits for it)
+    pub commit_lock: Arc<Mutex<()>>,
+    /// warm-up progress (the node answers Info requests once it's ready)
+    pub readiness: Arc<Readiness>,
+    /// halt status on invariant violations (reported by the health endpoint)
+    pub circuit_breaker: Arc<CircuitBreaker>,
 
     /// consensus buffer of staking merkle trie storage
     pub staking_buffer: StakingBuffer,
@@ -240,6 +380,94 @@ pub fn init_app_hash(conf: &InitConfig, genesis_time: Timespec) -> H256 {
     )
 }
 
+/// Checks that the last state matches the app hash, staking version and staking trie committed
+/// with it, a mismatch means that the storage was modified outside of a commit (e.g. corrupted)
+fn check_committed_state(storage: &Storage, state: &ChainNodeState) -> Result<(), String> {
+    let height = state.last_block_height;
+    if storage.get_historical_app_hash(height) != Some(state.last_apphash) {
+        return Err(format!("app hash of height {} doesn't match", height));
+    }
+    if storage.get_historical_staking_version(height) != Some(state.staking_version) {
+        return Err(format!(
+            "staking version of height {} doesn't match",
+            height
+        ));
+    }
+    match root_hash(storage, state.staking_version) {
+        Ok(Some(root)) if root == state.top_level.account_root => {}
+        _ => {
+            return Err(format!(
+                "staking trie root of version {} doesn't match",
+                state.staking_version
+            ))
+        }
+    }
+    // every commit stores the tree with the state (and state sync restores the one of the
+    // snapshot height with it), only the genesis state has no transactions merkle tree
+    let tree = match storage.lookup_item(LookupItem::TxsMerkle, &state.last_apphash) {
+        Some(data) => MerkleTree::decode(&mut data.as_slice())
+            .map_err(|e| format!("invalid transactions merkle tree: {}", e))?,
+        None if height == BlockHeight::genesis() => MerkleTree::empty(),
+        None => {
+            return Err(format!(
+                "transactions merkle tree of height {} is missing",
+                height
+            ))
+        }
+    };
+    let app_hash = compute_app_hash(
+        &tree,
+        &state.top_level.account_root,
+        &state.top_level.rewards_pool,
+        &state.top_level.network_params,
+    );
+    if app_hash != state.last_apphash {
+        return Err(format!(
+            "app hash computed from the state of height {} doesn't match",
+            height
+        ));
+    }
+    Ok(())
+}
+
+/// Makes the newest of the recent committed states which is consistent with the storage
+/// (see `check_committed_state`) the last state, so Tendermint replays the later blocks.
+/// The UTxO set isn't versioned, a replay diverging because of it fails the app hash check.
+fn rollback_committed_state(
+    storage: &mut Storage,
+    inconsistent: &ChainNodeState,
+) -> Option<ChainNodeState> {
+    let last_height = inconsistent.last_block_height.value();
+    for height in (last_height.saturating_sub(RECENT_STATES_KEPT)..last_height).rev() {
+        let height = BlockHeight::new(height);
+        let state = match storage
+            .get_recent_state(height)
+            .and_then(|data| ChainNodeState::decode(&mut data.as_slice()).ok())
+        {
+            Some(state) => state,
+            None => continue,
+        };
+        if let Err(e) = check_committed_state(storage, &state) {
+            warn!(
+                "committed state of height {} is inconsistent: {}",
+                height, e
+            );
+            continue;
+        }
+        let write_history_states = storage.get_historical_state(height).is_some();
+        let mut buffer = KVBuffer::new();
+        chain_storage::store_chain_state(
+            &mut BufferStore::new(&*storage, &mut buffer),
+            &state,
+            height,
+            write_history_states,
+        );
+        flush_storage(storage, buffer).expect("write rolled back app state");
+        return Some(state);
+    }
+    None
+}
+
 impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
     fn restore_from_storage(
         tx_validator: T,
@@ -277,9 +505,30 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             last_state: Some(last_app_state.clone()),
             mempool_state: Some(last_app_state),
             tx_validator,
+            tx_registry: TxRegistry::default(),
             rewards_pool_updated: false,
+            block_filter: None,
             tx_query_address,
+            tx_query_endpoints: None,
             tdbe_address,
+            commit_hooks: Vec::new(),
+            block_events: Vec::new(),
+            tx_results: Vec::new(),
+            delivered_tx_count: 0,
+            block_flows: CoinFlows::default(),
+            block_start_balances: None,
+            last_reconciliation: None,
+            scrub_metrics: None,
+            metrics: None,
+            snapshots: None,
+            snapshot_restore: None,
+            prune_keep_recent: None,
+            max_mempool_tx_size: None,
+            address_index: false,
+            app_version_upgrades: BTreeMap::new(),
+            commit_lock: Arc::new(Mutex::new(())),
+            readiness: Arc::new(Readiness::default()),
+            circuit_breaker: Arc::new(CircuitBreaker::default()),
 
             staking_buffer: HashMap::new(),
             mempool_staking_buffer: HashMap::new(),
@@ -312,10 +561,22 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         let chain_hex_id = hex::decode(&chain_id[chain_id.len() - 2..])
             .expect("failed to decode two last hex digits in chain ID")[0];
 
+        migrate_stored_state(&mut storage);
         if let Some(data) = storage.get_last_app_state() {
             info!("last app state stored");
             let mut last_state =
                 ChainNodeState::decode(&mut data.as_slice()).expect("deserialize app state");
+            if let Err(e) = check_committed_state(&storage, &last_state) {
+                warn!("inconsistent last committed state: {}", e);
+                last_state = match rollback_committed_state(&mut storage, &last_state) {
+                    Some(state) => state,
+                    None => panic!("inconsistent last committed state: {} and no consistent recent state (restore the data directory from a backup or a state sync snapshot)", e),
+                };
+                warn!(
+                    "rolled back to the state of height {}, the later blocks are replayed",
+                    last_state.last_block_height
+                );
+            }
 
             // if tx-query address wasn't provided first time,
             // then it shouldn't be provided on another run, and vice versa
@@ -382,9 +643,30 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                 last_state: None,
                 mempool_state: None,
                 tx_validator,
+                tx_registry: TxRegistry::default(),
                 rewards_pool_updated: false,
+                block_filter: None,
                 tx_query_address,
+                tx_query_endpoints: None,
                 tdbe_address,
+                commit_hooks: Vec::new(),
+                block_events: Vec::new(),
+                tx_results: Vec::new(),
+                delivered_tx_count: 0,
+                block_flows: CoinFlows::default(),
+                block_start_balances: None,
+                last_reconciliation: None,
+                scrub_metrics: None,
+                metrics: None,
+                snapshots: None,
+                snapshot_restore: None,
+                prune_keep_recent: None,
+                max_mempool_tx_size: None,
+                address_index: false,
+                app_version_upgrades: BTreeMap::new(),
+                commit_lock: Arc::new(Mutex::new(())),
+                readiness: Arc::new(Readiness::default()),
+                circuit_breaker: Arc::new(CircuitBreaker::default()),
 
                 staking_buffer: HashMap::new(),
                 mempool_staking_buffer: HashMap::new(),
@@ -481,6 +763,7 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             network_params,
             staking_table,
             state.isv_svn,
+            conf.app_version,
         );
         chain_storage::store_genesis_state(
             &mut kv_store!(self),
@@ -494,6 +777,12 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         ResponseInitChain::new()
     }
 
+    /// Upgrades the state machine rules to `app_version` from the block at `height`
+    /// (all the nodes of the network need to schedule the same upgrades)
+    pub fn schedule_app_version_upgrade(&mut self, height: BlockHeight, app_version: u64) {
+        self.app_version_upgrades.insert(height, app_version);
+    }
+
     pub fn staking_store(&mut self, buffer_type: BufferType) -> impl StoreStaking + '_ {
         let version = self
             .last_state
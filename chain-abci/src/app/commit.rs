@@ -1,82 +1,101 @@
 use std::mem;
+use std::time::Instant;
 
+use super::commit_hook::{CommitHook, CommittedBlock};
+use super::shutdown::lock_commits;
+use super::tx_handler::{KVStore, TxKind, TxRegistry};
 use super::ChainNodeApp;
 use crate::enclave_bridge::EnclaveProxy;
 use abci::*;
 use chain_core::common::MerkleTree;
 use chain_core::compute_app_hash;
-use chain_core::tx::data::input::{TxoPointer, TxoSize};
+use chain_core::state::compact_block::{CompactBlock, CompactTx};
+use chain_core::state::tendermint::BlockHeight;
 use chain_core::tx::data::TxId;
-use chain_core::tx::{TxAux, TxEnclaveAux, TxPublicAux};
-use chain_storage::buffer::{flush_storage, StoreKV};
-use chain_storage::jellyfish::flush_stakings;
+use chain_core::tx::{TxAux, TxEnclaveAux};
+use chain_storage::buffer::flush_storage;
+use chain_storage::jellyfish::{flush_stakings, retain_changed_stakings, Version};
+use chain_storage::prune::{least_readable_version, prune_stale_nodes};
+use chain_tx_validation::witness::verify_tx_recover_address;
 use parity_scale_codec::Encode;
 
-/// Given a db and a DB transaction, it will go through TX inputs and mark them as spent
-/// in the TX_META storage and it will create a new entry for TX in TX_META with all outputs marked as unspent.
-fn update_utxos_commit(
-    inputs: &[TxoPointer],
-    no_of_outputs: TxoSize,
-    txid: TxId,
-    db: &mut impl StoreKV,
+/// Maximum number of stale trie nodes deleted after a commit, the rest is pruned after the
+/// following commits
+const MAX_PRUNED_NODES_PER_COMMIT: usize = 100_000;
+
+fn process_txs<T: EnclaveProxy>(
+    tx_registry: &TxRegistry<T>,
+    delivered_txs: &[TxAux],
+    db: &mut KVStore<'_>,
 ) {
-    chain_storage::spend_utxos(db, &inputs);
-    chain_storage::create_utxo(db, no_of_outputs, &txid);
+    for txaux in delivered_txs.iter() {
+        let txid: TxId = txaux.tx_id();
+        tx_registry
+            .delivered_handler(TxKind::of(txaux))
+            .commit(db, &txid, txaux);
+    }
 }
 
-fn process_txs(delivered_txs: &[TxAux], db: &mut impl StoreKV) {
+/// Indexes the delivered transactions by the staked state addresses they affect
+/// (addresses of transfer outputs are only known in the enclave)
+fn index_addresses(delivered_txs: &[TxAux], height: BlockHeight, db: &mut KVStore<'_>) {
     for txaux in delivered_txs.iter() {
-        let txid: TxId = txaux.tx_id();
-        match &txaux {
-            TxAux::MLSHandshake(_) => {
-                // FIXME
-            }
-            TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
-                inputs,
-                no_of_outputs,
-                ..
-            }) => {
-                update_utxos_commit(&inputs, *no_of_outputs, txid, db);
-            }
-            TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { tx, .. }) => {
-                chain_storage::store_tx_body(db, &txid, &tx.encode());
-                // witness is obfuscated -- TODO: could be stored on the enclave side or thrown away?
-                // this is not necessary (as they are spent in deliver_tx) and more of a sanity check (as update_utxos_commit does it)
-                chain_storage::spend_utxos(db, &tx.inputs);
-                // account should be already updated in deliver_tx
-            }
-            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, witness)) => {
-                chain_storage::store_tx_body(db, &txid, &tx.encode());
-                chain_storage::store_tx_witness(db, &txid, &witness.encode());
-                // account should be already updated in deliver_tx
-            }
+        let address = match txaux {
             TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
-                witness,
-                no_of_outputs,
-                ..
-            }) => {
-                chain_storage::store_tx_witness(db, &txid, &witness.encode());
-                // account should be already updated in deliver_tx
-                chain_storage::create_utxo(db, *no_of_outputs, &txid);
-            }
-            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, witness)) => {
-                chain_storage::store_tx_body(db, &txid, &tx.encode());
-                chain_storage::store_tx_witness(db, &txid, &witness.encode());
-                // account should be already unjailed in deliver_tx
-            }
-            TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, witness)) => {
-                chain_storage::store_tx_body(db, &txid, &tx.encode());
-                chain_storage::store_tx_witness(db, &txid, &witness.encode());
-                // staked state updated in deliver_tx
-                // validator state updated in end_block
-            }
+                payload, witness, ..
+            }) => verify_tx_recover_address(witness, &payload.txid).ok(),
+            _ => CompactTx::from(txaux).staking_address,
+        };
+        if let Some(address) = address {
+            chain_storage::index_address_tx(db, &address, height, &txaux.tx_id());
         }
     }
 }
 
 impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Enables indexing of committed transactions by staked state address
+    pub fn enable_address_index(&mut self) {
+        self.address_index = true;
+    }
+
+    /// Registers a plugin invoked after each successful commit
+    pub fn register_commit_hook(&mut self, hook: Box<dyn CommitHook>) {
+        self.commit_hooks.push(hook);
+    }
+
+    /// Enables pruning of the staking trie: only the `keep_recent` latest versions stay readable
+    pub fn set_pruning(&mut self, keep_recent: u64) {
+        self.prune_keep_recent = Some(keep_recent);
+    }
+
+    /// Deletes trie nodes which are only referenced by versions older than the retention window.
+    /// Failures are logged and don't affect consensus (the nodes are pruned on a later commit).
+    fn prune_stale_trie_nodes(&mut self, staking_version: Version) {
+        let keep_recent = match self.prune_keep_recent {
+            Some(keep_recent) => keep_recent,
+            None => return,
+        };
+        let least_readable = least_readable_version(staking_version, keep_recent);
+        match prune_stale_nodes(
+            &mut self.storage,
+            least_readable,
+            MAX_PRUNED_NODES_PER_COMMIT,
+        ) {
+            Ok(stats) if stats.pruned_nodes > 0 => log::debug!(
+                "pruned {} stale trie nodes (staking versions before {}{})",
+                stats.pruned_nodes,
+                least_readable,
+                if stats.incomplete { ", incomplete" } else { "" }
+            ),
+            Ok(_) => {}
+            Err(e) => log::error!("failed to prune stale trie nodes: {}", e),
+        }
+    }
+
     /// Commits delivered TX: flushes updates to the underlying storage
     pub fn commit_handler(&mut self, _req: &RequestCommit) -> ResponseCommit {
+        let commit_lock = self.commit_lock.clone();
+        let _committing = lock_commits(&commit_lock);
         let new_state = self.last_state.as_mut().expect("executing block commit, but no app state stored (i.e. no initchain or recovery was executed)");
         let mut top_level = &mut new_state.top_level;
         let mut resp = ResponseCommit::new();
@@ -89,25 +108,49 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         let tree = MerkleTree::new(ids);
 
         if !self.delivered_txs.is_empty() {
-            process_txs(&self.delivered_txs, &mut kv_store!(self));
+            process_txs(&self.tx_registry, &self.delivered_txs, &mut kv_store!(self));
+            if self.address_index {
+                index_addresses(
+                    &self.delivered_txs,
+                    new_state.last_block_height,
+                    &mut kv_store!(self),
+                );
+            }
         }
+        for (txid, result) in mem::take(&mut self.tx_results) {
+            // a rejected replay of a committed transaction doesn't shadow its result
+            if !result.is_ok() && chain_storage::get_tx_result(&kv_getter!(self), &txid).is_some() {
+                continue;
+            }
+            chain_storage::store_tx_result(&mut kv_store!(self), &txid, &result.encode());
+        }
+        self.delivered_tx_count = 0;
         if self.rewards_pool_updated {
             top_level.rewards_pool.last_block_height = new_state.last_block_height;
             self.rewards_pool_updated = false;
         }
 
-        // flush staking storage
-        if !self.staking_buffer.is_empty() {
+        // flush staking storage, blocks without staking changes keep the previous version
+        // (and root)
+        if retain_changed_stakings(
+            &self.storage,
+            new_state.staking_version,
+            &mut self.staking_buffer,
+        ) {
             new_state.staking_version = new_state
                 .staking_version
                 .checked_add(1)
                 .expect("staking version overflow, no way to recover");
+            let flush_started = Instant::now();
             top_level.account_root = flush_stakings(
                 &mut kv_store!(self),
                 new_state.staking_version,
                 mem::take(&mut self.staking_buffer),
             )
             .expect("merkle trie io error");
+            if let Some(metrics) = self.metrics.as_ref() {
+                metrics.record_trie_flush(flush_started.elapsed());
+            }
         }
 
         let app_hash = compute_app_hash(
@@ -125,17 +168,53 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             new_state.last_block_height,
             self.tx_query_address.is_some(),
         );
+        let compact_block = CompactBlock::new(
+            new_state.last_block_height,
+            app_hash,
+            self.block_filter.take(),
+            &self.delivered_txs,
+        );
+        chain_storage::store_compact_block(
+            &mut kv_store!(self),
+            new_state.last_block_height,
+            &compact_block.encode(),
+        );
+
+        // coins are reconciled before the block is flushed, so that a violation halts the node
+        // without committing the state
+        self.reconcile_block();
 
         // flush key-value storage
         flush_storage(&mut self.storage, mem::take(&mut self.kv_buffer))
             .expect("kv storage io error");
+        let new_state = self
+            .last_state
+            .as_ref()
+            .expect("executing block commit, but no app state stored");
+
+        let committed_block = CommittedBlock {
+            height: new_state.last_block_height,
+            app_hash,
+            delivered_txs: &self.delivered_txs,
+            events: &self.block_events,
+        };
+        for hook in self.commit_hooks.iter_mut() {
+            if let Err(e) = hook.on_commit(&committed_block) {
+                log::error!("commit hook {} failed: {}", hook.name(), e);
+            }
+        }
 
         resp.data = new_state.last_apphash.to_vec();
+        let height = new_state.last_block_height;
+        let staking_version = new_state.staking_version;
 
         self.mempool_state = Some(new_state.clone());
         self.delivered_txs.clear();
+        self.block_events.clear();
         self.mempool_kv_buffer.clear();
         self.mempool_staking_buffer.clear();
+        self.take_snapshot_if_due(height);
+        self.prune_stale_trie_nodes(staking_version);
         resp
     }
 }
@@ -0,0 +1,131 @@
+use std::io::{self, Write};
+
+use abci::Event;
+use chain_core::common::H256;
+use chain_core::state::tendermint::BlockHeight;
+use chain_core::tx::TxAux;
+use serde_json::{json, Value};
+
+/// Block data passed to commit hooks after it was successfully committed
+pub struct CommittedBlock<'a> {
+    /// height of the committed block
+    pub height: BlockHeight,
+    /// app hash after the block was committed
+    pub app_hash: H256,
+    /// valid transactions delivered in the block
+    pub delivered_txs: &'a [TxAux],
+    /// events emitted in BeginBlock, DeliverTx and EndBlock
+    pub events: &'a [Event],
+}
+
+/// Plugin invoked after each successful commit
+///
+/// Hooks run on the consensus connection, so they should be cheap
+/// (e.g. hand the data over to a background exporter).
+/// Errors are logged and don't affect consensus.
+pub trait CommitHook: Send {
+    /// name of the hook (used in logs)
+    fn name(&self) -> &str;
+
+    /// called after the block was committed to the storage
+    fn on_commit(&mut self, block: &CommittedBlock<'_>) -> Result<(), String>;
+}
+
+/// Creates a commit hook by its name in the node configuration
+pub fn commit_hook_from_name(name: &str) -> Option<Box<dyn CommitHook>> {
+    match name {
+        StdoutJsonHook::NAME => Some(Box::new(StdoutJsonHook)),
+        _ => None,
+    }
+}
+
+/// Sample hook which prints each committed block as a JSON line to stdout
+#[derive(Debug, Default)]
+pub struct StdoutJsonHook;
+
+impl StdoutJsonHook {
+    /// name of the hook in the node configuration
+    pub const NAME: &'static str = "stdout-json";
+}
+
+impl CommitHook for StdoutJsonHook {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn on_commit(&mut self, block: &CommittedBlock<'_>) -> Result<(), String> {
+        let line = committed_block_json(block).to_string();
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        writeln!(handle, "{}", line).map_err(|e| e.to_string())
+    }
+}
+
+fn committed_block_json(block: &CommittedBlock<'_>) -> Value {
+    let txids = block
+        .delivered_txs
+        .iter()
+        .map(|tx| hex::encode(tx.tx_id()))
+        .collect::<Vec<_>>();
+    let events = block
+        .events
+        .iter()
+        .map(|event| {
+            let attributes = event
+                .attributes
+                .iter()
+                .map(|pair| {
+                    json!({
+                        "key": String::from_utf8_lossy(&pair.key),
+                        "value": String::from_utf8_lossy(&pair.value),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "type": event.field_type,
+                "attributes": attributes,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "height": block.height.value(),
+        "app_hash": hex::encode(&block.app_hash),
+        "txids": txids,
+        "events": events,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use abci::Pair;
+
+    #[test]
+    fn check_committed_block_json() {
+        let mut pair = Pair::new();
+        pair.key = b"fee".to_vec();
+        pair.value = b"0.00000001".to_vec();
+        let mut event = Event::new();
+        event.field_type = "valid_txs".to_owned();
+        event.attributes.push(pair);
+        let events = vec![event];
+
+        let block = CommittedBlock {
+            height: BlockHeight::from(2),
+            app_hash: [1u8; 32],
+            delivered_txs: &[],
+            events: &events,
+        };
+        let value = committed_block_json(&block);
+        assert_eq!(2, value["height"]);
+        assert_eq!(hex::encode([1u8; 32]), value["app_hash"]);
+        assert_eq!("valid_txs", value["events"][0]["type"]);
+        assert_eq!("fee", value["events"][0]["attributes"][0]["key"]);
+    }
+
+    #[test]
+    fn check_commit_hook_from_name() {
+        assert!(commit_hook_from_name(StdoutJsonHook::NAME).is_some());
+        assert!(commit_hook_from_name("kafka").is_none());
+    }
+}
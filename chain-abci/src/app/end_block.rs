@@ -1,9 +1,12 @@
 use std::convert::TryInto;
 
 use crate::app::app_init::ChainNodeApp;
+use crate::app::halt::ViolationKind;
+use crate::app::staking_event::StakingEvent;
 use crate::enclave_bridge::EnclaveProxy;
 use abci::{Event, Pair as KVPair, PubKey, RequestEndBlock, ResponseEndBlock, ValidatorUpdate};
 use chain_core::common::TendermintEventType;
+use chain_core::state::tendermint::TendermintValidatorAddress;
 use chain_tx_filter::BlockFilter;
 use enclave_protocol::{IntraEnclaveRequest, IntraEnclaveResponseOk};
 
@@ -15,8 +18,8 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             let end_block_resp = self
                 .tx_validator
                 .process_request(IntraEnclaveRequest::EndBlock);
-            if let Ok(IntraEnclaveResponseOk::EndBlock(maybe_filter)) = end_block_resp {
-                if let Some(raw_filter) = maybe_filter {
+            match end_block_resp {
+                Ok(IntraEnclaveResponseOk::EndBlock(Some(raw_filter))) => {
                     let filter = BlockFilter::from(&*raw_filter);
 
                     let (key, value) = filter.get_tendermint_kv();
@@ -27,9 +30,18 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                     event.field_type = TendermintEventType::BlockFilter.to_string();
                     event.attributes.push(kvpair);
                     resp.events.push(event);
+                    self.block_filter = Some(raw_filter.to_vec());
                 }
-            } else {
-                panic!("end block request to obtain the block filter failed");
+                Ok(IntraEnclaveResponseOk::EndBlock(None)) => {}
+                // the enclave didn't process the delivered transactions
+                Err(e) => self.halt(
+                    ViolationKind::EnclaveDisagreement,
+                    format!("end block request to obtain the block filter failed: {}", e),
+                ),
+                Ok(_) => self.halt(
+                    ViolationKind::EnclaveDisagreement,
+                    "unexpected response to end block request".to_owned(),
+                ),
             }
         }
         // TODO: skipchain-based validator changes?
@@ -39,6 +51,17 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             state.top_level.network_params.get_max_validators(),
         );
 
+        for (pubkey, power) in val_updates.iter() {
+            let val_addr = TendermintValidatorAddress::from(pubkey);
+            // no panic: validators in the update list still have their records indexed
+            let staking_address = state
+                .staking_table
+                .lookup_address(&val_addr)
+                .expect("validator update for an unknown validator address");
+            resp.events
+                .push(StakingEvent::ValidatorUpdate(staking_address, *power).into());
+        }
+
         resp.set_validator_updates(
             val_updates
                 .into_iter()
@@ -0,0 +1,249 @@
+//! Emergency halt on critical invariant violations
+//!
+//! When coins aren't conserved by a block, the staking trie root doesn't match the chain state
+//! or the enclave disagrees with the delivered block, block processing is halted before the
+//! state is committed: a diagnostic dump is written into `halt.json` in the halt directory, the
+//! halt is reported by the health endpoint and the node panics, so that Tendermint stops.
+//! The dump keeps the node halted across restarts until the operator starts it with the halt
+//! override (e.g. after restoring the state), which also lets it process blocks despite
+//! violations (logged) during the recovery.
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+
+use super::app_init::ChainNodeState;
+use super::invariants::ReconciliationReport;
+use super::ChainNodeApp;
+use crate::enclave_bridge::EnclaveProxy;
+use chain_core::state::tendermint::BlockHeight;
+
+/// Name of the dump which marks the node as halted
+const HALT_FILE: &str = "halt.json";
+
+/// Kind of a critical invariant violation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    /// coins moved by a block don't match the balance changes
+    CoinConservation,
+    /// the staking trie root or the app hash doesn't match the chain state
+    TrieRootMismatch,
+    /// the enclave failed to process the delivered block
+    EnclaveDisagreement,
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViolationKind::CoinConservation => write!(f, "coin conservation failure"),
+            ViolationKind::TrieRootMismatch => write!(f, "trie root mismatch"),
+            ViolationKind::EnclaveDisagreement => write!(f, "enclave disagreement"),
+        }
+    }
+}
+
+/// Why and where block processing was halted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HaltRecord {
+    pub kind: ViolationKind,
+    pub block_height: BlockHeight,
+    pub details: String,
+}
+
+impl fmt::Display for HaltRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at height {}: {}",
+            self.kind, self.block_height, self.details
+        )
+    }
+}
+
+/// Diagnostic dump written on halt
+#[derive(Serialize)]
+struct HaltDump<'a> {
+    record: &'a HaltRecord,
+    last_state: Option<&'a ChainNodeState>,
+    last_reconciliation: Option<&'a ReconciliationReport>,
+    /// ids of the transactions delivered in the halted block
+    delivered_txs: Vec<String>,
+}
+
+/// Only the record is read back from the dump on startup
+#[derive(Deserialize)]
+struct StoredHalt {
+    record: HaltRecord,
+}
+
+/// Halt status of the node, shared with the health endpoint
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    /// where the dump is written (not persisted if not set)
+    dir: Option<PathBuf>,
+    /// process blocks despite violations and a previous halt
+    override_halt: bool,
+    halted: Mutex<Option<HaltRecord>>,
+}
+
+impl CircuitBreaker {
+    /// Creates a circuit breaker persisting the halt into `dir`, loads a previous halt
+    pub fn new(dir: &Path, override_halt: bool) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let halted = match fs::read(dir.join(HALT_FILE)) {
+            Ok(data) => {
+                let stored: StoredHalt = serde_json::from_slice(&data)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                Some(stored.record)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        Ok(CircuitBreaker {
+            dir: Some(dir.to_owned()),
+            override_halt,
+            halted: Mutex::new(halted),
+        })
+    }
+
+    /// The halt of block processing, if halted
+    pub fn halted(&self) -> Option<HaltRecord> {
+        self.halted.lock().expect("lock halt status").clone()
+    }
+
+    /// Whether violations are overridden by the operator
+    pub fn is_overridden(&self) -> bool {
+        self.override_halt
+    }
+
+    /// Clears a previous halt overridden by the operator, the dump is kept as
+    /// `halt-<height>.json`
+    fn clear(&self) -> io::Result<()> {
+        let record = match self.halted.lock().expect("lock halt status").take() {
+            Some(record) => record,
+            None => return Ok(()),
+        };
+        if let Some(dir) = self.dir.as_ref() {
+            fs::rename(
+                dir.join(HALT_FILE),
+                dir.join(format!("halt-{}.json", record.block_height)),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn trip(&self, record: HaltRecord, dump: &[u8]) -> io::Result<()> {
+        *self.halted.lock().expect("lock halt status") = Some(record);
+        match self.dir.as_ref() {
+            Some(dir) => fs::write(dir.join(HALT_FILE), dump),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Sets the halt status reported by the health endpoint
+    pub fn set_circuit_breaker(&mut self, circuit_breaker: Arc<CircuitBreaker>) {
+        self.circuit_breaker = circuit_breaker;
+    }
+
+    /// Refuses to start if the node was halted, unless overridden by the operator
+    pub fn check_halted(&self) {
+        if let Some(record) = self.circuit_breaker.halted() {
+            if !self.circuit_breaker.is_overridden() {
+                panic!(
+                    "block processing was halted on {} (restart with the halt override after recovery)",
+                    record
+                );
+            }
+            warn!(
+                "resuming block processing halted on {} (overridden)",
+                record
+            );
+            if let Err(e) = self.circuit_breaker.clear() {
+                error!("failed to clear the halt: {}", e);
+            }
+        }
+    }
+
+    /// Halts block processing on a critical invariant violation (before the block is committed),
+    /// only logs the violation if overridden by the operator
+    pub fn halt(&self, kind: ViolationKind, details: String) {
+        let block_height = self
+            .last_state
+            .as_ref()
+            .map(|state| state.block_height)
+            .unwrap_or_else(|| 0.into());
+        let record = HaltRecord {
+            kind,
+            block_height,
+            details,
+        };
+        if self.circuit_breaker.is_overridden() {
+            error!("invariant violated (overridden): {}", record);
+            return;
+        }
+
+        let dump = HaltDump {
+            record: &record,
+            last_state: self.last_state.as_ref(),
+            last_reconciliation: self.last_reconciliation.as_ref(),
+            delivered_txs: self
+                .delivered_txs
+                .iter()
+                .map(|tx| hex::encode(tx.tx_id()))
+                .collect(),
+        };
+        let dump = serde_json::to_vec_pretty(&dump).expect("serialize halt dump");
+        error!(
+            "halting block processing: {}",
+            String::from_utf8_lossy(&dump)
+        );
+        if let Err(e) = self.circuit_breaker.trip(record.clone(), &dump) {
+            error!("failed to persist the halt: {}", e);
+        }
+        panic!("block processing halted on {}", record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_halt_persisted() {
+        let dir = std::env::temp_dir().join(format!("halt-test-{}", std::process::id()));
+        let record = HaltRecord {
+            kind: ViolationKind::TrieRootMismatch,
+            block_height: 3.into(),
+            details: "staking trie root doesn't match the chain state".to_owned(),
+        };
+        let dump = serde_json::to_vec(&HaltDump {
+            record: &record,
+            last_state: None,
+            last_reconciliation: None,
+            delivered_txs: vec![],
+        })
+        .unwrap();
+
+        let breaker = CircuitBreaker::new(&dir, false).unwrap();
+        assert_eq!(None, breaker.halted());
+        breaker.trip(record.clone(), &dump).unwrap();
+        assert_eq!(Some(record.clone()), breaker.halted());
+
+        // still halted after restart
+        let breaker = CircuitBreaker::new(&dir, true).unwrap();
+        assert_eq!(Some(record), breaker.halted());
+        breaker.clear().unwrap();
+        assert_eq!(None, breaker.halted());
+        assert!(dir.join("halt-3.json").exists());
+        assert_eq!(None, CircuitBreaker::new(&dir, false).unwrap().halted());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
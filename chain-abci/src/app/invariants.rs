@@ -0,0 +1,267 @@
+//! Per-block checks that coins are conserved
+//!
+//! Coins moved by a block between the UTXO set, the staked states and the rewards pool (fees,
+//! slashes, rewards, deposits and withdrawals) are recorded while the block is executed, and
+//! reconciled with the balance changes on Commit (before the block is flushed). The rewards pool
+//! and UTXO balances are always reconciled; the staked states (summed over the whole staking
+//! trie) only when invariant checks are enabled. A violation halts the node (see `halt`).
+use serde::Serialize;
+
+use super::halt::ViolationKind;
+use super::{sanity_check_enabled, BufferType, ChainNodeApp};
+use crate::enclave_bridge::EnclaveProxy;
+use crate::staking::RewardsDistribution;
+use crate::storage::{TxAction, TxEnclaveAction, TxPublicAction};
+use chain_core::init::coin::Coin;
+use chain_core::state::tendermint::BlockHeight;
+use chain_storage::jellyfish::sum_staking_coins;
+
+/// Invariants are always checked in debug builds, and in release builds with sanity checks
+pub fn invariant_checks_enabled() -> bool {
+    cfg!(debug_assertions) || sanity_check_enabled()
+}
+
+fn add(total: &mut Coin, amount: Coin) {
+    *total = (*total + amount).expect("coins moved by a block greater than max coin?");
+}
+
+/// Coins moved by the transactions, punishments and rewards of a block
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CoinFlows {
+    /// fees of the delivered transactions (credited to the rewards pool)
+    pub fees: Coin,
+    /// part of `fees` paid from staked states
+    pub staking_fees: Coin,
+    /// slashed from staked states (credited to the rewards pool)
+    pub slashed: Coin,
+    /// minted into the rewards pool
+    pub minted: Coin,
+    /// distributed from the rewards pool to staked states
+    pub rewarded: Coin,
+    /// moved from UTXOs to staked states
+    pub deposited: Coin,
+    /// moved from staked states to UTXOs (including the fees of withdrawals)
+    pub withdrawn: Coin,
+}
+
+impl CoinFlows {
+    /// Records the coins moved by a delivered transaction
+    pub fn record_tx(&mut self, action: &TxAction) {
+        add(&mut self.fees, action.fee().to_coin());
+        match action {
+            TxAction::Enclave(TxEnclaveAction::Deposit {
+                deposit: (_, coin), ..
+            }) => add(&mut self.deposited, *coin),
+            TxAction::Enclave(TxEnclaveAction::Withdraw {
+                withdraw: (_, coin),
+                ..
+            }) => add(&mut self.withdrawn, *coin),
+            TxAction::Enclave(TxEnclaveAction::Transfer { .. }) => {}
+            TxAction::Public(TxPublicAction::Unbond { fee, .. }) => {
+                add(&mut self.staking_fees, fee.to_coin())
+            }
+            TxAction::Public(_) => {}
+        }
+    }
+
+    /// Records coins slashed from a staked state
+    pub fn record_slash(&mut self, slashed: Coin) {
+        add(&mut self.slashed, slashed);
+    }
+
+    /// Records a distribution of the rewards pool
+    pub fn record_rewards(&mut self, distribution: &RewardsDistribution, minted: Coin) {
+        add(&mut self.minted, minted);
+        for (_, amount) in distribution.iter() {
+            add(&mut self.rewarded, *amount);
+        }
+    }
+}
+
+/// Balances reconciled with the coins moved by a block
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct CoinBalances {
+    /// coins in UTXOs
+    pub utxo: Coin,
+    /// coins in the rewards pool
+    pub rewards_pool: Coin,
+    /// coins minted since genesis
+    pub minted: Coin,
+    /// bonded and unbonded coins of all staked states (only summed if invariant checks are
+    /// enabled)
+    pub staking: Option<Coin>,
+}
+
+/// Reconciliation of the coins moved by a committed block with its balance changes
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReconciliationReport {
+    pub block_height: BlockHeight,
+    pub before: CoinBalances,
+    pub after: CoinBalances,
+    pub flows: CoinFlows,
+    /// violated invariants, empty if the coins are conserved
+    pub violations: Vec<String>,
+}
+
+fn amount(coin: Coin) -> i128 {
+    i128::from(u64::from(coin))
+}
+
+fn check(violations: &mut Vec<String>, name: &str, before: Coin, after: Coin, expected: i128) {
+    let change = amount(after) - amount(before);
+    if change != expected {
+        violations.push(format!(
+            "{} changed by {} (from {} to {}), but the coins moved by the block change it by {}",
+            name, change, before, after, expected
+        ));
+    }
+}
+
+impl ReconciliationReport {
+    /// Reconciles the balance changes of a block with the coins it moved
+    pub fn new(
+        block_height: BlockHeight,
+        before: CoinBalances,
+        after: CoinBalances,
+        flows: CoinFlows,
+    ) -> Self {
+        let mut violations = Vec::new();
+        let enclave_fees = amount(flows.fees) - amount(flows.staking_fees);
+        check(
+            &mut violations,
+            "rewards pool",
+            before.rewards_pool,
+            after.rewards_pool,
+            amount(flows.fees) + amount(flows.slashed) + amount(flows.minted)
+                - amount(flows.rewarded),
+        );
+        check(
+            &mut violations,
+            "minted coins",
+            before.minted,
+            after.minted,
+            amount(flows.minted),
+        );
+        check(
+            &mut violations,
+            "UTXO coins",
+            before.utxo,
+            after.utxo,
+            amount(flows.withdrawn) - amount(flows.deposited) - enclave_fees,
+        );
+        if let (Some(staking_before), Some(staking_after)) = (before.staking, after.staking) {
+            check(
+                &mut violations,
+                "staked coins",
+                staking_before,
+                staking_after,
+                amount(flows.deposited) + amount(flows.rewarded)
+                    - amount(flows.withdrawn)
+                    - amount(flows.slashed)
+                    - amount(flows.staking_fees),
+            );
+        }
+
+        ReconciliationReport {
+            block_height,
+            before,
+            after,
+            flows,
+            violations,
+        }
+    }
+}
+
+impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Returns the balances of the committed state
+    pub fn coin_balances(&self) -> CoinBalances {
+        let state = self.last_state.as_ref().expect("expect last_state");
+        let staking = if invariant_checks_enabled() {
+            Some(
+                sum_staking_coins(
+                    &kv_getter!(self, BufferType::Consensus),
+                    state.staking_version,
+                )
+                .expect("sum of staked coins greater than max coin?"),
+            )
+        } else {
+            None
+        };
+        CoinBalances {
+            utxo: state.utxo_coins,
+            rewards_pool: state.top_level.rewards_pool.period_bonus,
+            minted: state.top_level.rewards_pool.minted,
+            staking,
+        }
+    }
+
+    /// Reconciles the block being committed with the coins it moved, halts on violations
+    pub fn reconcile_block(&mut self) {
+        let flows = std::mem::take(&mut self.block_flows);
+        let before = match self.block_start_balances.take() {
+            Some(before) => before,
+            // no BeginBlock for this block (e.g. replayed by tests)
+            None => return,
+        };
+        let block_height = self
+            .last_state
+            .as_ref()
+            .expect("expect last_state")
+            .last_block_height;
+        let report = ReconciliationReport::new(block_height, before, self.coin_balances(), flows);
+        let violations = report.violations.join("; ");
+        self.last_reconciliation = Some(report);
+
+        if !violations.is_empty() {
+            self.halt(ViolationKind::CoinConservation, violations);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::state::account::StakedStateAddress;
+    use chain_core::tx::fee::Fee;
+
+    fn coin(amount: u64) -> Coin {
+        Coin::new(amount).unwrap()
+    }
+
+    #[test]
+    fn check_reconciliation() {
+        let address = StakedStateAddress::BasicRedeem([0; 20].into());
+        let mut flows = CoinFlows::default();
+        flows.record_tx(&TxAction::Enclave(TxEnclaveAction::Deposit {
+            fee: Fee::new(coin(1)),
+            spend_utxo: vec![],
+            deposit: (address, coin(100)),
+        }));
+        flows.record_slash(coin(10));
+        flows.record_rewards(&vec![(address, coin(15))], coin(5));
+
+        let before = CoinBalances {
+            utxo: coin(1000),
+            rewards_pool: coin(20),
+            minted: coin(0),
+            staking: Some(coin(500)),
+        };
+        let after = CoinBalances {
+            utxo: coin(899),
+            rewards_pool: coin(21),
+            minted: coin(5),
+            staking: Some(coin(605)),
+        };
+        let report = ReconciliationReport::new(1.into(), before, after, flows.clone());
+        assert!(report.violations.is_empty(), "{:?}", report.violations);
+
+        // fee not credited to the rewards pool
+        let after = CoinBalances {
+            rewards_pool: coin(20),
+            ..after
+        };
+        let report = ReconciliationReport::new(1.into(), before, after, flows);
+        assert_eq!(report.violations.len(), 1);
+        assert!(report.violations[0].starts_with("rewards pool"));
+    }
+}
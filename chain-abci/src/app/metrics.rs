@@ -0,0 +1,306 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::halt::CircuitBreaker;
+use super::tx_handler::TxKind;
+use super::warmup::Readiness;
+use super::ChainNodeApp;
+use crate::enclave_bridge::EnclaveProxy;
+
+/// Upper bounds (in seconds) of the latency histogram buckets
+const LATENCY_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// All transaction kinds, in the order of the deliver tx counters
+const TX_KINDS: [TxKind; 7] = [
+    TxKind::Transfer,
+    TxKind::DepositStake,
+    TxKind::WithdrawUnbondedStake,
+    TxKind::UnbondStake,
+    TxKind::Unjail,
+    TxKind::NodeJoin,
+    TxKind::MLSHandshake,
+];
+
+fn tx_kind_label(kind: TxKind) -> &'static str {
+    match kind {
+        TxKind::Transfer => "transfer",
+        TxKind::DepositStake => "deposit_stake",
+        TxKind::WithdrawUnbondedStake => "withdraw_unbonded_stake",
+        TxKind::UnbondStake => "unbond_stake",
+        TxKind::Unjail => "unjail",
+        TxKind::NodeJoin => "node_join",
+        TxKind::MLSHandshake => "mls_handshake",
+    }
+}
+
+fn tx_kind_index(kind: TxKind) -> usize {
+    TX_KINDS
+        .iter()
+        .position(|k| *k == kind)
+        .expect("all kinds are listed")
+}
+
+/// Cumulative histogram of durations
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter().zip(LATENCY_BUCKETS.iter()) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        for (bucket, bound) in self.buckets.iter().zip(LATENCY_BUCKETS.iter()) {
+            let _ = writeln!(
+                out,
+                "{}_bucket{{le=\"{}\"}} {}",
+                name,
+                bound,
+                bucket.load(Ordering::Relaxed)
+            );
+        }
+        let count = self.count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, count);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "{}_sum {}", name, sum);
+        let _ = writeln!(out, "{}_count {}", name, count);
+    }
+}
+
+/// Metrics of the ABCI application, exposed in the Prometheus text format
+#[derive(Debug, Default)]
+pub struct AppMetrics {
+    delivered_txs: [AtomicU64; TX_KINDS.len()],
+    rejected_delivered_txs: AtomicU64,
+    mempool_rejections: AtomicU64,
+    block_height: AtomicU64,
+    validator_set_size: AtomicU64,
+    block_latency: Histogram,
+    trie_flush: Histogram,
+    block_started: Mutex<Option<Instant>>,
+}
+
+impl AppMetrics {
+    /// Records a transaction accepted in DeliverTx
+    pub fn record_delivered_tx(&self, kind: TxKind) {
+        self.delivered_txs[tx_kind_index(kind)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a transaction rejected in DeliverTx
+    pub fn record_rejected_delivered_tx(&self) {
+        self.rejected_delivered_txs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a transaction rejected in CheckTx
+    pub fn record_mempool_rejection(&self) {
+        self.mempool_rejections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the start of a block (BeginBlock)
+    pub fn record_block_start(&self) {
+        *self.block_started.lock().expect("lock block start") = Some(Instant::now());
+    }
+
+    /// Records the commit of a block, its latency is measured from BeginBlock
+    pub fn record_block_commit(&self, height: u64) {
+        if let Some(started) = self.block_started.lock().expect("lock block start").take() {
+            self.block_latency.observe(started.elapsed());
+        }
+        self.block_height.store(height, Ordering::Relaxed);
+    }
+
+    /// Records the time of flushing the account (staking) trie
+    pub fn record_trie_flush(&self, duration: Duration) {
+        self.trie_flush.observe(duration);
+    }
+
+    /// Records the size of the validator set chosen in EndBlock
+    pub fn record_validator_set_size(&self, size: usize) {
+        self.validator_set_size
+            .store(size as u64, Ordering::Relaxed);
+    }
+
+    /// Returns metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# HELP chain_abci_delivered_txs_total Transactions accepted in DeliverTx\n");
+        out.push_str("# TYPE chain_abci_delivered_txs_total counter\n");
+        for (kind, count) in TX_KINDS.iter().zip(self.delivered_txs.iter()) {
+            let _ = writeln!(
+                out,
+                "chain_abci_delivered_txs_total{{type=\"{}\"}} {}",
+                tx_kind_label(*kind),
+                count.load(Ordering::Relaxed)
+            );
+        }
+        render_value(
+            &mut out,
+            "chain_abci_rejected_delivered_txs_total",
+            "counter",
+            "Transactions rejected in DeliverTx",
+            self.rejected_delivered_txs.load(Ordering::Relaxed),
+        );
+        render_value(
+            &mut out,
+            "chain_abci_mempool_rejections_total",
+            "counter",
+            "Transactions rejected in CheckTx",
+            self.mempool_rejections.load(Ordering::Relaxed),
+        );
+        render_value(
+            &mut out,
+            "chain_abci_block_height",
+            "gauge",
+            "Height of the last committed block",
+            self.block_height.load(Ordering::Relaxed),
+        );
+        render_value(
+            &mut out,
+            "chain_abci_validator_set_size",
+            "gauge",
+            "Number of validators chosen in the last EndBlock",
+            self.validator_set_size.load(Ordering::Relaxed),
+        );
+        self.block_latency.render(
+            &mut out,
+            "chain_abci_block_processing_seconds",
+            "Time from BeginBlock to the end of Commit",
+        );
+        self.trie_flush.render(
+            &mut out,
+            "chain_abci_account_trie_flush_seconds",
+            "Time of flushing the account trie on Commit",
+        );
+        out
+    }
+}
+
+fn render_value(out: &mut String, name: &str, kind: &str, help: &str, value: u64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn serve_metrics(
+    mut stream: TcpStream,
+    metrics: &AppMetrics,
+    readiness: &Readiness,
+    circuit_breaker: &CircuitBreaker,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // skip the headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let (status, content_type, body) = if path == "/metrics" {
+        ("200 OK", "text/plain; version=0.0.4", metrics.render())
+    } else if path == "/health" {
+        let halted = circuit_breaker.halted();
+        let ready = readiness.is_ready() && halted.is_none();
+        let body = serde_json::json!({
+            "ready": ready,
+            "phase": readiness.phase(),
+            "halted": halted,
+        })
+        .to_string();
+        if ready {
+            ("200 OK", "application/json", body)
+        } else {
+            ("503 Service Unavailable", "application/json", body)
+        }
+    } else {
+        ("404 Not Found", "text/plain", "not found\n".to_owned())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Serves `/metrics` and `/health` (503 until the warm-up completes or if block processing is
+/// halted) over HTTP on the given address (in a background thread)
+pub fn spawn_metrics_server(
+    addr: SocketAddr,
+    metrics: Arc<AppMetrics>,
+    readiness: Arc<Readiness>,
+    circuit_breaker: Arc<CircuitBreaker>,
+) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)?;
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream.and_then(|stream| {
+                stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+                serve_metrics(stream, &metrics, &readiness, &circuit_breaker)
+            });
+            if let Err(e) = result {
+                log::warn!("failed to serve metrics: {}", e);
+            }
+        }
+    }))
+}
+
+impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Sets metrics updated by the ABCI requests
+    pub fn set_metrics(&mut self, metrics: Arc<AppMetrics>) {
+        self.metrics = Some(metrics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_render_metrics() {
+        let metrics = AppMetrics::default();
+        metrics.record_delivered_tx(TxKind::Transfer);
+        metrics.record_delivered_tx(TxKind::Transfer);
+        metrics.record_delivered_tx(TxKind::Unjail);
+        metrics.record_mempool_rejection();
+        metrics.record_validator_set_size(3);
+        metrics.record_trie_flush(Duration::from_millis(20));
+        metrics.record_block_start();
+        metrics.record_block_commit(7);
+
+        let text = metrics.render();
+        assert!(text.contains("chain_abci_delivered_txs_total{type=\"transfer\"} 2\n"));
+        assert!(text.contains("chain_abci_delivered_txs_total{type=\"unjail\"} 1\n"));
+        assert!(text.contains("chain_abci_delivered_txs_total{type=\"node_join\"} 0\n"));
+        assert!(text.contains("chain_abci_mempool_rejections_total 1\n"));
+        assert!(text.contains("chain_abci_validator_set_size 3\n"));
+        assert!(text.contains("chain_abci_block_height 7\n"));
+        assert!(text.contains("chain_abci_account_trie_flush_seconds_bucket{le=\"0.01\"} 0\n"));
+        assert!(text.contains("chain_abci_account_trie_flush_seconds_bucket{le=\"0.025\"} 1\n"));
+        assert!(text.contains("chain_abci_account_trie_flush_seconds_count 1\n"));
+        assert!(text.contains("chain_abci_block_processing_seconds_count 1\n"));
+    }
+}
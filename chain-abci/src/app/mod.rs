@@ -3,11 +3,21 @@ mod macros;
 
 mod app_init;
 mod commit;
+pub mod commit_hook;
 mod end_block;
+pub mod halt;
+pub mod invariants;
+pub mod metrics;
+mod precheck;
 mod query;
 mod rewards;
+pub mod shutdown;
+pub mod snapshot;
 mod staking_event;
+pub mod tx_handler;
+pub mod tx_query;
 pub mod validate_tx;
+pub mod warmup;
 
 use abci::Pair as KVPair;
 use abci::*;
@@ -19,8 +29,10 @@ use std::env;
 pub use self::app_init::check_validators;
 pub use self::app_init::{
     get_validator_key, init_app_hash, BufferType, ChainNodeApp, ChainNodeState,
+    CHAIN_NODE_STATE_FORMAT,
 };
 use crate::app::staking_event::StakingEvent;
+use crate::app::tx_handler::TxKind;
 use crate::app::validate_tx::ResponseWithCodeAndLog;
 use crate::enclave_bridge::EnclaveProxy;
 use crate::staking::RewardsDistribution;
@@ -30,6 +42,8 @@ use chain_core::init::coin::Coin;
 use chain_core::init::config::NetworkParameters;
 use chain_core::state::account::PunishmentKind;
 use chain_core::state::tendermint::{BlockHeight, TendermintValidatorAddress, TendermintVotePower};
+use chain_core::state::tx_result::{TxEvent, TxEventAttribute, TxResult};
+use chain_core::tx::fee::Fee;
 use chain_core::tx::TxAux;
 use parity_scale_codec::Decode;
 
@@ -45,6 +59,7 @@ fn get_version() -> String {
 /// Encapsulate some information for begin block event
 pub struct BeginBlockInfo<'a> {
     pub params: &'a NetworkParameters,
+    pub app_version: u64,
     pub block_time: Timespec,
     pub block_height: BlockHeight,
     pub max_evidence_age: Timespec,
@@ -66,8 +81,10 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
     /// to the application.
     fn info(&mut self, _req: &RequestInfo) -> ResponseInfo {
         info!("received info request");
+        // Tendermint starts sending blocks after the handshake, so it waits until the node is ready
+        self.warm_up();
         let mut resp = ResponseInfo::new();
-        resp.app_version = chain_core::APP_VERSION;
+        resp.app_version = chain_core::INITIAL_APP_VERSION;
         resp.version = get_version();
         if let Some(raw) = chain_storage::get_last_app_state(&self.storage) {
             let app_state =
@@ -99,6 +116,9 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
                 resp.set_code(0);
             }
             Err(msg) => {
+                if let Some(metrics) = self.metrics.as_ref() {
+                    metrics.record_mempool_rejection();
+                }
                 resp.set_code(1);
                 resp.add_log(&msg.to_string());
                 log::warn!("check tx failed: {}", msg);
@@ -122,6 +142,9 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
     /// commit()
     fn begin_block(&mut self, req: &RequestBeginBlock) -> ResponseBeginBlock {
         info!("received beginblock request");
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.record_block_start();
+        }
         // TODO: Check security implications once https://github.com/tendermint/tendermint/issues/2653 is closed
         let header = req
             .header
@@ -147,6 +170,8 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
             vec![]
         };
 
+        self.block_start_balances = Some(self.coin_balances());
+
         let last_state = self
             .last_state
             .as_mut()
@@ -154,6 +179,27 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
         last_state.block_time = block_time;
         last_state.block_height = block_height;
 
+        // scheduled app version upgrades take effect from the block at their height
+        if let Some(app_version) = self.app_version_upgrades.get(&block_height) {
+            if *app_version > last_state.app_version {
+                log::info!(
+                    "app version upgraded to {} at height {}",
+                    app_version,
+                    block_height
+                );
+                last_state.app_version = *app_version;
+            }
+        }
+
+        // scheduled network parameters updates take effect from the block at their height
+        if last_state
+            .top_level
+            .network_params
+            .apply_scheduled_updates(block_height)
+        {
+            log::info!("network parameters updated at height {}", block_height);
+        }
+
         // ignore the invalid items (logged)
         let evidences = req
             .byzantine_validators
@@ -177,6 +223,7 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
             &mut staking_store!(self, last_state.staking_version),
             &BeginBlockInfo {
                 params: &last_state.top_level.network_params,
+                app_version: last_state.app_version,
                 block_time: last_state.block_time,
                 block_height: last_state.block_height,
                 max_evidence_age: last_state.max_evidence_age,
@@ -196,6 +243,7 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
                 .expect("sum of bonded and unbonded slash amount exceed maximum coin");
             rewards_pool.period_bonus = (rewards_pool.period_bonus + slashed_amount)
                 .expect("rewards pool + fee greater than max coin?");
+            self.block_flows.record_slash(slashed_amount);
 
             self.rewards_pool_updated = true;
 
@@ -237,12 +285,17 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
         }
 
         if let Some((distributed, minted)) = self.rewards_try_distribute() {
+            self.block_flows.record_rewards(&distributed, minted);
             let events = generate_reward_events(distributed, minted);
             for event in events.iter() {
                 response.events.push(event.to_owned());
             }
         }
 
+        if !self.commit_hooks.is_empty() {
+            self.block_events.extend(response.events.iter().cloned());
+        }
+
         response
     }
 
@@ -251,10 +304,18 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
     fn deliver_tx(&mut self, req: &RequestDeliverTx) -> ResponseDeliverTx {
         info!("received delivertx request");
         let mut resp = ResponseDeliverTx::new();
+        let index = self.delivered_tx_count;
+        self.delivered_tx_count += 1;
         let result = self.process_tx(req, BufferType::Consensus);
-        match result {
+        let mut fee = Fee::new(Coin::zero());
+        let txid = match result {
             Ok((txaux, tx_action)) => {
-                let fee_amount = tx_action.fee().to_coin();
+                fee = tx_action.fee();
+                let fee_amount = fee.to_coin();
+                if let Some(metrics) = self.metrics.as_ref() {
+                    metrics.record_delivered_tx(TxKind::of(&txaux));
+                }
+                self.block_flows.record_tx(&tx_action);
                 let tx_events = generate_tx_events(&txaux, tx_action);
 
                 resp.set_code(0);
@@ -262,7 +323,11 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
                 for event in tx_events.iter() {
                     resp.events.push(event.to_owned());
                 }
+                if !self.commit_hooks.is_empty() {
+                    self.block_events.extend(tx_events);
+                }
 
+                let txid = txaux.tx_id();
                 self.delivered_txs.push(txaux);
 
                 if fee_amount > Coin::zero() {
@@ -272,12 +337,36 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
                         .expect("rewards pool + fee greater than max coin?");
                     self.rewards_pool_updated = true;
                 }
+                Some(txid)
             }
             Err(msg) => {
+                if let Some(metrics) = self.metrics.as_ref() {
+                    metrics.record_rejected_delivered_tx();
+                }
                 resp.set_code(1);
                 resp.add_log(&msg.to_string());
                 log::error!("deliver tx failed: {}", msg);
+                TxAux::decode(&mut &req.tx[..])
+                    .ok()
+                    .map(|txaux| txaux.tx_id())
             }
+        };
+
+        if let Some(txid) = txid {
+            let height = self
+                .last_state
+                .as_ref()
+                .expect("executing deliver tx, but no app state stored")
+                .block_height;
+            let result = TxResult {
+                code: resp.code,
+                log: resp.log.clone(),
+                fee,
+                events: resp.events.iter().map(to_tx_event).collect(),
+                height,
+                index,
+            };
+            self.tx_results.push((txid, result));
         }
         resp
     }
@@ -285,13 +374,23 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
     /// Consensus Connection: Called at the end of the block. used to update the validator set.
     fn end_block(&mut self, req: &RequestEndBlock) -> ResponseEndBlock {
         info!("received endblock request");
-        ChainNodeApp::end_block_handler(self, req)
+        let resp = ChainNodeApp::end_block_handler(self, req);
+        if let (Some(metrics), Some(state)) = (self.metrics.as_ref(), self.last_state.as_ref()) {
+            metrics.record_validator_set_size(state.staking_table.get_chosen_validators().len());
+        }
+        if !self.commit_hooks.is_empty() {
+            self.block_events.extend(resp.events.iter().cloned());
+        }
+        resp
     }
 
     /// Consensus Connection: Commit the block with the latest state from the application.
     fn commit(&mut self, _req: &RequestCommit) -> ResponseCommit {
         info!("received commit request");
         let resp = ChainNodeApp::commit_handler(self, _req);
+        if let (Some(metrics), Some(state)) = (self.metrics.as_ref(), self.last_state.as_ref()) {
+            metrics.record_block_commit(state.last_block_height.value());
+        }
 
         if sanity_check_enabled() {
             self.check_circulating_coins();
@@ -301,6 +400,20 @@ impl<T: EnclaveProxy + 'static> abci::Application for ChainNodeApp<T> {
     }
 }
 
+fn to_tx_event(event: &Event) -> TxEvent {
+    TxEvent {
+        event_type: event.field_type.clone(),
+        attributes: event
+            .attributes
+            .iter()
+            .map(|pair| TxEventAttribute {
+                key: pair.key.clone(),
+                value: pair.value.clone(),
+            })
+            .collect(),
+    }
+}
+
 fn iter_votes(last_commit_info: &LastCommitInfo) -> impl Iterator<Item = &VoteInfo> {
     last_commit_info.votes.iter()
 }
@@ -415,6 +528,7 @@ fn generate_tx_staking_change_event(tx_action: TxAction) -> Option<abci::Event>
             TxPublicAction::Unjail(staking_address) => {
                 Some(StakingEvent::Unjail(&staking_address).into())
             }
+            TxPublicAction::NetworkParamsUpdate(_) => None,
         },
     }
 }
@@ -0,0 +1,120 @@
+//! Cheap checks of CheckTx requests against the mempool state, done before the transaction
+//! handlers (i.e. before transfers, deposits and withdrawals hit the validation enclave)
+use std::collections::BTreeSet;
+
+use super::app_init::BufferType;
+use super::ChainNodeApp;
+use crate::enclave_bridge::EnclaveProxy;
+use crate::tx_error::PrecheckError;
+use chain_core::state::account::{Nonce, StakedStateAddress};
+use chain_core::tx::data::input::TxoPointer;
+use chain_core::tx::{TxAux, TxEnclaveAux, TxPublicAux};
+use chain_storage::buffer::Get;
+
+impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Sets the maximum size of transactions accepted in the mempool
+    pub fn set_max_mempool_tx_size(&mut self, max_size: usize) {
+        self.max_mempool_tx_size = Some(max_size);
+    }
+
+    /// Rejects raw transactions larger than the mempool limit (if set)
+    pub fn check_mempool_tx_size(&self, tx_len: usize) -> Result<(), PrecheckError> {
+        match self.max_mempool_tx_size {
+            Some(max_size) if tx_len > max_size => Err(PrecheckError::TooLarge(tx_len, max_size)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects transactions spending duplicated or already spent inputs and public transactions
+    /// with a nonce not matching the staked state (or the network parameters for their updates,
+    /// which also need enough witnesses to possibly reach the voting power threshold),
+    /// the witnesses are not verified here
+    pub fn precheck_mempool_tx(&self, txaux: &TxAux) -> Result<(), PrecheckError> {
+        match txaux {
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx { inputs, .. }) => self.check_inputs(inputs),
+            TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { tx, .. }) => {
+                self.check_inputs(&tx.inputs)
+            }
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => {
+                self.check_nonce(&tx.from_staked_account, tx.nonce)
+            }
+            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, _)) => {
+                self.check_nonce(&tx.address, tx.nonce)
+            }
+            TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, _)) => {
+                self.check_nonce(&tx.address, tx.nonce)
+            }
+            TxAux::PublicTx(TxPublicAux::NetworkParamsUpdateTx(tx, witnesses)) => {
+                self.check_params_update_nonce(tx.nonce)?;
+                self.check_params_update_witnesses(witnesses.len())
+            }
+            // the nonce of withdrawals is only known in the enclave
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx { .. })
+            | TxAux::MLSHandshake(_) => Ok(()),
+        }
+    }
+
+    fn check_inputs(&self, inputs: &[TxoPointer]) -> Result<(), PrecheckError> {
+        let mut seen = BTreeSet::new();
+        if !inputs.iter().all(|input| seen.insert(input)) {
+            return Err(PrecheckError::DuplicateInputs);
+        }
+        let kv = kv_getter!(self, BufferType::Mempool);
+        // unknown inputs are reported by the handlers
+        if inputs
+            .iter()
+            .any(|input| chain_storage::lookup_input(&kv, input) == Some(true))
+        {
+            return Err(PrecheckError::InputSpent);
+        }
+        Ok(())
+    }
+
+    fn check_nonce(&self, address: &StakedStateAddress, nonce: Nonce) -> Result<(), PrecheckError> {
+        let state = self.mempool_state.as_ref().expect("expect mempool_state");
+        let staking = staking_getter!(self, state.staking_version, BufferType::Mempool);
+        // missing staked states are reported by the handlers
+        match staking.get(address) {
+            Some(account) if account.nonce != nonce => {
+                Err(PrecheckError::IncorrectNonce(nonce, account.nonce))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_params_update_nonce(&self, nonce: Nonce) -> Result<(), PrecheckError> {
+        let state = self.mempool_state.as_ref().expect("expect mempool_state");
+        let update_nonce = state.top_level.network_params.get_update_nonce();
+        if nonce != update_nonce {
+            return Err(PrecheckError::IncorrectParamsUpdateNonce(
+                nonce,
+                update_nonce,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Rejects updates with too few witnesses to hold more than 2/3 of the voting power
+    /// even if they're from the most powerful validators (the handler checks the actual signers)
+    fn check_params_update_witnesses(&self, witnesses: usize) -> Result<(), PrecheckError> {
+        let state = self.mempool_state.as_ref().expect("expect mempool_state");
+        let mut powers = state
+            .staking_table
+            .get_chosen_validators()
+            .values()
+            .map(|power| u64::from(*power))
+            .collect::<Vec<_>>();
+        powers.sort_unstable_by(|a, b| b.cmp(a));
+        let total_power = powers
+            .iter()
+            .fold(0u64, |acc, power| acc.saturating_add(*power));
+        let max_signed_power = powers
+            .iter()
+            .take(witnesses)
+            .fold(0u64, |acc, power| acc.saturating_add(*power));
+        if (max_signed_power as u128) * 3 <= (total_power as u128) * 2 {
+            return Err(PrecheckError::NotEnoughParamsUpdateWitnesses(witnesses));
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,20 @@
 use std::convert::{TryFrom, TryInto};
+use std::sync::Arc;
 
+use super::app_init::ChainNodeState;
+use super::tx_query::TxQueryEndpoints;
 use super::ChainNodeApp;
 use crate::enclave_bridge::EnclaveProxy;
 use abci::*;
 use chain_core::common::{MerkleTree, Proof as MerkleProof, H256, HASH_SIZE_256};
 use chain_core::state::account::StakedStateAddress;
 use chain_core::state::tendermint::BlockHeight;
+use chain_core::state::tx_result::TxResult;
 use chain_core::state::ChainState;
-use chain_core::tx::data::TXID_HASH_ID;
-use chain_storage::jellyfish::get_with_proof;
+use chain_core::tx::data::{TxId, TXID_HASH_ID};
+use chain_core::AppHashParts;
+use chain_storage::jellyfish::{get_with_proof, has_version, SparseMerkleProof, Version};
+use chain_storage::scrub::ScrubMetrics;
 use chain_storage::LookupItem;
 use parity_scale_codec::{Decode, Encode};
 
@@ -35,6 +41,121 @@ fn get_key(resp: &mut ResponseQuery, data_key: &[u8]) -> Option<H256> {
 }
 
 impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Sets health-checked tx query enclave endpoints advertised on the "txquery" path
+    pub fn set_tx_query_endpoints(&mut self, endpoints: Arc<TxQueryEndpoints>) {
+        self.tx_query_endpoints = Some(endpoints);
+    }
+
+    /// Sets counters of the trie scrubber returned on the "scrub" path
+    pub fn set_scrub_metrics(&mut self, metrics: Arc<ScrubMetrics>) {
+        self.scrub_metrics = Some(metrics);
+    }
+
+    /// Parts of the app hash of the state at given height (or of the last committed state), which
+    /// prove the staking trie root of that state
+    fn app_hash_parts(&self, height: Option<BlockHeight>) -> Option<AppHashParts> {
+        let last_state = self
+            .storage
+            .get_last_app_state()
+            .and_then(|raw| ChainNodeState::decode(&mut raw.as_slice()).ok());
+        let (state, app_hash) = match last_state {
+            Some(last) if height.map_or(true, |height| height == last.last_block_height) => {
+                (last.top_level, last.last_apphash)
+            }
+            _ => {
+                let height = height?;
+                let raw = self.storage.get_historical_state(height)?;
+                (
+                    ChainState::decode(&mut raw.as_slice()).ok()?,
+                    self.storage.get_historical_app_hash(height)?,
+                )
+            }
+        };
+        let tree = match self.storage.lookup_item(LookupItem::TxsMerkle, &app_hash) {
+            Some(data) => MerkleTree::decode(&mut data.as_slice()).ok()?,
+            // genesis
+            None => MerkleTree::empty(),
+        };
+        let parts = AppHashParts::new(
+            &tree,
+            &state.account_root,
+            &state.rewards_pool,
+            &state.network_params,
+        );
+        if parts.app_hash() == app_hash {
+            Some(parts)
+        } else {
+            None
+        }
+    }
+
+    /// Proof operations of a staked state: the jellyfish proof against the staking trie root
+    /// ("staking") and the parts of the app hash which commit to that root ("app_hash")
+    fn staking_proof(
+        &self,
+        height: Option<BlockHeight>,
+        address: &StakedStateAddress,
+        proof: &SparseMerkleProof,
+    ) -> Proof {
+        let mut ops = vec![ProofOp {
+            field_type: "staking".to_owned(),
+            key: address.encode(),
+            data: proof.encode(),
+            ..Default::default()
+        }];
+        // links the trie root to the app hash (for past heights only if the node stores
+        // historical states, i.e. runs with tx query address set)
+        if let Some(parts) = self.app_hash_parts(height) {
+            ops.push(ProofOp {
+                field_type: "app_hash".to_owned(),
+                key: parts.app_hash().to_vec(),
+                data: parts.encode(),
+                ..Default::default()
+            });
+        }
+        Proof {
+            ops: ops.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Version of the staking trie at given height (or of the last committed state)
+    fn staking_version(&self, height: Option<BlockHeight>) -> Option<Version> {
+        if let Some(height) = height {
+            self.storage.get_historical_staking_version(height)
+        } else {
+            self.last_state.as_ref().map(|state| state.staking_version)
+        }
+    }
+
+    /// Past height requested by a transaction lookup (non-positive heights and heights from the
+    /// last committed block on query the latest state)
+    fn tx_query_height(&self, req: &RequestQuery) -> Option<BlockHeight> {
+        let height = BlockHeight::try_from(req.height).ok()?;
+        let last_height = self.last_state.as_ref()?.last_block_height;
+        if height > BlockHeight::genesis() && height < last_height {
+            Some(height)
+        } else {
+            None
+        }
+    }
+
+    /// Checks that the transaction was applied in a block at or below the given height, or logs
+    /// an error (stored in the response)
+    fn check_tx_height(&self, resp: &mut ResponseQuery, data_key: &[u8], height: BlockHeight) {
+        if let Some(txid) = get_key(resp, data_key) {
+            let included = self
+                .storage
+                .get_tx_result(&txid)
+                .and_then(|raw| TxResult::decode(&mut raw.as_slice()).ok())
+                .map_or(false, |result| result.is_ok() && result.height <= height);
+            if !included {
+                resp.log += "tx not found at the height";
+                resp.code = 1;
+            }
+        }
+    }
+
     fn lookup_key(
         &self,
         resp: &mut ResponseQuery,
@@ -85,14 +206,76 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
             return resp;
         }
 
+        // transaction lookups at a past height only see the transactions applied up to it
+        if let Some(height) = self.tx_query_height(_req) {
+            match _req.path.as_ref() {
+                "store" | "witness" | "sealed" => {
+                    self.check_tx_height(&mut resp, &_req.data[..], height);
+                }
+                "meta" => {
+                    resp.log += "spent flags at past heights are not stored";
+                    resp.code = 4;
+                }
+                _ => {}
+            }
+            if resp.code != 0 {
+                return resp;
+            }
+        }
+
         match _req.path.as_ref() {
-            "txquery" => match &self.tx_query_address {
-                Some(addr) => {
-                    resp.value = addr.clone().into_bytes();
+            "txquery" => match &self.tx_query_endpoints {
+                Some(endpoints) => match endpoints.advertise() {
+                    Some(addr) => {
+                        resp.value = addr.as_bytes().to_vec();
+                    }
+                    None => {
+                        resp.code = 1;
+                        resp.log += "no healthy tx query endpoint";
+                    }
+                },
+                None => match &self.tx_query_address {
+                    Some(addr) => {
+                        resp.value = addr.clone().into_bytes();
+                    }
+                    None => {
+                        resp.code = 1;
+                        resp.log += "tx query address not set";
+                    }
+                },
+            },
+            "txquery-endpoints" => match &self.tx_query_endpoints {
+                Some(endpoints) => {
+                    resp.value = serde_json::to_vec(&endpoints.health()).unwrap();
+                }
+                None => {
+                    resp.code = 1;
+                    resp.log += "tx query endpoints not set";
+                }
+            },
+            "reconciliation" => match &self.last_reconciliation {
+                Some(report) => {
+                    resp.value = serde_json::to_vec(report).unwrap();
                 }
                 None => {
                     resp.code = 1;
-                    resp.log += "tx query address not set";
+                    resp.log += "no block reconciled since start";
+                }
+            },
+            "scrub" => match &self.scrub_metrics {
+                Some(metrics) => {
+                    let stats = metrics.stats();
+                    resp.value = serde_json::to_vec(&serde_json::json!({
+                        "entries_checked": stats.entries_checked,
+                        "corrupt_entries": stats.corrupt_entries,
+                        "passes": stats.passes,
+                        "last_pass_millis": stats.last_pass_millis,
+                    }))
+                    .unwrap();
+                }
+                None => {
+                    resp.code = 1;
+                    resp.log += "trie scrubber not running";
                 }
             },
             "tdbe" => {
@@ -108,21 +291,11 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                 if let (Some(txid), true) = (key, _req.prove) {
                     let mwitness = self.storage.lookup_item(LookupItem::TxWitness, &txid);
                     if let Some(witness) = mwitness {
-                        // Negative height default to 0
-                        let req_height = _req
-                            .height
-                            .try_into()
-                            .unwrap_or_else(|_| BlockHeight::genesis());
-                        let last_height = self
-                            .last_state
-                            .as_ref()
-                            .map_or(BlockHeight::genesis(), |x| x.last_block_height);
-                        let height =
-                            if req_height == BlockHeight::genesis() || req_height > last_height {
-                                last_height
-                            } else {
-                                req_height
-                            };
+                        let height = self.tx_query_height(_req).unwrap_or_else(|| {
+                            self.last_state
+                                .as_ref()
+                                .map_or(BlockHeight::genesis(), |x| x.last_block_height)
+                        });
                         // note this should not crash if Tendermint delivers all blocks with height in order
                         // TODO: invariant / sanity check in rust-abci?
                         let app_hash = self.storage.get_historical_app_hash(height).unwrap();
@@ -175,14 +348,26 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                 );
             }
             "account" => {
+                // non-positive heights query the latest state (as before heights were supported)
+                let mheight = BlockHeight::try_from(_req.height)
+                    .ok()
+                    .filter(|height| *height != BlockHeight::genesis());
                 let account_address = StakedStateAddress::try_from(_req.data.as_slice());
-                if let (Some(state), Ok(address)) = (&self.last_state, account_address) {
-                    let (account, _proof) =
-                        get_with_proof(&self.storage, state.staking_version, &address);
+                if let (Some(version), Ok(address)) =
+                    (self.staking_version(mheight), account_address)
+                {
+                    if !has_version(&self.storage, version) {
+                        resp.log += "account lookup failed: account state at the height is pruned";
+                        resp.code = 4;
+                        return resp;
+                    }
+                    let (account, proof) = get_with_proof(&self.storage, version, &address);
                     match account {
                         Some(a) => {
                             resp.value = a.encode();
-                            // TODO: inclusion proof
+                            if _req.prove {
+                                resp.set_proof(self.staking_proof(mheight, &address, &proof));
+                            }
                         }
                         None => {
                             resp.log += "account lookup failed: account not exists";
@@ -195,26 +380,20 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                 }
             }
             "staking" => {
-                let mversion = if let Ok(height) = _req.height.try_into() {
-                    self.storage.get_historical_staking_version(height)
-                } else {
-                    self.last_state.as_ref().map(|state| state.staking_version)
-                };
+                let mheight: Option<BlockHeight> = _req.height.try_into().ok();
                 let account_address = StakedStateAddress::try_from(_req.data.as_slice());
-                if let (Some(version), Ok(address)) = (mversion, account_address) {
+                if let (Some(version), Ok(address)) =
+                    (self.staking_version(mheight), account_address)
+                {
+                    if !has_version(&self.storage, version) {
+                        resp.log += "staking lookup failed: staking state at the height is pruned";
+                        resp.code = 4;
+                        return resp;
+                    }
                     let (mstaking, proof) = get_with_proof(&self.storage, version, &address);
                     resp.value = mstaking.encode();
                     if _req.prove {
-                        resp.set_proof(Proof {
-                            ops: vec![ProofOp {
-                                field_type: "staking".to_owned(),
-                                key: address.encode(),
-                                data: proof.encode(),
-                                ..Default::default()
-                            }]
-                            .into(),
-                            ..Default::default()
-                        });
+                        resp.set_proof(self.staking_proof(mheight, &address, &proof));
                     }
                 } else {
                     resp.log += "account lookup failed (either invalid address or node not correctly restored / initialized)";
@@ -257,6 +436,65 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
                     .expect("Unable to serialize validator metadata into json")
                     .into_bytes();
             }
+            "rewards_pool" => {
+                let top_level = &self
+                    .last_state
+                    .as_ref()
+                    .expect("Missing last_state: init chain was not called")
+                    .top_level;
+                resp.value = serde_json::to_vec(&top_level.rewards_pool)
+                    .expect("Unable to serialize rewards pool state into json");
+            }
+            "network_params" => {
+                let top_level = &self
+                    .last_state
+                    .as_ref()
+                    .expect("Missing last_state: init chain was not called")
+                    .top_level;
+                resp.value = serde_json::to_vec(&top_level.network_params)
+                    .expect("Unable to serialize network parameters into json");
+            }
+            "txs_by_address" => {
+                if !self.address_index {
+                    resp.log += "address index not enabled";
+                    resp.code = 1;
+                } else if let Ok(address) = StakedStateAddress::try_from(_req.data.as_slice()) {
+                    resp.value = chain_storage::get_address_txs(&self.storage, &address)
+                        .unwrap_or_else(|| Vec::<(BlockHeight, TxId)>::new().encode());
+                } else {
+                    resp.log += "invalid address";
+                    resp.code = 4;
+                }
+            }
+            "compact-block" => {
+                if let Ok(height) = _req.height.try_into() {
+                    match self.storage.get_compact_block(height) {
+                        Some(value) => {
+                            resp.value = value;
+                        }
+                        None => {
+                            resp.log += "compact block not found";
+                            resp.code = 2;
+                        }
+                    }
+                } else {
+                    resp.log += "invalid block height";
+                    resp.code = 4;
+                }
+            }
+            "tx_result" => {
+                if let Some(txid) = get_key(&mut resp, &_req.data[..]) {
+                    match self.storage.get_tx_result(&txid) {
+                        Some(value) => {
+                            resp.value = value;
+                        }
+                        None => {
+                            resp.log += "tx result not found";
+                            resp.code = 1;
+                        }
+                    }
+                }
+            }
             "sealed" => {
                 self.lookup(
                     &mut resp,
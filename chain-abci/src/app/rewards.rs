@@ -1,10 +1,12 @@
 use std::cmp::min;
 
 use crate::app::ChainNodeApp;
+use crate::beacon::RandomnessBeacon;
 use crate::enclave_bridge::EnclaveProxy;
 use chain_core::fixed::monetary_expansion;
 use chain_core::init::coin::Coin;
 use chain_core::state::account::StakedStateAddress;
+use chain_core::REWARD_REMAINDER_APP_VERSION;
 use chain_storage::jellyfish::StakingGetter;
 
 // rate < 1_000_000, no overflow.
@@ -69,9 +71,16 @@ impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
         let total_rewards = (top_level.rewards_pool.period_bonus + minted).unwrap();
         top_level.rewards_pool.minted = (top_level.rewards_pool.minted + minted).unwrap();
 
+        let mut beacon = RandomnessBeacon::new(&state.last_apphash, state.block_height);
+        let beacon = if state.app_version >= REWARD_REMAINDER_APP_VERSION {
+            Some(&mut beacon)
+        } else {
+            None
+        };
         let (remainer, reward_distribution) = state.staking_table.reward_distribute(
             &mut staking_store!(self, state.staking_version),
             total_rewards,
+            beacon,
         );
 
         top_level.rewards_pool.period_bonus = remainer;
@@ -162,6 +171,51 @@ mod tests {
         assert!(reward2 > Coin::zero() && reward2 < reward1);
     }
 
+    #[test]
+    fn reward_remainder_should_follow_the_state_app_version() {
+        // 11 base units minted for 2 validators with the same voting power
+        let run_first_period = |upgrade: bool| {
+            let dist = Coin::new(10_0000_0000_0000_0000).unwrap();
+            let expansion_cap = Coin::new(11).unwrap();
+            let (mut env, storage) = ChainEnv::new(dist, expansion_cap, 2);
+            env.init_config.app_version = chain_core::INITIAL_APP_VERSION;
+            let mut app = env.chain_node(storage);
+            if upgrade {
+                app.schedule_app_version_upgrade(1.into(), chain_core::APP_VERSION);
+            }
+            let _rsp = app.init_chain(&env.req_init_chain());
+
+            let state = app.last_state.as_ref().unwrap();
+            let mut req = env.req_begin_block(1, 0);
+            req.mut_header().set_time(seconds_to_timestamp(
+                state.block_time
+                    + state
+                        .top_level
+                        .network_params
+                        .get_rewards_reward_period_seconds(),
+            ));
+            req.set_last_commit_info(env.last_commit_info_signed());
+            app.begin_block(&req);
+            app.end_block(&RequestEndBlock::new());
+            app.commit(&RequestCommit::new());
+
+            let state = app.last_state.unwrap();
+            assert_eq!(state.top_level.rewards_pool.minted, expansion_cap);
+            (state.app_version, state.top_level.rewards_pool.period_bonus)
+        };
+
+        // replayed with the initial rules, the rounding remainder stays in the rewards pool
+        assert_eq!(
+            run_first_period(false),
+            (chain_core::INITIAL_APP_VERSION, Coin::unit())
+        );
+        // handed out to a validator from the upgrade height
+        assert_eq!(
+            run_first_period(true),
+            (chain_core::APP_VERSION, Coin::zero())
+        );
+    }
+
     #[test]
     fn empty_block_should_not_change_app_hash() {
         let (env, storage) = ChainEnv::new(Coin::max(), Coin::zero(), 1);
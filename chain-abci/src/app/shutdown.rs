@@ -0,0 +1,95 @@
+//! Graceful shutdown on SIGTERM / SIGINT
+//!
+//! Each block is committed as a single write batch, so a shutdown only needs to wait for the
+//! commit in progress (if any) and keep later ones from starting. Transactions delivered in an
+//! uncommitted block are only kept in memory buffers: they are dropped, and Tendermint replays
+//! the block on restart. The database files are synced before exit, so that the last commit
+//! survives a power loss right after the shutdown.
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::ChainNodeApp;
+use crate::enclave_bridge::EnclaveProxy;
+
+/// Handle used by the signal handler to shut the app down between commits
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    commit_lock: Arc<Mutex<()>>,
+    db_path: Option<PathBuf>,
+}
+
+impl ShutdownHandle {
+    /// Waits for the commit in progress and syncs the database files, no block is committed
+    /// while the returned guard is held (i.e. until the process exits)
+    pub fn wait_and_sync(&self) -> io::Result<MutexGuard<'_, ()>> {
+        let guard = lock_commits(&self.commit_lock);
+        if let Some(path) = self.db_path.as_ref() {
+            sync_files(path)?;
+        }
+        Ok(guard)
+    }
+}
+
+/// Locks the commits, a commit which panicked doesn't prevent the shutdown
+pub(crate) fn lock_commits(commit_lock: &Mutex<()>) -> MutexGuard<'_, ()> {
+    commit_lock.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Syncs the files in the database directory (and the directory itself where supported)
+fn sync_files(path: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            File::open(entry.path())?.sync_all()?;
+        }
+    }
+    // directories can't be opened for syncing on every platform
+    if let Ok(dir) = File::open(path) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Returns a handle to shut the app down between commits, syncing the files of the database
+    /// directory (if given) before exit
+    pub fn shutdown_handle(&self, db_path: Option<PathBuf>) -> ShutdownHandle {
+        ShutdownHandle {
+            commit_lock: self.commit_lock.clone(),
+            db_path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn check_shutdown_waits_for_commit() {
+        let db_path = std::env::temp_dir().join(format!("shutdown-test-{}", std::process::id()));
+        fs::create_dir_all(&db_path).unwrap();
+        fs::write(db_path.join("000001.log"), b"wal").unwrap();
+        let commit_lock = Arc::new(Mutex::new(()));
+        let handle = ShutdownHandle {
+            commit_lock: commit_lock.clone(),
+            db_path: Some(db_path.clone()),
+        };
+        let committing = lock_commits(&commit_lock);
+        let (sender, receiver) = mpsc::channel();
+        let shutdown = thread::spawn(move || {
+            let _guard = handle.wait_and_sync().unwrap();
+            sender.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(100)).is_err());
+        drop(committing);
+        assert!(receiver.recv_timeout(Duration::from_secs(10)).is_ok());
+        shutdown.join().unwrap();
+        fs::remove_dir_all(db_path).unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+//! State sync snapshots
+//!
+//! The node exports a snapshot of its state every `interval` blocks (after Commit), and a new
+//! node can restore its state from the chunks of a snapshot instead of replaying all blocks.
+//! The methods below follow the state sync requests of ABCI (ListSnapshots, LoadSnapshotChunk,
+//! OfferSnapshot and ApplySnapshotChunk), which the `abci` crate doesn't support yet (they
+//! require Tendermint 0.34), so they aren't dispatched by the ABCI server until it's upgraded.
+use std::convert::TryInto;
+use std::mem;
+
+use log::{error, info, warn};
+use parity_scale_codec::Decode;
+
+use super::app_init::ChainNodeState;
+use super::ChainNodeApp;
+use crate::enclave_bridge::EnclaveProxy;
+use chain_core::common::H256;
+use chain_core::state::tendermint::BlockHeight;
+use chain_storage::buffer::flush_storage;
+use chain_storage::jellyfish::StakingGetter;
+use chain_storage::snapshot::{
+    verify_trie, SnapshotError, SnapshotMetadata, SnapshotRestorer, SnapshotStore, SNAPSHOT_FORMAT,
+};
+
+/// Exported snapshots and their schedule
+pub struct SnapshotManager {
+    /// directory of the snapshots
+    pub store: SnapshotStore,
+    /// blocks between snapshots
+    pub interval: u64,
+    /// number of the latest snapshots kept
+    pub keep_recent: usize,
+    /// approximate size of the chunks in bytes
+    pub chunk_size: usize,
+}
+
+/// Response to an offered snapshot (`ResponseOfferSnapshot.Result` in ABCI)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfferSnapshotResult {
+    /// start restoring the snapshot
+    Accept,
+    /// abort state sync (e.g. the node already has a state)
+    Abort,
+    /// reject the snapshot, try another one
+    Reject,
+    /// reject all snapshots of the format
+    RejectFormat,
+}
+
+/// Response to an applied chunk (`ResponseApplySnapshotChunk.Result` in ABCI)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplySnapshotChunkResult {
+    /// chunk applied
+    Accept,
+    /// abort state sync
+    Abort,
+    /// refetch and apply the chunk again
+    Retry,
+    /// reject the snapshot, try another one
+    RejectSnapshot,
+}
+
+impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Enables exporting snapshots after Commit
+    pub fn set_snapshots(&mut self, snapshots: SnapshotManager) {
+        self.snapshots = Some(snapshots);
+    }
+
+    /// Exports a snapshot of the just committed block, if one is due at its height.
+    /// Failures are logged and don't affect consensus.
+    pub fn take_snapshot_if_due(&self, height: BlockHeight) {
+        let snapshots = match self.snapshots.as_ref() {
+            Some(snapshots) if snapshots.interval > 0 => snapshots,
+            _ => return,
+        };
+        if height.value() == 0 || height.value() % snapshots.interval != 0 {
+            return;
+        }
+        let result =
+            snapshots
+                .store
+                .export(&self.storage.get_read_only(), height, snapshots.chunk_size);
+        match result {
+            Ok(metadata) => info!(
+                "exported snapshot at height {} ({} chunks)",
+                height,
+                metadata.chunks()
+            ),
+            Err(e) => error!("failed to export snapshot at height {}: {}", height, e),
+        }
+        if let Err(e) = snapshots.store.prune(snapshots.keep_recent) {
+            error!("failed to prune snapshots: {}", e);
+        }
+    }
+
+    /// Handles ListSnapshots: metadata of the exported snapshots
+    pub fn list_snapshots(&self) -> Vec<SnapshotMetadata> {
+        let snapshots = match self.snapshots.as_ref() {
+            Some(snapshots) => snapshots,
+            None => return Vec::new(),
+        };
+        snapshots.store.list().unwrap_or_else(|e| {
+            error!("failed to list snapshots: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Handles LoadSnapshotChunk: a chunk of an exported snapshot
+    pub fn load_snapshot_chunk(&self, height: u64, format: u32, chunk: u32) -> Option<Vec<u8>> {
+        if format != SNAPSHOT_FORMAT {
+            return None;
+        }
+        let snapshots = self.snapshots.as_ref()?;
+        snapshots
+            .store
+            .load_chunk(BlockHeight::new(height), chunk)
+            .unwrap_or_else(|e| {
+                error!("failed to load snapshot chunk: {}", e);
+                None
+            })
+    }
+
+    /// Handles OfferSnapshot: `metadata` is the metadata of the offered snapshot,
+    /// `app_hash` is the trusted app hash at its height (verified by the light client)
+    pub fn offer_snapshot(&mut self, metadata: &[u8], app_hash: &[u8]) -> OfferSnapshotResult {
+        if self.last_state.is_some() {
+            warn!("snapshot offered, but the node already has a state");
+            return OfferSnapshotResult::Abort;
+        }
+        let metadata = match SnapshotMetadata::decode(&mut &metadata[..]) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                warn!("invalid snapshot metadata: {}", e.what());
+                return OfferSnapshotResult::Reject;
+            }
+        };
+        let app_hash: H256 = match app_hash.try_into() {
+            Ok(app_hash) => app_hash,
+            Err(_) => return OfferSnapshotResult::Reject,
+        };
+        if let Some(restorer) = self.snapshot_restore.take() {
+            if let Err(e) = restorer.abort(&mut self.storage) {
+                error!("failed to clean up the previous snapshot: {}", e);
+                return OfferSnapshotResult::Abort;
+            }
+        }
+        match SnapshotRestorer::new(metadata, &app_hash) {
+            Ok(restorer) => {
+                info!(
+                    "restoring snapshot at height {}",
+                    restorer.metadata().height
+                );
+                self.snapshot_restore = Some(restorer);
+                OfferSnapshotResult::Accept
+            }
+            Err(SnapshotError::UnsupportedFormat(_)) => OfferSnapshotResult::RejectFormat,
+            Err(e) => {
+                warn!("snapshot rejected: {}", e);
+                OfferSnapshotResult::Reject
+            }
+        }
+    }
+
+    /// Handles ApplySnapshotChunk: the state is loaded after the last chunk was applied and
+    /// the restored state was verified against the app hash
+    pub fn apply_snapshot_chunk(&mut self, index: u32, chunk: &[u8]) -> ApplySnapshotChunkResult {
+        let restorer = match self.snapshot_restore.as_mut() {
+            Some(restorer) => restorer,
+            None => return ApplySnapshotChunkResult::Abort,
+        };
+        let result = match restorer.apply_chunk(&mut self.storage, index, chunk) {
+            Ok(None) => return ApplySnapshotChunkResult::Accept,
+            Ok(Some(state)) => self.load_restored_state(&state),
+            Err(e @ SnapshotError::UnexpectedChunk { .. })
+            | Err(e @ SnapshotError::ChunkHashMismatch(_)) => {
+                warn!("snapshot chunk {} not applied: {}", index, e);
+                return ApplySnapshotChunkResult::Retry;
+            }
+            Err(SnapshotError::Io(e)) => {
+                error!("snapshot storage io error: {}", e);
+                return ApplySnapshotChunkResult::Abort;
+            }
+            Err(e) => Err(e.to_string()),
+        };
+
+        let restorer = self
+            .snapshot_restore
+            .take()
+            .expect("snapshot restore in progress");
+        match result {
+            Ok(()) => {
+                info!("restored snapshot at height {}", restorer.metadata().height);
+                ApplySnapshotChunkResult::Accept
+            }
+            Err(e) => {
+                warn!("snapshot rejected: {}", e);
+                if let Err(e) = restorer.abort(&mut self.storage) {
+                    error!("failed to clean up the rejected snapshot: {}", e);
+                    return ApplySnapshotChunkResult::Abort;
+                }
+                ApplySnapshotChunkResult::RejectSnapshot
+            }
+        }
+    }
+
+    /// Verifies the restored chain state against the trusted app hash, then stores it
+    fn load_restored_state(&mut self, encoded_state: &[u8]) -> Result<(), String> {
+        let metadata = self
+            .snapshot_restore
+            .as_ref()
+            .expect("snapshot restore in progress")
+            .metadata();
+        let mut state = ChainNodeState::decode(&mut &encoded_state[..])
+            .map_err(|e| format!("invalid chain state: {}", e.what()))?;
+        if state.last_block_height != metadata.height || state.last_apphash != metadata.app_hash {
+            return Err("chain state isn't the one of the snapshot".to_owned());
+        }
+
+        let staking_root = verify_trie(&self.storage.get_read_only(), state.staking_version)
+            .map_err(|e| e.to_string())?;
+        if staking_root != state.top_level.account_root {
+            return Err("staking trie root doesn't match the chain state".to_owned());
+        }
+        self.verify_app_hash(&state)?;
+
+        state.staking_table.initialize(
+            &StakingGetter::new(&self.storage, state.staking_version),
+            state
+                .top_level
+                .network_params
+                .get_required_council_node_stake(),
+        );
+        chain_storage::store_chain_state(
+            &mut kv_store!(self),
+            &state,
+            state.last_block_height,
+            self.tx_query_address.is_some(),
+        );
+        flush_storage(&mut self.storage, mem::take(&mut self.kv_buffer))
+            .map_err(|e| e.to_string())?;
+        self.last_state = Some(state.clone());
+        self.mempool_state = Some(state);
+        Ok(())
+    }
+}
@@ -7,6 +7,7 @@ use abci::*;
 use chain_core::common::{TendermintEventKey, TendermintEventType, Timespec};
 use chain_core::init::coin::Coin;
 use chain_core::state::account::{CouncilNodeMeta, PunishmentKind, StakedStateAddress};
+use chain_core::state::tendermint::TendermintVotePower;
 use chain_core::tx::fee::Fee;
 
 pub(crate) enum StakingEvent<'a> {
@@ -18,6 +19,7 @@ pub(crate) enum StakingEvent<'a> {
     Jail(&'a StakedStateAddress, Timespec, PunishmentKind),
     Slash(&'a StakedStateAddress, Coin, Coin, PunishmentKind),
     Unjail(&'a StakedStateAddress),
+    ValidatorUpdate(&'a StakedStateAddress, TendermintVotePower),
 }
 
 impl<'a> From<StakingEvent<'a>> for Event {
@@ -55,6 +57,9 @@ impl<'a> From<StakingEvent<'a>> for Event {
                 punishment_kind,
             ),
             StakingEvent::Unjail(staking_address) => builder.unjail(staking_address),
+            StakingEvent::ValidatorUpdate(staking_address, voting_power) => {
+                builder.validator_update(staking_address, voting_power)
+            }
         }
 
         builder.to_event()
@@ -188,6 +193,19 @@ impl StakingEventBuilder {
         self.attributes.push(StakingEventOpType::Unjail.into());
     }
 
+    fn validator_update(
+        &mut self,
+        staking_address: &StakedStateAddress,
+        voting_power: TendermintVotePower,
+    ) {
+        self.attributes
+            .push(staking_address_attribute(staking_address));
+        self.attributes
+            .push(StakingEventOpType::ValidatorUpdate.into());
+        self.attributes
+            .push(StakingDiffField(vec![StakingDiff::VotingPower(voting_power)]).into());
+    }
+
     fn to_event(&self) -> Event {
         let mut event = Event::new();
         event.field_type = TendermintEventType::StakingChange.to_string();
@@ -225,6 +243,7 @@ enum StakingEventOpType {
     Jail,
     Slash,
     Unjail,
+    ValidatorUpdate,
 }
 
 impl fmt::Display for StakingEventOpType {
@@ -238,6 +257,7 @@ impl fmt::Display for StakingEventOpType {
             StakingEventOpType::Jail => write!(f, "jail"),
             StakingEventOpType::Slash => write!(f, "slash"),
             StakingEventOpType::Unjail => write!(f, "unjail"),
+            StakingEventOpType::ValidatorUpdate => write!(f, "validatorupdate"),
         }
     }
 }
@@ -282,6 +302,7 @@ enum StakingDiff {
     UnbondedFrom(Timespec),
     NodeJoin(CouncilNodeMeta),
     JailedUntil(Timespec),
+    VotingPower(TendermintVotePower),
 }
 
 impl Serialize for StakingDiff {
@@ -326,6 +347,12 @@ impl Serialize for StakingDiff {
                 state.serialize_field("value", &jailed_until)?;
                 state.end()
             }
+            StakingDiff::VotingPower(voting_power) => {
+                let mut state = serializer.serialize_struct("VotingPower", 2)?;
+                state.serialize_field("key", "VotingPower")?;
+                state.serialize_field("value", voting_power.to_string().as_str())?;
+                state.end()
+            }
         }
     }
 }
@@ -472,6 +499,21 @@ mod tests {
                 );
             }
         }
+
+        mod voting_power {
+            use super::*;
+
+            #[test]
+            fn to_string_should_serialize_to_json() {
+                let any_voting_power = TendermintVotePower::new(10).unwrap();
+                let staking_diff = StakingDiff::VotingPower(any_voting_power);
+
+                assert_eq!(
+                    staking_diff.to_string(),
+                    "{\"key\":\"VotingPower\",\"value\":\"10\"}",
+                );
+            }
+        }
     }
 
     mod staking_event {
@@ -629,6 +671,21 @@ mod tests {
             }
         }
 
+        mod validator_update {
+            use super::*;
+
+            #[test]
+            fn should_create_validator_update_event() {
+                let any_staking_address = any_staking_address();
+                let any_voting_power = TendermintVotePower::new(10).unwrap();
+
+                let event: Event =
+                    StakingEvent::ValidatorUpdate(&any_staking_address, any_voting_power).into();
+
+                assert_validator_update_event(event, any_staking_address, any_voting_power);
+            }
+        }
+
         fn assert_deposit_event(
             event: Event,
             staking_address: StakedStateAddress,
@@ -934,6 +991,43 @@ mod tests {
             );
         }
 
+        fn assert_validator_update_event(
+            event: Event,
+            staking_address: StakedStateAddress,
+            voting_power: TendermintVotePower,
+        ) {
+            assert_eq!(
+                event.field_type,
+                TendermintEventType::StakingChange.to_string()
+            );
+            assert_eq!(event.attributes.len(), 3);
+
+            let staking_address_attribute = event.attributes.first().unwrap();
+            assert_kv_pair(
+                staking_address_attribute,
+                TendermintEventKey::StakingAddress.to_string(),
+                staking_address.to_string(),
+            );
+
+            let staking_optype_attribute = event.attributes.get(1).unwrap();
+            assert_kv_pair(
+                staking_optype_attribute,
+                TendermintEventKey::StakingOpType.to_string(),
+                StakingEventOpType::ValidatorUpdate.to_string(),
+            );
+
+            let staking_diff_attribute = event.attributes.get(2).unwrap();
+            let expected_value = format!(
+                "[{{\"key\":\"VotingPower\",\"value\":\"{}\"}}]",
+                i64::from(voting_power)
+            );
+            assert_kv_pair(
+                staking_diff_attribute,
+                TendermintEventKey::StakingDiff.to_string(),
+                expected_value,
+            );
+        }
+
         fn assert_kv_pair(kv_pair: &KVPair, expected_key: String, expected_value: String) {
             assert_eq!(String::from_utf8_lossy(&kv_pair.key), expected_key);
             assert_eq!(String::from_utf8_lossy(&kv_pair.value), expected_value);
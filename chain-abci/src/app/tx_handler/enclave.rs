@@ -0,0 +1,142 @@
+use parity_scale_codec::Encode;
+
+use super::{KVStore, StakingStore, TxContext, TxHandler};
+use crate::app::ChainNodeState;
+use crate::enclave_bridge::EnclaveProxy;
+use crate::storage::{verify_enclave_tx, TxAction, TxEnclaveAction};
+use crate::tx_error::TxError;
+use chain_core::tx::data::input::{TxoPointer, TxoSize};
+use chain_core::tx::data::TxId;
+use chain_core::tx::{TxAux, TxEnclaveAux};
+use chain_storage::buffer::StoreKV;
+
+/// Transactions validated in the enclave: transfer, deposit and withdraw
+pub struct EnclaveTxHandler;
+
+impl<T: EnclaveProxy> TxHandler<T> for EnclaveTxHandler {
+    fn process(&self, ctx: TxContext<'_, T>, txaux: &TxAux) -> Result<TxAction, TxError> {
+        let TxContext {
+            tx_validator,
+            chain_info,
+            state,
+            mut staking_store,
+            mut kv_store,
+        } = ctx;
+        let tx = match txaux {
+            TxAux::EnclaveTx(tx) => tx,
+            _ => unreachable!("enclave tx handler registered for other transaction kinds"),
+        };
+        let txid = txaux.tx_id();
+        let action = verify_enclave_tx(tx_validator, &tx, chain_info, &staking_store, &kv_store)?;
+        // execute the action
+        execute_enclave_tx(&mut staking_store, &mut kv_store, state, &txid, &action);
+
+        // update utxo coins
+        match action {
+            TxEnclaveAction::Withdraw {
+                withdraw: (_, coin),
+                fee,
+                ..
+            } => state.utxo_coins = (state.utxo_coins + (coin - fee.to_coin()).unwrap()).unwrap(),
+            TxEnclaveAction::Deposit {
+                deposit: (_, coin),
+                fee,
+                ..
+            } => state.utxo_coins = (state.utxo_coins - (coin + fee.to_coin()).unwrap()).unwrap(),
+            TxEnclaveAction::Transfer { fee, .. } => {
+                state.utxo_coins = (state.utxo_coins - fee.to_coin()).unwrap()
+            }
+        }
+
+        Ok(TxAction::Enclave(action))
+    }
+
+    fn commit(&self, kv_store: &mut KVStore<'_>, txid: &TxId, txaux: &TxAux) {
+        match txaux {
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                inputs,
+                no_of_outputs,
+                ..
+            }) => {
+                update_utxos_commit(&inputs, *no_of_outputs, txid, kv_store);
+            }
+            TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { tx, .. }) => {
+                chain_storage::store_tx_body(kv_store, txid, &tx.encode());
+                // witness is obfuscated -- TODO: could be stored on the enclave side or thrown away?
+                // this is not necessary (as they are spent in deliver_tx) and more of a sanity check (as update_utxos_commit does it)
+                chain_storage::spend_utxos(kv_store, &tx.inputs);
+                // account should be already updated in deliver_tx
+            }
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                witness,
+                no_of_outputs,
+                ..
+            }) => {
+                chain_storage::store_tx_witness(kv_store, txid, &witness.encode());
+                // account should be already updated in deliver_tx
+                chain_storage::create_utxo(kv_store, *no_of_outputs, txid);
+            }
+            _ => unreachable!("enclave tx handler registered for other transaction kinds"),
+        }
+    }
+}
+
+/// Given a db and a DB transaction, it will go through TX inputs and mark them as spent
+/// in the TX_META storage and it will create a new entry for TX in TX_META with all outputs marked as unspent.
+fn update_utxos_commit(
+    inputs: &[TxoPointer],
+    no_of_outputs: TxoSize,
+    txid: &TxId,
+    db: &mut impl StoreKV,
+) {
+    chain_storage::spend_utxos(db, &inputs);
+    chain_storage::create_utxo(db, no_of_outputs, txid);
+}
+
+fn execute_enclave_tx(
+    trie: &mut StakingStore<'_>,
+    kvdb: &mut KVStore<'_>,
+    state: &mut ChainNodeState,
+    txid: &TxId,
+    action: &TxEnclaveAction,
+) {
+    match action {
+        TxEnclaveAction::Transfer {
+            spend_utxo,
+            sealed_log,
+            ..
+        } => {
+            chain_storage::spend_utxos(kvdb, &spend_utxo);
+            // Done in commit event
+            // storage.create_utxo(no_of_outputs, txid);
+            chain_storage::store_sealed_log(kvdb, &txid, sealed_log);
+        }
+        TxEnclaveAction::Deposit {
+            spend_utxo,
+            deposit: (address, amount),
+            ..
+        } => {
+            chain_storage::spend_utxos(kvdb, &spend_utxo);
+            state
+                .staking_table
+                .deposit(trie, address, *amount)
+                .expect("deposit sanity check");
+        }
+        TxEnclaveAction::Withdraw {
+            withdraw: (address, amount),
+            sealed_log,
+            ..
+        } => {
+            // Done in commit event
+            // storage.create_utxo(no_of_outputs, txid);
+            chain_storage::store_sealed_log(kvdb, &txid, sealed_log);
+
+            // no panic: tx is already verified, all the error in execution is not allowed.
+            // operations are sequential in the state machine, so no concurrent updates
+            state
+                .staking_table
+                .withdraw(trie, state.block_time, address, *amount)
+                .expect("withdraw sanity check");
+        }
+    }
+}
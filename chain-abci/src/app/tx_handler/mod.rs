@@ -0,0 +1,232 @@
+//! Dispatch of transaction kinds to their handlers
+//!
+//! A new transaction type only needs its `TxKind`, a handler module and a registration in
+//! `TxRegistry::default`; the app code (CheckTx, DeliverTx, Commit) goes through the registry.
+mod enclave;
+mod params_update;
+mod public;
+
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use self::enclave::EnclaveTxHandler;
+use self::params_update::NetworkParamsUpdateTxHandler;
+use self::public::PublicTxHandler;
+use super::ChainNodeState;
+use crate::enclave_bridge::EnclaveProxy;
+use crate::storage::TxAction;
+use crate::tx_error::TxError;
+use chain_core::tx::data::TxId;
+use chain_core::tx::{TxAux, TxEnclaveAux, TxPublicAux};
+use chain_core::ChainInfo;
+use chain_storage::buffer::BufferStore;
+use chain_storage::jellyfish::StakingBufferStore;
+use chain_storage::Storage;
+
+/// Buffered key-value storage transactions are executed against
+pub type KVStore<'a> = BufferStore<'a, Storage, RandomState>;
+/// Buffered staking storage transactions are executed against
+pub type StakingStore<'a> = StakingBufferStore<'a, Storage, RandomState>;
+
+/// Kinds of transactions, the key handlers are registered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TxKind {
+    Transfer,
+    DepositStake,
+    WithdrawUnbondedStake,
+    UnbondStake,
+    Unjail,
+    NodeJoin,
+    NetworkParamsUpdate,
+    MLSHandshake,
+}
+
+impl TxKind {
+    pub fn of(txaux: &TxAux) -> Self {
+        match txaux {
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx { .. }) => TxKind::Transfer,
+            TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { .. }) => TxKind::DepositStake,
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx { .. }) => {
+                TxKind::WithdrawUnbondedStake
+            }
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(..)) => TxKind::UnbondStake,
+            TxAux::PublicTx(TxPublicAux::UnjailTx(..)) => TxKind::Unjail,
+            TxAux::PublicTx(TxPublicAux::NodeJoinTx(..)) => TxKind::NodeJoin,
+            TxAux::PublicTx(TxPublicAux::NetworkParamsUpdateTx(..)) => TxKind::NetworkParamsUpdate,
+            TxAux::MLSHandshake(_) => TxKind::MLSHandshake,
+        }
+    }
+}
+
+impl fmt::Display for TxKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TxKind::Transfer => "transfer",
+            TxKind::DepositStake => "deposit stake",
+            TxKind::WithdrawUnbondedStake => "withdraw unbonded stake",
+            TxKind::UnbondStake => "unbond stake",
+            TxKind::Unjail => "unjail",
+            TxKind::NodeJoin => "node join",
+            TxKind::NetworkParamsUpdate => "network parameters update",
+            TxKind::MLSHandshake => "MLS handshake",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Uncommitted state (consensus or mempool) a transaction is executed against
+pub struct TxContext<'a, T: EnclaveProxy> {
+    pub tx_validator: &'a mut T,
+    pub chain_info: &'a ChainInfo,
+    pub state: &'a mut ChainNodeState,
+    pub staking_store: StakingStore<'a>,
+    pub kv_store: KVStore<'a>,
+}
+
+/// Validation and execution of one or more transaction kinds
+pub trait TxHandler<T: EnclaveProxy>: Send + Sync {
+    /// Validates the transaction and applies it to the uncommitted state
+    fn process(&self, ctx: TxContext<'_, T>, txaux: &TxAux) -> Result<TxAction, TxError>;
+
+    /// Stores the data of a delivered transaction when the block is committed
+    fn commit(&self, kv_store: &mut KVStore<'_>, txid: &TxId, txaux: &TxAux);
+}
+
+struct Registration<T: EnclaveProxy> {
+    /// first app version (network parameter) accepting the transaction kind
+    since_app_version: u64,
+    handler: Box<dyn TxHandler<T>>,
+}
+
+/// Handlers of the transaction kinds known by the app
+pub struct TxRegistry<T: EnclaveProxy> {
+    registrations: BTreeMap<TxKind, Registration<T>>,
+}
+
+impl<T: EnclaveProxy> TxRegistry<T> {
+    /// Creates an empty registry, no transaction is accepted
+    pub fn empty() -> Self {
+        TxRegistry {
+            registrations: BTreeMap::new(),
+        }
+    }
+
+    /// Registers handler of a transaction kind, accepted from given app version
+    pub fn register(
+        &mut self,
+        kind: TxKind,
+        since_app_version: u64,
+        handler: impl TxHandler<T> + 'static,
+    ) {
+        self.registrations.insert(
+            kind,
+            Registration {
+                since_app_version,
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    /// Returns handler of a transaction kind if it's accepted by given app version
+    pub fn handler(&self, kind: TxKind, app_version: u64) -> Result<&dyn TxHandler<T>, TxError> {
+        match self.registrations.get(&kind) {
+            Some(registration) if registration.since_app_version <= app_version => {
+                Ok(registration.handler.as_ref())
+            }
+            _ => Err(TxError::UnsupportedTxKind(kind, app_version)),
+        }
+    }
+
+    /// Returns handler of a delivered transaction (already checked against the app version)
+    pub fn delivered_handler(&self, kind: TxKind) -> &dyn TxHandler<T> {
+        self.registrations
+            .get(&kind)
+            .expect("delivered transaction kind is registered")
+            .handler
+            .as_ref()
+    }
+}
+
+impl<T: EnclaveProxy> Default for TxRegistry<T> {
+    fn default() -> Self {
+        let mut registry = TxRegistry::empty();
+        registry.register(TxKind::Transfer, 0, EnclaveTxHandler);
+        registry.register(TxKind::DepositStake, 0, EnclaveTxHandler);
+        registry.register(TxKind::WithdrawUnbondedStake, 0, EnclaveTxHandler);
+        registry.register(TxKind::UnbondStake, 0, PublicTxHandler);
+        registry.register(TxKind::Unjail, 0, PublicTxHandler);
+        registry.register(TxKind::NodeJoin, 0, PublicTxHandler);
+        registry.register(
+            TxKind::NetworkParamsUpdate,
+            chain_core::NETWORK_PARAMS_UPDATE_APP_VERSION,
+            NetworkParamsUpdateTxHandler,
+        );
+        registry.register(TxKind::MLSHandshake, 0, MLSHandshakeHandler);
+        registry
+    }
+}
+
+/// FIXME/WIP payload for MLS handshake (not yet supported)
+struct MLSHandshakeHandler;
+
+impl<T: EnclaveProxy> TxHandler<T> for MLSHandshakeHandler {
+    fn process(&self, _ctx: TxContext<'_, T>, _txaux: &TxAux) -> Result<TxAction, TxError> {
+        Err(TxError::WIPMLSData)
+    }
+
+    fn commit(&self, _kv_store: &mut KVStore<'_>, _txid: &TxId, _txaux: &TxAux) {
+        // FIXME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enclave_bridge::mock::MockClient;
+    use chain_core::state::tendermint::BlockHeight;
+    use chain_core::tx::data::input::TxoPointer;
+    use chain_core::tx::TxObfuscated;
+
+    fn transfer_tx() -> TxAux {
+        TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+            inputs: vec![TxoPointer::new([0; 32], 0)],
+            no_of_outputs: 1,
+            payload: TxObfuscated {
+                txid: [0; 32],
+                key_from: BlockHeight::genesis(),
+                init_vector: [0; 12],
+                txpayload: vec![],
+            },
+        })
+    }
+
+    #[test]
+    fn check_registry_version_gating() {
+        let kind = TxKind::of(&transfer_tx());
+        assert_eq!(kind, TxKind::Transfer);
+
+        let registry = TxRegistry::<MockClient>::default();
+        assert!(registry.handler(kind, 0).is_ok());
+        let since = chain_core::NETWORK_PARAMS_UPDATE_APP_VERSION;
+        assert!(matches!(
+            registry.handler(TxKind::NetworkParamsUpdate, since - 1),
+            Err(TxError::UnsupportedTxKind(TxKind::NetworkParamsUpdate, _))
+        ));
+        assert!(registry
+            .handler(TxKind::NetworkParamsUpdate, chain_core::APP_VERSION)
+            .is_ok());
+
+        let mut registry = TxRegistry::<MockClient>::empty();
+        assert!(matches!(
+            registry.handler(kind, chain_core::APP_VERSION),
+            Err(TxError::UnsupportedTxKind(TxKind::Transfer, _))
+        ));
+        registry.register(kind, 3, EnclaveTxHandler);
+        assert!(matches!(
+            registry.handler(kind, 2),
+            Err(TxError::UnsupportedTxKind(TxKind::Transfer, 2))
+        ));
+        assert!(registry.handler(kind, 3).is_ok());
+    }
+}
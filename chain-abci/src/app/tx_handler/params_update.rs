@@ -0,0 +1,42 @@
+use parity_scale_codec::Encode;
+
+use super::{KVStore, TxContext, TxHandler};
+use crate::enclave_bridge::EnclaveProxy;
+use crate::storage::{process_network_params_update_tx, TxAction};
+use crate::tx_error::TxError;
+use chain_core::tx::data::TxId;
+use chain_core::tx::{TxAux, TxPublicAux};
+
+/// Network parameters updates signed by the council validators
+pub struct NetworkParamsUpdateTxHandler;
+
+impl<T: EnclaveProxy> TxHandler<T> for NetworkParamsUpdateTxHandler {
+    fn process(&self, ctx: TxContext<'_, T>, txaux: &TxAux) -> Result<TxAction, TxError> {
+        let TxContext {
+            chain_info, state, ..
+        } = ctx;
+        let (tx, witnesses) = match txaux {
+            TxAux::PublicTx(TxPublicAux::NetworkParamsUpdateTx(tx, witnesses)) => (tx, witnesses),
+            _ => unreachable!("network parameters update handler registered for other tx kinds"),
+        };
+        // the scheduled update is part of the network parameters (and so of the app hash),
+        // it's applied in the begin block of the effective height
+        let action = process_network_params_update_tx(
+            &mut state.top_level.network_params,
+            &state.staking_table,
+            chain_info,
+            tx,
+            witnesses,
+        )?;
+        Ok(TxAction::Public(action))
+    }
+
+    fn commit(&self, kv_store: &mut KVStore<'_>, txid: &TxId, txaux: &TxAux) {
+        let (tx, witnesses) = match txaux {
+            TxAux::PublicTx(TxPublicAux::NetworkParamsUpdateTx(tx, witnesses)) => (tx, witnesses),
+            _ => unreachable!("network parameters update handler registered for other tx kinds"),
+        };
+        chain_storage::store_tx_body(kv_store, txid, &tx.encode());
+        chain_storage::store_tx_witness(kv_store, txid, &witnesses.encode());
+    }
+}
@@ -0,0 +1,54 @@
+use parity_scale_codec::Encode;
+
+use super::{KVStore, TxContext, TxHandler};
+use crate::enclave_bridge::EnclaveProxy;
+use crate::storage::{process_public_tx, TxAction, TxPublicAction};
+use crate::tx_error::TxError;
+use chain_core::tx::data::TxId;
+use chain_core::tx::{TxAux, TxPublicAux};
+
+/// Transactions processed outside of the enclave: unbond, unjail and node join
+pub struct PublicTxHandler;
+
+impl<T: EnclaveProxy> TxHandler<T> for PublicTxHandler {
+    fn process(&self, ctx: TxContext<'_, T>, txaux: &TxAux) -> Result<TxAction, TxError> {
+        let TxContext {
+            chain_info,
+            state,
+            mut staking_store,
+            ..
+        } = ctx;
+        let tx = match txaux {
+            TxAux::PublicTx(tx) => tx,
+            _ => unreachable!("public tx handler registered for other transaction kinds"),
+        };
+        let action = process_public_tx(
+            &mut staking_store,
+            &mut state.staking_table,
+            state.enclave_isv_svn,
+            chain_info,
+            &tx,
+        )?;
+
+        if let TxPublicAction::NodeJoin { isv_svn, .. } = action {
+            state.enclave_isv_svn = isv_svn;
+        };
+
+        Ok(TxAction::Public(action))
+    }
+
+    fn commit(&self, kv_store: &mut KVStore<'_>, txid: &TxId, txaux: &TxAux) {
+        let (tx_body, witness) = match txaux {
+            // account should be already updated in deliver_tx
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, witness)) => (tx.encode(), witness),
+            // account should be already unjailed in deliver_tx
+            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, witness)) => (tx.encode(), witness),
+            // staked state updated in deliver_tx
+            // validator state updated in end_block
+            TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, witness)) => (tx.encode(), witness),
+            _ => unreachable!("public tx handler registered for other transaction kinds"),
+        };
+        chain_storage::store_tx_body(kv_store, txid, &tx_body);
+        chain_storage::store_tx_witness(kv_store, txid, &witness.encode());
+    }
+}
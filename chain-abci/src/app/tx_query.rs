@@ -0,0 +1,177 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Default interval between health checks of tx query endpoints
+pub const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Timeout of connecting to a tx query endpoint in a health check
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Health of a tx query endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointHealth {
+    /// address advertised to clients
+    pub address: String,
+    /// whether the endpoint accepted a connection in the last health check
+    pub healthy: bool,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    address: String,
+    healthy: AtomicBool,
+}
+
+/// Tx query enclave endpoints advertised to clients
+///
+/// Only the endpoints which passed the last health check are advertised, one after another,
+/// so that clients are spread across them. Endpoints are considered healthy until checked.
+#[derive(Debug)]
+pub struct TxQueryEndpoints {
+    endpoints: Vec<Endpoint>,
+    next: AtomicUsize,
+}
+
+impl TxQueryEndpoints {
+    /// Creates endpoints with given addresses (e.g. mydomain.com:4444)
+    pub fn new(addresses: Vec<String>) -> Self {
+        Self {
+            endpoints: addresses
+                .into_iter()
+                .map(|address| Endpoint {
+                    address,
+                    healthy: AtomicBool::new(true),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the next healthy endpoint to advertise (if any)
+    pub fn advertise(&self) -> Option<&str> {
+        let healthy = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| endpoint.healthy.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+        if healthy.is_empty() {
+            return None;
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % healthy.len();
+        Some(&healthy[index].address)
+    }
+
+    /// Returns health of all endpoints
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| EndpointHealth {
+                address: endpoint.address.clone(),
+                healthy: endpoint.healthy.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    /// Sets health of the endpoint with given address
+    pub fn set_healthy(&self, address: &str, healthy: bool) {
+        for endpoint in self.endpoints.iter().filter(|e| e.address == address) {
+            endpoint.healthy.store(healthy, Ordering::Relaxed);
+        }
+    }
+
+    /// Checks all endpoints by connecting to them
+    pub fn check_all(&self) {
+        for endpoint in self.endpoints.iter() {
+            let healthy = is_reachable(&endpoint.address);
+            let was_healthy = endpoint.healthy.swap(healthy, Ordering::Relaxed);
+            if was_healthy && !healthy {
+                log::warn!("tx query endpoint {} is down", endpoint.address);
+            } else if !was_healthy && healthy {
+                log::info!("tx query endpoint {} is up again", endpoint.address);
+            }
+        }
+    }
+}
+
+fn is_reachable(address: &str) -> bool {
+    match address.to_socket_addrs() {
+        Ok(addrs) => addrs
+            .into_iter()
+            .any(|addr| TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok()),
+        Err(err) => {
+            log::warn!("failed to resolve tx query endpoint {}: {}", address, err);
+            false
+        }
+    }
+}
+
+/// Spawns a thread checking health of the endpoints periodically
+pub fn spawn_health_checker(
+    endpoints: Arc<TxQueryEndpoints>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    thread::Builder::new()
+        .name("tx-query-health".to_owned())
+        .spawn(move || loop {
+            endpoints.check_all();
+            thread::sleep(interval);
+        })
+        .expect("failed to spawn tx query health checker")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn check_advertise_healthy_endpoints() {
+        let endpoints = TxQueryEndpoints::new(vec![
+            "a:4444".to_owned(),
+            "b:4444".to_owned(),
+            "c:4444".to_owned(),
+        ]);
+        let advertised = (0..3)
+            .map(|_| endpoints.advertise().unwrap().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["a:4444", "b:4444", "c:4444"], advertised);
+
+        endpoints.set_healthy("b:4444", false);
+        for _ in 0..4 {
+            assert_ne!(Some("b:4444"), endpoints.advertise());
+        }
+
+        endpoints.set_healthy("a:4444", false);
+        endpoints.set_healthy("c:4444", false);
+        assert_eq!(None, endpoints.advertise());
+    }
+
+    #[test]
+    fn check_health_check() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let live = listener.local_addr().unwrap().to_string();
+        let closed = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().to_string()
+        };
+        let endpoints = TxQueryEndpoints::new(vec![live.clone(), closed.clone()]);
+        endpoints.check_all();
+        assert_eq!(
+            vec![
+                EndpointHealth {
+                    address: live,
+                    healthy: true
+                },
+                EndpointHealth {
+                    address: closed,
+                    healthy: false
+                }
+            ],
+            endpoints.health()
+        );
+    }
+}
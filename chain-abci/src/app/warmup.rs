@@ -0,0 +1,175 @@
+//! Warm-up on startup
+//!
+//! Tendermint starts feeding blocks as soon as the handshake (Info request) is done, so a node
+//! with cold storage caches or an enclave which is still starting is slow on its first blocks and
+//! may time out. The node warms up before it answers the first Info request: it pre-loads the
+//! upper levels of the staking trie, verifies the last app hash against the stored data and
+//! reconnects the enclave. The progress is logged and reported by the health endpoint.
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use parity_scale_codec::Decode;
+use serde::Serialize;
+
+use super::app_init::ChainNodeState;
+use super::halt::ViolationKind;
+use super::ChainNodeApp;
+use crate::enclave_bridge::EnclaveProxy;
+use chain_core::common::MerkleTree;
+use chain_core::compute_app_hash;
+use chain_storage::jellyfish::{preload_trie, root_hash};
+use chain_storage::LookupItem;
+
+/// Levels of the staking trie pre-loaded (up to 16^n nodes on the last level)
+const WARMUP_TRIE_LEVELS: usize = 4;
+/// Attempts of connecting the enclave before giving up
+const ENCLAVE_CONNECT_ATTEMPTS: u32 = 10;
+/// Pause between attempts of connecting the enclave
+const ENCLAVE_CONNECT_RETRY: Duration = Duration::from_secs(3);
+
+/// Phase of the warm-up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupPhase {
+    /// not started yet (waiting for the first Info request)
+    Pending,
+    /// pre-loading the upper levels of the staking trie
+    LoadingTrie,
+    /// verifying the last app hash against the stored data
+    VerifyingAppHash,
+    /// connecting the enclave
+    ConnectingEnclave,
+    /// ready to process blocks
+    Ready,
+}
+
+impl Default for WarmupPhase {
+    fn default() -> Self {
+        WarmupPhase::Pending
+    }
+}
+
+impl fmt::Display for WarmupPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarmupPhase::Pending => write!(f, "pending"),
+            WarmupPhase::LoadingTrie => write!(f, "loading trie"),
+            WarmupPhase::VerifyingAppHash => write!(f, "verifying app hash"),
+            WarmupPhase::ConnectingEnclave => write!(f, "connecting enclave"),
+            WarmupPhase::Ready => write!(f, "ready"),
+        }
+    }
+}
+
+/// Readiness of the node, shared with the health endpoint
+#[derive(Debug, Default)]
+pub struct Readiness {
+    phase: Mutex<WarmupPhase>,
+}
+
+impl Readiness {
+    /// Current phase of the warm-up
+    pub fn phase(&self) -> WarmupPhase {
+        *self.phase.lock().expect("lock warm-up phase")
+    }
+
+    /// Whether the warm-up completed
+    pub fn is_ready(&self) -> bool {
+        self.phase() == WarmupPhase::Ready
+    }
+
+    fn set_phase(&self, phase: WarmupPhase) {
+        info!("warm-up: {}", phase);
+        *self.phase.lock().expect("lock warm-up phase") = phase;
+    }
+}
+
+impl<T: EnclaveProxy + 'static> ChainNodeApp<T> {
+    /// Sets the readiness reported by the health endpoint
+    pub fn set_readiness(&mut self, readiness: Arc<Readiness>) {
+        self.readiness = readiness;
+    }
+
+    /// Warms up the node, if it isn't ready yet: called before the first Info response, so that
+    /// Tendermint doesn't send blocks before
+    pub fn warm_up(&mut self) {
+        if self.readiness.is_ready() {
+            return;
+        }
+        let started = Instant::now();
+        self.check_halted();
+        if let Some(raw) = chain_storage::get_last_app_state(&self.storage) {
+            let state =
+                ChainNodeState::decode(&mut raw.as_slice()).expect("decode chain node state");
+            self.readiness.set_phase(WarmupPhase::LoadingTrie);
+            match preload_trie(&self.storage, state.staking_version, WARMUP_TRIE_LEVELS) {
+                Ok(loaded) => info!("warm-up: loaded {} staking trie nodes", loaded),
+                // a corrupted trie is detected by the app hash verification
+                Err(e) => error!("warm-up: failed to load staking trie: {}", e),
+            }
+
+            self.readiness.set_phase(WarmupPhase::VerifyingAppHash);
+            if let Err(e) = self.verify_app_hash(&state) {
+                self.halt(
+                    ViolationKind::TrieRootMismatch,
+                    format!(
+                        "last app state doesn't match the stored data at height {}: {}",
+                        state.last_block_height, e
+                    ),
+                );
+            }
+        }
+
+        self.readiness.set_phase(WarmupPhase::ConnectingEnclave);
+        let mut attempt = 1;
+        while self.tx_validator.check_chain(self.chain_hex_id).is_err() {
+            if attempt == ENCLAVE_CONNECT_ATTEMPTS {
+                panic!("enclave sanity check failed (either a binary for a different network is used or there is a problem with enclave process)");
+            }
+            warn!(
+                "warm-up: enclave not ready (attempt {}/{}), retrying in {:?}",
+                attempt, ENCLAVE_CONNECT_ATTEMPTS, ENCLAVE_CONNECT_RETRY
+            );
+            thread::sleep(ENCLAVE_CONNECT_RETRY);
+            attempt += 1;
+        }
+
+        self.readiness.set_phase(WarmupPhase::Ready);
+        info!("warm-up completed in {:?}", started.elapsed());
+    }
+
+    /// Verifies the app hash of the chain state against the stored staking trie root and
+    /// transaction merkle tree
+    pub(crate) fn verify_app_hash(&self, state: &ChainNodeState) -> Result<(), String> {
+        let top_level = &state.top_level;
+        let staking_root = root_hash(&self.storage, state.staking_version)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "staking trie not stored".to_owned())?;
+        if staking_root != top_level.account_root {
+            return Err("staking trie root doesn't match the chain state".to_owned());
+        }
+        let tree = match self
+            .storage
+            .lookup_item(LookupItem::TxsMerkle, &state.last_apphash)
+        {
+            Some(data) => MerkleTree::decode(&mut data.as_slice())
+                .map_err(|e| format!("invalid transaction merkle tree: {}", e.what()))?,
+            // no block committed yet
+            None if state.last_block_height.value() == 0 => MerkleTree::empty(),
+            None => return Err("transaction merkle tree not stored".to_owned()),
+        };
+        let app_hash = compute_app_hash(
+            &tree,
+            &top_level.account_root,
+            &top_level.rewards_pool,
+            &top_level.network_params,
+        );
+        if app_hash != state.last_apphash {
+            return Err("app hash doesn't match the stored state".to_owned());
+        }
+        Ok(())
+    }
+}
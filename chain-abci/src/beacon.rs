@@ -0,0 +1,102 @@
+//! Deterministic pseudo-randomness derived from block data
+//!
+//! Consensus code must not depend on anything that differs across nodes (system randomness,
+//! iteration order of hash maps, ...). Where ties need to be broken in a fair way, a
+//! `RandomnessBeacon` seeded with data all nodes agree on is used instead.
+//!
+//! The seed is `blake3("chain-beacon" || last app hash || block height)`, the n-th output is the
+//! first 8 bytes (little endian) of `blake3(seed || n)`. The block proposer can influence the
+//! app hash only by choosing the transactions of the previous block, so the beacon must not be
+//! used where that influence matters.
+use chain_core::common::H256;
+use chain_core::state::tendermint::BlockHeight;
+
+const BEACON_DOMAIN: &[u8] = b"chain-beacon";
+
+/// Deterministic pseudo-random number generator seeded with block data
+#[derive(Debug, Clone)]
+pub struct RandomnessBeacon {
+    seed: H256,
+    counter: u64,
+}
+
+impl RandomnessBeacon {
+    /// Creates a beacon for the block at `block_height` whose previous block resulted in
+    /// `last_app_hash`
+    pub fn new(last_app_hash: &H256, block_height: BlockHeight) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(BEACON_DOMAIN);
+        hasher.update(last_app_hash);
+        hasher.update(&block_height.value().to_le_bytes());
+        Self {
+            seed: hasher.finalize().into(),
+            counter: 0,
+        }
+    }
+
+    /// Returns next pseudo-random number
+    pub fn next_u64(&mut self) -> u64 {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.seed);
+        hasher.update(&self.counter.to_le_bytes());
+        self.counter += 1;
+
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hasher.finalize().as_bytes()[..8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Returns uniformly distributed pseudo-random number in `0..bound`
+    ///
+    /// # Panics
+    ///
+    /// If `bound` is zero
+    pub fn gen_range(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "empty range");
+        // reject the values of the incomplete last segment to avoid modulo bias
+        let zone = u64::max_value() - (u64::max_value() - bound + 1) % bound;
+        loop {
+            let value = self.next_u64();
+            if value <= zone {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Shuffles `items` in place (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.gen_range(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_beacon_is_deterministic() {
+        let app_hash = [1u8; 32];
+        let mut beacon1 = RandomnessBeacon::new(&app_hash, BlockHeight::new(10));
+        let mut beacon2 = RandomnessBeacon::new(&app_hash, BlockHeight::new(10));
+        let mut beacon3 = RandomnessBeacon::new(&app_hash, BlockHeight::new(11));
+        let values1 = (0..10).map(|_| beacon1.next_u64()).collect::<Vec<_>>();
+        let values2 = (0..10).map(|_| beacon2.next_u64()).collect::<Vec<_>>();
+        let values3 = (0..10).map(|_| beacon3.next_u64()).collect::<Vec<_>>();
+        assert_eq!(values1, values2);
+        assert_ne!(values1, values3);
+
+        for bound in 1..20 {
+            assert!(beacon1.gen_range(bound) < bound);
+        }
+        assert_eq!(0, beacon1.gen_range(1));
+
+        let mut items = (0..20).collect::<Vec<u32>>();
+        beacon1.shuffle(&mut items);
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!((0..20).collect::<Vec<u32>>(), sorted);
+    }
+}
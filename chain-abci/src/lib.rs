@@ -1,4 +1,5 @@
 pub mod app;
+pub mod beacon;
 pub mod enclave_bridge;
 pub mod liveness;
 pub mod staking;
@@ -1,3 +1,11 @@
+use chain_abci::app::commit_hook::commit_hook_from_name;
+use chain_abci::app::halt::CircuitBreaker;
+use chain_abci::app::metrics::{spawn_metrics_server, AppMetrics};
+use chain_abci::app::snapshot::SnapshotManager;
+use chain_abci::app::tx_query::{
+    spawn_health_checker, TxQueryEndpoints, DEFAULT_HEALTH_CHECK_INTERVAL,
+};
+use chain_abci::app::warmup::Readiness;
 use chain_abci::app::{sanity_check_enabled, ChainNodeApp};
 #[cfg(all(not(feature = "mock-enclave"), feature = "edp", target_os = "linux"))]
 use chain_abci::enclave_bridge::edp::{
@@ -8,8 +16,10 @@ use chain_abci::enclave_bridge::edp::{
 use chain_abci::enclave_bridge::mock::MockClient;
 use chain_abci::enclave_bridge::{EnclaveProxy, TdbeConfig};
 use chain_core::init::network::{get_network, get_network_id, init_chain_id};
+use chain_storage::scrub::{CorruptEntry, CorruptionAlert, ScrubConfig};
+use chain_storage::snapshot::{SnapshotStore, DEFAULT_CHUNK_SIZE};
 use chain_storage::ReadOnlyStorage;
-use chain_storage::{Storage, StorageConfig, StorageType};
+use chain_storage::{CompactionStyle, DbOptions, Storage, StorageConfig, StorageType};
 use kvdb::KeyValueDB;
 use log::{error, info, warn};
 use ra_sp_server::config::SpRaConfig;
@@ -21,7 +31,9 @@ use std::net::SocketAddr;
 #[cfg(all(not(feature = "mock-enclave"), feature = "edp", target_os = "linux"))]
 use std::os::unix::net::UnixStream;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::Arc;
+use std::time::Duration;
 use structopt::StructOpt;
 
 /// TODO: should this also set the tx-query enclave file path
@@ -35,9 +47,147 @@ pub struct Config {
     tx_query: Option<String>,
     // if different from `tx_query`
     tx_query_listen: Option<String>,
+    /// tx query enclaves on other hosts advertised to clients (besides `tx_query`) while they
+    /// pass health checks
+    #[serde(default)]
+    tx_query_endpoints: Vec<String>,
     launch_ra_proxy: bool,
     remote_attestation: SpRaConfig,
     data_bootstrap: TdbeConfig,
+    /// names of plugins invoked after each commit (e.g. "stdout-json")
+    #[serde(default)]
+    commit_hooks: Vec<String>,
+    /// verify the merkle trie columns in background (disabled if not set)
+    #[serde(default)]
+    trie_scrub: Option<TrieScrubConfig>,
+    /// address of the HTTP endpoint serving Prometheus metrics on `/metrics` (disabled if not
+    /// set)
+    #[serde(default)]
+    metrics_addr: Option<String>,
+    /// export state sync snapshots into `snapshots` in the data directory (disabled if not set)
+    #[serde(default)]
+    snapshots: Option<SnapshotConfig>,
+    /// number of the latest staking trie versions kept, nodes only referenced by older versions
+    /// are deleted after each commit (pruning disabled if not set)
+    #[serde(default)]
+    prune: Option<u64>,
+    /// maximum size in bytes of transactions accepted by CheckTx (not limited if not set)
+    #[serde(default)]
+    max_mempool_tx_size: Option<usize>,
+    /// index committed transactions by the staked state addresses they affect (served on the
+    /// "txs_by_address" query path)
+    #[serde(default)]
+    address_index: bool,
+    /// upgrades of the state machine rules (app version) at block heights,
+    /// all the nodes of the network need the same schedule
+    #[serde(default)]
+    app_version_upgrades: Vec<AppVersionUpgrade>,
+    /// RocksDB tuning (kvdb-rocksdb defaults if not set)
+    #[serde(default)]
+    rocksdb: Option<RocksDbConfig>,
+}
+
+/// Upgrade of the state machine rules from a block height
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AppVersionUpgrade {
+    /// first block height executed with the new rules
+    height: u64,
+    /// the new app version
+    app_version: u64,
+}
+
+/// Schedule of the background trie scrubber
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrieScrubConfig {
+    /// entries verified before each pause
+    batch_size: usize,
+    batch_pause_ms: u64,
+    pass_interval_secs: u64,
+    /// command run with the column, hex-encoded key and description of each corrupt entry
+    alert_command: Option<String>,
+}
+
+impl TrieScrubConfig {
+    fn scrub_config(&self) -> ScrubConfig {
+        ScrubConfig {
+            batch_size: self.batch_size,
+            batch_pause: Duration::from_millis(self.batch_pause_ms),
+            pass_interval: Duration::from_secs(self.pass_interval_secs),
+        }
+    }
+
+    fn alert(&self) -> CorruptionAlert {
+        let alert_command = self.alert_command.clone();
+        Box::new(move |entry: &CorruptEntry| {
+            error!("corrupt trie storage entry: {}", entry);
+            if let Some(command) = alert_command.as_ref() {
+                let status = Command::new(command)
+                    .arg(entry.column.to_string())
+                    .arg(hex::encode(&entry.key))
+                    .arg(entry.corruption.to_string())
+                    .status();
+                if let Err(err) = status {
+                    error!("failed to run trie scrub alert command: {}", err);
+                }
+            }
+        })
+    }
+}
+
+/// Schedule of the state sync snapshots
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SnapshotConfig {
+    /// blocks between snapshots
+    interval: u64,
+    /// number of the latest snapshots kept
+    keep_recent: usize,
+    /// approximate size of the chunks in bytes
+    #[serde(default = "default_snapshot_chunk_size")]
+    chunk_size: usize,
+}
+
+fn default_snapshot_chunk_size() -> usize {
+    DEFAULT_CHUNK_SIZE
+}
+
+/// RocksDB tuning of the node storage, unset options keep the kvdb-rocksdb defaults
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RocksDbConfig {
+    /// memory budget (in MiB) of each column's block cache and memtables
+    #[serde(default)]
+    column_memory_budget_mb: Option<usize>,
+    /// maximum number of open files (-1 for unlimited)
+    #[serde(default)]
+    max_open_files: Option<i32>,
+    /// "auto" (detected from the drive), "ssd" or "hdd"
+    #[serde(default)]
+    compaction: Option<CompactionConfig>,
+    /// number of RocksDB info log files kept
+    #[serde(default)]
+    keep_log_file_num: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum CompactionConfig {
+    Auto,
+    Ssd,
+    Hdd,
+}
+
+impl RocksDbConfig {
+    fn db_options(&self) -> DbOptions {
+        DbOptions {
+            column_memory_budget: self.column_memory_budget_mb,
+            max_open_files: self.max_open_files,
+            compaction: self.compaction.map(|compaction| match compaction {
+                CompactionConfig::Auto => CompactionStyle::Auto,
+                CompactionConfig::Ssd => CompactionStyle::Ssd,
+                CompactionConfig::Hdd => CompactionStyle::Hdd,
+            }),
+            keep_log_file_num: self.keep_log_file_num,
+        }
+    }
 }
 
 impl Default for Config {
@@ -50,6 +200,7 @@ impl Default for Config {
             chain_id: "testnet-thaler-crypto-com-chain-42".into(),
             tx_query: None,
             tx_query_listen: None,
+            tx_query_endpoints: Vec::new(),
             // in multi-node integration tests, the proxy is shared among nodes
             launch_ra_proxy: false,
             remote_attestation: SpRaConfig {
@@ -68,6 +219,15 @@ impl Default for Config {
                 ias_report_path: "/attestation/v4/report".into(),
             },
             data_bootstrap: TdbeConfig::default(),
+            commit_hooks: Vec::new(),
+            trie_scrub: None,
+            metrics_addr: None,
+            snapshots: None,
+            prune: None,
+            max_mempool_tx_size: None,
+            address_index: false,
+            app_version_upgrades: vec![],
+            rocksdb: None,
         }
     }
 }
@@ -98,6 +258,12 @@ impl Config {
         if opt.tx_query.is_some() {
             self.tx_query = opt.tx_query.clone();
         }
+        if opt.metrics_addr.is_some() {
+            self.metrics_addr = opt.metrics_addr.clone();
+        }
+        if opt.prune.is_some() {
+            self.prune = opt.prune;
+        }
     }
     pub fn is_valid(&self) -> bool {
         let mut valid = true;
@@ -109,6 +275,61 @@ impl Config {
             error!("chain_id should be set");
             valid = false
         }
+        if !self.tx_query_endpoints.is_empty() && self.tx_query.is_none() {
+            error!("tx_query should be set when tx_query_endpoints are set");
+            valid = false
+        }
+        if let Some(addr) = self.metrics_addr.as_ref() {
+            if addr.parse::<SocketAddr>().is_err() {
+                error!("invalid metrics address: {}", addr);
+                valid = false
+            }
+        }
+        if let Some(snapshots) = self.snapshots.as_ref() {
+            if snapshots.interval == 0 || snapshots.keep_recent == 0 || snapshots.chunk_size == 0 {
+                error!("snapshot interval, keep_recent and chunk_size should be positive");
+                valid = false
+            }
+        }
+        if self.prune == Some(0) {
+            error!("prune should keep at least one version");
+            valid = false
+        }
+        if self.max_mempool_tx_size == Some(0) {
+            error!("max_mempool_tx_size should be positive");
+            valid = false
+        }
+        if let Some(rocksdb) = self.rocksdb.as_ref() {
+            if rocksdb.column_memory_budget_mb == Some(0) {
+                error!("rocksdb column_memory_budget_mb should be positive");
+                valid = false
+            }
+            if let Some(max_open_files) = rocksdb.max_open_files {
+                if max_open_files == 0 || max_open_files < -1 {
+                    error!("rocksdb max_open_files should be positive or -1 (unlimited)");
+                    valid = false
+                }
+            }
+            if rocksdb.keep_log_file_num.map_or(false, |num| num <= 0) {
+                error!("rocksdb keep_log_file_num should be positive");
+                valid = false
+            }
+        }
+        for name in self.commit_hooks.iter() {
+            if commit_hook_from_name(name).is_none() {
+                error!("unknown commit hook: {}", name);
+                valid = false
+            }
+        }
+        for upgrade in self.app_version_upgrades.iter() {
+            if upgrade.height == 0 || upgrade.app_version > chain_core::APP_VERSION {
+                error!(
+                    "app version upgrade should be at a positive height and to a version up to {}",
+                    chain_core::APP_VERSION
+                );
+                valid = false
+            }
+        }
         valid
     }
 }
@@ -176,6 +397,21 @@ pub struct AbciOpt {
         help = "Optional transaction query support for clients (tx query enclave listening address, e.g. mydomain.com:4444)"
     )]
     tx_query: Option<String>,
+    #[structopt(
+        long = "metrics-addr",
+        help = "Optional address serving Prometheus metrics on /metrics (e.g. 127.0.0.1:26660)"
+    )]
+    metrics_addr: Option<String>,
+    #[structopt(
+        long = "prune",
+        help = "Optional number of the latest staking trie versions kept, older versions are pruned"
+    )]
+    prune: Option<u64>,
+    #[structopt(
+        long = "override-halt",
+        help = "Resumes block processing halted on an invariant violation and only logs further violations (for recovery procedures)"
+    )]
+    override_halt: bool,
 }
 
 /// edp
@@ -297,9 +533,45 @@ fn main() {
 
             let host = config.host.parse().expect("invalid host");
             let addr = SocketAddr::new(host, config.port);
-            let storage = Storage::new(&StorageConfig::new(&opt.data, StorageType::Node));
+            // served before the node is started, so that the health endpoint reports the
+            // warm-up
+            let readiness = Arc::new(Readiness::default());
+            let circuit_breaker = Arc::new(
+                CircuitBreaker::new(&Path::new(&opt.data).join("halt"), opt.override_halt)
+                    .expect("load halt status"),
+            );
+            if let Some(record) = circuit_breaker.halted() {
+                error!("block processing was halted on {}", record);
+            }
+            let metrics = config.metrics_addr.as_ref().map(|addr| {
+                let addr = addr.parse().expect("metrics address checked in config");
+                let metrics = Arc::new(AppMetrics::default());
+                spawn_metrics_server(
+                    addr,
+                    metrics.clone(),
+                    readiness.clone(),
+                    circuit_breaker.clone(),
+                )
+                .expect("start metrics server");
+                info!("serving metrics on http://{}/metrics", addr);
+                metrics
+            });
+            let db_options = config
+                .rocksdb
+                .as_ref()
+                .map(RocksDbConfig::db_options)
+                .unwrap_or_default();
+            let storage = Storage::new(
+                &StorageConfig::new(&opt.data, StorageType::Node).with_db_options(db_options),
+            );
 
             let tx_validator = get_enclave_proxy(&config, storage.temp_hack_for_tdbe());
+            let trie_scrubber = config.trie_scrub.as_ref().map(|scrub| {
+                info!("trie scrubber enabled");
+                storage
+                    .get_read_only()
+                    .spawn_trie_scrubber(scrub.scrub_config(), scrub.alert())
+            });
             if sanity_check_enabled() {
                 warn!("Enabled sanity checks");
             }
@@ -309,18 +581,90 @@ fn main() {
                 tx_validator.get_comm_only(),
                 storage.get_read_only(),
             );
+            let tx_query_endpoints = config.tx_query.as_ref().map(|address| {
+                let mut addresses = vec![address.clone()];
+                addresses.extend(config.tx_query_endpoints.iter().cloned());
+                Arc::new(TxQueryEndpoints::new(addresses))
+            });
             info!("starting up");
-            abci::run(
-                addr,
-                ChainNodeApp::new_with_storage(
-                    tx_validator,
-                    &config.genesis_app_hash,
-                    &config.chain_id,
-                    storage,
-                    config.tx_query,
-                    config.data_bootstrap.external_listen_address,
-                ),
+            let mut app = ChainNodeApp::new_with_storage(
+                tx_validator,
+                &config.genesis_app_hash,
+                &config.chain_id,
+                storage,
+                config.tx_query,
+                config.data_bootstrap.external_listen_address,
             );
+            if let Some(endpoints) = tx_query_endpoints {
+                spawn_health_checker(endpoints.clone(), DEFAULT_HEALTH_CHECK_INTERVAL);
+                app.set_tx_query_endpoints(endpoints);
+            }
+            if let Some(scrubber) = trie_scrubber.as_ref() {
+                app.set_scrub_metrics(scrubber.metrics());
+            }
+            app.set_readiness(readiness);
+            app.set_circuit_breaker(circuit_breaker);
+            if let Some(metrics) = metrics {
+                app.set_metrics(metrics);
+            }
+            if let Some(snapshots) = config.snapshots.as_ref() {
+                let dir = Path::new(&opt.data).join("snapshots");
+                let store = SnapshotStore::new(&dir).expect("create snapshot directory");
+                info!(
+                    "exporting snapshots every {} blocks into {}",
+                    snapshots.interval,
+                    dir.display()
+                );
+                app.set_snapshots(SnapshotManager {
+                    store,
+                    interval: snapshots.interval,
+                    keep_recent: snapshots.keep_recent,
+                    chunk_size: snapshots.chunk_size,
+                });
+            }
+            if let Some(keep_recent) = config.prune {
+                info!(
+                    "pruning staking trie versions older than the latest {}",
+                    keep_recent
+                );
+                app.set_pruning(keep_recent);
+            }
+            if let Some(max_size) = config.max_mempool_tx_size {
+                app.set_max_mempool_tx_size(max_size);
+            }
+            if config.address_index {
+                info!("indexing transactions by staked state address");
+                app.enable_address_index();
+            }
+            for upgrade in config.app_version_upgrades.iter() {
+                info!(
+                    "app version upgrade to {} scheduled at height {}",
+                    upgrade.app_version, upgrade.height
+                );
+                app.schedule_app_version_upgrade(upgrade.height.into(), upgrade.app_version);
+            }
+            for name in config.commit_hooks.iter() {
+                let hook = commit_hook_from_name(name).expect("commit hook checked in config");
+                info!("commit hook enabled: {}", name);
+                app.register_commit_hook(hook);
+            }
+            let db_path = StorageConfig::new(&opt.data, StorageType::Node).db_path();
+            let shutdown = app.shutdown_handle(Some(PathBuf::from(db_path)));
+            ctrlc::set_handler(move || {
+                info!("shutting down after the commit in progress");
+                match shutdown.wait_and_sync() {
+                    Ok(_committing) => {
+                        info!("storage synced, exiting");
+                        std::process::exit(0);
+                    }
+                    Err(e) => {
+                        error!("failed to sync storage: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            })
+            .expect("failed to set the shutdown signal handler");
+            abci::run(addr, app);
         }
     }
 }
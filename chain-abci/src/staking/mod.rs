@@ -1,21 +1,27 @@
 mod table;
 mod tx;
 
-pub use table::{RewardsDistribution, StakingTable};
+pub use table::{LegacyStakingTable, RewardsDistribution, StakingTable};
 
 #[cfg(test)]
 mod tests {
+    use quickcheck::quickcheck;
     use secp256k1::key::{PublicKey, SecretKey};
+    use std::collections::BTreeMap;
     use std::str::FromStr;
 
     use chain_core::init::address::RedeemAddress;
-    use chain_core::init::coin::Coin;
+    use chain_core::init::coin::{sum_coins, Coin};
     use chain_core::init::config::SlashRatio;
-    use chain_core::init::params::NetworkParameters;
+    use chain_core::init::params::{
+        NetworkParameters, PunishmentParameters, ScheduledParametersUpdate,
+    };
     use chain_core::state::account::{
         NodeState, PunishmentKind, StakedState, StakedStateAddress, UnbondTx, UnjailTx, Validator,
     };
-    use chain_core::state::tendermint::{BlockHeight, TendermintValidatorPubKey};
+    use chain_core::state::tendermint::{
+        BlockHeight, TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
+    };
     use chain_core::state::validator::NodeJoinRequestTx;
     use chain_core::tx::fee::Fee;
     use chain_storage::buffer::{Get, GetStaking, MemStore, StoreStaking};
@@ -26,6 +32,7 @@ mod tests {
 
     use super::*;
     use crate::app::BeginBlockInfo;
+    use crate::beacon::RandomnessBeacon;
     use crate::staking::table::{PunishmentOutcome, SlashedCoin};
     use crate::tx_error::{
         DepositError, NodeJoinError, PublicTxError, UnbondError, UnjailError, WithdrawError,
@@ -188,6 +195,7 @@ mod tests {
         let params = NetworkParameters::Genesis(init_params);
         let info = BeginBlockInfo {
             params: &params,
+            app_version: chain_core::APP_VERSION,
             max_evidence_age: 10,
             block_time: DEFAULT_GENESIS_TIME,
             block_height: 0.into(),
@@ -297,6 +305,109 @@ mod tests {
         assert!(!staking.is_jailed());
     }
 
+    #[test]
+    fn check_evidence_dedup() {
+        let init_params = get_init_network_params(Coin::zero());
+        let mut params = NetworkParameters::Genesis(init_params.clone());
+        params.schedule_update(ScheduledParametersUpdate {
+            effective_height: 1.into(),
+            punishment_params: PunishmentParameters {
+                jailing_config: init_params.jailing_config,
+                slashing_config: init_params.slashing_config,
+                evidence_dedup_window: 20,
+            },
+        });
+        assert!(params.apply_scheduled_updates(1.into()));
+        let info = BeginBlockInfo {
+            params: &params,
+            app_version: chain_core::APP_VERSION,
+            max_evidence_age: 10,
+            block_time: DEFAULT_GENESIS_TIME,
+            block_height: 1.into(),
+            voters: &[],
+            evidences: &[],
+        };
+
+        let (mut table, mut store) = init_staking_table();
+        let addr1 = staking_address(&[0xcc; 32]);
+        let val_pk1 = validator_pubkey(&[0xcc; 32]);
+        let evidence = (val_pk1.into(), 1.into(), DEFAULT_GENESIS_TIME);
+
+        // the same evidence included twice in one block
+        let punishment_outcomes = table.begin_block(
+            &mut store,
+            &BeginBlockInfo {
+                evidences: &[evidence.clone(), evidence.clone()],
+                ..info
+            },
+        );
+        assert_eq!(punishment_outcomes.len(), 1);
+
+        let tx = UnjailTx {
+            nonce: store.get(&addr1).unwrap().nonce,
+            address: addr1,
+            attributes: Default::default(),
+        };
+        table
+            .unjail(&mut store, DEFAULT_GENESIS_TIME + 10, &tx)
+            .unwrap();
+
+        // replayed in a later block after unjailed, inside the dedup window
+        let punishment_outcomes = table.begin_block(
+            &mut store,
+            &BeginBlockInfo {
+                block_time: DEFAULT_GENESIS_TIME + 11,
+                block_height: 2.into(),
+                max_evidence_age: 100,
+                evidences: &[evidence.clone()],
+                ..info
+            },
+        );
+        assert_eq!(punishment_outcomes, vec![]);
+        assert!(!store.get(&addr1).unwrap().is_jailed());
+
+        // forgotten after the dedup window
+        let punishment_outcomes = table.begin_block(
+            &mut store,
+            &BeginBlockInfo {
+                block_time: DEFAULT_GENESIS_TIME + 20,
+                block_height: 3.into(),
+                max_evidence_age: 100,
+                evidences: &[evidence.clone()],
+                ..info
+            },
+        );
+        assert_eq!(punishment_outcomes.len(), 1);
+
+        // replayed with the rules before the dedup, the evidence is punished again
+        let (mut table, mut store) = init_staking_table();
+        let evidences = [evidence];
+        let legacy_info = BeginBlockInfo {
+            app_version: chain_core::EVIDENCE_DEDUP_APP_VERSION - 1,
+            evidences: &evidences,
+            ..info
+        };
+        assert_eq!(table.begin_block(&mut store, &legacy_info).len(), 1);
+        let tx = UnjailTx {
+            nonce: store.get(&addr1).unwrap().nonce,
+            address: addr1,
+            attributes: Default::default(),
+        };
+        table
+            .unjail(&mut store, DEFAULT_GENESIS_TIME + 10, &tx)
+            .unwrap();
+        let punishment_outcomes = table.begin_block(
+            &mut store,
+            &BeginBlockInfo {
+                block_time: DEFAULT_GENESIS_TIME + 11,
+                block_height: 2.into(),
+                max_evidence_age: 100,
+                ..legacy_info
+            },
+        );
+        assert_eq!(punishment_outcomes.len(), 1);
+    }
+
     fn unbond_deposit_rejoin(
         table: &mut StakingTable,
         store: &mut impl StoreStaking,
@@ -413,6 +524,7 @@ mod tests {
         let params = NetworkParameters::Genesis(init_params);
         let info = BeginBlockInfo {
             params: &params,
+            app_version: chain_core::APP_VERSION,
             max_evidence_age: 61,
             block_time: 0,
             block_height: 0.into(),
@@ -476,6 +588,7 @@ mod tests {
         let params = NetworkParameters::Genesis(init_params);
         let info = BeginBlockInfo {
             params: &params,
+            app_version: chain_core::APP_VERSION,
             max_evidence_age: 61,
             block_time: DEFAULT_GENESIS_TIME,
             block_height: 0.into(),
@@ -695,6 +808,7 @@ mod tests {
         let params = NetworkParameters::Genesis(init_params);
         let info = BeginBlockInfo {
             params: &params,
+            app_version: chain_core::APP_VERSION,
             block_time: DEFAULT_GENESIS_TIME,
             block_height: 0.into(),
             max_evidence_age: 10,
@@ -839,4 +953,171 @@ mod tests {
         );
         assert!(staking.is_jailed());
     }
+
+    #[test]
+    fn check_reward_remainder_distribution() {
+        let seeds = [[0xcc; 32], [0xcd; 32], [0xce; 32]];
+        let distribute = |block_height: Option<u64>| {
+            let (mut table, mut store) = init_staking_table();
+            for seed in seeds.iter() {
+                assert!(table.reward_record(
+                    &store,
+                    &TendermintValidatorAddress::from(&validator_pubkey(seed)),
+                    TendermintVotePower::from(Coin::one()),
+                ));
+            }
+            let mut beacon = block_height.map(|block_height| {
+                RandomnessBeacon::new(&[0; 32], BlockHeight::new(block_height))
+            });
+            table.reward_distribute(&mut store, Coin::new(10).unwrap(), beacon.as_mut())
+        };
+
+        // without beacon (before `REWARD_REMAINDER_APP_VERSION`) the left over unit is kept
+        let (remainder, distribution) = distribute(None);
+        assert_eq!(remainder, Coin::unit());
+        assert!(distribution
+            .iter()
+            .all(|(_, amount)| *amount == Coin::new(3).unwrap()));
+
+        // 10 base units for 3 participators, the left over unit goes to one of them
+        let (remainder, distribution) = distribute(Some(1));
+        assert_eq!(remainder, Coin::zero());
+        assert_eq!(
+            sum_coins(distribution.iter().map(|(_, amount)| *amount)).unwrap(),
+            Coin::new(10).unwrap()
+        );
+        assert_eq!(
+            distribution
+                .iter()
+                .filter(|(_, amount)| *amount == Coin::new(4).unwrap())
+                .count(),
+            1
+        );
+        assert_eq!((remainder, distribution), distribute(Some(1)));
+    }
+
+    /// Validator set recomputed from scratch:
+    /// active council nodes ordered by bonded desc then staking address.
+    fn expected_validators(
+        store: &StakingMemStore,
+        addrs: &[StakedStateAddress],
+        max_validators: usize,
+    ) -> BTreeMap<TendermintValidatorPubKey, TendermintVotePower> {
+        let mut candidates = addrs
+            .iter()
+            .filter_map(|addr| store.get(addr))
+            .filter_map(|staking| match &staking.node_meta {
+                Some(NodeState::CouncilNode(val)) if val.is_active() => Some((
+                    staking.bonded,
+                    staking.address,
+                    val.council_node.consensus_pubkey.clone(),
+                )),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        candidates
+            .into_iter()
+            .take(max_validators)
+            .map(|(bonded, _, pubkey)| (pubkey, bonded.into()))
+            .collect()
+    }
+
+    /// Replay random deposit/unbond/join/evidence events, apply the end block updates
+    /// to a simulated tendermint validator set, and check it against the from-scratch one.
+    /// (Validators keep their consensus key, key rotation is covered by `check_used_validator_key`)
+    fn check_validator_updates(events: Vec<(u8, u8, u8)>) -> bool {
+        let max_validators = 3;
+        let (mut table, mut store) = init_staking_table();
+        let mut init_params = get_init_network_params(Coin::zero());
+        init_params.slashing_config.byzantine_slash_percent = "0.1".parse().unwrap();
+        let params = NetworkParameters::Genesis(init_params);
+        let seeds = [[0xcc; 32], [0xcd; 32], [0xce; 32], [0xcf; 32]];
+        let addrs = seeds.iter().map(staking_address).collect::<Vec<_>>();
+
+        let mut tendermint = expected_validators(&store, &addrs, max_validators);
+        let mut block_height: u64 = 1;
+        let mut evidences = vec![];
+        for (kind, index, amount) in events.into_iter() {
+            let index = index as usize % seeds.len();
+            let addr = addrs[index];
+            let val_pk = validator_pubkey(&seeds[index]);
+            let nonce = store.get_or_default(&addr).nonce;
+            let amount = Coin::new(u64::from(amount % 8 + 1) * 1_0000_0000).unwrap();
+            // failed transactions don't change the state, ignore the errors
+            match kind % 5 {
+                0 => {
+                    let _ = table.deposit(&mut store, &addr, amount);
+                }
+                1 => {
+                    let unbond = UnbondTx {
+                        from_staked_account: addr,
+                        nonce,
+                        value: amount,
+                        attributes: Default::default(),
+                    };
+                    let _ = table.unbond(
+                        &mut store,
+                        10,
+                        DEFAULT_GENESIS_TIME,
+                        block_height.into(),
+                        &unbond,
+                        Fee::zero(),
+                    );
+                }
+                2 => {
+                    let node_join = NodeJoinRequestTx {
+                        nonce,
+                        address: addr,
+                        attributes: Default::default(),
+                        node_meta: mock_council_node_join(val_pk),
+                    };
+                    let _ = table.node_join(&mut store, DEFAULT_GENESIS_TIME, 10, 0, &node_join);
+                }
+                3 => {
+                    evidences.push((
+                        TendermintValidatorAddress::from(&val_pk),
+                        BlockHeight::from(block_height),
+                        DEFAULT_GENESIS_TIME,
+                    ));
+                }
+                _ => {
+                    for (pubkey, power) in table.end_block(&store, max_validators) {
+                        if power == TendermintVotePower::zero() {
+                            tendermint.remove(&pubkey);
+                        } else {
+                            tendermint.insert(pubkey, power);
+                        }
+                    }
+                    assert_eq!(
+                        tendermint,
+                        expected_validators(&store, &addrs, max_validators)
+                    );
+
+                    block_height += 1;
+                    table.begin_block(
+                        &mut store,
+                        &BeginBlockInfo {
+                            params: &params,
+                            app_version: chain_core::APP_VERSION,
+                            max_evidence_age: 10,
+                            block_time: DEFAULT_GENESIS_TIME,
+                            block_height: block_height.into(),
+                            voters: &[],
+                            evidences: &evidences,
+                        },
+                    );
+                    evidences.clear();
+                }
+            }
+        }
+        true
+    }
+
+    quickcheck! {
+        // incremental validator updates always match the validator set chosen from scratch
+        fn validator_updates_match_recomputed_set(events: Vec<(u8, u8, u8)>) -> bool {
+            check_validator_updates(events)
+        }
+    }
 }
@@ -2,14 +2,14 @@
 //! so many methods here needs caller to provide access to external merkle trie
 //! through traits `GetStaking` and `StoreStaking`.
 
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet};
 
 use core::cmp::Ordering;
 use itertools::Itertools;
 use parity_scale_codec::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 
-use chain_core::common::Timespec;
+use chain_core::common::{Timespec, H256};
 use chain_core::init::coin::{sum_coins, Coin, CoinError, CoinResult};
 use chain_core::init::config::SlashRatio;
 use chain_core::state::account::{
@@ -19,9 +19,11 @@ use chain_core::state::account::{
 use chain_core::state::tendermint::{
     BlockHeight, TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
 };
+use chain_core::EVIDENCE_DEDUP_APP_VERSION;
 use chain_storage::buffer::{GetStaking, StoreStaking};
 
 use crate::app::BeginBlockInfo;
+use crate::beacon::RandomnessBeacon;
 use crate::liveness::LivenessTracker;
 
 pub type RewardsDistribution = Vec<(StakedStateAddress, Coin)>;
@@ -127,6 +129,9 @@ pub struct StakingTable {
     chosen_validators: BTreeMap<StakedStateAddress, TendermintVotePower>,
     liveness: BTreeMap<StakedStateAddress, LivenessTracker>,
     participator_stats: BTreeMap<StakedStateAddress, u64>,
+    // Hashes of processed byzantine evidences -> evidence time,
+    // bounded by the `evidence_dedup_window` network parameter (or the maximum evidence age).
+    processed_evidences: BTreeMap<H256, Timespec>,
 
     // Call `initialize` to populate the indexes after deserialized.
     // Keep the recent value of minimal_required_staking to do sanity check on validator states.
@@ -138,6 +143,26 @@ pub struct StakingTable {
     idx_sort: BTreeSet<ValidatorSortKey>,
 }
 
+/// Layout of the staking table stored before processed evidences were tracked
+/// (state format 0), only decoded to migrate the stored chain state
+#[derive(Decode)]
+pub struct LegacyStakingTable {
+    chosen_validators: BTreeMap<StakedStateAddress, TendermintVotePower>,
+    liveness: BTreeMap<StakedStateAddress, LivenessTracker>,
+    participator_stats: BTreeMap<StakedStateAddress, u64>,
+}
+
+impl From<LegacyStakingTable> for StakingTable {
+    fn from(legacy: LegacyStakingTable) -> Self {
+        StakingTable {
+            chosen_validators: legacy.chosen_validators,
+            liveness: legacy.liveness,
+            participator_stats: legacy.participator_stats,
+            ..Default::default()
+        }
+    }
+}
+
 /// Returned if the caller did not do the necessary validations
 /// before inserting the validator record
 #[derive(Debug)]
@@ -268,10 +293,15 @@ impl StakingTable {
     }
 
     /// Returns (remainder, distribution)
+    ///
+    /// Rewards are distributed in proportion to the voting power of signed blocks, base units
+    /// left over by the rounding are given one each to participators picked by `beacon` (without
+    /// beacon, app versions before `REWARD_REMAINDER_APP_VERSION`, they're the remainder).
     pub fn reward_distribute(
         &mut self,
         heap: &mut impl StoreStaking,
         total_rewards: Coin,
+        beacon: Option<&mut RandomnessBeacon>,
     ) -> (Coin, RewardsDistribution) {
         let sum_power: u64 = self
             .participator_stats
@@ -282,18 +312,40 @@ impl StakingTable {
             return (total_rewards, vec![]);
         }
 
-        let mut distributed = Vec::new();
-        let mut remainder = total_rewards;
         let stats = std::mem::take(&mut self.participator_stats);
-        for (addr, count) in stats.into_iter() {
-            let mut staking = self.get_or_default(heap, &addr);
-            let amount = Coin::new(
-                (((u64::from(total_rewards) as u128) * count as u128) / sum_power as u128) as u64,
-            )
-            .expect("Overflow while distributing rewards");
-            remainder = (remainder - amount).unwrap();
-            distributed.push((addr, amount));
-            self.add_bonded(amount, &mut staking).unwrap();
+        let mut distributed = stats
+            .into_iter()
+            .map(|(addr, count)| {
+                let amount = Coin::new(
+                    (((u64::from(total_rewards) as u128) * count as u128) / sum_power as u128)
+                        as u64,
+                )
+                .expect("Overflow while distributing rewards");
+                (addr, amount)
+            })
+            .collect::<RewardsDistribution>();
+        let mut remainder = (total_rewards
+            - sum_coins(distributed.iter().map(|(_, amount)| *amount))
+                .expect("Overflow while distributing rewards"))
+        .unwrap();
+
+        // the rounding leaves less base units than participators (unless `sum_power` saturated)
+        if let Some(beacon) = beacon {
+            let mut order = (0..distributed.len()).collect::<Vec<_>>();
+            beacon.shuffle(&mut order);
+            for index in order {
+                if remainder == Coin::zero() {
+                    break;
+                }
+                let unit = Coin::unit();
+                distributed[index].1 = (distributed[index].1 + unit).unwrap();
+                remainder = (remainder - unit).unwrap();
+            }
+        }
+
+        for (addr, amount) in distributed.iter() {
+            let mut staking = self.get_or_default(heap, addr);
+            self.add_bonded(*amount, &mut staking).unwrap();
             set_staking(heap, staking, self.minimal_required_staking);
         }
         #[cfg(debug_assertions)]
@@ -545,7 +597,7 @@ impl StakingTable {
                     .get(val_addr)
                     .map(|addr| (*addr, *signed))
             })
-            .collect::<HashMap<_, _>>();
+            .collect::<BTreeMap<_, _>>();
 
         // update liveness trackers
         for (addr, tracker) in self.liveness.iter_mut() {
@@ -580,13 +632,31 @@ impl StakingTable {
             log::error!("validator for vote not exists or is cleaned up");
         }
 
+        let dedup_evidences = info.app_version >= EVIDENCE_DEDUP_APP_VERSION;
+        if dedup_evidences {
+            // forget evidences out of the dedup window
+            let dedup_window = info
+                .params
+                .get_evidence_dedup_window()
+                .unwrap_or(info.max_evidence_age);
+            self.processed_evidences
+                .retain(|_, ev_time| ev_time.saturating_add(dedup_window) > info.block_time);
+        }
+
         // handle byzantine evidences, ignore invalid addresses
-        for (val_addr, _, ev_time) in info.evidences.iter() {
+        for evidence in info.evidences.iter() {
+            let (val_addr, _, ev_time) = evidence;
             if info.block_time >= ev_time.saturating_add(info.get_unbonding_period()) {
                 // ignore evidence too long ago
                 log::warn!("evidence older than unbonding period detected");
                 continue;
             }
+            // ignore evidence already processed in previous blocks (or replayed in this one)
+            let ev_hash: H256 = blake3::hash(&evidence.encode()).into();
+            if dedup_evidences && self.processed_evidences.insert(ev_hash, *ev_time).is_some() {
+                log::warn!("duplicated evidence detected");
+                continue;
+            }
             // slash and jail if not already jailed.
             if let Some(addr) = self.idx_validator_address.get(val_addr) {
                 // panic: Invariant 2.1
@@ -1,11 +1,17 @@
+use std::collections::BTreeSet;
+
 use crate::enclave_bridge::EnclaveProxy;
 use crate::staking::StakingTable;
-use crate::tx_error::PublicTxError;
+use crate::tx_error::{NetworkParamsUpdateError, PublicTxError};
 use chain_core::common::Timespec;
 use chain_core::init::coin::Coin;
+use chain_core::init::params::NetworkParameters;
 use chain_core::state::account::{
     CouncilNodeMeta, NodeMetadata, StakedStateAddress, StakedStateOpAttributes,
+    StakedStateOpWitness,
 };
+use chain_core::state::tendermint::BlockHeight;
+use chain_core::state::validator::NetworkParamsUpdateTx;
 use chain_core::tx::data::input::{TxoPointer, TxoSize};
 use chain_core::tx::fee::Fee;
 use chain_core::tx::{TransactionId, TxEnclaveAux, TxObfuscated, TxPublicAux};
@@ -125,6 +131,8 @@ pub enum TxPublicAction {
         isv_svn: u16,
     },
     Unjail(StakedStateAddress),
+    /// network parameters update scheduled at the block height
+    NetworkParamsUpdate(BlockHeight),
 }
 
 impl TxPublicAction {
@@ -151,6 +159,7 @@ impl TxPublicAction {
             Self::Unbond { fee, .. } => *fee,
             Self::NodeJoin { .. } => Fee::new(Coin::zero()),
             Self::Unjail(_) => Fee::new(Coin::zero()),
+            Self::NetworkParamsUpdate(_) => Fee::new(Coin::zero()),
         }
     }
 
@@ -159,6 +168,7 @@ impl TxPublicAction {
             Self::Unbond { unbond, .. } => Some(unbond.0),
             Self::NodeJoin { address, .. } => Some(*address),
             Self::Unjail(staking_address) => Some(*staking_address),
+            Self::NetworkParamsUpdate(_) => None,
         }
     }
 }
@@ -358,5 +368,65 @@ pub fn process_public_tx(
                 isv_svn,
             ))
         }
+        TxPublicAux::NetworkParamsUpdateTx(..) => {
+            unreachable!("network parameters updates are processed by their own handler")
+        }
+    }
+}
+
+/// Schedules the network parameters update if it's signed by validators holding
+/// more than 2/3 of the voting power of the current validator set
+/// (and its nonce is the number of updates accepted before, so it can't be replayed)
+pub fn process_network_params_update_tx(
+    network_params: &mut NetworkParameters,
+    staking_table: &StakingTable,
+    chain_info: &ChainInfo,
+    maintx: &NetworkParamsUpdateTx,
+    witnesses: &[StakedStateOpWitness],
+) -> Result<TxPublicAction, PublicTxError> {
+    check_staking_attributes(&maintx.attributes, chain_info.chain_hex_id)?;
+    let update_nonce = network_params.get_update_nonce();
+    if maintx.nonce != update_nonce {
+        return Err(NetworkParamsUpdateError::IncorrectNonce(maintx.nonce, update_nonce).into());
     }
+    let effective_height = maintx.update.effective_height;
+    if effective_height <= chain_info.block_height {
+        return Err(NetworkParamsUpdateError::EffectiveHeightPassed(
+            effective_height,
+            chain_info.block_height,
+        )
+        .into());
+    }
+    maintx
+        .update
+        .punishment_params
+        .validate()
+        .map_err(NetworkParamsUpdateError::InvalidParameters)?;
+
+    let validators = staking_table.get_chosen_validators();
+    let txid = maintx.id();
+    let mut signers = BTreeSet::new();
+    let mut signed_power: u64 = 0;
+    for witness in witnesses.iter() {
+        let address = verify_tx_recover_address(witness, &txid)?;
+        let power = validators
+            .get(&address)
+            .ok_or(NetworkParamsUpdateError::NotValidator(address))?;
+        if !signers.insert(address) {
+            return Err(NetworkParamsUpdateError::DuplicateWitness(address).into());
+        }
+        signed_power = signed_power.saturating_add(u64::from(*power));
+    }
+    let total_power = validators
+        .values()
+        .fold(0u64, |acc, power| acc.saturating_add(u64::from(*power)));
+    // more than 2/3, the same as the quorum of tendermint commits
+    if (signed_power as u128) * 3 <= (total_power as u128) * 2 {
+        return Err(
+            NetworkParamsUpdateError::NotEnoughVotingPower(signed_power, total_power).into(),
+        );
+    }
+
+    network_params.schedule_update(maintx.update.clone());
+    Ok(TxPublicAction::NetworkParamsUpdate(effective_height))
 }
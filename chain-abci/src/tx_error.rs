@@ -1,4 +1,7 @@
+use crate::app::tx_handler::TxKind;
 use chain_core::init::coin::{Coin, CoinError};
+use chain_core::state::account::StakedStateAddress;
+use chain_core::state::tendermint::BlockHeight;
 use mls::extras::{self};
 
 #[derive(thiserror::Error, Debug)]
@@ -11,6 +14,26 @@ pub enum TxError {
     Public(#[from] PublicTxError),
     #[error("FIXME/WIP payload for MLS handshake (not yet supported)")]
     WIPMLSData,
+    #[error("{0} tx is not supported by app version {1}")]
+    UnsupportedTxKind(TxKind, u64),
+    #[error("mempool pre-check failed: {0}")]
+    Precheck(#[from] PrecheckError),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PrecheckError {
+    #[error("tx size {0} exceeds the mempool limit {1}")]
+    TooLarge(usize, usize),
+    #[error("tx has duplicated inputs")]
+    DuplicateInputs,
+    #[error("tx spends an input that was already spent")]
+    InputSpent,
+    #[error("tx nonce {0} doesn't match staking state nonce {1}")]
+    IncorrectNonce(u64, u64),
+    #[error("tx nonce {0} doesn't match network parameters update nonce {1}")]
+    IncorrectParamsUpdateNonce(u64, u64),
+    #[error("{0} witnesses can't hold more than 2/3 of the validators voting power")]
+    NotEnoughParamsUpdateWitnesses(usize),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -31,6 +54,8 @@ pub enum PublicTxError {
     NodeJoin(#[from] NodeJoinError),
     #[error("unbond tx process failed: {0}")]
     Unbond(#[from] UnbondError),
+    #[error("network parameters update tx process failed: {0}")]
+    NetworkParamsUpdate(#[from] NetworkParamsUpdateError),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -83,6 +108,22 @@ pub enum UnbondError {
     ZeroValue,
 }
 
+#[derive(thiserror::Error, Debug)]
+pub enum NetworkParamsUpdateError {
+    #[error("tx nonce {0} doesn't match network parameters update nonce {1}")]
+    IncorrectNonce(u64, u64),
+    #[error("effective height {0} is not after the current block height {1}")]
+    EffectiveHeightPassed(BlockHeight, BlockHeight),
+    #[error("invalid punishment parameters: {0}")]
+    InvalidParameters(&'static str),
+    #[error("the witness of {0} is not from a validator")]
+    NotValidator(StakedStateAddress),
+    #[error("duplicated witness of {0}")]
+    DuplicateWitness(StakedStateAddress),
+    #[error("witnesses have {0} of the total voting power {1}, more than 2/3 is required")]
+    NotEnoughVotingPower(u64, u64),
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum DepositError {
     #[error("coin error in deposit tx: {0}")]
@@ -1,25 +1,31 @@
 use abci::*;
 use bit_vec::BitVec;
+use chain_abci::app::snapshot::{ApplySnapshotChunkResult, OfferSnapshotResult, SnapshotManager};
+use chain_abci::app::warmup::WarmupPhase;
 use chain_abci::app::*;
 use chain_abci::enclave_bridge::mock::MockClient;
 use chain_abci::staking::StakingTable;
-use chain_core::common::{MerkleTree, Proof, H256, HASH_SIZE_256};
-use chain_core::compute_app_hash;
+use chain_core::common::{
+    MerkleTree, Proof, TendermintEventKey, TendermintEventType, H256, HASH_SIZE_256,
+};
 use chain_core::init::address::RedeemAddress;
 use chain_core::init::coin::Coin;
 use chain_core::init::config::InitConfig;
 use chain_core::init::config::InitNetworkParameters;
 use chain_core::init::config::NetworkParameters;
 use chain_core::init::config::{
-    JailingParameters, RewardsParameters, SlashRatio, SlashingParameters,
+    JailingParameters, PunishmentParameters, RewardsParameters, ScheduledParametersUpdate,
+    SlashRatio, SlashingParameters,
 };
 use chain_core::state::account::{
     DepositBondTx, NodeState, StakedState, StakedStateAddress, StakedStateDestination,
     StakedStateOpAttributes, StakedStateOpWitness, UnbondTx, WithdrawUnbondedTx,
 };
+use chain_core::state::compact_block::{CompactBlock, CompactTxType};
 use chain_core::state::tendermint::{
     BlockHeight, TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
 };
+use chain_core::state::tx_result::TxResult;
 use chain_core::state::validator::NodeJoinRequestTx;
 use chain_core::state::{ChainState, RewardsPoolState};
 use chain_core::tx::fee::{LinearFee, Milli};
@@ -37,11 +43,13 @@ use chain_core::tx::{
     witness::{TxInWitness, TxWitness},
     PlainTxAux, TransactionId, TxAux, TxEnclaveAux, TxPublicAux,
 };
-use chain_storage::buffer::Get;
+use chain_core::{compute_app_hash, AppHashParts};
+use chain_storage::buffer::{flush_storage, Get, KVBuffer};
 use chain_storage::jellyfish::SparseMerkleProof;
+use chain_storage::snapshot::SnapshotStore;
 use chain_storage::{
-    LookupItem, Storage, CHAIN_ID_KEY, COL_EXTRA, COL_NODE_INFO, GENESIS_APP_HASH_KEY,
-    LAST_STATE_KEY, NUM_COLUMNS,
+    LookupItem, Storage, CHAIN_ID_KEY, COL_APP_HASHS, COL_EXTRA, COL_MERKLE_PROOFS, COL_NODE_INFO,
+    COL_STAKING_VERSIONS, GENESIS_APP_HASH_KEY, LAST_STATE_KEY, NUM_COLUMNS, STATE_FORMAT_KEY,
 };
 use chain_tx_filter::BlockFilter;
 use hex::decode;
@@ -54,10 +62,11 @@ use secp256k1::{key::PublicKey, key::SecretKey, key::XOnlyPublicKey, Message, Se
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::convert::TryInto;
+use std::mem;
 use std::str::FromStr;
 use std::sync::Arc;
 use test_common::chain_env::{
-    mock_confidential_init, mock_council_node_join, ChainEnv, DEFAULT_GENESIS_TIME,
+    create_storage, mock_confidential_init, mock_council_node_join, ChainEnv, DEFAULT_GENESIS_TIME,
 };
 
 const TEST_CHAIN_ID: &str = "test-00";
@@ -85,12 +94,23 @@ fn create_db_with_state_history() -> Arc<dyn KeyValueDB> {
     let decoded_gah = decode(EXAMPLE_HASH).unwrap();
     let mut genesis_app_hash = [0u8; HASH_SIZE_256];
     genesis_app_hash.copy_from_slice(&decoded_gah[..]);
+    let account = StakedState::default(RedeemAddress::default().into());
+    let account_root = Storage::new_db(db.clone()).put_stakings(0, &[account]);
+    let state = get_dummy_app_state(account_root);
     let mut inittx = db.transaction();
     inittx.put(COL_NODE_INFO, GENESIS_APP_HASH_KEY, &genesis_app_hash);
+    inittx.put(COL_NODE_INFO, LAST_STATE_KEY, &state.encode());
     inittx.put(
         COL_NODE_INFO,
-        LAST_STATE_KEY,
-        &get_dummy_app_state(genesis_app_hash).encode(),
+        STATE_FORMAT_KEY,
+        &CHAIN_NODE_STATE_FORMAT.encode(),
+    );
+    let height = state.last_block_height.encode();
+    inittx.put(COL_APP_HASHS, &height, &state.last_apphash);
+    inittx.put(
+        COL_STAKING_VERSIONS,
+        &height,
+        &state.staking_version.encode(),
     );
     inittx.put(COL_EXTRA, CHAIN_ID_KEY, TEST_CHAIN_ID.as_bytes());
 
@@ -137,6 +157,54 @@ fn proper_last_state_should_be_restored() {
     assert_eq!(chain_id, TEST_CHAIN_ID.as_bytes());
 }
 
+#[test]
+fn legacy_last_state_should_be_migrated() {
+    let db = create_db_with_state_history();
+    let storage = Storage::new_db(db.clone());
+    let state =
+        ChainNodeState::decode(&mut storage.get_last_app_state().unwrap().as_slice()).unwrap();
+    // format 0 layout: the staking table without the processed evidences
+    let mut legacy = (
+        state.last_block_height,
+        state.last_apphash,
+        state.block_time,
+        state.block_height,
+    )
+        .encode();
+    // empty chosen validators, liveness trackers and participator stats
+    legacy.extend_from_slice(&[0, 0, 0]);
+    legacy.extend(
+        (
+            state.genesis_time,
+            state.max_evidence_age,
+            state.staking_version,
+            state.utxo_coins,
+            state.enclave_isv_svn,
+            &state.top_level,
+        )
+            .encode(),
+    );
+    let mut tx = db.transaction();
+    tx.put(COL_NODE_INFO, LAST_STATE_KEY, &legacy);
+    tx.delete(COL_NODE_INFO, STATE_FORMAT_KEY);
+    db.write(tx).unwrap();
+
+    let app = ChainNodeApp::new_with_storage(
+        get_enclave_bridge_mock(),
+        EXAMPLE_HASH,
+        TEST_CHAIN_ID,
+        Storage::new_db(db),
+        None,
+        "".to_string(),
+    );
+    assert_eq!(app.storage.get_state_format(), CHAIN_NODE_STATE_FORMAT);
+    assert_eq!(
+        app.storage.get_last_app_state().unwrap(),
+        app.last_state.as_ref().unwrap().encode()
+    );
+    assert_eq!(app.last_state.unwrap().last_apphash, state.last_apphash);
+}
+
 #[test]
 #[should_panic]
 fn too_long_hash_should_panic() {
@@ -183,7 +251,11 @@ fn nonhex_hash_should_panic() {
 }
 
 fn get_dummy_network_params() -> NetworkParameters {
-    NetworkParameters::Genesis(InitNetworkParameters {
+    NetworkParameters::Genesis(get_dummy_init_network_params())
+}
+
+fn get_dummy_init_network_params() -> InitNetworkParameters {
+    InitNetworkParameters {
         initial_fee_policy: LinearFee::new(
             Milli::try_new(1, 1).unwrap(),
             Milli::try_new(1, 1).unwrap(),
@@ -207,14 +279,15 @@ fn get_dummy_network_params() -> NetworkParameters {
             monetary_expansion_decay: 999_860,
         },
         max_validators: 2,
-    })
+    }
 }
 
-fn get_dummy_app_state(app_hash: H256) -> ChainNodeState {
+fn get_dummy_app_state(account_root: H256) -> ChainNodeState {
     let params = get_dummy_network_params();
+    let rewards_pool = RewardsPoolState::new(0, params.get_rewards_monetary_expansion_tau());
     ChainNodeState {
         last_block_height: BlockHeight::genesis(),
-        last_apphash: app_hash,
+        last_apphash: compute_app_hash(&MerkleTree::empty(), &account_root, &rewards_pool, &params),
         block_time: 0,
         block_height: BlockHeight::genesis(),
         genesis_time: 0,
@@ -223,9 +296,10 @@ fn get_dummy_app_state(app_hash: H256) -> ChainNodeState {
         staking_version: 0,
         utxo_coins: Coin::zero(),
         enclave_isv_svn: 0,
+        app_version: chain_core::APP_VERSION,
         top_level: ChainState {
-            account_root: [0u8; 32],
-            rewards_pool: RewardsPoolState::new(0, params.get_rewards_monetary_expansion_tau()),
+            account_root,
+            rewards_pool,
             network_params: params,
         },
     }
@@ -246,6 +320,84 @@ fn previously_stored_hash_should_match() {
     );
 }
 
+#[test]
+#[should_panic(expected = "inconsistent last committed state")]
+fn inconsistent_last_state_should_panic() {
+    let db = create_db_with_state_history();
+    let mut tx = db.transaction();
+    tx.put(COL_APP_HASHS, &BlockHeight::genesis().encode(), &[0u8; 32]);
+    db.write(tx).unwrap();
+    let _app = ChainNodeApp::new_with_storage(
+        get_enclave_bridge_mock(),
+        EXAMPLE_HASH,
+        TEST_CHAIN_ID,
+        Storage::new_db(db),
+        None,
+        "".to_string(),
+    );
+}
+
+/// Commits two blocks, returns the storage with the given entry overwritten (or deleted)
+/// and the encoded state committed at height 1
+fn storage_with_inconsistent_height_2(
+    env: &ChainEnv,
+    storage: Storage,
+    corrupt: impl FnOnce(&ChainNodeState) -> ((u32, Vec<u8>), Option<Vec<u8>>),
+) -> (Storage, Vec<u8>) {
+    let mut app = env.chain_node(storage);
+    let _rsp = app.init_chain(&env.req_init_chain());
+    for height in 1..=2 {
+        app.begin_block(&env.req_begin_block(height, 0));
+        app.end_block(&RequestEndBlock {
+            height,
+            ..Default::default()
+        });
+        app.commit(&RequestCommit::default());
+    }
+    let state_1 = app.storage.get_recent_state(1.into()).unwrap();
+    let (key, value) = corrupt(app.last_state.as_ref().unwrap());
+    let mut storage = mem::replace(&mut app.storage, create_storage());
+    let mut buffer = KVBuffer::new();
+    buffer.insert(key, value);
+    flush_storage(&mut storage, buffer).unwrap();
+    (storage, state_1)
+}
+
+#[test]
+fn inconsistent_last_state_should_roll_back_to_a_recent_state() {
+    let (env, storage) = ChainEnv::new(Coin::max(), Coin::zero(), 1);
+    let (storage, state_1) = storage_with_inconsistent_height_2(&env, storage, |_| {
+        (
+            (COL_APP_HASHS, BlockHeight::new(2).encode()),
+            Some(vec![0u8; 32]),
+        )
+    });
+
+    let app = env.chain_node(storage);
+    let state = app.last_state.as_ref().unwrap();
+    assert_eq!(state.last_block_height, 1.into());
+    assert_eq!(state.encode(), state_1);
+    assert_eq!(app.storage.get_last_app_state().unwrap(), state_1);
+}
+
+#[test]
+fn missing_txs_merkle_tree_should_roll_back_to_a_recent_state() {
+    let (env, storage) = ChainEnv::new(Coin::max(), Coin::zero(), 1);
+    // only genesis states have no tree (state sync restores it with the snapshot state)
+    let (storage, _) = storage_with_inconsistent_height_2(&env, storage, |state| {
+        ((COL_MERKLE_PROOFS, state.last_apphash.to_vec()), None)
+    });
+
+    // empty blocks may share the app hash (and so the tree) with the previous ones
+    let app = env.chain_node(storage);
+    let state = app.last_state.as_ref().unwrap();
+    assert!(state.last_block_height < 2.into());
+    assert_eq!(
+        app.storage.get_recent_state(state.last_block_height),
+        Some(state.encode())
+    );
+}
+
 fn init_chain_for(address: RedeemAddress) -> ChainNodeApp<MockClient> {
     let db = create_db();
     let total = (Coin::max() - Coin::unit()).unwrap();
@@ -266,7 +418,7 @@ fn init_chain_for(address: RedeemAddress) -> ChainNodeApp<MockClient> {
     .iter()
     .cloned()
     .collect();
-    let NetworkParameters::Genesis(params) = get_dummy_network_params();
+    let params = get_dummy_init_network_params();
     let mut nodes = BTreeMap::new();
     let pub_key =
         TendermintValidatorPubKey::from_base64(b"MDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDA=")
@@ -501,6 +653,37 @@ fn check_tx_should_accept_valid_tx() {
     assert_eq!(0, cresp.code, "{}", cresp.log);
 }
 
+#[test]
+fn check_tx_should_reject_oversized_tx() {
+    let (mut app, txaux, _) = prepare_app_valid_tx();
+    let mut creq = RequestCheckTx::default();
+    creq.set_tx(txaux.encode());
+    app.set_max_mempool_tx_size(creq.tx.len() - 1);
+    let cresp = app.check_tx(&creq);
+    assert_ne!(0, cresp.code);
+    assert!(cresp.log.contains("pre-check"));
+    app.set_max_mempool_tx_size(creq.tx.len());
+    let cresp = app.check_tx(&creq);
+    assert_eq!(0, cresp.code, "{}", cresp.log);
+}
+
+#[test]
+fn check_tx_should_reject_incorrect_nonce_before_verifying_witness() {
+    let (env, storage) = ChainEnv::new(Coin::max(), Coin::zero(), 1);
+    let mut app = env.chain_node(storage);
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    let mut creq = RequestCheckTx::default();
+    creq.set_tx(env.unbond_tx(Coin::one(), 1, 0).encode());
+    let cresp = app.check_tx(&creq);
+    assert_ne!(0, cresp.code);
+    assert!(cresp.log.contains("pre-check"));
+
+    creq.set_tx(env.unbond_tx(Coin::one(), 0, 0).encode());
+    let cresp = app.check_tx(&creq);
+    assert_eq!(0, cresp.code, "{}", cresp.log);
+}
+
 #[test]
 #[should_panic]
 fn two_beginblocks_should_panic() {
@@ -797,6 +980,71 @@ fn valid_commit_should_persist() {
     assert!(!new_utxos.any());
 }
 
+#[test]
+fn snapshot_should_restore_state() {
+    let (mut app, tx, _, _) = deliver_valid_tx();
+    let dir = std::env::temp_dir().join(format!("chain-abci-snapshots-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    app.set_snapshots(SnapshotManager {
+        store: SnapshotStore::new(&dir).unwrap(),
+        interval: 10,
+        keep_recent: 2,
+        chunk_size: 256,
+    });
+    let mut endreq = RequestEndBlock::default();
+    endreq.set_height(10);
+    app.end_block(&endreq);
+    app.commit(&RequestCommit::default());
+    let last_state = app.last_state.clone().unwrap();
+
+    let snapshots = app.list_snapshots();
+    assert_eq!(1, snapshots.len());
+    let metadata = &snapshots[0];
+    assert_eq!(BlockHeight::new(10), metadata.height);
+    assert_eq!(last_state.last_apphash, metadata.app_hash);
+    assert_eq!(
+        OfferSnapshotResult::Abort,
+        app.offer_snapshot(&metadata.encode(), &metadata.app_hash)
+    );
+
+    let mut new_app = ChainNodeApp::new_with_storage(
+        get_enclave_bridge_mock(),
+        &hex::encode_upper(app.genesis_app_hash),
+        TEST_CHAIN_ID,
+        Storage::new_db(create_db()),
+        None,
+        "".to_string(),
+    );
+    assert_eq!(
+        OfferSnapshotResult::Reject,
+        new_app.offer_snapshot(&metadata.encode(), &[0u8; 32])
+    );
+    assert_eq!(
+        OfferSnapshotResult::Accept,
+        new_app.offer_snapshot(&metadata.encode(), &metadata.app_hash)
+    );
+    for index in 0..metadata.chunks() {
+        let chunk = app
+            .load_snapshot_chunk(metadata.height.value(), metadata.format, index)
+            .unwrap();
+        assert_eq!(
+            ApplySnapshotChunkResult::Accept,
+            new_app.apply_snapshot_chunk(index, &chunk)
+        );
+    }
+    let restored_state = new_app.last_state.as_ref().unwrap();
+    assert_eq!(last_state.encode(), restored_state.encode());
+    assert!(new_app
+        .storage
+        .lookup_item(LookupItem::TxMetaSpent, &tx.id())
+        .is_some());
+    assert_eq!(
+        new_app.storage.get_last_app_state(),
+        app.storage.get_last_app_state()
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
 #[test]
 fn no_delivered_tx_commit_should_keep_apphash() {
     let mut app = init_chain_for(
@@ -821,7 +1069,22 @@ fn query_should_return_an_account() {
     qreq.path = "account".into();
     let qresp = app.query(&qreq);
     let account = StakedState::decode(&mut qresp.value.as_slice()).unwrap();
-    assert_eq!(account.address, StakedStateAddress::from_str(addr).unwrap());
+    let address = StakedStateAddress::from_str(addr).unwrap();
+    assert_eq!(account.address, address);
+    assert!(qresp.proof.is_none());
+
+    qreq.prove = true;
+    let qresp = app.query(&qreq);
+    let ops = &qresp.proof.get_ref().ops;
+    let proof = SparseMerkleProof::decode(&mut ops[0].data.as_slice()).unwrap();
+    let parts = AppHashParts::decode(&mut ops[1].data.as_slice()).unwrap();
+    assert_eq!(
+        parts.app_hash(),
+        app.last_state.as_ref().unwrap().last_apphash
+    );
+    assert!(proof
+        .verify(parts.account_root, &address, Some(&account))
+        .is_ok());
 }
 
 #[test]
@@ -835,10 +1098,46 @@ fn staking_query_should_return_an_account() {
     let qresp = app.query(&qreq);
     let mstaking = <Option<StakedState>>::decode(&mut qresp.value.as_slice()).unwrap();
     let mut proof_bytes = qresp.proof.get_ref().ops[0].data.as_slice();
-    let _proof = SparseMerkleProof::decode(&mut proof_bytes).unwrap();
+    let proof = SparseMerkleProof::decode(&mut proof_bytes).unwrap();
+    let address = StakedStateAddress::from_str(addr).unwrap();
+    assert_eq!(mstaking.as_ref().unwrap().address, address);
+
+    let app_hash_op = &qresp.proof.get_ref().ops[1];
+    assert_eq!(app_hash_op.field_type, "app_hash");
+    let parts = AppHashParts::decode(&mut app_hash_op.data.as_slice()).unwrap();
+    let last_state = app.last_state.as_ref().unwrap();
+    assert_eq!(parts.app_hash(), last_state.last_apphash);
+    assert!(proof
+        .verify(parts.account_root, &address, mstaking.as_ref())
+        .is_ok());
+}
+
+#[test]
+fn query_should_return_rewards_pool_and_network_params() {
+    let addr = "fe7c045110b8dbf29765047380898919c5cb56f9";
+    let mut app = init_chain_for(addr.parse().unwrap());
+    let top_level = app.last_state.as_ref().unwrap().top_level.clone();
+    let mut qreq = RequestQuery::new();
+    qreq.path = "rewards_pool".into();
+    let qresp = app.query(&qreq);
+    let rewards_pool: RewardsPoolState = serde_json::from_slice(&qresp.value).unwrap();
+    assert_eq!(rewards_pool, top_level.rewards_pool);
+
+    qreq.path = "network_params".into();
+    let qresp = app.query(&qreq);
+    let params: NetworkParameters = serde_json::from_slice(&qresp.value).unwrap();
+    assert_eq!(params, top_level.network_params);
+}
+
+#[test]
+fn info_should_wait_for_warm_up() {
+    let mut app = init_chain_for("fe7c045110b8dbf29765047380898919c5cb56f9".parse().unwrap());
+    assert_eq!(WarmupPhase::Pending, app.readiness.phase());
+    let info = app.info(&RequestInfo::default());
+    assert!(app.readiness.is_ready());
     assert_eq!(
-        mstaking.unwrap().address,
-        StakedStateAddress::from_str(addr).unwrap()
+        info.last_block_app_hash,
+        app.last_state.as_ref().unwrap().last_apphash.to_vec()
     );
 }
 
@@ -1204,6 +1503,438 @@ fn query_should_return_proof_for_committed_tx() {
     assert_eq!(proof.ops[1].data, witness_hash.to_vec());
 }
 
+#[test]
+fn query_should_return_compact_block_for_committed_block() {
+    let (env, storage) =
+        ChainEnv::new_with_customizer(Coin::max(), Coin::zero(), 2, |parameters| {
+            parameters.required_council_node_stake = (Coin::max() / 10).unwrap();
+        });
+    let mut app = env.chain_node(storage);
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    app.begin_block(&env.req_begin_block(1, 0));
+    let tx_aux = env.unbond_tx(Coin::new(5000000000000000000).unwrap(), 0, 0);
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: tx_aux.encode(),
+        ..Default::default()
+    });
+    assert_eq!(0, rsp_tx.code);
+    let _response_end_block = app.end_block(&RequestEndBlock {
+        height: 1,
+        ..Default::default()
+    });
+    let cresp = app.commit(&RequestCommit::default());
+
+    let mut qreq = RequestQuery::new();
+    qreq.path = "compact-block".into();
+    qreq.height = 1;
+    let qresp = app.query(&qreq);
+    assert_eq!(0, qresp.code);
+    let compact_block = CompactBlock::decode(&mut qresp.value.as_slice()).unwrap();
+    assert_eq!(compact_block.height, BlockHeight::from(1));
+    assert_eq!(compact_block.app_hash.to_vec(), cresp.data);
+    assert!(compact_block.block_filter.is_none());
+    assert_eq!(compact_block.txids(), vec![tx_aux.tx_id()]);
+    assert_eq!(
+        compact_block.transactions[0].tx_type,
+        CompactTxType::UnbondStake
+    );
+    match &tx_aux {
+        TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => {
+            assert_eq!(
+                compact_block.transactions[0].staking_address,
+                Some(tx.from_staked_account)
+            );
+        }
+        _ => unreachable!(),
+    }
+
+    qreq.height = 2;
+    let qresp = app.query(&qreq);
+    assert_ne!(0, qresp.code);
+}
+
+#[test]
+fn query_should_return_historical_state_at_height() {
+    let (env, storage) =
+        ChainEnv::new_with_customizer(Coin::max(), Coin::zero(), 2, |parameters| {
+            parameters.required_council_node_stake = (Coin::max() / 10).unwrap();
+        });
+    let mut app = env.chain_node(storage);
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    app.begin_block(&env.req_begin_block(1, 0));
+    app.end_block(&RequestEndBlock {
+        height: 1,
+        ..Default::default()
+    });
+    app.commit(&RequestCommit::default());
+
+    app.begin_block(&env.req_begin_block(2, 0));
+    let tx_aux = env.unbond_tx(Coin::new(5000000000000000000).unwrap(), 0, 0);
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: tx_aux.encode(),
+        ..Default::default()
+    });
+    assert_eq!(0, rsp_tx.code);
+    app.end_block(&RequestEndBlock {
+        height: 2,
+        ..Default::default()
+    });
+    app.commit(&RequestCommit::default());
+
+    let mut qreq = RequestQuery::new();
+    qreq.path = "account".into();
+    qreq.data = env.accounts[0].staking_address().as_ref().to_vec();
+    let latest = StakedState::decode(&mut app.query(&qreq).value.as_slice()).unwrap();
+    qreq.height = 1;
+    let past = StakedState::decode(&mut app.query(&qreq).value.as_slice()).unwrap();
+    assert_eq!(latest.nonce, 1);
+    assert_eq!(past.nonce, 0);
+    assert!(past.bonded > latest.bonded);
+
+    qreq.path = "store".into();
+    qreq.data = tx_aux.tx_id().to_vec();
+    assert_ne!(0, app.query(&qreq).code);
+    qreq.path = "meta".into();
+    assert_ne!(0, app.query(&qreq).code);
+    qreq.path = "store".into();
+    qreq.height = 2;
+    assert_eq!(0, app.query(&qreq).code);
+}
+
+#[test]
+fn query_should_return_txs_by_address() {
+    let (env, storage) =
+        ChainEnv::new_with_customizer(Coin::max(), Coin::zero(), 2, |parameters| {
+            parameters.required_council_node_stake = (Coin::max() / 10).unwrap();
+        });
+    let mut app = env.chain_node(storage);
+    app.enable_address_index();
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    app.begin_block(&env.req_begin_block(1, 0));
+    let tx_aux = env.unbond_tx(Coin::new(5000000000000000000).unwrap(), 0, 0);
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: tx_aux.encode(),
+        ..Default::default()
+    });
+    assert_eq!(0, rsp_tx.code);
+    app.end_block(&RequestEndBlock {
+        height: 1,
+        ..Default::default()
+    });
+    app.commit(&RequestCommit::default());
+
+    let mut qreq = RequestQuery::new();
+    qreq.path = "txs_by_address".into();
+    qreq.data = env.accounts[0].staking_address().as_ref().to_vec();
+    let qresp = app.query(&qreq);
+    assert_eq!(0, qresp.code);
+    let txs = <Vec<(BlockHeight, TxId)>>::decode(&mut qresp.value.as_slice()).unwrap();
+    assert_eq!(txs, vec![(BlockHeight::from(1), tx_aux.tx_id())]);
+
+    qreq.data = env.accounts[1].staking_address().as_ref().to_vec();
+    let qresp = app.query(&qreq);
+    let txs = <Vec<(BlockHeight, TxId)>>::decode(&mut qresp.value.as_slice()).unwrap();
+    assert!(txs.is_empty());
+}
+
+struct RecordingHook(Arc<std::sync::Mutex<Vec<(BlockHeight, H256, Vec<TxId>, usize)>>>);
+
+impl commit_hook::CommitHook for RecordingHook {
+    fn name(&self) -> &str {
+        "recording"
+    }
+
+    fn on_commit(&mut self, block: &commit_hook::CommittedBlock<'_>) -> Result<(), String> {
+        let txids = block.delivered_txs.iter().map(TxAux::tx_id).collect();
+        self.0
+            .lock()
+            .unwrap()
+            .push((block.height, block.app_hash, txids, block.events.len()));
+        Err("hook errors are ignored".to_owned())
+    }
+}
+
+#[test]
+fn commit_hooks_should_be_invoked_after_commit() {
+    let (env, storage) =
+        ChainEnv::new_with_customizer(Coin::max(), Coin::zero(), 2, |parameters| {
+            parameters.required_council_node_stake = (Coin::max() / 10).unwrap();
+        });
+    let mut app = env.chain_node(storage);
+    let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+    app.register_commit_hook(Box::new(RecordingHook(records.clone())));
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    app.begin_block(&env.req_begin_block(1, 0));
+    let tx_aux = env.unbond_tx(Coin::new(5000000000000000000).unwrap(), 0, 0);
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: tx_aux.encode(),
+        ..Default::default()
+    });
+    assert_eq!(0, rsp_tx.code);
+    let _response_end_block = app.end_block(&RequestEndBlock {
+        height: 1,
+        ..Default::default()
+    });
+    let cresp = app.commit(&RequestCommit::default());
+
+    let records = records.lock().unwrap();
+    assert_eq!(1, records.len());
+    let (height, app_hash, txids, no_of_events) = &records[0];
+    assert_eq!(BlockHeight::from(1), *height);
+    assert_eq!(app_hash.to_vec(), cresp.data);
+    assert_eq!(&vec![tx_aux.tx_id()], txids);
+    assert!(*no_of_events >= rsp_tx.events.len());
+    assert!(app.block_events.is_empty());
+}
+
+#[test]
+fn end_block_should_emit_validator_update_events() {
+    let (env, storage) =
+        ChainEnv::new_with_customizer(Coin::max(), Coin::zero(), 2, |parameters| {
+            parameters.required_council_node_stake = (Coin::max() / 10).unwrap();
+        });
+    let mut app = env.chain_node(storage);
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    app.begin_block(&env.req_begin_block(1, 0));
+    let tx_aux = env.unbond_tx(Coin::new(5000000000000000000).unwrap(), 0, 0);
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: tx_aux.encode(),
+        ..Default::default()
+    });
+    assert_eq!(0, rsp_tx.code);
+    let rsp_end_block = app.end_block(&RequestEndBlock {
+        height: 1,
+        ..Default::default()
+    });
+    assert_eq!(1, rsp_end_block.validator_updates.len());
+    assert_eq!(0, rsp_end_block.validator_updates[0].power);
+
+    let events = rsp_end_block
+        .events
+        .iter()
+        .filter(|event| event.field_type == TendermintEventType::StakingChange.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(1, events.len());
+    let attribute = |key: TendermintEventKey| {
+        events[0]
+            .attributes
+            .iter()
+            .find(|kv| kv.key == key)
+            .map(|kv| String::from_utf8(kv.value.clone()).unwrap())
+    };
+    assert_eq!(
+        Some(env.accounts[0].staking_address().to_string()),
+        attribute(TendermintEventKey::StakingAddress)
+    );
+    assert_eq!(
+        Some("validatorupdate".to_owned()),
+        attribute(TendermintEventKey::StakingOpType)
+    );
+    assert_eq!(
+        Some("[{\"key\":\"VotingPower\",\"value\":\"0\"}]".to_owned()),
+        attribute(TendermintEventKey::StakingDiff)
+    );
+}
+
+#[test]
+fn network_params_update_should_take_effect_at_effective_height() {
+    let (env, storage) =
+        ChainEnv::new_with_customizer(Coin::max(), Coin::zero(), 2, |parameters| {
+            parameters.required_council_node_stake = (Coin::max() / 10).unwrap();
+        });
+    let mut app = env.chain_node(storage);
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    let genesis_params = &env.init_config.network_params;
+    let mut punishment_params = PunishmentParameters {
+        jailing_config: genesis_params.jailing_config,
+        slashing_config: genesis_params.slashing_config,
+        evidence_dedup_window: 86400,
+    };
+    punishment_params.slashing_config.liveness_slash_percent = SlashRatio::from_str("0.5").unwrap();
+    let update = ScheduledParametersUpdate {
+        effective_height: 3.into(),
+        punishment_params,
+    };
+
+    app.begin_block(&env.req_begin_block(1, 0));
+    // half of the voting power is not enough
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: env.params_update_tx(update.clone(), 0, &[0]).encode(),
+        ..Default::default()
+    });
+    assert_ne!(0, rsp_tx.code);
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: env.params_update_tx(update.clone(), 0, &[0, 0]).encode(),
+        ..Default::default()
+    });
+    assert_ne!(0, rsp_tx.code);
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: env.params_update_tx(update.clone(), 0, &[0, 1]).encode(),
+        ..Default::default()
+    });
+    assert_eq!(0, rsp_tx.code);
+    // the accepted update can't be replayed
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: env.params_update_tx(update.clone(), 0, &[0, 1]).encode(),
+        ..Default::default()
+    });
+    assert_ne!(0, rsp_tx.code);
+    app.end_block(&RequestEndBlock {
+        height: 1,
+        ..Default::default()
+    });
+    let cresp = app.commit(&RequestCommit::default());
+
+    // the scheduled update is committed in the app hash before it takes effect
+    let state = app.last_state.as_ref().unwrap();
+    let network_params = &state.top_level.network_params;
+    assert_eq!(network_params.get_scheduled_updates(), &[update.clone()]);
+    assert_eq!(network_params.get_update_nonce(), 1);
+    assert_eq!(
+        network_params.get_liveness_slash_percent(),
+        genesis_params.slashing_config.liveness_slash_percent
+    );
+    assert_ne!(
+        network_params.hash(),
+        NetworkParameters::Genesis(genesis_params.clone()).hash()
+    );
+    assert_eq!(cresp.data, state.last_apphash.to_vec());
+    let rsp_check = app.check_tx(&RequestCheckTx {
+        tx: env.params_update_tx(update.clone(), 0, &[0, 1]).encode(),
+        ..Default::default()
+    });
+    assert_ne!(0, rsp_check.code);
+    // a single witness can't hold more than 2/3 of the voting power
+    let rsp_check = app.check_tx(&RequestCheckTx {
+        tx: env.params_update_tx(update.clone(), 1, &[0]).encode(),
+        ..Default::default()
+    });
+    assert_ne!(0, rsp_check.code);
+    assert!(rsp_check.log.contains("witnesses can't hold"));
+
+    app.begin_block(&env.req_begin_block(2, 0));
+    app.end_block(&RequestEndBlock {
+        height: 2,
+        ..Default::default()
+    });
+    app.commit(&RequestCommit::default());
+    assert_eq!(
+        app.last_state
+            .as_ref()
+            .unwrap()
+            .top_level
+            .network_params
+            .get_liveness_slash_percent(),
+        genesis_params.slashing_config.liveness_slash_percent
+    );
+
+    app.begin_block(&env.req_begin_block(3, 0));
+    let network_params = &app.last_state.as_ref().unwrap().top_level.network_params;
+    assert!(network_params.get_scheduled_updates().is_empty());
+    assert_eq!(
+        network_params.get_liveness_slash_percent(),
+        SlashRatio::from_str("0.5").unwrap()
+    );
+    // the effective height has passed
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: env.params_update_tx(update, 1, &[0, 1]).encode(),
+        ..Default::default()
+    });
+    assert_ne!(0, rsp_tx.code);
+}
+
+#[test]
+fn network_params_update_should_be_accepted_from_the_upgrade_height() {
+    let (mut env, storage) =
+        ChainEnv::new_with_customizer(Coin::max(), Coin::zero(), 2, |parameters| {
+            parameters.required_council_node_stake = (Coin::max() / 10).unwrap();
+        });
+    env.init_config.app_version = chain_core::NETWORK_PARAMS_UPDATE_APP_VERSION - 1;
+    let mut app = env.chain_node(storage);
+    app.schedule_app_version_upgrade(2.into(), chain_core::NETWORK_PARAMS_UPDATE_APP_VERSION);
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    let genesis_params = &env.init_config.network_params;
+    let update = ScheduledParametersUpdate {
+        effective_height: 5.into(),
+        punishment_params: PunishmentParameters {
+            jailing_config: genesis_params.jailing_config,
+            slashing_config: genesis_params.slashing_config,
+            evidence_dedup_window: 86400,
+        },
+    };
+    let tx = env.params_update_tx(update, 0, &[0, 1]).encode();
+
+    app.begin_block(&env.req_begin_block(1, 0));
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: tx.clone(),
+        ..Default::default()
+    });
+    assert_ne!(0, rsp_tx.code);
+    app.end_block(&RequestEndBlock {
+        height: 1,
+        ..Default::default()
+    });
+    app.commit(&RequestCommit::default());
+
+    app.begin_block(&env.req_begin_block(2, 0));
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx,
+        ..Default::default()
+    });
+    assert_eq!(0, rsp_tx.code);
+}
+
+#[test]
+fn query_should_return_tx_result_for_delivered_tx() {
+    let (env, storage) =
+        ChainEnv::new_with_customizer(Coin::max(), Coin::zero(), 2, |parameters| {
+            parameters.required_council_node_stake = (Coin::max() / 10).unwrap();
+        });
+    let mut app = env.chain_node(storage);
+    let _rsp = app.init_chain(&env.req_init_chain());
+
+    app.begin_block(&env.req_begin_block(1, 0));
+    let tx_aux = env.unbond_tx(Coin::new(5000000000000000000).unwrap(), 0, 0);
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: tx_aux.encode(),
+        ..Default::default()
+    });
+    assert_eq!(0, rsp_tx.code);
+    // replay is rejected, but doesn't shadow the result of the applied transaction
+    let rsp_tx = app.deliver_tx(&RequestDeliverTx {
+        tx: tx_aux.encode(),
+        ..Default::default()
+    });
+    assert_ne!(0, rsp_tx.code);
+    let _response_end_block = app.end_block(&RequestEndBlock {
+        height: 1,
+        ..Default::default()
+    });
+    app.commit(&RequestCommit::default());
+
+    let mut qreq = RequestQuery::new();
+    qreq.path = "tx_result".into();
+    qreq.data = tx_aux.tx_id().to_vec();
+    let qresp = app.query(&qreq);
+    assert_eq!(0, qresp.code);
+    let result = TxResult::decode(&mut qresp.value.as_slice()).unwrap();
+    assert!(result.is_ok());
+    assert_eq!(result.height, BlockHeight::from(1));
+    assert_eq!(result.index, 0);
+    assert!(!result.events.is_empty());
+
+    qreq.data = [0u8; 32].to_vec();
+    let qresp = app.query(&qreq);
+    assert_ne!(0, qresp.code);
+}
+
 #[test]
 #[should_panic]
 fn check_invalid_punishment_config() {
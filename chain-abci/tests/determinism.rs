@@ -0,0 +1,86 @@
+use abci::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use chain_core::init::coin::Coin;
+use parity_scale_codec::Encode;
+use test_common::chain_env::{create_storage, ChainEnv, DEFAULT_GENESIS_TIME};
+
+const VALIDATORS: usize = 4;
+const BLOCKS: i64 = 30;
+
+/// Two nodes executing the same blocks must end up with the same app hash after every block,
+/// regardless of the (per instance randomized) iteration order of hash maps they use.
+#[test]
+fn nodes_should_agree_on_app_hash_under_random_workload() {
+    for seed in 0..4 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let dist = Coin::new(10_0000_0000_0000_0000).unwrap();
+        let (env, storage) = ChainEnv::new(dist, dist, VALIDATORS);
+        let mut nodes = vec![env.chain_node(storage), env.chain_node(create_storage())];
+        for node in nodes.iter_mut() {
+            node.init_chain(&env.req_init_chain());
+        }
+
+        let reward_period = env
+            .init_config
+            .network_params
+            .rewards_config
+            .reward_period_seconds as i64;
+        let mut block_time = DEFAULT_GENESIS_TIME as i64;
+        let mut nonces = [0u64; VALIDATORS];
+        for height in 1..=BLOCKS {
+            block_time += rng.gen_range(0, reward_period * 2);
+            let mut req = env.req_begin_block_with_time(height, 0, block_time);
+            let last_commit_info = if rng.gen_bool(0.5) {
+                env.last_commit_info_signed()
+            } else {
+                env.last_commit_info(rng.gen_range(0, VALIDATORS), false)
+            };
+            req.set_last_commit_info(last_commit_info);
+            if rng.gen_bool(0.05) {
+                req.byzantine_validators =
+                    vec![env.byzantine_evidence(rng.gen_range(0, VALIDATORS))].into();
+            }
+
+            let txs = (0..rng.gen_range(0, 3))
+                .map(|_| {
+                    let index = rng.gen_range(0, VALIDATORS);
+                    let amount = Coin::new(rng.gen_range(1, 1_0000_0000_0000)).unwrap();
+                    (index, env.unbond_tx(amount, nonces[index], index))
+                })
+                .collect::<Vec<_>>();
+
+            let mut app_hashes = vec![];
+            let mut validator_updates = vec![];
+            for (i, node) in nodes.iter_mut().enumerate() {
+                node.begin_block(&req);
+                for (index, tx) in txs.iter() {
+                    let rsp = node.deliver_tx(&RequestDeliverTx {
+                        tx: tx.encode(),
+                        ..Default::default()
+                    });
+                    if i == 0 && rsp.code == 0 {
+                        nonces[*index] += 1;
+                    }
+                }
+                let rsp = node.end_block(&RequestEndBlock {
+                    height,
+                    ..Default::default()
+                });
+                validator_updates.push(rsp.validator_updates.to_vec());
+                app_hashes.push(node.commit(&RequestCommit::new()).data);
+            }
+            assert_eq!(
+                validator_updates[0], validator_updates[1],
+                "validator updates diverged at height {} (seed {})",
+                height, seed
+            );
+            assert_eq!(
+                app_hashes[0], app_hashes[1],
+                "app hash diverged at height {} (seed {})",
+                height, seed
+            );
+        }
+    }
+}
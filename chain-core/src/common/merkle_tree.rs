@@ -15,10 +15,13 @@ const EMPTY_HASH: H256 = [
 #[derive(Debug, Encode, Decode)]
 pub enum Tree<T> {
     /// Empty Node
+    #[codec(index = "0")]
     Empty,
     /// Leaf Node
+    #[codec(index = "1")]
     Leaf { hash: H256, value: T },
     /// Middle Node
+    #[codec(index = "2")]
     Node {
         hash: H256,
         left: Box<Tree<T>>,
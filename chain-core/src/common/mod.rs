@@ -4,6 +4,7 @@ use std::prelude::v1::{String, Vec};
 use std::string::ToString;
 
 use digest::Digest;
+use parity_scale_codec::Error;
 
 /// Generic merkle tree
 mod merkle_tree;
@@ -25,6 +26,40 @@ pub fn hash256<D: Digest>(data: &[u8]) -> H256 {
 /// Seconds since UNIX epoch
 pub type Timespec = u64;
 
+/// First tag which is neither used nor reserved by SCALE-encoded consensus enums
+/// (kept for a possible multi-byte tag encoding)
+pub const FIRST_INVALID_TAG: u8 = 0x80;
+
+/// Discriminants of a SCALE-encoded consensus enum
+///
+/// Tags of the existing variants are fixed (`0..variants`), as changing them would fork the
+/// chain; new variants take the next tags from the reserved range (`variants..FIRST_INVALID_TAG`).
+/// Decoding a reserved tag fails with a distinct error, so that payloads of a newer protocol
+/// version can be told apart from malformed ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnumTags {
+    /// number of variants in this version
+    pub variants: u8,
+    /// message of the error of decoding an invalid tag
+    pub invalid_tag: &'static str,
+}
+
+impl EnumTags {
+    /// Returns whether the tag is reserved for variants of future versions
+    pub fn is_reserved(&self, tag: u8) -> bool {
+        tag >= self.variants && tag < FIRST_INVALID_TAG
+    }
+
+    /// Returns the error of decoding an unknown tag
+    pub fn unknown_tag(&self, tag: u8) -> Error {
+        if self.is_reserved(tag) {
+            "Variant reserved for a newer protocol version".into()
+        } else {
+            self.invalid_tag.into()
+        }
+    }
+}
+
 /// 32-byte for keys or hashes etc.
 pub type H256 = [u8; HASH_SIZE_256];
 /// 33-byte for pubkeys etc.
@@ -0,0 +1,210 @@
+//! Textual encodings of transfer addresses
+//!
+//! Transfer addresses are encoded with bech32 and the human readable part of the network.
+//! To allow introducing a different encoding (e.g. bech32m) or human readable part later,
+//! the address formats of a network are a single format used for encoding, while decoding
+//! also accepts the formats in transition (dual-decode / single-encode).
+//!
+//! Wallets and the synchronizer match outputs by the binary address, so an address is
+//! recognized the same way whichever of the accepted encodings it was entered in.
+use std::sync::Arc;
+
+use bech32::{FromBase32, ToBase32, Variant};
+use serde::{Deserialize, Serialize};
+
+use crate::init::address::CroAddressError;
+use crate::init::network::{get_bech32_human_part_from_network, Network};
+
+/// Encodes and decodes address payloads (e.g. the root hash of `ExtendedAddr::OrTree`)
+pub trait AddressCodec: Send + Sync {
+    /// encodes the payload to textual form
+    fn encode(&self, payload: &[u8]) -> Result<String, CroAddressError>;
+    /// decodes the payload from textual form
+    fn decode(&self, encoded: &str) -> Result<Vec<u8>, CroAddressError>;
+}
+
+/// Checksum variants of bech32
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bech32Variant {
+    /// BIP-173
+    Bech32,
+    /// BIP-350
+    Bech32m,
+}
+
+impl From<Bech32Variant> for Variant {
+    fn from(variant: Bech32Variant) -> Self {
+        match variant {
+            Bech32Variant::Bech32 => Variant::Bech32,
+            Bech32Variant::Bech32m => Variant::Bech32m,
+        }
+    }
+}
+
+/// Built-in address format: bech32 family with a human readable part
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressFormat {
+    /// checksum variant
+    pub variant: Bech32Variant,
+    /// human readable part
+    pub hrp: String,
+}
+
+impl AddressFormat {
+    /// Creates a new address format
+    pub fn new(variant: Bech32Variant, hrp: &str) -> Self {
+        AddressFormat {
+            variant,
+            hrp: hrp.to_owned(),
+        }
+    }
+}
+
+impl AddressCodec for AddressFormat {
+    fn encode(&self, payload: &[u8]) -> Result<String, CroAddressError> {
+        bech32::encode(&self.hrp, payload.to_base32(), self.variant.into())
+            .map_err(|e| CroAddressError::Bech32Error(e.to_string()))
+    }
+
+    fn decode(&self, encoded: &str) -> Result<Vec<u8>, CroAddressError> {
+        if !encoded.to_lowercase().starts_with(&self.hrp) {
+            return Err(CroAddressError::InvalidNetwork);
+        }
+        let (hrp, data, variant) =
+            bech32::decode(encoded).map_err(|e| CroAddressError::Bech32Error(e.to_string()))?;
+        if hrp != self.hrp {
+            return Err(CroAddressError::InvalidNetwork);
+        }
+        if variant != self.variant.into() {
+            return Err(CroAddressError::Bech32Error("invalid checksum".to_owned()));
+        }
+        Vec::from_base32(&data).map_err(|_| CroAddressError::ConvertError)
+    }
+}
+
+/// Address formats of a network
+///
+/// To migrate to a new format, set it as `encode` and keep the previous one in `accept`
+/// for the transition period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressFormatParams {
+    /// format used to encode addresses
+    pub encode: AddressFormat,
+    /// other formats still accepted when decoding
+    pub accept: Vec<AddressFormat>,
+}
+
+/// Returns the address formats of the provided network
+pub fn get_address_format_params_from_network(network: Network) -> AddressFormatParams {
+    AddressFormatParams {
+        encode: AddressFormat::new(
+            Bech32Variant::Bech32,
+            get_bech32_human_part_from_network(network),
+        ),
+        accept: vec![],
+    }
+}
+
+/// Single encoder and a list of decoders tried in order
+#[derive(Clone)]
+pub struct AddressCodecRegistry {
+    encoder: Arc<dyn AddressCodec>,
+    decoders: Vec<Arc<dyn AddressCodec>>,
+}
+
+impl AddressCodecRegistry {
+    /// Creates a registry which encodes (and decodes) with `encoder`
+    pub fn new(encoder: Arc<dyn AddressCodec>) -> Self {
+        AddressCodecRegistry {
+            decoders: vec![encoder.clone()],
+            encoder,
+        }
+    }
+
+    /// Creates a registry from the address formats of a network
+    pub fn from_params(params: &AddressFormatParams) -> Self {
+        params.accept.iter().fold(
+            AddressCodecRegistry::new(Arc::new(params.encode.clone())),
+            |registry, format| registry.accept(Arc::new(format.clone())),
+        )
+    }
+
+    /// Also accepts `decoder` when decoding
+    pub fn accept(mut self, decoder: Arc<dyn AddressCodec>) -> Self {
+        self.decoders.push(decoder);
+        self
+    }
+
+    /// Encodes the payload with the encoder
+    pub fn encode(&self, payload: &[u8]) -> Result<String, CroAddressError> {
+        self.encoder.encode(payload)
+    }
+
+    /// Decodes with the first decoder which accepts the encoded address,
+    /// returns the error of the encoder if none does
+    pub fn decode(&self, encoded: &str) -> Result<Vec<u8>, CroAddressError> {
+        let mut first_error = None;
+        for decoder in self.decoders.iter() {
+            match decoder.decode(encoded) {
+                Ok(payload) => return Ok(payload),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        Err(first_error.unwrap_or(CroAddressError::ConvertError))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PAYLOAD: &str = "0e7c045110b8dbf29765047380898919c5cb56f400112233445566778899aabb";
+    const BECH32_ADDRESS: &str = "dcro1pe7qg5gshrdl99m9q3ecpzvfr8zuk4h5qqgjyv6y24n80zye42as88x8tg";
+    const BECH32M_ADDRESS: &str = "dcro1pe7qg5gshrdl99m9q3ecpzvfr8zuk4h5qqgjyv6y24n80zye42asjmktw2";
+
+    #[test]
+    fn check_bech32_variants() {
+        let payload = hex::decode(PAYLOAD).unwrap();
+        let bech32 = AddressFormat::new(Bech32Variant::Bech32, "dcro");
+        let bech32m = AddressFormat::new(Bech32Variant::Bech32m, "dcro");
+
+        assert_eq!(bech32.encode(&payload).unwrap(), BECH32_ADDRESS);
+        assert_eq!(bech32m.encode(&payload).unwrap(), BECH32M_ADDRESS);
+
+        assert_eq!(bech32.decode(BECH32_ADDRESS).unwrap(), payload);
+        assert_eq!(
+            bech32.decode(&BECH32_ADDRESS.to_uppercase()).unwrap(),
+            payload
+        );
+        assert_eq!(bech32m.decode(BECH32M_ADDRESS).unwrap(), payload);
+        assert!(bech32.decode(BECH32M_ADDRESS).is_err());
+        assert!(bech32m.decode(BECH32_ADDRESS).is_err());
+        assert_eq!(
+            AddressFormat::new(Bech32Variant::Bech32, "cro").decode(BECH32_ADDRESS),
+            Err(CroAddressError::InvalidNetwork)
+        );
+    }
+
+    #[test]
+    fn check_transition() {
+        let payload = hex::decode(PAYLOAD).unwrap();
+        let params = AddressFormatParams {
+            encode: AddressFormat::new(Bech32Variant::Bech32m, "dcro"),
+            accept: vec![AddressFormat::new(Bech32Variant::Bech32, "dcro")],
+        };
+        let registry = AddressCodecRegistry::from_params(&params);
+
+        assert_eq!(registry.encode(&payload).unwrap(), BECH32M_ADDRESS);
+        assert_eq!(registry.decode(BECH32_ADDRESS).unwrap(), payload);
+        assert_eq!(registry.decode(BECH32M_ADDRESS).unwrap(), payload);
+
+        let registry = AddressCodecRegistry::from_params(&get_address_format_params_from_network(
+            Network::Devnet,
+        ));
+        assert_eq!(registry.encode(&payload).unwrap(), BECH32_ADDRESS);
+        assert!(registry.decode(BECH32M_ADDRESS).is_err());
+    }
+}
@@ -50,6 +50,9 @@ pub enum DistributionError {
     /// Invalid punishment configuration parameter
     #[error("Invalid punishment parameters")]
     InvalidPunishmentParamter,
+    /// app version not supported by this binary
+    #[error("Unsupported app version: {0}")]
+    UnsupportedAppVersion(u64),
     /// keypackage decode error
     #[error("key package decode failed")]
     KeyPackageDecodeError,
@@ -76,6 +79,14 @@ pub struct InitConfig {
             ConfidentialInit,
         ),
     >,
+    /// app version (state machine rules) the network starts with
+    #[serde(default = "default_app_version")]
+    pub app_version: u64,
+}
+
+/// genesis configurations from before the app version was configurable
+fn default_app_version() -> u64 {
+    crate::INITIAL_APP_VERSION
 }
 
 /// the initial state at genesis
@@ -109,6 +120,7 @@ impl InitConfig {
             distribution: owners,
             network_params,
             council_nodes,
+            app_version: crate::APP_VERSION,
         }
     }
 
@@ -165,6 +177,9 @@ impl InitConfig {
         &self,
         genesis_time: Timespec,
     ) -> Result<GenesisState, DistributionError> {
+        if self.app_version > crate::APP_VERSION {
+            return Err(DistributionError::UnsupportedAppVersion(self.app_version));
+        }
         let jailing_config = &self.network_params.jailing_config;
         if jailing_config.missed_block_threshold > jailing_config.block_signing_window {
             return Err(DistributionError::InvalidPunishmentParamter);
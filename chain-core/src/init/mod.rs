@@ -3,6 +3,8 @@
 
 /// Eth-style address
 pub mod address;
+/// Textual encodings of transfer addresses
+pub mod address_codec;
 /// Fixed supply coin/amounts
 pub mod coin;
 /// Configuration in JSON passed to InitChain
@@ -1,5 +1,7 @@
-use crate::common::H256;
+use crate::common::{Timespec, H256};
 use crate::init::coin::{Coin, CoinError};
+use crate::state::account::Nonce;
+use crate::state::tendermint::BlockHeight;
 use crate::tx::fee::{Fee, FeeAlgorithm};
 use crate::tx::fee::{LinearFee, Milli, MilliError};
 use parity_scale_codec::{Decode, Encode};
@@ -33,11 +35,39 @@ pub struct InitNetworkParameters {
     pub max_validators: u16,
 }
 
-/// so far only one specified at genesis
+/// network parameters specified at genesis, possibly updated later by network parameters
+/// update transactions
 #[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize)]
 pub enum NetworkParameters {
     /// parameters specified at genesis time
+    #[codec(index = "0")]
     Genesis(InitNetworkParameters),
+    /// parameters after the first scheduled update
+    #[codec(index = "1")]
+    Updated(UpdatedNetworkParameters),
+}
+
+/// network parameters with the updates scheduled by network parameters update transactions
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct UpdatedNetworkParameters {
+    /// parameters currently in effect
+    pub params: InitNetworkParameters,
+    /// updates waiting for their effective height, sorted by it
+    pub scheduled: Vec<ScheduledParametersUpdate>,
+    /// number of updates scheduled so far (the nonce of the next update transaction)
+    pub update_nonce: Nonce,
+    /// evidence dedup window set by the last applied update
+    /// (none until one is applied, genesis parameters don't have it)
+    pub evidence_dedup_window: Option<Timespec>,
+}
+
+/// update of the punishment parameters taking effect at a future block height
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct ScheduledParametersUpdate {
+    /// first block height the new parameters are used in
+    pub effective_height: BlockHeight,
+    /// the new parameters
+    pub punishment_params: PunishmentParameters,
 }
 
 /// TODO: extract these to a trait?
@@ -47,100 +77,148 @@ impl NetworkParameters {
         blake3::hash(&self.encode()).into()
     }
 
-    /// cap on validators in tendermint
-    pub fn get_max_validators(&self) -> usize {
+    /// parameters currently in effect
+    fn current(&self) -> &InitNetworkParameters {
         match self {
-            NetworkParameters::Genesis(params) => params.max_validators as usize,
+            NetworkParameters::Genesis(params) => params,
+            NetworkParameters::Updated(updated) => &updated.params,
         }
     }
 
-    /// minimal stake required for node joining (to be a validator)
-    pub fn get_required_council_node_stake(&self) -> Coin {
+    /// nonce the next network parameters update transaction needs to be signed with
+    pub fn get_update_nonce(&self) -> Nonce {
         match self {
-            NetworkParameters::Genesis(params) => params.required_council_node_stake,
+            NetworkParameters::Genesis(_) => 0,
+            NetworkParameters::Updated(updated) => updated.update_nonce,
         }
     }
 
-    /// infraction configuration for byzantine fault
-    pub fn get_byzantine_slash_percent(&self) -> SlashRatio {
+    /// updates scheduled at future block heights
+    pub fn get_scheduled_updates(&self) -> &[ScheduledParametersUpdate] {
         match self {
-            NetworkParameters::Genesis(params) => params.slashing_config.byzantine_slash_percent,
+            NetworkParameters::Genesis(_) => &[],
+            NetworkParameters::Updated(updated) => &updated.scheduled,
         }
     }
 
+    /// schedules an update of the punishment parameters (kept sorted by the effective height,
+    /// updates at the same height are applied in the order they were scheduled)
+    /// and increments the update nonce
+    pub fn schedule_update(&mut self, update: ScheduledParametersUpdate) {
+        if let NetworkParameters::Genesis(params) = self {
+            *self = NetworkParameters::Updated(UpdatedNetworkParameters {
+                params: params.clone(),
+                scheduled: vec![],
+                update_nonce: 0,
+                evidence_dedup_window: None,
+            });
+        }
+        if let NetworkParameters::Updated(updated) = self {
+            let index = updated
+                .scheduled
+                .iter()
+                .position(|scheduled| scheduled.effective_height > update.effective_height)
+                .unwrap_or(updated.scheduled.len());
+            updated.scheduled.insert(index, update);
+            updated.update_nonce += 1;
+        }
+    }
+
+    /// applies the scheduled updates which take effect at or before the given block height,
+    /// returns true if any was applied
+    pub fn apply_scheduled_updates(&mut self, block_height: BlockHeight) -> bool {
+        match self {
+            NetworkParameters::Genesis(_) => false,
+            NetworkParameters::Updated(updated) => {
+                let due = updated
+                    .scheduled
+                    .iter()
+                    .take_while(|scheduled| scheduled.effective_height <= block_height)
+                    .count();
+                for scheduled in updated.scheduled.drain(..due) {
+                    updated.params.jailing_config = scheduled.punishment_params.jailing_config;
+                    updated.params.slashing_config = scheduled.punishment_params.slashing_config;
+                    updated.evidence_dedup_window =
+                        Some(scheduled.punishment_params.evidence_dedup_window);
+                }
+                due > 0
+            }
+        }
+    }
+
+    /// cap on validators in tendermint
+    pub fn get_max_validators(&self) -> usize {
+        self.current().max_validators as usize
+    }
+
+    /// minimal stake required for node joining (to be a validator)
+    pub fn get_required_council_node_stake(&self) -> Coin {
+        self.current().required_council_node_stake
+    }
+
+    /// infraction configuration for byzantine fault
+    pub fn get_byzantine_slash_percent(&self) -> SlashRatio {
+        self.current().slashing_config.byzantine_slash_percent
+    }
+
     /// infraction configuration for liveness fault
     pub fn get_liveness_slash_percent(&self) -> SlashRatio {
+        self.current().slashing_config.liveness_slash_percent
+    }
+
+    /// how long processed byzantine evidences are remembered
+    /// (none if no update has set it, callers then fall back to the maximum evidence age)
+    pub fn get_evidence_dedup_window(&self) -> Option<Timespec> {
         match self {
-            NetworkParameters::Genesis(params) => params.slashing_config.liveness_slash_percent,
+            NetworkParameters::Genesis(_) => None,
+            NetworkParameters::Updated(updated) => updated.evidence_dedup_window,
         }
     }
 
     /// infraction configuration for liveness fault
     pub fn get_missed_block_threshold(&self) -> u16 {
-        match self {
-            NetworkParameters::Genesis(params) => params.jailing_config.missed_block_threshold,
-        }
+        self.current().jailing_config.missed_block_threshold
     }
 
     /// infraction configuration for liveness fault
     pub fn get_block_signing_window(&self) -> u16 {
-        match self {
-            NetworkParameters::Genesis(params) => params.jailing_config.block_signing_window,
-        }
+        self.current().jailing_config.block_signing_window
     }
 
     /// The period of reward being distributed
     pub fn get_rewards_reward_period_seconds(&self) -> u64 {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.reward_period_seconds,
-        }
+        self.current().rewards_config.reward_period_seconds
     }
 
     /// The upper bound for the reward rate per annum
     pub fn get_rewards_monetary_expansion_r0(&self) -> Milli {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.monetary_expansion_r0,
-        }
+        self.current().rewards_config.monetary_expansion_r0
     }
 
     /// Initial value of tau in the reward function
     pub fn get_rewards_monetary_expansion_tau(&self) -> u64 {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.monetary_expansion_tau,
-        }
+        self.current().rewards_config.monetary_expansion_tau
     }
 
     /// The decay rate of tau.
     pub fn get_rewards_monetary_expansion_decay(&self) -> u64 {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.monetary_expansion_decay,
-        }
+        self.current().rewards_config.monetary_expansion_decay
     }
 
     /// The total amount of tokens reserved for validator's reward in the basic unit
     pub fn get_rewards_monetary_expansion_cap(&self) -> Coin {
-        match self {
-            NetworkParameters::Genesis(params) => params.rewards_config.monetary_expansion_cap,
-        }
+        self.current().rewards_config.monetary_expansion_cap
     }
 
     /// constant fee -- TODO: will it be necessary? (used in the tx-query fee?)
     pub fn get_min_const_fee(&self) -> Result<Fee, CoinError> {
-        match self {
-            NetworkParameters::Genesis(params) => {
-                let coin = Coin::new(params.initial_fee_policy.coefficient.to_integral())?;
-                Ok(Fee::new(coin))
-            }
-        }
+        let coin = Coin::new(self.current().initial_fee_policy.coefficient.to_integral())?;
+        Ok(Fee::new(coin))
     }
 
     /// calculates the fee based on the specified policy
     pub fn calculate_fee(&self, num_bytes: usize) -> Result<Fee, CoinError> {
-        match self {
-            NetworkParameters::Genesis(params) => {
-                params.initial_fee_policy.calculate_fee(num_bytes)
-            }
-        }
+        self.current().initial_fee_policy.calculate_fee(num_bytes)
     }
 }
 
@@ -167,6 +245,35 @@ pub struct SlashingParameters {
     pub invalid_commit_slash_percent: SlashRatio,
 }
 
+/// jailing and slashing parameters, which can be changed by network parameters update transactions
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode, Serialize, Deserialize)]
+pub struct PunishmentParameters {
+    /// Jailing configuration
+    pub jailing_config: JailingParameters,
+    /// Slashing configuration
+    pub slashing_config: SlashingParameters,
+    /// Seconds (of block time) for which processed byzantine evidences are remembered,
+    /// so the same evidence included in multiple blocks can't slash a validator twice.
+    /// It should be no less than the maximum evidence age.
+    pub evidence_dedup_window: Timespec,
+}
+
+impl PunishmentParameters {
+    /// check if punishment parameters are correct (same rules as the genesis configuration)
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.jailing_config.block_signing_window == 0 {
+            return Err("block signing window can't == 0");
+        }
+        if self.jailing_config.missed_block_threshold > self.jailing_config.block_signing_window {
+            return Err("missed block threshold can't > block signing window");
+        }
+        if self.evidence_dedup_window == 0 {
+            return Err("evidence dedup window can't == 0");
+        }
+        Ok(())
+    }
+}
+
 /// reward parameters
 /// ref: https://crypto-com.github.io/getting-started/reward-and-punishments.html#validator-rewards
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode, Serialize, Deserialize)]
@@ -22,19 +22,64 @@ use state::tendermint::BlockHeight;
 use state::RewardsPoolState;
 use tx::fee::Fee;
 
-/// The app version returned in Tendermint "Info" response,
-/// included in every header + transaction metadata.
-/// It denotes both binary schema and semantics (state machine rules)
+/// The highest app version (state machine rules) this binary supports.
+/// It denotes both binary schema and semantics (state machine rules);
+/// the version a network runs is kept in the chain state: it starts at the `app_version`
+/// of the genesis configuration and is only raised at the upgrade heights scheduled
+/// in the node configuration, so replaying old blocks uses the rules they were executed with.
 /// ref: https://github.com/tendermint/tendermint/blob/master/docs/architecture/adr-016-protocol-versions.md#appversion
-/// TODO: upgrades/new version signalling
 ///
 /// version 0 -- 0.4.0 release
 /// version 1 -- 0.5.0 release (wire format didn't change, but unbond tx semantics changed: https://github.com/crypto-com/chain/pull/1516)
+/// version 2 -- 0.5.0 wire format, the rounding remainder of rewards is handed out to validators,
+/// network parameters update transactions, deduplicated byzantine evidences
 #[cfg(not(feature = "new-txid"))]
-pub const APP_VERSION: u64 = 1;
+pub const APP_VERSION: u64 = 2;
 #[cfg(feature = "new-txid")]
 /// version 2 -- 0.6.0 (not yet released --> transaction data bootstrapping, new TX types, genesis changes, TXID calculation change, app hash calculation change);
-pub const APP_VERSION: u64 = 2;
+/// version 3 -- 0.6.0 + the rounding remainder of rewards is handed out to validators,
+/// network parameters update transactions, deduplicated byzantine evidences
+pub const APP_VERSION: u64 = 3;
+
+/// The app version returned in Tendermint "Info" response, included in every header.
+/// Tendermint requires it to be the same on all nodes, so it doesn't follow the upgrades;
+/// it's also the version of networks whose genesis configuration doesn't set `app_version`.
+#[cfg(not(feature = "new-txid"))]
+pub const INITIAL_APP_VERSION: u64 = 1;
+/// The app version returned in Tendermint "Info" response, included in every header.
+/// Tendermint requires it to be the same on all nodes, so it doesn't follow the upgrades;
+/// it's also the version of networks whose genesis configuration doesn't set `app_version`.
+#[cfg(feature = "new-txid")]
+pub const INITIAL_APP_VERSION: u64 = 2;
+
+/// first app version handing out the rounding remainder of rewards to validators
+/// (before it's kept in the rewards pool for the next period)
+#[cfg(not(feature = "new-txid"))]
+pub const REWARD_REMAINDER_APP_VERSION: u64 = 2;
+/// first app version handing out the rounding remainder of rewards to validators
+/// (before it's kept in the rewards pool for the next period)
+#[cfg(feature = "new-txid")]
+pub const REWARD_REMAINDER_APP_VERSION: u64 = 3;
+
+/// first app version accepting network parameters update transactions
+/// (activated at genesis if the configuration's `app_version` is at least this one,
+/// otherwise from the height of the node configuration's upgrade to it)
+#[cfg(not(feature = "new-txid"))]
+pub const NETWORK_PARAMS_UPDATE_APP_VERSION: u64 = 2;
+/// first app version accepting network parameters update transactions
+/// (activated at genesis if the configuration's `app_version` is at least this one,
+/// otherwise from the height of the node configuration's upgrade to it)
+#[cfg(feature = "new-txid")]
+pub const NETWORK_PARAMS_UPDATE_APP_VERSION: u64 = 3;
+
+/// first app version ignoring byzantine evidences already processed in previous blocks
+/// (before the same evidence included in multiple blocks is punished each time)
+#[cfg(not(feature = "new-txid"))]
+pub const EVIDENCE_DEDUP_APP_VERSION: u64 = 2;
+/// first app version ignoring byzantine evidences already processed in previous blocks
+/// (before the same evidence included in multiple blocks is punished each time)
+#[cfg(feature = "new-txid")]
+pub const EVIDENCE_DEDUP_APP_VERSION: u64 = 3;
 
 /// computes the "global" application hash (used by Tendermint to check consistency + block replaying)
 /// currently: app_hash = blake3(b"app_hash" || root of valid TX merkle tree
@@ -46,16 +91,49 @@ pub fn compute_app_hash(
     reward_pool: &RewardsPoolState,
     params: &NetworkParameters,
 ) -> H256 {
-    let valid_tx_part = valid_tx_id_tree.root_hash();
-    let rewards_pool_part = reward_pool.hash();
-    let network_params_part = params.hash();
-    let mut hasher = blake3::Hasher::new();
-    hasher.update(b"app_hash");
-    hasher.update(&valid_tx_part);
-    hasher.update(&account_state_root[..]);
-    hasher.update(&rewards_pool_part);
-    hasher.update(&network_params_part);
-    hasher.finalize().into()
+    AppHashParts::new(valid_tx_id_tree, account_state_root, reward_pool, params).app_hash()
+}
+
+/// The parts the application hash is computed from
+/// (e.g. for proving the account/staked state trie root to light clients)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode)]
+pub struct AppHashParts {
+    /// root of valid TX merkle tree
+    pub valid_tx_root: H256,
+    /// root of account/staked state trie
+    pub account_root: H256,
+    /// blake3(scale bytes(rewards pool state))
+    pub rewards_pool_hash: H256,
+    /// blake3(scale bytes(network params))
+    pub network_params_hash: H256,
+}
+
+impl AppHashParts {
+    /// collects the parts of the application hash
+    pub fn new(
+        valid_tx_id_tree: &MerkleTree<H256>,
+        account_state_root: &H256,
+        reward_pool: &RewardsPoolState,
+        params: &NetworkParameters,
+    ) -> Self {
+        AppHashParts {
+            valid_tx_root: valid_tx_id_tree.root_hash(),
+            account_root: *account_state_root,
+            rewards_pool_hash: reward_pool.hash(),
+            network_params_hash: params.hash(),
+        }
+    }
+
+    /// computes the "global" application hash from the parts
+    pub fn app_hash(&self) -> H256 {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(b"app_hash");
+        hasher.update(&self.valid_tx_root);
+        hasher.update(&self.account_root);
+        hasher.update(&self.rewards_pool_hash);
+        hasher.update(&self.network_params_hash);
+        hasher.finalize().into()
+    }
 }
 
 /// External information needed for TX validation
@@ -61,12 +61,15 @@ impl From<NackMsgTx> for TaggedTransaction {
 #[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 pub enum MLSHandshakeAux {
     /// the reaction to node leaving (keypackage expire, unbond tx or events)
+    #[codec(index = "0")]
     RemoveCommitProposal(CommitRemoveTx),
     /// when the keypackage is about to expire, the member submits its renewal
+    #[codec(index = "1")]
     SelfUpdateProposal(SelfUpdateProposalTx),
     /// DLEQ proof: https://github.com/crypto-com/chain/pull/1805/files#diff-f5bad205e7530b482b54bda5e678249aR23
     /// + some way to refer to the message part that went wrong
     /// FIXME: spec/data type
+    #[codec(index = "2")]
     MsgNack(NackMsgTx),
 }
 
@@ -1,6 +1,6 @@
 mod address;
 mod op;
-use crate::common::{Timespec, HASH_SIZE_256};
+use crate::common::{EnumTags, Timespec, HASH_SIZE_256};
 use crate::init::coin::Coin;
 use crate::state::tendermint::{
     BlockHeight, TendermintValidatorAddress, TendermintValidatorPubKey,
@@ -35,8 +35,10 @@ pub type NodeSecurityContact = Option<String>;
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Encode, Decode)]
 pub enum MLSInit {
     /// KeyPackage
+    #[codec(index = "0")]
     Genesis(Vec<u8>),
     /// payloads retrieved from other node's TDBE
+    #[codec(index = "1")]
     NodeJoin {
         /// MLSPlaintext -- Add
         add: Vec<u8>,
@@ -219,16 +221,28 @@ impl fmt::Display for NodeMetadata {
     }
 }
 
+impl NodeMetadata {
+    /// tag of `NodeMetadata::CouncilNode` (stable)
+    pub const COUNCIL_NODE_TAG: u8 = 0;
+    /// tag of `NodeMetadata::CommunityNode` (stable)
+    pub const COMMUNITY_NODE_TAG: u8 = 1;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 2,
+        invalid_tag: "Unsupported Node variant",
+    };
+}
+
 // TODO: size hint once MLS payloads are there
 impl Encode for NodeMetadata {
     fn encode_to<W: Output>(&self, dest: &mut W) {
         match self {
             NodeMetadata::CouncilNode(cm) => {
-                dest.push_byte(0);
+                dest.push_byte(NodeMetadata::COUNCIL_NODE_TAG);
                 cm.encode_to(dest);
             }
             NodeMetadata::CommunityNode(cm) => {
-                dest.push_byte(1);
+                dest.push_byte(NodeMetadata::COMMUNITY_NODE_TAG);
                 cm.encode_to(dest);
             }
         }
@@ -239,15 +253,15 @@ impl Decode for NodeMetadata {
     fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
         let tag = input.read_byte()?;
         match tag {
-            0 => {
+            NodeMetadata::COUNCIL_NODE_TAG => {
                 let node_info = CouncilNodeMeta::decode(input)?;
                 Ok(NodeMetadata::CouncilNode(node_info))
             }
-            1 => {
+            NodeMetadata::COMMUNITY_NODE_TAG => {
                 let node_info = NodeCommonInfo::decode(input)?;
                 Ok(NodeMetadata::CommunityNode(node_info))
             }
-            _ => Err(Error::from("Unsupported Node variant")),
+            tag => Err(NodeMetadata::TAGS.unknown_tag(tag)),
         }
     }
 }
@@ -329,8 +343,10 @@ impl CouncilNodeMeta {
 )]
 pub enum PunishmentKind {
     /// liveness fault
+    #[codec(index = "0")]
     NonLive,
     /// byzantine fault (double vote signing initially)
+    #[codec(index = "1")]
     ByzantineFault,
 }
 
@@ -463,8 +479,10 @@ impl Validator {
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Encode, Decode, Serialize, Deserialize)]
 pub enum NodeState {
     /// information related to council nodes (validator metadata + keypackage from TDBE)
+    #[codec(index = "0")]
     CouncilNode(Validator),
     /// information related to community nodes (keypackage from TDBE)
+    #[codec(index = "1")]
     CommunityNode(NodeCommonInfo),
 }
 
@@ -1,3 +1,4 @@
+use crate::common::EnumTags;
 use crate::init::address::ErrorAddress;
 use crate::init::address::RedeemAddress;
 use parity_scale_codec::{Decode, Encode, Error, Input, Output};
@@ -14,11 +15,21 @@ pub enum StakedStateAddress {
     BasicRedeem(RedeemAddress),
 }
 
+impl StakedStateAddress {
+    /// tag of `StakedStateAddress::BasicRedeem` (stable)
+    pub const BASIC_REDEEM_TAG: u8 = 0;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 1,
+        invalid_tag: "No such variant in enum StakedStateAddress",
+    };
+}
+
 impl Encode for StakedStateAddress {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         match *self {
             StakedStateAddress::BasicRedeem(ref ra) => {
-                dest.push_byte(0);
+                dest.push_byte(StakedStateAddress::BASIC_REDEEM_TAG);
                 dest.push(ra);
             }
         }
@@ -35,11 +46,11 @@ impl Decode for StakedStateAddress {
     fn decode<DecIn: Input>(input: &mut DecIn) -> Result<Self, Error> {
         let tag = input.read_byte()?;
         match tag {
-            0 => {
+            StakedStateAddress::BASIC_REDEEM_TAG => {
                 let addr = RedeemAddress::decode(input)?;
                 Ok(StakedStateAddress::BasicRedeem(addr))
             }
-            _ => Err("No such variant in enum StakedStateAddress".into()),
+            tag => Err(StakedStateAddress::TAGS.unknown_tag(tag)),
         }
     }
 }
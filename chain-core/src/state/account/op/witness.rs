@@ -1,3 +1,4 @@
+use crate::common::EnumTags;
 use crate::tx::witness::{tree::RawSignature, EcdsaSignature};
 use parity_scale_codec::{Decode, Encode, Error, Input, Output};
 use secp256k1::recovery::{RecoverableSignature, RecoveryId};
@@ -17,11 +18,21 @@ impl StakedStateOpWitness {
     }
 }
 
+impl StakedStateOpWitness {
+    /// tag of `StakedStateOpWitness::BasicRedeem` (stable)
+    pub const BASIC_REDEEM_TAG: u8 = 0;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 1,
+        invalid_tag: "Invalid tag",
+    };
+}
+
 impl Encode for StakedStateOpWitness {
     fn encode_to<W: Output>(&self, dest: &mut W) {
         match *self {
             StakedStateOpWitness::BasicRedeem(ref sig) => {
-                dest.push_byte(0);
+                dest.push_byte(StakedStateOpWitness::BASIC_REDEEM_TAG);
                 let (recovery_id, serialized_sig) = sig.serialize_compact();
                 // recovery_id is one of 0 | 1 | 2 | 3
                 let rid = recovery_id.to_i32() as u8;
@@ -42,7 +53,7 @@ impl Decode for StakedStateOpWitness {
     fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
         let tag = input.read_byte()?;
         match tag {
-            0 => {
+            StakedStateOpWitness::BASIC_REDEEM_TAG => {
                 let rid: u8 = input.read_byte()?;
                 let raw_sig = RawSignature::decode(input)?;
                 let recovery_id = RecoveryId::from_i32(i32::from(rid))
@@ -51,7 +62,7 @@ impl Decode for StakedStateOpWitness {
                     .map_err(|_| Error::from("Unable to create recoverable signature"))?;
                 Ok(StakedStateOpWitness::BasicRedeem(sig))
             }
-            _ => Err(Error::from("Invalid tag")),
+            tag => Err(StakedStateOpWitness::TAGS.unknown_tag(tag)),
         }
     }
 }
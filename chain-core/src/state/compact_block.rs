@@ -0,0 +1,141 @@
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::Vec;
+
+use crate::common::H256;
+use crate::state::account::StakedStateAddress;
+use crate::state::tendermint::BlockHeight;
+use crate::tx::data::input::{TxoPointer, TxoSize};
+use crate::tx::data::TxId;
+use crate::tx::{TxAux, TxEnclaveAux, TxPublicAux};
+
+/// Type tag of a transaction in compact block
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode, Serialize, Deserialize)]
+pub enum CompactTxType {
+    /// value transfer between UTXOs
+    #[codec(index = "0")]
+    Transfer,
+    /// UTXOs deposited into a staked state
+    #[codec(index = "1")]
+    DepositStake,
+    /// bonded stake moved into unbonded
+    #[codec(index = "2")]
+    UnbondStake,
+    /// unbonded stake withdrawn into UTXOs
+    #[codec(index = "3")]
+    WithdrawUnbondedStake,
+    /// staked state unjailed
+    #[codec(index = "4")]
+    Unjail,
+    /// node metadata added to a staked state
+    #[codec(index = "5")]
+    NodeJoin,
+    /// TDBE-related MLS handshake message
+    #[codec(index = "6")]
+    MLSHandshake,
+    /// network parameters update scheduled
+    #[codec(index = "7")]
+    NetworkParamsUpdate,
+}
+
+/// Minimal metadata of a committed transaction
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct CompactTx {
+    /// transaction id
+    pub txid: TxId,
+    /// transaction type
+    pub tx_type: CompactTxType,
+    /// number of created outputs (zero for transactions that don't create UTXOs)
+    pub no_of_outputs: TxoSize,
+    /// staked state address in the public part of the transaction (if any)
+    pub staking_address: Option<StakedStateAddress>,
+    /// inputs spent in the public part of the transaction (only for deposit transactions,
+    /// inputs of transfer transactions are covered by the view key filter)
+    pub inputs: Vec<TxoPointer>,
+}
+
+impl From<&TxAux> for CompactTx {
+    fn from(txaux: &TxAux) -> Self {
+        let (tx_type, no_of_outputs, staking_address, inputs) = match txaux {
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx { no_of_outputs, .. }) => {
+                (CompactTxType::Transfer, *no_of_outputs, None, Vec::new())
+            }
+            TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { tx, .. }) => (
+                CompactTxType::DepositStake,
+                0,
+                Some(tx.to_staked_account),
+                tx.inputs.clone(),
+            ),
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx { no_of_outputs, .. }) => (
+                CompactTxType::WithdrawUnbondedStake,
+                *no_of_outputs,
+                None,
+                Vec::new(),
+            ),
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => (
+                CompactTxType::UnbondStake,
+                0,
+                Some(tx.from_staked_account),
+                Vec::new(),
+            ),
+            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, _)) => {
+                (CompactTxType::Unjail, 0, Some(tx.address), Vec::new())
+            }
+            TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, _)) => {
+                (CompactTxType::NodeJoin, 0, Some(tx.address), Vec::new())
+            }
+            TxAux::PublicTx(TxPublicAux::NetworkParamsUpdateTx(..)) => {
+                (CompactTxType::NetworkParamsUpdate, 0, None, Vec::new())
+            }
+            TxAux::MLSHandshake(_) => (CompactTxType::MLSHandshake, 0, None, Vec::new()),
+        };
+        CompactTx {
+            txid: txaux.tx_id(),
+            tx_type,
+            no_of_outputs,
+            staking_address,
+            inputs,
+        }
+    }
+}
+
+/// Compact representation of a committed block served to wallets,
+/// so that they can decide whether the full block needs to be fetched
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct CompactBlock {
+    /// block height
+    pub height: BlockHeight,
+    /// the resulting app hash after this block
+    pub app_hash: H256,
+    /// raw block filter of view keys (if there were any enclave transactions)
+    pub block_filter: Option<Vec<u8>>,
+    /// valid transactions in this block (in the committed order)
+    pub transactions: Vec<CompactTx>,
+}
+
+impl CompactBlock {
+    /// creates a compact block out of transactions delivered in a block
+    pub fn new(
+        height: BlockHeight,
+        app_hash: H256,
+        block_filter: Option<Vec<u8>>,
+        delivered_txs: &[TxAux],
+    ) -> Self {
+        CompactBlock {
+            height,
+            app_hash,
+            block_filter,
+            transactions: delivered_txs.iter().map(CompactTx::from).collect(),
+        }
+    }
+
+    /// ids of valid transactions in this block
+    pub fn txids(&self) -> Vec<TxId> {
+        self.transactions.iter().map(|tx| tx.txid).collect()
+    }
+
+    /// true if there are no transactions in this block
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+}
@@ -1,7 +1,14 @@
 /// data types related to staked state operations
 pub mod account;
+/// compact block representation served to wallets
+pub mod compact_block;
+/// proofs of staked states against the root of the staking trie
+#[cfg(feature = "jellyfish")]
+pub mod proof;
 /// data types related to working with Tendermint
 pub mod tendermint;
+/// execution results of delivered transactions
+pub mod tx_result;
 /// data types related to council node operations in staked state (nodejoin and unjail)
 pub mod validator;
 
@@ -1,3 +1,4 @@
+use crate::common::EnumTags;
 use crate::init::coin::Coin;
 use crate::init::MAX_COIN_DECIMALS;
 use parity_scale_codec::{Decode, Encode, EncodeLike, Error, Input, Output};
@@ -167,11 +168,21 @@ pub enum TendermintValidatorPubKey {
     // TODO: some other schemes when they are added in TM?
 }
 
+impl TendermintValidatorPubKey {
+    /// tag of `TendermintValidatorPubKey::Ed25519` (stable)
+    pub const ED25519_TAG: u8 = 0;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 1,
+        invalid_tag: "No such variant in enum TendermintValidatorPubKey",
+    };
+}
+
 impl Encode for TendermintValidatorPubKey {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         match *self {
             TendermintValidatorPubKey::Ed25519(ref key) => {
-                dest.push_byte(0);
+                dest.push_byte(TendermintValidatorPubKey::ED25519_TAG);
                 dest.push(key);
             }
         }
@@ -188,11 +199,11 @@ impl Decode for TendermintValidatorPubKey {
     fn decode<DecIn: Input>(input: &mut DecIn) -> core::result::Result<Self, Error> {
         let tag = input.read_byte()?;
         match tag {
-            0 => {
+            TendermintValidatorPubKey::ED25519_TAG => {
                 let key: [u8; PUBLIC_KEY_SIZE] = Decode::decode(input)?;
                 Ok(TendermintValidatorPubKey::Ed25519(key))
             }
-            _ => Err("No such variant in enum TendermintValidatorPubKey".into()),
+            tag => Err(TendermintValidatorPubKey::TAGS.unknown_tag(tag)),
         }
     }
 }
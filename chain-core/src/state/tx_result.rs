@@ -0,0 +1,48 @@
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::{String, Vec};
+
+use crate::state::tendermint::BlockHeight;
+use crate::tx::fee::Fee;
+
+/// Key-value attribute of a transaction event
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct TxEventAttribute {
+    /// attribute key
+    pub key: Vec<u8>,
+    /// attribute value
+    pub value: Vec<u8>,
+}
+
+/// Event emitted when executing a transaction
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct TxEvent {
+    /// event type
+    pub event_type: String,
+    /// event attributes
+    pub attributes: Vec<TxEventAttribute>,
+}
+
+/// Execution result of a transaction delivered in a block
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode, Serialize, Deserialize)]
+pub struct TxResult {
+    /// response code of DeliverTx (0 if the transaction was applied)
+    pub code: u32,
+    /// log of DeliverTx (the error message if the transaction was rejected)
+    pub log: String,
+    /// fee charged for the transaction (zero if the transaction was rejected)
+    pub fee: Fee,
+    /// events emitted by the transaction
+    pub events: Vec<TxEvent>,
+    /// height of the block which included the transaction
+    pub height: BlockHeight,
+    /// index of the transaction in the block
+    pub index: u32,
+}
+
+impl TxResult {
+    /// returns true if the transaction was applied to the state
+    pub fn is_ok(&self) -> bool {
+        self.code == 0
+    }
+}
@@ -1,5 +1,7 @@
 mod nodejoin;
+mod params_update;
 mod unjail;
 
 pub use nodejoin::NodeJoinRequestTx;
+pub use params_update::NetworkParamsUpdateTx;
 pub use unjail::UnjailTx;
@@ -0,0 +1,92 @@
+use crate::init::params::ScheduledParametersUpdate;
+use crate::state::account::{Nonce, StakedStateOpAttributes};
+#[cfg(feature = "new-txid")]
+use crate::tx::TaggedTransaction;
+#[cfg(not(feature = "new-txid"))]
+use crate::tx::TransactionId;
+use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// Schedules an update of the jailing and slashing network parameters at a future block height.
+///
+/// tx-validation should check that:
+/// - the nonce is the number of updates accepted so far (so it can't be replayed)
+/// - the effective height is after the current block
+/// - the new parameters are valid
+/// - the witnesses are from distinct validators in the current validator set
+///   holding more than 2/3 of its voting power
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct NetworkParamsUpdateTx {
+    /// the new parameters and the height they take effect at
+    pub update: ScheduledParametersUpdate,
+    /// the number of network parameters updates accepted before this one
+    pub nonce: Nonce,
+    /// the versioning and network identifier
+    pub attributes: StakedStateOpAttributes,
+}
+
+impl Decode for NetworkParamsUpdateTx {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let update = ScheduledParametersUpdate::decode(input)?;
+        let nonce = Nonce::decode(input)?;
+        let attributes = StakedStateOpAttributes::decode(input)?;
+
+        Ok(NetworkParamsUpdateTx {
+            update,
+            nonce,
+            attributes,
+        })
+    }
+}
+
+impl Encode for NetworkParamsUpdateTx {
+    fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
+        dest.push(&self.update);
+        dest.push(&self.nonce);
+        dest.push(&self.attributes);
+    }
+
+    fn size_hint(&self) -> usize {
+        self.update.size_hint() + self.nonce.size_hint() + self.attributes.size_hint()
+    }
+}
+
+#[cfg(not(feature = "new-txid"))]
+impl TransactionId for NetworkParamsUpdateTx {}
+
+#[cfg(feature = "new-txid")]
+impl From<NetworkParamsUpdateTx> for TaggedTransaction {
+    fn from(tx: NetworkParamsUpdateTx) -> TaggedTransaction {
+        TaggedTransaction::NetworkParamsUpdateTx(tx)
+    }
+}
+
+impl NetworkParamsUpdateTx {
+    /// constructs a new network parameters update transaction from the provided components
+    #[inline]
+    pub fn new(
+        update: ScheduledParametersUpdate,
+        nonce: Nonce,
+        attributes: StakedStateOpAttributes,
+    ) -> Self {
+        Self {
+            update,
+            nonce,
+            attributes,
+        }
+    }
+}
+
+impl fmt::Display for NetworkParamsUpdateTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "network parameters update (nonce {}) at height {}: {:?}",
+            self.nonce, self.update.effective_height, self.update.punishment_params
+        )?;
+        write!(f, "")
+    }
+}
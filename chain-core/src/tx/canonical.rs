@@ -0,0 +1,73 @@
+//! Canonical ordering of transaction parts, so that independently constructed copies of the
+//! same logical transaction (e.g. by co-signers) produce the same transaction id
+//!
+//! The rules are:
+//! 1. inputs are sorted by the referenced transaction id (compared bytewise) and then by the
+//!    output index (like BIP-69), which also rules out duplicate inputs
+//! 2. witnesses follow the order of the inputs they sign (the n-th witness belongs to the n-th
+//!    input), so they are reordered together with the inputs
+//! 3. public keys of multi-signature signers are sorted before they are combined into one
+//!    (aggregated) key, and the combined keys of an address are sorted before building its tree
+//!
+//! Outputs keep the order chosen by the creator of the transaction.
+use crate::tx::data::input::TxoPointer;
+
+/// Sorts items carrying an input (e.g. an input together with its witness or spent output) into
+/// canonical input order
+pub fn sort_inputs<T, F>(items: &mut [T], input: F)
+where
+    F: Fn(&T) -> &TxoPointer,
+{
+    items.sort_by(|a, b| input(a).cmp(input(b)));
+}
+
+/// Checks if inputs are in canonical order (strictly ascending, i.e. without duplicates)
+pub fn is_canonical_input_order(inputs: &[TxoPointer]) -> bool {
+    inputs.windows(2).all(|pair| pair[0] < pair[1])
+}
+
+/// Sorts public keys of multi-signature signers into canonical order before combining them
+#[inline]
+pub fn sort_signers<T: Ord>(public_keys: &mut [T]) {
+    public_keys.sort();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_canonical_input_order() {
+        let mut inputs = vec![
+            (TxoPointer::new([2; 32], 0), "c"),
+            (TxoPointer::new([1; 32], 1), "b"),
+            (TxoPointer::new([1; 32], 0), "a"),
+        ];
+        let mut reversed = inputs.iter().rev().cloned().collect::<Vec<_>>();
+
+        sort_inputs(&mut inputs, |(input, _)| input);
+        sort_inputs(&mut reversed, |(input, _)| input);
+        assert_eq!(inputs, reversed);
+        assert_eq!(
+            vec!["a", "b", "c"],
+            inputs
+                .iter()
+                .map(|(_, witness)| *witness)
+                .collect::<Vec<_>>()
+        );
+
+        let pointers = inputs
+            .into_iter()
+            .map(|(input, _)| input)
+            .collect::<Vec<_>>();
+        assert!(is_canonical_input_order(&pointers));
+        assert!(!is_canonical_input_order(&[
+            pointers[1].clone(),
+            pointers[0].clone()
+        ]));
+        assert!(!is_canonical_input_order(&[
+            pointers[0].clone(),
+            pointers[0].clone()
+        ]));
+    }
+}
@@ -8,8 +8,9 @@ use secp256k1::key::PublicKey;
 use serde::de;
 
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use subtle::{Choice, ConstantTimeEq};
 
-use crate::common::H264;
+use crate::common::{EnumTags, H264};
 
 /// What can be accessed in TX (enforced by enclave code in HW)
 /// Initial schema will only allow access to all TX data,
@@ -32,17 +33,27 @@ impl Decode for TxAccess {
         let tag = input.read_byte()?;
         // NOTE: tag 1 likely reserved for txout restriction
         match tag {
-            0 => Ok(TxAccess::AllData),
-            _ => Err("No such variant in enum TxAccess".into()),
+            TxAccess::ALL_DATA_TAG => Ok(TxAccess::AllData),
+            tag => Err(TxAccess::TAGS.unknown_tag(tag)),
         }
     }
 }
 
+impl TxAccess {
+    /// tag of `TxAccess::AllData` (stable)
+    pub const ALL_DATA_TAG: u8 = 0;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 1,
+        invalid_tag: "No such variant in enum TxAccess",
+    };
+}
+
 impl Encode for TxAccess {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         match *self {
             TxAccess::AllData => {
-                dest.push_byte(0);
+                dest.push_byte(TxAccess::ALL_DATA_TAG);
             }
         }
     }
@@ -128,3 +139,53 @@ impl TxAccessPolicy {
         TxAccessPolicy { view_key, access }
     }
 }
+
+/// Returns what the view key can access in a transaction with the given policies
+/// (None if it isn't allowed to access anything).
+/// The view keys are compared in constant time and all the policies are checked,
+/// so that the timing doesn't reveal which (or whether any) policy matched.
+pub fn allowed_access(allowed_views: &[TxAccessPolicy], view_key: &PublicKey) -> Option<TxAccess> {
+    let view_key = view_key.serialize();
+    let mut all_data = Choice::from(0);
+    for policy in allowed_views.iter() {
+        let matched = policy.view_key.serialize()[..].ct_eq(&view_key[..]);
+        match policy.access {
+            TxAccess::AllData => all_data |= matched,
+        }
+    }
+    if bool::from(all_data) {
+        Some(TxAccess::AllData)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secp256k1::key::SecretKey;
+
+    fn view_key(seed: u8) -> PublicKey {
+        let secp = secp256k1::SECP256K1;
+        let secret_key = SecretKey::from_slice(&[seed; 32]).expect("valid secret key");
+        PublicKey::from_secret_key(&secp, &secret_key)
+    }
+
+    #[test]
+    fn check_allowed_access() {
+        let policies = vec![
+            TxAccessPolicy::new(view_key(1), TxAccess::AllData),
+            TxAccessPolicy::new(view_key(2), TxAccess::AllData),
+        ];
+        assert_eq!(
+            allowed_access(&policies, &view_key(1)),
+            Some(TxAccess::AllData)
+        );
+        assert_eq!(
+            allowed_access(&policies, &view_key(2)),
+            Some(TxAccess::AllData)
+        );
+        assert_eq!(allowed_access(&policies, &view_key(3)), None);
+        assert_eq!(allowed_access(&[], &view_key(1)), None);
+    }
+}
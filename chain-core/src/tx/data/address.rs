@@ -6,13 +6,15 @@ use std::fmt;
 
 use std::str::FromStr;
 
-use crate::common::H256;
+use crate::common::{EnumTags, H256, HASH_SIZE_256};
 
 use crate::init::address::{CroAddress, CroAddressError};
 
-use bech32::{self, u5, FromBase32, ToBase32};
+use crate::init::address_codec::{
+    get_address_format_params_from_network, AddressCodec, AddressCodecRegistry,
+};
 
-use crate::init::network::{get_bech32_human_part_from_network, get_network, Network};
+use crate::init::network::{get_network, Network};
 
 type TreeRoot = H256;
 
@@ -27,11 +29,21 @@ pub enum ExtendedAddr {
     OrTree(TreeRoot),
 }
 
+impl ExtendedAddr {
+    /// tag of `ExtendedAddr::OrTree` (stable)
+    pub const OR_TREE_TAG: u8 = 0;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 1,
+        invalid_tag: "No such variant in enum ExtendedAddr",
+    };
+}
+
 impl Encode for ExtendedAddr {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         match *self {
             ExtendedAddr::OrTree(ref aa) => {
-                dest.push_byte(0);
+                dest.push_byte(ExtendedAddr::OR_TREE_TAG);
                 dest.push(aa);
             }
         }
@@ -50,49 +62,44 @@ impl Decode for ExtendedAddr {
         // NOTE: tag 1 may be used for other address types -- e.g. one to denote
         // requiring a different witness type (leaf may be a combination of root + timelock)
         match tag {
-            0 => Ok(ExtendedAddr::OrTree({
+            ExtendedAddr::OR_TREE_TAG => Ok(ExtendedAddr::OrTree({
                 let address: TreeRoot = Decode::decode(input)?;
                 address
             })),
-            _ => Err("No such variant in enum ExtendedAddr".into()),
+            tag => Err(ExtendedAddr::TAGS.unknown_tag(tag)),
         }
     }
 }
 
+impl ExtendedAddr {
+    fn from_payload(payload: &[u8]) -> Result<Self, CroAddressError> {
+        if payload.len() != HASH_SIZE_256 {
+            return Err(CroAddressError::ConvertError);
+        }
+        let mut tree_root_hash: TreeRoot = [0 as u8; HASH_SIZE_256];
+        tree_root_hash.copy_from_slice(payload);
+        Ok(ExtendedAddr::OrTree(tree_root_hash))
+    }
+}
+
 impl CroAddress<ExtendedAddr> for ExtendedAddr {
     fn to_cro(&self, network: Network) -> Result<String, CroAddressError> {
+        let params = get_address_format_params_from_network(network);
         match self {
-            ExtendedAddr::OrTree(hash) => {
-                let checked_data: Vec<u5> = hash.to_vec().to_base32();
-                let encoded =
-                    bech32::encode(get_bech32_human_part_from_network(network), checked_data)
-                        .expect("bech32 encoding error");
-                Ok(encoded)
-            }
+            ExtendedAddr::OrTree(hash) => params.encode.encode(hash),
         }
     }
 
     fn from_cro(encoded_addr: &str, network: Network) -> Result<Self, CroAddressError> {
-        if !encoded_addr.starts_with(get_bech32_human_part_from_network(network)) {
-            return Err(CroAddressError::InvalidNetwork);
-        }
-
-        bech32::decode(encoded_addr)
-            .map_err(|e| CroAddressError::Bech32Error(e.to_string()))
-            .and_then(|decoded| {
-                Vec::from_base32(&decoded.1).map_err(|_e| CroAddressError::ConvertError)
-            })
-            .map(|hash| {
-                let mut tree_root_hash: TreeRoot = [0 as u8; 32];
-                tree_root_hash.copy_from_slice(&hash.as_slice());
-                ExtendedAddr::OrTree(tree_root_hash)
-            })
+        AddressCodecRegistry::from_params(&get_address_format_params_from_network(network))
+            .decode(encoded_addr)
+            .and_then(|payload| ExtendedAddr::from_payload(&payload))
     }
 }
 
 impl fmt::Display for ExtendedAddr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.to_cro(get_network()).unwrap())
+        write!(f, "{}", self.to_cro(get_network()).map_err(|_| fmt::Error)?)
     }
 }
 
@@ -143,6 +150,47 @@ mod test {
         assert_eq!(extended_addr_from_hash, extended_addr_from_str);
     }
 
+    #[test]
+    fn should_match_baseline_addresses_of_each_network() {
+        use crate::init::network::get_bech32_human_part_from_network;
+
+        let mut tree_root_hash = [0; 32];
+        tree_root_hash.copy_from_slice(
+            &hex::decode("0e7c045110b8dbf29765047380898919c5cb56f400112233445566778899aabb")
+                .unwrap(),
+        );
+        let extended_addr = ExtendedAddr::OrTree(tree_root_hash);
+        let golden = [
+            (
+                Network::Mainnet,
+                "cro",
+                "cro1pe7qg5gshrdl99m9q3ecpzvfr8zuk4h5qqgjyv6y24n80zye42asjwegvg",
+            ),
+            (
+                Network::Testnet,
+                "tcro",
+                "tcro1pe7qg5gshrdl99m9q3ecpzvfr8zuk4h5qqgjyv6y24n80zye42aslf77dm",
+            ),
+            (
+                Network::Devnet,
+                "dcro",
+                "dcro1pe7qg5gshrdl99m9q3ecpzvfr8zuk4h5qqgjyv6y24n80zye42as88x8tg",
+            ),
+        ];
+        for (network, hrp, address) in golden.iter() {
+            assert_eq!(get_bech32_human_part_from_network(*network), *hrp);
+            assert_eq!(extended_addr.to_cro(*network).unwrap(), *address);
+            assert_eq!(
+                ExtendedAddr::from_cro(address, *network).unwrap(),
+                extended_addr
+            );
+        }
+
+        let displayed = extended_addr.to_string();
+        assert_eq!(displayed, extended_addr.to_cro(get_network()).unwrap());
+        assert_eq!(ExtendedAddr::from_str(&displayed).unwrap(), extended_addr);
+    }
+
     mod from_cro {
         use super::*;
 
@@ -33,6 +33,12 @@ use crate::tx::TransactionId;
 /// So, maximum transaction size (34 * 64) + (50 * 64) + 2688 = 8064
 const MAX_TX_SIZE: usize = 8100; // 8100 bytes
 
+/// Maximum number of outputs of a transfer transaction assumed by `MAX_TX_SIZE`
+pub const MAX_TX_OUTPUTS: usize = 64;
+
+/// Maximum number of allowed view keys of a transaction assumed by `MAX_TX_SIZE`
+pub const MAX_ALLOWED_VIEW_KEYS: usize = 64;
+
 /// Key to identify the used TXID hash function, e.g. in ProofOps.
 pub const TXID_HASH_ID: &[u8; 6] = b"blake3";
 
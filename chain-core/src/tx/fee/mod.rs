@@ -8,6 +8,7 @@ use crate::tx::TxAux;
 use parity_scale_codec::{Decode, Encode};
 
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 use std::num::ParseIntError;
 use std::ops::{Add, Div, Mul};
 use std::prelude::v1::Vec;
@@ -38,10 +39,36 @@ impl Fee {
     }
 }
 
+/// Rounding of fixed decimal operations whose exact result has more than 3 fractional digits
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Rounding {
+    /// round towards zero (truncate)
+    Down,
+    /// round away from zero (ceiling)
+    Up,
+}
+
+/// divides with given rounding, `denominator` must not be zero
+#[inline]
+fn div_rounded(numerator: u128, denominator: u128, rounding: Rounding) -> u128 {
+    let quotient = numerator / denominator;
+    if rounding == Rounding::Up && numerator % denominator != 0 {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
+
 /// Represents a 3 digit fixed decimal
-/// TODO: overflow checks in Cargo?
-/// [profile.release]
-/// overflow-checks = true
+///
+/// The value is stored as an integer number of thousandths (`i.fff` is `i * 1000 + fff`).
+///
+/// The `checked_*` methods are exact whenever the result is representable: intermediate results
+/// are computed in `u128`, the only rounding happens once at the end in the requested direction,
+/// and `None` is returned if the result doesn't fit into `u64` thousandths.
+/// Fee computation relies only on these methods.
+///
+/// The operator implementations (`+`, `*`, `/`) truncate and don't check for overflow.
 #[derive(
     PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Encode, Decode, Serialize, Deserialize,
 )]
@@ -76,21 +103,58 @@ impl Milli {
     }
 
     /// returns the ceiled integer
-    /// (used in tests)
+    #[inline]
     pub fn to_integral(self) -> u64 {
-        // note that we want the ceiling
-        if self.0 % 1000 == 0 {
-            self.0 / 1000
-        } else {
-            (self.0 / 1000) + 1
-        }
+        self.to_integral_rounded(Rounding::Up)
     }
 
     /// returns the floored integer
     /// (used in tests)
     #[inline]
     pub fn to_integral_trunc(self) -> u64 {
-        self.0 / 1000
+        self.to_integral_rounded(Rounding::Down)
+    }
+
+    /// returns the integer rounded in given direction
+    #[inline]
+    pub fn to_integral_rounded(self, rounding: Rounding) -> u64 {
+        // the result is at most `u64::MAX / 1000 + 1`
+        div_rounded(u128::from(self.0), 1000, rounding) as u64
+    }
+
+    /// exact addition, returns `None` on overflow
+    #[inline]
+    pub fn checked_add(self, other: Milli) -> Option<Milli> {
+        self.0.checked_add(other.0).map(Milli)
+    }
+
+    /// exact multiplication by an integer, returns `None` on overflow
+    #[inline]
+    pub fn checked_mul_integral(self, n: u64) -> Option<Milli> {
+        self.0.checked_mul(n).map(Milli)
+    }
+
+    /// multiplication rounded to 3 fractional digits in given direction,
+    /// returns `None` on overflow
+    pub fn checked_mul(self, other: Milli, rounding: Rounding) -> Option<Milli> {
+        // can't overflow: (2^64 - 1)^2 < 2^128
+        let product = u128::from(self.0) * u128::from(other.0);
+        u64::try_from(div_rounded(product, 1000, rounding))
+            .ok()
+            .map(Milli)
+    }
+
+    /// division rounded to 3 fractional digits in given direction,
+    /// returns `None` on overflow or division by zero
+    pub fn checked_div(self, rhs: Milli, rounding: Rounding) -> Option<Milli> {
+        if rhs.0 == 0 {
+            return None;
+        }
+        // can't overflow: (2^64 - 1) * 1000 < 2^128
+        let numerator = u128::from(self.0) * 1000;
+        u64::try_from(div_rounded(numerator, u128::from(rhs.0), rounding))
+            .ok()
+            .map(Milli)
     }
 
     /// returns the encoded fixed decimal number
@@ -225,10 +289,18 @@ impl LinearFee {
     }
 
     /// calculates the fee based on the provided transaction size
+    ///
+    /// The fee is `constant + coefficient * sz` computed exactly and then rounded up to the base
+    /// unit, so chain validation and client transaction builders (which both go through this
+    /// function) always agree on the result.
+    /// Returns `CoinError::Overflow` if the fee can't be represented.
     pub fn estimate(&self, sz: usize) -> Result<Fee, CoinError> {
-        let msz = Milli::integral(sz as u64).map_err(|_| CoinError::Overflow)?;
-        let fee = self.constant + self.coefficient * msz;
-        let coin = Coin::new(fee.to_integral())?;
+        let fee = u64::try_from(sz)
+            .ok()
+            .and_then(|sz| self.coefficient.checked_mul_integral(sz))
+            .and_then(|fee| fee.checked_add(self.constant))
+            .ok_or(CoinError::Overflow)?;
+        let coin = Coin::new(fee.to_integral_rounded(Rounding::Up))?;
         Ok(Fee(coin))
     }
 }
@@ -338,7 +410,134 @@ mod test {
         assert!(t.is_err());
     }
 
+    /// rational reference `numerator / denominator` rounded to an integer in given direction
+    fn reference_rounded(numerator: u128, denominator: u128, rounding: Rounding) -> u128 {
+        let floor = numerator / denominator;
+        let exact = floor * denominator == numerator;
+        match rounding {
+            Rounding::Down => floor,
+            Rounding::Up if exact => floor,
+            Rounding::Up => floor + 1,
+        }
+    }
+
+    fn to_milli(v: u128) -> Option<Milli> {
+        if v > u128::from(std::u64::MAX) {
+            None
+        } else {
+            Some(Milli::from_millis(v as u64))
+        }
+    }
+
+    fn check_checked_mul(n1: u64, n2: u64, rounding: Rounding) -> bool {
+        // (n1 / 1000) * (n2 / 1000) in thousandths is n1 * n2 / 1000
+        let expected = to_milli(reference_rounded(
+            u128::from(n1) * u128::from(n2),
+            1000,
+            rounding,
+        ));
+        Milli::from_millis(n1).checked_mul(Milli::from_millis(n2), rounding) == expected
+    }
+
+    fn check_checked_div(n1: u64, n2: u64, rounding: Rounding) -> bool {
+        let result = Milli::from_millis(n1).checked_div(Milli::from_millis(n2), rounding);
+        if n2 == 0 {
+            return result.is_none();
+        }
+        // (n1 / 1000) / (n2 / 1000) in thousandths is n1 * 1000 / n2
+        let expected = to_milli(reference_rounded(
+            u128::from(n1) * 1000,
+            u128::from(n2),
+            rounding,
+        ));
+        result == expected
+    }
+
+    fn check_estimate(constant: u64, coefficient: u64, sz: u32) -> bool {
+        let fee = LinearFee::new(
+            Milli::from_millis(constant),
+            Milli::from_millis(coefficient),
+        );
+        let millis = u128::from(constant) + u128::from(coefficient) * u128::from(sz);
+        let expected = reference_rounded(millis, 1000, Rounding::Up);
+        match fee.estimate(sz as usize) {
+            Ok(fee) => {
+                millis <= u128::from(std::u64::MAX)
+                    && u128::from(u64::from(fee.to_coin())) == expected
+            }
+            Err(_) => {
+                millis > u128::from(std::u64::MAX) || expected > u128::from(u64::from(Coin::max()))
+            }
+        }
+    }
+
+    #[test]
+    fn check_checked_arithmetic() {
+        let one = Milli::integral(1).unwrap();
+        let third = Milli::new(0, 333);
+        assert_eq!(
+            Some(Milli::new(0, 333)),
+            one.checked_div(Milli::new(3, 0), Rounding::Down)
+        );
+        assert_eq!(
+            Some(Milli::new(0, 334)),
+            one.checked_div(Milli::new(3, 0), Rounding::Up)
+        );
+        assert_eq!(
+            Some(Milli::new(0, 110)),
+            third.checked_mul(third, Rounding::Down)
+        );
+        assert_eq!(
+            Some(Milli::new(0, 111)),
+            third.checked_mul(third, Rounding::Up)
+        );
+        assert_eq!(None, one.checked_div(Milli::from_millis(0), Rounding::Down));
+        assert_eq!(
+            None,
+            Milli::from_millis(std::u64::MAX).checked_add(Milli::from_millis(1))
+        );
+        assert_eq!(
+            None,
+            Milli::from_millis(std::u64::MAX).checked_mul(Milli::new(2, 0), Rounding::Down)
+        );
+        assert_eq!(2, Milli::new(1, 1).to_integral_rounded(Rounding::Up));
+        assert_eq!(1, Milli::new(1, 1).to_integral_rounded(Rounding::Down));
+    }
+
+    #[test]
+    fn check_estimate_rounding() {
+        let fee = LinearFee::new(Milli::new(1, 25), Milli::new(0, 1));
+        // 1.025 + 0.001 * 975 = 2.000
+        assert_eq!(Coin::new(2).unwrap(), fee.estimate(975).unwrap().to_coin());
+        // 1.025 + 0.001 * 976 = 2.001
+        assert_eq!(Coin::new(3).unwrap(), fee.estimate(976).unwrap().to_coin());
+
+        let fee = LinearFee::new(Milli::from_millis(std::u64::MAX), Milli::new(0, 1));
+        assert!(fee.estimate(1).is_err());
+    }
+
     quickcheck! {
+        fn prop_checked_mul(n1: u64, n2: u64) -> bool {
+            check_checked_mul(n1, n2, Rounding::Down) && check_checked_mul(n1, n2, Rounding::Up)
+        }
+
+        fn prop_checked_div(n1: u64, n2: u64) -> bool {
+            check_checked_div(n1, n2, Rounding::Down) && check_checked_div(n1, n2, Rounding::Up)
+        }
+
+        fn prop_checked_add(n1: u64, n2: u64) -> bool {
+            let expected = to_milli(u128::from(n1) + u128::from(n2));
+            Milli::from_millis(n1).checked_add(Milli::from_millis(n2)) == expected
+        }
+
+        fn prop_estimate(constant: u64, coefficient: u64, sz: u32) -> bool {
+            check_estimate(constant, coefficient, sz)
+        }
+
+        fn prop_estimate_small(constant: u16, coefficient: u16, sz: u16) -> bool {
+            check_estimate(u64::from(constant), u64::from(coefficient), u32::from(sz))
+        }
+
         fn prop_milli_add(n1: u64, n2: u64) -> bool {
             test_milli_add_eq(n1, n2)
         }
@@ -1,5 +1,7 @@
 use std::prelude::v1::Vec;
 
+/// Canonical ordering of inputs, witnesses and multi-signature keys
+pub mod canonical;
 /// Transaction internal structure
 pub mod data;
 /// Transaction fee calculation
@@ -13,13 +15,14 @@ use parity_scale_codec::{Decode, Encode, Error, Input, Output};
 
 use self::data::Tx;
 use self::witness::TxWitness;
+use crate::common::EnumTags;
 use crate::mls::MLSHandshakeAux;
 use crate::state::account::{
     DepositBondTx, StakedStateOpAttributes, StakedStateOpWitness, UnbondTx, UnjailTx,
     WithdrawUnbondedTx,
 };
 use crate::state::tendermint::BlockHeight;
-use crate::state::validator::NodeJoinRequestTx;
+use crate::state::validator::{NetworkParamsUpdateTx, NodeJoinRequestTx};
 use crate::tx::data::TxId;
 use aead::Payload;
 use data::input::{TxoPointer, TxoSize};
@@ -32,8 +35,10 @@ pub const TX_AUX_SIZE: usize = 1024 * 60; // 60 KB
 #[derive(Encode, Decode, Clone)]
 pub enum TxWithOutputs {
     /// normal transfer
+    #[codec(index = "0")]
     Transfer(Tx),
     /// withdrawing unbonded amount from a staked state
+    #[codec(index = "1")]
     StakeWithdraw(WithdrawUnbondedTx),
 }
 
@@ -66,20 +71,34 @@ pub enum PlainTxAux {
     WithdrawUnbondedStakeTx(WithdrawUnbondedTx),
 }
 
+impl PlainTxAux {
+    /// tag of `PlainTxAux::TransferTx` (stable)
+    pub const TRANSFER_TAG: u8 = 0;
+    /// tag of `PlainTxAux::DepositStakeTx` (stable)
+    pub const DEPOSIT_STAKE_TAG: u8 = 1;
+    /// tag of `PlainTxAux::WithdrawUnbondedStakeTx` (stable)
+    pub const WITHDRAW_UNBONDED_STAKE_TAG: u8 = 2;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 3,
+        invalid_tag: "No such variant in enum PlainTxAux",
+    };
+}
+
 impl Encode for PlainTxAux {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         match *self {
             PlainTxAux::TransferTx(ref tx, ref witness) => {
-                dest.push_byte(0);
+                dest.push_byte(PlainTxAux::TRANSFER_TAG);
                 dest.push(tx);
                 dest.push(witness);
             }
             PlainTxAux::DepositStakeTx(ref witness) => {
-                dest.push_byte(1);
+                dest.push_byte(PlainTxAux::DEPOSIT_STAKE_TAG);
                 dest.push(witness);
             }
             PlainTxAux::WithdrawUnbondedStakeTx(ref tx) => {
-                dest.push_byte(2);
+                dest.push_byte(PlainTxAux::WITHDRAW_UNBONDED_STAKE_TAG);
                 dest.push(tx);
             }
         }
@@ -98,20 +117,20 @@ impl Decode for PlainTxAux {
     fn decode<DecIn: Input>(input: &mut DecIn) -> Result<Self, Error> {
         let tag = input.read_byte()?;
         match tag {
-            0 => {
+            PlainTxAux::TRANSFER_TAG => {
                 let tx = Tx::decode(input)?;
                 let witness = TxWitness::decode(input)?;
                 Ok(PlainTxAux::TransferTx(tx, witness))
             }
-            1 => {
+            PlainTxAux::DEPOSIT_STAKE_TAG => {
                 let witness = TxWitness::decode(input)?;
                 Ok(PlainTxAux::DepositStakeTx(witness))
             }
-            2 => {
+            PlainTxAux::WITHDRAW_UNBONDED_STAKE_TAG => {
                 let tx = WithdrawUnbondedTx::decode(input)?;
                 Ok(PlainTxAux::WithdrawUnbondedStakeTx(tx))
             }
-            _ => Err("No such variant in enum PlainTxAux".into()),
+            tag => Err(PlainTxAux::TAGS.unknown_tag(tag)),
         }
     }
 }
@@ -273,6 +292,20 @@ pub enum TxEnclaveAux {
     },
 }
 
+impl TxEnclaveAux {
+    /// tag of `TxEnclaveAux::TransferTx` (stable)
+    pub const TRANSFER_TAG: u8 = 0;
+    /// tag of `TxEnclaveAux::DepositStakeTx` (stable)
+    pub const DEPOSIT_STAKE_TAG: u8 = 1;
+    /// tag of `TxEnclaveAux::WithdrawUnbondedStakeTx` (stable)
+    pub const WITHDRAW_UNBONDED_STAKE_TAG: u8 = 2;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 3,
+        invalid_tag: "No such variant in enum TxEnclaveAux",
+    };
+}
+
 impl Encode for TxEnclaveAux {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         match *self {
@@ -281,7 +314,7 @@ impl Encode for TxEnclaveAux {
                 ref no_of_outputs,
                 ref payload,
             } => {
-                dest.push_byte(0);
+                dest.push_byte(TxEnclaveAux::TRANSFER_TAG);
                 dest.push(inputs);
                 dest.push(no_of_outputs);
                 dest.push(payload);
@@ -290,7 +323,7 @@ impl Encode for TxEnclaveAux {
                 ref tx,
                 ref payload,
             } => {
-                dest.push_byte(1);
+                dest.push_byte(TxEnclaveAux::DEPOSIT_STAKE_TAG);
                 dest.push(tx);
                 dest.push(payload);
             }
@@ -299,7 +332,7 @@ impl Encode for TxEnclaveAux {
                 ref witness,
                 ref payload,
             } => {
-                dest.push_byte(2);
+                dest.push_byte(TxEnclaveAux::WITHDRAW_UNBONDED_STAKE_TAG);
                 dest.push(no_of_outputs);
                 dest.push(witness);
                 dest.push(payload);
@@ -332,7 +365,7 @@ impl Decode for TxEnclaveAux {
         let tag = input.read_byte()?;
         // note: 3.. tags expected for TDBE tx (MLS messages)
         match tag {
-            0 => {
+            TxEnclaveAux::TRANSFER_TAG => {
                 let inputs: Vec<TxoPointer> = Vec::decode(input)?;
                 let no_of_outputs = TxoSize::decode(input)?;
                 let payload = TxObfuscated::decode(input)?;
@@ -342,12 +375,12 @@ impl Decode for TxEnclaveAux {
                     payload,
                 })
             }
-            1 => {
+            TxEnclaveAux::DEPOSIT_STAKE_TAG => {
                 let tx = DepositBondTx::decode(input)?;
                 let payload = TxObfuscated::decode(input)?;
                 Ok(TxEnclaveAux::DepositStakeTx { tx, payload })
             }
-            2 => {
+            TxEnclaveAux::WITHDRAW_UNBONDED_STAKE_TAG => {
                 let no_of_outputs = TxoSize::decode(input)?;
                 let witness = StakedStateOpWitness::decode(input)?;
                 let payload = TxObfuscated::decode(input)?;
@@ -357,7 +390,7 @@ impl Decode for TxEnclaveAux {
                     payload,
                 })
             }
-            _ => Err("No such variant in enum TxEnclaveAux".into()),
+            tag => Err(TxEnclaveAux::TAGS.unknown_tag(tag)),
         }
     }
 }
@@ -389,26 +422,49 @@ pub enum TxPublicAux {
     UnjailTx(UnjailTx, StakedStateOpWitness),
     /// Tx that updates a staked state with node (community or council node) details
     NodeJoinTx(NodeJoinRequestTx, StakedStateOpWitness),
+    /// Tx that schedules a network parameters update (witnesses of the council validators)
+    NetworkParamsUpdateTx(NetworkParamsUpdateTx, Vec<StakedStateOpWitness>),
+}
+
+impl TxPublicAux {
+    /// tag of `TxPublicAux::UnbondStakeTx` (stable)
+    pub const UNBOND_STAKE_TAG: u8 = 0;
+    /// tag of `TxPublicAux::UnjailTx` (stable)
+    pub const UNJAIL_TAG: u8 = 1;
+    /// tag of `TxPublicAux::NodeJoinTx` (stable)
+    pub const NODE_JOIN_TAG: u8 = 2;
+    /// tag of `TxPublicAux::NetworkParamsUpdateTx` (stable)
+    pub const NETWORK_PARAMS_UPDATE_TAG: u8 = 3;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 4,
+        invalid_tag: "No such variant in enum TxPublicAux",
+    };
 }
 
 impl Encode for TxPublicAux {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         match *self {
             TxPublicAux::UnbondStakeTx(ref tx, ref witness) => {
-                dest.push_byte(0);
+                dest.push_byte(TxPublicAux::UNBOND_STAKE_TAG);
                 dest.push(tx);
                 dest.push(witness);
             }
             TxPublicAux::UnjailTx(ref tx, ref witness) => {
-                dest.push_byte(1);
+                dest.push_byte(TxPublicAux::UNJAIL_TAG);
                 dest.push(tx);
                 dest.push(witness);
             }
             TxPublicAux::NodeJoinTx(ref tx, ref witness) => {
-                dest.push_byte(2);
+                dest.push_byte(TxPublicAux::NODE_JOIN_TAG);
                 dest.push(tx);
                 dest.push(witness);
             }
+            TxPublicAux::NetworkParamsUpdateTx(ref tx, ref witnesses) => {
+                dest.push_byte(TxPublicAux::NETWORK_PARAMS_UPDATE_TAG);
+                dest.push(tx);
+                dest.push(witnesses);
+            }
         }
     }
 
@@ -417,6 +473,9 @@ impl Encode for TxPublicAux {
             TxPublicAux::UnbondStakeTx(tx, witness) => tx.size_hint() + witness.size_hint(),
             TxPublicAux::UnjailTx(tx, witness) => tx.size_hint() + witness.size_hint(),
             TxPublicAux::NodeJoinTx(tx, witness) => tx.size_hint() + witness.size_hint(),
+            TxPublicAux::NetworkParamsUpdateTx(tx, witnesses) => {
+                tx.size_hint() + witnesses.size_hint()
+            }
         }
     }
 }
@@ -424,24 +483,29 @@ impl Encode for TxPublicAux {
 impl Decode for TxPublicAux {
     fn decode<DecIn: Input>(input: &mut DecIn) -> Result<Self, Error> {
         let tag = input.read_byte()?;
-        // note: 3.. tags reserved for other tx types (node metadata update etc.)
+        // note: 4.. tags reserved for other tx types (node metadata update etc.)
         match tag {
-            0 => {
+            TxPublicAux::UNBOND_STAKE_TAG => {
                 let tx = UnbondTx::decode(input)?;
                 let witness = StakedStateOpWitness::decode(input)?;
                 Ok(TxPublicAux::UnbondStakeTx(tx, witness))
             }
-            1 => {
+            TxPublicAux::UNJAIL_TAG => {
                 let tx = UnjailTx::decode(input)?;
                 let witness = StakedStateOpWitness::decode(input)?;
                 Ok(TxPublicAux::UnjailTx(tx, witness))
             }
-            2 => {
+            TxPublicAux::NODE_JOIN_TAG => {
                 let tx = NodeJoinRequestTx::decode(input)?;
                 let witness = StakedStateOpWitness::decode(input)?;
                 Ok(TxPublicAux::NodeJoinTx(tx, witness))
             }
-            _ => Err("No such variant in enum TxPublicAux".into()),
+            TxPublicAux::NETWORK_PARAMS_UPDATE_TAG => {
+                let tx = NetworkParamsUpdateTx::decode(input)?;
+                let witnesses = Vec::<StakedStateOpWitness>::decode(input)?;
+                Ok(TxPublicAux::NetworkParamsUpdateTx(tx, witnesses))
+            }
+            tag => Err(TxPublicAux::TAGS.unknown_tag(tag)),
         }
     }
 }
@@ -453,6 +517,7 @@ impl TxPublicAux {
             TxPublicAux::UnbondStakeTx(tx, _) => tx.id(),
             TxPublicAux::UnjailTx(tx, _) => tx.id(),
             TxPublicAux::NodeJoinTx(tx, _) => tx.id(),
+            TxPublicAux::NetworkParamsUpdateTx(tx, _) => tx.id(),
         }
     }
 
@@ -462,6 +527,7 @@ impl TxPublicAux {
             TxPublicAux::UnbondStakeTx(tx, _) => &tx.attributes,
             TxPublicAux::UnjailTx(tx, _) => &tx.attributes,
             TxPublicAux::NodeJoinTx(tx, _) => &tx.attributes,
+            TxPublicAux::NetworkParamsUpdateTx(tx, _) => &tx.attributes,
         }
     }
 
@@ -480,6 +546,8 @@ impl TxPublicAux {
 /// it can be a variant in ExtendedAddr + a corresponding witness type.
 /// (could be even to e.g. support a different signature scheme)
 /// - If the extension is a different behaviour, it'll be a new transaction type (possibly under enclave or public auxiliary type).
+/// - Tags of the existing variants never change, new variants take tags from the reserved range
+/// (see `EnumTags`), so older nodes reject them as payloads of a newer protocol version.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum TxAux {
     /// transactions that need to be processed inside TEE (or need TEE in their finalization)
@@ -490,19 +558,33 @@ pub enum TxAux {
     MLSHandshake(MLSHandshakeAux),
 }
 
+impl TxAux {
+    /// tag of `TxAux::EnclaveTx` (stable)
+    pub const ENCLAVE_TX_TAG: u8 = 0;
+    /// tag of `TxAux::PublicTx` (stable)
+    pub const PUBLIC_TX_TAG: u8 = 1;
+    /// tag of `TxAux::MLSHandshake` (stable)
+    pub const MLS_HANDSHAKE_TAG: u8 = 2;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 3,
+        invalid_tag: "No such variant in enum TxAux",
+    };
+}
+
 impl Encode for TxAux {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
         match *self {
             TxAux::EnclaveTx(ref tx) => {
-                dest.push_byte(0);
+                dest.push_byte(TxAux::ENCLAVE_TX_TAG);
                 dest.push(tx);
             }
             TxAux::PublicTx(ref tx) => {
-                dest.push_byte(1);
+                dest.push_byte(TxAux::PUBLIC_TX_TAG);
                 dest.push(tx);
             }
             TxAux::MLSHandshake(ref tx) => {
-                dest.push_byte(2);
+                dest.push_byte(TxAux::MLS_HANDSHAKE_TAG);
                 dest.push(tx);
             }
         }
@@ -528,10 +610,10 @@ impl Decode for TxAux {
         }
 
         match input.read_byte()? {
-            0 => Ok(TxAux::EnclaveTx(TxEnclaveAux::decode(input)?)),
-            1 => Ok(TxAux::PublicTx(TxPublicAux::decode(input)?)),
-            2 => Ok(TxAux::MLSHandshake(MLSHandshakeAux::decode(input)?)),
-            _ => Err("No such variant in enum TxAux".into()),
+            TxAux::ENCLAVE_TX_TAG => Ok(TxAux::EnclaveTx(TxEnclaveAux::decode(input)?)),
+            TxAux::PUBLIC_TX_TAG => Ok(TxAux::PublicTx(TxPublicAux::decode(input)?)),
+            TxAux::MLS_HANDSHAKE_TAG => Ok(TxAux::MLSHandshake(MLSHandshakeAux::decode(input)?)),
+            tag => Err(TxAux::TAGS.unknown_tag(tag)),
         }
     }
 }
@@ -573,23 +655,35 @@ pub trait TransactionId: Encode {
 #[derive(Encode)]
 pub enum TaggedTransaction {
     /// transfer transaction
+    #[codec(index = "0")]
     Transfer(Tx),
     /// deposit stake to bonded amount
+    #[codec(index = "1")]
     Deposit(DepositBondTx),
     /// withdraw unbonded amount
+    #[codec(index = "2")]
     Withdraw(WithdrawUnbondedTx),
     /// unbond stake
+    #[codec(index = "3")]
     UnbondStakeTx(UnbondTx),
     /// unjail request
+    #[codec(index = "4")]
     UnjailTx(UnjailTx),
     /// node join request
+    #[codec(index = "5")]
     NodeJoinTx(NodeJoinRequestTx),
     /// removal proposals + commit
+    #[codec(index = "6")]
     MLSRemoveCommitProposal(crate::mls::CommitRemoveTx),
     /// update proposal + commit
+    #[codec(index = "7")]
     MLSSelfUpdateProposal(crate::mls::SelfUpdateProposalTx),
     /// NACK
+    #[codec(index = "8")]
     MLSMsgNack(crate::mls::NackMsgTx),
+    /// network parameters update
+    #[codec(index = "9")]
+    NetworkParamsUpdateTx(NetworkParamsUpdateTx),
 }
 
 #[cfg(feature = "new-txid")]
@@ -652,6 +746,9 @@ impl fmt::Display for TxAux {
             TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, witness)) => {
                 display_tx_witness(f, tx, witness)
             }
+            TxAux::PublicTx(TxPublicAux::NetworkParamsUpdateTx(tx, witnesses)) => {
+                display_tx_witness(f, tx, witnesses)
+            }
             TxAux::MLSHandshake(_) => {
                 // FIXME
                 writeln!(f, "mls handshake")
@@ -663,8 +760,10 @@ impl fmt::Display for TxAux {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::common::MerkleTree;
+    use crate::common::{MerkleTree, FIRST_INVALID_TAG};
     use crate::init::coin::Coin;
+    use crate::state::account::{MLSInit, PunishmentKind};
+    use crate::state::compact_block::CompactTxType;
     use crate::tx::data::access::{TxAccess, TxAccessPolicy};
     use crate::tx::data::address::ExtendedAddr;
     use crate::tx::data::input::TxoPointer;
@@ -705,5 +804,38 @@ pub mod tests {
         let mut data: &[u8] = encoded.as_mut();
         let decoded = PlainTxAux::decode(&mut data).expect("decode tx aux");
         assert_eq!(txa, decoded);
+        assert_eq!(PlainTxAux::TRANSFER_TAG, encoded[0]);
+    }
+
+    #[test]
+    fn check_enum_tags() {
+        let reserved = "Variant reserved for a newer protocol version";
+        for tag in [TxAux::TAGS.variants, FIRST_INVALID_TAG - 1].iter() {
+            let error = TxAux::decode(&mut [*tag].as_ref()).unwrap_err();
+            assert_eq!(reserved, error.what());
+        }
+        let error = TxAux::decode(&mut [FIRST_INVALID_TAG].as_ref()).unwrap_err();
+        assert_eq!("No such variant in enum TxAux", error.what());
+
+        let error = TxEnclaveAux::decode(&mut [3u8].as_ref()).unwrap_err();
+        assert_eq!(reserved, error.what());
+        let error = TxPublicAux::decode(&mut [TxPublicAux::TAGS.variants].as_ref()).unwrap_err();
+        assert_eq!(reserved, error.what());
+        let error = PlainTxAux::decode(&mut [0xffu8].as_ref()).unwrap_err();
+        assert_eq!("No such variant in enum PlainTxAux", error.what());
+        let error = ExtendedAddr::decode(&mut [1u8].as_ref()).unwrap_err();
+        assert_eq!(reserved, error.what());
+        let error = TxInWitness::decode(&mut [0x80u8].as_ref()).unwrap_err();
+        assert_eq!("Invalid tag", error.what());
+
+        // derived encodings keep the tags of their variants
+        assert_eq!(vec![1], PunishmentKind::ByzantineFault.encode());
+        assert_eq!(vec![6], CompactTxType::MLSHandshake.encode());
+        let init = MLSInit::NodeJoin {
+            add: vec![],
+            commit: vec![],
+        };
+        assert_eq!(vec![1, 0, 0], init.encode());
+        assert_eq!(init, MLSInit::decode(&mut init.encode().as_ref()).unwrap());
     }
 }
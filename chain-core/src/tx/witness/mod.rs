@@ -7,7 +7,7 @@ use std::prelude::v1::Vec;
 // TODO: switch to normal signatures + explicit public key
 use secp256k1::{self, recovery::RecoverableSignature, schnorrsig::SchnorrSignature};
 
-use crate::common::Proof;
+use crate::common::{EnumTags, Proof};
 use crate::tx::witness::tree::{RawSignature, RawXOnlyPubkey};
 
 /// ETH-style recoverable ECDSA
@@ -109,11 +109,21 @@ impl fmt::Display for TxInWitness {
     }
 }
 
+impl TxInWitness {
+    /// tag of `TxInWitness::TreeSig` (stable)
+    pub const TREE_SIG_TAG: u8 = 0;
+    /// discriminants of the SCALE encoding (new variants take reserved tags)
+    pub const TAGS: EnumTags = EnumTags {
+        variants: 1,
+        invalid_tag: "Invalid tag",
+    };
+}
+
 impl Encode for TxInWitness {
     fn encode_to<W: Output>(&self, dest: &mut W) {
         match *self {
             TxInWitness::TreeSig(ref schnorrsig, ref proof) => {
-                dest.push_byte(0);
+                dest.push_byte(TxInWitness::TREE_SIG_TAG);
                 schnorrsig.serialize_default().encode_to(dest);
                 proof.encode_to(dest);
             }
@@ -131,14 +141,14 @@ impl Decode for TxInWitness {
     fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
         let tag = input.read_byte()?;
         match tag {
-            0 => {
+            TxInWitness::TREE_SIG_TAG => {
                 let raw_sig = RawSignature::decode(input)?;
                 let schnorrsig = SchnorrSignature::from_default(&raw_sig)
                     .map_err(|_| Error::from("Unable to parse schnorr signature"))?;
                 let proof = Proof::decode(input)?;
                 Ok(TxInWitness::TreeSig(schnorrsig, proof))
             }
-            _ => Err(Error::from("Invalid tag")),
+            tag => Err(TxInWitness::TAGS.unknown_tag(tag)),
         }
     }
 }
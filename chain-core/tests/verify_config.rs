@@ -1,8 +1,8 @@
 use chain_core::init::address::RedeemAddress;
 use chain_core::init::coin::Coin;
 use chain_core::init::config::{
-    InitConfig, InitNetworkParameters, JailingParameters, RewardsParameters, SlashRatio,
-    SlashingParameters,
+    DistributionError, InitConfig, InitNetworkParameters, JailingParameters, RewardsParameters,
+    SlashRatio, SlashingParameters,
 };
 use chain_core::state::account::StakedStateDestination;
 use chain_core::state::tendermint::TendermintValidatorPubKey;
@@ -88,6 +88,20 @@ fn test_verify_test_example_snapshot() {
         .validate_config_get_genesis(DEFAULT_GENESIS_TIME)
         .unwrap();
 
+    // genesis configurations without the app version start with the initial one
+    let mut json = serde_json::to_value(&config).unwrap();
+    json.as_object_mut().unwrap().remove("app_version");
+    let legacy_config: InitConfig = serde_json::from_value(json).unwrap();
+    assert_eq!(legacy_config.app_version, chain_core::INITIAL_APP_VERSION);
+
+    // rules this binary doesn't know can't be started with
+    let mut future_config = config.clone();
+    future_config.app_version = chain_core::APP_VERSION + 1;
+    assert!(matches!(
+        future_config.validate_config_get_genesis(DEFAULT_GENESIS_TIME),
+        Err(DistributionError::UnsupportedAppVersion(_))
+    ));
+
     // add 1 into rewards_pool
     params.rewards_config.monetary_expansion_cap = Coin::new(951_6484_5705_9733_7035).unwrap();
     let config = InitConfig::new(dist, params, nodes);
@@ -5,6 +5,7 @@ use parity_scale_codec::{Decode, Encode};
 
 use crate::jellyfish::Version;
 use chain_core::common::H256;
+use chain_core::state::account::StakedStateAddress;
 use chain_core::state::tendermint::BlockHeight;
 use chain_core::tx::data::{
     input::{TxoPointer, TxoSize},
@@ -13,15 +14,23 @@ use chain_core::tx::data::{
 
 use super::buffer::{GetKV, StoreKV};
 use super::{
-    LookupItem, StoredChainState, CHAIN_ID_KEY, COL_APP_HASHS, COL_APP_STATES, COL_EXTRA,
-    COL_NODE_INFO, COL_STAKING_VERSIONS, GENESIS_APP_HASH_KEY, LAST_FETCHED_BLOCK_KEY,
-    LAST_STATE_KEY,
+    LookupItem, StoredChainState, CHAIN_ID_KEY, COL_ADDRESS_INDEX, COL_APP_HASHS, COL_APP_STATES,
+    COL_COMPACT_BLOCKS, COL_EXTRA, COL_NODE_INFO, COL_RECENT_STATES, COL_STAKING_VERSIONS,
+    COL_TX_RESULTS, GENESIS_APP_HASH_KEY, LAST_FETCHED_BLOCK_KEY, LAST_STATE_KEY,
+    RECENT_STATES_KEPT, STATE_FORMAT_KEY,
 };
 
 pub fn get_last_app_state(db: &impl GetKV) -> Option<Vec<u8>> {
     db.get(&(COL_NODE_INFO, LAST_STATE_KEY.to_vec()))
 }
 
+/// format version of the last stored chain state, 0 for the legacy states stored without it
+pub fn get_state_format(db: &impl GetKV) -> u32 {
+    db.get(&(COL_NODE_INFO, STATE_FORMAT_KEY.to_vec()))
+        .map(|format| u32::decode(&mut format.as_slice()).expect("decode state format"))
+        .unwrap_or(0)
+}
+
 pub fn get_sealed_log(db: &impl GetKV, txid: &TxId) -> Option<Vec<u8>> {
     lookup_item(db, LookupItem::TxSealed, txid)
 }
@@ -74,11 +83,59 @@ pub fn get_historical_app_hash(db: &impl GetKV, height: BlockHeight) -> Option<H
     Some(stored_ah)
 }
 
+/// whole chain state committed at one of the last `RECENT_STATES_KEPT` heights
+pub fn get_recent_state(db: &impl GetKV, height: BlockHeight) -> Option<Vec<u8>> {
+    db.get(&(COL_RECENT_STATES, height.encode()))
+}
+
 pub fn get_historical_staking_version(db: &impl GetKV, height: BlockHeight) -> Option<Version> {
     let sah = db.get(&(COL_STAKING_VERSIONS, height.encode()))?;
     Version::decode(&mut sah.as_slice()).ok()
 }
 
+pub fn get_compact_block(db: &impl GetKV, height: BlockHeight) -> Option<Vec<u8>> {
+    db.get(&(COL_COMPACT_BLOCKS, height.encode()))
+}
+
+pub fn store_compact_block(db: &mut impl StoreKV, height: BlockHeight, block_payload: &[u8]) {
+    db.set(
+        (COL_COMPACT_BLOCKS, height.encode()),
+        block_payload.to_vec(),
+    );
+}
+
+pub fn get_tx_result(db: &impl GetKV, txid: &TxId) -> Option<Vec<u8>> {
+    db.get(&(COL_TX_RESULTS, txid.to_vec()))
+}
+
+pub fn store_tx_result(db: &mut impl StoreKV, txid: &TxId, result: &[u8]) {
+    db.set((COL_TX_RESULTS, txid.to_vec()), result.to_vec());
+}
+
+/// Transactions affecting the address (in the committed order), SCALE-encoded
+/// `Vec<(BlockHeight, TxId)>`
+pub fn get_address_txs(db: &impl GetKV, address: &StakedStateAddress) -> Option<Vec<u8>> {
+    db.get(&(COL_ADDRESS_INDEX, address.as_ref().to_vec()))
+}
+
+/// Appends a committed transaction to the index of the address
+pub fn index_address_tx(
+    db: &mut impl StoreKV,
+    address: &StakedStateAddress,
+    height: BlockHeight,
+    txid: &TxId,
+) {
+    let key = (COL_ADDRESS_INDEX, address.as_ref().to_vec());
+    let mut txs = db
+        .get(&key)
+        .map(|raw| {
+            <Vec<(BlockHeight, TxId)>>::decode(&mut raw.as_slice()).expect("address index entry")
+        })
+        .unwrap_or_default();
+    txs.push((height, *txid));
+    db.set(key, txs.encode());
+}
+
 pub fn store_chain_state<T: StoredChainState>(
     db: &mut impl StoreKV,
     genesis_state: &T,
@@ -89,7 +146,18 @@ pub fn store_chain_state<T: StoredChainState>(
         (COL_NODE_INFO, LAST_STATE_KEY.to_vec()),
         genesis_state.get_encoded(),
     );
+    db.set(
+        (COL_NODE_INFO, STATE_FORMAT_KEY.to_vec()),
+        genesis_state.get_format_version().encode(),
+    );
     let encoded_height = block_height.encode();
+    db.set(
+        (COL_RECENT_STATES, encoded_height.clone()),
+        genesis_state.get_encoded(),
+    );
+    if let Some(expired) = block_height.value().checked_sub(RECENT_STATES_KEPT) {
+        db.delete((COL_RECENT_STATES, BlockHeight::new(expired).encode()));
+    }
     db.set(
         (COL_APP_HASHS, encoded_height.clone()),
         genesis_state.get_last_app_hash().to_vec(),
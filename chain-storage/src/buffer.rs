@@ -288,6 +288,9 @@ where
 }
 
 /// Buffer used for staking storage
+///
+/// The iteration order differs between nodes, consensus code must sort the entries (as
+/// `put_stakings` does) before deriving any state from them.
 pub type StakingBuffer = HashMap<StakedStateAddress, StakedState>;
 
 /// Buffer used for key-value storage
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::mem;
 use std::sync::Arc;
@@ -6,12 +7,12 @@ use anyhow::{ensure, Result};
 use jellyfish_merkle::iterator::JellyfishMerkleIterator;
 use jellyfish_merkle::{
     node_type::{LeafNode, Node, NodeKey},
-    HashValue, JellyfishMerkleTree, StaleNodeIndex, TreeReader,
+    CryptoHash, HashValue, JellyfishMerkleTree, StaleNodeIndex, TreeReader,
 };
 use kvdb::KeyValueDB;
-use parity_scale_codec::{Decode, Encode, Error, Input, Output};
+use parity_scale_codec::{Decode, Encode};
 
-use chain_core::common::{H256, HASH_SIZE_256};
+use chain_core::common::H256;
 use chain_core::init::coin::{sum_coins, Coin, CoinError};
 use chain_core::state::account::{to_stake_key, StakedState, StakedStateAddress};
 use chain_core::state::tendermint::BlockHeight;
@@ -20,7 +21,9 @@ use super::{COL_TRIE_NODE, COL_TRIE_STALED};
 use crate::buffer::{
     BufferGetter, BufferSimpleStore, Get, GetKV, MemStore, StakingBuffer, StoreKV,
 };
+use crate::scrub::{child_node_key, NODE_KEY_HEADER_SIZE};
 
+pub use chain_core::state::proof::SparseMerkleProof;
 pub use jellyfish_merkle::Version;
 
 pub struct KVReader<'a, S: GetKV>(&'a S);
@@ -59,10 +62,9 @@ impl<'a, S: GetKV> Get for StakingGetter<'a, S> {
     type Value = StakedState;
     fn get(&self, key: &Self::Key) -> Option<Self::Value> {
         // treat non exist version as empty set.
-        self.storage.get(&(
-            COL_TRIE_NODE,
-            NodeKey::new_empty_path(self.version).encode().unwrap(),
-        ))?;
+        if !has_version(self.storage, self.version) {
+            return None;
+        }
         JellyfishMerkleTree::new(&KVReader::new(self.storage))
             .get_with_proof(HashValue::new(to_stake_key(key)), self.version)
             .expect("merkle trie internal error")
@@ -86,8 +88,13 @@ pub fn put_stakings<'a, S: StoreKV>(
 ) -> Result<H256> {
     let reader = KVReader::new(storage);
     let tree = JellyfishMerkleTree::new(&reader);
+    // sort by key, so the written nodes don't depend on the iteration order of the caller
+    // (e.g. of a `HashMap` buffer)
     let stakings = stakings
-        .map(|staking| (HashValue::new(staking.key()), staking.encode().into()))
+        .map(|staking| (staking.key(), staking))
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(key, staking)| (HashValue::new(key), staking.encode().into()))
         .collect::<Vec<_>>();
     ensure!(!stakings.is_empty(), "can't put empty stakings");
     let (root_hashes, batch) = tree.put_blob_sets(vec![stakings], version)?;
@@ -110,78 +117,67 @@ pub fn flush_stakings<S: StoreKV>(
     put_stakings(storage, version, buffer.values())
 }
 
+/// Drop the stakings in buffer which are identical to the ones stored at `version`, so blocks
+/// which only rewrite stakings with their current values don't create a new trie version.
+///
+/// Returns true if there are still changes to flush.
+pub fn retain_changed_stakings<S: GetKV>(
+    storage: &S,
+    version: Version,
+    buffer: &mut StakingBuffer,
+) -> bool {
+    let getter = StakingGetter::new(storage, version);
+    buffer.retain(|addr, staking| getter.get(addr).as_ref() != Some(staking));
+    !buffer.is_empty()
+}
+
 /// Compute root hash of stakings in memory
 pub fn compute_staking_root(stakings: &[StakedState]) -> H256 {
     let mut store = MemStore::new();
     put_stakings(&mut store, 0, stakings.iter()).expect("jellyfish error with in memory storage")
 }
 
-/// Wrap `SparseMerkleProof` to support SCALE encoding
-#[derive(Debug, Clone)]
-pub struct SparseMerkleProof(jellyfish_merkle::SparseMerkleProof);
-
-impl Encode for SparseMerkleProof {
-    fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
-        // leaf
-        match self.0.leaf() {
-            None => dest.push_byte(0),
-            Some((hash1, hash2)) => {
-                dest.push_byte(1);
-                dest.write(hash1.as_ref());
-                dest.write(hash2.as_ref());
-            }
-        }
-
-        // siblings
-        let siblings = self
-            .0
-            .siblings()
-            .iter()
-            .map(HashValue::as_ref)
-            .collect::<Vec<_>>();
-        dest.push(&siblings);
-    }
-
-    fn size_hint(&self) -> usize {
-        let len = self.0.siblings().len();
-        HASH_SIZE_256 * 2 + 1 + mem::size_of::<u32>() + len * HASH_SIZE_256
-    }
-}
-
-impl Decode for SparseMerkleProof {
-    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
-        let leaf = match input.read_byte()? {
-            0 => None,
-            1 => Some((
-                HashValue::new(H256::decode(input)?),
-                HashValue::new(H256::decode(input)?),
-            )),
-            _ => return Err("Invalid variant in Option<_> SparseMerkleProof::leaf".into()),
-        };
-
-        let siblings = <Vec<H256>>::decode(input)?
-            .into_iter()
-            .map(HashValue::new)
-            .collect::<Vec<_>>();
-        Ok(SparseMerkleProof(jellyfish_merkle::SparseMerkleProof::new(
-            leaf, siblings,
-        )))
-    }
+/// Recompute root hashes of a jellyfish merkle trie purely in memory (without a kvdb),
+/// so that roots published by nodes can be verified out-of-process.
+///
+/// `change_sets[i]` are the `(key, value)` pairs set at version `i` (the later one wins if a
+/// key is repeated in a change set), returns the root hash after each version.
+pub fn compute_root_hashes(change_sets: Vec<Vec<(H256, Vec<u8>)>>) -> Result<Vec<H256>> {
+    ensure!(
+        change_sets.iter().all(|change_set| !change_set.is_empty()),
+        "can't put empty change set"
+    );
+    let store: MemStore<(u32, Vec<u8>), Vec<u8>> = MemStore::new();
+    let reader = KVReader::new(&store);
+    let blob_sets = change_sets
+        .into_iter()
+        .map(|change_set| {
+            change_set
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .map(|(key, value)| (HashValue::new(key), value.into()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let (root_hashes, _) = JellyfishMerkleTree::new(&reader).put_blob_sets(blob_sets, 0)?;
+    Ok(root_hashes.iter().map(|hash| *hash.as_ref()).collect())
 }
 
-impl SparseMerkleProof {
-    pub fn verify(
-        &self,
-        root_hash: H256,
-        address: &StakedStateAddress,
-        value: Option<&StakedState>,
-    ) -> Result<()> {
-        self.0.verify(
-            HashValue::new(root_hash),
-            HashValue::new(to_stake_key(address)),
-            value.map(|staking| staking.encode().into()).as_ref(),
-        )
-    }
+/// Recompute root hashes of the staking trie after each version purely in memory,
+/// `history[i]` are the stakings modified at version `i`.
+pub fn compute_staking_root_hashes(history: &[Vec<StakedState>]) -> Result<Vec<H256>> {
+    compute_root_hashes(
+        history
+            .iter()
+            .map(|stakings| {
+                stakings
+                    .iter()
+                    .map(|staking| (staking.key(), staking.encode()))
+                    .collect()
+            })
+            .collect(),
+    )
 }
 
 /// Get with proof from underlying storage.
@@ -197,10 +193,21 @@ pub fn get_with_proof<S: GetKV>(
         blob.map(|blob| {
             StakedState::decode(&mut blob.as_ref()).expect("merkle trie storage corrupted")
         }),
-        SparseMerkleProof(proof),
+        proof.into(),
     )
 }
 
+/// Returns true if the trie of the version is stored (it's not stored if no stakings were ever put
+/// at the version, or if it was pruned)
+pub fn has_version<S: GetKV>(storage: &S, version: Version) -> bool {
+    storage
+        .get(&(
+            COL_TRIE_NODE,
+            NodeKey::new_empty_path(version).encode().unwrap(),
+        ))
+        .is_some()
+}
+
 /// Collect staled nodes
 pub fn collect_stale_node_indices<S: KeyValueDB>(
     storage: &S,
@@ -221,7 +228,7 @@ fn encode_stale_node_index(index: &StaleNodeIndex) -> Result<Vec<u8>> {
     Ok(encoded)
 }
 
-fn decode_stale_node_index(data: &[u8]) -> Result<StaleNodeIndex> {
+pub(crate) fn decode_stale_node_index(data: &[u8]) -> Result<StaleNodeIndex> {
     let version_size = mem::size_of::<u64>();
 
     let stale_since_version = u64::from_be_bytes(data[..version_size].try_into().unwrap());
@@ -233,6 +240,45 @@ fn decode_stale_node_index(data: &[u8]) -> Result<StaleNodeIndex> {
     })
 }
 
+/// Returns the root hash of the trie at `version` (`None` if the version is not stored)
+pub fn root_hash<S: GetKV>(storage: &S, version: Version) -> Result<Option<H256>> {
+    storage
+        .get(&(COL_TRIE_NODE, NodeKey::new_empty_path(version).encode()?))
+        .map(|value| Node::decode(&value).map(|node| *node.hash().as_ref()))
+        .transpose()
+}
+
+/// Reads the nodes in the upper `levels` levels of the trie at `version`, so that they are cached
+/// by the storage before they are needed (e.g. on startup), returns the number of read nodes
+pub fn preload_trie<S: GetKV>(storage: &S, version: Version, levels: usize) -> Result<usize> {
+    let mut pending = vec![NodeKey::new_empty_path(version).encode()?];
+    let mut loaded = 0;
+    while let Some(key) = pending.pop() {
+        let node = match storage.get(&(COL_TRIE_NODE, key.clone())) {
+            Some(value) => Node::decode(&value)?,
+            None => continue,
+        };
+        loaded += 1;
+        let depth = key[NODE_KEY_HEADER_SIZE - 1] as usize;
+        if let Node::Internal(internal) = node {
+            if depth + 1 >= levels {
+                continue;
+            }
+            for n in 0..16 {
+                // the nibble type is only constructed by decoding a path
+                let nibble = NodeKey::decode(&child_node_key(&key, version, n))?
+                    .nibble_path()
+                    .last()
+                    .expect("child path is not empty");
+                if let Some(child) = internal.child(nibble) {
+                    pending.push(child_node_key(&key, child.version, n));
+                }
+            }
+        }
+    }
+    Ok(loaded)
+}
+
 /// Iterate through all stakings
 pub fn iter_stakings<S: GetKV>(
     storage: &S,
@@ -268,6 +314,7 @@ mod tests {
     use jellyfish_merkle::node_type::Node;
     use jellyfish_merkle::{AccountStateBlob, CryptoHash};
     use kvdb_memorydb::{create as create_memorydb, InMemory};
+    use quickcheck::quickcheck;
 
     use super::*;
     use crate::buffer::{flush_kvdb, BufferStore, GetStaking, KVBuffer, StoreStaking};
@@ -379,6 +426,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_retain_changed_stakings() {
+        let mut app = App::new();
+        let staking = StakedState::default(StakedStateAddress::BasicRedeem([0x01; 20].into()));
+        app.staking_store().set_staking(staking.clone());
+        app.commit();
+
+        let version = app.version - 1;
+        let mut buffer = StakingBuffer::new();
+        buffer.insert(staking.address, staking.clone());
+        assert!(!retain_changed_stakings(&app.storage, version, &mut buffer));
+        assert!(buffer.is_empty());
+
+        let mut staking1 = staking.clone();
+        staking1.nonce = 1;
+        let staking2 = StakedState::default(StakedStateAddress::BasicRedeem([0x02; 20].into()));
+        buffer.insert(staking.address, staking);
+        buffer.insert(staking1.address, staking1.clone());
+        buffer.insert(staking2.address, staking2.clone());
+        assert!(retain_changed_stakings(&app.storage, version, &mut buffer));
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.get(&staking1.address), Some(&staking1));
+        assert_eq!(buffer.get(&staking2.address), Some(&staking2));
+    }
+
+    /// Stakings modified at each version, generated from `(address byte, nonce)` pairs
+    fn staking_history(history: Vec<Vec<(u8, u64)>>) -> Vec<Vec<StakedState>> {
+        history
+            .into_iter()
+            .filter(|stakings| !stakings.is_empty())
+            .map(|stakings| {
+                stakings
+                    .into_iter()
+                    .map(|(addr, nonce)| StakedState {
+                        nonce,
+                        ..StakedState::default(StakedStateAddress::BasicRedeem([addr; 20].into()))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    quickcheck! {
+        // roots computed in memory equal roots of the storage backed trie
+        fn compute_root_hashes_equivalent(history: Vec<Vec<(u8, u64)>>) -> bool {
+            let history = staking_history(history);
+            let mut app = App::new();
+            let root_hashes = history
+                .iter()
+                .map(|stakings| {
+                    for staking in stakings.iter() {
+                        app.staking_store().set_staking(staking.clone());
+                    }
+                    app.commit();
+                    app.root_hash
+                })
+                .collect::<Vec<_>>();
+            compute_staking_root_hashes(&history).unwrap() == root_hashes
+        }
+
+        // the root only depends on the final states, not on the history
+        fn compute_root_hashes_history_independent(history: Vec<Vec<(u8, u64)>>) -> bool {
+            let history = staking_history(history);
+            let final_states = history
+                .iter()
+                .flatten()
+                .map(|staking| (staking.address, staking.clone()))
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .map(|(_, staking)| staking)
+                .collect::<Vec<_>>();
+            match compute_staking_root_hashes(&history).unwrap().last() {
+                Some(root_hash) => *root_hash == compute_staking_root(&final_states),
+                None => final_states.is_empty(),
+            }
+        }
+    }
+
     fn check_proof(
         app: &mut App,
         stakings: &[StakedState],
@@ -417,6 +542,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn check_preload_trie() {
+        let mut app = App::new();
+        for i in 0..40 {
+            app.staking_store()
+                .set_staking(StakedState::default(StakedStateAddress::BasicRedeem(
+                    [0x01 + i; 20].into(),
+                )));
+        }
+        app.commit();
+
+        let version = app.version - 1;
+        let nodes = app.storage.iter(COL_TRIE_NODE).count();
+        assert_eq!(1, preload_trie(&app.storage, version, 1).unwrap());
+        let loaded = preload_trie(&app.storage, version, 2).unwrap();
+        assert!(loaded > 1 && loaded <= 17);
+        assert_eq!(nodes, preload_trie(&app.storage, version, 64).unwrap());
+        assert_eq!(0, preload_trie(&app.storage, version + 1, 64).unwrap());
+        assert_eq!(
+            Some(app.root_hash),
+            root_hash(&app.storage, version).unwrap()
+        );
+        assert_eq!(None, root_hash(&app.storage, version + 1).unwrap());
+    }
+
     /// Test encoding of jellyfish nodes
     #[test]
     fn check_nodes() {
@@ -1,9 +1,13 @@
 mod api;
 pub mod buffer;
 pub mod jellyfish;
+pub mod prune;
+pub mod scrub;
+pub mod snapshot;
 
 use crate::buffer::{flush_storage, BufferStore, Get, KVBuffer};
 use crate::jellyfish::{put_stakings, Version};
+use crate::scrub::{CorruptionAlert, ScrubConfig, TrieScrubber};
 use chain_core::common::H256;
 use chain_core::state::account::StakedState;
 use chain_core::state::tendermint::BlockHeight;
@@ -37,14 +41,28 @@ pub const COL_ENCLAVE_TX: u32 = 8;
 pub const COL_TRIE_NODE: u32 = 9;
 /// Column for staled node key in merkle trie
 pub const COL_TRIE_STALED: u32 = 10;
-/// Column to store block height -> staking version
+/// Column to store block height -> staking version, heights without staking changes map to the
+/// version of the last block which changed them
 pub const COL_STAKING_VERSIONS: u32 = 11;
+/// Column for compact blocks served to wallets: height => CompactBlock
+pub const COL_COMPACT_BLOCKS: u32 = 12;
+/// Column for execution results of delivered transactions: txid => TxResult
+pub const COL_TX_RESULTS: u32 = 13;
+/// Column for the optional address index: staked state address => Vec<(BlockHeight, TxId)>
+pub const COL_ADDRESS_INDEX: u32 = 14;
+/// Column for the whole chain states of the recent heights (to roll back to if the last one
+/// is inconsistent): height => encoded chain state
+pub const COL_RECENT_STATES: u32 = 15;
 /// Number of columns in DB
-pub const NUM_COLUMNS: u32 = 12;
+pub const NUM_COLUMNS: u32 = 16;
+/// Number of the latest heights whose whole chain states are kept in `COL_RECENT_STATES`
+pub const RECENT_STATES_KEPT: u64 = 100;
 
 pub const CHAIN_ID_KEY: &[u8] = b"chain_id";
 pub const GENESIS_APP_HASH_KEY: &[u8] = b"genesis_app_hash";
 pub const LAST_STATE_KEY: &[u8] = b"last_state";
+/// Format version of the value stored under `LAST_STATE_KEY` (missing in the legacy format 0)
+pub const STATE_FORMAT_KEY: &[u8] = b"state_format";
 pub const LAST_FETCHED_BLOCK_KEY: &[u8] = b"last_fetched_block";
 
 pub enum StorageType {
@@ -52,11 +70,34 @@ pub enum StorageType {
     AccountTrie,
 }
 
-/// Storage configuration -- currently only the path to RocksDB directory
-/// TODO: other options? e.g. HDD vs SDD?
+/// Compaction profile of the RocksDB instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompactionStyle {
+    /// detected from the type of the drive the database is stored on
+    Auto,
+    /// tuned for solid state drives
+    Ssd,
+    /// tuned for hard disk drives
+    Hdd,
+}
+
+/// RocksDB tuning, unset options keep the kvdb-rocksdb defaults
+#[derive(Debug, Clone, Default)]
+pub struct DbOptions {
+    /// memory budget (in MiB) of each column, split between its block cache and memtables
+    pub column_memory_budget: Option<usize>,
+    /// maximum number of open files (-1 for unlimited)
+    pub max_open_files: Option<i32>,
+    pub compaction: Option<CompactionStyle>,
+    /// number of RocksDB info log files kept
+    pub keep_log_file_num: Option<i32>,
+}
+
+/// Storage configuration -- the path to RocksDB directory and its tuning
 pub struct StorageConfig<'a> {
     base_dbs_path: &'a str,
     purpose: StorageType,
+    db_options: DbOptions,
 }
 
 impl<'a> StorageConfig<'a> {
@@ -64,9 +105,15 @@ impl<'a> StorageConfig<'a> {
         StorageConfig {
             base_dbs_path,
             purpose,
+            db_options: DbOptions::default(),
         }
     }
 
+    pub fn with_db_options(mut self, db_options: DbOptions) -> Self {
+        self.db_options = db_options;
+        self
+    }
+
     pub fn db_path(&self) -> String {
         match self.purpose {
             StorageType::Node => Path::new(self.base_dbs_path)
@@ -81,6 +128,31 @@ impl<'a> StorageConfig<'a> {
                 .to_string(),
         }
     }
+
+    #[cfg(feature = "kvdb-rocksdb")]
+    fn database_config(&self) -> kvdb_rocksdb::DatabaseConfig {
+        use kvdb_rocksdb::CompactionProfile;
+
+        let mut db_config = kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS);
+        let options = &self.db_options;
+        if let Some(budget) = options.column_memory_budget {
+            db_config.memory_budget = (0..NUM_COLUMNS).map(|col| (col, budget)).collect();
+        }
+        if let Some(max_open_files) = options.max_open_files {
+            db_config.max_open_files = max_open_files;
+        }
+        if let Some(compaction) = options.compaction {
+            db_config.compaction = match compaction {
+                CompactionStyle::Auto => CompactionProfile::auto(Path::new(&self.db_path())),
+                CompactionStyle::Ssd => CompactionProfile::ssd(),
+                CompactionStyle::Hdd => CompactionProfile::hdd(),
+            };
+        }
+        if let Some(keep_log_file_num) = options.keep_log_file_num {
+            db_config.keep_log_file_num = keep_log_file_num;
+        }
+        db_config
+    }
 }
 
 /// Storage wrapper -- currently only holds the reference to KV DB.
@@ -132,6 +204,11 @@ impl ReadOnlyStorage {
             .expect("IO fail")
             .map(|x| x.to_vec())
     }
+
+    /// Starts verifying the merkle trie columns in background
+    pub fn spawn_trie_scrubber(&self, config: ScrubConfig, alert: CorruptionAlert) -> TrieScrubber {
+        TrieScrubber::spawn(self.db.clone(), config, alert)
+    }
 }
 
 pub trait StoredChainState {
@@ -143,6 +220,8 @@ pub trait StoredChainState {
     fn get_last_app_hash(&self) -> H256;
     /// the staking version
     fn get_staking_version(&self) -> Version;
+    /// format version of the encoded state, so older layouts can be migrated when loaded
+    fn get_format_version(&self) -> u32;
 }
 
 #[repr(u32)]
@@ -194,11 +273,8 @@ impl Storage {
     #[cfg(feature = "kvdb-rocksdb")]
     pub fn new(config: &StorageConfig<'_>) -> Self {
         let db = Arc::new(
-            kvdb_rocksdb::Database::open(
-                &kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS),
-                &config.db_path(),
-            )
-            .expect("failed to open db"),
+            kvdb_rocksdb::Database::open(&config.database_config(), &config.db_path())
+                .expect("failed to open db"),
         );
         Storage {
             db,
@@ -240,10 +316,18 @@ impl Storage {
         get_last_app_state(self)
     }
 
+    pub fn get_state_format(&self) -> u32 {
+        get_state_format(self)
+    }
+
     pub fn get_historical_state(&self, height: BlockHeight) -> Option<Vec<u8>> {
         get_historical_state(self, height)
     }
 
+    pub fn get_recent_state(&self, height: BlockHeight) -> Option<Vec<u8>> {
+        get_recent_state(self, height)
+    }
+
     pub fn get_historical_staking_version(&self, height: BlockHeight) -> Option<Version> {
         get_historical_staking_version(self, height)
     }
@@ -252,6 +336,14 @@ impl Storage {
         get_historical_app_hash(self, height)
     }
 
+    pub fn get_compact_block(&self, height: BlockHeight) -> Option<Vec<u8>> {
+        get_compact_block(self, height)
+    }
+
+    pub fn get_tx_result(&self, txid: &TxId) -> Option<Vec<u8>> {
+        get_tx_result(self, txid)
+    }
+
     pub fn write_genesis_chain_id(&mut self, genesis_app_hash: &H256, chain_id: &str) {
         let inittx = self.get_or_create_tx();
         inittx.put(COL_NODE_INFO, GENESIS_APP_HASH_KEY, genesis_app_hash);
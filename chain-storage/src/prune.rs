@@ -0,0 +1,144 @@
+//! Pruning of stale merkle trie nodes
+//!
+//! Every new version of the staking trie replaces the nodes along the paths of the changed keys,
+//! the replaced nodes are recorded in `COL_TRIE_STALED` (keyed by the version since which they are
+//! stale) but stay in `COL_TRIE_NODE`, so that old versions can still be read. Pruning deletes the
+//! nodes which are only referenced by versions older than the retention window.
+use std::io;
+
+use jellyfish_merkle::node_type::NodeKey;
+
+use crate::jellyfish::{decode_stale_node_index, Version};
+use crate::{Storage, COL_TRIE_NODE, COL_TRIE_STALED};
+
+/// Number of stale node indices processed in one write
+pub const DEFAULT_PRUNE_BATCH_SIZE: usize = 10_000;
+
+/// Outcome of a prune pass
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PruneStats {
+    /// number of deleted trie nodes
+    pub pruned_nodes: usize,
+    /// true if stale nodes were left because of the limit of the pass
+    pub incomplete: bool,
+}
+
+/// Oldest version kept readable when the `keep_recent` latest versions up to `latest` are retained
+pub fn least_readable_version(latest: Version, keep_recent: u64) -> Version {
+    latest.saturating_sub(keep_recent.saturating_sub(1))
+}
+
+/// Deletes the trie nodes which became stale at or before `least_readable_version`: versions
+/// older than it can't be read afterwards (their nodes may be missing).
+///
+/// At most `limit` nodes are deleted, the rest is left for the next pass (so that a large
+/// backlog, e.g. when pruning is enabled on an existing node, doesn't block a single commit).
+pub fn prune_stale_nodes(
+    storage: &mut Storage,
+    least_readable_version: Version,
+    limit: usize,
+) -> io::Result<PruneStats> {
+    let mut stats = PruneStats::default();
+    loop {
+        let batch = stale_nodes(
+            storage,
+            least_readable_version,
+            DEFAULT_PRUNE_BATCH_SIZE.min(limit - stats.pruned_nodes),
+        )?;
+        if batch.is_empty() {
+            return Ok(stats);
+        }
+
+        let mut tx = storage.db.transaction();
+        for (key, node_key) in batch.iter() {
+            tx.delete(COL_TRIE_NODE, &encode_node_key(node_key)?);
+            tx.delete(COL_TRIE_STALED, key);
+        }
+        storage.db.write(tx)?;
+        stats.pruned_nodes += batch.len();
+        if stats.pruned_nodes >= limit {
+            stats.incomplete = !stale_nodes(storage, least_readable_version, 1)?.is_empty();
+            return Ok(stats);
+        }
+    }
+}
+
+/// Returns up to `limit` stale node indices (and the keys of the indices) which became stale at or
+/// before `version`
+fn stale_nodes(
+    storage: &Storage,
+    version: Version,
+    limit: usize,
+) -> io::Result<Vec<(Box<[u8]>, NodeKey)>> {
+    let mut nodes = Vec::new();
+    // stale node indices are ordered by version (big endian prefix)
+    for (key, _) in storage.db.iter(COL_TRIE_STALED).take(limit) {
+        let index = decode_stale_node_index(&key)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        if index.stale_since_version > version {
+            break;
+        }
+        nodes.push((key, index.node_key));
+    }
+    Ok(nodes)
+}
+
+fn encode_node_key(node_key: &NodeKey) -> io::Result<Vec<u8>> {
+    node_key
+        .encode()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Get;
+    use crate::jellyfish::{has_version, StakingGetter};
+    use crate::NUM_COLUMNS;
+    use chain_core::state::account::{StakedState, StakedStateAddress};
+    use kvdb_memorydb::create as create_memorydb;
+    use std::sync::Arc;
+
+    #[test]
+    fn check_prune_stale_nodes() {
+        let mut storage = Storage::new_db(Arc::new(create_memorydb(NUM_COLUMNS)));
+        let mut stakings = (1..=20)
+            .map(|i| StakedState::default(StakedStateAddress::BasicRedeem([i; 20].into())))
+            .collect::<Vec<_>>();
+        storage.put_stakings(0, &stakings);
+        for version in 1..=5 {
+            stakings[0].nonce = version;
+            storage.put_stakings(version, &stakings[..1]);
+        }
+        let nodes = storage.db.iter(COL_TRIE_NODE).count();
+
+        let least_readable = least_readable_version(5, 2);
+        assert_eq!(4, least_readable);
+        let stats = prune_stale_nodes(&mut storage, least_readable, 1).unwrap();
+        assert_eq!(1, stats.pruned_nodes);
+        assert!(stats.incomplete);
+        let stats = prune_stale_nodes(&mut storage, least_readable, usize::MAX).unwrap();
+        assert!(!stats.incomplete);
+        assert_eq!(
+            nodes - stats.pruned_nodes - 1,
+            storage.db.iter(COL_TRIE_NODE).count()
+        );
+        assert_eq!(
+            PruneStats::default(),
+            prune_stale_nodes(&mut storage, least_readable, usize::MAX).unwrap()
+        );
+
+        for version in 0..least_readable {
+            assert!(!has_version(&storage, version));
+        }
+        for version in least_readable..=5 {
+            assert!(has_version(&storage, version));
+            let getter = StakingGetter::new(&storage, version);
+            assert_eq!(
+                Some(version),
+                getter.get(&stakings[0].address).map(|s| s.nonce)
+            );
+            assert!(getter.get(&stakings[19].address).is_some());
+        }
+    }
+}
@@ -0,0 +1,382 @@
+//! Background checksum scrubbing of the merkle trie columns
+//!
+//! Trie nodes are only read along the paths of the accessed keys, so a corrupted node (e.g. bit-rot
+//! on the disk of a long-running validator) may go unnoticed until it's needed to compute the app
+//! hash of a block. The scrubber iterates the trie columns at low priority (in small batches with
+//! pauses in between) and verifies every node against its key and the hashes of its children.
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use jellyfish_merkle::node_type::{Node, NodeKey};
+use jellyfish_merkle::CryptoHash;
+use kvdb::KeyValueDB;
+
+use crate::jellyfish::decode_stale_node_index;
+use crate::{COL_TRIE_NODE, COL_TRIE_STALED};
+
+/// Size of the version (big endian) and number of nibbles prefixing an encoded node key
+pub(crate) const NODE_KEY_HEADER_SIZE: usize = 9;
+
+/// Scrubbing schedule
+#[derive(Debug, Clone)]
+pub struct ScrubConfig {
+    /// number of entries verified before pausing
+    pub batch_size: usize,
+    /// pause after each batch
+    pub batch_pause: Duration,
+    /// pause between passes over the trie columns
+    pub pass_interval: Duration,
+}
+
+impl Default for ScrubConfig {
+    fn default() -> Self {
+        ScrubConfig {
+            batch_size: 1000,
+            batch_pause: Duration::from_millis(50),
+            pass_interval: Duration::from_secs(6 * 60 * 60),
+        }
+    }
+}
+
+/// Kind of corruption found in a trie column entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Corruption {
+    /// key can't be decoded
+    InvalidKey,
+    /// value can't be decoded
+    InvalidValue,
+    /// hash of the leaf blob doesn't match the stored one
+    BlobHashMismatch,
+    /// account key of the leaf doesn't start with the nibble path of its node key
+    KeyPathMismatch,
+    /// child of the internal node is not stored
+    MissingChild { child_key: Vec<u8> },
+    /// hash of the stored child doesn't match the one in the internal node
+    ChildHashMismatch { child_key: Vec<u8> },
+}
+
+impl fmt::Display for Corruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Corruption::InvalidKey => write!(f, "invalid key"),
+            Corruption::InvalidValue => write!(f, "invalid value"),
+            Corruption::BlobHashMismatch => write!(f, "blob hash mismatch"),
+            Corruption::KeyPathMismatch => write!(f, "account key doesn't match node key"),
+            Corruption::MissingChild { child_key } => {
+                write!(f, "missing child {}", hex::encode(child_key))
+            }
+            Corruption::ChildHashMismatch { child_key } => {
+                write!(f, "hash mismatch of child {}", hex::encode(child_key))
+            }
+        }
+    }
+}
+
+/// Corrupt entry found by the scrubber
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptEntry {
+    pub column: u32,
+    pub key: Vec<u8>,
+    pub corruption: Corruption,
+}
+
+impl fmt::Display for CorruptEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "column {} key {}: {}",
+            self.column,
+            hex::encode(&self.key),
+            self.corruption
+        )
+    }
+}
+
+/// Hook invoked for each corrupt entry (e.g. to alert the operator)
+pub type CorruptionAlert = Box<dyn Fn(&CorruptEntry) + Send + Sync>;
+
+/// Counters of the scrubber, updated while it runs
+#[derive(Debug, Default)]
+pub struct ScrubMetrics {
+    entries_checked: AtomicU64,
+    corrupt_entries: AtomicU64,
+    passes: AtomicU64,
+    last_pass_millis: AtomicU64,
+}
+
+/// Snapshot of `ScrubMetrics`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScrubStats {
+    /// entries verified since start
+    pub entries_checked: u64,
+    /// corrupt entries found since start
+    pub corrupt_entries: u64,
+    /// completed passes over the trie columns
+    pub passes: u64,
+    /// duration of the last completed pass (including pauses)
+    pub last_pass_millis: u64,
+}
+
+impl ScrubMetrics {
+    pub fn stats(&self) -> ScrubStats {
+        ScrubStats {
+            entries_checked: self.entries_checked.load(Ordering::Relaxed),
+            corrupt_entries: self.corrupt_entries.load(Ordering::Relaxed),
+            passes: self.passes.load(Ordering::Relaxed),
+            last_pass_millis: self.last_pass_millis.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Key of the child of the node with key `node_key` at `version` and `nibble`
+pub(crate) fn child_node_key(node_key: &[u8], version: u64, nibble: u8) -> Vec<u8> {
+    let num_nibbles = node_key[NODE_KEY_HEADER_SIZE - 1];
+    let mut path = node_key[NODE_KEY_HEADER_SIZE..].to_vec();
+    if num_nibbles % 2 == 0 {
+        path.push(nibble << 4);
+    } else {
+        *path.last_mut().expect("odd number of nibbles") |= nibble;
+    }
+
+    let mut key = version.to_be_bytes().to_vec();
+    key.push(num_nibbles + 1);
+    key.extend(path);
+    key
+}
+
+/// Whether `account_key` starts with the nibble path of `node_key`
+fn is_path_prefix(node_key: &[u8], account_key: &[u8]) -> bool {
+    let num_nibbles = node_key[NODE_KEY_HEADER_SIZE - 1] as usize;
+    let path = &node_key[NODE_KEY_HEADER_SIZE..];
+    let full_bytes = num_nibbles / 2;
+    if account_key.len() < path.len() || path[..full_bytes] != account_key[..full_bytes] {
+        return false;
+    }
+    num_nibbles % 2 == 0 || path[full_bytes] == account_key[full_bytes] & 0xf0
+}
+
+fn check_node(db: &dyn KeyValueDB, key: &[u8], value: &[u8]) -> Result<(), Corruption> {
+    let node_key = NodeKey::decode(key).map_err(|_| Corruption::InvalidKey)?;
+    match Node::decode(value).map_err(|_| Corruption::InvalidValue)? {
+        Node::Null => Ok(()),
+        Node::Leaf(leaf) => {
+            if leaf.blob().hash() != leaf.blob_hash() {
+                return Err(Corruption::BlobHashMismatch);
+            }
+            if !is_path_prefix(key, leaf.account_key().as_ref()) {
+                return Err(Corruption::KeyPathMismatch);
+            }
+            Ok(())
+        }
+        Node::Internal(internal) => {
+            for n in 0..16 {
+                // the nibble type is only constructed by decoding a path
+                let nibble = NodeKey::decode(&child_node_key(key, node_key.version(), n))
+                    .map_err(|_| Corruption::InvalidKey)?
+                    .nibble_path()
+                    .last()
+                    .expect("child path is not empty");
+                let child = match internal.child(nibble) {
+                    Some(child) => child,
+                    None => continue,
+                };
+                let child_key = child_node_key(key, child.version, n);
+                match db
+                    .get(COL_TRIE_NODE, &child_key)
+                    .expect("kv storage io error")
+                {
+                    // both may have been pruned since the node was iterated
+                    None if db
+                        .get(COL_TRIE_NODE, key)
+                        .expect("kv storage io error")
+                        .is_none() => {}
+                    None => return Err(Corruption::MissingChild { child_key }),
+                    Some(bytes) => match Node::decode(&bytes) {
+                        Ok(node) if node.hash() != child.hash => {
+                            return Err(Corruption::ChildHashMismatch { child_key })
+                        }
+                        // undecodable children are reported when they're iterated
+                        _ => {}
+                    },
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn check_stale_node_index(key: &[u8], value: &[u8]) -> Result<(), Corruption> {
+    if key.len() < 8 || decode_stale_node_index(key).is_err() {
+        return Err(Corruption::InvalidKey);
+    }
+    if !value.is_empty() {
+        return Err(Corruption::InvalidValue);
+    }
+    Ok(())
+}
+
+/// Verifies all entries of the trie columns once, pausing after each batch.
+///
+/// Returns the number of corrupt entries, or `None` if the pass was stopped.
+pub fn scrub_trie(
+    db: &dyn KeyValueDB,
+    config: &ScrubConfig,
+    metrics: &ScrubMetrics,
+    alert: &dyn Fn(&CorruptEntry),
+    stop: &AtomicBool,
+) -> Option<u64> {
+    let started = Instant::now();
+    let mut checked = 0;
+    let mut corrupt = 0;
+    for column in [COL_TRIE_NODE, COL_TRIE_STALED].iter() {
+        for (key, value) in db.iter(*column) {
+            let result = if *column == COL_TRIE_NODE {
+                check_node(db, &key, &value)
+            } else {
+                check_stale_node_index(&key, &value)
+            };
+            metrics.entries_checked.fetch_add(1, Ordering::Relaxed);
+            if let Err(corruption) = result {
+                corrupt += 1;
+                metrics.corrupt_entries.fetch_add(1, Ordering::Relaxed);
+                alert(&CorruptEntry {
+                    column: *column,
+                    key: key.to_vec(),
+                    corruption,
+                });
+            }
+
+            checked += 1;
+            if checked % config.batch_size.max(1) == 0 {
+                if stop.load(Ordering::Relaxed) {
+                    return None;
+                }
+                thread::sleep(config.batch_pause);
+            }
+        }
+    }
+
+    metrics.passes.fetch_add(1, Ordering::Relaxed);
+    metrics
+        .last_pass_millis
+        .store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    Some(corrupt)
+}
+
+/// Scrubber running passes over the trie columns in a background thread
+pub struct TrieScrubber {
+    metrics: Arc<ScrubMetrics>,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl TrieScrubber {
+    /// Spawns the scrubber thread, `alert` is invoked for each corrupt entry
+    pub fn spawn(db: Arc<dyn KeyValueDB>, config: ScrubConfig, alert: CorruptionAlert) -> Self {
+        let metrics = Arc::new(ScrubMetrics::default());
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let metrics = metrics.clone();
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("trie-scrubber".to_owned())
+                .spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        if scrub_trie(db.as_ref(), &config, &metrics, &alert, &stop).is_none() {
+                            break;
+                        }
+                        thread::park_timeout(config.pass_interval);
+                    }
+                })
+                .expect("failed to spawn trie scrubber")
+        };
+        TrieScrubber {
+            metrics,
+            stop,
+            handle,
+        }
+    }
+
+    pub fn metrics(&self) -> Arc<ScrubMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Stops the scrubber after its current batch
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.thread().unpark();
+        let _ = self.handle.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Storage, NUM_COLUMNS};
+    use chain_core::state::account::{StakedState, StakedStateAddress};
+    use kvdb_memorydb::create as create_memorydb;
+    use std::sync::Mutex;
+
+    fn scrub(db: &dyn KeyValueDB) -> Vec<CorruptEntry> {
+        let found = Mutex::new(Vec::new());
+        let config = ScrubConfig {
+            batch_pause: Duration::from_millis(0),
+            ..Default::default()
+        };
+        let corrupt = scrub_trie(
+            db,
+            &config,
+            &ScrubMetrics::default(),
+            &|entry| found.lock().unwrap().push(entry.clone()),
+            &AtomicBool::new(false),
+        );
+        let found = found.into_inner().unwrap();
+        assert_eq!(corrupt, Some(found.len() as u64));
+        found
+    }
+
+    #[test]
+    fn check_scrub_trie() {
+        let db: Arc<dyn KeyValueDB> = Arc::new(create_memorydb(NUM_COLUMNS));
+        let mut storage = Storage::new_db(db.clone());
+        let stakings = (1..=3)
+            .map(|i| StakedState::default(StakedStateAddress::BasicRedeem([i; 20].into())))
+            .collect::<Vec<_>>();
+        storage.put_stakings(0, &stakings);
+        assert_eq!(scrub(db.as_ref()), vec![]);
+
+        let leaves = db
+            .iter(COL_TRIE_NODE)
+            .filter(|(_, value)| matches!(Node::decode(value), Ok(Node::Leaf(_))))
+            .collect::<Vec<_>>();
+        let (rotten_key, rotten_value) = &leaves[0];
+        let (missing_key, _) = &leaves[1];
+        let mut rotten_value = rotten_value.to_vec();
+        *rotten_value.last_mut().unwrap() ^= 1;
+        let mut tx = db.transaction();
+        tx.put(COL_TRIE_NODE, rotten_key, &rotten_value);
+        tx.delete(COL_TRIE_NODE, missing_key);
+        tx.put(COL_TRIE_STALED, b"bad", b"");
+        db.write(tx).unwrap();
+
+        let found = scrub(db.as_ref());
+        assert_eq!(found.len(), 3);
+        assert!(found.contains(&CorruptEntry {
+            column: COL_TRIE_NODE,
+            key: rotten_key.to_vec(),
+            corruption: Corruption::BlobHashMismatch,
+        }));
+        assert!(found.iter().any(|entry| entry.corruption
+            == Corruption::MissingChild {
+                child_key: missing_key.to_vec()
+            }));
+        assert!(found.contains(&CorruptEntry {
+            column: COL_TRIE_STALED,
+            key: b"bad".to_vec(),
+            corruption: Corruption::InvalidKey,
+        }));
+    }
+}
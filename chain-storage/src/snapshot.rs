@@ -0,0 +1,559 @@
+//! State snapshots for fast syncing of new nodes
+//!
+//! A snapshot of the committed state at some height consists of the staking trie nodes reachable
+//! from the root of the staking version, the UTxO set (`COL_TX_META`), the transaction merkle
+//! tree of the last block and the encoded chain state. The entries are split into chunks which
+//! are stored as files next to a metadata file with the hash of each chunk.
+//!
+//! A restored snapshot is verified against the app hash the light client trusts at its height:
+//! chunks are checked against the metadata hashes, the staking trie is verified node by node
+//! from its root, and the caller recomputes the app hash from the chain state, the trie root and
+//! the transaction merkle tree. The UTxO set isn't part of the app hash, so its entries are
+//! only authenticated by the chunk hashes. Sealed transaction payloads are sealed to the
+//! machine of the exporting node, so they aren't included either (the node fetches them from
+//! the data bootstrapping enclave as before).
+use std::fmt;
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::PathBuf;
+
+use jellyfish_merkle::node_type::{Node, NodeKey};
+use jellyfish_merkle::CryptoHash;
+use parity_scale_codec::{Decode, Encode};
+
+use crate::api::{get_historical_app_hash, get_historical_staking_version, lookup_item};
+use crate::jellyfish::Version;
+use crate::scrub::child_node_key;
+use crate::{
+    LookupItem, ReadOnlyStorage, Storage, COL_MERKLE_PROOFS, COL_NODE_INFO, COL_TRIE_NODE,
+    COL_TX_META, LAST_STATE_KEY,
+};
+use chain_core::common::H256;
+use chain_core::state::tendermint::BlockHeight;
+
+/// Version of the chunk format, snapshots in other formats are rejected
+pub const SNAPSHOT_FORMAT: u32 = 1;
+
+/// Default (approximate) size of a chunk in bytes
+pub const DEFAULT_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Name of the metadata file in the directory of a snapshot
+const METADATA_FILE: &str = "metadata";
+
+/// Description of a snapshot, advertised to the syncing nodes
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SnapshotMetadata {
+    /// height of the last block included in the snapshot
+    pub height: BlockHeight,
+    /// chunk format version
+    pub format: u32,
+    /// app hash after the last block was committed
+    pub app_hash: H256,
+    /// blake3 hashes of the chunks
+    pub chunk_hashes: Vec<H256>,
+}
+
+impl SnapshotMetadata {
+    /// Hash identifying the snapshot
+    pub fn hash(&self) -> H256 {
+        blake3::hash(&self.encode()).into()
+    }
+
+    /// Number of chunks
+    pub fn chunks(&self) -> u32 {
+        self.chunk_hashes.len() as u32
+    }
+}
+
+/// Key-value storage entry in a chunk
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct SnapshotEntry {
+    pub column: u32,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+/// Errors of exporting or restoring a snapshot
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// snapshot file or storage io error
+    Io(io::Error),
+    /// state at the height isn't stored (e.g. not committed yet)
+    MissingState(BlockHeight),
+    /// chunk format of the snapshot isn't supported
+    UnsupportedFormat(u32),
+    /// app hash of the snapshot isn't the trusted one
+    AppHashMismatch,
+    /// snapshot has no chunks
+    NoChunks,
+    /// chunks should be applied in order
+    UnexpectedChunk { expected: u32, index: u32 },
+    /// hash of the chunk doesn't match the metadata
+    ChunkHashMismatch(u32),
+    /// chunk can't be decoded
+    InvalidChunk(u32),
+    /// chunk has an entry of a column which isn't part of snapshots
+    UnexpectedColumn(u32),
+    /// snapshot doesn't include the chain state
+    MissingChainState,
+    /// staking trie node is missing or doesn't match its parent
+    InvalidTrie(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot io error: {}", e),
+            SnapshotError::MissingState(height) => write!(f, "no state stored at {}", height),
+            SnapshotError::UnsupportedFormat(format) => {
+                write!(f, "unsupported snapshot format {}", format)
+            }
+            SnapshotError::AppHashMismatch => write!(f, "app hash doesn't match the trusted one"),
+            SnapshotError::NoChunks => write!(f, "snapshot has no chunks"),
+            SnapshotError::UnexpectedChunk { expected, index } => {
+                write!(f, "expected chunk {}, got chunk {}", expected, index)
+            }
+            SnapshotError::ChunkHashMismatch(index) => {
+                write!(f, "hash mismatch of chunk {}", index)
+            }
+            SnapshotError::InvalidChunk(index) => write!(f, "invalid chunk {}", index),
+            SnapshotError::UnexpectedColumn(column) => {
+                write!(f, "unexpected entry of column {}", column)
+            }
+            SnapshotError::MissingChainState => write!(f, "chain state not in snapshot"),
+            SnapshotError::InvalidTrie(e) => write!(f, "invalid staking trie: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+/// Visits the staking trie nodes reachable from the root at `version` (parents before
+/// children), verifying each node against the hash in its parent.
+///
+/// Returns the root hash.
+fn walk_trie(
+    storage: &ReadOnlyStorage,
+    version: Version,
+    mut visit: impl FnMut(&[u8], &[u8]) -> Result<(), SnapshotError>,
+) -> Result<H256, SnapshotError> {
+    let invalid =
+        |msg: &str, key: &[u8]| SnapshotError::InvalidTrie(format!("{} {}", msg, hex::encode(key)));
+    let root_key = NodeKey::new_empty_path(version)
+        .encode()
+        .map_err(|e| SnapshotError::InvalidTrie(e.to_string()))?;
+    let mut root_hash = None;
+    let mut pending = vec![(root_key, None)];
+    while let Some((key, expected_hash)) = pending.pop() {
+        let value = storage
+            .db
+            .get(COL_TRIE_NODE, &key)?
+            .ok_or_else(|| invalid("missing node", &key))?;
+        let node = Node::decode(&value).map_err(|_| invalid("invalid node", &key))?;
+        let hash = node.hash();
+        match expected_hash {
+            Some(expected) if expected != hash => {
+                return Err(invalid("hash mismatch of node", &key))
+            }
+            Some(_) => {}
+            None => root_hash = Some(*hash.as_ref()),
+        }
+        match &node {
+            Node::Null => {}
+            Node::Leaf(leaf) => {
+                if leaf.blob().hash() != leaf.blob_hash() {
+                    return Err(invalid("blob hash mismatch of node", &key));
+                }
+            }
+            Node::Internal(internal) => {
+                for n in 0..16 {
+                    // the nibble type is only constructed by decoding a path
+                    let nibble = NodeKey::decode(&child_node_key(&key, version, n))
+                        .map_err(|_| invalid("invalid node key", &key))?
+                        .nibble_path()
+                        .last()
+                        .expect("child path is not empty");
+                    if let Some(child) = internal.child(nibble) {
+                        pending.push((child_node_key(&key, child.version, n), Some(child.hash)));
+                    }
+                }
+            }
+        }
+        visit(&key, &value)?;
+    }
+    Ok(root_hash.expect("root is visited first"))
+}
+
+/// Verifies the staking trie at `version` node by node, returns its root hash
+pub fn verify_trie(storage: &ReadOnlyStorage, version: Version) -> Result<H256, SnapshotError> {
+    walk_trie(storage, version, |_, _| Ok(()))
+}
+
+/// Writes entries into chunk files of about `chunk_size` bytes
+struct ChunkWriter {
+    dir: PathBuf,
+    chunk_size: usize,
+    entries: Vec<SnapshotEntry>,
+    size: usize,
+    chunk_hashes: Vec<H256>,
+}
+
+impl ChunkWriter {
+    fn push(&mut self, column: u32, key: &[u8], value: &[u8]) -> io::Result<()> {
+        self.size += key.len() + value.len();
+        self.entries.push(SnapshotEntry {
+            column,
+            key: key.to_vec(),
+            value: value.to_vec(),
+        });
+        if self.size >= self.chunk_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+        let chunk = mem::take(&mut self.entries).encode();
+        fs::write(self.dir.join(self.chunk_hashes.len().to_string()), &chunk)?;
+        self.chunk_hashes.push(blake3::hash(&chunk).into());
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Directory of exported snapshots, one sub-directory per height
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(SnapshotStore { dir })
+    }
+
+    fn snapshot_dir(&self, height: BlockHeight) -> PathBuf {
+        self.dir.join(height.to_string())
+    }
+
+    /// Exports the committed state at `height`, which should be the last committed block
+    /// (the UTxO set and the chain state are only stored for the latest height)
+    pub fn export(
+        &self,
+        storage: &ReadOnlyStorage,
+        height: BlockHeight,
+        chunk_size: usize,
+    ) -> Result<SnapshotMetadata, SnapshotError> {
+        let missing = || SnapshotError::MissingState(height);
+        let app_hash = get_historical_app_hash(storage, height).ok_or_else(missing)?;
+        let version = get_historical_staking_version(storage, height).ok_or_else(missing)?;
+        let state = storage.get_last_app_state().ok_or_else(missing)?;
+        let txs_merkle =
+            lookup_item(storage, LookupItem::TxsMerkle, &app_hash).ok_or_else(missing)?;
+
+        let tmp_dir = self.dir.join(format!("{}.tmp", height));
+        if tmp_dir.exists() {
+            fs::remove_dir_all(&tmp_dir)?;
+        }
+        fs::create_dir_all(&tmp_dir)?;
+        let mut writer = ChunkWriter {
+            dir: tmp_dir.clone(),
+            chunk_size: chunk_size.max(1),
+            entries: Vec::new(),
+            size: 0,
+            chunk_hashes: Vec::new(),
+        };
+        writer.push(COL_NODE_INFO, LAST_STATE_KEY, &state)?;
+        writer.push(COL_MERKLE_PROOFS, &app_hash, &txs_merkle)?;
+        walk_trie(storage, version, |key, value| {
+            Ok(writer.push(COL_TRIE_NODE, key, value)?)
+        })?;
+        for (key, value) in storage.db.iter(COL_TX_META) {
+            writer.push(COL_TX_META, &key, &value)?;
+        }
+        writer.flush()?;
+
+        let metadata = SnapshotMetadata {
+            height,
+            format: SNAPSHOT_FORMAT,
+            app_hash,
+            chunk_hashes: writer.chunk_hashes,
+        };
+        fs::write(tmp_dir.join(METADATA_FILE), metadata.encode())?;
+        let dir = self.snapshot_dir(height);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::rename(tmp_dir, dir)?;
+        Ok(metadata)
+    }
+
+    /// Metadata of the stored snapshots, ordered by height
+    pub fn list(&self) -> io::Result<Vec<SnapshotMetadata>> {
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            // skip unfinished exports
+            let is_height = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| name.parse::<u64>().is_ok());
+            if !is_height {
+                continue;
+            }
+            let bytes = match fs::read(path.join(METADATA_FILE)) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            let metadata = SnapshotMetadata::decode(&mut bytes.as_slice()).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid snapshot metadata {}: {}", path.display(), e.what()),
+                )
+            })?;
+            snapshots.push(metadata);
+        }
+        snapshots.sort_by_key(|metadata| metadata.height);
+        Ok(snapshots)
+    }
+
+    /// Loads a chunk of the snapshot at `height`
+    pub fn load_chunk(&self, height: BlockHeight, index: u32) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(self.snapshot_dir(height).join(index.to_string())) {
+            Ok(chunk) => Ok(Some(chunk)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Removes all but the `keep_recent` latest snapshots
+    pub fn prune(&self, keep_recent: usize) -> io::Result<()> {
+        let snapshots = self.list()?;
+        let stale = snapshots.len().saturating_sub(keep_recent);
+        for metadata in snapshots.iter().take(stale) {
+            fs::remove_dir_all(self.snapshot_dir(metadata.height))?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies the chunks of a snapshot to an empty storage
+pub struct SnapshotRestorer {
+    metadata: SnapshotMetadata,
+    next_chunk: u32,
+    state: Option<Vec<u8>>,
+}
+
+impl SnapshotRestorer {
+    /// Starts restoring the snapshot, if it's the one at the height the light client trusts
+    pub fn new(metadata: SnapshotMetadata, trusted_app_hash: &H256) -> Result<Self, SnapshotError> {
+        if metadata.format != SNAPSHOT_FORMAT {
+            return Err(SnapshotError::UnsupportedFormat(metadata.format));
+        }
+        if metadata.app_hash != *trusted_app_hash {
+            return Err(SnapshotError::AppHashMismatch);
+        }
+        if metadata.chunk_hashes.is_empty() {
+            return Err(SnapshotError::NoChunks);
+        }
+        Ok(SnapshotRestorer {
+            metadata,
+            next_chunk: 0,
+            state: None,
+        })
+    }
+
+    pub fn metadata(&self) -> &SnapshotMetadata {
+        &self.metadata
+    }
+
+    /// Index of the next chunk to apply
+    pub fn next_chunk(&self) -> u32 {
+        self.next_chunk
+    }
+
+    /// Verifies the chunk and writes its entries into the storage.
+    ///
+    /// Returns the encoded chain state after the last chunk was applied, which isn't written
+    /// into the storage: the caller stores it once the restored state is verified.
+    pub fn apply_chunk(
+        &mut self,
+        storage: &mut Storage,
+        index: u32,
+        chunk: &[u8],
+    ) -> Result<Option<Vec<u8>>, SnapshotError> {
+        if index != self.next_chunk {
+            return Err(SnapshotError::UnexpectedChunk {
+                expected: self.next_chunk,
+                index,
+            });
+        }
+        let hash: H256 = blake3::hash(chunk).into();
+        if self.metadata.chunk_hashes[index as usize] != hash {
+            return Err(SnapshotError::ChunkHashMismatch(index));
+        }
+        let entries = Vec::<SnapshotEntry>::decode(&mut &chunk[..])
+            .map_err(|_| SnapshotError::InvalidChunk(index))?;
+
+        let mut tx = storage.db.transaction();
+        for entry in entries {
+            match entry.column {
+                COL_NODE_INFO if entry.key == LAST_STATE_KEY => self.state = Some(entry.value),
+                COL_TRIE_NODE | COL_TX_META | COL_MERKLE_PROOFS => {
+                    tx.put(entry.column, &entry.key, &entry.value)
+                }
+                column => return Err(SnapshotError::UnexpectedColumn(column)),
+            }
+        }
+        storage.db.write(tx)?;
+
+        self.next_chunk += 1;
+        if self.next_chunk < self.metadata.chunks() {
+            return Ok(None);
+        }
+        self.state
+            .take()
+            .map(Some)
+            .ok_or(SnapshotError::MissingChainState)
+    }
+
+    /// Removes the entries written by an abandoned (e.g. rejected) restore
+    pub fn abort(self, storage: &mut Storage) -> io::Result<()> {
+        let mut tx = storage.db.transaction();
+        for column in [COL_TRIE_NODE, COL_TX_META, COL_MERKLE_PROOFS].iter() {
+            tx.delete_prefix(*column, &[]);
+        }
+        storage.db.write(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{create_utxo, store_chain_state, store_txs_merkle_tree};
+    use crate::buffer::{flush_storage, BufferStore, KVBuffer};
+    use crate::{StoredChainState, NUM_COLUMNS};
+    use chain_core::state::account::{StakedState, StakedStateAddress};
+    use kvdb_memorydb::create as create_memorydb;
+    use std::sync::Arc;
+
+    struct TestState {
+        app_hash: H256,
+        staking_version: Version,
+    }
+
+    impl StoredChainState for TestState {
+        fn get_encoded(&self) -> Vec<u8> {
+            b"chain state".to_vec()
+        }
+        fn get_encoded_top_level(&self) -> Vec<u8> {
+            vec![]
+        }
+        fn get_last_app_hash(&self) -> H256 {
+            self.app_hash
+        }
+        fn get_staking_version(&self) -> Version {
+            self.staking_version
+        }
+        fn get_format_version(&self) -> u32 {
+            1
+        }
+    }
+
+    fn new_storage() -> Storage {
+        Storage::new_db(Arc::new(create_memorydb(NUM_COLUMNS)))
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("chain-storage-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn check_export_restore() {
+        let mut storage = new_storage();
+        let stakings = (1..=50)
+            .map(|i| StakedState::default(StakedStateAddress::BasicRedeem([i; 20].into())))
+            .collect::<Vec<_>>();
+        storage.put_stakings(0, &stakings[..10]);
+        let staking_root = storage.put_stakings(1, &stakings[10..]);
+        let state = TestState {
+            app_hash: [0xaa; 32],
+            staking_version: 1,
+        };
+        let height = BlockHeight::new(10);
+        let mut buffer = KVBuffer::new();
+        for i in 0..100u8 {
+            create_utxo(&mut BufferStore::new(&storage, &mut buffer), 2, &[i; 32]);
+        }
+        let mut store = BufferStore::new(&storage, &mut buffer);
+        store_txs_merkle_tree(&mut store, &state.app_hash, b"merkle tree");
+        store_chain_state(&mut store, &state, height, false);
+        flush_storage(&mut storage, buffer).unwrap();
+
+        let dir = test_dir("snapshot");
+        let snapshots = SnapshotStore::new(&dir).unwrap();
+        let metadata = snapshots
+            .export(&storage.get_read_only(), height, 1024)
+            .unwrap();
+        assert_eq!(metadata.app_hash, state.app_hash);
+        assert!(metadata.chunks() > 1);
+        assert_eq!(snapshots.list().unwrap(), vec![metadata.clone()]);
+
+        assert!(matches!(
+            SnapshotRestorer::new(metadata.clone(), &[0xbb; 32]),
+            Err(SnapshotError::AppHashMismatch)
+        ));
+        let mut restored = new_storage();
+        let mut restorer = SnapshotRestorer::new(metadata.clone(), &state.app_hash).unwrap();
+        let mut chunk = snapshots.load_chunk(height, 0).unwrap().unwrap();
+        *chunk.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            restorer.apply_chunk(&mut restored, 0, &chunk),
+            Err(SnapshotError::ChunkHashMismatch(0))
+        ));
+        assert!(matches!(
+            restorer.apply_chunk(&mut restored, 1, &chunk),
+            Err(SnapshotError::UnexpectedChunk { .. })
+        ));
+        let mut restored_state = None;
+        for index in 0..metadata.chunks() {
+            let chunk = snapshots.load_chunk(height, index).unwrap().unwrap();
+            restored_state = restorer.apply_chunk(&mut restored, index, &chunk).unwrap();
+        }
+        assert_eq!(restored_state, Some(b"chain state".to_vec()));
+
+        let restored = restored.get_read_only();
+        assert_eq!(verify_trie(&restored, 1).unwrap(), staking_root);
+        for i in 0..100u8 {
+            assert_eq!(
+                lookup_item(&restored, LookupItem::TxMetaSpent, &[i; 32]),
+                lookup_item(&storage, LookupItem::TxMetaSpent, &[i; 32])
+            );
+        }
+        assert_eq!(
+            lookup_item(&restored, LookupItem::TxsMerkle, &state.app_hash),
+            Some(b"merkle tree".to_vec())
+        );
+        // trie nodes of older versions aren't exported
+        assert!(verify_trie(&restored, 0).is_err());
+
+        snapshots.prune(0).unwrap();
+        assert_eq!(snapshots.list().unwrap(), vec![]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,15 +1,17 @@
-#[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+#[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
 use aead::{generic_array::GenericArray, AeadInPlace, NewAead};
-#[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+#[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
 use aes_gcm::Aes128Gcm;
 use aes_gcm::Tag;
-#[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+#[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
 use rand::random;
 use sgx_isa::Keyrequest;
 #[cfg(all(feature = "sgxstd", target_env = "sgx"))]
-use sgx_isa::{ErrorCode, Keyname, Keypolicy, Report};
+use sgx_isa::Report;
+#[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
+use sgx_isa::{ErrorCode, Keyname, Keypolicy};
 use std::convert::TryFrom;
-#[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+#[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
 use zeroize::Zeroize;
 #[cfg(all(feature = "sgxstd", target_env = "sgx"))]
 pub mod tls;
@@ -30,13 +32,67 @@ pub struct SealedData {
 
 /// current setup in tx-validation
 /// FIXME: check, verify and adapt when tx-validation is moved to EDP + "production"
-#[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+#[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
 const MISCMASK: u32 = 4026531840;
-#[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+#[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
 const ATTRIBUTEMASK: [u64; 2] = [18374686479671623691, 0];
 
+/// security versions of the current enclave put in the key requests
+#[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+fn self_svn() -> (u16, [u8; 16]) {
+    let report = Report::for_self();
+    (report.isvsvn, report.cpusvn)
+}
+
+#[cfg(all(
+    feature = "mock-enclave",
+    not(all(feature = "sgxstd", target_env = "sgx"))
+))]
+fn self_svn() -> (u16, [u8; 16]) {
+    (0, [0; 16])
+}
+
+/// derives the sealing key of the request with EGETKEY
+#[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+fn seal_key(key_request: &Keyrequest) -> Result<[u8; 16], ErrorCode> {
+    key_request.egetkey()
+}
+
+// anyone can unseal the data sealed with the software key,
+// so it must not end up in the optimized (shipped) builds
+#[cfg(all(
+    feature = "mock-enclave",
+    not(all(feature = "sgxstd", target_env = "sgx")),
+    not(debug_assertions)
+))]
+compile_error!(
+    "`mock-enclave` seals with a public key: it is only meant for test and debug builds"
+);
+
+/// public key the software sealing keys are derived from (development only)
+#[cfg(all(
+    feature = "mock-enclave",
+    not(all(feature = "sgxstd", target_env = "sgx"))
+))]
+const MOCK_SEAL_KEY: [u8; 16] = *b"mock-enclave-key";
+
+/// software replacement of EGETKEY for development and CI without SGX hardware:
+/// the key is derived from the public `MOCK_SEAL_KEY` and the random key id of the request
+/// (so that keys are still unique per request), it doesn't keep the sealed data confidential
+#[cfg(all(
+    feature = "mock-enclave",
+    not(all(feature = "sgxstd", target_env = "sgx"))
+))]
+fn seal_key(key_request: &Keyrequest) -> Result<[u8; 16], ErrorCode> {
+    let mut key = MOCK_SEAL_KEY;
+    for (byte, id) in key.iter_mut().zip(key_request.keyid.iter()) {
+        *byte ^= id;
+    }
+    Ok(key)
+}
+
 impl SealedData {
-    #[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+    #[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
     pub fn seal(plain: &[u8], txid: [u8; 32]) -> Result<Vec<u8>, ErrorCode> {
         let plain_len = plain.len();
         // TODO: max tx size instead of u32::MAX
@@ -44,7 +100,7 @@ impl SealedData {
             // TODO: better error code
             return Err(ErrorCode::Success);
         }
-        let report = Report::for_self();
+        let (isvsvn, cpusvn) = self_svn();
         const RESERVED: [u8; 12] = [0; 12];
         let plain_offset = plain_len as u32;
         let payload_len = plain_offset + 32;
@@ -57,8 +113,8 @@ impl SealedData {
         let key_request = Keyrequest {
             keyname: Keyname::Seal as _,
             keypolicy: Keypolicy::MRSIGNER,
-            isvsvn: report.isvsvn,
-            cpusvn: report.cpusvn,
+            isvsvn,
+            cpusvn,
             attributemask: ATTRIBUTEMASK,
             keyid: random(), // random in Intel SDK
             miscmask: MISCMASK,
@@ -70,7 +126,7 @@ impl SealedData {
         result.extend_from_slice(&u32::to_le_bytes(payload_len)[..]);
         result.extend_from_slice(&RESERVED[..]);
 
-        let mut key = key_request.egetkey()?;
+        let mut key = seal_key(&key_request)?;
         let gk = GenericArray::clone_from_slice(&key);
         key.zeroize();
         let aead = Aes128Gcm::new(&gk);
@@ -119,7 +175,7 @@ impl SealedData {
         })
     }
 
-    #[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+    #[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
     pub fn unseal(&self) -> Result<Vec<u8>, ErrorCode> {
         // Make sure the parameters that are not checked for correctness
         // by EGETKEY match the current enclave. Without this check,
@@ -133,7 +189,7 @@ impl SealedData {
         // https://github.com/intel/linux-sgx/blob/master/sdk/tseal/tSeal_internal.cpp#L123
         let nonce = GenericArray::from_slice(&[0u8; 12]);
         let mut result = self.aes_data.encrypt_txt.clone();
-        let mut key = self.key_request.egetkey()?;
+        let mut key = seal_key(&self.key_request)?;
         let gk = GenericArray::clone_from_slice(&key);
         key.zeroize();
         let aead = Aes128Gcm::new(&gk);
@@ -159,7 +215,8 @@ mod tests {
     use super::*;
 
     // can be run with cargo test --target x86_64-fortanix-unknown-sgx --features sgxstd
-    #[cfg(all(feature = "sgxstd", target_env = "sgx"))]
+    // (or with cargo test --features mock-enclave without SGX)
+    #[cfg(any(all(feature = "sgxstd", target_env = "sgx"), feature = "mock-enclave"))]
     #[test]
     fn seal_unseal() {
         let v: [u8; 32] = random();
@@ -6,14 +6,17 @@ use std::{
 };
 
 use parity_scale_codec::{Decode, Encode};
-use secp256k1::key::PublicKey;
 use zeroize::Zeroize;
 
 use chain_core::{
     common::H256,
     state::account::WithdrawUnbondedTx,
     tx::{
-        data::{access::TxAccessPolicy, attribute::TxAttributes, Tx},
+        data::{
+            access::{allowed_access, TxAccess},
+            attribute::TxAttributes,
+            Tx,
+        },
         TxWithOutputs,
     },
 };
@@ -91,27 +94,21 @@ pub fn handle_decryption_request(
                     .unseal()
                     .map_err(|e| format!("Error while unsealing sealed data: {:?}", e))?;
                 let otx = TxWithOutputs::decode(&mut unsealed_data.as_slice());
-                let push: bool;
-
-                match &otx {
+                let access = match &otx {
                     Ok(TxWithOutputs::Transfer(Tx {
                         attributes: TxAttributes { allowed_view, .. },
                         ..
-                    })) => {
-                        push = is_allowed_view(&allowed_view, &view_key);
-                    }
+                    })) => allowed_access(allowed_view, &view_key),
                     Ok(TxWithOutputs::StakeWithdraw(WithdrawUnbondedTx {
                         attributes: TxAttributes { allowed_view, .. },
                         ..
-                    })) => {
-                        push = is_allowed_view(&allowed_view, &view_key);
-                    }
+                    })) => allowed_access(allowed_view, &view_key),
                     _ => {
                         return Err("Invalid transaction type".to_owned());
                     }
-                }
+                };
 
-                if push {
+                if let Some(TxAccess::AllData) = access {
                     return_result.push(otx.unwrap());
                 }
 
@@ -128,9 +125,3 @@ pub fn handle_decryption_request(
         )),
     }
 }
-
-#[inline]
-fn is_allowed_view(allowed_views: &[TxAccessPolicy], view_key: &PublicKey) -> bool {
-    // TODO: policy != alldata + const eq?
-    allowed_views.iter().any(|x| x.view_key == *view_key)
-}
@@ -1,6 +1,7 @@
 #![cfg_attr(target_env = "sgx", feature(proc_macro_hygiene))]
 
-#[cfg(target_env = "sgx")]
+// without SGX, the validation logic is only built for its tests (sealing with a software key)
+#[cfg(any(target_env = "sgx", all(feature = "mock-enclave", test)))]
 mod sgx_module;
 
 #[cfg(target_env = "sgx")]
@@ -2,6 +2,7 @@
 mod obfuscate;
 mod validate;
 
+#[cfg(target_env = "sgx")]
 #[allow(unused_imports)]
 use rs_libc::alloc::*;
 
@@ -12,11 +13,15 @@ use chain_tx_filter::BlockFilter;
 use chain_tx_validation::Error;
 use enclave_macro::get_network_id;
 use enclave_protocol::{IntraEnclaveRequest, IntraEnclaveResponse, IntraEnclaveResponseOk};
+#[cfg(target_env = "sgx")]
 use enclave_utils::tls::{create_ra_context, create_tls_client_stream};
 use parity_scale_codec::{Decode, Encode};
+#[cfg(target_env = "sgx")]
 use ra_client::{EnclaveCertVerifier, EnclaveCertVerifierConfig, EnclaveInfo};
+#[cfg(target_env = "sgx")]
 use sgx_isa::Report;
 use std::io::{Read, Write};
+#[cfg(target_env = "sgx")]
 use std::net::TcpStream;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
@@ -24,6 +29,7 @@ use std::sync::Arc;
 
 /// FIXME: genesis app hash etc.?
 pub const NETWORK_HEX_ID: u8 = get_network_id!();
+#[cfg(target_env = "sgx")]
 pub const TDBE_MRENCLAVE: &[u8; 32] = include_bytes!("tdbe.mrenclave");
 
 pub(crate) fn write_response<I: Write>(response: IntraEnclaveResponse, output: &mut I) {
@@ -32,6 +38,7 @@ pub(crate) fn write_response<I: Write>(response: IntraEnclaveResponse, output: &
     }
 }
 
+#[cfg(target_env = "sgx")]
 fn get_tdbe_enclave_verifier() -> EnclaveCertVerifier {
     log::info!("Creating enclave certificate verifier for transaction data bootstrapping");
 
@@ -140,6 +147,7 @@ fn handling_loop<I: Read + Write, J: Read + Write>(
     }
 }
 
+#[cfg(target_env = "sgx")]
 pub fn entry() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "debug");
     env_logger::init();
@@ -293,6 +301,7 @@ mod tests {
     const TEST_NETWORK_ID: u8 = 0xab;
 
     // can be run with cargo test --target x86_64-fortanix-unknown-sgx
+    // (or with cargo test --features mock-enclave without SGX)
     #[test]
     fn test_sealing() {
         let (sender, receiver) = std::sync::mpsc::channel();
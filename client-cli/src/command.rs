@@ -1,5 +1,7 @@
 mod address_command;
 mod multisig_command;
+mod operator_command;
+mod storage_command;
 mod transaction_command;
 mod wallet_command;
 
@@ -13,7 +15,7 @@ use hex::encode;
 #[cfg(feature = "mock-enclave")]
 use log::warn;
 use pbr::ProgressBar;
-use quest::{ask, success};
+use quest::{ask, error, success};
 use structopt::StructOpt;
 
 use chain_core::init::coin::Coin;
@@ -28,6 +30,7 @@ use client_common::{ErrorKind, Result, ResultExt, SecKey, Storage};
 use client_core::signer::WalletSignerManager;
 use client_core::transaction_builder::DefaultWalletTransactionBuilder;
 use client_core::types::BalanceChange;
+use client_core::wallet::middleware::{Layered, PassphraseLockout};
 use client_core::wallet::syncer::{
     spawn_light_client_supervisor, Handle, ObfuscationSyncerConfig, ProgressReport, SyncerOptions,
     WalletSyncer,
@@ -37,6 +40,8 @@ use client_network::network_ops::{DefaultNetworkOpsClient, NetworkOpsClient};
 
 use self::address_command::AddressCommand;
 use self::multisig_command::MultiSigCommand;
+use self::operator_command::OperatorCommand;
+use self::storage_command::StorageCommand;
 use self::transaction_command::TransactionCommand;
 use self::wallet_command::WalletCommand;
 use crate::logo::{get_jok, get_logo};
@@ -45,6 +50,8 @@ use chain_core::tx::fee::LinearFee;
 use client_core::hd_wallet::HardwareKind;
 #[cfg(feature = "mock-hardware-wallet")]
 use client_core::service::MockHardwareService;
+#[cfg(feature = "pkcs11")]
+use client_core::service::Pkcs11Service;
 use client_core::service::{HwKeyService, LedgerService, WalletService};
 use once_cell::sync::Lazy;
 use std::env;
@@ -61,6 +68,13 @@ type AppTransactionCipher = MockAbciTransactionObfuscation<WebsocketRpcClient>;
 
 type AppTxBuilder = DefaultWalletTransactionBuilder<SledStorage, LinearFee, AppTransactionCipher>;
 type AppWalletClient = DefaultWalletClient<SledStorage, WebsocketRpcClient, AppTxBuilder>;
+type AppNetworkOpsClient = DefaultNetworkOpsClient<
+    AppWalletClient,
+    SledStorage,
+    WebsocketRpcClient,
+    LinearFee,
+    AppTransactionCipher,
+>;
 
 static VERSION: Lazy<String> = Lazy::new(|| {
     format!(
@@ -243,6 +257,27 @@ pub enum Command {
             help = "Disable address recovery, which is not necessary, if addresses already exist"
         )]
         disable_address_recovery: bool,
+        #[structopt(
+            name = "address-recovery-gap-limit",
+            long,
+            default_value = "20",
+            help = "Number of not yet derived addresses checked when recovering addresses"
+        )]
+        address_recovery_gap_limit: u32,
+        #[structopt(
+            name = "sync-from-now",
+            long,
+            conflicts_with = "force",
+            help = "Start syncing brand-new wallet from current block height instead of genesis"
+        )]
+        sync_from_now: bool,
+        #[structopt(
+            name = "rescan-from",
+            long,
+            conflicts_with_all = &["force", "sync-from-now"],
+            help = "Clear unspent transactions and history, then rescan from given block height"
+        )]
+        rescan_from: Option<u64>,
         #[structopt(
             name = "block-height-ensure",
             long,
@@ -256,6 +291,16 @@ pub enum Command {
         #[structopt(subcommand)]
         multisig_command: MultiSigCommand,
     },
+    #[structopt(name = "storage", about = "Storage maintenance operations")]
+    Storage {
+        #[structopt(subcommand)]
+        storage_command: StorageCommand,
+    },
+    #[structopt(name = "operator", about = "Council node operator operations")]
+    Operator {
+        #[structopt(subcommand)]
+        operator_command: OperatorCommand,
+    },
 }
 
 /// normal
@@ -289,7 +334,7 @@ fn get_tx_query(
     tendermint_client: WebsocketRpcClient,
 ) -> Result<MockAbciTransactionObfuscation<WebsocketRpcClient>> {
     warn!("WARNING: Using mock (non-enclave) infrastructure");
-    Ok(MockAbciTransactionObfuscation::new(tendermint_client))
+    MockAbciTransactionObfuscation::from_tx_query(&tendermint_client)
 }
 
 impl Command {
@@ -297,7 +342,10 @@ impl Command {
         match self {
             Command::Wallet { wallet_command } => {
                 let storage = SledStorage::new(storage_path())?;
-                let wallet_client = DefaultWalletClient::new_read_only(storage);
+                let wallet_client = Layered::new(
+                    DefaultWalletClient::new_read_only(storage.clone()),
+                    PassphraseLockout::new(storage),
+                );
                 wallet_command.execute(wallet_client)
             }
             Command::Address { address_command } => {
@@ -329,52 +377,25 @@ impl Command {
             Command::Transaction {
                 transaction_command,
             } => {
-                let storage = SledStorage::new(storage_path())?;
-                let tendermint_client = WebsocketRpcClient::new(&tendermint_url())?;
                 let wallet_name = transaction_command.wallet_name();
-                let wallet_service = WalletService::new(storage.clone());
                 let enckey = ask_seckey(None)?;
-                let wallet = wallet_service.get_wallet(&wallet_name, &enckey)?;
-                let hw_key_service = match wallet.hardware_kind {
-                    #[cfg(feature = "mock-hardware-wallet")]
-                    HardwareKind::Mock => HwKeyService::Mock(MockHardwareService::new()),
-                    HardwareKind::Trezor => HwKeyService::default(),
-                    HardwareKind::Ledger => {
-                        let ledger_service = LedgerService::new(true)?;
-                        HwKeyService::Ledger(ledger_service)
-                    }
-                    HardwareKind::LocalOnly => HwKeyService::default(),
-                };
-                let signer_manager =
-                    WalletSignerManager::new(storage.clone(), hw_key_service.clone());
-                let fee_algorithm = tendermint_client.genesis()?.fee_policy();
-                let transaction_obfuscation = get_tx_query(tendermint_client.clone())?;
-                let transaction_builder = DefaultWalletTransactionBuilder::new(
-                    signer_manager.clone(),
-                    fee_algorithm,
-                    transaction_obfuscation.clone(),
-                );
-
-                let wallet_client = DefaultWalletClient::new(
-                    storage,
-                    tendermint_client.clone(),
-                    transaction_builder,
-                    None,
-                    hw_key_service,
-                );
-                let network_ops_client = DefaultNetworkOpsClient::new(
-                    wallet_client,
-                    signer_manager,
-                    tendermint_client,
-                    fee_algorithm,
-                    transaction_obfuscation,
-                );
+                let network_ops_client = get_network_ops_client(&wallet_name, &enckey)?;
                 transaction_command.execute(
                     network_ops_client.get_wallet_client(),
                     &network_ops_client,
                     enckey,
                 )
             }
+            Command::Operator { operator_command } => {
+                let wallet_name = operator_command.wallet_name();
+                let enckey = ask_seckey(None)?;
+                let network_ops_client = get_network_ops_client(&wallet_name, &enckey)?;
+                operator_command.execute(
+                    network_ops_client.get_wallet_client(),
+                    &network_ops_client,
+                    enckey,
+                )
+            }
             Command::StakedState {
                 name,
                 address,
@@ -389,6 +410,10 @@ impl Command {
                         let ledger_service = LedgerService::new(true)?;
                         HwKeyService::Ledger(ledger_service)
                     }
+                    #[cfg(feature = "pkcs11")]
+                    Some(HardwareKind::Pkcs11) => HwKeyService::Pkcs11(Pkcs11Service::from_env()?),
+                    #[cfg(not(feature = "pkcs11"))]
+                    Some(HardwareKind::Pkcs11) => return Err(pkcs11_disabled_error()),
                     Some(HardwareKind::LocalOnly) => HwKeyService::default(),
                 };
                 let storage = SledStorage::new(storage_path())?;
@@ -426,6 +451,9 @@ impl Command {
                 enable_fast_forward,
                 disable_light_client,
                 disable_address_recovery,
+                address_recovery_gap_limit,
+                sync_from_now,
+                rescan_from,
                 block_height_ensure,
                 light_client_peers,
                 light_client_trusting_period_seconds,
@@ -569,6 +597,7 @@ impl Command {
                         enable_fast_forward: *enable_fast_forward,
                         disable_light_client: *disable_light_client,
                         enable_address_recovery: !*disable_address_recovery,
+                        address_recovery_gap_limit: *address_recovery_gap_limit,
                         batch_size: *batch_size,
                         block_height_ensure: *block_height_ensure,
                         light_client_peers: light_client_peers_user,
@@ -576,10 +605,11 @@ impl Command {
                             light_client_trusting_period_seconds_user,
                         light_client_trusting_height: light_client_trusting_height_user,
                         light_client_trusting_blockhash: light_client_trusting_blockhash_user,
+                        sync_from_now: *sync_from_now,
                     },
                     handle.clone(),
                 );
-                Self::resync(config, name.clone(), enckey, *force, storage)?;
+                Self::resync(config, name.clone(), enckey, *force, *rescan_from, storage)?;
                 if let Some(this_handle) = handle.as_ref() {
                     this_handle
                         .terminate()
@@ -592,6 +622,10 @@ impl Command {
                 let wallet_client = DefaultWalletClient::new_read_only(storage);
                 multisig_command.execute(wallet_client)
             }
+            Command::Storage { storage_command } => {
+                let storage = SledStorage::new(storage_path())?;
+                storage_command.execute(storage)
+            }
         }
     }
 
@@ -755,9 +789,10 @@ impl Command {
         let enckey = ask_seckey(None)?;
         print_sync_warning();
 
-        let history = wallet_client.history(name, &enckey, offset, limit, reversed)?;
+        let history =
+            wallet_client.history(name, &enckey, Default::default(), offset, limit, reversed)?;
 
-        if !history.is_empty() {
+        if !history.transactions.is_empty() {
             let bold = CellFormat::builder().bold(true).build();
 
             let mut rows = Vec::new();
@@ -770,9 +805,10 @@ impl Command {
                 Cell::new("Transaction Type", bold),
                 Cell::new("Block Height", bold),
                 Cell::new("Block Time", bold),
+                Cell::new("Memo", bold),
             ]));
 
-            for change in history {
+            for change in history.transactions {
                 let green = CellFormat::builder()
                     .foreground_color(Some(Color::Green))
                     .build();
@@ -805,6 +841,10 @@ impl Command {
                     Cell::new(&change.transaction_type, Default::default()),
                     Cell::new(&change.block_height, right_justify),
                     Cell::new(&change.block_time, Default::default()),
+                    Cell::new(
+                        change.memo.as_deref().unwrap_or_default(),
+                        Default::default(),
+                    ),
                 ]));
             }
 
@@ -814,6 +854,7 @@ impl Command {
             table
                 .print_stdout()
                 .chain(|| (ErrorKind::IoError, "Unable to print table"))?;
+            println!("Total transactions: {}", history.total);
         } else {
             success("No history found!")
         }
@@ -821,17 +862,22 @@ impl Command {
         Ok(())
     }
 
-    fn resync<S: Storage, C: Client, O: TransactionObfuscation, L: Handle + Send + Sync + Clone>(
+    fn resync<
+        S: Storage,
+        C: Client + 'static,
+        O: TransactionObfuscation,
+        L: Handle + Send + Sync + Clone,
+    >(
         config: ObfuscationSyncerConfig<S, C, O, L>,
         name: String,
         enckey: SecKey,
         force: bool,
+        rescan_from: Option<u64>,
         storage: SledStorage,
     ) -> Result<()> {
         let wallet_client = get_wallet_client(storage)?;
 
         let mut init_block_height = 0;
-        let mut final_block_height = 0;
         let mut progress_bar = None;
         let progress_callback = move |report| {
             match report {
@@ -841,7 +887,6 @@ impl Command {
                     ..
                 } => {
                     init_block_height = start_block_height;
-                    final_block_height = finish_block_height;
                     progress_bar = Some(ProgressBar::new(finish_block_height - start_block_height));
 
                     let pb = progress_bar.as_mut().unwrap();
@@ -852,11 +897,16 @@ impl Command {
                     ..
                 } => {
                     if let Some(ref mut pb) = progress_bar {
-                        if current_block_height == final_block_height {
-                            pb.finish_print("Synchronization complete!");
-                        } else {
-                            pb.set(current_block_height - init_block_height);
-                        }
+                        pb.set(current_block_height - init_block_height);
+                    }
+                }
+                ProgressReport::StateChanged { .. } => {}
+                ProgressReport::Alert { alert, .. } => {
+                    error(&format!("\nAlert: {}", alert.message));
+                }
+                ProgressReport::Finish { .. } => {
+                    if let Some(ref mut pb) = progress_bar {
+                        pb.finish_print("Synchronization complete!");
                     }
                 }
             };
@@ -868,6 +918,9 @@ impl Command {
         if force {
             syncer.reset_state()?;
         }
+        if let Some(from_height) = rescan_from {
+            syncer.rescan_state(from_height)?;
+        }
         syncer.sync(progress_callback)
     }
 }
@@ -877,6 +930,52 @@ fn print_sync_warning() {
     println!();
 }
 
+/// Creates network operations client for given wallet, using the hardware wallet it was created
+/// with
+fn get_network_ops_client(wallet_name: &str, enckey: &SecKey) -> Result<AppNetworkOpsClient> {
+    let storage = SledStorage::new(storage_path())?;
+    let tendermint_client = WebsocketRpcClient::new(&tendermint_url())?;
+    let wallet_service = WalletService::new(storage.clone());
+    let wallet = wallet_service.get_wallet(wallet_name, enckey)?;
+    let hw_key_service = match wallet.hardware_kind {
+        #[cfg(feature = "mock-hardware-wallet")]
+        HardwareKind::Mock => HwKeyService::Mock(MockHardwareService::new()),
+        HardwareKind::Trezor => HwKeyService::default(),
+        HardwareKind::Ledger => {
+            let ledger_service = LedgerService::new(true)?;
+            HwKeyService::Ledger(ledger_service)
+        }
+        #[cfg(feature = "pkcs11")]
+        HardwareKind::Pkcs11 => HwKeyService::Pkcs11(Pkcs11Service::from_env()?),
+        #[cfg(not(feature = "pkcs11"))]
+        HardwareKind::Pkcs11 => return Err(pkcs11_disabled_error()),
+        HardwareKind::LocalOnly => HwKeyService::default(),
+    };
+    let signer_manager = WalletSignerManager::new(storage.clone(), hw_key_service.clone());
+    let fee_algorithm = tendermint_client.genesis()?.fee_policy();
+    let transaction_obfuscation = get_tx_query(tendermint_client.clone())?;
+    let transaction_builder = DefaultWalletTransactionBuilder::new(
+        signer_manager.clone(),
+        fee_algorithm,
+        transaction_obfuscation.clone(),
+    );
+
+    let wallet_client = DefaultWalletClient::new(
+        storage,
+        tendermint_client.clone(),
+        transaction_builder,
+        None,
+        hw_key_service,
+    );
+    Ok(DefaultNetworkOpsClient::new(
+        wallet_client,
+        signer_manager,
+        tendermint_client,
+        fee_algorithm,
+        transaction_obfuscation,
+    ))
+}
+
 fn get_wallet_client(storage: SledStorage) -> Result<AppWalletClient> {
     let tendermint_client = WebsocketRpcClient::new(&tendermint_url())?;
 
@@ -900,3 +999,11 @@ fn get_wallet_client(storage: SledStorage) -> Result<AppWalletClient> {
     );
     Ok(wallet_client)
 }
+
+#[cfg(not(feature = "pkcs11"))]
+fn pkcs11_disabled_error() -> client_common::Error {
+    client_common::Error::new(
+        ErrorKind::InitializationError,
+        "PKCS#11 support is not enabled, rebuild with `pkcs11` feature",
+    )
+}
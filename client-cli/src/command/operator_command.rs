@@ -0,0 +1,347 @@
+use std::convert::TryInto;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, NaiveDateTime, Utc};
+use cli_table::format::{CellFormat, Justify};
+use cli_table::{Cell, Row, Table};
+use quest::success;
+use structopt::StructOpt;
+
+use chain_core::common::Timespec;
+use chain_core::init::coin::Coin;
+use chain_core::init::network::get_network_id;
+use chain_core::state::account::{StakedStateAddress, StakedStateOpAttributes};
+use client_common::{Error, ErrorKind, Result, ResultExt, SecKey};
+use client_core::types::{ConsensusHealth, OperatorAction};
+use client_core::WalletAuditClient;
+use client_network::NetworkOpsClient;
+
+use super::transaction_command::{ask_node_metadata, deposit_amount_to_address};
+
+#[derive(Debug, StructOpt)]
+pub enum OperatorCommand {
+    #[structopt(
+        name = "link",
+        about = "Links the wallet to the council node operated with its staking address"
+    )]
+    Link {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "staking address",
+            short = "a",
+            long = "address",
+            help = "Staking address of the council node"
+        )]
+        address: StakedStateAddress,
+    },
+    #[structopt(
+        name = "status",
+        about = "Shows balance and consensus health of the linked council node"
+    )]
+    Status {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "window",
+            short = "w",
+            long = "window",
+            default_value = "100",
+            help = "Number of recent blocks to inspect for missed signatures"
+        )]
+        window: u64,
+    },
+    #[structopt(
+        name = "join",
+        about = "Joins the validator set with the linked council node"
+    )]
+    Join {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "keypackage",
+            long = "keypackage",
+            parse(from_os_str),
+            help = "Path to the keypackage file"
+        )]
+        keypackage: Option<PathBuf>,
+    },
+    #[structopt(
+        name = "rotate-key",
+        about = "Re-joins the validator set with a new consensus key"
+    )]
+    RotateKey {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "keypackage",
+            long = "keypackage",
+            parse(from_os_str),
+            help = "Path to the keypackage file"
+        )]
+        keypackage: Option<PathBuf>,
+    },
+    #[structopt(name = "unjail", about = "Unjails the linked council node")]
+    Unjail {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+    },
+    #[structopt(
+        name = "top-up",
+        about = "Deposits more stake to the bonded amount of the linked council node"
+    )]
+    TopUp {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+    },
+}
+
+impl OperatorCommand {
+    pub fn wallet_name(&self) -> String {
+        match self {
+            OperatorCommand::Link { name, .. } => name.clone(),
+            OperatorCommand::Status { name, .. } => name.clone(),
+            OperatorCommand::Join { name, .. } => name.clone(),
+            OperatorCommand::RotateKey { name, .. } => name.clone(),
+            OperatorCommand::Unjail { name } => name.clone(),
+            OperatorCommand::TopUp { name } => name.clone(),
+        }
+    }
+
+    pub fn execute<T: WalletAuditClient, N: NetworkOpsClient>(
+        &self,
+        wallet_client: &T,
+        network_ops_client: &N,
+        enckey: SecKey,
+    ) -> Result<()> {
+        match self {
+            OperatorCommand::Link { name, address } => {
+                wallet_client.set_operator_profile(name, &enckey, *address)?;
+                success(&format!(
+                    "Wallet {} is linked to council node {}",
+                    name, address
+                ));
+                Ok(())
+            }
+            OperatorCommand::Status { name, window } => {
+                let address = linked_address(wallet_client, name)?;
+                let balance = wallet_client.balance(name, &enckey)?;
+                let health = network_ops_client.get_consensus_health(name, &address, *window)?;
+                print_status(balance.available, &health)
+            }
+            OperatorCommand::Join { name, keypackage } => node_join(
+                wallet_client,
+                network_ops_client,
+                name,
+                &enckey,
+                OperatorAction::NodeJoin,
+                keypackage.clone(),
+            ),
+            OperatorCommand::RotateKey { name, keypackage } => node_join(
+                wallet_client,
+                network_ops_client,
+                name,
+                &enckey,
+                OperatorAction::RotateKey,
+                keypackage.clone(),
+            ),
+            OperatorCommand::Unjail { name } => {
+                let address = linked_address(wallet_client, name)?;
+                network_ops_client.check_operator_action(name, &address, OperatorAction::Unjail)?;
+                let attributes = StakedStateOpAttributes::new(get_network_id());
+                let tx_aux = network_ops_client
+                    .create_unjail_transaction(name, &enckey, address, attributes, true)?;
+                wallet_client.broadcast_transaction(&tx_aux)?;
+                success(&format!(
+                    "Unjail transaction broadcasted, transaction id is: {}",
+                    hex::encode(tx_aux.tx_id())
+                ));
+                Ok(())
+            }
+            OperatorCommand::TopUp { name } => {
+                let address = linked_address(wallet_client, name)?;
+                network_ops_client.check_operator_action(
+                    name,
+                    &address,
+                    OperatorAction::TopUpBond,
+                )?;
+                deposit_amount_to_address(wallet_client, network_ops_client, name, &enckey, address)
+            }
+        }
+    }
+}
+
+fn linked_address<T: WalletAuditClient>(
+    wallet_client: &T,
+    name: &str,
+) -> Result<StakedStateAddress> {
+    wallet_client
+        .operator_profile(name)?
+        .map(|profile| profile.staking_address)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Wallet {} is not linked to a council node, use `client-cli operator link` first",
+                    name
+                ),
+            )
+        })
+}
+
+/// Node join flow, which is also used to rotate the consensus key of an inactive council node
+fn node_join<T: WalletAuditClient, N: NetworkOpsClient>(
+    wallet_client: &T,
+    network_ops_client: &N,
+    name: &str,
+    enckey: &SecKey,
+    action: OperatorAction,
+    keypackage: Option<PathBuf>,
+) -> Result<()> {
+    let address = linked_address(wallet_client, name)?;
+    network_ops_client.check_operator_action(name, &address, action)?;
+
+    let attributes = StakedStateOpAttributes::new(get_network_id());
+    let node_metadata = ask_node_metadata(keypackage)?;
+    let tx_aux = network_ops_client.create_node_join_transaction(
+        name,
+        enckey,
+        address,
+        attributes,
+        node_metadata,
+        true,
+    )?;
+    wallet_client.broadcast_transaction(&tx_aux)?;
+    success(&format!(
+        "Node join transaction broadcasted, transaction id is: {}",
+        hex::encode(tx_aux.tx_id())
+    ));
+    Ok(())
+}
+
+fn print_status(available: Coin, health: &ConsensusHealth) -> Result<()> {
+    let bold = CellFormat::builder().bold(true).build();
+    let justify_right = CellFormat::builder().justify(Justify::Right).build();
+
+    let status = if !health.is_council_node {
+        "Not a council node".to_owned()
+    } else if health.jailed_until.is_some() {
+        "Jailed".to_owned()
+    } else if health.is_active {
+        "Active".to_owned()
+    } else {
+        "Inactive".to_owned()
+    };
+    let rank = health
+        .voting_power_rank
+        .map(|rank| format!("{} / {}", rank, health.validator_count))
+        .unwrap_or_else(|| format!("- / {}", health.validator_count));
+
+    let table = Table::new(
+        vec![
+            Row::new(vec![
+                Cell::new("Staking Address", bold),
+                Cell::new(&health.staking_address, justify_right),
+            ]),
+            Row::new(vec![
+                Cell::new("Available Balance", bold),
+                Cell::new(&available, justify_right),
+            ]),
+            Row::new(vec![
+                Cell::new("Bonded", bold),
+                Cell::new(&health.bonded, justify_right),
+            ]),
+            Row::new(vec![
+                Cell::new("Unbonded", bold),
+                Cell::new(&health.unbonded, justify_right),
+            ]),
+            Row::new(vec![
+                Cell::new("Required Stake", bold),
+                Cell::new(&health.required_stake, justify_right),
+            ]),
+            Row::new(vec![
+                Cell::new("Status", bold),
+                Cell::new(&status, justify_right),
+            ]),
+            Row::new(vec![
+                Cell::new("Jailed Until", bold),
+                health.jailed_until.map_or_else(
+                    || Cell::new("Not jailed", justify_right),
+                    |jailed_until| Cell::new(&format_time(jailed_until), justify_right),
+                ),
+            ]),
+            Row::new(vec![
+                Cell::new("Voting Power Rank", bold),
+                Cell::new(&rank, justify_right),
+            ]),
+            Row::new(vec![
+                Cell::new("Missed Blocks", bold),
+                Cell::new(
+                    &format!("{} / {}", health.missed_blocks, health.window),
+                    justify_right,
+                ),
+            ]),
+            Row::new(vec![
+                Cell::new("Last Slash", bold),
+                health.last_slash.as_ref().map_or_else(
+                    || Cell::new("Not slashed", justify_right),
+                    |slash| {
+                        Cell::new(
+                            &format!(
+                                "{} {} ({})",
+                                slash.kind,
+                                slash.amount,
+                                format_time(slash.time)
+                            ),
+                            justify_right,
+                        )
+                    },
+                ),
+            ]),
+        ],
+        Default::default(),
+    )
+    .chain(|| (ErrorKind::InternalError, "Unable to create new table"))?;
+
+    table
+        .print_stdout()
+        .chain(|| (ErrorKind::IoError, "Unable to print table"))
+}
+
+fn format_time(time: Timespec) -> String {
+    <DateTime<Local>>::from(DateTime::<Utc>::from_utc(
+        NaiveDateTime::from_timestamp(time.try_into().unwrap(), 0),
+        Utc,
+    ))
+    .to_string()
+}
@@ -0,0 +1,123 @@
+use cli_table::format::{CellFormat, Justify};
+use cli_table::{Cell, Row, Table};
+use quest::{ask, success, yesno};
+use structopt::StructOpt;
+
+use client_common::{Error, ErrorKind, Result, ResultExt, Storage};
+use client_core::service::{OrphanKind, OrphanReport, StorageMaintenanceService};
+
+#[derive(Debug, StructOpt)]
+pub enum StorageCommand {
+    #[structopt(name = "stats", about = "Shows size on disk and number of records")]
+    Stats,
+    #[structopt(
+        name = "gc",
+        about = "Deletes orphaned records (data of deleted wallets, expired locks)"
+    )]
+    Gc {
+        #[structopt(
+            name = "dry run",
+            long = "dry-run",
+            help = "Only report orphaned records without deleting them"
+        )]
+        dry_run: bool,
+    },
+}
+
+impl StorageCommand {
+    pub fn execute<S: Storage>(&self, storage: S) -> Result<()> {
+        let service = StorageMaintenanceService::new(storage);
+        match self {
+            StorageCommand::Stats => Self::stats(&service),
+            StorageCommand::Gc { dry_run } => Self::gc(&service, *dry_run),
+        }
+    }
+
+    fn stats<S: Storage>(service: &StorageMaintenanceService<S>) -> Result<()> {
+        let stats = service.stats()?;
+        let justify_right = CellFormat::builder().justify(Justify::Right).build();
+        let table = Table::new(
+            vec![
+                Row::new(vec![
+                    Cell::new("Size on disk (bytes)", Default::default()),
+                    Cell::new(&stats.size_on_disk, justify_right),
+                ]),
+                Row::new(vec![
+                    Cell::new("Keyspaces", Default::default()),
+                    Cell::new(&stats.keyspaces, justify_right),
+                ]),
+                Row::new(vec![
+                    Cell::new("Records", Default::default()),
+                    Cell::new(&stats.records, justify_right),
+                ]),
+            ],
+            Default::default(),
+        )
+        .chain(|| (ErrorKind::InternalError, "Unable to create new table"))?;
+
+        table
+            .print_stdout()
+            .chain(|| (ErrorKind::IoError, "Unable to print table"))
+    }
+
+    fn gc<S: Storage>(service: &StorageMaintenanceService<S>, dry_run: bool) -> Result<()> {
+        let report = service.scan()?;
+        if report.records.is_empty() {
+            success("No orphaned records found");
+            return Ok(());
+        }
+        print_report(&report)?;
+        if dry_run {
+            return Ok(());
+        }
+
+        ask(&format!(
+            "Delete {} orphaned records ({} bytes)? [yN] ",
+            report.records.len(),
+            report.reclaimable
+        ));
+        match yesno(false).chain(|| (ErrorKind::IoError, "Unable to read yes/no"))? {
+            Some(true) => {
+                let deleted = service.collect(&report.records)?;
+                success(&format!(
+                    "Deleted {} orphaned records ({} bytes)",
+                    deleted.records.len(),
+                    deleted.reclaimable
+                ));
+                Ok(())
+            }
+            _ => Err(Error::new(ErrorKind::InvalidInput, "User canceled")),
+        }
+    }
+}
+
+fn print_report(report: &OrphanReport) -> Result<()> {
+    let bold = CellFormat::builder().bold(true).build();
+    let justify_right = CellFormat::builder().justify(Justify::Right).build();
+
+    let mut rows = Vec::with_capacity(report.records.len() + 1);
+    rows.push(Row::new(vec![
+        Cell::new("Keyspace", bold),
+        Cell::new("Key", bold),
+        Cell::new("Reason", bold),
+        Cell::new("Size (bytes)", bold),
+    ]));
+    for record in report.records.iter() {
+        let reason = match record.kind {
+            OrphanKind::DeletedWallet => "deleted wallet",
+            OrphanKind::ExpiredLock => "expired lock",
+        };
+        rows.push(Row::new(vec![
+            Cell::new(&record.keyspace, Default::default()),
+            Cell::new(record.key.as_deref().unwrap_or("*"), Default::default()),
+            Cell::new(reason, Default::default()),
+            Cell::new(&record.size, justify_right),
+        ]));
+    }
+
+    let table = Table::new(rows, Default::default())
+        .chain(|| (ErrorKind::InternalError, "Unable to create new table"))?;
+    table
+        .print_stdout()
+        .chain(|| (ErrorKind::IoError, "Unable to print table"))
+}
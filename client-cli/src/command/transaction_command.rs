@@ -13,16 +13,16 @@ use chain_core::state::account::{
     ConfidentialInit, CouncilNodeMeta, MLSInit, StakedStateAddress, StakedStateOpAttributes,
 };
 use chain_core::state::tendermint::TendermintValidatorPubKey;
-use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
-use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
 use chain_core::tx::TxAux;
 use client_common::{Error, ErrorKind, PublicKey, Result, ResultExt, SecKey, Transaction};
-use client_core::transaction_builder::SignedTransferTransaction;
-use client_core::types::{BalanceChange, TransactionPending};
-use client_core::WalletClient;
+use client_core::transaction_builder::{AttributesBuilder, SignedTransferTransaction};
+use client_core::types::{
+    parse_annotations, AnnotationConflict, AnnotationFormat, BalanceChange, TransactionPending,
+};
+use client_core::{WalletAuditClient, WalletClient};
 use client_network::NetworkOpsClient;
 use mls::{Codec, DefaultCipherSuite, KeyPackage};
 
@@ -30,7 +30,7 @@ use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 use cli_table::format::{CellFormat, Color, Justify};
 use cli_table::{Cell, Row, Table};
 use hex::decode;
-use quest::{ask, success, text, yesno};
+use quest::{ask, error, success, text, yesno};
 use structopt::StructOpt;
 use unicase::eq_ascii;
 
@@ -241,6 +241,43 @@ pub enum TransactionCommand {
         )]
         file: PathBuf,
     },
+    #[structopt(
+        name = "import-annotations",
+        about = "Import transaction annotations from a CSV or JSON file"
+    )]
+    ImportAnnotations {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "file",
+            short = "f",
+            long = "file",
+            parse(from_os_str),
+            help = "annotation file, CSV needs a header row with a txid column"
+        )]
+        file: PathBuf,
+        #[structopt(
+            name = "format",
+            long = "format",
+            possible_values = &["csv", "json"],
+            default_value = "csv",
+            help = "Format of annotation file"
+        )]
+        format: AnnotationFormat,
+        #[structopt(
+            name = "conflict",
+            long = "conflict",
+            possible_values = &["keep_existing", "overwrite", "merge"],
+            default_value = "keep_existing",
+            help = "How to combine imported annotations with existing ones"
+        )]
+        conflict: AnnotationConflict,
+    },
 }
 
 impl TransactionCommand {
@@ -253,9 +290,10 @@ impl TransactionCommand {
             TransactionCommand::Build { name, .. } => name.clone(),
             TransactionCommand::Export { name, .. } => name.clone(),
             TransactionCommand::Sign { name, .. } => name.clone(),
+            TransactionCommand::ImportAnnotations { name, .. } => name.clone(),
         }
     }
-    pub fn execute<T: WalletClient, N: NetworkOpsClient>(
+    pub fn execute<T: WalletAuditClient, N: NetworkOpsClient>(
         &self,
         wallet_client: &T,
         network_ops_client: &N,
@@ -339,6 +377,28 @@ impl TransactionCommand {
                 success(hex::encode(tx_id).as_str());
                 Ok(())
             }
+            TransactionCommand::ImportAnnotations {
+                name,
+                file,
+                format,
+                conflict,
+            } => {
+                let content = std::fs::read_to_string(file)
+                    .chain(|| (ErrorKind::IoError, "Unable to read from file"))?;
+                let entries = parse_annotations(*format, &content)?;
+                let report = wallet_client.import_annotations(name, &enckey, entries, *conflict)?;
+                success(&format!(
+                    "Imported annotations: {} added, {} updated, {} unchanged",
+                    report.added, report.updated, report.unchanged
+                ));
+                for txid in report.unknown_transactions.iter() {
+                    error(&format!(
+                        "Skipped transaction not in wallet history: {}",
+                        txid
+                    ));
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -604,20 +664,10 @@ fn new_withdraw_transaction<T: WalletClient, N: NetworkOpsClient>(
 ) -> Result<(TxAux, TransactionPending)> {
     let from_address = ask_staking_address()?;
     let to_address = ask_transfer_address()?;
-    let mut view_keys = ask_view_keys()?;
-    let self_view_key = wallet_client.view_key(name, enckey)?;
-    view_keys.push(self_view_key);
-
-    let access_policies: BTreeSet<_> = view_keys
-        .iter()
-        .map(|key| TxAccessPolicy {
-            view_key: key.into(),
-            access: TxAccess::AllData,
-        })
-        .collect();
-
-    let attributes =
-        TxAttributes::new_with_access(get_network_id(), access_policies.into_iter().collect());
+    let attributes = AttributesBuilder::new()
+        .view_keys(ask_view_keys()?)
+        .wallet_view_key(wallet_client, name, enckey)?
+        .build()?;
 
     network_ops_client.create_withdraw_all_unbonded_stake_transaction(
         name,
@@ -744,6 +794,24 @@ fn new_deposit_amount_transaction<T: WalletClient, N: NetworkOpsClient>(
         enckey,
         &to_staking_address,
     )?;
+    deposit_amount_to_address(
+        wallet_client,
+        network_ops_client,
+        name,
+        enckey,
+        to_staking_address,
+    )
+}
+
+/// Asks for an amount, makes a UTXO worth that amount (plus deposit fee) and deposits it to
+/// given staking address
+pub(crate) fn deposit_amount_to_address<T: WalletClient, N: NetworkOpsClient>(
+    wallet_client: &T,
+    network_ops_client: &N,
+    name: &str,
+    enckey: &SecKey,
+    to_staking_address: StakedStateAddress,
+) -> Result<()> {
     let attr = StakedStateOpAttributes::new(get_network_id());
     let amount = ask_cro()?;
     let fee = network_ops_client.calculate_deposit_fee()?;
@@ -809,30 +877,23 @@ fn new_transfer_transaction<T: WalletClient>(
     enckey: &SecKey,
 ) -> Result<(TxAux, TransactionPending)> {
     let outputs = ask_outputs()?;
-    let mut view_keys = ask_view_keys()?;
-    let self_view_key = wallet_client.view_key(name, enckey)?;
-    view_keys.push(self_view_key);
-    let access_policies: BTreeSet<_> = view_keys
-        .iter()
-        .map(|key| TxAccessPolicy {
-            view_key: key.into(),
-            access: TxAccess::AllData,
-        })
-        .collect();
-
-    let attributes =
-        TxAttributes::new_with_access(get_network_id(), access_policies.into_iter().collect());
+    let attributes = AttributesBuilder::new()
+        .view_keys(ask_view_keys()?)
+        .wallet_view_key(wallet_client, name, enckey)?
+        .build()?;
 
     let return_address = wallet_client.new_transfer_address(name, &enckey)?;
 
-    let (transaction, used_inputs, return_amount) = wallet_client.create_transaction(
+    let (transaction, reference, used_inputs, return_amount) = wallet_client.create_transaction(
         name,
         &enckey,
         outputs,
         attributes,
         None,
+        None,
         return_address,
     )?;
+    success(&format!("transaction id is: {}", reference));
     let tx_pending = TransactionPending {
         block_height: wallet_client.get_current_block_height()?,
         used_inputs,
@@ -1151,7 +1212,7 @@ fn ask_keypackage() -> Result<Vec<u8>> {
 }
 
 /// FIXME: take Add + Commit instead of keypackage
-fn ask_node_metadata(keypackage: Option<PathBuf>) -> Result<CouncilNodeMeta> {
+pub(crate) fn ask_node_metadata(keypackage: Option<PathBuf>) -> Result<CouncilNodeMeta> {
     ask("Enter validator node name: ");
     let name = text().chain(|| (ErrorKind::IoError, "Unable to read validator node name"))?;
 
@@ -76,9 +76,9 @@ pub(crate) fn ask_seckey(message: Option<&str>) -> Result<SecKey> {
 
 pub(crate) fn ask_hardware_kind(message: Option<&str>) -> Result<HardwareKind> {
     let s = if cfg!(feature = "mock-hardware-wallet") {
-        "Which hardware wallet type do you have: ledger|trezor|mock: "
+        "Which hardware wallet type do you have: ledger|trezor|pkcs11|mock: "
     } else {
-        "Which hardware wallet type do you have: ledger|trezor: "
+        "Which hardware wallet type do you have: ledger|trezor|pkcs11: "
     };
     ask(message.unwrap_or(s));
     let key = text().err_kind(ErrorKind::InvalidInput, || {
@@ -2,7 +2,8 @@
 use std::convert::TryInto;
 
 use crate::tendermint::Client;
-use crate::{PrivateKey, Result, SignedTransaction, Transaction};
+use crate::{Error, ErrorKind, PrivateKey, Result, SignedTransaction, Transaction};
+use chain_core::init::network::{get_network, Network};
 use chain_core::tx::data::TxId;
 use chain_core::tx::{TxAux, TxEnclaveAux, TxWithOutputs};
 use mock_utils::{encrypt, unseal};
@@ -27,8 +28,15 @@ where
     pub fn new(client: C) -> Self {
         Self { client }
     }
-    /// Same constructor as `DefaultTransactionObfuscation`
+    /// Same constructor as `DefaultTransactionObfuscation`,
+    /// refuses to run on mainnet where transactions are only obfuscated by the enclaves
     pub fn from_tx_query(client: &C) -> Result<Self> {
+        if let Network::Mainnet = get_network() {
+            return Err(Error::new(
+                ErrorKind::InitializationError,
+                "Mock transaction obfuscation can't be used on mainnet",
+            ));
+        }
         Ok(Self::new(client.clone()))
     }
 }
@@ -135,6 +143,17 @@ mod tests {
             unreachable!()
         }
 
+        fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+            &self,
+            _transactions: T,
+        ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+            unreachable!()
+        }
+
+        fn unconfirmed_txs(&self, _limit: u64) -> Result<UnconfirmedTxsResponse> {
+            unreachable!()
+        }
+
         fn query(
             &self,
             _path: &str,
@@ -0,0 +1,93 @@
+//! Time source used wherever wall-clock time affects behavior
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of wall-clock time
+pub trait Clock: Debug + Send + Sync {
+    /// Returns current time
+    fn now(&self) -> SystemTime;
+
+    /// Returns current unix timestamp (in seconds)
+    fn unix_timestamp(&self) -> u64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+/// Clock backed by system time
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Manually controlled clock for deterministic tests
+///
+/// Clones share the same time, so a clock handed over to a service can still be advanced by the
+/// test.
+#[derive(Debug, Default, Clone)]
+pub struct MockClock {
+    unix_timestamp: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    /// Creates a new clock set to given unix timestamp (in seconds)
+    pub fn new(unix_timestamp: u64) -> Self {
+        Self {
+            unix_timestamp: Arc::new(AtomicU64::new(unix_timestamp)),
+        }
+    }
+
+    /// Sets current unix timestamp (in seconds)
+    pub fn set(&self, unix_timestamp: u64) {
+        self.unix_timestamp.store(unix_timestamp, Ordering::SeqCst);
+    }
+
+    /// Moves the clock forward by given number of seconds
+    pub fn advance(&self, secs: u64) {
+        self.unix_timestamp.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.unix_timestamp.load(Ordering::SeqCst))
+    }
+
+    #[inline]
+    fn unix_timestamp(&self) -> u64 {
+        self.unix_timestamp.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_mock_clock() {
+        let clock = MockClock::new(1_000);
+        let shared = clock.clone();
+        assert_eq!(1_000, shared.unix_timestamp());
+
+        clock.advance(5);
+        assert_eq!(1_005, shared.unix_timestamp());
+        assert_eq!(UNIX_EPOCH + Duration::from_secs(1_005), shared.now());
+
+        clock.set(10);
+        assert_eq!(10, shared.unix_timestamp());
+    }
+
+    #[test]
+    fn check_system_clock() {
+        assert!(SystemClock.unix_timestamp() > 0);
+    }
+}
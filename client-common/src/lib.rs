@@ -8,6 +8,7 @@
 mod transaction;
 
 pub mod cipher;
+pub mod clock;
 pub mod error;
 pub mod key;
 pub mod multi_sig_address;
@@ -18,6 +19,8 @@ pub mod tendermint;
 #[doc(inline)]
 pub use crate::cipher::TransactionObfuscation;
 #[doc(inline)]
+pub use clock::{Clock, SystemClock};
+#[doc(inline)]
 pub use error::{Error, ErrorKind, Result, ResultExt};
 #[doc(inline)]
 pub use key::{PrivateKey, PrivateKeyAction, PublicKey};
@@ -6,6 +6,7 @@ use parity_scale_codec::{Decode, Encode};
 
 use super::{Error, ErrorKind, PublicKey, Result};
 use chain_core::common::{MerkleTree, Proof, H256};
+use chain_core::tx::canonical::sort_signers;
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::witness::tree::RawXOnlyPubkey;
 use secp256k1::key::XOnlyPublicKey;
@@ -148,7 +149,7 @@ impl MultiSigAddress {
             ));
         }
 
-        public_keys.sort();
+        sort_signers(&mut public_keys);
 
         let raw_pubkey = if public_keys.len() == 1 {
             Ok(RawXOnlyPubkey::from(
@@ -225,7 +226,7 @@ fn public_key_combinations(
                 .into_iter()
                 .combinations(required_signers)
                 .map(|mut combination| {
-                    combination.sort();
+                    sort_signers(&mut combination);
                     PublicKey::combine_to_raw_pubkey(&combination)
                 })
                 .collect::<Result<Vec<RawXOnlyPubkey>>>()?;
@@ -3,11 +3,16 @@ use std::str::FromStr;
 
 use aes::{Aes256, NewBlockCipher};
 use aes_gcm_siv::aead::generic_array::GenericArray;
+use argon2::{ThreadMode, Variant, Version};
+use parity_scale_codec::{Decode, Encode};
 use secstr::{SecBox, SecUtf8};
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use zeroize::Zeroize;
 
-use crate::{Error, ErrorKind, Result};
+use crate::{Error, ErrorKind, Result, Storage};
+
+/// Keyspace of the key derivation configs of wallets (keyed by wallet name)
+pub const KDF_CONFIG_KEYSPACE: &str = "common_wallet_kdf_config";
 
 /// Encryption key size
 pub type SecKeySize = <Aes256 as NewBlockCipher>::KeySize;
@@ -47,20 +52,137 @@ pub fn parse_hex_enckey(s: &str) -> Result<SecKey> {
 const GLOBAL_DATA_CONTEXT: &str =
     "Crypto.com Chain Wallet 2020-03-30 16:59:10 global wallet data encryption";
 const SALT_CONTEXT: &str = "Crypto.com Chain Wallet 2020-03-30 16:59:10 salt from wallet name";
+const SHARED_KEY_CONTEXT: &str =
+    "Crypto.com Chain Wallet 2020-03-30 16:59:10 key shared by paired devices";
+
+/// Password hashing algorithm of the key derivation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KdfAlgorithm {
+    /// Argon2i (used by wallets created before the key derivation was configurable)
+    Argon2i,
+    /// Argon2id
+    Argon2id,
+}
+
+/// Key derivation parameters of a wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct KdfConfig {
+    /// password hashing algorithm
+    pub algorithm: KdfAlgorithm,
+    /// memory cost in KiB
+    pub memory_kib: u32,
+    /// number of passes
+    pub iterations: u32,
+    /// degree of parallelism (number of lanes)
+    pub parallelism: u32,
+}
+
+impl Default for KdfConfig {
+    /// Parameters of the key derivation before it was configurable
+    fn default() -> Self {
+        KdfConfig {
+            algorithm: KdfAlgorithm::Argon2i,
+            memory_kib: 4096,
+            iterations: 3,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfConfig {
+    /// Argon2id with given memory cost (KiB), passes and parallelism
+    pub fn argon2id(memory_kib: u32, iterations: u32, parallelism: u32) -> Self {
+        KdfConfig {
+            algorithm: KdfAlgorithm::Argon2id,
+            memory_kib,
+            iterations,
+            parallelism,
+        }
+    }
+
+    /// Checks the parameters against the bounds of Argon2
+    pub fn validate(&self) -> Result<()> {
+        if self.iterations == 0
+            || self.parallelism == 0
+            || self.parallelism > 0x00ff_ffff
+            || u64::from(self.memory_kib) < 8 * u64::from(self.parallelism)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid key derivation parameters",
+            ));
+        }
+        Ok(())
+    }
+
+    fn to_argon2(&self) -> argon2::Config<'static> {
+        argon2::Config {
+            variant: match self.algorithm {
+                KdfAlgorithm::Argon2i => Variant::Argon2i,
+                KdfAlgorithm::Argon2id => Variant::Argon2id,
+            },
+            version: Version::Version13,
+            mem_cost: self.memory_kib,
+            time_cost: self.iterations,
+            lanes: self.parallelism,
+            // lanes are computed sequentially, it doesn't change the derived key
+            thread_mode: ThreadMode::Sequential,
+            secret: &[],
+            ad: &[],
+            hash_length: 32,
+        }
+    }
+}
 
 /// derive encryption key from passphrase
 /// FIXME: derivation should derive multiple keys, e.g. for view/sync and spending operations
 pub fn derive_enckey(passphrase: &SecUtf8, name: &str) -> argon2::Result<SecKey> {
+    derive_enckey_with(passphrase, name, &KdfConfig::default())
+}
+
+/// derive encryption key from passphrase with given key derivation parameters
+pub fn derive_enckey_with(
+    passphrase: &SecUtf8,
+    name: &str,
+    kdf_config: &KdfConfig,
+) -> argon2::Result<SecKey> {
     let mut salt = [0; 32];
     blake3::derive_key(SALT_CONTEXT, name.as_bytes(), &mut salt);
-    let mut extended =
-        argon2::hash_raw(passphrase.unsecure().as_bytes(), &salt, &Default::default())?;
+    let mut extended = argon2::hash_raw(
+        passphrase.unsecure().as_bytes(),
+        &salt,
+        &kdf_config.to_argon2(),
+    )?;
     let mut arr = GenericArray::clone_from_slice(&[0; 32]);
     blake3::derive_key(GLOBAL_DATA_CONTEXT, &extended, &mut arr);
     extended.zeroize();
     Ok(SecKey(SecBox::new(Box::new(arr))))
 }
 
+/// Returns the key derivation parameters of a wallet (the default ones if not stored)
+pub fn load_kdf_config<S: Storage>(storage: &S, name: &str) -> Result<KdfConfig> {
+    Ok(storage.load(KDF_CONFIG_KEYSPACE, name)?.unwrap_or_default())
+}
+
+/// Stores the key derivation parameters of a wallet
+pub fn save_kdf_config<S: Storage>(storage: &S, name: &str, kdf_config: &KdfConfig) -> Result<()> {
+    kdf_config.validate()?;
+    storage.save(KDF_CONFIG_KEYSPACE, name, kdf_config)
+}
+
+/// Deletes the key derivation parameters of a wallet
+pub fn delete_kdf_config<S: Storage>(storage: &S, name: &str) -> Result<()> {
+    storage.delete(KDF_CONFIG_KEYSPACE, name).map(|_| ())
+}
+
+/// derive encryption key shared by two devices from their ECDH shared secret
+pub fn derive_shared_enckey(shared_secret: &[u8]) -> SecKey {
+    let mut arr = GenericArray::clone_from_slice(&[0; 32]);
+    blake3::derive_key(SHARED_KEY_CONTEXT, shared_secret, &mut arr);
+    SecKey(SecBox::new(Box::new(arr)))
+}
+
 impl<'de> Deserialize<'de> for SecKey {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
@@ -96,6 +218,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_kdf_config() {
+        let passphrase = SecUtf8::from("passphrase");
+        // wallets without stored parameters keep their key
+        assert_eq!(
+            derive_enckey(&passphrase, "Wallet").unwrap(),
+            derive_enckey_with(&passphrase, "Wallet", &KdfConfig::default()).unwrap()
+        );
+        let kdf_config = KdfConfig::argon2id(8192, 2, 2);
+        assert_ne!(
+            derive_enckey(&passphrase, "Wallet").unwrap(),
+            derive_enckey_with(&passphrase, "Wallet", &kdf_config).unwrap()
+        );
+
+        let storage = crate::storage::MemoryStorage::default();
+        assert_eq!(
+            KdfConfig::default(),
+            load_kdf_config(&storage, "Wallet").unwrap()
+        );
+        save_kdf_config(&storage, "Wallet", &kdf_config).unwrap();
+        assert_eq!(kdf_config, load_kdf_config(&storage, "Wallet").unwrap());
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            save_kdf_config(&storage, "Wallet", &KdfConfig::argon2id(8192, 0, 1))
+                .unwrap_err()
+                .kind()
+        );
+        delete_kdf_config(&storage, "Wallet").unwrap();
+        assert_eq!(
+            KdfConfig::default(),
+            load_kdf_config(&storage, "Wallet").unwrap()
+        );
+    }
+
     quickcheck! {
         fn check_serialization(passphrase: String, name: String) -> bool {
             let key = derive_enckey(&SecUtf8::from(passphrase), &name).unwrap();
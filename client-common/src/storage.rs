@@ -1,10 +1,15 @@
 //! Data storage layer
+mod instrumented_storage;
 mod memory_storage;
 #[cfg(feature = "sled")]
 mod sled_storage;
 mod unauthorized_storage;
 use parity_scale_codec::{Decode, Encode};
 
+pub use instrumented_storage::{
+    InstrumentedStorage, OperationStats, SlowOperationLogger, StorageEvent, StorageObserver,
+    StorageOperation, StorageStats,
+};
 pub use memory_storage::MemoryStorage;
 #[cfg(feature = "sled")]
 pub use sled_storage::SledStorage;
@@ -61,6 +66,9 @@ pub trait Storage: Send + Sync + Clone {
     /// Returns all the keyspaces currently available.
     fn keyspaces(&self) -> Result<Vec<Vec<u8>>>;
 
+    /// Returns the (approximate) number of bytes occupied by the storage.
+    fn size_on_disk(&self) -> Result<u64>;
+
     /// load and deserialize object
     fn load<T: Decode>(&self, keyspace: &str, key: &str) -> Result<Option<T>> {
         if let Some(bytes) = self.get(keyspace, key)? {
@@ -237,6 +245,12 @@ pub fn encrypt_bytes<K: AsRef<[u8]>>(key: K, enckey: &SecKey, bytes: &[u8]) -> R
 
 /// Decrypts bytes with given enckey
 pub fn decrypt_bytes<K: AsRef<[u8]>>(key: K, enckey: &SecKey, bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.len() < NONCE_SIZE {
+        return Err(Error::new(
+            ErrorKind::DecryptionError,
+            "Incorrect enckey: Unable to unlock stored values",
+        ));
+    }
     let algo = get_algo(enckey);
 
     let payload = Payload {
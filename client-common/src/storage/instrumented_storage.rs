@@ -0,0 +1,393 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{Result, Storage};
+
+/// Operation of a `Storage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StorageOperation {
+    /// `clear`
+    Clear,
+    /// `get`
+    Get,
+    /// `set`
+    Set,
+    /// `delete`
+    Delete,
+    /// `fetch_and_update`
+    FetchAndUpdate,
+    /// `keys`
+    Keys,
+    /// `contains_key`
+    ContainsKey,
+    /// `keyspaces`
+    Keyspaces,
+    /// `size_on_disk`
+    SizeOnDisk,
+    /// `flush`
+    Flush,
+}
+
+impl fmt::Display for StorageOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StorageOperation::Clear => "clear",
+            StorageOperation::Get => "get",
+            StorageOperation::Set => "set",
+            StorageOperation::Delete => "delete",
+            StorageOperation::FetchAndUpdate => "fetch_and_update",
+            StorageOperation::Keys => "keys",
+            StorageOperation::ContainsKey => "contains_key",
+            StorageOperation::Keyspaces => "keyspaces",
+            StorageOperation::SizeOnDisk => "size_on_disk",
+            StorageOperation::Flush => "flush",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Measurement of a storage operation
+#[derive(Debug, Clone, Copy)]
+pub struct StorageEvent<'a> {
+    /// Operation
+    pub operation: StorageOperation,
+    /// Keyspace of the operation (empty for operations on the whole storage)
+    pub keyspace: &'a [u8],
+    /// Time spent in the underlying storage
+    pub duration: Duration,
+    /// Bytes read or written (values, or keys for `keys`)
+    pub payload_size: usize,
+    /// Whether the operation succeeded
+    pub success: bool,
+}
+
+/// Receives the measurements of storage operations (e.g. to export them as metrics or tracing
+/// spans)
+pub trait StorageObserver: Send + Sync {
+    /// Called after each storage operation
+    fn observe(&self, event: &StorageEvent<'_>);
+}
+
+/// Logs a warning for operations slower than a threshold
+#[derive(Debug, Clone, Copy)]
+pub struct SlowOperationLogger {
+    threshold: Duration,
+}
+
+impl SlowOperationLogger {
+    /// Creates a new instance of `SlowOperationLogger`
+    pub fn new(threshold: Duration) -> Self {
+        Self { threshold }
+    }
+}
+
+impl StorageObserver for SlowOperationLogger {
+    fn observe(&self, event: &StorageEvent<'_>) {
+        if event.duration >= self.threshold {
+            log::warn!(
+                "Slow storage operation: {} on keyspace {} took {:?} ({} bytes)",
+                event.operation,
+                String::from_utf8_lossy(event.keyspace),
+                event.duration,
+                event.payload_size
+            );
+        }
+    }
+}
+
+/// Aggregated measurements of an operation on a keyspace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperationStats {
+    /// Number of operations
+    pub count: u64,
+    /// Number of failed operations
+    pub failures: u64,
+    /// Total time spent
+    pub total_duration: Duration,
+    /// Longest operation
+    pub max_duration: Duration,
+    /// Total bytes read or written
+    pub payload_size: u64,
+}
+
+/// Aggregates the measurements by operation and keyspace (e.g. for a metrics exporter)
+#[derive(Debug, Default)]
+pub struct StorageStats {
+    stats: Mutex<HashMap<(StorageOperation, Vec<u8>), OperationStats>>,
+}
+
+impl StorageStats {
+    /// Returns the aggregated measurements
+    pub fn snapshot(&self) -> Vec<(StorageOperation, Vec<u8>, OperationStats)> {
+        let mut snapshot = self
+            .stats
+            .lock()
+            .expect("lock storage stats")
+            .iter()
+            .map(|((operation, keyspace), stats)| (*operation, keyspace.clone(), *stats))
+            .collect::<Vec<_>>();
+        snapshot.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+        snapshot
+    }
+}
+
+impl StorageObserver for StorageStats {
+    fn observe(&self, event: &StorageEvent<'_>) {
+        let mut stats = self.stats.lock().expect("lock storage stats");
+        let entry = stats
+            .entry((event.operation, event.keyspace.to_vec()))
+            .or_default();
+        entry.count += 1;
+        if !event.success {
+            entry.failures += 1;
+        }
+        entry.total_duration += event.duration;
+        entry.max_duration = entry.max_duration.max(event.duration);
+        entry.payload_size += event.payload_size as u64;
+    }
+}
+
+/// `Storage` which reports the operations of the wrapped storage to observers
+///
+/// Encryption of `SecureStorage` isn't included in the measurements, so that the time spent in
+/// the underlying storage (e.g. sled) can be told apart from the rest of a wallet operation.
+#[derive(Clone)]
+pub struct InstrumentedStorage<T: Storage> {
+    inner: T,
+    observers: Arc<Vec<Arc<dyn StorageObserver>>>,
+}
+
+impl<T: Storage> InstrumentedStorage<T> {
+    /// Creates a new instance of `InstrumentedStorage` without observers
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            observers: Default::default(),
+        }
+    }
+
+    /// Adds an observer of the storage operations
+    pub fn with_observer(mut self, observer: Arc<dyn StorageObserver>) -> Self {
+        Arc::make_mut(&mut self.observers).push(observer);
+        self
+    }
+
+    /// Returns the wrapped storage
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn observe<R>(
+        &self,
+        operation: StorageOperation,
+        keyspace: &[u8],
+        f: impl FnOnce() -> Result<R>,
+        payload_size: impl FnOnce(&R) -> usize,
+    ) -> Result<R> {
+        if self.observers.is_empty() {
+            return f();
+        }
+
+        let started = Instant::now();
+        let result = f();
+        let event = StorageEvent {
+            operation,
+            keyspace,
+            duration: started.elapsed(),
+            payload_size: result.as_ref().map(payload_size).unwrap_or_default(),
+            success: result.is_ok(),
+        };
+        for observer in self.observers.iter() {
+            observer.observe(&event);
+        }
+        result
+    }
+}
+
+fn value_size(value: &Option<Vec<u8>>) -> usize {
+    value.as_ref().map(Vec::len).unwrap_or_default()
+}
+
+impl<T: Storage> Storage for InstrumentedStorage<T> {
+    fn clear<S: AsRef<[u8]>>(&self, keyspace: S) -> Result<()> {
+        self.observe(
+            StorageOperation::Clear,
+            keyspace.as_ref(),
+            || self.inner.clear(&keyspace),
+            |_| 0,
+        )
+    }
+
+    fn get<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, keyspace: S, key: K) -> Result<Option<Vec<u8>>> {
+        self.observe(
+            StorageOperation::Get,
+            keyspace.as_ref(),
+            || self.inner.get(&keyspace, key),
+            value_size,
+        )
+    }
+
+    fn set<S: AsRef<[u8]>, K: AsRef<[u8]>>(
+        &self,
+        keyspace: S,
+        key: K,
+        value: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>> {
+        let size = value.len();
+        self.observe(
+            StorageOperation::Set,
+            keyspace.as_ref(),
+            || self.inner.set(&keyspace, key, value),
+            |_| size,
+        )
+    }
+
+    fn delete<S: AsRef<[u8]>, K: AsRef<[u8]>>(
+        &self,
+        keyspace: S,
+        key: K,
+    ) -> Result<Option<Vec<u8>>> {
+        self.observe(
+            StorageOperation::Delete,
+            keyspace.as_ref(),
+            || self.inner.delete(&keyspace, key),
+            value_size,
+        )
+    }
+
+    fn fetch_and_update<S, K, F>(&self, keyspace: S, key: K, f: F) -> Result<Option<Vec<u8>>>
+    where
+        S: AsRef<[u8]>,
+        K: AsRef<[u8]>,
+        F: Fn(Option<&[u8]>) -> Result<Option<Vec<u8>>>,
+    {
+        // size of the written value
+        let size = Cell::new(0);
+        self.observe(
+            StorageOperation::FetchAndUpdate,
+            keyspace.as_ref(),
+            || {
+                self.inner.fetch_and_update(&keyspace, key, |current| {
+                    let next = f(current)?;
+                    size.set(value_size(&next));
+                    Ok(next)
+                })
+            },
+            |_| size.get(),
+        )
+    }
+
+    fn keys<S: AsRef<[u8]>>(&self, keyspace: S) -> Result<Vec<Vec<u8>>> {
+        self.observe(
+            StorageOperation::Keys,
+            keyspace.as_ref(),
+            || self.inner.keys(&keyspace),
+            |keys| keys.iter().map(Vec::len).sum(),
+        )
+    }
+
+    fn contains_key<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, keyspace: S, key: K) -> Result<bool> {
+        self.observe(
+            StorageOperation::ContainsKey,
+            keyspace.as_ref(),
+            || self.inner.contains_key(&keyspace, key),
+            |_| 0,
+        )
+    }
+
+    fn keyspaces(&self) -> Result<Vec<Vec<u8>>> {
+        self.observe(
+            StorageOperation::Keyspaces,
+            &[],
+            || self.inner.keyspaces(),
+            |keyspaces| keyspaces.iter().map(Vec::len).sum(),
+        )
+    }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        self.observe(
+            StorageOperation::SizeOnDisk,
+            &[],
+            || self.inner.size_on_disk(),
+            |_| 0,
+        )
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.observe(StorageOperation::Flush, &[], || self.inner.flush(), |_| 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use crate::{ErrorKind, SecureStorage};
+    use secstr::SecUtf8;
+
+    #[test]
+    fn check_storage_stats() {
+        let stats = Arc::new(StorageStats::default());
+        let storage = InstrumentedStorage::new(MemoryStorage::default())
+            .with_observer(stats.clone())
+            .with_observer(Arc::new(SlowOperationLogger::new(Duration::from_secs(1))));
+
+        storage.set("wallet", "key", vec![1, 2, 3]).unwrap();
+        assert_eq!(Some(vec![1, 2, 3]), storage.get("wallet", "key").unwrap());
+        storage
+            .fetch_and_update("wallet", "key", |_| Ok(Some(vec![0; 5])))
+            .unwrap();
+        storage
+            .fetch_and_update("wallet", "key", |_| Err(ErrorKind::InvalidInput.into()))
+            .unwrap_err();
+
+        let snapshot = stats.snapshot();
+        let operations = snapshot
+            .iter()
+            .map(|(operation, keyspace, stats)| {
+                (
+                    *operation,
+                    keyspace.as_slice(),
+                    stats.count,
+                    stats.failures,
+                    stats.payload_size,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (StorageOperation::Get, &b"wallet"[..], 1, 0, 3),
+                (StorageOperation::Set, &b"wallet"[..], 1, 0, 3),
+                (StorageOperation::FetchAndUpdate, &b"wallet"[..], 2, 1, 5),
+            ],
+            operations
+        );
+    }
+
+    #[test]
+    fn check_secure_storage_is_instrumented() {
+        let stats = Arc::new(StorageStats::default());
+        let storage =
+            InstrumentedStorage::new(MemoryStorage::default()).with_observer(stats.clone());
+        let enckey = crate::seckey::derive_enckey(&SecUtf8::from("passphrase"), "").unwrap();
+
+        storage
+            .set_secure("wallet", "key", vec![1, 2, 3], &enckey)
+            .unwrap();
+        assert_eq!(
+            Some(vec![1, 2, 3]),
+            storage.get_secure("wallet", "key", &enckey).unwrap()
+        );
+
+        // the stored value is encrypted (nonce and tag added)
+        let snapshot = stats.snapshot();
+        let (_, _, set_stats) = snapshot
+            .iter()
+            .find(|(operation, _, _)| *operation == StorageOperation::Set)
+            .unwrap();
+        assert!(set_stats.payload_size > 3);
+    }
+}
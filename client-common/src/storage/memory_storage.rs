@@ -153,4 +153,21 @@ impl Storage for MemoryStorage {
 
         Ok(keyspaces)
     }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        let memory = self.0.read().map_err(|_| {
+            Error::new(
+                ErrorKind::StorageError,
+                "Unable to acquire read lock on memory storage",
+            )
+        })?;
+
+        let size = memory
+            .values()
+            .flat_map(|space| space.iter())
+            .map(|(key, value)| (key.len() + value.len()) as u64)
+            .sum();
+
+        Ok(size)
+    }
 }
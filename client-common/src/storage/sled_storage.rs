@@ -256,6 +256,15 @@ impl Storage for SledStorage {
         }
         Ok(result)
     }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        self.0.size_on_disk().chain(|| {
+            (
+                ErrorKind::StorageError,
+                "Unable to get size of sled storage",
+            )
+        })
+    }
 }
 
 #[cfg(test)]
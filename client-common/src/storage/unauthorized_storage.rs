@@ -57,4 +57,8 @@ impl Storage for UnauthorizedStorage {
     fn keyspaces(&self) -> Result<Vec<Vec<u8>>> {
         Err(ErrorKind::PermissionDenied.into())
     }
+
+    fn size_on_disk(&self) -> Result<u64> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
 }
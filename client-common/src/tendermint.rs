@@ -1,14 +1,17 @@
 //! Tendermint client operations
 mod client;
+mod multi_endpoint_client;
 #[cfg(feature = "websocket-rpc")]
 mod rpc_client;
 mod unauthorized_client;
 
 pub mod lite;
 pub mod mock;
+pub mod proof;
 pub mod types;
 
 pub use client::Client;
+pub use multi_endpoint_client::MultiEndpointClient;
 #[cfg(feature = "websocket-rpc")]
 pub use rpc_client::WebsocketRpcClient;
 pub use unauthorized_client::UnauthorizedClient;
@@ -1,6 +1,8 @@
 use crate::tendermint::types::*;
-use crate::Result;
-use chain_core::state::ChainState;
+use crate::{ErrorKind, Result, ResultExt};
+use chain_core::init::params::NetworkParameters;
+use chain_core::state::{ChainState, RewardsPoolState};
+use std::{thread, time::Duration};
 
 /// Makes remote calls to tendermint (backend agnostic)
 pub trait Client: Send + Sync + Clone {
@@ -28,6 +30,18 @@ pub trait Client: Send + Sync + Clone {
     /// Makes `broadcast_tx_sync` call to tendermint
     fn broadcast_transaction(&self, transaction: &[u8]) -> Result<BroadcastTxResponse>;
 
+    /// Makes batched `broadcast_tx_sync` call to tendermint
+    ///
+    /// Returns a result for each transaction in the given order, the outer error is only returned
+    /// when the batch couldn't be sent at all
+    fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+        &self,
+        transactions: T,
+    ) -> Result<Vec<Result<BroadcastTxResponse>>>;
+
+    /// Makes `unconfirmed_txs` call to tendermint
+    fn unconfirmed_txs(&self, limit: u64) -> Result<UnconfirmedTxsResponse>;
+
     /// Makes `abci_query` call to tendermint
     ///
     /// height: `None` means latest
@@ -42,4 +56,50 @@ pub trait Client: Send + Sync + Clone {
 
     /// Match batch state `abci_query` call to tendermint
     fn query_state_batch<T: Iterator<Item = u64>>(&self, heights: T) -> Result<Vec<ChainState>>;
+
+    /// Makes batched `abci_query` call to tendermint (of the same path and data at given heights)
+    ///
+    /// Returns a result for each height in the given order, the outer error is only returned
+    /// when the batch couldn't be sent at all
+    fn query_batch<T: Iterator<Item = u64>>(
+        &self,
+        path: &str,
+        data: &[u8],
+        heights: T,
+    ) -> Result<Vec<Result<AbciQuery>>> {
+        Ok(heights
+            .map(|height| self.query(path, data, Some(Height::from(height)), false))
+            .collect())
+    }
+
+    /// Queries the latest rewards pool state
+    fn query_rewards_pool(&self) -> Result<RewardsPoolState> {
+        let bytes = self.query("rewards_pool", &[], None, false)?.bytes();
+        serde_json::from_slice(&bytes).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to deserialize rewards pool state from json",
+            )
+        })
+    }
+
+    /// Queries the effective network parameters (including the updated values)
+    fn query_network_params(&self) -> Result<NetworkParameters> {
+        let bytes = self.query("network_params", &[], None, false)?.bytes();
+        serde_json::from_slice(&bytes).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to deserialize network parameters from json",
+            )
+        })
+    }
+
+    /// Blocks until a new block is committed or `timeout` elapses, returns `true` if a new block
+    /// was notified
+    ///
+    /// Clients without push notifications just sleep for `timeout` (callers poll `status` after)
+    fn wait_for_new_block(&self, timeout: Duration) -> bool {
+        thread::sleep(timeout);
+        false
+    }
 }
@@ -0,0 +1,431 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+
+use crate::{
+    tendermint::{types::*, Client},
+    Error, ErrorKind, Result,
+};
+use chain_core::state::ChainState;
+
+#[cfg(feature = "websocket-rpc")]
+use crate::tendermint::WebsocketRpcClient;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Health of an endpoint
+#[derive(Debug, Default)]
+struct EndpointState {
+    /// Number of consecutive failures
+    failures: u32,
+    /// Endpoint is skipped until this time
+    retry_at: Option<Instant>,
+}
+
+struct Endpoint<C: Client> {
+    url: String,
+    client: C,
+    state: Mutex<EndpointState>,
+}
+
+impl<C: Client> Endpoint<C> {
+    fn is_available(&self, now: Instant) -> bool {
+        match self.state.lock().unwrap().retry_at {
+            Some(retry_at) => retry_at <= now,
+            None => true,
+        }
+    }
+
+    fn succeeded(&self) {
+        *self.state.lock().unwrap() = Default::default();
+    }
+
+    /// Backs off the endpoint exponentially (`INITIAL_BACKOFF * 2^(failures - 1)`, up to
+    /// `MAX_BACKOFF`)
+    fn failed(&self) {
+        let mut state = self.state.lock().unwrap();
+        let backoff = INITIAL_BACKOFF
+            .checked_mul(1 << state.failures.min(16))
+            .map_or(MAX_BACKOFF, |backoff| backoff.min(MAX_BACKOFF));
+        state.failures = state.failures.saturating_add(1);
+        state.retry_at = Some(Instant::now() + backoff);
+    }
+
+    /// Checks the endpoint with a `status` call and updates its state
+    fn check_health(&self) -> bool {
+        match self.client.status() {
+            Ok(_) => {
+                self.succeeded();
+                true
+            }
+            Err(e) => {
+                log::warn!("Tendermint endpoint {} is unhealthy: {}", self.url, e);
+                self.failed();
+                false
+            }
+        }
+    }
+}
+
+/// `Client` which fails over across multiple tendermint endpoints
+///
+/// # How it works
+///
+/// - Requests are sent to the current endpoint.
+/// - When a request fails with a connection/RPC error, the endpoint is health checked with a
+///   `status` call. If it's unhealthy, it's backed off exponentially and the request is retried
+///   on the next endpoint (round-robin), which becomes the current one on success.
+/// - Endpoints in backoff are skipped until their retry time, unless all of them are in backoff.
+/// - Reconnection is handled by the underlying clients.
+#[derive(Clone)]
+pub struct MultiEndpointClient<C: Client> {
+    endpoints: Arc<Vec<Endpoint<C>>>,
+    current: Arc<AtomicUsize>,
+}
+
+impl<C: Client> MultiEndpointClient<C> {
+    /// Creates a new instance of `MultiEndpointClient` from `(url, client)` pairs
+    pub fn new(endpoints: Vec<(String, C)>) -> Result<Self> {
+        if endpoints.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "At least one tendermint endpoint is required",
+            ));
+        }
+
+        let endpoints = endpoints
+            .into_iter()
+            .map(|(url, client)| Endpoint {
+                url,
+                client,
+                state: Default::default(),
+            })
+            .collect();
+
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            current: Default::default(),
+        })
+    }
+
+    /// Returns the url of the current endpoint
+    pub fn current_url(&self) -> &str {
+        &self.endpoints[self.current.load(Ordering::Relaxed)].url
+    }
+
+    /// Health checks all the endpoints, returns the health of each endpoint by url
+    pub fn check_health(&self) -> Vec<(String, bool)> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| (endpoint.url.clone(), endpoint.check_health()))
+            .collect()
+    }
+
+    /// Calls `f` on the endpoints in round-robin order starting from the current one until an
+    /// endpoint is healthy
+    fn call<T>(&self, f: impl Fn(&C) -> Result<T>) -> Result<T> {
+        let now = Instant::now();
+        let start = self.current.load(Ordering::Relaxed);
+        let len = self.endpoints.len();
+
+        let order = (0..len).map(|i| (start + i) % len);
+        let mut candidates = order
+            .clone()
+            .filter(|&index| self.endpoints[index].is_available(now))
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            candidates = order.collect();
+        }
+
+        let mut last_error = None;
+
+        for index in candidates {
+            let endpoint = &self.endpoints[index];
+
+            match f(&endpoint.client) {
+                Err(e) if is_endpoint_error(&e) && !endpoint.check_health() => {
+                    log::warn!(
+                        "Tendermint endpoint {} failed, trying the next one: {}",
+                        endpoint.url,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+                result => {
+                    if index != start {
+                        log::info!("Switched to tendermint endpoint {}", endpoint.url);
+                    }
+                    endpoint.succeeded();
+                    self.current.store(index, Ordering::Relaxed);
+                    return result;
+                }
+            }
+        }
+
+        Err(last_error.expect("At least one tendermint endpoint is tried"))
+    }
+}
+
+#[cfg(feature = "websocket-rpc")]
+impl MultiEndpointClient<WebsocketRpcClient> {
+    /// Creates a new instance of `MultiEndpointClient` from websocket urls
+    pub fn from_urls(urls: &[&str]) -> Result<Self> {
+        let endpoints = urls
+            .iter()
+            .map(|url| Ok((url.to_string(), WebsocketRpcClient::new(url)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Self::new(endpoints)
+    }
+}
+
+/// Returns `true` if the error may be caused by the endpoint (rather than the request)
+fn is_endpoint_error(error: &Error) -> bool {
+    match error.kind() {
+        ErrorKind::InitializationError
+        | ErrorKind::ConnectionError
+        | ErrorKind::TendermintRpcError => true,
+        _ => false,
+    }
+}
+
+impl<C: Client> Client for MultiEndpointClient<C> {
+    fn genesis(&self) -> Result<Genesis> {
+        self.call(|client| client.genesis())
+    }
+
+    fn status(&self) -> Result<StatusResponse> {
+        self.call(|client| client.status())
+    }
+
+    fn block(&self, height: u64) -> Result<Block> {
+        self.call(|client| client.block(height))
+    }
+
+    fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, heights: T) -> Result<Vec<Block>> {
+        let heights = heights.collect::<Vec<_>>();
+        self.call(|client| client.block_batch(heights.iter().copied()))
+    }
+
+    fn block_results(&self, height: u64) -> Result<BlockResultsResponse> {
+        self.call(|client| client.block_results(height))
+    }
+
+    fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+        &self,
+        heights: T,
+    ) -> Result<Vec<BlockResultsResponse>> {
+        let heights = heights.collect::<Vec<_>>();
+        self.call(|client| client.block_results_batch(heights.iter().copied()))
+    }
+
+    fn broadcast_transaction(&self, transaction: &[u8]) -> Result<BroadcastTxResponse> {
+        self.call(|client| client.broadcast_transaction(transaction))
+    }
+
+    fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+        &self,
+        transactions: T,
+    ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+        let transactions = transactions.collect::<Vec<_>>();
+        self.call(|client| client.broadcast_transaction_batch(transactions.iter().copied()))
+    }
+
+    fn unconfirmed_txs(&self, limit: u64) -> Result<UnconfirmedTxsResponse> {
+        self.call(|client| client.unconfirmed_txs(limit))
+    }
+
+    fn query(
+        &self,
+        path: &str,
+        data: &[u8],
+        height: Option<Height>,
+        prove: bool,
+    ) -> Result<AbciQuery> {
+        self.call(|client| client.query(path, data, height, prove))
+    }
+
+    fn query_state_batch<T: Iterator<Item = u64>>(&self, heights: T) -> Result<Vec<ChainState>> {
+        let heights = heights.collect::<Vec<_>>();
+        self.call(|client| client.query_state_batch(heights.iter().copied()))
+    }
+
+    fn query_batch<T: Iterator<Item = u64>>(
+        &self,
+        path: &str,
+        data: &[u8],
+        heights: T,
+    ) -> Result<Vec<Result<AbciQuery>>> {
+        let heights = heights.collect::<Vec<_>>();
+        self.call(|client| client.query_batch(path, data, heights.iter().copied()))
+    }
+
+    fn wait_for_new_block(&self, timeout: Duration) -> bool {
+        self.endpoints[self.current.load(Ordering::Relaxed)]
+            .client
+            .wait_for_new_block(timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tendermint::mock;
+    use std::sync::atomic::AtomicBool;
+
+    #[derive(Clone, Default)]
+    struct MockClient {
+        down: Arc<AtomicBool>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl MockClient {
+        fn check(&self) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if self.down.load(Ordering::Relaxed) {
+                Err(Error::new(
+                    ErrorKind::TendermintRpcError,
+                    "Request timed out",
+                ))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Client for MockClient {
+        fn genesis(&self) -> Result<Genesis> {
+            unreachable!()
+        }
+
+        fn status(&self) -> Result<StatusResponse> {
+            self.check()?;
+            Ok(mock::status_response())
+        }
+
+        fn block(&self, _height: u64) -> Result<Block> {
+            unreachable!()
+        }
+
+        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
+            unreachable!()
+        }
+
+        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+            unreachable!()
+        }
+
+        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<BlockResultsResponse>> {
+            unreachable!()
+        }
+
+        fn broadcast_transaction(&self, _transaction: &[u8]) -> Result<BroadcastTxResponse> {
+            self.check()?;
+            // the transaction is rejected by a healthy endpoint
+            Err(Error::new(ErrorKind::TendermintRpcError, "invalid tx"))
+        }
+
+        fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+            &self,
+            _transactions: T,
+        ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+            unreachable!()
+        }
+
+        fn unconfirmed_txs(&self, _limit: u64) -> Result<UnconfirmedTxsResponse> {
+            self.check()?;
+            Ok(UnconfirmedTxsResponse { txs: None })
+        }
+
+        fn query(
+            &self,
+            _path: &str,
+            _data: &[u8],
+            _height: Option<Height>,
+            _prove: bool,
+        ) -> Result<AbciQuery> {
+            unreachable!()
+        }
+
+        fn query_state_batch<T: Iterator<Item = u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<ChainState>> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn check_failover() {
+        let first = MockClient::default();
+        let second = MockClient::default();
+        let client = MultiEndpointClient::new(vec![
+            ("ws://first".to_owned(), first.clone()),
+            ("ws://second".to_owned(), second.clone()),
+        ])
+        .unwrap();
+
+        assert!(client.unconfirmed_txs(1).is_ok());
+        assert_eq!("ws://first", client.current_url());
+
+        // fails over to the second endpoint
+        first.down.store(true, Ordering::Relaxed);
+        assert!(client.unconfirmed_txs(1).is_ok());
+        assert_eq!("ws://second", client.current_url());
+
+        // the first endpoint is backed off
+        second.down.store(true, Ordering::Relaxed);
+        let first_calls = first.calls.load(Ordering::Relaxed);
+        assert_eq!(
+            ErrorKind::TendermintRpcError,
+            client.unconfirmed_txs(1).unwrap_err().kind()
+        );
+        assert_eq!(first_calls, first.calls.load(Ordering::Relaxed));
+
+        // all endpoints are backed off, so all of them are tried
+        first.down.store(false, Ordering::Relaxed);
+        assert!(client.unconfirmed_txs(1).is_ok());
+        assert_eq!("ws://first", client.current_url());
+        assert_eq!(
+            vec![
+                ("ws://first".to_owned(), true),
+                ("ws://second".to_owned(), false)
+            ],
+            client.check_health()
+        );
+    }
+
+    #[test]
+    fn check_request_error_does_not_fail_over() {
+        let first = MockClient::default();
+        let second = MockClient::default();
+        let client = MultiEndpointClient::new(vec![
+            ("ws://first".to_owned(), first),
+            ("ws://second".to_owned(), second.clone()),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            "invalid tx",
+            client.broadcast_transaction(&[0]).unwrap_err().message()
+        );
+        assert_eq!("ws://first", client.current_url());
+        assert_eq!(0, second.calls.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn check_empty_endpoints() {
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            MultiEndpointClient::<MockClient>::new(vec![])
+                .err()
+                .unwrap()
+                .kind()
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! Verification of proven `abci_query` responses
+//!
+//! The "staking" and "account" queries (with `prove` set) return the SCALE-encoded staked state
+//! (optional for "staking", "account" fails for absent accounts) with two proof operations: the
+//! jellyfish proof of the staked state against the staking trie root ("staking") and the parts of
+//! the app hash which commit to that root ("app_hash"). The app hash of the state after block `h`
+//! is committed in the header of block `h + 1`, which light clients verify.
+//!
+//! The spent flags returned by the "meta" query aren't committed to the app hash, so they can't be
+//! proven.
+use parity_scale_codec::Decode;
+
+use crate::tendermint::types::AbciQuery;
+use crate::{Error, ErrorKind, Result, ResultExt};
+use chain_core::common::H256;
+use chain_core::state::account::{StakedState, StakedStateAddress};
+use chain_core::state::proof::SparseMerkleProof;
+use chain_core::AppHashParts;
+
+/// Field type of the proof operation carrying the jellyfish proof of a staked state
+pub const STAKING_PROOF_OP: &str = "staking";
+/// Field type of the proof operation carrying the parts of the app hash
+pub const APP_HASH_PROOF_OP: &str = "app_hash";
+
+/// Verifies the response of a proven "staking" query for `address` against a trusted app hash
+/// (e.g. of a header verified by the light client), returns the staked state (`None` is a proven
+/// absence of the account)
+pub fn verify_staking_query(
+    response: &AbciQuery,
+    address: &StakedStateAddress,
+    app_hash: &H256,
+) -> Result<Option<StakedState>> {
+    let staked_state =
+        <Option<StakedState>>::decode(&mut response.value.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                format!("Cannot deserialize staked state for address: {}", address),
+            )
+        })?;
+    verify_staked_state(response, address, staked_state.as_ref(), app_hash)?;
+    Ok(staked_state)
+}
+
+/// Verifies the response of a proven "account" query for `address` against a trusted app hash,
+/// returns the staked state
+pub fn verify_account_query(
+    response: &AbciQuery,
+    address: &StakedStateAddress,
+    app_hash: &H256,
+) -> Result<StakedState> {
+    let staked_state = StakedState::decode(&mut response.value.as_slice()).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            format!("Cannot deserialize staked state for address: {}", address),
+        )
+    })?;
+    verify_staked_state(response, address, Some(&staked_state), app_hash)?;
+    Ok(staked_state)
+}
+
+fn verify_staked_state(
+    response: &AbciQuery,
+    address: &StakedStateAddress,
+    staked_state: Option<&StakedState>,
+    app_hash: &H256,
+) -> Result<()> {
+    let proof =
+        SparseMerkleProof::decode(&mut proof_op_data(response, STAKING_PROOF_OP)?).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                format!(
+                    "Cannot deserialize staked state proof for address: {}",
+                    address
+                ),
+            )
+        })?;
+    let parts =
+        AppHashParts::decode(&mut proof_op_data(response, APP_HASH_PROOF_OP)?).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Cannot deserialize app hash parts",
+            )
+        })?;
+
+    if &parts.app_hash() != app_hash {
+        return Err(Error::new(
+            ErrorKind::VerifyError,
+            "App hash parts don't match the trusted app hash",
+        ));
+    }
+    proof
+        .verify(parts.account_root, address, staked_state)
+        .err_kind(ErrorKind::VerifyError, || "Verify staking state failed")
+}
+
+fn proof_op_data<'a>(response: &'a AbciQuery, field_type: &str) -> Result<&'a [u8]> {
+    response
+        .proof
+        .as_ref()
+        .and_then(|proof| proof.ops.iter().find(|op| op.field_type == field_type))
+        .map(|op| op.data.as_slice())
+        .chain(|| {
+            (
+                ErrorKind::TendermintRpcError,
+                format!("There is no {} proof in the query response", field_type),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_storage::{Storage, NUM_COLUMNS};
+    use kvdb_memorydb::create as create_memorydb;
+    use parity_scale_codec::Encode;
+    use std::sync::Arc;
+    use tendermint::merkle::proof::{Proof, ProofOp};
+
+    fn staking_response(
+        storage: &Storage,
+        address: &StakedStateAddress,
+        parts: &AppHashParts,
+    ) -> AbciQuery {
+        let (staked_state, proof) = chain_storage::jellyfish::get_with_proof(storage, 0, address);
+        AbciQuery {
+            value: staked_state.encode(),
+            proof: Some(Proof {
+                ops: vec![
+                    ProofOp {
+                        field_type: STAKING_PROOF_OP.to_owned(),
+                        key: address.encode(),
+                        data: proof.encode(),
+                    },
+                    ProofOp {
+                        field_type: APP_HASH_PROOF_OP.to_owned(),
+                        key: parts.app_hash().to_vec(),
+                        data: parts.encode(),
+                    },
+                ],
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn check_verify_staking_query() {
+        let mut storage = Storage::new_db(Arc::new(create_memorydb(NUM_COLUMNS)));
+        let address = StakedStateAddress::BasicRedeem([1; 20].into());
+        let account_root = storage.put_stakings(0, &[StakedState::default(address)]);
+        let parts = AppHashParts {
+            valid_tx_root: [2; 32],
+            account_root,
+            rewards_pool_hash: [3; 32],
+            network_params_hash: [4; 32],
+        };
+        let app_hash = parts.app_hash();
+
+        let response = staking_response(&storage, &address, &parts);
+        let staked_state = verify_staking_query(&response, &address, &app_hash).unwrap();
+        assert_eq!(Some(address), staked_state.map(|state| state.address));
+
+        let absent = StakedStateAddress::BasicRedeem([5; 20].into());
+        let response = staking_response(&storage, &absent, &parts);
+        assert_eq!(
+            None,
+            verify_staking_query(&response, &absent, &app_hash).unwrap()
+        );
+
+        // the proof doesn't match another address
+        assert_eq!(
+            ErrorKind::VerifyError,
+            verify_staking_query(&response, &address, &app_hash)
+                .unwrap_err()
+                .kind()
+        );
+        // the parts don't match another app hash
+        assert_eq!(
+            ErrorKind::VerifyError,
+            verify_staking_query(&response, &absent, &[0; 32])
+                .unwrap_err()
+                .kind()
+        );
+        // "account" responses carry the staked state itself
+        let mut response = staking_response(&storage, &address, &parts);
+        response.value = StakedState::default(address).encode();
+        let staked_state = verify_account_query(&response, &address, &app_hash).unwrap();
+        assert_eq!(address, staked_state.address);
+        response.value = StakedState::default(absent).encode();
+        assert!(verify_account_query(&response, &absent, &app_hash).is_err());
+
+        // proof is missing
+        let response = staking_response(&storage, &absent, &parts);
+        let response = AbciQuery {
+            value: response.value,
+            ..Default::default()
+        };
+        assert_eq!(
+            ErrorKind::TendermintRpcError,
+            verify_staking_query(&response, &absent, &app_hash)
+                .unwrap_err()
+                .kind()
+        );
+    }
+}
@@ -8,14 +8,16 @@ use std::{
 
 use anyhow::{anyhow, bail, Context, Result};
 use futures_util::{
+    future,
     sink::SinkExt,
-    stream::{SplitSink, SplitStream},
+    stream::{self, BoxStream, SplitSink, SplitStream, StreamExt},
 };
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
 use tokio::{
     net::TcpStream,
     sync::{
+        mpsc::unbounded_channel,
         oneshot::{channel, Receiver, Sender},
         Mutex,
     },
@@ -29,14 +31,18 @@ pub type WebSocketWriter = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>,
 pub type WebSocketReader = SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>;
 use super::{
     types::{ConnectionState, JsonRpcRequest, JsonRpcResponse},
-    websocket_rpc_loop,
+    websocket_rpc_loop::{self, SubscriptionMap},
 };
+use crate::tendermint::types::{Block, TxEvent};
 
 const WAIT_FOR_CONNECTION_SLEEP_INTERVAL: Duration = Duration::from_millis(200);
 const WAIT_FOR_CONNECTION_COUNT: usize = 50;
 
 const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
 
+const NEW_BLOCK_QUERY: &str = "tm.event='NewBlock'";
+const TX_QUERY: &str = "tm.event='Tx'";
+
 /// Tendermint RPC Client (uses websocket in transport layer)
 #[derive(Clone)]
 pub struct AsyncRpcClient {
@@ -44,6 +50,7 @@ pub struct AsyncRpcClient {
     /// websocket
     pub websocket_writer: Arc<Mutex<WebSocketWriter>>,
     channel_map: Arc<Mutex<HashMap<String, Sender<JsonRpcResponse>>>>,
+    subscriptions: SubscriptionMap,
     unique_id: Arc<AtomicUsize>,
 }
 
@@ -56,12 +63,14 @@ impl AsyncRpcClient {
     // - Spawns `websocket_rpc_loop` monitor.
     pub async fn new(url: &str) -> Result<Self> {
         let channel_map: Arc<Mutex<HashMap<String, Sender<JsonRpcResponse>>>> = Default::default();
+        let subscriptions: SubscriptionMap = Default::default();
 
         let (websocket_writer, websocket_reader) = websocket_rpc_loop::new_connection(url).await?;
         let websocket_writer = Arc::new(Mutex::new(websocket_writer));
 
         let loop_handle = websocket_rpc_loop::spawn(
             channel_map.clone(),
+            subscriptions.clone(),
             websocket_reader,
             websocket_writer.clone(),
         );
@@ -69,6 +78,7 @@ impl AsyncRpcClient {
         let connection_state = websocket_rpc_loop::monitor(
             url.to_owned(),
             channel_map.clone(),
+            subscriptions.clone(),
             loop_handle,
             websocket_writer.clone(),
         );
@@ -77,6 +87,7 @@ impl AsyncRpcClient {
             connection_state,
             websocket_writer,
             channel_map,
+            subscriptions,
             unique_id: Arc::new(AtomicUsize::new(0)),
         })
     }
@@ -172,20 +183,146 @@ impl AsyncRpcClient {
         Ok(responses)
     }
 
+    /// Makes RPC call in batch and deserializes responses, unlike `call_batch` a failed request
+    /// doesn't affect the responses of the other requests
+    pub async fn call_batch_each<T>(
+        &self,
+        batch_params: &[(&str, Vec<Value>)],
+    ) -> Result<Vec<Result<T>>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        let mut receivers = Vec::with_capacity(batch_params.len());
+
+        for (ref method, ref params) in batch_params.iter() {
+            let (id, channel_receiver) = self.send_request(method, params).await?;
+            receivers.push((id, channel_receiver));
+        }
+
+        let mut responses = Vec::with_capacity(batch_params.len());
+
+        for ((method, params), (id, receiver)) in batch_params.iter().zip(receivers.into_iter()) {
+            let response = self
+                .receive_response(method, params, &id, receiver)
+                .await
+                .and_then(|response_value| {
+                    serde_json::from_value(response_value).with_context(|| {
+                        format!(
+                            "Unable to deserialize `{}` from JSON-RPC response for params: {:?}",
+                            method, params
+                        )
+                    })
+                });
+            responses.push(response);
+        }
+
+        Ok(responses)
+    }
+
+    /// Subscribes to the events matching `query` (e.g. `tm.event='NewBlock'`), returns the stream
+    /// of event results
+    ///
+    /// The stream ends when the websocket is disconnected (events in between are lost, so
+    /// subscribers should catch up by polling and subscribe again).
+    pub async fn subscribe(&self, query: &str) -> Result<BoxStream<'static, Value>> {
+        let params = [json!(query)];
+        let id = self.next_id();
+        let (event_sender, event_receiver) = unbounded_channel();
+
+        // registers the event channel before subscribing so that no event is missed
+        self.subscriptions
+            .lock()
+            .await
+            .insert(id.clone(), event_sender);
+
+        let response = match self.send_request_with_id(&id, "subscribe", &params).await {
+            Ok(channel_receiver) => {
+                self.receive_response("subscribe", &params, &id, channel_receiver)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = response {
+            self.subscriptions.lock().await.remove(&id);
+            bail!(err);
+        }
+
+        Ok(
+            stream::unfold(event_receiver, |mut event_receiver| async move {
+                event_receiver
+                    .recv()
+                    .await
+                    .map(|event| (event, event_receiver))
+            })
+            .boxed(),
+        )
+    }
+
+    /// Subscribes to new blocks, returns the stream of blocks
+    pub async fn subscribe_new_blocks(&self) -> Result<BoxStream<'static, Block>> {
+        let events = self.subscribe(NEW_BLOCK_QUERY).await?;
+
+        Ok(events
+            .filter_map(|event| {
+                let block = serde_json::from_value(event["data"]["value"]["block"].clone())
+                    .map_err(|err| log::error!("Unable to deserialize new block event: {}", err))
+                    .ok();
+                future::ready(block)
+            })
+            .boxed())
+    }
+
+    /// Subscribes to transactions matching `query` (e.g. `valid_txs.txid='<txid>'`, all the
+    /// transactions if empty), returns the stream of transaction events
+    pub async fn subscribe_tx_events(&self, query: &str) -> Result<BoxStream<'static, TxEvent>> {
+        let query = if query.is_empty() {
+            TX_QUERY.to_owned()
+        } else {
+            format!("{} AND {}", TX_QUERY, query)
+        };
+        let events = self.subscribe(&query).await?;
+
+        Ok(events
+            .filter_map(|event| {
+                let tx_event = TxEvent::from_event(&event)
+                    .map_err(|err| log::error!("Unable to deserialize tx event: {}", err))
+                    .ok();
+                future::ready(tx_event)
+            })
+            .boxed())
+    }
+
+    /// Generates a new `request_id`
+    fn next_id(&self) -> String {
+        self.unique_id.fetch_add(1, Ordering::Relaxed).to_string()
+    }
+
     /// Sends a JSON-RPC request and returns `request_id` and `response_channel`
     async fn send_request(
         &self,
         method: &str,
         params: &[Value],
     ) -> Result<(String, Receiver<JsonRpcResponse>)> {
-        let id = self.unique_id.fetch_add(1, Ordering::Relaxed).to_string();
-        let message = prepare_message(&id, method, params)?;
+        let id = self.next_id();
+        let channel_receiver = self.send_request_with_id(&id, method, params).await?;
+        Ok((id, channel_receiver))
+    }
+
+    /// Sends a JSON-RPC request with given `request_id` and returns `response_channel`
+    async fn send_request_with_id(
+        &self,
+        id: &str,
+        method: &str,
+        params: &[Value],
+    ) -> Result<Receiver<JsonRpcResponse>> {
+        let message = prepare_message(id, method, params)?;
         let (channel_sender, channel_receiver) = channel::<JsonRpcResponse>();
 
         self.channel_map
             .lock()
             .await
-            .insert(id.clone(), channel_sender);
+            .insert(id.to_owned(), channel_sender);
 
         self.ensure_connected().await?;
 
@@ -197,11 +334,11 @@ impl AsyncRpcClient {
             .await
             .context("Unable to send message to websocket writer")
         {
-            self.channel_map.lock().await.remove(&id);
+            self.channel_map.lock().await.remove(id);
             bail!(err);
         }
 
-        Ok((id, channel_receiver))
+        Ok(channel_receiver)
     }
 
     /// Receives response from websocket for given id.
@@ -1,6 +1,8 @@
 use std::{
     convert::TryFrom,
-    sync::{mpsc::sync_channel, Arc},
+    future::Future,
+    sync::{mpsc::sync_channel, Arc, Condvar},
+    thread,
     time::Duration,
 };
 
@@ -33,13 +35,23 @@ use chain_core::init::coin::CoinError;
 use chain_core::tx::data::TxId;
 use chain_core::tx::fee::{Fee, FeeAlgorithm, LinearFee};
 use chain_core::tx::TxAux;
-use futures_util::sink::SinkExt;
+use futures_util::{
+    sink::SinkExt,
+    stream::{BoxStream, StreamExt},
+};
 use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio_tungstenite::tungstenite::protocol::frame::CloseFrame;
 use tokio_tungstenite::tungstenite::Message;
 
 const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Latest block height pushed by the new block subscription
+#[derive(Default)]
+struct NewBlockNotifier {
+    subscribed: bool,
+    height: u64,
+}
+
 /// Wraps asynchronous RPC client and executes it in tokio runtime
 #[derive(Clone)]
 pub struct SyncRpcClient {
@@ -47,6 +59,7 @@ pub struct SyncRpcClient {
     /// ASYNC RPC CLIENT
     pub async_rpc_client: Arc<Mutex<Option<AsyncRpcClient>>>,
     url: String,
+    new_blocks: Arc<(Mutex<NewBlockNotifier>, Condvar)>,
 }
 
 impl FeeAlgorithm for SyncRpcClient {
@@ -89,6 +102,7 @@ impl SyncRpcClient {
             runtime: Arc::new(Mutex::new(runtime)),
             async_rpc_client: Arc::new(Mutex::new(None)),
             url: url.to_string(),
+            new_blocks: Default::default(),
         })
     }
 
@@ -196,6 +210,111 @@ impl SyncRpcClient {
                 )
             })
     }
+
+    /// Makes RPC call in batch and deserializes responses, keeping the result of each request
+    pub fn call_batch_each<T>(
+        &self,
+        params: Vec<(&'static str, Vec<Value>)>,
+    ) -> Result<Vec<Result<T>>>
+    where
+        T: Send + 'static,
+        for<'de> T: Deserialize<'de>,
+    {
+        let (sender, receiver) = sync_channel(1);
+        let async_rpc_client = self.get_async_client()?;
+
+        self.runtime.lock().unwrap().spawn(async move {
+            let response = async_rpc_client.call_batch_each(&params).await;
+            if let Err(e) = sender.send(response) {
+                log::error!(
+                    "Unable to send tendermint RPC response back to response channel: {}",
+                    e
+                );
+            }
+        });
+
+        let responses = receiver
+            .recv_timeout(RESPONSE_TIMEOUT)
+            .chain(|| (ErrorKind::TendermintRpcError, "Request timed out"))?
+            .chain(|| {
+                (
+                    ErrorKind::TendermintRpcError,
+                    "Error while calling tendermint RPC call",
+                )
+            })?;
+        Ok(responses
+            .into_iter()
+            .map(|response| {
+                response.chain(|| {
+                    (
+                        ErrorKind::TendermintRpcError,
+                        "Error while calling tendermint RPC call",
+                    )
+                })
+            })
+            .collect())
+    }
+
+    /// Subscribes to new blocks, returns the stream of blocks (which ends when the websocket is
+    /// disconnected)
+    pub fn subscribe_new_blocks(&self) -> Result<BoxStream<'static, Block>> {
+        let async_rpc_client = self.get_async_client()?;
+        self.subscribe(async move { async_rpc_client.subscribe_new_blocks().await })
+    }
+
+    /// Subscribes to transactions matching `query` (all the transactions if empty), returns the
+    /// stream of transaction events (which ends when the websocket is disconnected)
+    pub fn subscribe_tx_events(&self, query: &str) -> Result<BoxStream<'static, TxEvent>> {
+        let async_rpc_client = self.get_async_client()?;
+        let query = query.to_owned();
+        self.subscribe(async move { async_rpc_client.subscribe_tx_events(&query).await })
+    }
+
+    /// Executes the subscription request in tokio runtime
+    fn subscribe<T, F>(&self, subscription: F) -> Result<BoxStream<'static, T>>
+    where
+        T: 'static,
+        F: Future<Output = anyhow::Result<BoxStream<'static, T>>> + Send + 'static,
+    {
+        let (sender, receiver) = sync_channel(1);
+
+        self.runtime.lock().unwrap().spawn(async move {
+            if let Err(e) = sender.send(subscription.await) {
+                log::error!(
+                    "Unable to send tendermint subscription back to response channel: {}",
+                    e
+                );
+            }
+        });
+
+        receiver
+            .recv_timeout(RESPONSE_TIMEOUT)
+            .chain(|| (ErrorKind::TendermintRpcError, "Request timed out"))?
+            .chain(|| {
+                (
+                    ErrorKind::TendermintRpcError,
+                    "Unable to subscribe to tendermint events",
+                )
+            })
+    }
+
+    /// Forwards the heights of new blocks to the threads blocked in `wait_for_new_block`
+    fn notify_new_blocks(&self, mut blocks: BoxStream<'static, Block>) {
+        let new_blocks = self.new_blocks.clone();
+
+        self.runtime.lock().unwrap().spawn(async move {
+            while let Some(block) = blocks.next().await {
+                let (notifier, condvar) = &*new_blocks;
+                notifier.lock().unwrap().height = block.header.height.value();
+                condvar.notify_all();
+            }
+
+            log::warn!("New block subscription ended");
+            let (notifier, condvar) = &*new_blocks;
+            notifier.lock().unwrap().subscribed = false;
+            condvar.notify_all();
+        });
+    }
 }
 
 impl Client for SyncRpcClient {
@@ -255,6 +374,35 @@ impl Client for SyncRpcClient {
         }
     }
 
+    /// Makes batched `broadcast_tx_sync` call to tendermint
+    fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+        &self,
+        transactions: T,
+    ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+        let params = transactions
+            .map(|transaction| ("broadcast_tx_sync", vec![json!(transaction)]))
+            .collect::<Vec<(&'static str, Vec<Value>)>>();
+        let rsps = self.call_batch_each::<BroadcastTxResponse>(params)?;
+
+        Ok(rsps
+            .into_iter()
+            .map(|rsp| {
+                let rsp = rsp?;
+                if rsp.code.is_err() {
+                    Err(Error::new(ErrorKind::TendermintRpcError, rsp.log.as_ref()))
+                } else {
+                    Ok(rsp)
+                }
+            })
+            .collect())
+    }
+
+    /// Makes `unconfirmed_txs` call to tendermint
+    fn unconfirmed_txs(&self, limit: u64) -> Result<UnconfirmedTxsResponse> {
+        let params = vec![json!(limit.to_string())];
+        self.call("unconfirmed_txs", params)
+    }
+
     /// Makes `abci_query` call to tendermint
     fn query(
         &self,
@@ -317,6 +465,77 @@ impl Client for SyncRpcClient {
         }
         Ok(states)
     }
+
+    /// Makes batched `abci_query` call to tendermint
+    fn query_batch<T: Iterator<Item = u64>>(
+        &self,
+        path: &str,
+        data: &[u8],
+        heights: T,
+    ) -> Result<Vec<Result<AbciQuery>>> {
+        let params = heights
+            .map(|height| {
+                (
+                    "abci_query",
+                    vec![
+                        json!(path),
+                        json!(hex::encode(data)),
+                        json!(height.to_string()),
+                        json!(false),
+                    ],
+                )
+            })
+            .collect::<Vec<(&'static str, Vec<Value>)>>();
+        let rsps = self.call_batch_each::<AbciQueryResponse>(params)?;
+
+        Ok(rsps
+            .into_iter()
+            .map(|rsp| {
+                let rsp = rsp?.response;
+                if rsp.code.is_err() {
+                    Err(Error::new(
+                        ErrorKind::TendermintRpcError,
+                        rsp.log.to_string(),
+                    ))
+                } else {
+                    Ok(rsp)
+                }
+            })
+            .collect())
+    }
+
+    fn wait_for_new_block(&self, timeout: Duration) -> bool {
+        let (notifier, condvar) = &*self.new_blocks;
+        let mut state = notifier.lock().unwrap();
+
+        if !state.subscribed {
+            state.subscribed = true;
+            drop(state);
+
+            match self.subscribe_new_blocks() {
+                Ok(blocks) => self.notify_new_blocks(blocks),
+                Err(e) => {
+                    log::warn!(
+                        "Unable to subscribe to new blocks, fall back to polling: {}",
+                        e
+                    );
+                    notifier.lock().unwrap().subscribed = false;
+                    thread::sleep(timeout);
+                    return false;
+                }
+            }
+
+            state = notifier.lock().unwrap();
+        }
+
+        let height = state.height;
+        let (state, _) = condvar
+            .wait_timeout_while(state, timeout, |state| {
+                state.subscribed && state.height == height
+            })
+            .unwrap();
+        state.height != height
+    }
 }
 
 impl Drop for SyncRpcClient {
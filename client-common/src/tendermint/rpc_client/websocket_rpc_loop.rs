@@ -3,8 +3,9 @@ use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use futures_util::{sink::SinkExt, stream::StreamExt};
+use serde_json::Value;
 use tokio::{
-    sync::{oneshot::Sender, Mutex},
+    sync::{mpsc::UnboundedSender, oneshot::Sender, Mutex},
     task::JoinHandle,
     time::{delay_for, Duration},
 };
@@ -17,6 +18,12 @@ use super::{
 
 const MONITOR_RETRY_INTERVAL: Duration = Duration::from_secs(2);
 
+/// Suffix tendermint appends to the `request_id` of a subscription for its events
+const EVENT_ID_SUFFIX: &str = "#event";
+
+/// Event channels of active subscriptions by subscription `request_id`
+pub type SubscriptionMap = Arc<Mutex<HashMap<String, UnboundedSender<Value>>>>;
+
 /// Creates a new websocket connection with given url
 pub async fn new_connection(url: &str) -> Result<(WebSocketWriter, WebSocketReader)> {
     let (websocket_stream, _) = connect_async(url).await.with_context(|| {
@@ -39,8 +46,11 @@ pub async fn new_connection(url: &str) -> Result<(WebSocketWriter, WebSocketRead
 ///   - Parse the message into JSON-RPC response.
 ///   - Pop the response channel from `channel_map` corresponding to response's `request_id`.
 ///   - Send the response to the channel.
+///   - Events of subscriptions (`request_id` suffixed with `#event`) are sent to the subscription
+///     channel in `subscriptions` instead.
 pub fn spawn(
     channel_map: Arc<Mutex<HashMap<String, Sender<JsonRpcResponse>>>>,
+    subscriptions: SubscriptionMap,
     mut websocket_reader: WebSocketReader,
     websocket_writer: Arc<Mutex<WebSocketWriter>>,
 ) -> JoinHandle<()> {
@@ -48,9 +58,11 @@ pub fn spawn(
         while let Some(message) = websocket_reader.next().await {
             match message {
                 Ok(message) => match message {
-                    Message::Text(ref message) => handle_text(message, channel_map.clone()).await,
+                    Message::Text(ref message) => {
+                        handle_text(message, channel_map.clone(), subscriptions.clone()).await
+                    }
                     Message::Binary(ref message) => {
-                        handle_slice(message, channel_map.clone()).await
+                        handle_slice(message, channel_map.clone(), subscriptions.clone()).await
                     }
                     Message::Ping(data) => send_pong(websocket_writer.clone(), data).await,
                     _ => {
@@ -76,9 +88,12 @@ pub fn spawn(
 /// - This function spawns a thread and runs connection state machine in a loop.
 ///   - If current state is `Disconnected`: Spawns `websocket_rpc_loop` and sets state to `Connected`.
 ///   - If current state is `Connected`: Waits for `websocket_rpc_loop` thread to end and sets state to `Disconnected`.
+/// - Subscriptions don't survive the reconnection, their channels are dropped on disconnection
+///   (which ends the event streams).
 pub fn monitor(
     url: String,
     channel_map: Arc<Mutex<HashMap<String, Sender<JsonRpcResponse>>>>,
+    subscriptions: SubscriptionMap,
     loop_handle: JoinHandle<()>,
     websocket_writer: Arc<Mutex<WebSocketWriter>>,
 ) -> Arc<Mutex<ConnectionState>> {
@@ -107,6 +122,7 @@ pub fn monitor(
 
                             let new_handle = spawn(
                                 channel_map.clone(),
+                                subscriptions.clone(),
                                 new_websocket_reader,
                                 websocket_writer.clone(),
                             );
@@ -119,6 +135,7 @@ pub fn monitor(
                     let _ = connection_handle
                         .expect("Connection handle must be present when websocket is connected")
                         .await;
+                    subscriptions.lock().await.clear();
                     (ConnectionState::Disconnected, None)
                 }
             };
@@ -149,11 +166,12 @@ fn parse_slice(message: &[u8]) -> Result<JsonRpcResponse> {
 async fn handle_text(
     message: &str,
     channel_map: Arc<Mutex<HashMap<String, Sender<JsonRpcResponse>>>>,
+    subscriptions: SubscriptionMap,
 ) {
     log::trace!("Received text websocket message: {}", message);
 
     match parse_text(message) {
-        Ok(text) => send_response(text, channel_map).await,
+        Ok(text) => send_response(text, channel_map, subscriptions).await,
         Err(err) => log::error!("{:?}", err),
     }
 }
@@ -162,10 +180,11 @@ async fn handle_text(
 async fn handle_slice(
     message: &[u8],
     channel_map: Arc<Mutex<HashMap<String, Sender<JsonRpcResponse>>>>,
+    subscriptions: SubscriptionMap,
 ) {
     log::trace!("Received binary websocket message: {:?}", message);
     match parse_slice(message) {
-        Ok(slice) => send_response(slice, channel_map).await,
+        Ok(slice) => send_response(slice, channel_map, subscriptions).await,
         Err(err) => log::error!("{:?}", err),
     }
 }
@@ -174,7 +193,14 @@ async fn handle_slice(
 async fn send_response(
     response: JsonRpcResponse,
     channel_map: Arc<Mutex<HashMap<String, Sender<JsonRpcResponse>>>>,
+    subscriptions: SubscriptionMap,
 ) {
+    if response.id.ends_with(EVENT_ID_SUFFIX) {
+        let id = &response.id[..response.id.len() - EVENT_ID_SUFFIX.len()];
+        send_event(id, response, subscriptions).await;
+        return;
+    }
+
     let sender = channel_map.lock().await.remove(&response.id);
 
     if let Some(sender) = sender {
@@ -187,6 +213,25 @@ async fn send_response(
     }
 }
 
+/// Sends subscription event to its channel, drops the subscription if the stream is dropped
+async fn send_event(id: &str, response: JsonRpcResponse, subscriptions: SubscriptionMap) {
+    let mut subscriptions = subscriptions.lock().await;
+
+    match (subscriptions.get(id), response.result) {
+        (Some(sender), Some(event)) => {
+            log::debug!("Sending subscription event to channel");
+            if sender.send(event).is_err() {
+                subscriptions.remove(id);
+            }
+        }
+        (Some(_), None) => {
+            log::error!("Subscription error: {:?}", response.error);
+            subscriptions.remove(id);
+        }
+        (None, _) => log::warn!("Received an event of unknown subscription: {}", id),
+    }
+}
+
 /// Silently sends pong message on websocket (does nothing in case of error)
 async fn send_pong(websocket_writer: Arc<Mutex<WebSocketWriter>>, data: Vec<u8>) {
     let pong = websocket_writer
@@ -3,6 +3,8 @@ mod block_results;
 
 use parity_scale_codec::Decode;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use crate::{ErrorKind, Result, ResultExt, Transaction};
@@ -33,6 +35,25 @@ pub struct GenesisResponse {
     pub genesis: Genesis,
 }
 
+/// Response of `unconfirmed_txs` call
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UnconfirmedTxsResponse {
+    /// Raw transactions in the mempool (tendermint returns `null` when there's none)
+    #[serde(default)]
+    pub txs: Option<Vec<abci::Transaction>>,
+}
+
+impl UnconfirmedTxsResponse {
+    /// Returns the decodable transactions in the mempool
+    pub fn transactions(&self) -> Vec<TxAux> {
+        self.txs
+            .iter()
+            .flatten()
+            .filter_map(|raw| TxAux::decode(&mut raw.clone().into_vec().as_slice()).ok())
+            .collect()
+    }
+}
+
 /// crypto-chain specific methods.
 pub trait BlockExt {
     /// Returns un-encrypted staking(deposit/unbound) transactions in a block
@@ -127,3 +148,92 @@ impl AbciQueryExt for AbciQuery {
         self.value.clone()
     }
 }
+
+/// Transaction event pushed by tendermint to the subscribers of `tm.event='Tx'`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxEvent {
+    /// Height of the block which includes the transaction
+    pub height: u64,
+    /// Raw transaction
+    pub tx: Vec<u8>,
+    /// Events of the transaction (composite key `<type>.<attribute>` to values)
+    pub events: BTreeMap<String, Vec<String>>,
+}
+
+impl TxEvent {
+    /// Parses the `result` of a tendermint `Tx` subscription event
+    pub fn from_event(event: &Value) -> Result<Self> {
+        let tx_result = &event["data"]["value"]["TxResult"];
+        let height = tx_result["height"]
+            .as_str()
+            .and_then(|height| height.parse().ok())
+            .chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    "Invalid block height in tx event",
+                )
+            })?;
+        let tx = tx_result["tx"]
+            .as_str()
+            .and_then(|tx| base64::decode(tx).ok())
+            .chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    "Invalid transaction in tx event",
+                )
+            })?;
+        let events = match event.get("events") {
+            Some(events) if !events.is_null() => {
+                serde_json::from_value(events.clone()).chain(|| {
+                    (
+                        ErrorKind::DeserializationError,
+                        "Invalid events in tx event",
+                    )
+                })?
+            }
+            _ => Default::default(),
+        };
+        Ok(TxEvent { height, tx, events })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn check_tx_event_from_event() {
+        let event = json!({
+            "query": "tm.event='Tx'",
+            "data": {
+                "type": "tendermint/event/Tx",
+                "value": {
+                    "TxResult": {
+                        "height": "12",
+                        "index": 0,
+                        "tx": base64::encode(&[1, 2, 3]),
+                        "result": {}
+                    }
+                }
+            },
+            "events": {
+                "tm.event": ["Tx"],
+                "valid_txs.txid": ["aa"]
+            }
+        });
+        let tx_event = TxEvent::from_event(&event).unwrap();
+        assert_eq!(12, tx_event.height);
+        assert_eq!(vec![1, 2, 3], tx_event.tx);
+        assert_eq!(
+            Some(&vec!["aa".to_owned()]),
+            tx_event.events.get("valid_txs.txid")
+        );
+
+        let event = json!({ "data": { "value": { "TxResult": { "height": 12 } } } });
+        assert_eq!(
+            ErrorKind::DeserializationError,
+            TxEvent::from_event(&event).unwrap_err().kind()
+        );
+    }
+}
@@ -40,6 +40,17 @@ impl Client for UnauthorizedClient {
         Err(ErrorKind::PermissionDenied.into())
     }
 
+    fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+        &self,
+        _transactions: T,
+    ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
+    fn unconfirmed_txs(&self, _limit: u64) -> Result<UnconfirmedTxsResponse> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
     fn query(
         &self,
         _path: &str,
@@ -16,6 +16,8 @@ pub enum HardwareKind {
     /// mock wallet
     #[cfg(feature = "mock-hardware-wallet")]
     Mock,
+    /// PKCS#11 hardware security module
+    Pkcs11 = 4,
 }
 
 impl FromStr for HardwareKind {
@@ -29,6 +31,8 @@ impl FromStr for HardwareKind {
             Ok(HardwareKind::Trezor)
         } else if s == "mock" {
             Ok(HardwareKind::Mock)
+        } else if s == "pkcs11" {
+            Ok(HardwareKind::Pkcs11)
         } else {
             Err(ErrorKind::DeserializationError.into())
         }
@@ -40,6 +44,8 @@ impl FromStr for HardwareKind {
             Ok(HardwareKind::Ledger)
         } else if s == "trezor" {
             Ok(HardwareKind::Trezor)
+        } else if s == "pkcs11" {
+            Ok(HardwareKind::Pkcs11)
         } else {
             Err(ErrorKind::DeserializationError.into())
         }
@@ -1,8 +1,11 @@
 //! Input selection operations
+use serde::{Deserialize, Serialize};
+
 use crate::unspent_transactions::{Operation, Sorter};
 
 /// Different strategies for input selection
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InputSelectionStrategy {
     /// Selects unspent transactions with highest value first
     HighestValueFirst,
@@ -10,6 +13,9 @@ pub enum InputSelectionStrategy {
     LowestValueFirst,
     /// Selects unspent transactions randomly
     Random,
+    /// Searches for unspent transactions which exactly pay the outputs and fee, so that no
+    /// change output is needed, otherwise selects ones with highest value first
+    BranchAndBound,
 }
 
 impl Default for InputSelectionStrategy {
@@ -22,7 +28,7 @@ impl Default for InputSelectionStrategy {
 impl AsRef<[Operation]> for InputSelectionStrategy {
     fn as_ref(&self) -> &[Operation] {
         match self {
-            InputSelectionStrategy::HighestValueFirst => {
+            InputSelectionStrategy::HighestValueFirst | InputSelectionStrategy::BranchAndBound => {
                 &[Operation::Sort(Sorter::HighestValueFirst)]
             }
             InputSelectionStrategy::LowestValueFirst => {
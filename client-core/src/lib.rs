@@ -39,7 +39,10 @@ pub use crate::transaction_builder::WalletTransactionBuilder;
 #[doc(inline)]
 pub use crate::unspent_transactions::{SelectedUnspentTransactions, UnspentTransactions};
 #[doc(inline)]
-pub use crate::wallet::WalletClient;
+pub use crate::wallet::{
+    WalletAuditClient, WalletClient, WalletMaintenanceClient, WalletPaymentClient,
+    WalletRekeyClient, WalletSpendControlClient,
+};
 
 #[cfg(feature = "experimental")]
 #[doc(inline)]
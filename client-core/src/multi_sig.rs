@@ -6,3 +6,4 @@ mod signer;
 pub use builder::MultiSigBuilder;
 pub use session::MultiSigSession;
 use signer::Signer;
+pub use signer::SignerStatus;
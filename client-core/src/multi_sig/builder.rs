@@ -4,7 +4,7 @@ use secp256k1::schnorrsig::SchnorrSignature;
 use chain_core::common::H256;
 use client_common::{ErrorKind, PrivateKey, PublicKey, Result, ResultExt};
 
-use super::MultiSigSession;
+use super::{MultiSigSession, SignerStatus};
 
 /// MultiSig session builder tailored for Crypto.com chain flow
 ///
@@ -155,6 +155,11 @@ impl MultiSigBuilder {
         self.session.has_partial_signature(public_key)
     }
 
+    /// Returns progress of all signers in the session
+    pub fn signer_statuses(&self) -> Vec<SignerStatus> {
+        self.session.signer_statuses()
+    }
+
     /// Returns incompleted MultiSig session in bytes
     pub fn to_incomplete(&self) -> Vec<u8> {
         self.session.encode()
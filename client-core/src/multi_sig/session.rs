@@ -18,9 +18,10 @@ thread_local! {
 }
 
 use chain_core::common::H256;
+use chain_core::tx::canonical::sort_signers;
 use client_common::{Error, ErrorKind, PrivateKey, PublicKey, Result, ResultExt};
 
-use super::Signer;
+use super::{Signer, SignerStatus};
 
 /// A MultiSig session as a basic building block
 #[derive(Debug, Encode, Decode)]
@@ -72,7 +73,7 @@ impl MultiSigSession {
             ));
         }
 
-        signer_public_keys.sort();
+        sort_signers(&mut signer_public_keys);
 
         if signer_public_keys.binary_search(&self_public_key).is_err() {
             return Err(Error::new(
@@ -404,6 +405,11 @@ impl MultiSigSession {
             .collect()
     }
 
+    /// Returns progress of all signers in this session
+    pub fn signer_statuses(&self) -> Vec<SignerStatus> {
+        self.signers.iter().map(Signer::status).collect()
+    }
+
     /// Returns true if nonce commitment for given public key is already set, false otherwise
     pub fn has_nonce_commitment(&self, public_key: &PublicKey) -> Result<bool> {
         let signer_index = self.signer_index(public_key)?;
@@ -1,4 +1,5 @@
 use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 
 use chain_core::common::H256;
 use client_common::{Error, ErrorKind, PublicKey, Result};
@@ -16,7 +17,30 @@ pub struct Signer {
     pub partial_signature: Option<H256>,
 }
 
+/// Progress of a signer in a MultiSig session, i.e. which of its values are already added
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignerStatus {
+    /// Public key of signer
+    pub public_key: PublicKey,
+    /// Whether nonce commitment of signer is added
+    pub nonce_commitment: bool,
+    /// Whether nonce of signer is added
+    pub nonce: bool,
+    /// Whether partial signature of signer is added
+    pub partial_signature: bool,
+}
+
 impl Signer {
+    /// Returns progress of current signer
+    pub fn status(&self) -> SignerStatus {
+        SignerStatus {
+            public_key: self.public_key.clone(),
+            nonce_commitment: self.nonce_commitment.is_some(),
+            nonce: self.nonce.is_some(),
+            partial_signature: self.partial_signature.is_some(),
+        }
+    }
+
     /// Adds nonce commitment to current signer if not already added.
     pub fn add_nonce_commitment(&mut self, nonce_commitment: H256) -> Result<()> {
         if self.nonce_commitment.is_some() {
@@ -1,4 +1,8 @@
 //! Management services
+mod alert_service;
+mod annotation_service;
+mod approval_service;
+mod device_sync_service;
 mod hd_key_service;
 mod hw_key_service;
 mod key_service;
@@ -7,7 +11,15 @@ mod ledger_hw_key_service;
 mod mock_hw_key_service;
 #[cfg(feature = "experimental")]
 mod multi_sig_session_service;
+mod operator_profile_service;
+mod passphrase_attempt_service;
+mod payout_batch_service;
+#[cfg(feature = "pkcs11")]
+mod pkcs11_hw_key_service;
+mod rekey_service;
+mod reservation_service;
 mod root_hash_service;
+mod storage_maintenance_service;
 mod sync_state_service;
 mod wallet_service;
 mod wallet_state_service;
@@ -15,6 +27,10 @@ mod wallet_state_service;
 #[doc(hidden)]
 pub use self::wallet_state_service::WalletStateMemento;
 
+pub use self::alert_service::AlertService;
+pub use self::annotation_service::AnnotationService;
+pub use self::approval_service::ApprovalService;
+pub use self::device_sync_service::DeviceSyncService;
 pub use self::hd_key_service::{HDAccountType, HdKey, HdKeyService};
 pub use self::hw_key_service::{HwKeyService, UnauthorizedHwKeyService};
 pub use self::key_service::KeyService;
@@ -23,11 +39,32 @@ pub use self::ledger_hw_key_service::{LedgerService, LedgerSignKey};
 pub use self::mock_hw_key_service::{MockHardwareKey, MockHardwareService, MockHardwareWallet};
 #[cfg(feature = "experimental")]
 pub use self::multi_sig_session_service::MultiSigSessionService;
+pub use self::operator_profile_service::{OperatorProfile, OperatorProfileService};
+pub use self::passphrase_attempt_service::{
+    LockoutStatus, PassphraseAttemptService, PassphraseAttempts,
+};
+pub use self::payout_batch_service::PayoutBatchService;
+#[cfg(feature = "pkcs11")]
+pub use self::pkcs11_hw_key_service::{Pkcs11Service, Pkcs11SignKey};
+pub use self::rekey_service::RekeyService;
+pub(crate) use self::rekey_service::KEYSPACE as REKEY_JOURNAL_KEYSPACE;
+pub use self::reservation_service::{withhold_reserved, ReservationService};
 pub use self::root_hash_service::RootHashService;
+pub(crate) use self::storage_maintenance_service::delete_wallet_records;
+pub use self::storage_maintenance_service::{
+    OrphanKind, OrphanRecord, OrphanReport, StorageMaintenanceService, StorageStats,
+};
+pub(crate) use self::sync_state_service::CHECKPOINT_KEYSPACE as SYNC_CHECKPOINT_KEYSPACE;
+pub(crate) use self::sync_state_service::JOURNAL_KEYSPACE as SYNC_JOURNAL_KEYSPACE;
 pub use self::sync_state_service::{
-    delete_sync_state, load_sync_state, save_sync_state, SyncState, SyncStateService,
+    clear_brand_new, delete_sync_state, is_brand_new, load_birth_height, load_sync_journal,
+    load_sync_state, mark_brand_new, save_birth_height, save_sync_journal, save_sync_state,
+    SyncJournalEntry, SyncState, SyncStateService, SYNC_JOURNAL_LENGTH,
+};
+pub use self::wallet_service::{
+    load_wallet, Wallet, WalletExport, WalletInfo, WalletService, WalletStorageImpl,
+    WALLET_EXPORT_VERSION,
 };
-pub use self::wallet_service::{load_wallet, Wallet, WalletInfo, WalletService, WalletStorageImpl};
 pub use self::wallet_state_service::{
     delete_wallet_state, load_wallet_state, modify_wallet_state, save_wallet_state, WalletState,
     WalletStateService,
@@ -0,0 +1,201 @@
+use chain_core::common::Timespec;
+use chain_core::state::account::{StakedState, StakedStateAddress};
+use client_common::{Result, Storage};
+use parity_scale_codec::{Decode, Encode};
+
+use crate::types::{Alert, AlertRule, TransactionChange, WalletBalance};
+
+/// key space of wallet alert rules
+pub(crate) const KEYSPACE: &str = "core_wallet_alert_rules";
+/// key space of triggered alerts
+pub(crate) const LOG_KEYSPACE: &str = "core_wallet_alert_log";
+
+/// Maximum number of alerts kept in the log of a wallet, older ones are dropped
+const MAX_LOG_SIZE: usize = 1000;
+
+/// Alert rules of a wallet and whether their conditions held at last evaluation
+#[derive(Debug, Default, Clone, PartialEq, Eq, Encode, Decode)]
+struct WalletAlertRules {
+    rules: Vec<AlertRule>,
+    firing: Vec<bool>,
+}
+
+/// Exposes functionalities for managing alert rules of wallets and the log of triggered alerts
+///
+/// Stores `wallet-name -> alert-rules` and `wallet-name -> alerts`
+#[derive(Debug, Default, Clone)]
+pub struct AlertService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> AlertService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of alert service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Returns alert rules of given wallet
+    pub fn get_rules(&self, name: &str) -> Result<Vec<AlertRule>> {
+        Ok(self.load_rules(name)?.rules)
+    }
+
+    /// Replaces alert rules of given wallet
+    pub fn set_rules(&self, name: &str, rules: Vec<AlertRule>) -> Result<()> {
+        let firing = vec![false; rules.len()];
+        self.storage
+            .save(KEYSPACE, name, &WalletAlertRules { rules, firing })
+    }
+
+    /// Returns triggered alerts of given wallet, latest first
+    pub fn get_alerts(&self, name: &str, offset: usize, limit: usize) -> Result<Vec<Alert>> {
+        let alerts = self
+            .storage
+            .load::<Vec<Alert>>(LOG_KEYSPACE, name)?
+            .unwrap_or_default();
+        Ok(alerts.into_iter().rev().skip(offset).take(limit).collect())
+    }
+
+    /// Evaluates alert rules of given wallet against a synchronized batch of blocks, records
+    /// and returns the triggered alerts
+    ///
+    /// Transaction rules trigger on every matching transaction. Balance and staking rules
+    /// trigger once when their condition starts to hold, and again only after it cleared.
+    /// `staked_state` is only called for watched staking addresses.
+    pub fn evaluate<F>(
+        &self,
+        name: &str,
+        block_height: u64,
+        block_time: Timespec,
+        changes: &[&TransactionChange],
+        balance: &WalletBalance,
+        staked_state: F,
+    ) -> Result<Vec<Alert>>
+    where
+        F: Fn(&StakedStateAddress) -> Result<Option<StakedState>>,
+    {
+        let mut state = self.load_rules(name)?;
+        if state.rules.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut alerts = vec![];
+        for (rule, firing) in state.rules.iter().zip(state.firing.iter_mut()) {
+            for change in changes {
+                if let Some(message) = rule.check_transaction(change) {
+                    alerts.push(Alert {
+                        rule: rule.clone(),
+                        block_height: change.block_height,
+                        transaction_id: Some(hex::encode(change.transaction_id)),
+                        message,
+                    });
+                }
+            }
+
+            let message = match rule {
+                AlertRule::BalanceBelow { .. } => rule.check_balance(balance),
+                AlertRule::StakingJailed { address } => staked_state(address)?
+                    .and_then(|state| rule.check_staked_state(&state, block_time)),
+                AlertRule::OutgoingAbove { .. } => continue,
+            };
+            match message {
+                Some(message) if !*firing => alerts.push(Alert {
+                    rule: rule.clone(),
+                    block_height,
+                    transaction_id: None,
+                    message,
+                }),
+                _ => {}
+            }
+            *firing = message.is_some();
+        }
+
+        self.storage.save(KEYSPACE, name, &state)?;
+        if !alerts.is_empty() {
+            let mut log = self
+                .storage
+                .load::<Vec<Alert>>(LOG_KEYSPACE, name)?
+                .unwrap_or_default();
+            log.extend(alerts.iter().cloned());
+            let excess = log.len().saturating_sub(MAX_LOG_SIZE);
+            log.drain(..excess);
+            self.storage.save(LOG_KEYSPACE, name, &log)?;
+        }
+        Ok(alerts)
+    }
+
+    /// Deletes alert rules and alerts of given wallet
+    pub fn delete(&self, name: &str) -> Result<()> {
+        self.storage.delete(KEYSPACE, name)?;
+        self.storage.delete(LOG_KEYSPACE, name)?;
+        Ok(())
+    }
+
+    /// Clears all storage
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(KEYSPACE)?;
+        self.storage.clear(LOG_KEYSPACE)
+    }
+
+    fn load_rules(&self, name: &str) -> Result<WalletAlertRules> {
+        Ok(self.storage.load(KEYSPACE, name)?.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::coin::Coin;
+    use client_common::storage::MemoryStorage;
+
+    #[test]
+    fn check_flow() {
+        let service = AlertService::new(MemoryStorage::default());
+        let name = "name";
+        let rule = AlertRule::BalanceBelow {
+            amount: Coin::new(100).unwrap(),
+        };
+        service.set_rules(name, vec![rule.clone()]).unwrap();
+        assert_eq!(vec![rule], service.get_rules(name).unwrap());
+
+        let low = WalletBalance {
+            total: Coin::new(50).unwrap(),
+            available: Coin::new(50).unwrap(),
+            pending: Coin::zero(),
+            reserved: Coin::zero(),
+        };
+        let high = WalletBalance {
+            total: Coin::new(200).unwrap(),
+            available: Coin::new(200).unwrap(),
+            pending: Coin::zero(),
+            reserved: Coin::zero(),
+        };
+        let evaluate = |height, balance: &WalletBalance| {
+            service
+                .evaluate(name, height, 0, &[], balance, |_| Ok(None))
+                .unwrap()
+                .len()
+        };
+
+        // triggers once while the condition holds
+        assert_eq!(1, evaluate(1, &low));
+        assert_eq!(0, evaluate(2, &low));
+        assert_eq!(0, evaluate(3, &high));
+        assert_eq!(1, evaluate(4, &low));
+
+        let alerts = service.get_alerts(name, 0, 10).unwrap();
+        assert_eq!(2, alerts.len());
+        assert_eq!(4, alerts[0].block_height);
+        assert_eq!(1, service.get_alerts(name, 1, 10).unwrap().len());
+
+        service.delete(name).unwrap();
+        assert!(service.get_rules(name).unwrap().is_empty());
+        assert!(service.get_alerts(name, 0, 10).unwrap().is_empty());
+    }
+}
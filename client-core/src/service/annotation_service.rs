@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+
+use chain_core::tx::data::TxId;
+use client_common::{Result, SecKey, SecureStorage, Storage};
+
+use crate::types::{AnnotationConflict, AnnotationImportReport, TxAnnotation};
+
+/// key space of transaction annotations
+pub(crate) const KEYSPACE: &str = "core_wallet_tx_annotation";
+
+/// Exposes functionalities for managing annotations of wallet transactions
+///
+/// Stores `wallet-name -> (transaction-id -> annotation)` (encrypted)
+#[derive(Debug, Default, Clone)]
+pub struct AnnotationService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> AnnotationService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of annotation service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Returns all annotations of given wallet
+    #[inline]
+    pub fn get_annotations(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<BTreeMap<TxId, TxAnnotation>> {
+        Ok(self
+            .storage
+            .load_secure(KEYSPACE, name, enckey)?
+            .unwrap_or_default())
+    }
+
+    /// Sets annotation of a transaction of given wallet, an empty annotation removes it
+    pub fn set_annotation(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: TxId,
+        annotation: TxAnnotation,
+    ) -> Result<()> {
+        let mut annotations = self.get_annotations(name, enckey)?;
+        if annotation.is_empty() {
+            annotations.remove(&transaction_id);
+        } else {
+            annotations.insert(transaction_id, annotation);
+        }
+        self.storage
+            .save_secure(KEYSPACE, name, enckey, &annotations)
+    }
+
+    /// Merges imported annotations into the annotations of given wallet, entries are applied in
+    /// order and `conflict` decides how they are combined with already present annotations
+    pub fn import(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        entries: Vec<(TxId, TxAnnotation)>,
+        conflict: AnnotationConflict,
+    ) -> Result<AnnotationImportReport> {
+        let mut annotations = self.get_annotations(name, enckey)?;
+        let mut report = AnnotationImportReport::default();
+
+        for (transaction_id, imported) in entries {
+            let existing = annotations.remove(&transaction_id).unwrap_or_default();
+            let resolved = existing.resolve(imported, conflict);
+            if resolved == existing {
+                report.unchanged += 1;
+            } else if existing.is_empty() {
+                report.added += 1;
+            } else {
+                report.updated += 1;
+            }
+            if !resolved.is_empty() {
+                annotations.insert(transaction_id, resolved);
+            }
+        }
+
+        self.storage
+            .save_secure(KEYSPACE, name, enckey, &annotations)?;
+        Ok(report)
+    }
+
+    /// Deletes annotations of given wallet
+    #[inline]
+    pub fn delete(&self, name: &str) -> Result<()> {
+        self.storage.delete(KEYSPACE, name).map(|_| ())
+    }
+
+    /// Clears all storage
+    #[inline]
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(KEYSPACE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::MemoryStorage;
+    use secstr::SecUtf8;
+
+    fn annotation(label: &str, category: &str) -> TxAnnotation {
+        TxAnnotation {
+            label: label.to_owned(),
+            category: category.to_owned(),
+            counterparty: "".to_owned(),
+        }
+    }
+
+    #[test]
+    fn check_import() {
+        let service = AnnotationService::new(MemoryStorage::default());
+        let name = "name";
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+
+        service
+            .set_annotation(name, &enckey, [0; 32], annotation("rent", ""))
+            .unwrap();
+        service
+            .set_annotation(name, &enckey, [1; 32], annotation("salary", "income"))
+            .unwrap();
+
+        let report = service
+            .import(
+                name,
+                &enckey,
+                vec![
+                    ([0; 32], annotation("office rent", "expenses")),
+                    ([1; 32], annotation("", "income")),
+                    ([2; 32], annotation("refund", "")),
+                ],
+                AnnotationConflict::Merge,
+            )
+            .unwrap();
+        assert_eq!(
+            AnnotationImportReport {
+                added: 1,
+                updated: 1,
+                unchanged: 1,
+                unknown_transactions: vec![],
+            },
+            report
+        );
+
+        let annotations = service.get_annotations(name, &enckey).unwrap();
+        assert_eq!(annotation("rent", "expenses"), annotations[&[0; 32]]);
+        assert_eq!(annotation("salary", "income"), annotations[&[1; 32]]);
+        assert_eq!(annotation("refund", ""), annotations[&[2; 32]]);
+
+        service
+            .set_annotation(name, &enckey, [2; 32], TxAnnotation::default())
+            .unwrap();
+        assert_eq!(2, service.get_annotations(name, &enckey).unwrap().len());
+
+        service.delete(name).unwrap();
+        assert!(service.get_annotations(name, &enckey).unwrap().is_empty());
+    }
+}
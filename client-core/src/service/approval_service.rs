@@ -0,0 +1,156 @@
+use client_common::{Result, SecKey, SecureStorage, Storage};
+
+use crate::types::PendingApproval;
+
+/// key space of spend approval policies
+pub(crate) const POLICY_KEYSPACE: &str = "core_wallet_approval_policy";
+/// key space of transfers pending approval
+pub(crate) const KEYSPACE: &str = "core_wallet_pending_approval";
+
+/// Exposes functionalities for managing spend approval of wallets
+///
+/// Stores `wallet-name -> approval-required` and `wallet-name -> pending-approvals` (encrypted)
+#[derive(Debug, Default, Clone)]
+pub struct ApprovalService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> ApprovalService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of approval service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Returns true if spends of given wallet need approval
+    #[inline]
+    pub fn is_required(&self, name: &str) -> Result<bool> {
+        Ok(self.storage.load(POLICY_KEYSPACE, name)?.unwrap_or(false))
+    }
+
+    /// Sets if spends of given wallet need approval
+    pub fn set_required(&self, name: &str, required: bool) -> Result<()> {
+        if required {
+            self.storage.save(POLICY_KEYSPACE, name, &required)
+        } else {
+            self.storage.delete(POLICY_KEYSPACE, name).map(|_| ())
+        }
+    }
+
+    /// Returns transfers of given wallet pending approval, in the order they were requested
+    #[inline]
+    pub fn get_pending(&self, name: &str, enckey: &SecKey) -> Result<Vec<PendingApproval>> {
+        Ok(self
+            .storage
+            .load_secure(KEYSPACE, name, enckey)?
+            .unwrap_or_default())
+    }
+
+    /// Returns the transfer pending approval with given transaction id (hex encoded)
+    pub fn find_pending(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: &str,
+    ) -> Result<Option<PendingApproval>> {
+        Ok(self
+            .get_pending(name, enckey)?
+            .into_iter()
+            .find(|pending| pending.transaction_id == transaction_id))
+    }
+
+    /// Adds a transfer pending approval to given wallet
+    pub fn add_pending(&self, name: &str, enckey: &SecKey, pending: PendingApproval) -> Result<()> {
+        let mut pendings = self.get_pending(name, enckey)?;
+        pendings.push(pending);
+        self.storage.save_secure(KEYSPACE, name, enckey, &pendings)
+    }
+
+    /// Removes the transfer pending approval with given transaction id (hex encoded), returns
+    /// false if there is no such transfer
+    pub fn remove_pending(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: &str,
+    ) -> Result<bool> {
+        let mut pendings = self.get_pending(name, enckey)?;
+        let count = pendings.len();
+        pendings.retain(|pending| pending.transaction_id != transaction_id);
+        if pendings.len() == count {
+            return Ok(false);
+        }
+        self.storage
+            .save_secure(KEYSPACE, name, enckey, &pendings)?;
+        Ok(true)
+    }
+
+    /// Deletes approval policy and pending approvals of given wallet
+    pub fn delete(&self, name: &str) -> Result<()> {
+        self.storage.delete(POLICY_KEYSPACE, name)?;
+        self.storage.delete(KEYSPACE, name).map(|_| ())
+    }
+
+    /// Clears all storage
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(POLICY_KEYSPACE)?;
+        self.storage.clear(KEYSPACE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::coin::Coin;
+    use chain_core::tx::data::Tx;
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::MemoryStorage;
+    use secstr::SecUtf8;
+
+    fn pending(transaction_id: &str) -> PendingApproval {
+        PendingApproval {
+            transaction_id: transaction_id.to_owned(),
+            transaction: Tx::default(),
+            spent_outputs: vec![],
+            return_amount: Coin::zero(),
+            block_height: 1,
+        }
+    }
+
+    #[test]
+    fn check_flow() {
+        let service = ApprovalService::new(MemoryStorage::default());
+        let name = "name";
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+
+        assert!(!service.is_required(name).unwrap());
+        service.set_required(name, true).unwrap();
+        assert!(service.is_required(name).unwrap());
+        service.set_required(name, false).unwrap();
+        assert!(!service.is_required(name).unwrap());
+
+        service.add_pending(name, &enckey, pending("01")).unwrap();
+        service.add_pending(name, &enckey, pending("02")).unwrap();
+        assert_eq!(
+            Some(pending("02")),
+            service.find_pending(name, &enckey, "02").unwrap()
+        );
+        assert!(service.remove_pending(name, &enckey, "01").unwrap());
+        assert!(!service.remove_pending(name, &enckey, "01").unwrap());
+        assert_eq!(
+            vec![pending("02")],
+            service.get_pending(name, &enckey).unwrap()
+        );
+
+        service.set_required(name, true).unwrap();
+        service.delete(name).unwrap();
+        assert!(!service.is_required(name).unwrap());
+        assert!(service.get_pending(name, &enckey).unwrap().is_empty());
+    }
+}
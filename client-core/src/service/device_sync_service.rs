@@ -0,0 +1,425 @@
+use parity_scale_codec::{Decode, Encode};
+use secp256k1::{PublicKey as SecpPublicKey, SecretKey};
+
+use client_common::seckey::derive_shared_enckey;
+use client_common::storage::{decrypt_bytes, encrypt_bytes};
+use client_common::{
+    Error, ErrorKind, PrivateKey, PublicKey, Result, ResultExt, SecKey, SecureStorage, Storage,
+};
+
+use crate::types::{DevicePairing, DeviceRole, EncryptedWalletEvent, PairedDevice, WalletEvent};
+
+/// key space of paired devices
+pub(crate) const KEYSPACE: &str = "core_wallet_device";
+
+/// Device key and paired devices of a wallet
+#[derive(Debug, Encode, Decode)]
+struct DeviceSyncState {
+    device_name: String,
+    device_key: PrivateKey,
+    devices: Vec<DeviceRecord>,
+}
+
+#[derive(Debug, Encode, Decode)]
+struct DeviceRecord {
+    device: PairedDevice,
+    /// events recorded for a secondary device and not acknowledged yet
+    outbox: Vec<(u64, WalletEvent)>,
+}
+
+impl DeviceSyncState {
+    fn find(&mut self, device_name: &str, role: DeviceRole) -> Result<&mut DeviceRecord> {
+        self.devices
+            .iter_mut()
+            .find(|record| record.device.name == device_name && record.device.role == role)
+            .ok_or_else(|| no_paired_device(device_name, role))
+    }
+
+    /// Returns the key shared with a paired device (ECDH)
+    fn shared_key(&self, device: &PairedDevice) -> Result<SecKey> {
+        let mut point = SecpPublicKey::from(&device.public_key);
+        point
+            .mul_assign(secp256k1::SECP256K1, &SecretKey::from(&self.device_key)[..])
+            .chain(|| (ErrorKind::EncryptionError, "Unable to derive shared key"))?;
+        Ok(derive_shared_enckey(&point.serialize()))
+    }
+}
+
+fn no_paired_device(device_name: &str, role: DeviceRole) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("No paired {:?} device with name ({})", role, device_name),
+    )
+}
+
+/// Exposes functionalities for pairing devices of a wallet and exchanging wallet events
+///
+/// Stores `wallet-name -> (device-key, paired-devices)` (encrypted)
+#[derive(Debug, Default, Clone)]
+pub struct DeviceSyncService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> DeviceSyncService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of device sync service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Returns pairing data of this device for given wallet, the device key is created on first
+    /// use
+    pub fn device_pairing(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        device_name: &str,
+    ) -> Result<DevicePairing> {
+        let state = match self.load_state(name, enckey)? {
+            Some(mut state) => {
+                state.device_name = device_name.to_owned();
+                state
+            }
+            None => DeviceSyncState {
+                device_name: device_name.to_owned(),
+                device_key: PrivateKey::new()?,
+                devices: Vec::new(),
+            },
+        };
+        self.save_state(name, enckey, &state)?;
+
+        Ok(DevicePairing {
+            device_name: state.device_name,
+            public_key: PublicKey::from(&state.device_key),
+        })
+    }
+
+    /// Pairs a device with given wallet, `role` is the role of the other device
+    pub fn pair(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        peer: DevicePairing,
+        role: DeviceRole,
+    ) -> Result<()> {
+        let mut state = self.load_state(name, enckey)?.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                "Pairing data of this device is not created yet",
+            )
+        })?;
+        if peer.public_key == PublicKey::from(&state.device_key) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Unable to pair a device with itself",
+            ));
+        }
+        if state
+            .devices
+            .iter()
+            .any(|record| record.device.name == peer.device_name)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Device with name ({}) is already paired", peer.device_name),
+            ));
+        }
+
+        state.devices.push(DeviceRecord {
+            device: PairedDevice {
+                name: peer.device_name,
+                public_key: peer.public_key,
+                role,
+                last_sequence: 0,
+                synced_height: None,
+            },
+            outbox: Vec::new(),
+        });
+        self.save_state(name, enckey, &state)
+    }
+
+    /// Removes a paired device of given wallet
+    pub fn unpair(&self, name: &str, enckey: &SecKey, device_name: &str) -> Result<()> {
+        if let Some(mut state) = self.load_state(name, enckey)? {
+            state
+                .devices
+                .retain(|record| record.device.name != device_name);
+            self.save_state(name, enckey, &state)?;
+        }
+        Ok(())
+    }
+
+    /// Returns devices paired with given wallet
+    pub fn devices(&self, name: &str, enckey: &SecKey) -> Result<Vec<PairedDevice>> {
+        Ok(self
+            .load_state(name, enckey)?
+            .map(|state| {
+                state
+                    .devices
+                    .into_iter()
+                    .map(|record| record.device)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Records a wallet event for the secondary devices of given wallet (does nothing if there
+    /// is none)
+    pub fn record_event(&self, name: &str, enckey: &SecKey, event: WalletEvent) -> Result<()> {
+        let mut state = match self.load_state(name, enckey)? {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        let mut recorded = false;
+        for record in state
+            .devices
+            .iter_mut()
+            .filter(|record| record.device.role == DeviceRole::Secondary)
+        {
+            if let WalletEvent::SyncCheckpoint { .. } = event {
+                record
+                    .outbox
+                    .retain(|(_, event)| !matches!(event, WalletEvent::SyncCheckpoint { .. }));
+            }
+            record.device.last_sequence += 1;
+            record
+                .outbox
+                .push((record.device.last_sequence, event.clone()));
+            recorded = true;
+        }
+
+        if recorded {
+            self.save_state(name, enckey, &state)?;
+        }
+        Ok(())
+    }
+
+    /// Returns events recorded for a secondary device after given sequence number, encrypted
+    /// with the key shared with the device
+    ///
+    /// Events up to `after` are acknowledged by the device, so they are removed.
+    pub fn export_events(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        device_name: &str,
+        after: u64,
+    ) -> Result<Vec<EncryptedWalletEvent>> {
+        let mut state = self
+            .load_state(name, enckey)?
+            .ok_or_else(|| no_paired_device(device_name, DeviceRole::Secondary))?;
+        let record = state.find(device_name, DeviceRole::Secondary)?;
+        let length = record.outbox.len();
+        record.outbox.retain(|(sequence, _)| *sequence > after);
+        let acknowledged = record.outbox.len() != length;
+        let device = record.device.clone();
+        let outbox = record.outbox.clone();
+        if acknowledged {
+            self.save_state(name, enckey, &state)?;
+        }
+
+        let shared_key = state.shared_key(&device)?;
+        outbox
+            .into_iter()
+            .map(|(sequence, event)| {
+                let payload = encrypt_bytes(sequence.to_le_bytes(), &shared_key, &event.encode())?;
+                Ok(EncryptedWalletEvent {
+                    sequence,
+                    payload: base64::encode(&payload),
+                })
+            })
+            .collect()
+    }
+
+    /// Decrypts events of a primary device and applies the ones which aren't imported yet, in
+    /// order of their sequence numbers
+    ///
+    /// Returns the sequence number of the last imported event, which is kept even if applying a
+    /// later event fails.
+    pub fn import_events<F>(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        device_name: &str,
+        mut events: Vec<EncryptedWalletEvent>,
+        mut apply: F,
+    ) -> Result<u64>
+    where
+        F: FnMut(&WalletEvent) -> Result<()>,
+    {
+        let mut state = self
+            .load_state(name, enckey)?
+            .ok_or_else(|| no_paired_device(device_name, DeviceRole::Primary))?;
+        let device = state.find(device_name, DeviceRole::Primary)?.device.clone();
+        let shared_key = state.shared_key(&device)?;
+
+        events.sort_by_key(|event| event.sequence);
+        events.dedup_by_key(|event| event.sequence);
+        let decrypted = events
+            .into_iter()
+            .filter(|event| event.sequence > device.last_sequence)
+            .map(|event| {
+                let payload = base64::decode(&event.payload).chain(|| {
+                    (
+                        ErrorKind::DeserializationError,
+                        "Unable to decode base64 wallet event",
+                    )
+                })?;
+                let bytes = decrypt_bytes(event.sequence.to_le_bytes(), &shared_key, &payload)
+                    .chain(|| {
+                        (
+                            ErrorKind::DecryptionError,
+                            format!("Unable to decrypt wallet event from ({})", device_name),
+                        )
+                    })?;
+                let decoded = WalletEvent::decode(&mut bytes.as_slice()).chain(|| {
+                    (
+                        ErrorKind::DeserializationError,
+                        "Unable to decode wallet event",
+                    )
+                })?;
+                Ok((event.sequence, decoded))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let record = state.find(device_name, DeviceRole::Primary)?;
+        let mut result = Ok(());
+        for (sequence, event) in decrypted {
+            result = apply(&event);
+            if result.is_err() {
+                break;
+            }
+            if let WalletEvent::SyncCheckpoint { block_height, .. } = event {
+                record.device.synced_height = Some(block_height);
+            }
+            record.device.last_sequence = sequence;
+        }
+        let last_sequence = record.device.last_sequence;
+
+        self.save_state(name, enckey, &state)?;
+        result.map(|_| last_sequence)
+    }
+
+    /// Deletes device key and paired devices of given wallet
+    #[inline]
+    pub fn delete(&self, name: &str) -> Result<()> {
+        self.storage.delete(KEYSPACE, name).map(|_| ())
+    }
+
+    /// Clears all storage
+    #[inline]
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(KEYSPACE)
+    }
+
+    fn load_state(&self, name: &str, enckey: &SecKey) -> Result<Option<DeviceSyncState>> {
+        self.storage.load_secure(KEYSPACE, name, enckey)
+    }
+
+    fn save_state(&self, name: &str, enckey: &SecKey, state: &DeviceSyncState) -> Result<()> {
+        self.storage.save_secure(KEYSPACE, name, enckey, state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::MemoryStorage;
+    use secstr::SecUtf8;
+
+    use crate::types::TxAnnotation;
+
+    #[test]
+    fn check_device_sync() {
+        let name = "name";
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let primary = DeviceSyncService::new(MemoryStorage::default());
+        let secondary = DeviceSyncService::new(MemoryStorage::default());
+
+        let checkpoint = |block_height| WalletEvent::SyncCheckpoint {
+            block_height,
+            block_hash: String::new(),
+        };
+        let annotation = || WalletEvent::Annotation {
+            transaction_id: [0; 32],
+            annotation: TxAnnotation::default(),
+        };
+
+        // events aren't recorded before a secondary device is paired
+        primary.record_event(name, &enckey, checkpoint(0)).unwrap();
+
+        let primary_pairing = primary.device_pairing(name, &enckey, "desktop").unwrap();
+        let secondary_pairing = secondary.device_pairing(name, &enckey, "phone").unwrap();
+        primary
+            .pair(name, &enckey, secondary_pairing, DeviceRole::Secondary)
+            .unwrap();
+        secondary
+            .pair(name, &enckey, primary_pairing.clone(), DeviceRole::Primary)
+            .unwrap();
+        assert!(secondary
+            .pair(name, &enckey, primary_pairing, DeviceRole::Primary)
+            .is_err());
+
+        primary.record_event(name, &enckey, checkpoint(1)).unwrap();
+        primary.record_event(name, &enckey, annotation()).unwrap();
+        // replaces the first checkpoint
+        primary.record_event(name, &enckey, checkpoint(2)).unwrap();
+
+        let events = primary.export_events(name, &enckey, "phone", 0).unwrap();
+        assert_eq!(
+            vec![2, 3],
+            events
+                .iter()
+                .map(|event| event.sequence)
+                .collect::<Vec<_>>()
+        );
+        assert!(primary.export_events(name, &enckey, "desktop", 0).is_err());
+
+        let mut applied = 0;
+        let last_sequence = secondary
+            .import_events(name, &enckey, "desktop", events.clone(), |_| {
+                applied += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(3, last_sequence);
+        assert_eq!(2, applied);
+        assert_eq!(
+            Some(2),
+            secondary.devices(name, &enckey).unwrap()[0].synced_height
+        );
+
+        // already imported events are skipped
+        secondary
+            .import_events(name, &enckey, "desktop", events, |_| unreachable!())
+            .unwrap();
+
+        // acknowledged events are removed
+        assert!(primary
+            .export_events(name, &enckey, "phone", last_sequence)
+            .unwrap()
+            .is_empty());
+
+        // events of another device can't be decrypted
+        let other = DeviceSyncService::new(MemoryStorage::default());
+        let other_pairing = other.device_pairing(name, &enckey, "tablet").unwrap();
+        primary
+            .pair(name, &enckey, other_pairing, DeviceRole::Secondary)
+            .unwrap();
+        for _ in 0..4 {
+            primary.record_event(name, &enckey, annotation()).unwrap();
+        }
+        let events = primary.export_events(name, &enckey, "tablet", 0).unwrap();
+        let error = secondary
+            .import_events(name, &enckey, "desktop", events, |_| Ok(()))
+            .unwrap_err();
+        assert_eq!(ErrorKind::DecryptionError, error.kind());
+    }
+}
@@ -12,7 +12,7 @@ use crate::{HDSeed, Mnemonic};
 use crate::hd_wallet::ChainPath;
 use std::convert::From;
 
-const KEYSPACE: &str = "core_hd_key";
+pub(crate) const KEYSPACE: &str = "core_hd_key";
 
 /// HD key
 #[derive(Debug, Clone, PartialEq, Default, Encode, Decode)]
@@ -28,6 +28,17 @@ pub struct HdKey {
     pub seed: HDSeed,
 }
 
+impl HdKey {
+    /// index of the last derived key of given account type
+    pub fn index(&self, account_type: HDAccountType) -> u32 {
+        match account_type {
+            HDAccountType::Transfer => self.transfer_index,
+            HDAccountType::Staking => self.staking_index,
+            HDAccountType::Viewkey => self.viewkey_index,
+        }
+    }
+}
+
 /// Enum for specifying different types of accounts
 #[derive(Debug, Clone, Copy)]
 pub enum HDAccountType {
@@ -75,23 +86,17 @@ where
 
     /// automatically recover address in syncing
     pub fn get_latest_transfer_index(&mut self, name: &str, enckey: &SecKey) -> Result<u32> {
-        let bytes: Vec<u8> = self.storage.get_secure(KEYSPACE, name, enckey)?.chain(|| {
-            (
-                ErrorKind::InvalidInput,
-                format!("HD Key with name ({}) not found", name),
-            )
-        })?;
-
-        //  let hd_key_bytes = decrypt_bytes(name, enckey, &bytes)?;
-        let hd_key = HdKey::decode(&mut bytes.as_slice()).chain(|| {
-            (
-                ErrorKind::DeserializationError,
-                "Unable to decode HD key bytes",
-            )
-        })?;
+        self.get_latest_index(name, enckey, HDAccountType::Transfer)
+    }
 
-        let index = hd_key.transfer_index;
-        Ok(index)
+    /// Returns the index of the last derived key of given account type
+    pub fn get_latest_index(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        account_type: HDAccountType,
+    ) -> Result<u32> {
+        Ok(self.load_hd_key(name, enckey)?.index(account_type))
     }
 
     /// Returns true if wallet's HD key is present in storage
@@ -158,6 +163,44 @@ where
 
     /// peek key pair by index
     pub fn peek_pubkey(&self, name: &str, enckey: &SecKey, index: u32) -> Result<PublicKey> {
+        self.peek_account_pubkey(name, enckey, HDAccountType::Transfer, index)
+    }
+
+    /// peek public key of given account type by index, without updating the stored HDKey
+    pub fn peek_account_pubkey(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        account_type: HDAccountType,
+        index: u32,
+    ) -> Result<PublicKey> {
+        self.load_hd_key(name, enckey)?
+            .seed
+            .get_pubkey(get_network(), account_type.index(), index)
+    }
+
+    /// peek public keys of the next `gap_limit` keys of given account type (not derived yet),
+    /// which are scanned for when recovering a wallet
+    pub fn peek_recovery_window(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        account_type: HDAccountType,
+        gap_limit: u32,
+    ) -> Result<Vec<(u32, PublicKey)>> {
+        let hd_key = self.load_hd_key(name, enckey)?;
+        let latest = hd_key.index(account_type);
+        (latest + 1..=latest.saturating_add(gap_limit))
+            .map(|index| {
+                hd_key
+                    .seed
+                    .get_pubkey(get_network(), account_type.index(), index)
+                    .map(|public_key| (index, public_key))
+            })
+            .collect()
+    }
+
+    fn load_hd_key(&self, name: &str, enckey: &SecKey) -> Result<HdKey> {
         let bytes: Vec<u8> = self.storage.get_secure(KEYSPACE, name, enckey)?.chain(|| {
             (
                 ErrorKind::InvalidInput,
@@ -165,17 +208,12 @@ where
             )
         })?;
 
-        //  let hd_key_bytes = decrypt_bytes(name, enckey, &bytes)?;
-        let hd_key = HdKey::decode(&mut bytes.as_slice()).chain(|| {
+        HdKey::decode(&mut bytes.as_slice()).chain(|| {
             (
                 ErrorKind::DeserializationError,
                 "Unable to decode HD key bytes",
             )
-        })?;
-
-        hd_key
-            .seed
-            .get_pubkey(get_network(), HDAccountType::Transfer.index(), index)
+        })
     }
 
     /// update the stored HDKey, return the updated one
@@ -246,11 +284,7 @@ where
         account_type: HDAccountType,
     ) -> Result<(PublicKey, PrivateKey)> {
         let hd_key = self.update_hd_key(name, enckey, account_type)?;
-        let index = match account_type {
-            HDAccountType::Transfer => hd_key.transfer_index,
-            HDAccountType::Staking => hd_key.staking_index,
-            HDAccountType::Viewkey => hd_key.viewkey_index,
-        };
+        let index = hd_key.index(account_type);
 
         hd_key
             .seed
@@ -267,11 +301,7 @@ where
         account_type: HDAccountType,
     ) -> Result<ChainPath> {
         let hd_key = self.update_hd_key(name, enckey, account_type)?;
-        let index = match account_type {
-            HDAccountType::Transfer => hd_key.transfer_index,
-            HDAccountType::Staking => hd_key.staking_index,
-            HDAccountType::Viewkey => hd_key.viewkey_index,
-        };
+        let index = hd_key.index(account_type);
         let chain_path = ChainPath::create_bip44(get_network(), account_type.index(), index);
         Ok(chain_path)
     }
@@ -428,4 +458,48 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn check_peek_recovery_window() {
+        let storage = MemoryStorage::default();
+        let name = "testhdwallet";
+        let passphrase = SecUtf8::from("passphrase");
+        let service = HdKeyService::new(storage.clone());
+        let mnemonic = Mnemonic::new(24).unwrap();
+
+        let wallet = DefaultWalletClient::new_read_only(storage);
+        let enckey = wallet
+            .restore_wallet(&name, &passphrase, &mnemonic)
+            .expect("restore wallet");
+
+        let window = service
+            .peek_recovery_window(name, &enckey, HDAccountType::Staking, 3)
+            .unwrap();
+        assert_eq!(
+            vec![1, 2, 3],
+            window.iter().map(|(index, _)| *index).collect::<Vec<_>>()
+        );
+
+        // the window slides once a key is derived
+        let (public_key, _) = service
+            .generate_keypair(name, &enckey, HDAccountType::Staking)
+            .unwrap();
+        assert_eq!(window[0].1, public_key);
+        assert_eq!(
+            1,
+            service
+                .get_latest_index(name, &enckey, HDAccountType::Staking)
+                .unwrap()
+        );
+        let window = service
+            .peek_recovery_window(name, &enckey, HDAccountType::Staking, 3)
+            .unwrap();
+        assert_eq!(2, window[0].0);
+        assert_eq!(
+            window[1].1,
+            service
+                .peek_account_pubkey(name, &enckey, HDAccountType::Staking, 3)
+                .unwrap()
+        );
+    }
 }
@@ -2,6 +2,8 @@ use crate::hd_wallet::ChainPath;
 use crate::service::ledger_hw_key_service::LedgerService;
 #[cfg(feature = "mock-hardware-wallet")]
 use crate::service::MockHardwareService;
+#[cfg(feature = "pkcs11")]
+use crate::service::Pkcs11Service;
 use client_common::{ErrorKind, PrivateKeyAction, PublicKey, Result};
 
 /// collection of hardware key interfaces
@@ -32,6 +34,9 @@ pub enum HwKeyService {
     Mock(MockHardwareService),
     /// ledger service
     Ledger(LedgerService),
+    /// PKCS#11 (HSM) service
+    #[cfg(feature = "pkcs11")]
+    Pkcs11(Pkcs11Service),
 }
 
 impl Default for HwKeyService {
@@ -48,6 +53,8 @@ impl HwKeyService {
             #[cfg(feature = "mock-hardware-wallet")]
             HwKeyService::Mock(hw_key_service) => hw_key_service.get_sign_key(hd_path),
             HwKeyService::Ledger(ledger_service) => ledger_service.get_sign_key(hd_path),
+            #[cfg(feature = "pkcs11")]
+            HwKeyService::Pkcs11(pkcs11_service) => pkcs11_service.get_sign_key(hd_path),
         }
     }
 
@@ -58,6 +65,8 @@ impl HwKeyService {
             #[cfg(feature = "mock-hardware-wallet")]
             HwKeyService::Mock(hw_key_service) => hw_key_service.get_public_key(chain_path),
             HwKeyService::Ledger(ledger_service) => ledger_service.get_public_key(chain_path),
+            #[cfg(feature = "pkcs11")]
+            HwKeyService::Pkcs11(pkcs11_service) => pkcs11_service.get_public_key(chain_path),
         }
     }
 }
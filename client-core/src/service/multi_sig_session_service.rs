@@ -5,7 +5,7 @@ use client_common::{
     ErrorKind, PrivateKey, PublicKey, Result, ResultExt, SecKey, SecureStorage, Storage,
 };
 
-use crate::multi_sig::MultiSigBuilder;
+use crate::multi_sig::{MultiSigBuilder, SignerStatus};
 
 const KEYSPACE: &str = "core_multi_sig_address";
 
@@ -169,6 +169,35 @@ where
         Ok(session.public_keys())
     }
 
+    /// Returns progress of all signers in this session, to resume a session with the co-signers
+    pub fn session_status(&self, session_id: &H256, enckey: &SecKey) -> Result<Vec<SignerStatus>> {
+        let session = self.get_session(session_id, enckey)?;
+        Ok(session.signer_statuses())
+    }
+
+    /// Returns ids of the sessions encrypted with given enckey (i.e. sessions of a wallet)
+    pub fn session_ids(&self, enckey: &SecKey) -> Result<Vec<H256>> {
+        let mut session_ids = Vec::new();
+        for key in self.storage.keys(KEYSPACE)? {
+            match self.storage.get_secure(KEYSPACE, &key, enckey) {
+                Ok(Some(_)) if key.len() == 32 => {
+                    let mut session_id = H256::default();
+                    session_id.copy_from_slice(&key);
+                    session_ids.push(session_id);
+                }
+                Ok(_) => {}
+                Err(err) if err.kind() == ErrorKind::DecryptionError => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(session_ids)
+    }
+
+    /// Deletes a session from storage
+    pub fn delete_session(&self, session_id: &H256) -> Result<()> {
+        self.storage.delete(KEYSPACE, session_id).map(|_| ())
+    }
+
     /// Retrieves a session from storage
     fn get_session(&self, session_id: &H256, enckey: &SecKey) -> Result<MultiSigBuilder> {
         let session_bytes = self
@@ -259,6 +288,14 @@ mod multi_sig_session_service_tests {
             .add_nonce_commitment(&session_id_2, nonce_commitment_1, &public_key_1, &enckey)
             .expect("Unable to add nonce commitment to session 2");
 
+        let status = multi_sig_service
+            .session_status(&session_id_1, &enckey)
+            .unwrap();
+        assert_eq!(2, status.len());
+        assert!(status
+            .iter()
+            .all(|signer| signer.nonce_commitment && !signer.nonce));
+
         let nonce_1 = multi_sig_service.nonce(&session_id_1, &enckey).unwrap();
         assert!(
             multi_sig_service.nonce(&session_id_1, &enckey).is_ok(),
@@ -297,6 +334,11 @@ mod multi_sig_session_service_tests {
         multi_sig_service
             .add_partial_signature(&session_id_2, partial_signature_1, &public_key_1, &enckey)
             .expect("Unable to add partial signature to session 2");
+        assert!(multi_sig_service
+            .session_status(&session_id_2, &enckey)
+            .unwrap()
+            .iter()
+            .all(|signer| signer.nonce && signer.partial_signature));
 
         let signature_1 = multi_sig_service.signature(&session_id_1, &enckey).unwrap();
         let signature_2 = multi_sig_service.signature(&session_id_2, &enckey).unwrap();
@@ -0,0 +1,87 @@
+use chain_core::state::account::StakedStateAddress;
+use client_common::{Result, Storage};
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// key space of wallet operator profile
+pub(crate) const KEYSPACE: &str = "core_wallet_operator_profile";
+
+/// Links a wallet to the council node it operates
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct OperatorProfile {
+    /// staking address of the council node
+    pub staking_address: StakedStateAddress,
+}
+
+/// Exposes functionalities for managing operator profiles of wallets
+///
+/// Stores `wallet-name -> operator-profile`
+#[derive(Debug, Default, Clone)]
+pub struct OperatorProfileService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> OperatorProfileService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of operator profile service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Returns operator profile of given wallet
+    #[inline]
+    pub fn get_profile(&self, name: &str) -> Result<Option<OperatorProfile>> {
+        self.storage.load(KEYSPACE, name)
+    }
+
+    /// Saves operator profile of given wallet
+    #[inline]
+    pub fn set_profile(&self, name: &str, profile: &OperatorProfile) -> Result<()> {
+        self.storage.save(KEYSPACE, name, profile)
+    }
+
+    /// Deletes operator profile of given wallet
+    #[inline]
+    pub fn delete_profile(&self, name: &str) -> Result<()> {
+        self.storage.delete(KEYSPACE, name).map(|_| ())
+    }
+
+    /// Clears all storage
+    #[inline]
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(KEYSPACE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::address::RedeemAddress;
+    use client_common::storage::MemoryStorage;
+
+    #[test]
+    fn check_flow() {
+        let service = OperatorProfileService::new(MemoryStorage::default());
+        let name = "name";
+        let profile = OperatorProfile {
+            staking_address: StakedStateAddress::BasicRedeem(RedeemAddress([1u8; 20])),
+        };
+
+        assert!(service.get_profile(name).unwrap().is_none());
+        service.set_profile(name, &profile).unwrap();
+        assert_eq!(Some(profile.clone()), service.get_profile(name).unwrap());
+
+        service.delete_profile(name).unwrap();
+        assert!(service.get_profile(name).unwrap().is_none());
+
+        service.set_profile(name, &profile).unwrap();
+        service.clear().unwrap();
+        assert!(service.get_profile(name).unwrap().is_none());
+    }
+}
@@ -0,0 +1,219 @@
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use client_common::{Clock, Error, ErrorKind, Result, ResultExt, Storage, SystemClock};
+
+/// key space of failed passphrase attempts
+pub(crate) const KEYSPACE: &str = "core_wallet_passphrase_attempt";
+
+/// Number of failed attempts allowed before any lockout is applied
+const FREE_ATTEMPTS: u32 = 3;
+/// Lockout applied on the first failure after free attempts are used up (in seconds)
+const BASE_LOCKOUT_SECS: u64 = 2;
+/// Upper bound of lockout window (in seconds)
+const MAX_LOCKOUT_SECS: u64 = 60 * 60;
+
+/// Failed passphrase attempts of a wallet
+#[derive(Debug, Default, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct PassphraseAttempts {
+    /// number of consecutive failed attempts
+    pub failed_attempts: u32,
+    /// unix timestamp (in seconds) of last failed attempt
+    pub last_failure: u64,
+    /// unix timestamp (in seconds) until which the wallet is locked
+    pub locked_until: u64,
+}
+
+impl PassphraseAttempts {
+    /// Lockout window (in seconds) after given number of consecutive failures
+    pub fn lockout_secs(failed_attempts: u32) -> u64 {
+        if failed_attempts < FREE_ATTEMPTS {
+            return 0;
+        }
+        let exponent = failed_attempts - FREE_ATTEMPTS;
+        if exponent >= 32 {
+            return MAX_LOCKOUT_SECS;
+        }
+        BASE_LOCKOUT_SECS
+            .saturating_mul(1u64 << exponent)
+            .min(MAX_LOCKOUT_SECS)
+    }
+
+    /// Returns true if the lockout ended long enough ago for the record to be discarded
+    pub fn is_expired<K: Clock>(&self, clock: &K) -> bool {
+        self.locked_until.saturating_add(MAX_LOCKOUT_SECS) <= clock.unix_timestamp()
+    }
+}
+
+/// Lockout status of a wallet returned to clients
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockoutStatus {
+    /// is the wallet currently locked
+    pub locked: bool,
+    /// number of consecutive failed attempts
+    pub failed_attempts: u32,
+    /// remaining seconds before next attempt is allowed
+    pub retry_after: u64,
+}
+
+/// Exposes brute-force protection for wallet passphrases
+///
+/// Stores `wallet-name -> passphrase-attempts`
+#[derive(Debug, Default, Clone)]
+pub struct PassphraseAttemptService<S, K = SystemClock>
+where
+    S: Storage,
+    K: Clock,
+{
+    storage: S,
+    clock: K,
+}
+
+impl<S> PassphraseAttemptService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of passphrase attempt service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self::with_clock(storage, SystemClock)
+    }
+}
+
+impl<S, K> PassphraseAttemptService<S, K>
+where
+    S: Storage,
+    K: Clock,
+{
+    /// Creates new instance of passphrase attempt service with given time source
+    #[inline]
+    pub fn with_clock(storage: S, clock: K) -> Self {
+        Self { storage, clock }
+    }
+
+    /// Returns failed attempts of given wallet
+    pub fn get_attempts(&self, name: &str) -> Result<PassphraseAttempts> {
+        if let Some(bytes) = self.storage.get(KEYSPACE, name)? {
+            PassphraseAttempts::decode(&mut bytes.as_slice()).chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!(
+                        "Unable to deserialize passphrase attempts for wallet with name {}",
+                        name
+                    ),
+                )
+            })
+        } else {
+            Ok(PassphraseAttempts::default())
+        }
+    }
+
+    /// Returns lockout status of given wallet
+    pub fn status(&self, name: &str) -> Result<LockoutStatus> {
+        let attempts = self.get_attempts(name)?;
+        let retry_after = attempts
+            .locked_until
+            .saturating_sub(self.clock.unix_timestamp());
+        Ok(LockoutStatus {
+            locked: retry_after > 0,
+            failed_attempts: attempts.failed_attempts,
+            retry_after,
+        })
+    }
+
+    /// Returns an error if given wallet is currently locked out
+    pub fn check(&self, name: &str) -> Result<()> {
+        let status = self.status(name)?;
+        if status.locked {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "Too many failed passphrase attempts for wallet ({}), retry after {} seconds",
+                    name, status.retry_after
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Records a failed attempt and extends lockout window of given wallet
+    pub fn record_failure(&self, name: &str) -> Result<LockoutStatus> {
+        let now = self.clock.unix_timestamp();
+        let mut attempts = self.get_attempts(name)?;
+        attempts.failed_attempts = attempts.failed_attempts.saturating_add(1);
+        attempts.last_failure = now;
+        attempts.locked_until = now + PassphraseAttempts::lockout_secs(attempts.failed_attempts);
+        self.storage.set(KEYSPACE, name, attempts.encode())?;
+        self.status(name)
+    }
+
+    /// Clears failed attempts of given wallet after a successful attempt
+    #[inline]
+    pub fn record_success(&self, name: &str) -> Result<()> {
+        self.storage.delete(KEYSPACE, name).map(|_| ())
+    }
+
+    /// Clears all storage
+    #[inline]
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(KEYSPACE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::clock::MockClock;
+    use client_common::storage::MemoryStorage;
+
+    #[test]
+    fn check_lockout_secs() {
+        assert_eq!(0, PassphraseAttempts::lockout_secs(0));
+        assert_eq!(0, PassphraseAttempts::lockout_secs(FREE_ATTEMPTS - 1));
+        assert_eq!(2, PassphraseAttempts::lockout_secs(FREE_ATTEMPTS));
+        assert_eq!(4, PassphraseAttempts::lockout_secs(FREE_ATTEMPTS + 1));
+        assert_eq!(
+            MAX_LOCKOUT_SECS,
+            PassphraseAttempts::lockout_secs(FREE_ATTEMPTS + 20)
+        );
+        assert_eq!(MAX_LOCKOUT_SECS, PassphraseAttempts::lockout_secs(u32::MAX));
+    }
+
+    #[test]
+    fn check_flow() {
+        let clock = MockClock::new(1_000);
+        let service = PassphraseAttemptService::with_clock(MemoryStorage::default(), clock.clone());
+        let name = "name";
+
+        for _ in 0..FREE_ATTEMPTS - 1 {
+            assert!(!service.record_failure(name).unwrap().locked);
+            assert!(service.check(name).is_ok());
+        }
+
+        let status = service.record_failure(name).unwrap();
+        assert!(status.locked);
+        assert_eq!(FREE_ATTEMPTS, status.failed_attempts);
+        assert_eq!(BASE_LOCKOUT_SECS, status.retry_after);
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            service.check(name).unwrap_err().kind()
+        );
+        clock.advance(BASE_LOCKOUT_SECS);
+        assert!(service.check(name).is_ok());
+
+        let status = service.record_failure(name).unwrap();
+        assert_eq!(BASE_LOCKOUT_SECS * 2, status.retry_after);
+
+        let attempts = service.get_attempts(name).unwrap();
+        assert!(!attempts.is_expired(&clock));
+        clock.advance(BASE_LOCKOUT_SECS * 2 + MAX_LOCKOUT_SECS);
+        assert!(attempts.is_expired(&clock));
+
+        service.record_success(name).unwrap();
+        assert_eq!(
+            PassphraseAttempts::default(),
+            service.get_attempts(name).unwrap()
+        );
+        assert!(service.check(name).is_ok());
+    }
+}
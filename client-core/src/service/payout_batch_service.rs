@@ -0,0 +1,117 @@
+use client_common::{Error, ErrorKind, Result, SecKey, SecureStorage, Storage};
+
+use crate::types::PayoutBatch;
+
+/// key space of payout batches
+pub(crate) const KEYSPACE: &str = "core_wallet_payout_batch";
+
+/// Exposes functionalities for managing payout batches of wallets
+///
+/// Stores `wallet-name -> payout-batches` (encrypted)
+#[derive(Debug, Default, Clone)]
+pub struct PayoutBatchService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> PayoutBatchService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of payout batch service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Returns payout batch of given wallet with given batch name
+    pub fn get_batch(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        batch_name: &str,
+    ) -> Result<Option<PayoutBatch>> {
+        Ok(self
+            .load_batches(name, enckey)?
+            .into_iter()
+            .find(|batch| batch.name == batch_name))
+    }
+
+    /// Returns an error if given wallet already has a payout batch with given name
+    pub fn check_name(&self, name: &str, enckey: &SecKey, batch_name: &str) -> Result<()> {
+        if self.get_batch(name, enckey, batch_name)?.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Payout batch with name ({}) already exists", batch_name),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Adds a payout batch to given wallet
+    pub fn add_batch(&self, name: &str, enckey: &SecKey, batch: PayoutBatch) -> Result<()> {
+        self.check_name(name, enckey, &batch.name)?;
+        let mut batches = self.load_batches(name, enckey)?;
+        batches.push(batch);
+        self.storage.save_secure(KEYSPACE, name, enckey, &batches)
+    }
+
+    /// Deletes payout batches of given wallet
+    #[inline]
+    pub fn delete(&self, name: &str) -> Result<()> {
+        self.storage.delete(KEYSPACE, name).map(|_| ())
+    }
+
+    /// Clears all storage
+    #[inline]
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(KEYSPACE)
+    }
+
+    fn load_batches(&self, name: &str, enckey: &SecKey) -> Result<Vec<PayoutBatch>> {
+        Ok(self
+            .storage
+            .load_secure(KEYSPACE, name, enckey)?
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::MemoryStorage;
+    use secstr::SecUtf8;
+
+    #[test]
+    fn check_flow() {
+        let service = PayoutBatchService::new(MemoryStorage::default());
+        let name = "name";
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let batch = PayoutBatch {
+            name: "payroll".to_owned(),
+            block_height: 1,
+            transaction_ids: vec![],
+            recipients: vec![],
+        };
+
+        assert!(service
+            .get_batch(name, &enckey, "payroll")
+            .unwrap()
+            .is_none());
+        service.add_batch(name, &enckey, batch.clone()).unwrap();
+        assert_eq!(
+            Some(batch.clone()),
+            service.get_batch(name, &enckey, "payroll").unwrap()
+        );
+        assert!(service.add_batch(name, &enckey, batch).is_err());
+
+        service.delete(name).unwrap();
+        assert!(service
+            .get_batch(name, &enckey, "payroll")
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,312 @@
+use crate::hd_wallet::ChainPath;
+use crate::service::hw_key_service::HardwareWalletAction;
+use client_common::{
+    Error, ErrorKind, PrivateKeyAction, PublicKey, Result, ResultExt, Transaction,
+};
+use pkcs11::types::{
+    CKA_CLASS, CKA_EC_PARAMS, CKA_EC_POINT, CKA_EXTRACTABLE, CKA_LABEL, CKA_SENSITIVE, CKA_SIGN,
+    CKA_TOKEN, CKA_VERIFY, CKF_RW_SESSION, CKF_SERIAL_SESSION, CKM_ECDSA, CKM_EC_KEY_PAIR_GEN,
+    CKO_PRIVATE_KEY, CKO_PUBLIC_KEY, CKR_USER_ALREADY_LOGGED_IN, CKU_USER, CK_ATTRIBUTE, CK_BBOOL,
+    CK_FALSE, CK_MECHANISM, CK_OBJECT_CLASS, CK_OBJECT_HANDLE, CK_SESSION_HANDLE, CK_SLOT_ID,
+    CK_TRUE,
+};
+use pkcs11::{errors::Error as Pkcs11Error, Ctx};
+use secp256k1::recovery::{RecoverableSignature, RecoveryId};
+use secp256k1::schnorrsig::SchnorrSignature;
+use secp256k1::{Message, Signature};
+use secstr::SecUtf8;
+use std::env;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+/// DER encoded OID of the secp256k1 curve (1.3.132.0.10)
+const SECP256K1_EC_PARAMS: [u8; 7] = [0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+/// prefix of the labels of keys created by the client
+const KEY_LABEL_PREFIX: &str = "crypto-chain";
+
+/// PKCS#11 Service, keys live in the HSM and are addressed by labels derived from `ChainPath`
+#[derive(Clone)]
+pub struct Pkcs11Service {
+    ctx: Arc<Mutex<Ctx>>,
+    slot: CK_SLOT_ID,
+    pin: SecUtf8,
+}
+
+impl std::fmt::Debug for Pkcs11Service {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pkcs11Service")
+            .field("ctx", &"Ctx")
+            .field("slot", &self.slot)
+            .finish()
+    }
+}
+
+impl Pkcs11Service {
+    /// create a new Pkcs11Service by loading the PKCS#11 module at `module_path`
+    pub fn new(module_path: &str, slot: CK_SLOT_ID, pin: SecUtf8) -> Result<Self> {
+        let ctx = Ctx::new_and_initialize(module_path).chain(|| {
+            (
+                ErrorKind::InitializationError,
+                format!("unable to load PKCS#11 module {}", module_path),
+            )
+        })?;
+        let service = Self {
+            ctx: Arc::new(Mutex::new(ctx)),
+            slot,
+            pin,
+        };
+        // check the slot and pin
+        service.with_session(|_, _| Ok(()))?;
+        Ok(service)
+    }
+
+    /// create a new Pkcs11Service configured by `CRYPTO_PKCS11_MODULE`, `CRYPTO_PKCS11_SLOT`
+    /// and `CRYPTO_PKCS11_PIN` environment variables
+    pub fn from_env() -> Result<Self> {
+        let module_path = env::var("CRYPTO_PKCS11_MODULE").chain(|| {
+            (
+                ErrorKind::InitializationError,
+                "CRYPTO_PKCS11_MODULE is not set",
+            )
+        })?;
+        let slot = env::var("CRYPTO_PKCS11_SLOT")
+            .unwrap_or_else(|_| "0".to_owned())
+            .parse::<CK_SLOT_ID>()
+            .chain(|| (ErrorKind::InitializationError, "invalid CRYPTO_PKCS11_SLOT"))?;
+        let pin = env::var("CRYPTO_PKCS11_PIN").chain(|| {
+            (
+                ErrorKind::InitializationError,
+                "CRYPTO_PKCS11_PIN is not set",
+            )
+        })?;
+        Self::new(&module_path, slot, SecUtf8::from(pin))
+    }
+
+    fn with_session<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Ctx, CK_SESSION_HANDLE) -> Result<T>,
+    {
+        let ctx = self.ctx.lock().expect("lock pkcs11 context");
+        let session = ctx
+            .open_session(self.slot, CKF_SERIAL_SESSION | CKF_RW_SESSION, None, None)
+            .chain(|| (ErrorKind::LedgerError, "unable to open PKCS#11 session"))?;
+        let result = match ctx.login(session, CKU_USER, Some(self.pin.unsecure())) {
+            Ok(()) | Err(Pkcs11Error::Pkcs11(CKR_USER_ALREADY_LOGGED_IN)) => f(&ctx, session),
+            Err(e) => Err(Error::new_with_source(
+                ErrorKind::PermissionDenied,
+                "PKCS#11 login failed",
+                Box::new(e),
+            )),
+        };
+        let _ = ctx.close_session(session);
+        result
+    }
+}
+
+fn key_label(chain_path: &ChainPath) -> String {
+    format!("{}:{}", KEY_LABEL_PREFIX, chain_path)
+}
+
+fn find_key(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    class: CK_OBJECT_CLASS,
+    label: &str,
+) -> Result<Option<CK_OBJECT_HANDLE>> {
+    let label = label.to_owned();
+    let template = vec![
+        CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&class),
+        CK_ATTRIBUTE::new(CKA_LABEL).with_string(&label),
+    ];
+    ctx.find_objects_init(session, &template)
+        .chain(|| (ErrorKind::LedgerError, "find PKCS#11 objects failed"))?;
+    let objects = ctx.find_objects(session, 1);
+    ctx.find_objects_final(session)
+        .chain(|| (ErrorKind::LedgerError, "find PKCS#11 objects failed"))?;
+    let objects = objects.chain(|| (ErrorKind::LedgerError, "find PKCS#11 objects failed"))?;
+    Ok(objects.first().copied())
+}
+
+fn generate_key_pair(ctx: &Ctx, session: CK_SESSION_HANDLE, label: &str) -> Result<()> {
+    let label = label.to_owned();
+    let yes: CK_BBOOL = CK_TRUE;
+    let no: CK_BBOOL = CK_FALSE;
+    let mechanism = CK_MECHANISM {
+        mechanism: CKM_EC_KEY_PAIR_GEN,
+        pParameter: ptr::null_mut(),
+        ulParameterLen: 0,
+    };
+    let public_template = vec![
+        CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&yes),
+        CK_ATTRIBUTE::new(CKA_VERIFY).with_bool(&yes),
+        CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(&SECP256K1_EC_PARAMS),
+        CK_ATTRIBUTE::new(CKA_LABEL).with_string(&label),
+    ];
+    let private_template = vec![
+        CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&yes),
+        CK_ATTRIBUTE::new(CKA_SIGN).with_bool(&yes),
+        CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&yes),
+        CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&no),
+        CK_ATTRIBUTE::new(CKA_LABEL).with_string(&label),
+    ];
+    ctx.generate_key_pair(session, &mechanism, &public_template, &private_template)
+        .chain(|| {
+            (
+                ErrorKind::LedgerError,
+                "generate secp256k1 key pair in HSM failed",
+            )
+        })?;
+    Ok(())
+}
+
+fn read_public_key(
+    ctx: &Ctx,
+    session: CK_SESSION_HANDLE,
+    object: CK_OBJECT_HANDLE,
+) -> Result<PublicKey> {
+    let mut template = vec![CK_ATTRIBUTE::new(CKA_EC_POINT)];
+    ctx.get_attribute_value(session, object, &mut template)
+        .chain(|| (ErrorKind::LedgerError, "read public key length failed"))?;
+    let ec_point = vec![0u8; template[0].ulValueLen as usize];
+    let mut template = vec![CK_ATTRIBUTE::new(CKA_EC_POINT).with_bytes(&ec_point)];
+    ctx.get_attribute_value(session, object, &mut template)
+        .chain(|| (ErrorKind::LedgerError, "read public key failed"))?;
+    PublicKey::deserialize_from(unwrap_ec_point(&ec_point))
+}
+
+/// `CKA_EC_POINT` is usually a DER encoded OCTET STRING wrapping the uncompressed point
+fn unwrap_ec_point(ec_point: &[u8]) -> &[u8] {
+    match ec_point {
+        [0x04, 0x41, point @ ..] if point.len() == 0x41 => point,
+        _ => ec_point,
+    }
+}
+
+impl HardwareWalletAction for Pkcs11Service {
+    fn get_public_key(&self, chain_path: ChainPath) -> Result<PublicKey> {
+        let label = key_label(&chain_path);
+        self.with_session(|ctx, session| {
+            let object = match find_key(ctx, session, CKO_PUBLIC_KEY, &label)? {
+                Some(object) => object,
+                None => {
+                    generate_key_pair(ctx, session, &label)?;
+                    find_key(ctx, session, CKO_PUBLIC_KEY, &label)?.chain(|| {
+                        (
+                            ErrorKind::LedgerError,
+                            "generated public key not found in HSM",
+                        )
+                    })?
+                }
+            };
+            read_public_key(ctx, session, object)
+        })
+    }
+
+    fn get_sign_key(&self, hd_path: &ChainPath) -> Result<Box<dyn PrivateKeyAction>> {
+        let hw_key = Pkcs11SignKey {
+            label: key_label(hd_path),
+            service: self.clone(),
+        };
+        Ok(Box::new(hw_key))
+    }
+}
+
+/// represent a private key stored in the HSM with `label`, can sign msg using `service`
+pub struct Pkcs11SignKey {
+    label: String,
+    service: Pkcs11Service,
+}
+
+impl Pkcs11SignKey {
+    fn public_key_and_signature(&self, message: &Message) -> Result<(PublicKey, Signature)> {
+        self.service.with_session(|ctx, session| {
+            let public_object = find_key(ctx, session, CKO_PUBLIC_KEY, &self.label)?
+                .chain(|| (ErrorKind::LedgerError, "public key not found in HSM"))?;
+            let public_key = read_public_key(ctx, session, public_object)?;
+            let private_object = find_key(ctx, session, CKO_PRIVATE_KEY, &self.label)?
+                .chain(|| (ErrorKind::LedgerError, "private key not found in HSM"))?;
+            let mechanism = CK_MECHANISM {
+                mechanism: CKM_ECDSA,
+                pParameter: ptr::null_mut(),
+                ulParameterLen: 0,
+            };
+            ctx.sign_init(session, &mechanism, private_object)
+                .chain(|| (ErrorKind::LedgerError, "sign failed"))?;
+            let raw_signature = ctx
+                .sign(session, &message[..])
+                .chain(|| (ErrorKind::LedgerError, "sign failed"))?;
+            let mut signature = Signature::from_compact(&raw_signature)
+                .chain(|| (ErrorKind::LedgerError, "Invalid signature"))?;
+            // HSMs don't necessarily produce low-s signatures
+            signature.normalize_s();
+            Ok((public_key, signature))
+        })
+    }
+}
+
+impl PrivateKeyAction for Pkcs11SignKey {
+    fn sign(&self, tx: &Transaction) -> Result<RecoverableSignature> {
+        let message = Message::from_slice(&tx.id()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to deserialize message to sign",
+            )
+        })?;
+        let (public_key, signature) = self.public_key_and_signature(&message)?;
+        let compact = signature.serialize_compact();
+
+        // PKCS#11 doesn't return the recovery id, find the one recovering the public key
+        for id in 0..4 {
+            let recover_id = RecoveryId::from_i32(id).expect("valid recovery id");
+            if let Ok(recoverable) = RecoverableSignature::from_compact(&compact, recover_id) {
+                if let Ok(recovered) = secp256k1::SECP256K1.recover(&message, &recoverable) {
+                    if PublicKey::from(recovered) == public_key {
+                        return Ok(recoverable);
+                    }
+                }
+            }
+        }
+        Err(Error::new(
+            ErrorKind::LedgerError,
+            "Invalid signature, get recover id failed",
+        ))
+    }
+
+    fn schnorr_sign(&self, _tx: &Transaction) -> Result<SchnorrSignature> {
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "schnorr signature is not supported by PKCS#11 keys",
+        ))
+    }
+
+    fn schnorr_sign_unsafe(
+        &self,
+        _tx: &Transaction,
+        _aux_payload: &[u8],
+    ) -> Result<SchnorrSignature> {
+        unreachable!()
+    }
+
+    fn public_key(&self) -> Result<PublicKey> {
+        self.service.with_session(|ctx, session| {
+            let object = find_key(ctx, session, CKO_PUBLIC_KEY, &self.label)?
+                .chain(|| (ErrorKind::LedgerError, "public key not found in HSM"))?;
+            read_public_key(ctx, session, object)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_unwrap_ec_point() {
+        let mut point = vec![0x04];
+        point.extend_from_slice(&[0x01; 64]);
+        let mut der = vec![0x04, 65];
+        der.extend_from_slice(&point);
+        assert_eq!(unwrap_ec_point(&der), point.as_slice());
+        assert_eq!(unwrap_ec_point(&point), point.as_slice());
+    }
+}
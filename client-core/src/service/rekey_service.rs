@@ -0,0 +1,199 @@
+use parity_scale_codec::{Decode, Encode};
+use zeroize::Zeroize;
+
+use client_common::seckey::{load_kdf_config, save_kdf_config, KdfConfig};
+use client_common::storage::{decrypt_bytes, encrypt_bytes};
+use client_common::{Result, SecKey, Storage};
+
+/// key space of interrupted re-encryptions
+pub(crate) const KEYSPACE: &str = "core_wallet_rekey_journal";
+
+/// Records of a wallet as they were before the re-encryption
+#[derive(Debug, Encode, Decode)]
+struct RekeyJournal {
+    kdf_config: KdfConfig,
+    /// keyspace, key and encrypted value of the records
+    records: Vec<(Vec<u8>, Vec<u8>, Vec<u8>)>,
+}
+
+/// Re-encrypts all the records of a wallet under a new encryption key
+///
+/// The records encrypted with the old key are found by trying to decrypt every record in storage
+/// (values are authenticated with their key, so only the records of the wallet can be decrypted).
+/// The previous records are journaled before anything is overwritten: a failed re-encryption is
+/// rolled back immediately and an interrupted one by `recover`.
+#[derive(Debug, Default, Clone)]
+pub struct RekeyService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> RekeyService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of rekey service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Re-encrypts the records of given wallet under `new_enckey` derived with `kdf_config`,
+    /// returns the number of re-encrypted records
+    pub fn rekey(
+        &self,
+        name: &str,
+        old_enckey: &SecKey,
+        new_enckey: &SecKey,
+        kdf_config: &KdfConfig,
+    ) -> Result<usize> {
+        self.recover(name)?;
+        kdf_config.validate()?;
+
+        let mut journal = RekeyJournal {
+            kdf_config: load_kdf_config(&self.storage, name)?,
+            records: Vec::new(),
+        };
+        let mut updates = Vec::new();
+        for keyspace in self.storage.keyspaces()? {
+            if keyspace == KEYSPACE.as_bytes() {
+                continue;
+            }
+            for key in self.storage.keys(&keyspace)? {
+                let value = match self.storage.get(&keyspace, &key)? {
+                    Some(value) => value,
+                    None => continue,
+                };
+                if let Ok(mut plain) = decrypt_bytes(&key, old_enckey, &value) {
+                    let cipher = encrypt_bytes(&key, new_enckey, &plain);
+                    plain.zeroize();
+                    updates.push((keyspace.clone(), key.clone(), cipher?));
+                    journal.records.push((keyspace.clone(), key, value));
+                }
+            }
+        }
+
+        self.storage.set(KEYSPACE, name, journal.encode())?;
+        self.storage.flush()?;
+        let written = updates
+            .iter()
+            .try_for_each(|(keyspace, key, cipher)| {
+                self.storage.set(keyspace, key, cipher.clone()).map(|_| ())
+            })
+            .and_then(|_| save_kdf_config(&self.storage, name, kdf_config))
+            .and_then(|_| self.storage.flush());
+        if let Err(err) = written {
+            // the journal is kept if the rollback fails as well, so that it is retried later
+            if let Err(rollback_err) = self.recover(name) {
+                log::error!(
+                    "unable to roll back re-encryption of wallet {}: {}",
+                    name,
+                    rollback_err
+                );
+            }
+            return Err(err);
+        }
+        self.storage.delete(KEYSPACE, name)?;
+        self.storage.flush()?;
+
+        Ok(updates.len())
+    }
+
+    /// Rolls back an interrupted re-encryption of given wallet, returns whether there was one
+    pub fn recover(&self, name: &str) -> Result<bool> {
+        let journal: RekeyJournal = match self.storage.load(KEYSPACE, name)? {
+            Some(journal) => journal,
+            None => return Ok(false),
+        };
+        log::warn!("rolling back interrupted re-encryption of wallet {}", name);
+        for (keyspace, key, value) in journal.records {
+            self.storage.set(keyspace, key, value)?;
+        }
+        save_kdf_config(&self.storage, name, &journal.kdf_config)?;
+        self.storage.flush()?;
+        self.storage.delete(KEYSPACE, name)?;
+        self.storage.flush()?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::seckey::{derive_enckey, derive_enckey_with};
+    use client_common::storage::MemoryStorage;
+    use client_common::SecureStorage;
+    use secstr::SecUtf8;
+
+    #[test]
+    fn check_rekey_and_recover() {
+        let storage = MemoryStorage::default();
+        let service = RekeyService::new(storage.clone());
+        let name = "name";
+        let old_enckey = derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let other_enckey = derive_enckey(&SecUtf8::from("passphrase"), "other").unwrap();
+        let kdf_config = KdfConfig::argon2id(8192, 1, 1);
+        let new_enckey =
+            derive_enckey_with(&SecUtf8::from("new passphrase"), name, &kdf_config).unwrap();
+
+        storage
+            .set_secure("keyspace", name, b"secret".to_vec(), &old_enckey)
+            .unwrap();
+        storage
+            .set_secure("keyspace", "other", b"other".to_vec(), &other_enckey)
+            .unwrap();
+        storage.set("keyspace", "plain", vec![1]).unwrap();
+
+        assert!(service
+            .rekey(
+                name,
+                &old_enckey,
+                &new_enckey,
+                &KdfConfig::argon2id(8192, 0, 1)
+            )
+            .is_err());
+        assert_eq!(
+            1,
+            service
+                .rekey(name, &old_enckey, &new_enckey, &kdf_config)
+                .unwrap()
+        );
+        assert_eq!(
+            Some(b"secret".to_vec()),
+            storage.get_secure("keyspace", name, &new_enckey).unwrap()
+        );
+        assert!(storage.get_secure("keyspace", name, &old_enckey).is_err());
+        assert_eq!(
+            Some(b"other".to_vec()),
+            storage
+                .get_secure("keyspace", "other", &other_enckey)
+                .unwrap()
+        );
+        assert_eq!(Some(vec![1]), storage.get("keyspace", "plain").unwrap());
+        assert_eq!(kdf_config, load_kdf_config(&storage, name).unwrap());
+        assert!(!service.recover(name).unwrap());
+
+        // interrupted after the records were journaled and partially overwritten
+        let journal = RekeyJournal {
+            kdf_config: KdfConfig::default(),
+            records: vec![(
+                b"keyspace".to_vec(),
+                name.as_bytes().to_vec(),
+                encrypt_bytes(name, &old_enckey, b"secret").unwrap(),
+            )],
+        };
+        storage.set(KEYSPACE, name, journal.encode()).unwrap();
+        assert!(service.recover(name).unwrap());
+        assert_eq!(
+            Some(b"secret".to_vec()),
+            storage.get_secure("keyspace", name, &old_enckey).unwrap()
+        );
+        assert_eq!(
+            KdfConfig::default(),
+            load_kdf_config(&storage, name).unwrap()
+        );
+        assert!(storage.get(KEYSPACE, name).unwrap().is_none());
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::BTreeMap;
+
+use parity_scale_codec::{Decode, Encode};
+
+use chain_core::init::coin::{sum_coins, Coin};
+use chain_core::tx::data::input::TxoPointer;
+use chain_core::tx::data::output::TxOut;
+use client_common::{
+    Clock, Error, ErrorKind, Result, ResultExt, SecKey, SecureStorage, Storage, SystemClock,
+};
+
+use crate::types::BalanceReservation;
+
+/// key space of balance reservations
+pub(crate) const KEYSPACE: &str = "core_wallet_reservation";
+
+/// Balance reservations of a wallet
+#[derive(Debug, Default, Encode, Decode)]
+struct Reservations {
+    /// id of the next reservation
+    next_id: u64,
+    /// reservations by id
+    reservations: BTreeMap<u64, BalanceReservation>,
+}
+
+/// Exposes functionalities for earmarking value of wallets
+///
+/// Stores `wallet-name -> reservations` (encrypted), expired reservations are dropped when
+/// reservations of the wallet are read.
+#[derive(Debug, Default, Clone)]
+pub struct ReservationService<S, K = SystemClock>
+where
+    S: Storage,
+    K: Clock,
+{
+    storage: S,
+    clock: K,
+}
+
+impl<S> ReservationService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of reservation service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self::with_clock(storage, SystemClock)
+    }
+}
+
+impl<S, K> ReservationService<S, K>
+where
+    S: Storage,
+    K: Clock,
+{
+    /// Creates new instance of reservation service with given time source
+    #[inline]
+    pub fn with_clock(storage: S, clock: K) -> Self {
+        Self { storage, clock }
+    }
+
+    fn load(&self, name: &str, enckey: &SecKey) -> Result<Reservations> {
+        let mut reservations: Reservations = self
+            .storage
+            .load_secure(KEYSPACE, name, enckey)?
+            .unwrap_or_default();
+        let now = self.clock.unix_timestamp();
+        reservations
+            .reservations
+            .retain(|_, reservation| !reservation.is_expired(now));
+        Ok(reservations)
+    }
+
+    /// Returns unexpired reservations of given wallet (ordered by id)
+    pub fn get_reservations(&self, name: &str, enckey: &SecKey) -> Result<Vec<BalanceReservation>> {
+        Ok(self
+            .load(name, enckey)?
+            .reservations
+            .into_iter()
+            .map(|(_, reservation)| reservation)
+            .collect())
+    }
+
+    /// Returns total amount of unexpired reservations of given wallet
+    pub fn reserved_amount(&self, name: &str, enckey: &SecKey) -> Result<Coin> {
+        let reservations = self.load(name, enckey)?;
+        sum_coins(
+            reservations
+                .reservations
+                .values()
+                .map(|reservation| reservation.amount),
+        )
+        .chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Total reserved amount exceeds maximum allowed value",
+            )
+        })
+    }
+
+    /// Reserves `amount` of given wallet for `ttl` seconds, `available` is the available balance
+    /// of the wallet (including the amount reserved already)
+    pub fn reserve(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        ttl: u64,
+        tag: String,
+        available: Coin,
+    ) -> Result<BalanceReservation> {
+        if amount == Coin::zero() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Reserved amount should be greater than zero",
+            ));
+        }
+        if ttl == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Reservation lifetime should be greater than zero",
+            ));
+        }
+
+        let mut reservations = self.load(name, enckey)?;
+        let reserved = sum_coins(
+            reservations
+                .reservations
+                .values()
+                .map(|reservation| reservation.amount),
+        )
+        .and_then(|reserved| reserved + amount)
+        .chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Total reserved amount exceeds maximum allowed value",
+            )
+        })?;
+        if reserved > available {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Insufficient available balance to reserve {} (available: {}, reserved: {})",
+                    amount,
+                    available,
+                    (reserved - amount).unwrap_or_default()
+                ),
+            ));
+        }
+
+        let now = self.clock.unix_timestamp();
+        let reservation = BalanceReservation {
+            id: reservations.next_id,
+            amount,
+            tag,
+            created_at: now,
+            expires_at: now.saturating_add(ttl),
+        };
+        reservations.next_id += 1;
+        reservations
+            .reservations
+            .insert(reservation.id, reservation.clone());
+        self.storage
+            .save_secure(KEYSPACE, name, enckey, &reservations)?;
+        Ok(reservation)
+    }
+
+    /// Releases reservation with given id
+    pub fn release(&self, name: &str, enckey: &SecKey, id: u64) -> Result<BalanceReservation> {
+        let mut reservations = self.load(name, enckey)?;
+        let reservation = reservations.reservations.remove(&id).chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!("Reservation with id {} not found", id),
+            )
+        })?;
+        self.storage
+            .save_secure(KEYSPACE, name, enckey, &reservations)?;
+        Ok(reservation)
+    }
+
+    /// Releases all reservations with given tag
+    pub fn release_tag(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tag: &str,
+    ) -> Result<Vec<BalanceReservation>> {
+        let mut reservations = self.load(name, enckey)?;
+        let (released, kept) = reservations
+            .reservations
+            .into_iter()
+            .partition::<BTreeMap<_, _>, _>(|(_, reservation)| reservation.tag == tag);
+        reservations.reservations = kept;
+        self.storage
+            .save_secure(KEYSPACE, name, enckey, &reservations)?;
+        Ok(released
+            .into_iter()
+            .map(|(_, reservation)| reservation)
+            .collect())
+    }
+
+    /// Deletes all reservations of given wallet
+    #[inline]
+    pub fn delete(&self, name: &str) -> Result<()> {
+        self.storage.delete(KEYSPACE, name).map(|_| ())
+    }
+
+    /// Clears all storage
+    #[inline]
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(KEYSPACE)
+    }
+}
+
+/// Withholds unspent transaction outputs covering `reserved` from input selection: outputs are
+/// withheld smallest first, so that large outputs stay spendable
+pub fn withhold_reserved(
+    mut unspent: Vec<(TxoPointer, TxOut)>,
+    reserved: Coin,
+) -> Vec<(TxoPointer, TxOut)> {
+    if reserved == Coin::zero() {
+        return unspent;
+    }
+    unspent.sort_by_key(|(_, output)| output.value);
+    let mut withheld = Coin::zero();
+    let mut split = 0;
+    for (_, output) in unspent.iter() {
+        if withheld >= reserved {
+            break;
+        }
+        withheld = (withheld + output.value).unwrap_or_else(|_| Coin::max());
+        split += 1;
+    }
+    unspent.split_off(split)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::tx::data::address::ExtendedAddr;
+    use client_common::clock::MockClock;
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::MemoryStorage;
+    use secstr::SecUtf8;
+
+    fn output(value: u64) -> (TxoPointer, TxOut) {
+        (
+            TxoPointer::new([value as u8; 32], 0),
+            TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(value).unwrap()),
+        )
+    }
+
+    #[test]
+    fn check_flow() {
+        let clock = MockClock::new(1_000);
+        let service = ReservationService::with_clock(MemoryStorage::default(), clock.clone());
+        let name = "name";
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let available = Coin::new(100).unwrap();
+
+        let first = service
+            .reserve(
+                name,
+                &enckey,
+                Coin::new(60).unwrap(),
+                10,
+                "w1".to_owned(),
+                available,
+            )
+            .unwrap();
+        assert_eq!(1_010, first.expires_at);
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            service
+                .reserve(
+                    name,
+                    &enckey,
+                    Coin::new(50).unwrap(),
+                    10,
+                    "w2".to_owned(),
+                    available
+                )
+                .unwrap_err()
+                .kind()
+        );
+        let second = service
+            .reserve(
+                name,
+                &enckey,
+                Coin::new(30).unwrap(),
+                100,
+                "w2".to_owned(),
+                available,
+            )
+            .unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(
+            Coin::new(90).unwrap(),
+            service.reserved_amount(name, &enckey).unwrap()
+        );
+
+        clock.advance(10);
+        assert_eq!(
+            vec![second.clone()],
+            service.get_reservations(name, &enckey).unwrap()
+        );
+        assert!(service.release(name, &enckey, first.id).is_err());
+
+        assert_eq!(
+            vec![second],
+            service.release_tag(name, &enckey, "w2").unwrap()
+        );
+        assert_eq!(
+            Coin::zero(),
+            service.reserved_amount(name, &enckey).unwrap()
+        );
+    }
+
+    #[test]
+    fn check_withhold_reserved() {
+        let unspent = vec![output(50), output(10), output(20), output(100)];
+
+        let spendable = withhold_reserved(unspent.clone(), Coin::zero());
+        assert_eq!(4, spendable.len());
+
+        let spendable = withhold_reserved(unspent.clone(), Coin::new(25).unwrap());
+        assert_eq!(vec![output(50), output(100)], spendable);
+
+        let spendable = withhold_reserved(unspent, Coin::new(500).unwrap());
+        assert!(spendable.is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::BTreeSet;
+
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+
+use client_common::seckey::KDF_CONFIG_KEYSPACE;
+use client_common::{Result, Storage, SystemClock};
+
+use super::alert_service::{KEYSPACE as ALERT_KEYSPACE, LOG_KEYSPACE as ALERT_LOG_KEYSPACE};
+use super::annotation_service::KEYSPACE as ANNOTATION_KEYSPACE;
+use super::approval_service::{
+    KEYSPACE as PENDING_APPROVAL_KEYSPACE, POLICY_KEYSPACE as APPROVAL_POLICY_KEYSPACE,
+};
+use super::device_sync_service::KEYSPACE as DEVICE_SYNC_KEYSPACE;
+use super::hd_key_service::KEYSPACE as HD_KEY_KEYSPACE;
+use super::key_service::KEYSPACE as KEY_KEYSPACE;
+use super::operator_profile_service::KEYSPACE as OPERATOR_PROFILE_KEYSPACE;
+use super::passphrase_attempt_service::KEYSPACE as PASSPHRASE_ATTEMPT_KEYSPACE;
+use super::payout_batch_service::KEYSPACE as PAYOUT_BATCH_KEYSPACE;
+use super::rekey_service::KEYSPACE as REKEY_JOURNAL_KEYSPACE;
+use super::reservation_service::KEYSPACE as RESERVATION_KEYSPACE;
+use super::sync_state_service::{
+    BIRTH_HEIGHT_KEYSPACE, BRAND_NEW_KEYSPACE, CHECKPOINT_KEYSPACE as SYNC_CHECKPOINT_KEYSPACE,
+    JOURNAL_KEYSPACE as SYNC_JOURNAL_KEYSPACE, KEYSPACE as SYNC_STATE_KEYSPACE,
+};
+use super::wallet_service::{
+    get_wallet_keyspace, parse_wallet_keyspace, KEYSPACE as WALLET_KEYSPACE,
+};
+use super::wallet_state_service::KEYSPACE as WALLET_STATE_KEYSPACE;
+use super::PassphraseAttempts;
+
+/// Keyspaces in which records are keyed by wallet name
+///
+/// Records of a wallet in these keyspaces are removed by `delete_wallet_records` when the wallet
+/// is deleted and reported as orphans if they are left behind.
+pub(crate) const WALLET_NAME_KEYSPACES: [&str; 20] = [
+    WALLET_KEYSPACE,
+    SYNC_STATE_KEYSPACE,
+    SYNC_CHECKPOINT_KEYSPACE,
+    SYNC_JOURNAL_KEYSPACE,
+    BIRTH_HEIGHT_KEYSPACE,
+    BRAND_NEW_KEYSPACE,
+    WALLET_STATE_KEYSPACE,
+    HD_KEY_KEYSPACE,
+    KEY_KEYSPACE,
+    OPERATOR_PROFILE_KEYSPACE,
+    ALERT_KEYSPACE,
+    ALERT_LOG_KEYSPACE,
+    PAYOUT_BATCH_KEYSPACE,
+    ANNOTATION_KEYSPACE,
+    APPROVAL_POLICY_KEYSPACE,
+    PENDING_APPROVAL_KEYSPACE,
+    DEVICE_SYNC_KEYSPACE,
+    RESERVATION_KEYSPACE,
+    KDF_CONFIG_KEYSPACE,
+    REKEY_JOURNAL_KEYSPACE,
+];
+
+/// Deletes the records of given wallet in all the keyspaces keyed by wallet name
+pub(crate) fn delete_wallet_records<S: Storage>(storage: &S, name: &str) -> Result<()> {
+    for keyspace in WALLET_NAME_KEYSPACES.iter() {
+        storage.delete(keyspace, name)?;
+    }
+    Ok(())
+}
+
+/// Reason why a record is considered orphaned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanKind {
+    /// record belongs to a wallet which doesn't exist anymore
+    DeletedWallet,
+    /// passphrase lockout which expired long ago
+    ExpiredLock,
+}
+
+/// Orphaned record found in storage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrphanRecord {
+    /// keyspace of the record
+    pub keyspace: String,
+    /// key of the record (`None` if the whole keyspace is orphaned)
+    pub key: Option<String>,
+    /// reason why the record is orphaned
+    pub kind: OrphanKind,
+    /// size of keys and values in bytes
+    pub size: u64,
+}
+
+/// Orphaned records found in storage
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrphanReport {
+    /// orphaned records
+    pub records: Vec<OrphanRecord>,
+    /// total size of orphaned records in bytes
+    pub reclaimable: u64,
+}
+
+impl OrphanReport {
+    fn push(&mut self, record: OrphanRecord) {
+        self.reclaimable += record.size;
+        self.records.push(record);
+    }
+}
+
+/// Statistics of storage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageStats {
+    /// size of storage on disk in bytes
+    pub size_on_disk: u64,
+    /// number of keyspaces
+    pub keyspaces: u64,
+    /// number of records in all keyspaces
+    pub records: u64,
+}
+
+/// Finds and removes records left behind in client storage
+#[derive(Debug, Default, Clone)]
+pub struct StorageMaintenanceService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> StorageMaintenanceService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of storage maintenance service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Returns statistics of storage
+    pub fn stats(&self) -> Result<StorageStats> {
+        let keyspaces = self.storage.keyspaces()?;
+        let mut records = 0;
+        for keyspace in keyspaces.iter() {
+            records += self.storage.keys(keyspace)?.len() as u64;
+        }
+        Ok(StorageStats {
+            size_on_disk: self.storage.size_on_disk()?,
+            keyspaces: keyspaces.len() as u64,
+            records,
+        })
+    }
+
+    /// Scans storage for orphaned records without deleting them
+    pub fn scan(&self) -> Result<OrphanReport> {
+        let wallet_keyspace = get_wallet_keyspace();
+        let names = self
+            .storage
+            .keys(&wallet_keyspace)?
+            .into_iter()
+            .map(|key| String::from_utf8_lossy(&key).into_owned())
+            .collect::<BTreeSet<_>>();
+
+        let mut report = OrphanReport::default();
+
+        for keyspace in self.storage.keyspaces()? {
+            let keyspace = String::from_utf8_lossy(&keyspace).into_owned();
+            let deleted_wallet =
+                parse_wallet_keyspace(&keyspace).map_or(false, |name| !names.contains(name));
+            if deleted_wallet {
+                let size = self.keyspace_size(&keyspace)?;
+                if size > 0 {
+                    report.push(OrphanRecord {
+                        keyspace,
+                        key: None,
+                        kind: OrphanKind::DeletedWallet,
+                        size,
+                    });
+                }
+            }
+        }
+
+        for keyspace in WALLET_NAME_KEYSPACES
+            .iter()
+            .chain(&[PASSPHRASE_ATTEMPT_KEYSPACE])
+        {
+            for key in self.storage.keys(keyspace)? {
+                let name = String::from_utf8_lossy(&key).into_owned();
+                let expired_lock =
+                    *keyspace == PASSPHRASE_ATTEMPT_KEYSPACE && self.is_expired_lock(&key)?;
+                let kind = if !names.contains(&name) {
+                    OrphanKind::DeletedWallet
+                } else if expired_lock {
+                    OrphanKind::ExpiredLock
+                } else {
+                    continue;
+                };
+                report.push(OrphanRecord {
+                    keyspace: (*keyspace).to_owned(),
+                    size: self.record_size(keyspace, &key)?,
+                    key: Some(name),
+                    kind,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Deletes confirmed orphaned records
+    ///
+    /// Only records which are both in `confirmed` and still orphaned in storage are deleted,
+    /// deleted records are returned.
+    pub fn collect(&self, confirmed: &[OrphanRecord]) -> Result<OrphanReport> {
+        let mut deleted = OrphanReport::default();
+        for record in self.scan()?.records {
+            if !confirmed.iter().any(|confirmed| {
+                confirmed.keyspace == record.keyspace && confirmed.key == record.key
+            }) {
+                continue;
+            }
+            match &record.key {
+                Some(key) => {
+                    self.storage.delete(&record.keyspace, key)?;
+                }
+                None => self.storage.clear(&record.keyspace)?,
+            }
+            deleted.push(record);
+        }
+        Ok(deleted)
+    }
+
+    fn is_expired_lock(&self, key: &[u8]) -> Result<bool> {
+        let expired = self
+            .storage
+            .get(PASSPHRASE_ATTEMPT_KEYSPACE, key)?
+            .and_then(|bytes| PassphraseAttempts::decode(&mut bytes.as_slice()).ok())
+            .map(|attempts| attempts.is_expired(&SystemClock))
+            .unwrap_or(true);
+        Ok(expired)
+    }
+
+    fn record_size(&self, keyspace: &str, key: &[u8]) -> Result<u64> {
+        let value_size = self
+            .storage
+            .get(keyspace, key)?
+            .map(|value| value.len())
+            .unwrap_or_default();
+        Ok((key.len() + value_size) as u64)
+    }
+
+    fn keyspace_size(&self, keyspace: &str) -> Result<u64> {
+        let mut size = 0;
+        for key in self.storage.keys(keyspace)? {
+            size += self.record_size(keyspace, &key)?;
+        }
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::storage::MemoryStorage;
+    use parity_scale_codec::Encode;
+
+    #[test]
+    fn check_scan_and_collect() {
+        let storage = MemoryStorage::default();
+        storage
+            .set(get_wallet_keyspace(), "alive", b"alive".to_vec())
+            .unwrap();
+        storage
+            .set(SYNC_STATE_KEYSPACE, "alive", vec![0; 4])
+            .unwrap();
+        storage
+            .set(SYNC_STATE_KEYSPACE, "deleted", vec![0; 4])
+            .unwrap();
+        storage
+            .set("core_wallet_deleted_info", "0", vec![0; 8])
+            .unwrap();
+        storage
+            .set("core_wallet_alive_info", "0", vec![0; 8])
+            .unwrap();
+        storage
+            .set(
+                PASSPHRASE_ATTEMPT_KEYSPACE,
+                "alive",
+                PassphraseAttempts::default().encode(),
+            )
+            .unwrap();
+
+        let service = StorageMaintenanceService::new(storage.clone());
+        let report = service.scan().unwrap();
+        assert_eq!(3, report.records.len());
+        assert_eq!(
+            report.records.iter().map(|record| record.size).sum::<u64>(),
+            report.reclaimable
+        );
+        let lock = report
+            .records
+            .iter()
+            .find(|record| record.keyspace == PASSPHRASE_ATTEMPT_KEYSPACE)
+            .unwrap();
+        assert_eq!(OrphanKind::ExpiredLock, lock.kind);
+
+        // only confirmed records are deleted
+        let deleted = service.collect(&report.records[..1]).unwrap();
+        assert_eq!(1, deleted.records.len());
+        assert_eq!(2, service.scan().unwrap().records.len());
+
+        service.collect(&report.records).unwrap();
+        assert!(service.scan().unwrap().records.is_empty());
+        assert!(storage.get(SYNC_STATE_KEYSPACE, "alive").unwrap().is_some());
+        assert!(storage
+            .get("core_wallet_alive_info", "0")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn check_parse_wallet_keyspace() {
+        assert_eq!(
+            Some("my_wallet"),
+            parse_wallet_keyspace("core_wallet_my_wallet_stakingkeyset")
+        );
+        assert_eq!(None, parse_wallet_keyspace(&get_wallet_keyspace()));
+        assert_eq!(None, parse_wallet_keyspace(SYNC_STATE_KEYSPACE));
+        assert_eq!(None, parse_wallet_keyspace("core_wallet__info"));
+    }
+}
@@ -1,11 +1,25 @@
 use chain_core::common::H256;
-use client_common::{ErrorKind, Result, ResultExt, Storage};
+use client_common::{ErrorKind, Result, ResultExt, SecKey, SecureStorage, Storage};
 use parity_scale_codec::{Decode, Encode};
+
+use crate::service::WalletStateMemento;
 /// key space of wallet sync state
-const KEYSPACE: &str = "core_wallet_sync";
+pub(crate) const KEYSPACE: &str = "core_wallet_sync";
+/// key space of wallet birth height
+pub(crate) const BIRTH_HEIGHT_KEYSPACE: &str = "core_wallet_birth_height";
+/// key space of markers of generated wallets which weren't synchronized yet
+pub(crate) const BRAND_NEW_KEYSPACE: &str = "core_wallet_brand_new";
+/// key space of checkpoints of partially synchronized batches (encrypted)
+pub(crate) const CHECKPOINT_KEYSPACE: &str = "core_wallet_sync_checkpoint";
+/// key space of journals of recently synchronized batches (encrypted)
+pub(crate) const JOURNAL_KEYSPACE: &str = "core_wallet_sync_journal";
+
+/// Number of recently synchronized batches kept in the sync journal, which bounds the depth of
+/// chain reorganizations a wallet can be rolled back from without a rescan
+pub const SYNC_JOURNAL_LENGTH: usize = 100;
 
 /// Sync state for wallet
-#[derive(Debug, Encode, Decode)]
+#[derive(Debug, Clone, Encode, Decode)]
 pub struct SyncState {
     /// last block height
     pub last_block_height: u64,
@@ -30,6 +44,33 @@ impl SyncState {
             trusted: true,
         }
     }
+
+    /// construct sync state of a brand-new wallet, which can't have any history before
+    /// given (verified) block
+    pub fn birth(
+        block_height: u64,
+        app_hash: String,
+        block_hash: String,
+        staking_root: H256,
+    ) -> SyncState {
+        SyncState {
+            last_block_height: block_height,
+            last_app_hash: app_hash,
+            last_block_hash: block_hash,
+            staking_root,
+            trusted: true,
+        }
+    }
+}
+
+/// Changes of a synchronized batch of blocks, kept to unwind the batch when the chain is
+/// reorganized
+#[derive(Debug, Encode, Decode)]
+pub struct SyncJournalEntry {
+    /// sync state before the batch
+    pub previous: SyncState,
+    /// memento reverting the changes of the batch to the wallet state
+    pub undo: WalletStateMemento,
 }
 
 /// Load sync state from storage
@@ -45,9 +86,61 @@ pub fn save_sync_state<S: Storage>(storage: &S, name: &str, state: &SyncState) -
 /// Delete sync state from storage
 pub fn delete_sync_state<S: Storage>(storage: &S, name: &str) -> Result<()> {
     storage.delete(KEYSPACE, name)?;
+    storage.delete(CHECKPOINT_KEYSPACE, name)?;
+    storage.delete(JOURNAL_KEYSPACE, name)?;
     Ok(())
 }
 
+/// Load sync journal (oldest batch first) from storage
+pub fn load_sync_journal<S: SecureStorage>(
+    storage: &S,
+    name: &str,
+    enckey: &SecKey,
+) -> Result<Vec<SyncJournalEntry>> {
+    Ok(storage
+        .load_secure(JOURNAL_KEYSPACE, name, enckey)?
+        .unwrap_or_default())
+}
+
+/// Save sync journal to storage, only the latest `SYNC_JOURNAL_LENGTH` batches are kept
+pub fn save_sync_journal<S: SecureStorage>(
+    storage: &S,
+    name: &str,
+    enckey: &SecKey,
+    journal: &[SyncJournalEntry],
+) -> Result<()> {
+    let start = journal.len().saturating_sub(SYNC_JOURNAL_LENGTH);
+    storage.save_secure(JOURNAL_KEYSPACE, name, enckey, &&journal[start..])
+}
+
+/// Load birth height of wallet from storage
+pub fn load_birth_height<S: Storage>(storage: &S, name: &str) -> Result<Option<u64>> {
+    storage.load(BIRTH_HEIGHT_KEYSPACE, name)
+}
+
+/// Save birth height of wallet to storage
+pub fn save_birth_height<S: Storage>(storage: &S, name: &str, height: u64) -> Result<()> {
+    storage.save(BIRTH_HEIGHT_KEYSPACE, name, &height)
+}
+
+/// Marks a generated (not restored or imported) wallet as brand-new, it can't have any history
+/// before its first synchronization
+pub fn mark_brand_new<S: Storage>(storage: &S, name: &str) -> Result<()> {
+    storage.save(BRAND_NEW_KEYSPACE, name, &true)
+}
+
+/// Checks if wallet was marked as brand-new and not synchronized since
+pub fn is_brand_new<S: Storage>(storage: &S, name: &str) -> Result<bool> {
+    Ok(storage
+        .load::<bool>(BRAND_NEW_KEYSPACE, name)?
+        .unwrap_or(false))
+}
+
+/// Removes the brand-new marker of wallet (once its sync state is initialized)
+pub fn clear_brand_new<S: Storage>(storage: &S, name: &str) -> Result<()> {
+    storage.delete(BRAND_NEW_KEYSPACE, name).map(|_| ())
+}
+
 /// Exposes functionalities for managing client's global state (for synchronization)
 ///
 /// Stores `wallet-name -> global-state`
@@ -77,12 +170,18 @@ where
     /// Deletes global state data for given wallet
     #[inline]
     pub fn delete_global_state(&self, name: &str) -> Result<()> {
-        self.storage.delete(KEYSPACE, name).map(|_| ())
+        self.storage.delete(KEYSPACE, name)?;
+        self.storage.delete(CHECKPOINT_KEYSPACE, name)?;
+        self.storage.delete(JOURNAL_KEYSPACE, name)?;
+        self.storage.delete(BRAND_NEW_KEYSPACE, name)?;
+        self.storage.delete(BIRTH_HEIGHT_KEYSPACE, name).map(|_| ())
     }
 
     /// Clears all storage
     #[inline]
     pub fn clear(&self) -> Result<()> {
+        self.storage.clear(CHECKPOINT_KEYSPACE)?;
+        self.storage.clear(JOURNAL_KEYSPACE)?;
         self.storage.clear(KEYSPACE)
     }
 
@@ -2,8 +2,8 @@ use indexmap::IndexSet;
 use parity_scale_codec::{Decode, Encode, Input, Output};
 
 use crate::hd_wallet::{ChainPath, HardwareKind};
-use crate::service::{load_wallet_state, HdKey, WalletState};
-use crate::types::WalletKind;
+use crate::service::{load_wallet_state, HdKey, SyncState, WalletState};
+use crate::types::{AddressMetadata, WalletKind};
 use chain_core::common::H256;
 use chain_core::init::address::RedeemAddress;
 use chain_core::state::account::StakedStateAddress;
@@ -21,7 +21,37 @@ use std::fmt;
 use std::str;
 
 /// Key space of wallet
-const KEYSPACE: &str = "core_wallet";
+pub(crate) const KEYSPACE: &str = "core_wallet";
+
+/// Suffixes of keyspaces holding data of a single wallet (`core_wallet_<name>_<suffix>`)
+const WALLET_KEYSPACE_SUFFIXES: [&str; 9] = [
+    "publickey",
+    "stakingkey",
+    "stakingkeyset",
+    "privatekey",
+    "hdpath",
+    "roothash",
+    "roothashset",
+    "multisigaddress",
+    "info",
+];
+
+/// Returns the wallet name if given keyspace holds data of a single wallet
+pub(crate) fn parse_wallet_keyspace(keyspace: &str) -> Option<&str> {
+    let prefix = format!("{}_", KEYSPACE);
+    if !keyspace.starts_with(&prefix) {
+        return None;
+    }
+    let rest = &keyspace[prefix.len()..];
+    WALLET_KEYSPACE_SUFFIXES.iter().find_map(|suffix| {
+        let suffix = format!("_{}", suffix);
+        if rest.len() > suffix.len() && rest.ends_with(&suffix) {
+            Some(&rest[..rest.len() - suffix.len()])
+        } else {
+            None
+        }
+    })
+}
 
 fn get_public_keyspace(name: &str) -> String {
     format!("{}_{}_publickey", KEYSPACE, name)
@@ -59,7 +89,7 @@ fn get_info_keyspace(name: &str) -> String {
     format!("{}_{}_info", KEYSPACE, name)
 }
 
-fn get_wallet_keyspace() -> String {
+pub(crate) fn get_wallet_keyspace() -> String {
     format!("{}_walletname", KEYSPACE)
 }
 
@@ -132,6 +162,27 @@ pub struct WalletInfo {
     pub staking_keys: Vec<PublicKey>,
 }
 
+/// Current version of `WalletExport`
+pub const WALLET_EXPORT_VERSION: u32 = 1;
+
+/// Wallet with its synced state to export and import, so it can be moved to another machine
+/// without syncing it again
+#[derive(Deserialize, Serialize)]
+pub struct WalletExport {
+    /// version of the export format
+    pub version: u32,
+    /// keys and addresses of the wallet (`hdkey` is not set if the HD seed is excluded)
+    pub info: WalletInfo,
+    /// unspent outputs, pending transactions and transaction history of the wallet
+    #[serde(deserialize_with = "deserde_from_str", serialize_with = "serde_to_str")]
+    pub wallet_state: WalletState,
+    /// last synced block of the wallet
+    #[serde(deserialize_with = "deserde_from_str", serialize_with = "serde_to_str")]
+    pub sync_state: Option<SyncState>,
+    /// block height the wallet starts syncing from
+    pub birth_height: Option<u64>,
+}
+
 use std::sync::{Arc, Mutex};
 
 /// proxy for the storage
@@ -241,6 +292,13 @@ where
         Ok(ret)
     }
 }
+
+/// Version of the encoding of `Wallet`, bumped when fields are appended
+///
+/// - 0: view key, wallet kind and hardware kind
+/// - 1: address book
+pub const WALLET_STORAGE_VERSION: u8 = 1;
+
 /// Wallet meta data
 #[derive(Clone)]
 pub struct Wallet {
@@ -256,6 +314,8 @@ pub struct Wallet {
     pub wallet_kind: WalletKind,
     /// hardware wallet type
     pub hardware_kind: HardwareKind,
+    /// address -> metadata (e.g. label) of own and other addresses
+    pub address_book: BTreeMap<String, AddressMetadata>,
 }
 
 impl Encode for Wallet {
@@ -263,6 +323,8 @@ impl Encode for Wallet {
         self.view_key.encode_to(dest);
         self.wallet_kind.encode_to(dest);
         self.hardware_kind.encode_to(dest);
+        WALLET_STORAGE_VERSION.encode_to(dest);
+        self.address_book.encode_to(dest);
     }
 }
 
@@ -271,6 +333,20 @@ impl Decode for Wallet {
         let view_key = PublicKey::decode(input)?;
         let wallet_kind = WalletKind::decode(input)?;
         let hardware_kind = HardwareKind::decode(input)?;
+        // wallets stored before versioning end here
+        let version = if input.remaining_len()? == Some(0) {
+            0
+        } else {
+            u8::decode(input)?
+        };
+        if version > WALLET_STORAGE_VERSION {
+            return Err("Unsupported wallet storage version".into());
+        }
+        let address_book = if version >= 1 {
+            BTreeMap::decode(input)?
+        } else {
+            BTreeMap::new()
+        };
         Ok(Wallet {
             wallet_storage: None,
             name: "".into(),
@@ -278,6 +354,7 @@ impl Decode for Wallet {
             view_key,
             wallet_kind,
             hardware_kind,
+            address_book,
         })
     }
 }
@@ -298,6 +375,7 @@ impl Wallet {
             view_key,
             wallet_kind,
             hardware_kind,
+            address_book: BTreeMap::new(),
         }
     }
 
@@ -762,6 +840,48 @@ where
         Ok(())
     }
 
+    /// Returns the address book of wallet
+    pub fn address_book(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<BTreeMap<String, AddressMetadata>> {
+        Ok(self.get_wallet_info(name, enckey)?.address_book)
+    }
+
+    /// Returns metadata of an address in the address book of wallet
+    pub fn get_address_metadata(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &str,
+    ) -> Result<Option<AddressMetadata>> {
+        Ok(self.address_book(name, enckey)?.remove(address))
+    }
+
+    /// Sets label of an address in the address book of wallet, an empty label removes it
+    pub fn set_address_label(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &str,
+        label: &str,
+    ) -> Result<()> {
+        let mut wallet = self.get_wallet_info(name, enckey)?;
+        if label.is_empty() {
+            wallet.address_book.remove(address);
+        } else {
+            wallet
+                .address_book
+                .entry(address.to_owned())
+                .or_default()
+                .label = label.to_owned();
+        }
+        // only the wallet meta data is replaced, keys and addresses are kept
+        self.storage.save_secure(KEYSPACE, name, enckey, &wallet)?;
+        Ok(())
+    }
+
     /// Returns view key of wallet
     pub fn view_key(&self, name: &str, enckey: &SecKey) -> Result<PublicKey> {
         let _wallet_found = self.get_wallet_info(name, enckey)?;
@@ -1122,6 +1242,52 @@ mod tests {
     use client_common::storage::MemoryStorage;
     use client_common::{seckey::derive_enckey, PrivateKey};
 
+    #[test]
+    fn check_address_book() {
+        let wallet_service = WalletService::new(MemoryStorage::default());
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), "name").unwrap();
+        let view_key = PublicKey::from(&PrivateKey::new().unwrap());
+        wallet_service
+            .create(
+                "name",
+                &enckey,
+                view_key,
+                WalletKind::Basic,
+                HardwareKind::LocalOnly,
+            )
+            .unwrap();
+        let public_key = PublicKey::from(&PrivateKey::new().unwrap());
+        wallet_service
+            .add_public_key("name", &enckey, &public_key)
+            .unwrap();
+
+        let address = "0x0e7c045110b8dbf29765047380898919c5cb56f4";
+        wallet_service
+            .set_address_label("name", &enckey, address, "exchange")
+            .unwrap();
+        assert_eq!(
+            Some(AddressMetadata {
+                label: "exchange".to_owned()
+            }),
+            wallet_service
+                .get_address_metadata("name", &enckey, address)
+                .unwrap()
+        );
+        // keys are kept
+        assert_eq!(
+            1,
+            wallet_service.public_keys("name", &enckey).unwrap().len()
+        );
+
+        wallet_service
+            .set_address_label("name", &enckey, address, "")
+            .unwrap();
+        assert!(wallet_service
+            .address_book("name", &enckey)
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn check_flow() {
         let wallet_service = WalletService::new(MemoryStorage::default());
@@ -1217,11 +1383,20 @@ mod test {
             view_key: PublicKey::from(&private_key),
             wallet_kind: WalletKind::Basic,
             hardware_kind: HardwareKind::LocalOnly,
+            address_book: BTreeMap::new(),
         };
         let wallet_raw = wallet.encode();
         let wallet_2 = Wallet::decode(&mut wallet_raw.as_slice()).unwrap();
         assert_eq!(wallet_2.wallet_kind, WalletKind::Basic);
 
+        // wallets stored before the address book
+        let mut wallet_raw_v0 = wallet.view_key.encode();
+        wallet_raw_v0.extend(wallet.wallet_kind.encode());
+        wallet_raw_v0.extend(wallet.hardware_kind.encode());
+        let wallet_v0 = Wallet::decode(&mut wallet_raw_v0.as_slice()).unwrap();
+        assert_eq!(wallet_v0.view_key, wallet.view_key);
+        assert!(wallet_v0.address_book.is_empty());
+
         let mut key_pairs = BTreeMap::new();
         key_pairs.insert(public_key_1.clone(), PrivateKey::new().unwrap());
         let mut multisig_address_pair = BTreeMap::new();
@@ -1,5 +1,5 @@
-use parity_scale_codec::{Decode, Encode};
-use std::collections::BTreeMap;
+use parity_scale_codec::{Decode, Encode, Input, Output};
+use std::collections::{BTreeMap, BTreeSet};
 
 use chain_core::{
     init::coin::{sum_coins, CoinError},
@@ -7,10 +7,12 @@ use chain_core::{
 };
 use client_common::{Error, ErrorKind, Result, ResultExt, SecKey, SecureStorage, Storage};
 
-use crate::types::{TransactionChange, TransactionPending, WalletBalance};
+use crate::types::{
+    TransactionChange, TransactionHistoryFilter, TransactionPending, WalletBalance,
+};
 
 /// key space of wallet state
-const KEYSPACE: &str = "core_wallet_state";
+pub(crate) const KEYSPACE: &str = "core_wallet_state";
 
 /// Maintains mapping `wallet-name -> wallet-state`
 #[derive(Debug, Default, Clone)]
@@ -87,17 +89,30 @@ where
             .collect())
     }
 
-    /// Returns currently stored transaction history for given wallet
+    /// Returns currently stored transaction history for given wallet, only the transactions
+    /// matching the filter (if any) are returned
     #[inline]
     pub fn get_transaction_history(
         &self,
         name: &str,
         enckey: &SecKey,
         reversed: bool,
+        filter: Option<TransactionHistoryFilter>,
     ) -> Result<Box<dyn Iterator<Item = TransactionChange>>> {
         let mut state = self.get_wallet_state(name, enckey)?;
         let mut history = std::mem::replace(&mut state.transaction_history, BTreeMap::new());
-        let get_tx = move |txid| history.remove(&txid);
+        let mut memos = std::mem::replace(&mut state.transaction_memos, BTreeMap::new());
+        let get_tx = move |txid| {
+            let memo = memos.remove(&txid);
+            history
+                .remove(&txid)
+                .map(|change| TransactionChange { memo, ..change })
+                .filter(|change| {
+                    filter
+                        .as_ref()
+                        .map_or(true, |filter| filter.matches(change))
+                })
+        };
         let iter = state.transaction_log.into_iter();
         Ok(if reversed {
             Box::new(iter.rev().filter_map(get_tx))
@@ -161,6 +176,35 @@ where
         self.modify_state(name, enckey, |state| state.apply_memento(memento))
     }
 
+    /// Sets the memo of a transaction in the history of a wallet, an empty memo removes it
+    pub fn set_transaction_memo(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: TxId,
+        memo: &str,
+    ) -> Result<()> {
+        self.modify_state(name, enckey, |state| {
+            state.set_transaction_memo(transaction_id, memo.to_owned())
+        })
+    }
+
+    /// Excludes an unspent transaction of a wallet from input selection
+    pub fn freeze_utxo(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<()> {
+        self.modify_state(name, enckey, |state| state.freeze_utxo(input.clone()))
+    }
+
+    /// Makes a frozen unspent transaction of a wallet selectable as input again
+    pub fn unfreeze_utxo(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<()> {
+        self.modify_state(name, enckey, |state| state.unfreeze_utxo(input))
+    }
+
+    /// Returns the frozen unspent transactions of a wallet
+    #[inline]
+    pub fn get_frozen_utxos(&self, name: &str, enckey: &SecKey) -> Result<BTreeSet<TxoPointer>> {
+        Ok(self.get_wallet_state(name, enckey)?.get_frozen_utxos())
+    }
+
     /// Deletes all the state data corresponding to a wallet
     #[inline]
     pub fn delete_wallet_state(&self, name: &str, enckey: &SecKey) -> Result<()> {
@@ -240,8 +284,18 @@ pub fn delete_wallet_state<S: Storage>(storage: &S, name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Version of the encoding of `WalletState`, bumped when fields are appended
+///
+/// - 0: unspent, pending and history of transactions
+/// - 1: transaction memos
+/// - 2: frozen UTxOs
+pub const WALLET_STATE_STORAGE_VERSION: u8 = 2;
+
+/// Maximum length of a transaction memo in bytes
+pub const MAX_TRANSACTION_MEMO_LENGTH: usize = 512;
+
 /// Wallet state
-#[derive(Debug, Encode, Decode)]
+#[derive(Debug)]
 pub struct WalletState {
     /// UTxO
     pub unspent_transactions: BTreeMap<TxoPointer, TxOut>,
@@ -251,6 +305,58 @@ pub struct WalletState {
     pub transaction_history: BTreeMap<TxId, TransactionChange>,
     /// Transaction ids ordered by insert order.
     pub transaction_log: Vec<TxId>,
+    /// Local memos of the transactions in history indexed by txid
+    pub transaction_memos: BTreeMap<TxId, String>,
+    /// UTxOs excluded from input selection
+    pub frozen_utxos: BTreeSet<TxoPointer>,
+}
+
+impl Encode for WalletState {
+    fn encode_to<W: Output>(&self, dest: &mut W) {
+        self.unspent_transactions.encode_to(dest);
+        self.pending_transactions.encode_to(dest);
+        self.transaction_history.encode_to(dest);
+        self.transaction_log.encode_to(dest);
+        WALLET_STATE_STORAGE_VERSION.encode_to(dest);
+        self.transaction_memos.encode_to(dest);
+        self.frozen_utxos.encode_to(dest);
+    }
+}
+
+impl Decode for WalletState {
+    fn decode<I: Input>(input: &mut I) -> std::result::Result<Self, parity_scale_codec::Error> {
+        let unspent_transactions = BTreeMap::decode(input)?;
+        let pending_transactions = BTreeMap::decode(input)?;
+        let transaction_history = BTreeMap::decode(input)?;
+        let transaction_log = Vec::decode(input)?;
+        // wallet states stored before versioning end here
+        let version = if input.remaining_len()? == Some(0) {
+            0
+        } else {
+            u8::decode(input)?
+        };
+        if version > WALLET_STATE_STORAGE_VERSION {
+            return Err("Unsupported wallet state storage version".into());
+        }
+        let transaction_memos = if version >= 1 {
+            BTreeMap::decode(input)?
+        } else {
+            BTreeMap::new()
+        };
+        let frozen_utxos = if version >= 2 {
+            BTreeSet::decode(input)?
+        } else {
+            BTreeSet::new()
+        };
+        Ok(WalletState {
+            unspent_transactions,
+            pending_transactions,
+            transaction_history,
+            transaction_log,
+            transaction_memos,
+            frozen_utxos,
+        })
+    }
 }
 
 impl Default for WalletState {
@@ -261,6 +367,8 @@ impl Default for WalletState {
             pending_transactions: Default::default(),
             transaction_history: Default::default(),
             transaction_log: vec![],
+            transaction_memos: Default::default(),
+            frozen_utxos: Default::default(),
         }
     }
 }
@@ -329,6 +437,7 @@ impl WalletState {
             total: amount_total,
             available: amount_available,
             pending: amount_pending,
+            reserved: Coin::zero(),
         };
         Ok(wallet_balances)
     }
@@ -340,6 +449,71 @@ impl WalletState {
         Ok(())
     }
 
+    /// Applies memento to wallet state, returns the memento which reverts the changes (e.g. to
+    /// unwind blocks of a fork)
+    pub fn apply_memento_with_undo(
+        &mut self,
+        memento: &WalletStateMemento,
+    ) -> Result<WalletStateMemento> {
+        let mut undo = Vec::new();
+        for operation in memento.0.iter() {
+            if let Some(inverse) = self.inverse_memento_operation(operation) {
+                undo.push(inverse);
+            }
+            self.apply_memento_operation(operation)?;
+        }
+        undo.reverse();
+        Ok(WalletStateMemento(undo))
+    }
+
+    /// Returns the operation which reverts given operation applied to current state (`None` if
+    /// it doesn't change the state)
+    fn inverse_memento_operation(
+        &self,
+        memento_operation: &MementoOperation,
+    ) -> Option<MementoOperation> {
+        match memento_operation {
+            MementoOperation::AddTransactionChange(ref transaction_id, _) => {
+                if self.transaction_history.contains_key(transaction_id) {
+                    None
+                } else {
+                    Some(MementoOperation::RemoveTransactionChange(*transaction_id))
+                }
+            }
+            MementoOperation::AddUnspentTransaction(ref input, _) => {
+                Some(match self.unspent_transactions.get(input) {
+                    Some(output) => {
+                        MementoOperation::AddUnspentTransaction(input.clone(), output.clone())
+                    }
+                    None => MementoOperation::RemoveUnspentTransaction(input.clone()),
+                })
+            }
+            MementoOperation::RemoveUnspentTransaction(ref input) => {
+                self.unspent_transactions.get(input).map(|output| {
+                    MementoOperation::AddUnspentTransaction(input.clone(), output.clone())
+                })
+            }
+            MementoOperation::AddPendingTransaction(ref transaction_id, _) => {
+                if self.pending_transactions.contains_key(transaction_id) {
+                    None
+                } else {
+                    Some(MementoOperation::RemovePendingTransaction(*transaction_id))
+                }
+            }
+            MementoOperation::RemovePendingTransaction(ref transaction_id) => self
+                .pending_transactions
+                .get(transaction_id)
+                .map(|pending| {
+                    MementoOperation::AddPendingTransaction(*transaction_id, pending.clone())
+                }),
+            MementoOperation::RemoveTransactionChange(ref transaction_id) => {
+                self.transaction_history.get(transaction_id).map(|change| {
+                    MementoOperation::AddTransactionChange(*transaction_id, change.clone())
+                })
+            }
+        }
+    }
+
     /// add tx change
     pub fn add_transaction_change(&mut self, txid: TxId, change: TransactionChange) {
         self.transaction_history.insert(txid, change);
@@ -371,13 +545,100 @@ impl WalletState {
             MementoOperation::RemovePendingTransaction(ref transaction_id) => {
                 self.pending_transactions.remove(transaction_id);
             }
+            MementoOperation::RemoveTransactionChange(ref transaction_id) => {
+                // the memo is kept, in case the transaction is included again
+                if self.transaction_history.remove(transaction_id).is_some() {
+                    self.transaction_log.retain(|txid| txid != transaction_id);
+                }
+            }
         }
         Ok(())
     }
 
     /// Returns currently stored transaction change for given wallet and transaction id
     pub fn get_transaction_change(&self, transaction_id: &TxId) -> Option<TransactionChange> {
-        self.transaction_history.get(transaction_id).cloned()
+        self.transaction_history
+            .get(transaction_id)
+            .cloned()
+            .map(|change| self.with_memo(change))
+    }
+
+    /// Fills in the memo of a transaction change
+    fn with_memo(&self, mut change: TransactionChange) -> TransactionChange {
+        change.memo = self.transaction_memos.get(&change.transaction_id).cloned();
+        change
+    }
+
+    /// Sets the memo of a transaction in history, an empty memo removes it
+    pub fn set_transaction_memo(&mut self, transaction_id: TxId, memo: String) -> Result<()> {
+        if !self.transaction_history.contains_key(&transaction_id) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Transaction ({}) is not in wallet history",
+                    hex::encode(transaction_id)
+                ),
+            ));
+        }
+        if memo.len() > MAX_TRANSACTION_MEMO_LENGTH {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Transaction memo is longer than {} bytes",
+                    MAX_TRANSACTION_MEMO_LENGTH
+                ),
+            ));
+        }
+        if memo.is_empty() {
+            self.transaction_memos.remove(&transaction_id);
+        } else {
+            self.transaction_memos.insert(transaction_id, memo);
+        }
+        Ok(())
+    }
+
+    /// Excludes an unspent transaction of the wallet from input selection
+    pub fn freeze_utxo(&mut self, input: TxoPointer) -> Result<()> {
+        if !self.unspent_transactions.contains_key(&input) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "UTxO ({}:{}) is not an unspent transaction of the wallet",
+                    hex::encode(input.id),
+                    input.index
+                ),
+            ));
+        }
+        // outputs spent since they were frozen are dropped
+        let unspent_transactions = &self.unspent_transactions;
+        self.frozen_utxos
+            .retain(|frozen| unspent_transactions.contains_key(frozen));
+        self.frozen_utxos.insert(input);
+        Ok(())
+    }
+
+    /// Makes a frozen unspent transaction selectable as input again
+    pub fn unfreeze_utxo(&mut self, input: &TxoPointer) -> Result<()> {
+        if !self.frozen_utxos.remove(input) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "UTxO ({}:{}) is not frozen",
+                    hex::encode(input.id),
+                    input.index
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the frozen transactions which are still unspent
+    pub fn get_frozen_utxos(&self) -> BTreeSet<TxoPointer> {
+        self.frozen_utxos
+            .iter()
+            .filter(|input| self.unspent_transactions.contains_key(input))
+            .cloned()
+            .collect()
     }
 
     /// Returns details corresponding to given input
@@ -398,16 +659,17 @@ impl WalletState {
 }
 
 /// A memento for wallet state used for batch operations on wallet state service
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Encode, Decode)]
 pub struct WalletStateMemento(Vec<MementoOperation>);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Encode, Decode)]
 enum MementoOperation {
     AddTransactionChange(TxId, TransactionChange),
     AddUnspentTransaction(TxoPointer, TxOut),
     AddPendingTransaction(TxId, TransactionPending),
     RemovePendingTransaction(TxId),
     RemoveUnspentTransaction(TxoPointer),
+    RemoveTransactionChange(TxId),
 }
 
 impl WalletStateMemento {
@@ -423,6 +685,14 @@ impl WalletStateMemento {
         self.0.is_empty()
     }
 
+    /// Returns transaction changes added to memento
+    pub fn transaction_changes(&self) -> impl Iterator<Item = &TransactionChange> {
+        self.0.iter().filter_map(|operation| match operation {
+            MementoOperation::AddTransactionChange(_, change) => Some(change),
+            _ => None,
+        })
+    }
+
     /// Adds transaction change to memento
     #[inline]
     pub fn add_transaction_change(&mut self, transaction_change: TransactionChange) {
@@ -472,7 +742,7 @@ mod tests {
     use client_common::tendermint::types::Time;
     use client_common::{seckey::derive_enckey, storage::MemoryStorage};
 
-    use crate::types::{BalanceChange, TransactionType};
+    use crate::types::{BalanceChange, BalanceChangeDirection, TransactionType};
     use chain_core::init::coin::Coin;
 
     #[test]
@@ -496,7 +766,7 @@ mod tests {
         assert_eq!(
             0,
             wallet_state_service
-                .get_transaction_history(name, enckey, false)
+                .get_transaction_history(name, enckey, false, None)
                 .unwrap()
                 .count()
         );
@@ -584,6 +854,7 @@ mod tests {
             block_height: 0,
             fee_paid: Fee::new(Coin::new(10).unwrap()),
             block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+            memo: None,
         });
 
         assert!(wallet_state_service
@@ -593,7 +864,18 @@ mod tests {
         assert_eq!(
             1,
             wallet_state_service
-                .get_transaction_history(name, enckey, false)
+                .get_transaction_history(name, enckey, false, None)
+                .unwrap()
+                .count()
+        );
+        let filter = TransactionHistoryFilter {
+            direction: Some(BalanceChangeDirection::Outgoing),
+            ..Default::default()
+        };
+        assert_eq!(
+            0,
+            wallet_state_service
+                .get_transaction_history(name, enckey, false, Some(filter))
                 .unwrap()
                 .count()
         );
@@ -623,6 +905,7 @@ mod tests {
             block_height: 0,
             fee_paid: Fee::new(Coin::new(10).unwrap()),
             block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+            memo: None,
         });
 
         assert!(wallet_state_service
@@ -632,7 +915,7 @@ mod tests {
         assert_eq!(
             2,
             wallet_state_service
-                .get_transaction_history(name, enckey, false)
+                .get_transaction_history(name, enckey, false, None)
                 .unwrap()
                 .count()
         );
@@ -663,6 +946,7 @@ mod tests {
                 total: Coin::new(140).unwrap(),
                 available: Coin::new(140).unwrap(),
                 pending: Coin::zero(),
+                reserved: Coin::zero(),
             }
         );
 
@@ -686,6 +970,7 @@ mod tests {
                 total: Coin::new(90).unwrap(),
                 available: Coin::new(40).unwrap(),
                 pending: Coin::new(50).unwrap(),
+                reserved: Coin::zero(),
             }
         );
 
@@ -724,6 +1009,7 @@ mod tests {
                 total: Coin::new(90).unwrap(),
                 available: Coin::new(90).unwrap(),
                 pending: Coin::zero(),
+                reserved: Coin::zero(),
             }
         );
         let unspent_tx = wallet_state_service
@@ -758,7 +1044,208 @@ mod tests {
                 total: Coin::new(140).unwrap(),
                 available: Coin::new(140).unwrap(),
                 pending: Coin::new(0).unwrap(),
+                reserved: Coin::zero(),
             }
         );
     }
+
+    #[test]
+    fn check_transaction_memo() {
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let wallet_state_service = WalletStateService::new(MemoryStorage::default());
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(TransactionChange {
+            transaction_id: [0; 32],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            balance_change: BalanceChange::Incoming {
+                value: Coin::new(50).unwrap(),
+            },
+            transaction_type: TransactionType::Transfer,
+            block_height: 0,
+            fee_paid: Fee::new(Coin::zero()),
+            block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+            memo: None,
+        });
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        assert!(wallet_state_service
+            .set_transaction_memo(name, enckey, [1; 32], "memo")
+            .is_err());
+        let too_long = "a".repeat(MAX_TRANSACTION_MEMO_LENGTH + 1);
+        assert!(wallet_state_service
+            .set_transaction_memo(name, enckey, [0; 32], &too_long)
+            .is_err());
+
+        wallet_state_service
+            .set_transaction_memo(name, enckey, [0; 32], "rent")
+            .unwrap();
+        let change = wallet_state_service
+            .get_transaction_history(name, enckey, false, None)
+            .unwrap()
+            .next()
+            .unwrap();
+        assert_eq!(Some("rent".to_owned()), change.memo);
+        assert_eq!(
+            Some("rent".to_owned()),
+            wallet_state_service
+                .get_transaction_change(name, enckey, &[0; 32])
+                .unwrap()
+                .unwrap()
+                .memo
+        );
+
+        wallet_state_service
+            .set_transaction_memo(name, enckey, [0; 32], "")
+            .unwrap();
+        assert!(wallet_state_service
+            .get_transaction_change(name, enckey, &[0; 32])
+            .unwrap()
+            .unwrap()
+            .memo
+            .is_none());
+    }
+
+    #[test]
+    fn check_apply_memento_with_undo() {
+        let change = |id: u8| TransactionChange {
+            transaction_id: [id; 32],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            balance_change: BalanceChange::Incoming {
+                value: Coin::new(50).unwrap(),
+            },
+            transaction_type: TransactionType::Transfer,
+            block_height: 0,
+            fee_paid: Fee::new(Coin::zero()),
+            block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+            memo: None,
+        };
+        let output = |value| TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(value).unwrap());
+        let pending = TransactionPending {
+            used_inputs: vec![TxoPointer::new([0; 32], 0)],
+            block_height: 1,
+            return_amount: Coin::zero(),
+        };
+
+        let mut state = WalletState::default();
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(change(0));
+        memento.add_unspent_transaction(TxoPointer::new([0; 32], 0), output(50));
+        memento.add_pending_transaction([1; 32], pending.clone());
+        state.apply_memento(&memento).unwrap();
+        let snapshot = state.encode();
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(change(0));
+        memento.add_transaction_change(change(2));
+        memento.remove_unspent_transaction(TxoPointer::new([0; 32], 0));
+        memento.add_unspent_transaction(TxoPointer::new([2; 32], 0), output(40));
+        memento.add_unspent_transaction(TxoPointer::new([2; 32], 0), output(30));
+        memento.remove_pending_transaction([1; 32]);
+        memento.add_pending_transaction([2; 32], pending);
+        let undo = state.apply_memento_with_undo(&memento).unwrap();
+        assert_eq!(vec![[0; 32], [2; 32]], state.transaction_log);
+        assert_eq!(1, state.unspent_transactions.len());
+
+        state.apply_memento(&undo).unwrap();
+        assert_eq!(snapshot, state.encode());
+    }
+
+    #[test]
+    fn check_wallet_state_decode_unversioned() {
+        let mut state = WalletState::default();
+        state.transaction_log.push([0; 32]);
+        state.transaction_memos.insert([0; 32], "memo".to_owned());
+
+        // encoding before the memos were added
+        let mut encoded = Vec::new();
+        state.unspent_transactions.encode_to(&mut encoded);
+        state.pending_transactions.encode_to(&mut encoded);
+        state.transaction_history.encode_to(&mut encoded);
+        state.transaction_log.encode_to(&mut encoded);
+        let decoded = WalletState::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(state.transaction_log, decoded.transaction_log);
+        assert!(decoded.transaction_memos.is_empty());
+
+        let decoded = WalletState::decode(&mut state.encode().as_slice()).unwrap();
+        assert_eq!(state.transaction_memos, decoded.transaction_memos);
+    }
+
+    #[test]
+    fn check_wallet_state_decode_version_1() {
+        let mut state = WalletState::default();
+        state.transaction_memos.insert([0; 32], "memo".to_owned());
+        state.frozen_utxos.insert(TxoPointer::new([1; 32], 0));
+
+        // encoding before the frozen UTxOs were added
+        let mut encoded = Vec::new();
+        state.unspent_transactions.encode_to(&mut encoded);
+        state.pending_transactions.encode_to(&mut encoded);
+        state.transaction_history.encode_to(&mut encoded);
+        state.transaction_log.encode_to(&mut encoded);
+        1u8.encode_to(&mut encoded);
+        state.transaction_memos.encode_to(&mut encoded);
+        let decoded = WalletState::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(state.transaction_memos, decoded.transaction_memos);
+        assert!(decoded.frozen_utxos.is_empty());
+
+        let decoded = WalletState::decode(&mut state.encode().as_slice()).unwrap();
+        assert_eq!(state.frozen_utxos, decoded.frozen_utxos);
+    }
+
+    #[test]
+    fn check_freeze_utxo() {
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let wallet_state_service = WalletStateService::new(MemoryStorage::default());
+
+        let input = TxoPointer::new([0; 32], 0);
+        let output = TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(50).unwrap());
+        assert!(wallet_state_service
+            .freeze_utxo(name, enckey, &input)
+            .is_err());
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(input.clone(), output);
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+        wallet_state_service
+            .freeze_utxo(name, enckey, &input)
+            .unwrap();
+        assert!(wallet_state_service
+            .get_frozen_utxos(name, enckey)
+            .unwrap()
+            .contains(&input));
+
+        wallet_state_service
+            .unfreeze_utxo(name, enckey, &input)
+            .unwrap();
+        assert!(wallet_state_service
+            .get_frozen_utxos(name, enckey)
+            .unwrap()
+            .is_empty());
+        assert!(wallet_state_service
+            .unfreeze_utxo(name, enckey, &input)
+            .is_err());
+
+        // spent outputs aren't frozen anymore
+        wallet_state_service
+            .freeze_utxo(name, enckey, &input)
+            .unwrap();
+        let mut memento = WalletStateMemento::default();
+        memento.remove_unspent_transaction(input);
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+        assert!(wallet_state_service
+            .get_frozen_utxos(name, enckey)
+            .unwrap()
+            .is_empty());
+    }
 }
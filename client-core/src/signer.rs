@@ -1,13 +1,15 @@
 //! Transaction signing
 mod dummy_signer;
+mod hardware_signer;
 mod key_pair_signer;
 mod unauthorized_signer;
 mod wallet_signer;
 
 pub use dummy_signer::DummySigner;
+pub use hardware_signer::HardwareSigner;
 pub use key_pair_signer::KeyPairSigner;
 pub use unauthorized_signer::UnauthorizedSigner;
-pub use wallet_signer::{WalletSigner, WalletSignerManager};
+pub use wallet_signer::{SignerBackend, WalletSigner, WalletSignerManager};
 
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::witness::{TxInWitness, TxWitness};
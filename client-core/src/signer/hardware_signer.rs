@@ -0,0 +1,136 @@
+//! Hardware signer responsible for signing as hardware wallet (e.g. Ledger device over HID)
+use chain_core::common::H256;
+use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::witness::{TxInWitness, TxWitness};
+use client_common::Transaction;
+use client_common::{Error, ErrorKind, Result, ResultExt, SecKey, Storage};
+
+use crate::service::{HwKeyService, RootHashService, WalletService};
+use crate::types::WalletKind;
+use crate::{SelectedUnspentTransactions, SignCondition, Signer};
+
+/// A short-lived signer belonging to a hardware wallet, the private keys never leave the device,
+/// only public keys and their chain paths are looked up in the local storage
+pub struct HardwareSigner<'a, S>
+where
+    S: Storage,
+{
+    name: &'a str,
+    enckey: &'a SecKey,
+    root_hash_service: &'a RootHashService<S>,
+    wallet_service: &'a WalletService<S>,
+    hw_key_service: &'a HwKeyService,
+}
+
+impl<'a, S> HardwareSigner<'a, S>
+where
+    S: Storage + 'static,
+{
+    /// Create an instance of hardware signer
+    pub fn new(
+        name: &'a str,
+        enckey: &'a SecKey,
+        root_hash_service: &'a RootHashService<S>,
+        wallet_service: &'a WalletService<S>,
+        hw_key_service: &'a HwKeyService,
+    ) -> Self {
+        HardwareSigner {
+            name,
+            enckey,
+            root_hash_service,
+            wallet_service,
+            hw_key_service,
+        }
+    }
+
+    /// Schnorr signs message on the device with the key corresponding to `self_public_key` in
+    /// given 1-of-n root hash
+    pub(crate) fn schnorr_sign_with_root_hash(
+        &self,
+        tx: &Transaction,
+        root_hash: &H256,
+    ) -> Result<TxInWitness> {
+        let wallet = self
+            .wallet_service
+            .get_wallet_info(self.name, self.enckey)?;
+        if wallet.wallet_kind != WalletKind::HW {
+            return Err(Error::new(
+                ErrorKind::IllegalInput,
+                "Hardware signer can only sign with hardware wallets",
+            ));
+        }
+        if self
+            .root_hash_service
+            .required_signers(self.name, &root_hash, self.enckey)?
+            != 1
+        {
+            return Err(Error::new(
+                ErrorKind::IllegalInput,
+                "Hardware signer cannot sign with multi-sig addresses",
+            ));
+        }
+
+        let public_key = self
+            .root_hash_service
+            .public_key(self.name, &root_hash, self.enckey)?;
+        let chain_path = self
+            .wallet_service
+            .find_chain_path(self.name, self.enckey, &public_key)?
+            .chain(|| (ErrorKind::PermissionDenied, "can not find chain path"))?;
+        let sign_key = self.hw_key_service.get_sign_key(&chain_path)?;
+
+        let proof = self.root_hash_service.generate_proof(
+            self.name,
+            &root_hash,
+            vec![public_key],
+            self.enckey,
+        )?;
+
+        Ok(TxInWitness::TreeSig(sign_key.schnorr_sign(tx)?, proof))
+    }
+}
+
+impl<'a, S> Signer for HardwareSigner<'a, S>
+where
+    S: Storage + 'static,
+{
+    fn schnorr_sign_transaction(
+        &self,
+        tx: &Transaction,
+        selected_unspent_transactions: &SelectedUnspentTransactions<'_>,
+    ) -> Result<TxWitness> {
+        selected_unspent_transactions
+            .iter()
+            .map(|(_, output)| self.schnorr_sign(tx, &output.address))
+            .collect::<Result<Vec<TxInWitness>>>()
+            .map(Into::into)
+    }
+
+    fn schnorr_sign_condition(&self, signing_addr: &ExtendedAddr) -> Result<SignCondition> {
+        let maybe_root_hash =
+            self.wallet_service
+                .find_root_hash(self.name, self.enckey, signing_addr)?;
+        if None == maybe_root_hash {
+            Ok(SignCondition::Impossible)
+        } else {
+            Ok(SignCondition::SingleSignUnlock)
+        }
+    }
+
+    fn schnorr_sign(&self, tx: &Transaction, signing_addr: &ExtendedAddr) -> Result<TxInWitness> {
+        let root_hash = self
+            .wallet_service
+            .find_root_hash(self.name, self.enckey, signing_addr)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Output's address ({}) does not belong to wallet with name: {}",
+                        signing_addr, self.name
+                    ),
+                )
+            })?;
+
+        self.schnorr_sign_with_root_hash(tx, &root_hash)
+    }
+}
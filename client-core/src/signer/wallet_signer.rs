@@ -3,20 +3,45 @@ use chain_core::common::H256;
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::witness::{TxInWitness, TxWitness};
 use client_common::Transaction;
-use client_common::{Error, ErrorKind, Result, ResultExt, SecKey, Storage};
+use client_common::{Error, ErrorKind, PrivateKeyAction, Result, ResultExt, SecKey, Storage};
 
+use super::HardwareSigner;
 use crate::service::{HwKeyService, KeyService, RootHashService, WalletService};
 use crate::types::WalletKind;
 use crate::{SelectedUnspentTransactions, SignCondition, Signer};
 
+/// Backend producing the signatures of hardware wallets
+#[derive(Debug, Clone)]
+pub enum SignerBackend {
+    /// only local (basic and HD) wallets can sign, private keys are stored encrypted in storage
+    Software,
+    /// hardware wallets sign on the device (e.g. a Ledger over HID), local wallets still sign
+    /// with their stored private keys
+    Hardware(HwKeyService),
+}
+
+impl Default for SignerBackend {
+    fn default() -> Self {
+        SignerBackend::Software
+    }
+}
+
+impl From<HwKeyService> for SignerBackend {
+    fn from(hw_key_service: HwKeyService) -> Self {
+        match hw_key_service {
+            HwKeyService::Unauthorized(_) => SignerBackend::Software,
+            hw_key_service => SignerBackend::Hardware(hw_key_service),
+        }
+    }
+}
+
 /// Wallet signer manager responsible for creating wallet signers
 #[derive(Debug, Clone)]
 pub struct WalletSignerManager<S>
 where
     S: Storage,
 {
-    /// hardware key serivce
-    pub hw_key_service: HwKeyService,
+    backend: SignerBackend,
     key_service: KeyService<S>,
     root_hash_service: RootHashService<S>,
     wallet_service: WalletService<S>,
@@ -28,32 +53,70 @@ where
 {
     /// Create an instance fo wallet signer manager
     pub fn new(storage: S, hw_key_service: HwKeyService) -> Self {
+        Self::with_backend(storage, hw_key_service.into())
+    }
+
+    /// Create an instance fo wallet signer manager with given signer backend
+    pub fn with_backend(storage: S, backend: SignerBackend) -> Self {
         Self {
-            hw_key_service,
+            backend,
             key_service: KeyService::new(storage.clone()),
             root_hash_service: RootHashService::new(storage.clone()),
             wallet_service: WalletService::new(storage),
         }
     }
 
+    /// Returns the signer backend
+    #[inline]
+    pub fn backend(&self) -> &SignerBackend {
+        &self.backend
+    }
+
+    /// Selects the signer backend used by signers created afterwards
+    #[inline]
+    pub fn set_backend(&mut self, backend: SignerBackend) {
+        self.backend = backend;
+    }
+
     /// Create an instance of wallet signer
-    pub fn create_signer<'a>(
-        &'a self,
-        name: &'a str,
-        enckey: &'a SecKey,
-        hw_key_service: &'a HwKeyService,
-    ) -> WalletSigner<'a, S> {
+    pub fn create_signer<'a>(&'a self, name: &'a str, enckey: &'a SecKey) -> WalletSigner<'a, S> {
         WalletSigner::new(
             name,
             enckey,
             &self.root_hash_service,
             &self.wallet_service,
-            hw_key_service,
+            &self.backend,
         )
     }
+
+    /// Create an instance of hardware signer, fails if the backend is not a hardware one
+    pub fn create_hardware_signer<'a>(
+        &'a self,
+        name: &'a str,
+        enckey: &'a SecKey,
+    ) -> Result<HardwareSigner<'a, S>> {
+        match &self.backend {
+            SignerBackend::Hardware(hw_key_service) => Ok(HardwareSigner::new(
+                name,
+                enckey,
+                &self.root_hash_service,
+                &self.wallet_service,
+                hw_key_service,
+            )),
+            SignerBackend::Software => Err(no_hardware_backend_error()),
+        }
+    }
 }
 
-/// A short-lived signer belonging to a wallet
+fn no_hardware_backend_error() -> Error {
+    Error::new(
+        ErrorKind::PermissionDenied,
+        "Hardware wallet can't sign without a hardware signer backend",
+    )
+}
+
+/// A short-lived signer belonging to a wallet, signing of hardware wallets is delegated to
+/// `HardwareSigner`
 pub struct WalletSigner<'a, S>
 where
     S: Storage,
@@ -62,7 +125,7 @@ where
     enckey: &'a SecKey,
     root_hash_service: &'a RootHashService<S>,
     wallet_service: &'a WalletService<S>,
-    hw_key_service: &'a HwKeyService,
+    backend: &'a SignerBackend,
 }
 
 impl<'a, S> WalletSigner<'a, S>
@@ -75,14 +138,14 @@ where
         enckey: &'a SecKey,
         root_hash_service: &'a RootHashService<S>,
         wallet_service: &'a WalletService<S>,
-        hw_key_service: &'a HwKeyService,
+        backend: &'a SignerBackend,
     ) -> Self {
         WalletSigner {
             name,
             enckey,
             root_hash_service,
             wallet_service,
-            hw_key_service,
+            backend,
         }
     }
 }
@@ -142,6 +205,24 @@ where
         tx: &Transaction,
         root_hash: &H256,
     ) -> Result<TxInWitness> {
+        let wallet = self
+            .wallet_service
+            .get_wallet_info(self.name, self.enckey)?;
+        wallet.wallet_kind.ensure_can_sign()?;
+        if wallet.wallet_kind == WalletKind::HW {
+            return match self.backend {
+                SignerBackend::Hardware(hw_key_service) => HardwareSigner::new(
+                    self.name,
+                    self.enckey,
+                    self.root_hash_service,
+                    self.wallet_service,
+                    hw_key_service,
+                )
+                .schnorr_sign_with_root_hash(tx, root_hash),
+                SignerBackend::Software => Err(no_hardware_backend_error()),
+            };
+        }
+
         if self
             .root_hash_service
             .required_signers(self.name, &root_hash, self.enckey)?
@@ -156,33 +237,18 @@ where
         let public_key = self
             .root_hash_service
             .public_key(self.name, &root_hash, self.enckey)?;
-        let wallet = self
+        let private_key = self
             .wallet_service
-            .get_wallet_info(self.name, self.enckey)?;
-        let sign_key = match wallet.wallet_kind {
-            WalletKind::HW => {
-                let chain_path = self
-                    .wallet_service
-                    .find_chain_path(self.name, self.enckey, &public_key)?
-                    .chain(|| (ErrorKind::PermissionDenied, "can not find chain path"))?;
-                self.hw_key_service.get_sign_key(&chain_path)?
-            }
-            WalletKind::Basic | WalletKind::HD => {
-                let private_key = self
-                    .wallet_service
-                    .find_private_key(self.name, self.enckey, &public_key)?
-                    .chain(|| {
-                        (
-                            ErrorKind::InvalidInput,
-                            format!(
-                                "Unable to find private key corresponding to given root hash: {}",
-                                hex::encode(root_hash)
-                            ),
-                        )
-                    })?;
-                Box::new(private_key)
-            }
-        };
+            .find_private_key(self.name, self.enckey, &public_key)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Unable to find private key corresponding to given root hash: {}",
+                        hex::encode(root_hash)
+                    ),
+                )
+            })?;
 
         let proof = self.root_hash_service.generate_proof(
             self.name,
@@ -191,7 +257,7 @@ where
             self.enckey,
         )?;
 
-        Ok(TxInWitness::TreeSig(sign_key.schnorr_sign(tx)?, proof))
+        Ok(TxInWitness::TreeSig(private_key.schnorr_sign(tx)?, proof))
     }
 }
 
@@ -202,6 +268,7 @@ mod wallet_signer_tests {
 
     use chain_core::tx::data::Tx;
     use chain_tx_validation::witness::verify_tx_address;
+    use client_common::seckey::derive_enckey;
     use client_common::storage::MemoryStorage;
 
     use crate::hd_wallet::HardwareKind;
@@ -243,9 +310,8 @@ mod wallet_signer_tests {
                 1,
             )
             .unwrap();
-        let hw_key_service = HwKeyService::default();
-        let signer_manager = WalletSignerManager::new(storage, hw_key_service.clone());
-        let signer = signer_manager.create_signer(name, &enckey, &hw_key_service);
+        let signer_manager = WalletSignerManager::new(storage, HwKeyService::default());
+        let signer = signer_manager.create_signer(name, &enckey);
 
         let witness = signer
             .schnorr_sign(&tx, &tree_address)
@@ -254,6 +320,27 @@ mod wallet_signer_tests {
         assert!(verify_tx_address(&witness, &tx.id(), &tree_address).is_ok());
     }
 
+    #[test]
+    fn check_signer_backend() {
+        let storage = MemoryStorage::default();
+        let mut signer_manager = WalletSignerManager::new(storage, HwKeyService::default());
+        assert!(matches!(signer_manager.backend(), SignerBackend::Software));
+
+        let name = "name";
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            signer_manager
+                .create_hardware_signer(name, &enckey)
+                .err()
+                .unwrap()
+                .kind()
+        );
+
+        signer_manager.set_backend(SignerBackend::Hardware(HwKeyService::default()));
+        assert!(signer_manager.create_hardware_signer(name, &enckey).is_ok());
+    }
+
     #[test]
     #[cfg(feature = "experimental")]
     fn check_2_of_3_invalid_signing_flow() {
@@ -291,9 +378,8 @@ mod wallet_signer_tests {
             )
             .unwrap();
 
-        let hw_key_service = HwKeyService::default();
-        let signer_manager = WalletSignerManager::new(storage, hw_key_service.clone());
-        let signer = signer_manager.create_signer(name, &enckey, &hw_key_service);
+        let signer_manager = WalletSignerManager::new(storage, HwKeyService::default());
+        let signer = signer_manager.create_signer(name, &enckey);
 
         assert_eq!(
             ErrorKind::IllegalInput,
@@ -1,12 +1,14 @@
 //! Transaction builder
+mod attributes_builder;
 mod default_wallet_transaction_builder;
 mod raw_transfer_transaction_builder;
 mod unauthorized_wallet_transaction_builder;
 
+pub use attributes_builder::AttributesBuilder;
 pub use default_wallet_transaction_builder::DefaultWalletTransactionBuilder;
 pub use raw_transfer_transaction_builder::{
-    RawTransferTransaction, RawTransferTransactionBuilder, SignedTransferTransaction,
-    UnsignedTransferTransaction, WitnessedUTxO,
+    PartiallySignedTransferTx, RawTransferTransaction, RawTransferTransactionBuilder,
+    SignedTransferTransaction, UnsignedTransferTransaction, UnsignedTransferTx, WitnessedUTxO,
 };
 pub use unauthorized_wallet_transaction_builder::UnauthorizedWalletTransactionBuilder;
 
@@ -15,6 +17,8 @@ use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
+use chain_core::tx::data::Tx;
+use chain_core::tx::witness::{TxInWitness, TxWitness};
 use chain_core::tx::TxAux;
 use client_common::{PrivateKey, Result, SecKey, SignedTransaction, Transaction};
 
@@ -49,6 +53,70 @@ pub trait WalletTransactionBuilder: Send + Sync + Clone {
         attributes: TxAttributes,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)>;
 
+    /// Selects unspent transactions and builds an unsigned transfer transaction, which can be
+    /// signed later with `sign_planned_transfer_tx`
+    ///
+    /// # return
+    /// - `Tx`: unsigned transaction
+    /// - `Vec<TxOut>`: the outputs spent by the inputs (in the order of the inputs)
+    /// - `Coin`: the return amount of Coin
+    fn plan_transfer_tx(
+        &self,
+        unspent_transactions: UnspentTransactions,
+        outputs: Vec<TxOut>,
+        return_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<(Tx, Vec<TxOut>, Coin)>;
+
+    /// Searches for unspent transactions (sorted with highest value first) which exactly pay given
+    /// outputs and the fee of a transaction without change output, and moves them to the front,
+    /// so that `build_transfer_tx` and `plan_transfer_tx` select them
+    ///
+    /// # return
+    /// - `bool`: whether such unspent transactions are found (otherwise they're left untouched)
+    fn select_exact_match(
+        &self,
+        unspent_transactions: &mut UnspentTransactions,
+        outputs: &[TxOut],
+        attributes: &TxAttributes,
+    ) -> Result<bool>;
+
+    /// Signs and obfuscates a transfer transaction built by `plan_transfer_tx`
+    fn sign_planned_transfer_tx(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction: Tx,
+        spent_outputs: Vec<TxOut>,
+    ) -> Result<TxAux>;
+
+    /// Signs the inputs of an unsigned transfer transaction (e.g. on an air-gapped wallet)
+    /// without obfuscating it
+    fn sign_unsigned_transfer_tx(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_tx: &UnsignedTransferTx,
+    ) -> Result<TxWitness>;
+
+    /// Signs the inputs of a transfer transaction spending given outputs (`None` if the output
+    /// isn't known) which belong to the wallet, the witnesses of the other inputs are `None`
+    fn sign_transfer_inputs(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction: &Tx,
+        spent_outputs: &[Option<TxOut>],
+    ) -> Result<Vec<Option<TxInWitness>>>;
+
+    /// Verifies the witness of an unsigned transfer transaction and obfuscates the signed
+    /// transaction
+    fn finalize_unsigned_transfer_tx(
+        &self,
+        unsigned_tx: &UnsignedTransferTx,
+        witness: TxWitness,
+    ) -> Result<TxAux>;
+
     /// Obfuscates given signed transaction
     fn obfuscate(&self, signed_transaction: SignedTransaction) -> Result<TxAux>;
 
@@ -0,0 +1,122 @@
+use std::collections::BTreeSet;
+
+use chain_core::init::network::get_network_id;
+use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
+use chain_core::tx::data::attribute::TxAttributes;
+use chain_core::tx::data::MAX_ALLOWED_VIEW_KEYS;
+use client_common::{Error, ErrorKind, PublicKey, Result, SecKey};
+
+use crate::WalletClient;
+
+/// Builds attributes of transfer and withdraw transactions
+///
+/// The transaction is readable by the added view keys, which should include the view key of the
+/// sending wallet (see `wallet_view_key`), otherwise the wallet can't decrypt it later.
+#[derive(Debug, Clone)]
+pub struct AttributesBuilder {
+    network_id: u8,
+    view_keys: BTreeSet<PublicKey>,
+}
+
+impl Default for AttributesBuilder {
+    fn default() -> Self {
+        AttributesBuilder::new()
+    }
+}
+
+impl AttributesBuilder {
+    /// Creates a builder for the network chosen with `init_chain_id`
+    pub fn new() -> Self {
+        AttributesBuilder::with_network_id(get_network_id())
+    }
+
+    /// Creates a builder for given network id
+    pub fn with_network_id(network_id: u8) -> Self {
+        AttributesBuilder {
+            network_id,
+            view_keys: BTreeSet::new(),
+        }
+    }
+
+    /// Allows given view key to read the transaction
+    pub fn view_key(mut self, view_key: PublicKey) -> Self {
+        self.view_keys.insert(view_key);
+        self
+    }
+
+    /// Allows given view keys to read the transaction
+    pub fn view_keys<I: IntoIterator<Item = PublicKey>>(mut self, view_keys: I) -> Self {
+        self.view_keys.extend(view_keys);
+        self
+    }
+
+    /// Allows the view key of given wallet to read the transaction
+    pub fn wallet_view_key<W: WalletClient + ?Sized>(
+        self,
+        wallet_client: &W,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<Self> {
+        let view_key = wallet_client.view_key(name, enckey)?;
+        Ok(self.view_key(view_key))
+    }
+
+    /// Builds the attributes, fails if there are more view keys than a transaction can have
+    pub fn build(self) -> Result<TxAttributes> {
+        if self.view_keys.len() > MAX_ALLOWED_VIEW_KEYS {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Too many view keys ({}), a transaction can have at most {}",
+                    self.view_keys.len(),
+                    MAX_ALLOWED_VIEW_KEYS
+                ),
+            ));
+        }
+
+        let access_policies = self
+            .view_keys
+            .iter()
+            .map(|key| TxAccessPolicy {
+                view_key: key.into(),
+                access: TxAccess::AllData,
+            })
+            .collect();
+        Ok(TxAttributes::new_with_access(
+            self.network_id,
+            access_policies,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use client_common::PrivateKey;
+
+    fn public_key() -> PublicKey {
+        PublicKey::from(&PrivateKey::new().unwrap())
+    }
+
+    #[test]
+    fn check_attributes_builder() {
+        let view_key = public_key();
+        let attributes = AttributesBuilder::with_network_id(0xab)
+            .view_key(view_key.clone())
+            .view_keys(vec![view_key.clone(), public_key()])
+            .build()
+            .unwrap();
+        assert_eq!(0xab, attributes.chain_hex_id);
+        assert_eq!(2, attributes.allowed_view.len());
+        let raw_view_key: secp256k1::PublicKey = (&view_key).into();
+        assert!(attributes
+            .allowed_view
+            .iter()
+            .any(|policy| policy.view_key == raw_view_key));
+
+        let builder = AttributesBuilder::with_network_id(0xab)
+            .view_keys((0..=MAX_ALLOWED_VIEW_KEYS).map(|_| public_key()));
+        assert_eq!(ErrorKind::InvalidInput, builder.build().unwrap_err().kind());
+    }
+}
@@ -3,15 +3,17 @@ use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
+use chain_core::tx::data::Tx;
 use chain_core::tx::fee::FeeAlgorithm;
+use chain_core::tx::witness::{TxInWitness, TxWitness};
 use chain_core::tx::TxAux;
 use client_common::{
-    ErrorKind, PrivateKey, Result, ResultExt, SecKey, SignedTransaction, Storage, Transaction,
-    TransactionObfuscation,
+    Error, ErrorKind, PrivateKey, Result, ResultExt, SecKey, SignedTransaction, Storage,
+    Transaction, TransactionObfuscation,
 };
 
-use crate::signer::WalletSignerManager;
-use crate::transaction_builder::RawTransferTransactionBuilder;
+use crate::signer::{SignCondition, Signer, WalletSignerManager};
+use crate::transaction_builder::{RawTransferTransactionBuilder, UnsignedTransferTx};
 use crate::{SelectedUnspentTransactions, UnspentTransactions, WalletTransactionBuilder};
 use chain_core::tx::data::TxId;
 
@@ -28,6 +30,15 @@ use chain_core::tx::data::TxId;
 /// 7. Calculate `new_fees`.
 /// 8. If `new_fees > fees`, then change `fees = new_fees` and goto step 3, otherwise return signed transaction.
 ///
+/// If the selected unspent transactions exactly pay outputs and the fee of a transaction without
+/// change output (e.g. moved to the front by `select_exact_match`), that transaction is returned.
+///
+/// # Dust policy
+///
+/// With a dust threshold (see `with_dust_threshold`), outputs below it are rejected and change
+/// below it is avoided: the inputs are selected again with the threshold added to the amount, so
+/// that the change output is worth spending. The change can't be left to the fee instead, as
+/// transfers have to pay exactly the minimum fee.
 #[derive(Debug, Clone)]
 pub struct DefaultWalletTransactionBuilder<S, F, O>
 where
@@ -38,6 +49,7 @@ where
     signer_manager: WalletSignerManager<S>,
     fee_algorithm: F,
     transaction_obfuscation: O,
+    dust_threshold: Coin,
 }
 
 impl<F, S, O> DefaultWalletTransactionBuilder<S, F, O>
@@ -77,9 +89,7 @@ where
             .map(|output| output.value)
             .unwrap_or_default();
 
-        let signer =
-            self.signer_manager
-                .create_signer(name, enckey, &self.signer_manager.hw_key_service);
+        let signer = self.signer_manager.create_signer(name, enckey);
 
         raw_builder.sign_all(signer)?;
 
@@ -115,6 +125,144 @@ where
         )
     }
 
+    fn plan_transfer_tx(
+        &self,
+        unspent_transactions: UnspentTransactions,
+        outputs: Vec<TxOut>,
+        return_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<(Tx, Vec<TxOut>, Coin)> {
+        let raw_builder = self.select_and_build(
+            &unspent_transactions,
+            outputs,
+            return_address.clone(),
+            attributes,
+            1,
+        )?;
+
+        let spent_outputs = raw_builder
+            .iter_inputs()
+            .map(|witness_utxo| witness_utxo.prev_tx_out.clone())
+            .collect();
+        let return_amount = raw_builder
+            .iter_outputs()
+            .find(|&m| m.address == return_address)
+            .map(|output| output.value)
+            .unwrap_or_default();
+
+        Ok((raw_builder.to_tx(), spent_outputs, return_amount))
+    }
+
+    fn select_exact_match(
+        &self,
+        unspent_transactions: &mut UnspentTransactions,
+        outputs: &[TxOut],
+        attributes: &TxAttributes,
+    ) -> Result<bool> {
+        let output_value = sum_coins(outputs.iter().map(|output| output.value)).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Sum of output values exceeds maximum allowed amount",
+            )
+        })?;
+        // fee of a transaction without change output only depends on the number of inputs
+        let indices = unspent_transactions.branch_and_bound(|count| {
+            let fee = self
+                .build_raw_transaction(
+                    &unspent_transactions.select_first(count),
+                    outputs,
+                    ExtendedAddr::OrTree([0; 32]),
+                    Coin::zero(),
+                    attributes.clone(),
+                    1,
+                )
+                .estimate_fee()?;
+            (output_value + fee).chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Sum of output values and fee exceeds maximum allowed amount",
+                )
+            })
+        })?;
+
+        match indices {
+            Some(indices) => {
+                unspent_transactions.move_to_front(&indices);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn sign_planned_transfer_tx(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction: Tx,
+        spent_outputs: Vec<TxOut>,
+    ) -> Result<TxAux> {
+        let mut raw_builder = self.rebuild_planned(&transaction, &spent_outputs)?;
+        let signer = self.signer_manager.create_signer(name, enckey);
+        raw_builder.sign_all(signer)?;
+        raw_builder.to_tx_aux(self.transaction_obfuscation.clone())
+    }
+
+    fn sign_unsigned_transfer_tx(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_tx: &UnsignedTransferTx,
+    ) -> Result<TxWitness> {
+        unsigned_tx.verify_sighashes()?;
+        let mut raw_builder =
+            self.rebuild_planned(&unsigned_tx.transaction, &unsigned_tx.spent_outputs)?;
+        let signer = self.signer_manager.create_signer(name, enckey);
+        raw_builder.sign_all(signer)?;
+        raw_builder.witness()
+    }
+
+    fn sign_transfer_inputs(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction: &Tx,
+        spent_outputs: &[Option<TxOut>],
+    ) -> Result<Vec<Option<TxInWitness>>> {
+        let signer = self.signer_manager.create_signer(name, enckey);
+        let tx = Transaction::TransferTransaction(transaction.clone());
+        spent_outputs
+            .iter()
+            .map(|spent_output| match spent_output {
+                Some(output)
+                    if signer.schnorr_sign_condition(&output.address)?
+                        == SignCondition::SingleSignUnlock =>
+                {
+                    signer.schnorr_sign(&tx, &output.address).map(Some)
+                }
+                _ => Ok(None),
+            })
+            .collect()
+    }
+
+    fn finalize_unsigned_transfer_tx(
+        &self,
+        unsigned_tx: &UnsignedTransferTx,
+        witness: TxWitness,
+    ) -> Result<TxAux> {
+        let mut raw_builder =
+            self.rebuild_planned(&unsigned_tx.transaction, &unsigned_tx.spent_outputs)?;
+        if witness.len() != raw_builder.inputs_len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Witness doesn't match the inputs of unsigned transaction",
+            ));
+        }
+        for (i, input_witness) in witness.iter().enumerate() {
+            raw_builder.add_witness(i, input_witness.clone())?;
+        }
+        raw_builder.to_tx_aux(self.transaction_obfuscation.clone())
+    }
+
     #[inline]
     fn obfuscate(&self, signed_transaction: SignedTransaction) -> Result<TxAux> {
         self.transaction_obfuscation.encrypt(signed_transaction)
@@ -149,7 +297,41 @@ where
             signer_manager,
             fee_algorithm,
             transaction_obfuscation,
+            dust_threshold: Coin::zero(),
+        }
+    }
+
+    /// Sets the dust threshold: outputs below it are rejected and change outputs below it are
+    /// avoided (no dust policy by default)
+    #[inline]
+    pub fn with_dust_threshold(mut self, dust_threshold: Coin) -> Self {
+        self.dust_threshold = dust_threshold;
+        self
+    }
+
+    /// Rebuilds a transaction built by `plan_transfer_tx` from the outputs it spends
+    fn rebuild_planned(
+        &self,
+        transaction: &Tx,
+        spent_outputs: &[TxOut],
+    ) -> Result<RawTransferTransactionBuilder<F>> {
+        let mut raw_builder = RawTransferTransactionBuilder::new(
+            transaction.attributes.clone(),
+            self.fee_algorithm.clone(),
+        );
+        for (input, output) in transaction.inputs.iter().zip(spent_outputs.iter()) {
+            raw_builder.add_input((input.clone(), output.clone()), 1);
+        }
+        for output in transaction.outputs.iter() {
+            raw_builder.add_output(output.clone());
+        }
+        if spent_outputs.len() != transaction.inputs.len() || raw_builder.to_tx() != *transaction {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Spent outputs don't match the inputs of planned transaction",
+            ));
         }
+        Ok(raw_builder)
     }
 
     /// Create a `DummySigner` which signs a transaction with dummy values for fees calculation.
@@ -163,6 +345,18 @@ where
         // FIXME: this should be per UnspentTransactions
         threshold: u16,
     ) -> Result<RawTransferTransactionBuilder<F>> {
+        if let Some(output) = outputs
+            .iter()
+            .find(|output| output.value < self.dust_threshold)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Output of {} to {} is below the dust threshold ({})",
+                    output.value, output.address, self.dust_threshold
+                ),
+            ));
+        }
         let output_value = sum_coins(outputs.iter().map(|output| output.value)).chain(|| {
             (
                 ErrorKind::IllegalInput,
@@ -170,14 +364,39 @@ where
             )
         })?;
         let mut fees = Coin::zero();
+        // raised to the dust threshold when the change would be dust
+        let mut min_change = Coin::zero();
         let raw_tx_builder = loop {
-            let (selected_unspent_txs, change_amount) =
-                unspent_transactions.select((output_value + fees).chain(|| {
+            let amount = (output_value + fees)
+                .and_then(|amount| amount + min_change)
+                .chain(|| {
                     (
                         ErrorKind::IllegalInput,
                         "Sum of output values and fee exceeds maximum allowed amount",
                     )
-                })?)?;
+                })?;
+            let (selected_unspent_txs, excess_amount) =
+                unspent_transactions.select(amount).map_err(|err| {
+                    if min_change == Coin::zero() {
+                        err
+                    } else {
+                        Error::new(
+                            err.kind(),
+                            format!(
+                                "Change would be below the dust threshold ({}) and the balance \
+                                 is insufficient to raise it, send a smaller amount or the exact \
+                                 balance instead",
+                                self.dust_threshold
+                            ),
+                        )
+                    }
+                })?;
+            let change_amount = (excess_amount + min_change).chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Change amount exceeds maximum allowed amount",
+                )
+            })?;
             let raw_tx_builder = self.build_raw_transaction(
                 &selected_unspent_txs,
                 &outputs,
@@ -187,9 +406,26 @@ where
                 threshold,
             );
 
+            if change_amount != Coin::zero() {
+                // the change may exactly pay the fee of a transaction without change output
+                let exact_tx_builder = self.build_raw_transaction(
+                    &selected_unspent_txs,
+                    &outputs,
+                    return_address.clone(),
+                    Coin::zero(),
+                    attributes.clone(),
+                    threshold,
+                );
+                if (fees + change_amount).ok() == Some(exact_tx_builder.estimate_fee()?) {
+                    break exact_tx_builder;
+                }
+            }
+
             let new_fees = raw_tx_builder.estimate_fee()?;
             if new_fees > fees {
                 fees = new_fees;
+            } else if change_amount != Coin::zero() && change_amount < self.dust_threshold {
+                min_change = self.dust_threshold;
             } else {
                 break raw_tx_builder;
             }
@@ -238,6 +474,7 @@ mod default_wallet_transaction_builder_tests {
     use chain_tx_validation::witness::verify_tx_address;
     use client_common::storage::MemoryStorage;
     use client_common::Transaction;
+    use std::str::FromStr;
 
     use crate::hd_wallet::HardwareKind;
     use crate::service::HwKeyService;
@@ -267,7 +504,7 @@ mod default_wallet_transaction_builder_tests {
                         inputs: tx.inputs.clone(),
                         no_of_outputs: tx.outputs.len() as TxoSize,
                         payload: TxObfuscated {
-                            txid: [0; 32],
+                            txid: tx.id(),
                             key_from: BlockHeight::genesis(),
                             init_vector: [0u8; 12],
                             txpayload,
@@ -419,6 +656,93 @@ mod default_wallet_transaction_builder_tests {
         }
     }
 
+    #[test]
+    fn check_unsigned_transaction_flow() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        // air-gapped wallet holding the keys
+        let storage = MemoryStorage::default();
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = wallet_client
+            .new_wallet(
+                name,
+                &passphrase,
+                WalletKind::Basic,
+                HardwareKind::LocalOnly,
+                None,
+            )
+            .unwrap();
+        let address = wallet_client.new_transfer_address(name, &enckey).unwrap();
+        let return_address = wallet_client.new_transfer_address(name, &enckey).unwrap();
+
+        let fee_algorithm =
+            LinearFee::new(Milli::try_new(1, 1).unwrap(), Milli::try_new(1, 1).unwrap());
+        let offline_builder = DefaultWalletTransactionBuilder::new(
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+        // online wallet without the keys
+        let online_builder = DefaultWalletTransactionBuilder::new(
+            WalletSignerManager::new(MemoryStorage::default(), HwKeyService::default()),
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        let unspent_transactions = UnspentTransactions::new(vec![
+            (
+                TxoPointer::new([0; 32], 0),
+                TxOut::new(address.clone(), Coin::new(5000).unwrap()),
+            ),
+            (
+                TxoPointer::new([1; 32], 0),
+                TxOut::new(address, Coin::new(10000).unwrap()),
+            ),
+        ]);
+        let outputs = vec![TxOut::new(
+            return_address.clone(),
+            Coin::new(12000).unwrap(),
+        )];
+        let (transaction, spent_outputs, return_amount) = online_builder
+            .plan_transfer_tx(
+                unspent_transactions,
+                outputs,
+                return_address,
+                TxAttributes::new(171),
+            )
+            .unwrap();
+        let unsigned_tx = UnsignedTransferTx::new(transaction, spent_outputs, return_amount);
+        assert_eq!(2, unsigned_tx.sighashes.len());
+
+        // transferred to the air-gapped wallet
+        let unsigned_tx = UnsignedTransferTx::from_str(&unsigned_tx.to_string()).unwrap();
+        assert!(online_builder
+            .sign_unsigned_transfer_tx(name, &enckey, &unsigned_tx)
+            .is_err());
+        let mut tampered_tx = unsigned_tx.clone();
+        tampered_tx.sighashes[1] = [0; 32];
+        assert_eq!(
+            ErrorKind::VerifyError,
+            offline_builder
+                .sign_unsigned_transfer_tx(name, &enckey, &tampered_tx)
+                .unwrap_err()
+                .kind()
+        );
+        let witness = offline_builder
+            .sign_unsigned_transfer_tx(name, &enckey, &unsigned_tx)
+            .unwrap();
+
+        // witness imported back into the online wallet
+        assert!(online_builder
+            .finalize_unsigned_transfer_tx(&unsigned_tx, TxWitness::new())
+            .is_err());
+        let tx_aux = online_builder
+            .finalize_unsigned_transfer_tx(&unsigned_tx, witness)
+            .unwrap();
+        assert_eq!(unsigned_tx.transaction.id(), tx_aux.tx_id());
+    }
+
     #[test]
     fn check_insufficient_balance_flow() {
         let name = "name";
@@ -500,4 +824,68 @@ mod default_wallet_transaction_builder_tests {
                 .kind()
         );
     }
+
+    #[test]
+    fn check_dust_policy() {
+        let fee_algorithm =
+            LinearFee::new(Milli::try_new(1, 1).unwrap(), Milli::try_new(1, 1).unwrap());
+        let transaction_builder = DefaultWalletTransactionBuilder::new(
+            WalletSignerManager::new(MemoryStorage::default(), HwKeyService::default()),
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+        let dust_builder = transaction_builder
+            .clone()
+            .with_dust_threshold(Coin::new(1000).unwrap());
+
+        let address = ExtendedAddr::OrTree([0; 32]);
+        let return_address = ExtendedAddr::OrTree([1; 32]);
+        let unspent_transactions = UnspentTransactions::new(vec![
+            (
+                TxoPointer::new([0; 32], 0),
+                TxOut::new(address.clone(), Coin::new(50000).unwrap()),
+            ),
+            (
+                TxoPointer::new([1; 32], 0),
+                TxOut::new(address.clone(), Coin::new(100_000).unwrap()),
+            ),
+        ]);
+        let plan = |builder: &DefaultWalletTransactionBuilder<_, _, _>,
+                    unspent_transactions: UnspentTransactions,
+                    amount: u64| {
+            builder.plan_transfer_tx(
+                unspent_transactions,
+                vec![TxOut::new(address.clone(), Coin::new(amount).unwrap())],
+                return_address.clone(),
+                TxAttributes::new(171),
+            )
+        };
+
+        let error = plan(&dust_builder, unspent_transactions.clone(), 999).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+
+        // the fee of a transaction with one input and change output
+        let (transaction, _, return_amount) =
+            plan(&transaction_builder, unspent_transactions.clone(), 40000).unwrap();
+        assert_eq!(1, transaction.inputs.len());
+        let fee = 10000 - u64::from(return_amount);
+
+        // without dust policy, a tiny change output is created
+        let amount = 50000 - fee - 5;
+        let (_, _, return_amount) =
+            plan(&transaction_builder, unspent_transactions.clone(), amount).unwrap();
+        assert_eq!(Coin::new(5).unwrap(), return_amount);
+
+        // another input is selected to avoid it
+        let (transaction, spent_outputs, return_amount) =
+            plan(&dust_builder, unspent_transactions.clone(), amount).unwrap();
+        assert_eq!(2, spent_outputs.len());
+        assert_eq!(2, transaction.outputs.len());
+        assert!(return_amount >= Coin::new(1000).unwrap());
+
+        // unless there isn't any
+        let unspent_transactions =
+            UnspentTransactions::new(vec![unspent_transactions.unwrap().remove(0)]);
+        assert!(plan(&dust_builder, unspent_transactions, amount).is_err());
+    }
 }
@@ -7,6 +7,7 @@ use std::string::ToString;
 use parity_scale_codec::{Decode, Encode};
 
 use chain_core::init::coin::{sum_coins, Coin};
+use chain_core::tx::canonical::{is_canonical_input_order, sort_inputs};
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
@@ -124,6 +125,177 @@ impl FromStr for SignedTransferTransaction {
     }
 }
 
+/// Transfer transaction with the inputs already selected by an online (e.g. watch-only) wallet,
+/// to be signed by the air-gapped wallet holding the keys. The witness is imported back into the
+/// online wallet, which obfuscates and broadcasts the transaction.
+#[derive(Debug, Clone, PartialEq, Decode, Encode)]
+pub struct UnsignedTransferTx {
+    /// unsigned transaction (the outputs include the change)
+    pub transaction: Tx,
+    /// outputs spent by the inputs (in the order of the inputs)
+    pub spent_outputs: Vec<TxOut>,
+    /// message signed for each input (in the order of the inputs)
+    pub sighashes: Vec<TxId>,
+    /// the return amount of coin
+    pub return_amount: Coin,
+}
+
+impl UnsignedTransferTx {
+    /// Creates a new instance of `UnsignedTransferTx`
+    pub fn new(transaction: Tx, spent_outputs: Vec<TxOut>, return_amount: Coin) -> Self {
+        // inputs are signed over the transaction id
+        let sighashes = vec![transaction.id(); transaction.inputs.len()];
+        UnsignedTransferTx {
+            transaction,
+            spent_outputs,
+            sighashes,
+            return_amount,
+        }
+    }
+
+    /// Verifies that the sighashes are the ones of the transaction, so that the signer signs the
+    /// transaction it's shown
+    pub fn verify_sighashes(&self) -> Result<()> {
+        let txid = self.transaction.id();
+        if self.sighashes.len() != self.transaction.inputs.len()
+            || self.sighashes.iter().any(|sighash| *sighash != txid)
+        {
+            return Err(Error::new(
+                ErrorKind::VerifyError,
+                "Sighashes don't match the unsigned transaction",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl ToString for UnsignedTransferTx {
+    fn to_string(&self) -> String {
+        let raw_data = self.encode();
+        base64::encode(&raw_data)
+    }
+}
+
+impl FromStr for UnsignedTransferTx {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let raw_data = base64::decode(s).chain(|| (ErrorKind::DecryptionError, "decode error"))?;
+        let tx = Self::decode(&mut raw_data.as_slice())
+            .chain(|| (ErrorKind::DecryptionError, "decode error"))?;
+        Ok(tx)
+    }
+}
+
+/// Transfer transaction built by an external service and signed step by step by the wallets
+/// holding the keys of its inputs, their signatures are combined before it's broadcast. Each
+/// signing wallet adds the outputs spent by its inputs with their witnesses.
+#[derive(Debug, Clone, PartialEq, Decode, Encode)]
+pub struct PartiallySignedTransferTx {
+    /// unsigned transaction
+    pub transaction: Tx,
+    /// output spent by each input and its witness, if already signed
+    pub signed_inputs: Vec<Option<(TxOut, TxInWitness)>>,
+}
+
+impl PartiallySignedTransferTx {
+    /// Creates a new instance of `PartiallySignedTransferTx` without signatures
+    pub fn new(transaction: Tx) -> Self {
+        let signed_inputs = vec![None; transaction.inputs.len()];
+        PartiallySignedTransferTx {
+            transaction,
+            signed_inputs,
+        }
+    }
+
+    /// Adds the signed input, the witness is verified against the spent output
+    pub fn add_signed_input(
+        &mut self,
+        index: usize,
+        spent_output: TxOut,
+        witness: TxInWitness,
+    ) -> Result<()> {
+        if index >= self.signed_inputs.len() {
+            return Err(Error::new(ErrorKind::InvalidInput, "Invalid input index"));
+        }
+        verify_tx_address(&witness, &self.transaction.id(), &spent_output.address).map_err(
+            |err| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Incorrect signature of input {}: {}", index, err),
+                )
+            },
+        )?;
+        self.signed_inputs[index] = Some((spent_output, witness));
+        Ok(())
+    }
+
+    /// Adds the signed inputs of another signer of the same transaction
+    pub fn combine(&mut self, other: PartiallySignedTransferTx) -> Result<()> {
+        if self.transaction != other.transaction
+            || self.signed_inputs.len() != other.signed_inputs.len()
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Partially signed transactions don't have the same transaction",
+            ));
+        }
+        for (index, signed_input) in other.signed_inputs.into_iter().enumerate() {
+            if let Some((spent_output, witness)) = signed_input {
+                if self.signed_inputs[index].is_none() {
+                    self.add_signed_input(index, spent_output, witness)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if all the inputs are signed
+    pub fn is_completed(&self) -> bool {
+        self.signed_inputs.iter().all(Option::is_some)
+    }
+
+    /// Returns the unsigned transaction and the witness of the completely signed transaction
+    pub fn to_unsigned_with_witness(&self) -> Result<(UnsignedTransferTx, TxWitness)> {
+        let (spent_outputs, witness): (Vec<TxOut>, Vec<TxInWitness>) = self
+            .signed_inputs
+            .iter()
+            .cloned()
+            .collect::<Option<Vec<_>>>()
+            .chain(|| (ErrorKind::InvalidInput, "Missing signature in inputs"))?
+            .into_iter()
+            .unzip();
+        // the change of the transaction isn't known, it's only tracked once committed
+        let unsigned_tx =
+            UnsignedTransferTx::new(self.transaction.clone(), spent_outputs, Coin::zero());
+        Ok((unsigned_tx, TxWitness::from(witness)))
+    }
+}
+
+impl ToString for PartiallySignedTransferTx {
+    fn to_string(&self) -> String {
+        let raw_data = self.encode();
+        base64::encode(&raw_data)
+    }
+}
+
+impl FromStr for PartiallySignedTransferTx {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let raw_data = base64::decode(s).chain(|| (ErrorKind::DecryptionError, "decode error"))?;
+        let tx = Self::decode(&mut raw_data.as_slice())
+            .chain(|| (ErrorKind::DecryptionError, "decode error"))?;
+        if tx.signed_inputs.len() != tx.transaction.inputs.len() {
+            return Err(Error::new(
+                ErrorKind::DecryptionError,
+                "Signed inputs don't match the inputs of the transaction",
+            ));
+        }
+        Ok(tx)
+    }
+}
+
 /// Raw transfer transaction data structure
 #[derive(Debug, Clone, Decode, Encode)]
 pub struct RawTransferTransaction {
@@ -170,9 +342,10 @@ where
         self.raw_transaction.outputs.iter()
     }
 
-    /// Append output to raw transaction
+    /// Add input to raw transaction, inputs are kept in canonical order
+    /// (`chain_core::tx::canonical`), so input indexes can shift
     /// # Warning
-    /// When a new input is appended, any previous witness will be cleared
+    /// When a new input is added, any previous witness will be cleared
     /// because transaction id will be changed
     pub fn add_input(&mut self, input: (TxoPointer, TxOut), threshold: u16) {
         self.raw_transaction.inputs.push(WitnessedUTxO {
@@ -181,6 +354,9 @@ where
             threshold,
             witness: None,
         });
+        sort_inputs(&mut self.raw_transaction.inputs, |input| {
+            &input.prev_txo_pointer
+        });
 
         self.clear_witness();
     }
@@ -378,14 +554,32 @@ where
         self.verify()?;
 
         let tx = self.to_tx();
-        let witness_vec: Vec<TxInWitness> = self
-            .iter_inputs()
-            .map(|input| input.witness.clone().unwrap())
-            .collect();
-        let witness = TxWitness::from(witness_vec);
+        let witness = self.witness()?;
+        // the id doesn't depend on witnesses, so every (re-)obfuscation of the signed transaction
+        // has to keep the id known before signing
+        let txid = tx.id();
         let signed_transaction = SignedTransaction::TransferTransaction(tx, witness);
 
-        transaction_obfuscation.encrypt(signed_transaction)
+        let tx_aux = transaction_obfuscation.encrypt(signed_transaction)?;
+        if tx_aux.tx_id() != txid {
+            return Err(Error::new(
+                ErrorKind::VerifyError,
+                "Transaction id of obfuscated transaction doesn't match the transaction",
+            ));
+        }
+        Ok(tx_aux)
+    }
+
+    /// Returns the witness of the inputs
+    /// # Error
+    /// Returns error when transaction is incompleted
+    pub fn witness(&self) -> Result<TxWitness> {
+        let witness_vec = self
+            .iter_inputs()
+            .map(|input| input.witness.clone())
+            .collect::<Option<Vec<TxInWitness>>>()
+            .chain(|| (ErrorKind::InvalidInput, "Missing signature in inputs"))?;
+        Ok(TxWitness::from(witness_vec))
     }
 
     /// Verify the raw transaction is valid
@@ -424,6 +618,12 @@ where
                 format!("Failed to validate transaction inputs: {}", e),
             )
         })?;
+        if !is_canonical_input_order(&inputs) {
+            return Err(Error::new(
+                ErrorKind::VerifyError,
+                "Failed to validate transaction inputs: inputs are not in canonical order",
+            ));
+        }
 
         Ok(())
     }
@@ -507,7 +707,8 @@ where
         self.iter_inputs().all(|input| input.has_witness())
     }
 
-    fn to_tx(&self) -> Tx {
+    /// Returns the (unsigned) transfer transaction
+    pub fn to_tx(&self) -> Tx {
         Tx {
             inputs: self
                 .iter_inputs()
@@ -737,6 +938,40 @@ mod raw_transfer_transaction_builder_tests {
             assert_eq!(builder.input_at_index(0).unwrap().prev_tx_out, input.1);
         }
 
+        #[test]
+        fn should_keep_inputs_in_canonical_order() {
+            let inputs = vec![
+                (
+                    TxoPointer::new([2; 32], 0),
+                    TxOut::new(ExtendedAddr::OrTree(random()), Coin::new(100).unwrap()),
+                ),
+                (
+                    TxoPointer::new([1; 32], 1),
+                    TxOut::new(ExtendedAddr::OrTree(random()), Coin::new(200).unwrap()),
+                ),
+            ];
+            let build = |inputs: Vec<(TxoPointer, TxOut)>| {
+                let mut builder = RawTransferTransactionBuilder::new(
+                    TxAttributes::default(),
+                    create_testing_fee_algorithm(),
+                );
+                for input in inputs {
+                    builder.add_input(input, 1);
+                }
+                builder
+            };
+
+            let builder = build(inputs.clone());
+            assert_eq!(
+                builder.input_at_index(0).unwrap().prev_txo_pointer,
+                inputs[1].0
+            );
+            assert_eq!(
+                builder.tx_id(),
+                build(inputs.into_iter().rev().collect()).tx_id()
+            );
+        }
+
         #[test]
         fn should_clear_existing_witness() {
             let (private_key, public_key, transfer_addr) = create_key_pair_and_transfer_addr();
@@ -1157,6 +1392,73 @@ mod raw_transfer_transaction_builder_tests {
         }
     }
 
+    mod to_tx_aux {
+        use super::*;
+
+        #[derive(Debug, Clone)]
+        struct ZeroTxIdTransactionCipher;
+
+        impl TransactionObfuscation for ZeroTxIdTransactionCipher {
+            fn decrypt(
+                &self,
+                _transaction_ids: &[TxId],
+                _private_key: &PrivateKey,
+            ) -> Result<Vec<Transaction>> {
+                unreachable!()
+            }
+
+            fn encrypt(&self, transaction: SignedTransaction) -> Result<TxAux> {
+                let tx_aux = MockTransactionCipher.encrypt(transaction)?;
+                match tx_aux {
+                    TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                        inputs,
+                        no_of_outputs,
+                        mut payload,
+                    }) => {
+                        payload.txid = [0; 32];
+                        Ok(TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                            inputs,
+                            no_of_outputs,
+                            payload,
+                        }))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+
+        fn create_signed_testing_raw_transaction_builder(
+        ) -> RawTransferTransactionBuilder<LinearFee> {
+            let (private_key, public_key, transfer_addr) = create_key_pair_and_transfer_addr();
+            let mut builder = create_2in2out_testing_raw_transaction_builder(transfer_addr);
+            let witness =
+                create_public_key_witness(private_key, public_key, &builder.to_transaction());
+            builder
+                .add_witness(0, witness.clone())
+                .expect("should add witness to input index");
+            builder
+                .add_witness(1, witness)
+                .expect("should add witness to input index");
+            builder
+        }
+
+        #[test]
+        fn should_keep_transaction_id_known_before_signing() {
+            let builder = create_signed_testing_raw_transaction_builder();
+            let tx_aux = builder.to_tx_aux(MockTransactionCipher).unwrap();
+
+            assert_eq!(tx_aux.tx_id(), builder.tx_id());
+        }
+
+        #[test]
+        fn should_return_error_when_obfuscated_transaction_id_does_not_match() {
+            let builder = create_signed_testing_raw_transaction_builder();
+
+            let err = builder.to_tx_aux(ZeroTxIdTransactionCipher).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::VerifyError);
+        }
+    }
+
     #[test]
     fn test_to_incomplete_from_incomplete_flow() {
         let (private_key, public_key, transfer_addr) = create_key_pair_and_transfer_addr();
@@ -1197,6 +1499,65 @@ mod raw_transfer_transaction_builder_tests {
         assert_eq!(restored_raw_transaction_builder.is_completed(), false);
     }
 
+    #[test]
+    fn check_partially_signed_transfer_tx_combine() {
+        let (private_key_1, public_key_1, transfer_addr_1) = create_key_pair_and_transfer_addr();
+        let (private_key_2, public_key_2, transfer_addr_2) = create_key_pair_and_transfer_addr();
+        let spent_output_1 = TxOut::new(transfer_addr_1, Coin::new(501).unwrap());
+        let spent_output_2 = TxOut::new(transfer_addr_2, Coin::new(500).unwrap());
+        let mut inputs = vec![TxoPointer::new(random(), 0), TxoPointer::new(random(), 0)];
+        sort_inputs(&mut inputs, |input| input);
+        let tx = Tx {
+            inputs,
+            outputs: vec![TxOut::new(
+                ExtendedAddr::OrTree(random()),
+                Coin::new(250).unwrap(),
+            )],
+            attributes: TxAttributes::default(),
+        };
+        let transaction = Transaction::TransferTransaction(tx.clone());
+        let witness_1 = create_public_key_witness(private_key_1, public_key_1, &transaction);
+        let witness_2 = create_public_key_witness(private_key_2, public_key_2, &transaction);
+
+        let mut signed_1 = PartiallySignedTransferTx::new(tx.clone());
+        // signature of another address
+        assert!(signed_1
+            .add_signed_input(0, spent_output_1.clone(), witness_2.clone())
+            .is_err());
+        signed_1
+            .add_signed_input(0, spent_output_1.clone(), witness_1.clone())
+            .unwrap();
+        assert!(!signed_1.is_completed());
+        assert!(signed_1.to_unsigned_with_witness().is_err());
+
+        let mut signed_2 = PartiallySignedTransferTx::new(tx.clone());
+        signed_2
+            .add_signed_input(1, spent_output_2.clone(), witness_2.clone())
+            .unwrap();
+        let mut other_tx = tx.clone();
+        other_tx.outputs.clear();
+        assert!(signed_1
+            .clone()
+            .combine(PartiallySignedTransferTx::new(other_tx))
+            .is_err());
+
+        signed_1.combine(signed_2).unwrap();
+        assert!(signed_1.is_completed());
+        let decoded = signed_1
+            .to_string()
+            .parse::<PartiallySignedTransferTx>()
+            .unwrap();
+        assert_eq!(decoded, signed_1);
+
+        let (unsigned_tx, witness) = signed_1.to_unsigned_with_witness().unwrap();
+        assert_eq!(unsigned_tx.transaction, tx);
+        assert_eq!(
+            unsigned_tx.spent_outputs,
+            vec![spent_output_1, spent_output_2]
+        );
+        assert_eq!(witness, TxWitness::from(vec![witness_1, witness_2]));
+    }
+
     fn create_2in2out_testing_raw_transaction_builder(
         transfer_addr: ExtendedAddr,
     ) -> RawTransferTransactionBuilder<LinearFee> {
@@ -1302,7 +1663,7 @@ mod raw_transfer_transaction_builder_tests {
                     Ok(TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
                         inputs: tx.inputs.clone(),
                         no_of_outputs: tx.outputs.len() as TxoSize,
-                        payload: encrypt(&transaction.clone().into(), [0; 32]),
+                        payload: encrypt(&transaction.clone().into(), tx.id()),
                     }))
                 }
                 _ => unreachable!(),
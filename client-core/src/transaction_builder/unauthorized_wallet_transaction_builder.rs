@@ -3,9 +3,12 @@ use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
+use chain_core::tx::data::Tx;
+use chain_core::tx::witness::{TxInWitness, TxWitness};
 use chain_core::tx::TxAux;
 use client_common::{ErrorKind, PrivateKey, Result, SecKey, SignedTransaction, Transaction};
 
+use crate::transaction_builder::UnsignedTransferTx;
 use crate::{UnspentTransactions, WalletTransactionBuilder};
 use chain_core::tx::data::TxId;
 
@@ -27,6 +30,52 @@ impl WalletTransactionBuilder for UnauthorizedWalletTransactionBuilder {
         Err(ErrorKind::PermissionDenied.into())
     }
 
+    fn plan_transfer_tx(
+        &self,
+        _: UnspentTransactions,
+        _: Vec<TxOut>,
+        _: ExtendedAddr,
+        _: TxAttributes,
+    ) -> Result<(Tx, Vec<TxOut>, Coin)> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
+    fn select_exact_match(
+        &self,
+        _: &mut UnspentTransactions,
+        _: &[TxOut],
+        _: &TxAttributes,
+    ) -> Result<bool> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
+    fn sign_planned_transfer_tx(&self, _: &str, _: &SecKey, _: Tx, _: Vec<TxOut>) -> Result<TxAux> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
+    fn sign_unsigned_transfer_tx(
+        &self,
+        _: &str,
+        _: &SecKey,
+        _: &UnsignedTransferTx,
+    ) -> Result<TxWitness> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
+    fn sign_transfer_inputs(
+        &self,
+        _: &str,
+        _: &SecKey,
+        _: &Tx,
+        _: &[Option<TxOut>],
+    ) -> Result<Vec<Option<TxInWitness>>> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
+    fn finalize_unsigned_transfer_tx(&self, _: &UnsignedTransferTx, _: TxWitness) -> Result<TxAux> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
     fn obfuscate(&self, _: SignedTransaction) -> Result<TxAux> {
         Err(ErrorKind::PermissionDenied.into())
     }
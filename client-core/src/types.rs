@@ -1,13 +1,42 @@
 //! Types used in `client-core`
+mod address_metadata;
 mod address_type;
+mod alert;
+mod annotation;
+mod approval;
+mod device_sync;
+mod history_export;
+mod mempool_transaction;
+mod operator;
+mod payout_batch;
+mod reservation;
+mod transaction_reference;
+mod transfer_recipient;
 mod wallet_type;
 
 pub mod transaction_change;
 
+pub use self::address_metadata::AddressMetadata;
 pub use self::address_type::AddressType;
+pub use self::alert::{Alert, AlertRule};
+pub use self::annotation::{
+    parse_annotations, AnnotationConflict, AnnotationFormat, AnnotationImportReport, TxAnnotation,
+    INTERNAL_TRANSFER_CATEGORY,
+};
+pub use self::approval::PendingApproval;
+pub use self::device_sync::{
+    DevicePairing, DeviceRole, EncryptedWalletEvent, PairedDevice, WalletEvent,
+};
+pub use self::history_export::{export_history, HistoryExportFormat, HISTORY_EXPORT_COLUMNS};
+pub use self::mempool_transaction::MempoolTransaction;
+pub use self::operator::{ConsensusHealth, OperatorAction};
+pub use self::payout_batch::{split_payouts, PayoutBatch, PayoutRecipient};
+pub use self::reservation::BalanceReservation;
 #[doc(inline)]
 pub use self::transaction_change::{
-    BalanceChange, TransactionChange, TransactionInput, TransactionPending, TransactionType,
-    WalletBalance,
+    BalanceChange, BalanceChangeDirection, TransactionChange, TransactionHistory,
+    TransactionHistoryFilter, TransactionInput, TransactionPending, TransactionType, WalletBalance,
 };
+pub use self::transaction_reference::TransactionReference;
+pub use self::transfer_recipient::{RecipientOutput, TransferRecipient};
 pub use self::wallet_type::WalletKind;
@@ -0,0 +1,9 @@
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+/// Metadata of an address in the address book of a wallet
+#[derive(Debug, Default, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct AddressMetadata {
+    /// free form label
+    pub label: String,
+}
@@ -0,0 +1,166 @@
+//! Alert rules evaluated on wallet synchronization
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use chain_core::common::Timespec;
+use chain_core::init::coin::Coin;
+use chain_core::state::account::{NodeState, StakedState, StakedStateAddress};
+
+use crate::types::{BalanceChange, TransactionChange, WalletBalance};
+
+/// Alert rule of a wallet
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertRule {
+    /// available balance dropped below `amount`
+    BalanceBelow {
+        /// threshold of available balance
+        amount: Coin,
+    },
+    /// single outgoing transaction spent more than `amount`
+    OutgoingAbove {
+        /// threshold of outgoing value
+        amount: Coin,
+    },
+    /// council node of the staking address got jailed
+    StakingJailed {
+        /// staking address to watch
+        address: StakedStateAddress,
+    },
+}
+
+impl AlertRule {
+    /// Returns alert message if the transaction triggers the rule
+    pub fn check_transaction(&self, change: &TransactionChange) -> Option<String> {
+        match (self, &change.balance_change) {
+            (AlertRule::OutgoingAbove { amount }, BalanceChange::Outgoing { value })
+                if value > amount =>
+            {
+                Some(format!(
+                    "outgoing transaction {} spent {}, above threshold {}",
+                    hex::encode(change.transaction_id),
+                    value,
+                    amount
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns alert message if the rule condition holds on current balance
+    pub fn check_balance(&self, balance: &WalletBalance) -> Option<String> {
+        match self {
+            AlertRule::BalanceBelow { amount } if balance.available < *amount => Some(format!(
+                "available balance {} is below threshold {}",
+                balance.available, amount
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns alert message if the rule condition holds on current staked state of watched
+    /// address
+    pub fn check_staked_state(
+        &self,
+        staked_state: &StakedState,
+        block_time: Timespec,
+    ) -> Option<String> {
+        match (self, &staked_state.node_meta) {
+            (AlertRule::StakingJailed { address }, Some(NodeState::CouncilNode(validator)))
+                if *address == staked_state.address =>
+            {
+                validator
+                    .jailed_until
+                    .filter(|jailed_until| *jailed_until > block_time)
+                    .map(|jailed_until| {
+                        format!(
+                            "council node {} is jailed until block time {}",
+                            address, jailed_until
+                        )
+                    })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Alert triggered by a rule
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct Alert {
+    /// the triggered rule
+    pub rule: AlertRule,
+    /// height of the block which triggered the alert
+    pub block_height: u64,
+    /// id of the transaction which triggered the alert (hex encoded)
+    pub transaction_id: Option<String>,
+    /// human readable message
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::address::RedeemAddress;
+    use chain_core::state::account::{ConfidentialInit, CouncilNodeMeta, MLSInit, Validator};
+    use chain_core::state::tendermint::TendermintValidatorPubKey;
+    use chain_core::tx::fee::Fee;
+    use client_common::tendermint::types::Time;
+
+    use crate::types::TransactionType;
+
+    fn outgoing(value: Coin) -> TransactionChange {
+        TransactionChange {
+            transaction_id: [0; 32],
+            inputs: vec![],
+            outputs: vec![],
+            fee_paid: Fee::new(Coin::zero()),
+            balance_change: BalanceChange::Outgoing { value },
+            transaction_type: TransactionType::Transfer,
+            block_height: 1,
+            block_time: Time::now(),
+            memo: None,
+        }
+    }
+
+    #[test]
+    fn check_alert_rules() {
+        let rule = AlertRule::OutgoingAbove {
+            amount: Coin::new(100).unwrap(),
+        };
+        assert!(rule
+            .check_transaction(&outgoing(Coin::new(100).unwrap()))
+            .is_none());
+        assert!(rule
+            .check_transaction(&outgoing(Coin::new(101).unwrap()))
+            .is_some());
+
+        let rule = AlertRule::BalanceBelow {
+            amount: Coin::new(100).unwrap(),
+        };
+        let mut balance = WalletBalance {
+            total: Coin::new(100).unwrap(),
+            available: Coin::new(100).unwrap(),
+            pending: Coin::zero(),
+            reserved: Coin::zero(),
+        };
+        assert!(rule.check_balance(&balance).is_none());
+        balance.available = Coin::new(99).unwrap();
+        assert!(rule.check_balance(&balance).is_some());
+
+        let address = StakedStateAddress::BasicRedeem(RedeemAddress([1; 20]));
+        let rule = AlertRule::StakingJailed { address };
+        let mut validator = Validator::new(CouncilNodeMeta::new_with_details(
+            "node".to_owned(),
+            None,
+            TendermintValidatorPubKey::Ed25519([1u8; 32]),
+            ConfidentialInit {
+                init_payload: MLSInit::Genesis(vec![]),
+            },
+        ));
+        validator.jailed_until = Some(10);
+        let mut state = StakedState::default(address);
+        state.node_meta = Some(NodeState::CouncilNode(validator));
+        assert!(rule.check_staked_state(&state, 9).is_some());
+        assert!(rule.check_staked_state(&state, 10).is_none());
+    }
+}
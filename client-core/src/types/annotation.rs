@@ -0,0 +1,310 @@
+//! Annotations of wallet transactions and their import from CSV/JSON files
+use std::str::FromStr;
+
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use chain_core::tx::data::TxId;
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+/// Category of transactions between two local wallets (see `WalletClient::internal_transfer`)
+pub const INTERNAL_TRANSFER_CATEGORY: &str = "internal_transfer";
+
+/// Annotation of a wallet transaction, empty fields are not set
+#[derive(Debug, Default, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct TxAnnotation {
+    /// free form label
+    #[serde(default)]
+    pub label: String,
+    /// category used for bookkeeping
+    #[serde(default)]
+    pub category: String,
+    /// name of the other party of the transaction
+    #[serde(default)]
+    pub counterparty: String,
+}
+
+impl TxAnnotation {
+    /// Returns true if none of the fields are set
+    pub fn is_empty(&self) -> bool {
+        self.label.is_empty() && self.category.is_empty() && self.counterparty.is_empty()
+    }
+
+    /// Resolves a conflict between this (existing) annotation and an imported one
+    pub fn resolve(&self, imported: TxAnnotation, conflict: AnnotationConflict) -> TxAnnotation {
+        let pick = |existing: &String, imported: String| {
+            if existing.is_empty() {
+                imported
+            } else {
+                existing.clone()
+            }
+        };
+        match conflict {
+            AnnotationConflict::KeepExisting => self.clone(),
+            AnnotationConflict::Overwrite => imported,
+            AnnotationConflict::Merge => TxAnnotation {
+                label: pick(&self.label, imported.label),
+                category: pick(&self.category, imported.category),
+                counterparty: pick(&self.counterparty, imported.counterparty),
+            },
+        }
+    }
+}
+
+/// How an imported annotation is combined with an existing annotation of the same transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationConflict {
+    /// keep the existing annotation, ignore the imported one
+    KeepExisting,
+    /// replace the existing annotation with the imported one
+    Overwrite,
+    /// keep the existing fields and fill the empty ones from the imported annotation
+    Merge,
+}
+
+impl FromStr for AnnotationConflict {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "keep_existing" => Ok(AnnotationConflict::KeepExisting),
+            "overwrite" => Ok(AnnotationConflict::Overwrite),
+            "merge" => Ok(AnnotationConflict::Merge),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown annotation conflict resolution: {}", s),
+            )),
+        }
+    }
+}
+
+/// Format of an annotation file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationFormat {
+    /// comma separated values with a header row naming the columns `txid`, `label`, `category`
+    /// and `counterparty` (in any order, missing columns are left empty)
+    Csv,
+    /// array of objects with a `txid` and the fields of `TxAnnotation`
+    Json,
+}
+
+impl FromStr for AnnotationFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(AnnotationFormat::Csv),
+            "json" => Ok(AnnotationFormat::Json),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown annotation file format: {}", s),
+            )),
+        }
+    }
+}
+
+/// Result of an annotation import
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnnotationImportReport {
+    /// number of transactions annotated for the first time
+    pub added: usize,
+    /// number of transactions whose annotation changed
+    pub updated: usize,
+    /// number of transactions whose annotation stayed the same
+    pub unchanged: usize,
+    /// imported transaction ids which are not in the wallet history (hex encoded)
+    pub unknown_transactions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonAnnotation {
+    txid: String,
+    #[serde(flatten)]
+    annotation: TxAnnotation,
+}
+
+/// Parses an annotation file, rows are returned in file order
+pub fn parse_annotations(
+    format: AnnotationFormat,
+    content: &str,
+) -> Result<Vec<(TxId, TxAnnotation)>> {
+    match format {
+        AnnotationFormat::Csv => parse_csv_annotations(content),
+        AnnotationFormat::Json => {
+            let rows: Vec<JsonAnnotation> = serde_json::from_str(content)
+                .chain(|| (ErrorKind::DeserializationError, "Invalid annotation file"))?;
+            rows.into_iter()
+                .map(|row| Ok((parse_txid(&row.txid)?, row.annotation)))
+                .collect()
+        }
+    }
+}
+
+fn parse_txid(txid: &str) -> Result<TxId> {
+    let bytes = hex::decode(txid.trim()).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            format!("Invalid transaction id: {}", txid),
+        )
+    })?;
+    if bytes.len() != 32 {
+        return Err(Error::new(
+            ErrorKind::DeserializationError,
+            format!("Invalid transaction id: {}", txid),
+        ));
+    }
+    let mut id = TxId::default();
+    id.copy_from_slice(&bytes);
+    Ok(id)
+}
+
+fn parse_csv_annotations(content: &str) -> Result<Vec<(TxId, TxAnnotation)>> {
+    let mut records = parse_csv(content)?.into_iter();
+    let header = records.next().unwrap_or_default();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|field| field.trim().eq_ignore_ascii_case(name))
+    };
+    let txid_column = column("txid").chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Annotation file has no txid column",
+        )
+    })?;
+    let (label, category, counterparty) =
+        (column("label"), column("category"), column("counterparty"));
+
+    records
+        .filter(|record| record.iter().any(|field| !field.trim().is_empty()))
+        .map(|record| {
+            let field = |index: Option<usize>| {
+                index
+                    .and_then(|index| record.get(index))
+                    .map(|field| field.trim().to_owned())
+                    .unwrap_or_default()
+            };
+            let annotation = TxAnnotation {
+                label: field(label),
+                category: field(category),
+                counterparty: field(counterparty),
+            };
+            Ok((parse_txid(&field(Some(txid_column)))?, annotation))
+        })
+        .collect()
+}
+
+/// Splits CSV content into records of fields, fields may be quoted with `"` (doubled inside)
+fn parse_csv(content: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => record.push(std::mem::replace(&mut field, String::new())),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::replace(&mut field, String::new()));
+                records.push(std::mem::replace(&mut record, Vec::new()));
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(Error::new(
+            ErrorKind::DeserializationError,
+            "Unterminated quoted field in annotation file",
+        ));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_parse_annotations() {
+        let txid = [1u8; 32];
+        let csv = format!(
+            "\u{feff}Category,TxId,Label,Notes\r\n\
+             payroll,{},\"Salary, \"\"March\"\"\",ignored\r\n\
+             ,,,\r\n",
+            hex::encode(txid)
+        );
+        let expected = vec![(
+            txid,
+            TxAnnotation {
+                label: "Salary, \"March\"".to_owned(),
+                category: "payroll".to_owned(),
+                counterparty: "".to_owned(),
+            },
+        )];
+        assert_eq!(
+            expected,
+            parse_annotations(AnnotationFormat::Csv, &csv).unwrap()
+        );
+
+        let json = format!(
+            r#"[{{"txid": "{}", "label": "Salary, \"March\"", "category": "payroll"}}]"#,
+            hex::encode(txid)
+        );
+        assert_eq!(
+            expected,
+            parse_annotations(AnnotationFormat::Json, &json).unwrap()
+        );
+
+        assert!(parse_annotations(AnnotationFormat::Csv, "label\nfoo\n").is_err());
+        assert!(parse_annotations(AnnotationFormat::Csv, "txid\n0102\n").is_err());
+        assert!(parse_annotations(AnnotationFormat::Csv, "txid,label\n\"01,foo\n").is_err());
+    }
+
+    #[test]
+    fn check_resolve_conflict() {
+        let existing = TxAnnotation {
+            label: "rent".to_owned(),
+            category: "".to_owned(),
+            counterparty: "landlord".to_owned(),
+        };
+        let imported = TxAnnotation {
+            label: "office rent".to_owned(),
+            category: "expenses".to_owned(),
+            counterparty: "".to_owned(),
+        };
+
+        assert_eq!(
+            existing,
+            existing.resolve(imported.clone(), AnnotationConflict::KeepExisting)
+        );
+        assert_eq!(
+            imported,
+            existing.resolve(imported.clone(), AnnotationConflict::Overwrite)
+        );
+        assert_eq!(
+            TxAnnotation {
+                label: "rent".to_owned(),
+                category: "expenses".to_owned(),
+                counterparty: "landlord".to_owned(),
+            },
+            existing.resolve(imported, AnnotationConflict::Merge)
+        );
+    }
+}
@@ -0,0 +1,22 @@
+//! Transfers of wallets requiring approval of spends
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use chain_core::init::coin::Coin;
+use chain_core::tx::data::output::TxOut;
+use chain_core::tx::data::Tx;
+
+/// Transfer waiting for approval, it's signed, obfuscated and broadcasted once approved
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct PendingApproval {
+    /// id of the transaction (hex encoded)
+    pub transaction_id: String,
+    /// unsigned transaction
+    pub transaction: Tx,
+    /// outputs spent by the inputs of the transaction (in the order of the inputs)
+    pub spent_outputs: Vec<TxOut>,
+    /// the return amount of coin
+    pub return_amount: Coin,
+    /// block height at which the transfer was requested
+    pub block_height: u64,
+}
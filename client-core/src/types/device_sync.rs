@@ -0,0 +1,104 @@
+//! Wallet sync between devices of the same owner
+//!
+//! Two devices exchange their `DevicePairing` (e.g. shown and scanned as QR codes), which gives
+//! them a shared key. The primary device then records wallet events for each paired secondary
+//! device, which fetches them encrypted with the shared key and applies them to its copy of the
+//! wallet.
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use chain_core::tx::data::TxId;
+use client_common::PublicKey;
+
+use crate::service::SyncState;
+use crate::types::{TransactionPending, TxAnnotation};
+
+/// Role of a paired device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceRole {
+    /// Device whose wallet events are applied by this device
+    Primary,
+    /// Device which applies the wallet events of this device
+    Secondary,
+}
+
+/// Pairing data of a device, exchanged with the other device (e.g. as QR code)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DevicePairing {
+    /// Name of the device
+    pub device_name: String,
+    /// Public key of the device
+    pub public_key: PublicKey,
+}
+
+/// Device paired with a wallet
+#[derive(Debug, Clone, PartialEq, Encode, Decode, Serialize, Deserialize)]
+pub struct PairedDevice {
+    /// Name of the device
+    pub name: String,
+    /// Public key of the device
+    pub public_key: PublicKey,
+    /// Role of the device
+    pub role: DeviceRole,
+    /// Sequence number of the last event recorded for a secondary device, or imported from a
+    /// primary device
+    pub last_sequence: u64,
+    /// Last block height synced by a primary device (from its imported events)
+    pub synced_height: Option<u64>,
+}
+
+/// Change of a wallet on the primary device, applied by secondary devices
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum WalletEvent {
+    /// New transfer address
+    TransferKey {
+        /// public key of the address
+        public_key: PublicKey,
+    },
+    /// New staking address
+    StakingKey {
+        /// public key of the address
+        public_key: PublicKey,
+    },
+    /// Annotation (label) of a transaction set, an empty annotation removes it
+    Annotation {
+        /// id of the transaction
+        transaction_id: TxId,
+        /// annotation of the transaction
+        annotation: TxAnnotation,
+    },
+    /// Transaction broadcasted and not committed yet
+    PendingTransaction {
+        /// id of the transaction
+        transaction_id: TxId,
+        /// inputs spent by the transaction
+        pending: TransactionPending,
+    },
+    /// Blocks synced (only the latest checkpoint is kept for devices which haven't fetched it)
+    SyncCheckpoint {
+        /// last synced block height
+        block_height: u64,
+        /// last synced block hash
+        block_hash: String,
+    },
+}
+
+impl WalletEvent {
+    /// Returns the sync checkpoint event of a sync state
+    pub fn sync_checkpoint(sync_state: &SyncState) -> Self {
+        WalletEvent::SyncCheckpoint {
+            block_height: sync_state.last_block_height,
+            block_hash: sync_state.last_block_hash.clone(),
+        }
+    }
+}
+
+/// Wallet event encrypted with the key shared by the paired devices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedWalletEvent {
+    /// Sequence number of the event, increasing for each secondary device
+    pub sequence: u64,
+    /// Encrypted event (base64)
+    pub payload: String,
+}
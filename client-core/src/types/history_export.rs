@@ -0,0 +1,233 @@
+//! Export of wallet transaction history for accounting tools
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+use crate::types::{BalanceChangeDirection, TransactionChange};
+
+/// Columns of an exported history, in CSV column order
+pub const HISTORY_EXPORT_COLUMNS: [&str; 10] = [
+    "timestamp",
+    "txid",
+    "direction",
+    "amount",
+    "fee",
+    "transaction_type",
+    "block_height",
+    "input_addresses",
+    "output_addresses",
+    "memo",
+];
+
+/// Format of an exported history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryExportFormat {
+    /// comma separated values with a header row, addresses are separated by `;`
+    Csv,
+    /// one JSON object per line
+    JsonLines,
+}
+
+impl FromStr for HistoryExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(HistoryExportFormat::Csv),
+            "json_lines" | "jsonl" => Ok(HistoryExportFormat::JsonLines),
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Unknown history export format: {}", s),
+            )),
+        }
+    }
+}
+
+/// A transaction of an exported history, amounts are in CRO (8 decimals)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct HistoryExportRecord {
+    timestamp: String,
+    txid: String,
+    direction: Option<BalanceChangeDirection>,
+    amount: String,
+    fee: String,
+    transaction_type: String,
+    block_height: u64,
+    input_addresses: Vec<String>,
+    output_addresses: Vec<String>,
+    memo: Option<String>,
+}
+
+impl From<&TransactionChange> for HistoryExportRecord {
+    fn from(change: &TransactionChange) -> Self {
+        let (direction, amount) = match change.balance_change.direction() {
+            Some((direction, amount)) => (Some(direction), amount.to_string()),
+            None => (None, "0.00000000".to_owned()),
+        };
+        HistoryExportRecord {
+            timestamp: change.block_time.to_rfc3339(),
+            txid: hex::encode(change.transaction_id),
+            direction,
+            amount,
+            fee: change.fee_paid.to_coin().to_string(),
+            transaction_type: change.transaction_type.to_string(),
+            block_height: change.block_height,
+            input_addresses: change
+                .inputs
+                .iter()
+                .filter_map(|input| input.output.as_ref())
+                .map(|output| output.address.to_string())
+                .collect(),
+            output_addresses: change
+                .outputs
+                .iter()
+                .map(|output| output.address.to_string())
+                .collect(),
+            memo: change.memo.clone(),
+        }
+    }
+}
+
+impl HistoryExportRecord {
+    fn csv_fields(&self) -> Vec<String> {
+        let direction = match self.direction {
+            Some(BalanceChangeDirection::Incoming) => "incoming",
+            Some(BalanceChangeDirection::Outgoing) => "outgoing",
+            None => "",
+        };
+        vec![
+            self.timestamp.clone(),
+            self.txid.clone(),
+            direction.to_owned(),
+            self.amount.clone(),
+            self.fee.clone(),
+            self.transaction_type.clone(),
+            self.block_height.to_string(),
+            self.input_addresses.join(";"),
+            self.output_addresses.join(";"),
+            self.memo.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// Formats transaction changes (in the given order) as an exported history
+pub fn export_history<'a, I>(format: HistoryExportFormat, changes: I) -> Result<String>
+where
+    I: IntoIterator<Item = &'a TransactionChange>,
+{
+    let records = changes.into_iter().map(HistoryExportRecord::from);
+    let mut output = String::new();
+    match format {
+        HistoryExportFormat::Csv => {
+            push_csv_record(&mut output, HISTORY_EXPORT_COLUMNS.iter());
+            for record in records {
+                push_csv_record(&mut output, record.csv_fields().iter());
+            }
+        }
+        HistoryExportFormat::JsonLines => {
+            for record in records {
+                let line = serde_json::to_string(&record)
+                    .chain(|| (ErrorKind::SerializationError, "Unable to export history"))?;
+                output.push_str(&line);
+                output.push('\n');
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Appends a CSV record, fields containing separators or quotes are quoted (`"` doubled inside)
+fn push_csv_record<I, T>(output: &mut String, fields: I)
+where
+    I: Iterator<Item = T>,
+    T: AsRef<str>,
+{
+    for (i, field) in fields.enumerate() {
+        let field = field.as_ref();
+        if i > 0 {
+            output.push(',');
+        }
+        if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+            output.push('"');
+            output.push_str(&field.replace('"', "\"\""));
+            output.push('"');
+        } else {
+            output.push_str(field);
+        }
+    }
+    output.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::coin::Coin;
+    use chain_core::tx::data::address::ExtendedAddr;
+    use chain_core::tx::data::input::TxoPointer;
+    use chain_core::tx::data::output::TxOut;
+    use chain_core::tx::fee::Fee;
+    use client_common::tendermint::types::Time;
+
+    use crate::types::{BalanceChange, TransactionInput, TransactionType};
+
+    #[test]
+    fn check_export_history() {
+        let from = ExtendedAddr::OrTree([1; 32]);
+        let to = ExtendedAddr::OrTree([2; 32]);
+        let change = TransactionChange {
+            transaction_id: [3; 32],
+            inputs: vec![TransactionInput {
+                pointer: TxoPointer::new([4; 32], 0),
+                output: Some(TxOut::new(from.clone(), Coin::new(150).unwrap())),
+            }],
+            outputs: vec![
+                TxOut::new(to.clone(), Coin::new(100).unwrap()),
+                TxOut::new(from.clone(), Coin::new(40).unwrap()),
+            ],
+            fee_paid: Fee::new(Coin::new(10).unwrap()),
+            balance_change: BalanceChange::Outgoing {
+                value: Coin::new(110).unwrap(),
+            },
+            transaction_type: TransactionType::Transfer,
+            block_height: 7,
+            block_time: Time::from_str("2019-04-09T09:38:41Z").unwrap(),
+            memo: Some("rent, \"April\"".to_owned()),
+        };
+
+        let csv = export_history(HistoryExportFormat::Csv, vec![&change]).unwrap();
+        let lines = csv.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(3, lines.len());
+        assert_eq!(HISTORY_EXPORT_COLUMNS.join(","), lines[0]);
+        assert_eq!(
+            format!(
+                "{},{},outgoing,0.00000110,0.00000010,Transfer,7,{},{};{},\"rent, \"\"April\"\"\"",
+                change.block_time.to_rfc3339(),
+                hex::encode([3; 32]),
+                from,
+                to,
+                from
+            ),
+            lines[1]
+        );
+        assert_eq!("", lines[2]);
+
+        let json = export_history(HistoryExportFormat::JsonLines, vec![&change, &change]).unwrap();
+        let lines = json.lines().collect::<Vec<_>>();
+        assert_eq!(2, lines.len());
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!("outgoing", value["direction"]);
+        assert_eq!("0.00000110", value["amount"]);
+        assert_eq!(to.to_string(), value["output_addresses"][0]);
+        assert_eq!(from.to_string(), value["input_addresses"][0]);
+        assert_eq!("rent, \"April\"", value["memo"]);
+
+        assert_eq!(
+            HistoryExportFormat::JsonLines,
+            "jsonl".parse::<HistoryExportFormat>().unwrap()
+        );
+        assert!("xml".parse::<HistoryExportFormat>().is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! Transactions in the mempool related to a wallet
+use serde::{Deserialize, Serialize};
+
+use chain_core::state::account::StakedStateAddress;
+use chain_core::tx::data::input::TxoPointer;
+use chain_core::tx::data::output::TxOut;
+use chain_core::tx::{TxAux, TxEnclaveAux, TxPublicAux};
+use chain_tx_validation::witness::verify_tx_recover_address;
+
+use crate::types::{TransactionInput, TransactionType};
+
+/// Transaction broadcasted to the network but not committed yet
+///
+/// Outputs of transfer transactions are confidential, so incoming payments can only be
+/// recognized when they come through the wallet's staking addresses (e.g. withdrawals);
+/// transfers are matched by the wallet's unspent outputs they spend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MempoolTransaction {
+    /// Transaction ID (hex encoded)
+    pub transaction_id: String,
+    /// Transaction type
+    pub transaction_type: TransactionType,
+    /// Unspent outputs of the wallet spent by the transaction
+    pub inputs: Vec<TransactionInput>,
+    /// Staking address of the wallet involved in the transaction
+    pub staking_address: Option<StakedStateAddress>,
+}
+
+impl MempoolTransaction {
+    /// Returns the mempool transaction if it's related to the wallet
+    ///
+    /// - `find_output`: returns the output if the pointer is an unspent output of the wallet
+    /// - `is_staking_address`: returns true if the staking address belongs to the wallet
+    pub fn for_wallet<F, G>(tx_aux: &TxAux, find_output: F, is_staking_address: G) -> Option<Self>
+    where
+        F: Fn(&TxoPointer) -> Option<TxOut>,
+        G: Fn(&StakedStateAddress) -> bool,
+    {
+        let own_inputs = |inputs: &[TxoPointer]| {
+            inputs
+                .iter()
+                .filter_map(|pointer| {
+                    find_output(pointer).map(|output| TransactionInput {
+                        pointer: pointer.clone(),
+                        output: Some(output),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        let own_address = |address: StakedStateAddress| {
+            if is_staking_address(&address) {
+                Some(address)
+            } else {
+                None
+            }
+        };
+
+        let (transaction_type, inputs, staking_address) = match tx_aux {
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx { inputs, .. }) => {
+                (TransactionType::Transfer, own_inputs(inputs), None)
+            }
+            TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { tx, .. }) => (
+                TransactionType::Deposit,
+                own_inputs(&tx.inputs),
+                own_address(tx.to_staked_account),
+            ),
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                witness, payload, ..
+            }) => (
+                TransactionType::Withdraw,
+                vec![],
+                verify_tx_recover_address(witness, &payload.txid)
+                    .ok()
+                    .and_then(own_address),
+            ),
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => (
+                TransactionType::Unbond,
+                vec![],
+                own_address(tx.from_staked_account),
+            ),
+            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, _)) => {
+                (TransactionType::Unjail, vec![], own_address(tx.address))
+            }
+            TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, _)) => {
+                (TransactionType::Nodejoin, vec![], own_address(tx.address))
+            }
+            // neither spends outputs nor changes a staked state
+            TxAux::PublicTx(TxPublicAux::NetworkParamsUpdateTx(..)) | TxAux::MLSHandshake(_) => {
+                return None
+            }
+        };
+
+        if inputs.is_empty() && staking_address.is_none() {
+            return None;
+        }
+        Some(MempoolTransaction {
+            transaction_id: hex::encode(tx_aux.tx_id()),
+            transaction_type,
+            inputs,
+            staking_address,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::address::RedeemAddress;
+    use chain_core::init::coin::Coin;
+    use chain_core::state::account::{StakedStateOpWitness, UnbondTx};
+    use chain_core::tx::data::address::ExtendedAddr;
+    use chain_core::tx::TxObfuscated;
+    use secp256k1::recovery::{RecoverableSignature, RecoveryId};
+
+    fn unbond_tx(address: StakedStateAddress) -> TxAux {
+        let tx = UnbondTx::new(address, 0, Coin::unit(), Default::default());
+        let witness = StakedStateOpWitness::new(
+            RecoverableSignature::from_compact(&[1; 64], RecoveryId::from_i32(0).unwrap()).unwrap(),
+        );
+        TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, witness))
+    }
+
+    fn transfer_tx(inputs: Vec<TxoPointer>) -> TxAux {
+        TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+            inputs,
+            no_of_outputs: 1,
+            payload: TxObfuscated {
+                txid: [0; 32],
+                key_from: 0.into(),
+                init_vector: [0; 12],
+                txpayload: vec![],
+            },
+        })
+    }
+
+    #[test]
+    fn check_for_wallet() {
+        let own_address = StakedStateAddress::BasicRedeem(RedeemAddress([1; 20]));
+        let other_address = StakedStateAddress::BasicRedeem(RedeemAddress([2; 20]));
+        let own_pointer = TxoPointer::new([1; 32], 0);
+        let other_pointer = TxoPointer::new([2; 32], 0);
+        let output = TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit());
+
+        let find_output = |pointer: &TxoPointer| {
+            if pointer == &own_pointer {
+                Some(output.clone())
+            } else {
+                None
+            }
+        };
+        let is_staking_address = |address: &StakedStateAddress| address == &own_address;
+
+        let tx = MempoolTransaction::for_wallet(
+            &unbond_tx(own_address),
+            find_output,
+            is_staking_address,
+        )
+        .unwrap();
+        assert_eq!(tx.transaction_type, TransactionType::Unbond);
+        assert_eq!(tx.staking_address, Some(own_address));
+        assert!(MempoolTransaction::for_wallet(
+            &unbond_tx(other_address),
+            find_output,
+            is_staking_address
+        )
+        .is_none());
+
+        let tx = MempoolTransaction::for_wallet(
+            &transfer_tx(vec![other_pointer.clone(), own_pointer.clone()]),
+            find_output,
+            is_staking_address,
+        )
+        .unwrap();
+        assert_eq!(tx.transaction_type, TransactionType::Transfer);
+        assert_eq!(
+            tx.inputs,
+            vec![TransactionInput {
+                pointer: own_pointer.clone(),
+                output: Some(output.clone()),
+            }]
+        );
+        assert!(MempoolTransaction::for_wallet(
+            &transfer_tx(vec![other_pointer]),
+            find_output,
+            is_staking_address
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,229 @@
+//! Types used by council node operators
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use unicase::eq_ascii;
+
+use chain_core::common::Timespec;
+use chain_core::init::coin::Coin;
+use chain_core::state::account::{NodeState, SlashRecord, StakedState, StakedStateAddress};
+use client_common::{Error, ErrorKind, Result};
+
+/// Consensus health of a council node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsensusHealth {
+    /// staking address of the council node
+    pub staking_address: StakedStateAddress,
+    /// is the staking address joined as a council node
+    pub is_council_node: bool,
+    /// is the council node in the active validator set
+    pub is_active: bool,
+    /// block time until which the council node is jailed
+    pub jailed_until: Option<Timespec>,
+    /// bonded amount (voting power)
+    pub bonded: Coin,
+    /// unbonded amount
+    pub unbonded: Coin,
+    /// bonded amount required to join as a council node
+    pub required_stake: Coin,
+    /// position in the validator set ordered by voting power (starting from 1)
+    pub voting_power_rank: Option<usize>,
+    /// number of validators in the validator set
+    pub validator_count: usize,
+    /// blocks not signed by the council node in the inspected window
+    pub missed_blocks: u64,
+    /// number of recent blocks inspected for missed signatures
+    pub window: u64,
+    /// last slashing of the account
+    pub last_slash: Option<SlashRecord>,
+}
+
+/// Guided actions of council node operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperatorAction {
+    /// join the validator set
+    NodeJoin,
+    /// re-join the validator set with a new consensus key
+    RotateKey,
+    /// unjail a jailed council node
+    Unjail,
+    /// deposit more stake to the bonded amount
+    TopUpBond,
+}
+
+impl FromStr for OperatorAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if eq_ascii(s, "node-join") {
+            Ok(OperatorAction::NodeJoin)
+        } else if eq_ascii(s, "rotate-key") {
+            Ok(OperatorAction::RotateKey)
+        } else if eq_ascii(s, "unjail") {
+            Ok(OperatorAction::Unjail)
+        } else if eq_ascii(s, "top-up-bond") {
+            Ok(OperatorAction::TopUpBond)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Operator action can be one of `node-join`, `rotate-key`, `unjail` or `top-up-bond`",
+            ))
+        }
+    }
+}
+
+impl OperatorAction {
+    /// Checks if the action can be performed on given staked state, returns a human readable
+    /// reason if it can't.
+    ///
+    /// `required_stake` is the bonded amount required for council nodes and `block_time` is
+    /// the time of latest block.
+    pub fn check(
+        self,
+        staked_state: &StakedState,
+        required_stake: Coin,
+        block_time: Timespec,
+    ) -> Result<()> {
+        let validator = match &staked_state.node_meta {
+            Some(NodeState::CouncilNode(validator)) => Some(validator),
+            _ => None,
+        };
+
+        let reason = match self {
+            OperatorAction::NodeJoin | OperatorAction::RotateKey
+                if staked_state.bonded < required_stake =>
+            {
+                Some(format!(
+                    "bonded amount ({}) is less than required council node stake ({}), top up bond first",
+                    staked_state.bonded, required_stake
+                ))
+            }
+            OperatorAction::NodeJoin => match validator {
+                Some(validator) if validator.is_jailed() => {
+                    Some("council node is jailed, unjail it first".to_owned())
+                }
+                Some(validator) if validator.is_active() => {
+                    Some("council node already joined the validator set".to_owned())
+                }
+                _ => None,
+            },
+            OperatorAction::RotateKey => match validator {
+                None => Some("staking address is not a council node, join first".to_owned()),
+                Some(validator) if validator.is_jailed() => {
+                    Some("council node is jailed, unjail it first".to_owned())
+                }
+                Some(validator) if validator.is_active() => Some(
+                    "consensus key can only be rotated when council node is inactive".to_owned(),
+                ),
+                _ => None,
+            },
+            OperatorAction::Unjail => match validator.and_then(|v| v.jailed_until) {
+                None => Some("council node is not jailed".to_owned()),
+                Some(jailed_until) if jailed_until > block_time => Some(format!(
+                    "council node is jailed until block time {}, current block time is {}",
+                    jailed_until, block_time
+                )),
+                _ => None,
+            },
+            OperatorAction::TopUpBond => None,
+        };
+
+        match reason {
+            None => Ok(()),
+            Some(reason) => Err(Error::new(ErrorKind::IllegalInput, reason)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_core::init::address::RedeemAddress;
+    use chain_core::state::account::{ConfidentialInit, CouncilNodeMeta, MLSInit, Validator};
+    use chain_core::state::tendermint::TendermintValidatorPubKey;
+
+    fn staked_state(bonded: Coin, validator: Option<Validator>) -> StakedState {
+        let mut state =
+            StakedState::default(StakedStateAddress::BasicRedeem(RedeemAddress([0u8; 20])));
+        state.bonded = bonded;
+        state.node_meta = validator.map(NodeState::CouncilNode);
+        state
+    }
+
+    fn validator() -> Validator {
+        Validator::new(CouncilNodeMeta::new_with_details(
+            "node".to_owned(),
+            None,
+            TendermintValidatorPubKey::Ed25519([1u8; 32]),
+            ConfidentialInit {
+                init_payload: MLSInit::Genesis(vec![]),
+            },
+        ))
+    }
+
+    #[test]
+    fn check_operator_action() {
+        let required = Coin::unit();
+        let time = 100;
+
+        let state = staked_state(Coin::zero(), None);
+        assert!(OperatorAction::NodeJoin
+            .check(&state, required, time)
+            .is_err());
+        assert!(OperatorAction::TopUpBond
+            .check(&state, required, time)
+            .is_ok());
+
+        let state = staked_state(required, None);
+        assert!(OperatorAction::NodeJoin
+            .check(&state, required, time)
+            .is_ok());
+        assert!(OperatorAction::RotateKey
+            .check(&state, required, time)
+            .is_err());
+        assert!(OperatorAction::Unjail
+            .check(&state, required, time)
+            .is_err());
+
+        let state = staked_state(required, Some(validator()));
+        assert!(OperatorAction::NodeJoin
+            .check(&state, required, time)
+            .is_err());
+        assert!(OperatorAction::RotateKey
+            .check(&state, required, time)
+            .is_err());
+
+        let mut inactive = validator();
+        inactive.inactive_time = Some(time);
+        inactive.inactive_block = Some(1.into());
+        let state = staked_state(required, Some(inactive.clone()));
+        assert!(OperatorAction::NodeJoin
+            .check(&state, required, time)
+            .is_ok());
+        assert!(OperatorAction::RotateKey
+            .check(&state, required, time)
+            .is_ok());
+
+        inactive.jailed_until = Some(time + 1);
+        let state = staked_state(required, Some(inactive));
+        assert!(OperatorAction::RotateKey
+            .check(&state, required, time)
+            .is_err());
+        assert!(OperatorAction::Unjail
+            .check(&state, required, time)
+            .is_err());
+        assert!(OperatorAction::Unjail
+            .check(&state, required, time + 1)
+            .is_ok());
+    }
+
+    #[test]
+    fn check_operator_action_from_str() {
+        assert_eq!(
+            OperatorAction::RotateKey,
+            "rotate-key".parse::<OperatorAction>().unwrap()
+        );
+        assert!("foo".parse::<OperatorAction>().is_err());
+    }
+}
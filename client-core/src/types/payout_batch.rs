@@ -0,0 +1,72 @@
+//! Named batches of payouts to multiple recipients
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use chain_core::init::coin::Coin;
+use chain_core::tx::data::address::ExtendedAddr;
+
+/// Payout to a single recipient of a batch
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct PayoutRecipient {
+    /// address of the recipient
+    pub address: ExtendedAddr,
+    /// paid amount
+    pub amount: Coin,
+    /// id of the transaction which pays the recipient (hex encoded)
+    pub transaction_id: String,
+}
+
+/// Named batch of payouts sent in one or more transactions
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct PayoutBatch {
+    /// name of the batch
+    pub name: String,
+    /// block height at which the batch was sent
+    pub block_height: u64,
+    /// ids of the sent transactions (hex encoded)
+    pub transaction_ids: Vec<String>,
+    /// recipients in the requested order
+    pub recipients: Vec<PayoutRecipient>,
+}
+
+/// Splits payouts into the least number of chunks of at most `max_outputs - 1` recipients (one
+/// output is left for the change), evening out the chunk sizes
+pub fn split_payouts<T>(mut payouts: Vec<T>, max_outputs: usize) -> Vec<Vec<T>> {
+    let per_tx = max_outputs.saturating_sub(1).max(1);
+    let chunks = (payouts.len() + per_tx - 1) / per_tx;
+
+    let mut result = Vec::with_capacity(chunks);
+    for remaining_chunks in (1..=chunks).rev() {
+        let size = (payouts.len() + remaining_chunks - 1) / remaining_chunks;
+        let rest = payouts.split_off(size);
+        result.push(payouts);
+        payouts = rest;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_split_payouts() {
+        assert!(split_payouts(Vec::<u32>::new(), 64).is_empty());
+
+        let sizes = |n: u32| {
+            split_payouts((0..n).collect(), 64)
+                .iter()
+                .map(Vec::len)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![63], sizes(63));
+        assert_eq!(vec![32, 32], sizes(64));
+        assert_eq!(vec![43, 43, 42], sizes(128));
+
+        let chunks = split_payouts((0..100).collect::<Vec<u32>>(), 64);
+        assert_eq!(
+            (0..100).collect::<Vec<u32>>(),
+            chunks.into_iter().flatten().collect::<Vec<_>>()
+        );
+    }
+}
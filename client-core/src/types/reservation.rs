@@ -0,0 +1,30 @@
+//! Earmarked value of wallets
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use chain_core::init::coin::Coin;
+
+/// Value of a wallet earmarked for a transaction which isn't built yet (e.g. a pending
+/// withdrawal request), it's excluded from the available balance and input selection until it's
+/// released or expires
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, Serialize, Deserialize)]
+pub struct BalanceReservation {
+    /// id of the reservation (unique in the wallet)
+    pub id: u64,
+    /// reserved amount
+    pub amount: Coin,
+    /// free form tag (e.g. id of the withdrawal request)
+    pub tag: String,
+    /// unix timestamp (in seconds) of the reservation
+    pub created_at: u64,
+    /// unix timestamp (in seconds) after which the reservation is released automatically
+    pub expires_at: u64,
+}
+
+impl BalanceReservation {
+    /// Returns true if the reservation expired at given unix timestamp
+    #[inline]
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at <= now
+    }
+}
@@ -51,6 +51,9 @@ pub struct WalletBalance {
     pub available: Coin,
     /// The pending amount balance
     pub pending: Coin,
+    /// The amount balance earmarked by reservations (not included in the available amount)
+    #[serde(default)]
+    pub reserved: Coin,
 }
 
 /// Transaction pending infomation
@@ -87,6 +90,10 @@ pub struct TransactionChange {
     pub block_height: u64,
     /// Time of block which has this transaction
     pub block_time: Time,
+    /// Local memo of the transaction (never broadcast), kept in the wallet state separately from
+    /// the transaction change and filled in when the history is read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
 }
 
 /// Transaction input
@@ -147,6 +154,77 @@ pub enum BalanceChange {
     NoChange,
 }
 
+impl BalanceChange {
+    /// Returns the direction and value of balance change (`None` for no change)
+    pub fn direction(&self) -> Option<(BalanceChangeDirection, Coin)> {
+        match self {
+            BalanceChange::Incoming { value } => Some((BalanceChangeDirection::Incoming, *value)),
+            BalanceChange::Outgoing { value } => Some((BalanceChangeDirection::Outgoing, *value)),
+            BalanceChange::NoChange => None,
+        }
+    }
+}
+
+/// Direction of balance change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceChangeDirection {
+    /// Balance addition
+    Incoming,
+    /// Balance reduction
+    Outgoing,
+}
+
+/// Filter of transaction history, unset conditions match all transactions
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransactionHistoryFilter {
+    /// Block time from which transactions are included
+    pub from_time: Option<Time>,
+    /// Block time until which transactions are included
+    pub to_time: Option<Time>,
+    /// Minimum value of balance change
+    pub min_amount: Option<Coin>,
+    /// Maximum value of balance change
+    pub max_amount: Option<Coin>,
+    /// Direction of balance change
+    pub direction: Option<BalanceChangeDirection>,
+    /// Transaction type
+    pub transaction_type: Option<TransactionType>,
+}
+
+impl TransactionHistoryFilter {
+    /// Checks if a transaction change matches the filter, changes without balance change never
+    /// match
+    pub fn matches(&self, change: &TransactionChange) -> bool {
+        let (direction, value) = match change.balance_change.direction() {
+            Some(direction) => direction,
+            None => return false,
+        };
+
+        self.from_time
+            .map_or(true, |time| change.block_time >= time)
+            && self.to_time.map_or(true, |time| change.block_time <= time)
+            && self.min_amount.map_or(true, |amount| value >= amount)
+            && self.max_amount.map_or(true, |amount| value <= amount)
+            && self
+                .direction
+                .map_or(true, |expected| direction == expected)
+            && self
+                .transaction_type
+                .map_or(true, |expected| change.transaction_type == expected)
+    }
+}
+
+/// A page of transaction history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionHistory {
+    /// Number of transactions matching the filter (in all pages)
+    pub total: usize,
+    /// Transactions in the page
+    pub transactions: Vec<TransactionChange>,
+}
+
 fn serialize_transaction_id<S>(
     transaction_id: &TxId,
     serializer: S,
@@ -174,6 +252,7 @@ where
     Ok(transaction_id)
 }
 
+// the memo is not encoded, it is stored in `WalletState::transaction_memos`
 impl Encode for TransactionChange {
     fn encode_to<W: Output>(&self, dest: &mut W) {
         self.transaction_id.encode_to(dest);
@@ -217,6 +296,7 @@ impl Decode for TransactionChange {
             transaction_type,
             block_height,
             block_time,
+            memo: None,
         })
     }
 }
@@ -282,6 +362,7 @@ mod tests {
             fee_paid: Fee::new(Coin::one()),
             block_height: 0,
             block_time: Time::now(),
+            memo: None,
         };
 
         let encoded = transaction_change.encode();
@@ -290,6 +371,74 @@ mod tests {
         assert_eq!(transaction_change, decoded);
     }
 
+    #[test]
+    fn check_transaction_history_filter() {
+        let change = |balance_change, transaction_type, block_time| TransactionChange {
+            transaction_id: [0; 32],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            balance_change,
+            transaction_type,
+            fee_paid: Fee::new(Coin::zero()),
+            block_height: 0,
+            block_time: Time::from_str(block_time).unwrap(),
+            memo: None,
+        };
+        let incoming = change(
+            BalanceChange::Incoming {
+                value: Coin::new(50).unwrap(),
+            },
+            TransactionType::Transfer,
+            "2019-04-09T09:38:41Z",
+        );
+        let outgoing = change(
+            BalanceChange::Outgoing {
+                value: Coin::new(100).unwrap(),
+            },
+            TransactionType::Deposit,
+            "2019-04-10T09:38:41Z",
+        );
+        let no_change = change(
+            BalanceChange::NoChange,
+            TransactionType::Unjail,
+            "2019-04-10T09:38:41Z",
+        );
+
+        let filter = TransactionHistoryFilter::default();
+        assert!(filter.matches(&incoming));
+        assert!(filter.matches(&outgoing));
+        assert!(!filter.matches(&no_change));
+
+        let filter = TransactionHistoryFilter {
+            from_time: Some(Time::from_str("2019-04-10T00:00:00Z").unwrap()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&incoming));
+        assert!(filter.matches(&outgoing));
+
+        let filter = TransactionHistoryFilter {
+            to_time: Some(Time::from_str("2019-04-10T00:00:00Z").unwrap()),
+            min_amount: Some(Coin::new(50).unwrap()),
+            max_amount: Some(Coin::new(50).unwrap()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&incoming));
+        assert!(!filter.matches(&outgoing));
+
+        let filter = TransactionHistoryFilter {
+            direction: Some(BalanceChangeDirection::Outgoing),
+            transaction_type: Some(TransactionType::Deposit),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&incoming));
+        assert!(filter.matches(&outgoing));
+
+        let filter: TransactionHistoryFilter =
+            serde_json::from_str(r#"{"direction": "incoming", "min_amount": "10"}"#).unwrap();
+        assert_eq!(Some(BalanceChangeDirection::Incoming), filter.direction);
+        assert!(filter.matches(&incoming));
+    }
+
     #[test]
     fn balance_change_add_incoming() {
         let coin = Coin::zero()
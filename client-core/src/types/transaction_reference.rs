@@ -0,0 +1,45 @@
+//! References of built transactions
+use std::fmt;
+
+use chain_core::common::H256;
+use chain_core::tx::data::TxId;
+use chain_core::tx::{TxAux, TxEnclaveAux};
+
+/// Identifiers of a built transaction which are known before it's broadcast, so that callers can
+/// persist their own reference prior to submission
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionReference {
+    /// transaction id (computed from the transaction without witnesses, so it doesn't change when
+    /// the transaction is signed or obfuscated again)
+    pub txid: TxId,
+    /// blake3 hash of the obfuscated payload (transactions obfuscated by the enclave only), it's
+    /// different for every obfuscation
+    pub payload_hash: Option<H256>,
+}
+
+impl TransactionReference {
+    /// Returns the reference of given transaction
+    pub fn new(tx_aux: &TxAux) -> Self {
+        let payload_hash = match tx_aux {
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx { payload, .. })
+            | TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx { payload, .. }) => {
+                Some(blake3::hash(&payload.txpayload).into())
+            }
+            _ => None,
+        };
+        TransactionReference {
+            txid: tx_aux.tx_id(),
+            payload_hash,
+        }
+    }
+}
+
+impl fmt::Display for TransactionReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.txid))?;
+        if let Some(payload_hash) = self.payload_hash {
+            write!(f, " (payload hash: {})", hex::encode(payload_hash))?;
+        }
+        Ok(())
+    }
+}
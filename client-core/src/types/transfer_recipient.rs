@@ -0,0 +1,33 @@
+//! Recipients of a transfer to multiple addresses in one transaction
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use chain_core::init::coin::Coin;
+use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::data::input::TxoSize;
+use client_common::PublicKey;
+
+/// Recipient of a transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferRecipient {
+    /// address of the recipient
+    pub address: ExtendedAddr,
+    /// sent amount
+    pub amount: Coin,
+    /// view keys of the recipient, which are allowed to read the transaction
+    pub view_keys: BTreeSet<PublicKey>,
+}
+
+/// Output paying a recipient of a sent transfer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipientOutput {
+    /// address of the recipient
+    pub address: ExtendedAddr,
+    /// sent amount
+    pub amount: Coin,
+    /// id of the transaction (hex encoded)
+    pub transaction_id: String,
+    /// index of the output paying the recipient in the transaction
+    pub output_index: TxoSize,
+}
@@ -7,7 +7,7 @@ use unicase::eq_ascii;
 
 use client_common::{Error, ErrorKind, Result};
 
-/// Enum for specifying the kind of wallet (e.g., `Basic`, `HD`)
+/// Enum for specifying the kind of wallet (e.g., `Basic`, `HD`, `WatchOnly`)
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
 pub enum WalletKind {
     /// Basic Wallet
@@ -16,6 +16,23 @@ pub enum WalletKind {
     HD,
     /// HW Wallet
     HW,
+    /// Watch-only Wallet, holds public keys only and can't sign
+    WatchOnly,
+}
+
+impl WalletKind {
+    /// Returns an `ErrorKind::PermissionDenied` error if wallets of this kind hold no keys to
+    /// sign with
+    pub fn ensure_can_sign(self) -> Result<()> {
+        if self == WalletKind::WatchOnly {
+            Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "Watch-only wallet holds no private keys and can't sign",
+            ))
+        } else {
+            Ok(())
+        }
+    }
 }
 
 impl From<u64> for WalletKind {
@@ -23,6 +40,7 @@ impl From<u64> for WalletKind {
         match code {
             0 => WalletKind::Basic,
             1 => WalletKind::HD,
+            3 => WalletKind::WatchOnly,
             _ => WalletKind::HW,
         }
     }
@@ -38,10 +56,12 @@ impl FromStr for WalletKind {
             Ok(WalletKind::HW)
         } else if eq_ascii(s, "basic") {
             Ok(WalletKind::Basic)
+        } else if eq_ascii(s, "watch-only") {
+            Ok(WalletKind::WatchOnly)
         } else {
             Err(Error::new(
                 ErrorKind::InvalidInput,
-                "Wallet type can either be `hd` or `hw` or `basic`",
+                "Wallet type can either be `hd` or `hw` or `basic` or `watch-only`",
             ))
         }
     }
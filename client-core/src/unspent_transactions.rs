@@ -8,6 +8,9 @@ use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
 use client_common::{Error, ErrorKind, Result, ResultExt};
 
+/// Maximum number of nodes visited by `UnspentTransactions::branch_and_bound`
+pub const MAX_BRANCH_AND_BOUND_TRIES: usize = 100_000;
+
 /// An iterator over unspent transactions
 ///
 /// # Usage
@@ -121,6 +124,126 @@ impl UnspentTransactions {
     pub fn select_all(&self) -> SelectedUnspentTransactions<'_> {
         SelectedUnspentTransactions { inner: &self.0 }
     }
+
+    /// Selects first `count` unspent transactions (or all of them if there are less)
+    pub fn select_first(&self, count: usize) -> SelectedUnspentTransactions<'_> {
+        SelectedUnspentTransactions {
+            inner: &self.0[..count.min(self.0.len())],
+        }
+    }
+
+    /// Searches (depth-first, like Bitcoin Core's branch-and-bound coin selection) for unspent
+    /// transactions whose values sum up exactly to `target(n)`, where `n` is the number of
+    /// selected unspent transactions (e.g. output value plus the fee of a transaction with `n`
+    /// inputs and no change output). `target` must not decrease when `n` increases.
+    ///
+    /// Unspent transactions should be sorted with highest value first. Returns indices of
+    /// selected unspent transactions in that order, or `None` if there is no exact match or the
+    /// search is given up after `MAX_BRANCH_AND_BOUND_TRIES` tries.
+    pub fn branch_and_bound<F>(&self, target: F) -> Result<Option<Vec<usize>>>
+    where
+        F: FnMut(usize) -> Result<Coin>,
+    {
+        let values = self
+            .0
+            .iter()
+            .map(|(_, output)| u64::from(output.value))
+            .collect::<Vec<_>>();
+        // remaining[i]: sum of values of unspent transactions from index `i`
+        let mut remaining = vec![0; values.len() + 1];
+        for i in (0..values.len()).rev() {
+            remaining[i] = remaining[i + 1] + values[i];
+        }
+
+        let mut search = BranchAndBound {
+            values: &values,
+            remaining: &remaining,
+            target,
+            targets: Vec::new(),
+            selected: Vec::new(),
+            tries: 0,
+        };
+
+        if search.search(0, 0)? {
+            Ok(Some(search.selected))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Moves unspent transactions at given indices (e.g. returned by `branch_and_bound`) to the
+    /// front, keeping them in given order followed by others in their current order
+    pub fn move_to_front(&mut self, indices: &[usize]) {
+        let mut unspent_transactions = std::mem::take(&mut self.0)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        let mut front = indices
+            .iter()
+            .filter_map(|&i| unspent_transactions.get_mut(i).and_then(Option::take))
+            .collect::<Vec<_>>();
+        front.extend(unspent_transactions.into_iter().flatten());
+        self.0 = front;
+    }
+}
+
+/// State of a branch-and-bound search for an exact match
+struct BranchAndBound<'a, F> {
+    values: &'a [u64],
+    remaining: &'a [u64],
+    target: F,
+    /// cached `target` for 1, 2, ... selected unspent transactions
+    targets: Vec<u64>,
+    selected: Vec<usize>,
+    tries: usize,
+}
+
+impl<'a, F> BranchAndBound<'a, F>
+where
+    F: FnMut(usize) -> Result<Coin>,
+{
+    fn target(&mut self, count: usize) -> Result<u64> {
+        while self.targets.len() < count {
+            let target = (self.target)(self.targets.len() + 1)?;
+            self.targets.push(target.into());
+        }
+        Ok(self.targets[count - 1])
+    }
+
+    /// Decides whether to select unspent transaction at `index` given the sum of selected ones,
+    /// returns `true` if an exact match is found
+    fn search(&mut self, index: usize, sum: u64) -> Result<bool> {
+        self.tries += 1;
+        if self.tries > MAX_BRANCH_AND_BOUND_TRIES {
+            return Ok(false);
+        }
+
+        let count = self.selected.len();
+        if count > 0 {
+            let target = self.target(count)?;
+            if sum == target {
+                return Ok(true);
+            }
+            // selecting more can't match (an unspent transaction worth less than the increase of
+            // target it causes is never worth selecting)
+            if sum > target {
+                return Ok(false);
+            }
+        }
+        // remaining unspent transactions are not enough to reach the target
+        if index == self.values.len() || sum + self.remaining[index] < self.target(count + 1)? {
+            return Ok(false);
+        }
+
+        // branch with highest value first
+        self.selected.push(index);
+        if self.search(index + 1, sum + self.values[index])? {
+            return Ok(true);
+        }
+        self.selected.pop();
+
+        self.search(index + 1, sum)
+    }
 }
 
 /// Builder for unspent transactions
@@ -263,4 +386,30 @@ mod unspent_transactions_tests {
             coin = tx_out.value;
         }
     }
+
+    #[test]
+    fn check_branch_and_bound() {
+        let mut unspent_transactions = sample();
+        unspent_transactions.apply_all(&[Operation::Sort(Sorter::HighestValueFirst)]);
+
+        // 430 plus 10 for each selected unspent transaction: 300 + 150
+        let indices = unspent_transactions
+            .branch_and_bound(|count| Ok(Coin::new(430 + 10 * count as u64).unwrap()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![0, 3], indices);
+
+        unspent_transactions.move_to_front(&indices);
+        let values = unspent_transactions
+            .iter()
+            .map(|(_, output)| u64::from(output.value))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![300, 150, 250, 200, 100], values);
+
+        // no subset sums up to 360 plus 10 for each selected unspent transaction
+        assert!(unspent_transactions
+            .branch_and_bound(|count| Ok(Coin::new(360 + 10 * count as u64).unwrap()))
+            .unwrap()
+            .is_none());
+    }
 }
@@ -1,5 +1,7 @@
 //! Wallet management
 mod default_wallet_client;
+/// Composable middlewares around `WalletClient`
+pub mod middleware;
 /// Wallet synchronizer
 pub mod syncer;
 mod syncer_logic;
@@ -10,7 +12,7 @@ use indexmap::IndexSet;
 #[cfg(feature = "experimental")]
 use secp256k1::schnorrsig::SchnorrSignature;
 use secstr::SecUtf8;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 use chain_core::common::{Proof, H256};
 use chain_core::init::address::RedeemAddress;
@@ -24,7 +26,9 @@ use chain_core::tx::data::output::TxOut;
 use chain_core::tx::data::Tx;
 use chain_core::tx::data::TxId;
 use chain_core::tx::witness::tree::RawXOnlyPubkey;
+use chain_core::tx::witness::TxWitness;
 use chain_core::tx::TxAux;
+use client_common::seckey::KdfConfig;
 use client_common::tendermint::types::BroadcastTxResponse;
 use client_common::{
     MultiSigAddress, PrivateKey, PrivateKeyAction, PublicKey, Result, SecKey, Transaction,
@@ -33,9 +37,20 @@ use client_common::{
 use serde::{Deserialize, Serialize};
 
 use crate::hd_wallet::HardwareKind;
-use crate::service::{SyncState, WalletInfo};
-use crate::transaction_builder::{SignedTransferTransaction, UnsignedTransferTransaction};
-use crate::types::{AddressType, TransactionChange, TransactionPending, WalletBalance, WalletKind};
+#[cfg(feature = "experimental")]
+use crate::multi_sig::SignerStatus;
+use crate::service::{LockoutStatus, OperatorProfile, SyncState, WalletExport, WalletInfo};
+use crate::transaction_builder::{
+    PartiallySignedTransferTx, SignedTransferTransaction, UnsignedTransferTransaction,
+    UnsignedTransferTx,
+};
+use crate::types::{
+    AddressMetadata, AddressType, Alert, AlertRule, AnnotationConflict, AnnotationImportReport,
+    BalanceReservation, DevicePairing, DeviceRole, EncryptedWalletEvent, HistoryExportFormat,
+    MempoolTransaction, PairedDevice, PayoutBatch, PendingApproval, RecipientOutput,
+    TransactionChange, TransactionHistory, TransactionHistoryFilter, TransactionPending,
+    TransactionReference, TransferRecipient, TxAnnotation, WalletBalance, WalletKind,
+};
 use crate::{InputSelectionStrategy, Mnemonic, UnspentTransactions};
 
 /// information needed when create/delete a wallet
@@ -72,6 +87,11 @@ pub trait WalletClient: Send + Sync {
     fn get_hardware_kind(&self, name: &str, enckey: &SecKey) -> Result<HardwareKind>;
 
     /// Send balance to a transfer address, return the transaction id directly
+    ///
+    /// If spends of the wallet need approval, the transfer is only queued as pending approval
+    /// (see `approve_transaction`). Inputs are only selected from the unspent transactions of
+    /// `only_from_addresses` (if set).
+    #[allow(clippy::too_many_arguments)]
     fn send_to_address(
         &self,
         name: &str,
@@ -80,6 +100,8 @@ pub trait WalletClient: Send + Sync {
         address: ExtendedAddr,
         view_keys: &mut BTreeSet<PublicKey>,
         network_id: u8,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+        only_from_addresses: Option<&[ExtendedAddr]>,
     ) -> Result<TxId>;
 
     /// send balance to a transfer address, waiting it transaction confirmed then return transaction id
@@ -136,7 +158,21 @@ pub trait WalletClient: Send + Sync {
         view_key: &PrivateKey,
     ) -> Result<SecKey>;
 
-    /// Remove a wallet
+    /// Creates a watch-only wallet from public keys, it tracks the given transfer and staking
+    /// addresses but holds no private keys, so signing APIs return `ErrorKind::PermissionDenied`
+    ///
+    /// Without the private view key, confidential transactions can't be decrypted (use
+    /// `restore_basic_wallet` to watch those)
+    fn new_watch_only_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        view_key: &PublicKey,
+        transfer_public_keys: &[PublicKey],
+        staking_public_keys: &[PublicKey],
+    ) -> Result<SecKey>;
+
+    /// Remove a wallet together with all of its keys, sync state, sessions and settings
     fn delete_wallet(&self, name: &str, passphrase: &SecUtf8) -> Result<()>;
 
     /// get auth token client
@@ -283,15 +319,17 @@ pub trait WalletClient: Send + Sync {
     /// Retrieves current balance of wallet
     fn balance(&self, name: &str, enckey: &SecKey) -> Result<WalletBalance>;
 
-    /// Retrieves transaction history of wallet
+    /// Retrieves a page of transaction history of wallet, only transactions changing the balance
+    /// and matching the filter are included (`total` counts them in all pages)
     fn history(
         &self,
         name: &str,
         enckey: &SecKey,
-        limit: usize,
+        filter: TransactionHistoryFilter,
         offset: usize,
+        limit: usize,
         reversed: bool,
-    ) -> Result<Vec<TransactionChange>>;
+    ) -> Result<TransactionHistory>;
 
     /// Retrieves transaction change corresponding to given transaction ID
     fn get_transaction_change(
@@ -333,7 +371,16 @@ pub trait WalletClient: Send + Sync {
     /// - `outputs`: Transaction outputs
     /// - `attributes`: Transaction attributes,
     /// - `input_selection_strategy`: Strategy to use while selecting unspent transactions
+    /// - `only_from_addresses`: Only unspent transactions of these addresses are selected (if
+    ///   set)
     /// - `return_address`: Address to which change amount will get returned
+    ///
+    /// # return
+    /// - `TxAux`: obfuscated transaction
+    /// - `TransactionReference`: identifiers of the transaction (known before it's broadcast)
+    /// - `Vec<TxoPointer>`: the selected inputs
+    /// - `Coin`: the return amount of Coin
+    #[allow(clippy::too_many_arguments)]
     fn create_transaction(
         &self,
         name: &str,
@@ -341,12 +388,37 @@ pub trait WalletClient: Send + Sync {
         outputs: Vec<TxOut>,
         attributes: TxAttributes,
         input_selection_strategy: Option<InputSelectionStrategy>,
+        only_from_addresses: Option<&[ExtendedAddr]>,
         return_address: ExtendedAddr,
-    ) -> Result<(TxAux, Vec<TxoPointer>, Coin)>;
+    ) -> Result<(TxAux, TransactionReference, Vec<TxoPointer>, Coin)>;
+
+    /// Estimates the fee of a transfer transaction, inputs are selected like `create_transaction`
+    /// does, but nothing is signed, obfuscated or stored
+    ///
+    /// # Attributes
+    ///
+    /// - `name`: Name of wallet
+    /// - `enckey`: Passphrase of wallet
+    /// - `outputs`: Transaction outputs
+    /// - `attributes`: Transaction attributes,
+    /// - `input_selection_strategy`: Strategy to use while selecting unspent transactions
+    fn estimate_fee(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+    ) -> Result<Coin>;
 
     /// Broadcasts a transaction to Crypto.com Chain
     fn broadcast_transaction(&self, tx_aux: &TxAux) -> Result<BroadcastTxResponse>;
 
+    /// Broadcasts transactions to Crypto.com Chain in one batch, returning the result of each
+    /// transaction in the given order
+    fn broadcast_transactions(&self, tx_auxs: &[TxAux])
+        -> Result<Vec<Result<BroadcastTxResponse>>>;
+
     /// When receiver's view key not included in the transaction, the receiver can't collect the outputs.
     /// The sender have to get the plain transaction and send it to the receiver by email or something
     /// so that the receiver can sync it into the wallet DB and get the outputs.
@@ -366,6 +438,15 @@ pub trait WalletClient: Send + Sync {
     /// Get the current block height
     fn get_current_block_height(&self) -> Result<u64>;
 
+    /// Returns transactions related to the wallet which are in the mempool of the connected
+    /// node, at most `limit` transactions of the mempool are inspected
+    fn mempool_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        limit: u64,
+    ) -> Result<Vec<MempoolTransaction>>;
+
     /// Update the wallet state
     fn update_tx_pending_state(
         &self,
@@ -405,6 +486,55 @@ pub trait WalletClient: Send + Sync {
         signed_tx: SignedTransferTransaction,
     ) -> Result<TxId>;
 
+    /// Selects the inputs and builds an unsigned transfer transaction on an online wallet (e.g.
+    /// watch-only), to be signed by the air-gapped wallet holding the keys
+    ///
+    /// # Attributes
+    ///
+    /// - `name`: Name of wallet
+    /// - `enckey`: Passphrase of wallet
+    /// - `outputs`: Transaction outputs
+    /// - `attributes`: Transaction attributes,
+    /// - `input_selection_strategy`: Strategy to use while selecting unspent transactions
+    /// - `return_address`: Address to which change amount will get returned
+    fn build_unsigned_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+        return_address: ExtendedAddr,
+    ) -> Result<UnsignedTransferTx>;
+
+    /// Signs an unsigned transfer transaction on the air-gapped wallet, returns the witness to be
+    /// imported into the online wallet with `import_transaction_witness`
+    fn sign_unsigned_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_tx: &UnsignedTransferTx,
+    ) -> Result<TxWitness>;
+
+    /// Signs the inputs of a raw transfer transaction which spend outputs of the wallet, the
+    /// other inputs are left to the wallets holding their keys
+    fn sign_raw_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        raw_tx: &PartiallySignedTransferTx,
+    ) -> Result<PartiallySignedTransferTx>;
+
+    /// Verifies the witness signed by the air-gapped wallet and obfuscates the transaction, which
+    /// can be sent with `broadcast_signed_transfer_tx`
+    fn import_transaction_witness(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_tx: &UnsignedTransferTx,
+        witness: TxWitness,
+    ) -> Result<SignedTransferTransaction>;
+
     /// Get current sync state of wallet, return genesis one if not exists.
     fn get_sync_state(&self, name: &str) -> Result<SyncState>;
 
@@ -412,6 +542,328 @@ pub trait WalletClient: Send + Sync {
     fn flush_database(&self) -> Result<()>;
 }
 
+/// Passphrase management of wallets
+pub trait WalletRekeyClient: WalletClient {
+    /// Changes the passphrase and key derivation parameters of a wallet and re-encrypts all of
+    /// its records under the new encryption key, returns the new encryption key
+    fn rekey_wallet(
+        &self,
+        name: &str,
+        old_passphrase: &SecUtf8,
+        new_passphrase: &SecUtf8,
+        kdf_config: KdfConfig,
+    ) -> Result<SecKey>;
+
+    /// Changes the passphrase of a wallet (keeping its key derivation parameters) and re-encrypts
+    /// all of its records under the new encryption key, returns the new encryption key
+    fn change_passphrase(
+        &self,
+        name: &str,
+        old_passphrase: &SecUtf8,
+        new_passphrase: &SecUtf8,
+    ) -> Result<SecKey>;
+
+    /// Returns brute-force lockout status of passphrase attempts for given wallet
+    fn passphrase_lockout_status(&self, name: &str) -> Result<LockoutStatus>;
+}
+
+/// Backups of wallets and their replication across paired devices
+pub trait WalletMaintenanceClient: WalletClient {
+    /// Exports the wallet with its synced state (unspent outputs, transaction history and last
+    /// synced block), the HD seed is only included if `include_hd_seed` is set
+    fn export_wallet_snapshot(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        include_hd_seed: bool,
+    ) -> Result<WalletExport>;
+
+    /// Imports a wallet exported by `export_wallet_snapshot`, it's synced from the last synced
+    /// block of the export
+    fn import_wallet_snapshot(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        snapshot: WalletExport,
+    ) -> Result<SecKey>;
+
+    /// Returns pairing data of this device for the wallet (e.g. shown as QR code to the other
+    /// device), the device key is created on first use
+    fn device_pairing(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        device_name: &str,
+    ) -> Result<DevicePairing>;
+
+    /// Pairs another device of the owner with the wallet, `role` is the role of the other device
+    ///
+    /// Both devices pair with each other: the primary with the secondary role and vice versa.
+    fn pair_device(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        peer: DevicePairing,
+        role: DeviceRole,
+    ) -> Result<()>;
+
+    /// Removes a paired device of the wallet
+    fn unpair_device(&self, name: &str, enckey: &SecKey, device_name: &str) -> Result<()>;
+
+    /// Returns devices paired with the wallet
+    fn paired_devices(&self, name: &str, enckey: &SecKey) -> Result<Vec<PairedDevice>>;
+
+    /// Returns wallet events for a secondary device after given sequence number (the last one
+    /// imported by the device), encrypted for the device
+    fn device_events(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        device_name: &str,
+        after: u64,
+    ) -> Result<Vec<EncryptedWalletEvent>>;
+
+    /// Applies wallet events of a primary device, returns the sequence number of the last
+    /// imported event
+    fn import_device_events(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        device_name: &str,
+        events: Vec<EncryptedWalletEvent>,
+    ) -> Result<u64>;
+}
+
+/// Bookkeeping and monitoring of wallets: address labels, transaction annotations, history
+/// exports, alerts and the operator profile
+pub trait WalletAuditClient: WalletClient {
+    /// Exports the transaction history of wallet matching the filter (oldest first) as CSV or
+    /// JSON lines for accounting tools
+    fn export_history(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        format: HistoryExportFormat,
+        filter: TransactionHistoryFilter,
+    ) -> Result<String>;
+
+    /// Returns annotations of the wallet's transactions
+    fn annotations(&self, name: &str, enckey: &SecKey) -> Result<BTreeMap<TxId, TxAnnotation>>;
+
+    /// Sets annotation of a transaction in the wallet's history, an empty annotation removes it
+    fn set_annotation(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: TxId,
+        annotation: TxAnnotation,
+    ) -> Result<()>;
+
+    /// Sets the local memo of a transaction in the wallet's history (memos are never broadcast),
+    /// an empty memo removes it
+    fn set_transaction_memo(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: TxId,
+        memo: String,
+    ) -> Result<()>;
+
+    /// Merges annotations in bulk (e.g. parsed by `types::parse_annotations`), `conflict` decides
+    /// how they are combined with existing annotations
+    ///
+    /// Transactions which are not in the wallet's history are skipped and reported.
+    fn import_annotations(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        entries: Vec<(TxId, TxAnnotation)>,
+        conflict: AnnotationConflict,
+    ) -> Result<AnnotationImportReport>;
+
+    /// Returns the address book of the wallet: address -> metadata (e.g. label) of own and other
+    /// addresses
+    fn address_book(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<BTreeMap<String, AddressMetadata>>;
+
+    /// Returns metadata of an address in the address book of the wallet
+    fn get_address_metadata(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &str,
+    ) -> Result<Option<AddressMetadata>>;
+
+    /// Sets label of a transfer or staking address in the address book of the wallet, an empty
+    /// label removes it
+    fn set_address_label(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &str,
+        label: &str,
+    ) -> Result<()>;
+
+    /// Returns operator profile of the wallet, if it's linked to a council node
+    fn operator_profile(&self, name: &str) -> Result<Option<OperatorProfile>>;
+
+    /// Links the wallet to the council node operated with given staking address of the wallet
+    fn set_operator_profile(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_address: StakedStateAddress,
+    ) -> Result<()>;
+
+    /// Returns alert rules of the wallet
+    fn alert_rules(&self, name: &str, enckey: &SecKey) -> Result<Vec<AlertRule>>;
+
+    /// Replaces alert rules of the wallet, which are evaluated on each synchronized batch of
+    /// blocks
+    fn set_alert_rules(&self, name: &str, enckey: &SecKey, rules: Vec<AlertRule>) -> Result<()>;
+
+    /// Returns alerts triggered by the alert rules of the wallet, latest first
+    fn alerts(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Alert>>;
+}
+
+/// Controls over spending of wallet funds: approval of transfers, frozen outputs and balance
+/// reservations
+pub trait WalletSpendControlClient: WalletClient {
+    /// Requires (or stops requiring) approval of spends of the wallet, which is given with the
+    /// passphrase while the auth token only requests transfers (maker/checker control)
+    ///
+    /// While approval is required, `send_to_address` queues transfers as pending approval
+    /// instead of broadcasting them, and the other APIs creating or signing transactions with
+    /// the auth token return `ErrorKind::PermissionDenied`
+    fn set_spend_approval(&self, name: &str, passphrase: &SecUtf8, required: bool) -> Result<()>;
+
+    /// Returns transfers pending approval, in the order they were requested
+    fn pending_approvals(&self, name: &str, enckey: &SecKey) -> Result<Vec<PendingApproval>>;
+
+    /// Approves a transfer pending approval: signs, obfuscates and broadcasts it
+    fn approve_transaction(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        transaction_id: TxId,
+    ) -> Result<TxId>;
+
+    /// Rejects a transfer pending approval, its inputs can be spent by other transfers again
+    fn reject_transaction(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        transaction_id: TxId,
+    ) -> Result<()>;
+
+    /// Freezes an unspent transaction of wallet: it's not selected as input of new transactions
+    /// until it's unfrozen (it's still included in the balance)
+    fn freeze_utxo(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<()>;
+
+    /// Unfreezes an unspent transaction of wallet, so it can be selected as input again
+    fn unfreeze_utxo(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<()>;
+
+    /// Retrieves the frozen unspent transactions of wallet
+    fn frozen_utxos(&self, name: &str, enckey: &SecKey) -> Result<Vec<TxoPointer>>;
+
+    /// Earmarks `amount` of the available balance for `ttl` seconds without building a
+    /// transaction: it's excluded from the available balance and input selection until it's
+    /// released or expires
+    fn reserve_balance(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        ttl: u64,
+        tag: String,
+    ) -> Result<BalanceReservation>;
+
+    /// Returns unexpired balance reservations of the wallet
+    fn reservations(&self, name: &str, enckey: &SecKey) -> Result<Vec<BalanceReservation>>;
+
+    /// Releases a balance reservation
+    fn release_reservation(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        id: u64,
+    ) -> Result<BalanceReservation>;
+
+    /// Releases all balance reservations with given tag
+    fn release_reservations_by_tag(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tag: &str,
+    ) -> Result<Vec<BalanceReservation>>;
+}
+
+/// Payments to multiple recipients and between local wallets
+pub trait WalletPaymentClient: WalletClient {
+    /// Transfers balance between two local wallets, return the transaction id
+    ///
+    /// The output goes to a freshly derived transfer address of the destination wallet, the
+    /// incoming amount is tracked as pending in the destination wallet until it's synced and the
+    /// transaction is annotated in both wallets (`INTERNAL_TRANSFER_CATEGORY`, with the other
+    /// wallet as counterparty), so that it shows up in both histories right away.
+    fn internal_transfer(
+        &self,
+        from_name: &str,
+        from_enckey: &SecKey,
+        to_name: &str,
+        to_enckey: &SecKey,
+        amount: Coin,
+        network_id: u8,
+    ) -> Result<TxId>;
+
+    /// Sends to multiple recipients in one transaction, returns the output paying each recipient
+    /// (in the requested order)
+    ///
+    /// The inputs are selected for all the recipients together, the view keys of all the
+    /// recipients (and of the wallet) are allowed to read the transaction. Like `send_to_address`,
+    /// the transfer is only queued as pending approval if spends of the wallet need approval.
+    fn send_to_many(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        recipients: Vec<TransferRecipient>,
+        network_id: u8,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+    ) -> Result<Vec<RecipientOutput>>;
+
+    /// Sends payouts to multiple recipients as a named batch, packing as many recipients as a
+    /// transaction can carry and splitting the rest into further transactions
+    ///
+    /// Sent transactions are tracked as pending. If a transaction fails, the already sent part
+    /// of the batch is recorded before returning the error.
+    fn send_batch(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        batch_name: &str,
+        recipients: Vec<(ExtendedAddr, Coin)>,
+        view_keys: &mut BTreeSet<PublicKey>,
+        network_id: u8,
+    ) -> Result<PayoutBatch>;
+
+    /// Returns payout batch with given name
+    fn payout_batch(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        batch_name: &str,
+    ) -> Result<Option<PayoutBatch>>;
+}
+
 #[cfg(feature = "experimental")]
 /// Interface for a generic wallet for multi-signature transactions
 pub trait MultiSigWalletClient: WalletClient {
@@ -483,6 +935,13 @@ pub trait MultiSigWalletClient: WalletClient {
     /// Returns final signature. This function will fail if partial signatures from all co-signers are not received.
     fn signature(&self, session_id: &H256, enckey: &SecKey) -> Result<SchnorrSignature>;
 
+    /// Returns progress of all co-signers in session with given id (e.g. to resume the session
+    /// after a restart)
+    fn session_status(&self, session_id: &H256, enckey: &SecKey) -> Result<Vec<SignerStatus>>;
+
+    /// Deletes session with given id (e.g. after its transaction is broadcasted)
+    fn delete_session(&self, session_id: &H256, enckey: &SecKey) -> Result<()>;
+
     /// Returns obfuscated transaction by signing given transaction with signature produced by current session id.
     fn transaction(
         &self,
@@ -1,43 +1,55 @@
 use crate::hd_wallet::{ChainPath, HardwareKind};
+#[cfg(feature = "experimental")]
+use crate::multi_sig::SignerStatus;
 use crate::service::*;
-use crate::transaction_builder::UnauthorizedWalletTransactionBuilder;
-use crate::transaction_builder::{SignedTransferTransaction, UnsignedTransferTransaction};
+use crate::transaction_builder::{
+    AttributesBuilder, PartiallySignedTransferTx, SignedTransferTransaction,
+    UnauthorizedWalletTransactionBuilder, UnsignedTransferTransaction, UnsignedTransferTx,
+};
 use crate::types::{
-    AddressType, BalanceChange, TransactionChange, TransactionPending, WalletBalance, WalletKind,
+    export_history, split_payouts, AddressMetadata, AddressType, Alert, AlertRule,
+    AnnotationConflict, AnnotationImportReport, BalanceChange, BalanceReservation, DevicePairing,
+    DeviceRole, EncryptedWalletEvent, HistoryExportFormat, MempoolTransaction, PairedDevice,
+    PayoutBatch, PayoutRecipient, PendingApproval, RecipientOutput, TransactionChange,
+    TransactionHistory, TransactionHistoryFilter, TransactionPending, TransactionReference,
+    TransferRecipient, TxAnnotation, WalletBalance, WalletEvent, WalletKind,
+    INTERNAL_TRANSFER_CATEGORY,
 };
 use crate::wallet::syncer::{get_genesis_sync_state, AddressRecovery};
 use crate::wallet::syncer_logic::create_transaction_change;
 #[cfg(feature = "experimental")]
 use crate::MultiSigWalletClient;
 use crate::{
-    InputSelectionStrategy, Mnemonic, UnspentTransactions, WalletClient, WalletTransactionBuilder,
+    InputSelectionStrategy, Mnemonic, UnspentTransactions, WalletAuditClient, WalletClient,
+    WalletMaintenanceClient, WalletPaymentClient, WalletRekeyClient, WalletSpendControlClient,
+    WalletTransactionBuilder,
 };
 use bit_vec::BitVec;
 use chain_core::common::{Proof, H256};
 use chain_core::init::address::RedeemAddress;
-use chain_core::init::coin::Coin;
+use chain_core::init::coin::{sum_coins, Coin};
 use chain_core::state::account::StakedStateAddress;
-use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
-use chain_core::tx::data::input::{str2txid, TxoPointer};
+use chain_core::tx::data::input::{str2txid, TxoPointer, TxoSize};
 use chain_core::tx::data::output::TxOut;
 #[cfg(feature = "experimental")]
 use chain_core::tx::data::Tx;
-use chain_core::tx::data::TxId;
+use chain_core::tx::data::{TxId, MAX_TX_OUTPUTS};
 use chain_core::tx::fee::Fee;
 use chain_core::tx::witness::tree::RawXOnlyPubkey;
 #[cfg(feature = "experimental")]
 use chain_core::tx::witness::{TxInWitness, TxWitness};
-use chain_core::tx::{TxAux, TxEnclaveAux, TxObfuscated};
+use chain_core::tx::{TransactionId, TxAux, TxEnclaveAux, TxObfuscated};
 use client_common::tendermint::types::Time;
 use client_common::tendermint::types::{AbciQueryExt, BlockResults, BroadcastTxResponse};
 use client_common::tendermint::{Client, UnauthorizedClient};
 #[cfg(feature = "experimental")]
 use client_common::SignedTransaction;
 use client_common::{
-    seckey::derive_enckey, Error, ErrorKind, MultiSigAddress, PrivateKey, PrivateKeyAction,
-    PublicKey, Result, ResultExt, SecKey, Storage, Transaction, TransactionInfo,
+    seckey::{derive_enckey_with, load_kdf_config, KdfConfig},
+    Error, ErrorKind, MultiSigAddress, PrivateKey, PrivateKeyAction, PublicKey, Result, ResultExt,
+    SecKey, Storage, Transaction, TransactionInfo,
 };
 use indexmap::IndexSet;
 use parity_scale_codec::Encode;
@@ -65,12 +77,23 @@ where
     wallet_state_service: WalletStateService<S>,
     sync_state_service: SyncStateService<S>,
     root_hash_service: RootHashService<S>,
+    passphrase_attempt_service: PassphraseAttemptService<S>,
+    operator_profile_service: OperatorProfileService<S>,
+    alert_service: AlertService<S>,
+    payout_batch_service: PayoutBatchService<S>,
+    annotation_service: AnnotationService<S>,
+    approval_service: ApprovalService<S>,
+    device_sync_service: DeviceSyncService<S>,
+    reservation_service: ReservationService<S>,
+    rekey_service: RekeyService<S>,
     #[cfg(feature = "experimental")]
     multi_sig_session_service: MultiSigSessionService<S>,
 
     tendermint_client: C,
     transaction_builder: T,
     block_height_ensure: Option<u64>,
+    /// Don't trust unproven query responses of the node
+    require_verified_queries: bool,
     storage: S,
 }
 
@@ -98,17 +121,103 @@ where
             #[cfg(feature = "experimental")]
             multi_sig_session_service: MultiSigSessionService::new(storage.clone()),
             root_hash_service: RootHashService::new(storage.clone()),
+            passphrase_attempt_service: PassphraseAttemptService::new(storage.clone()),
+            operator_profile_service: OperatorProfileService::new(storage.clone()),
+            alert_service: AlertService::new(storage.clone()),
+            payout_batch_service: PayoutBatchService::new(storage.clone()),
+            annotation_service: AnnotationService::new(storage.clone()),
+            approval_service: ApprovalService::new(storage.clone()),
+            device_sync_service: DeviceSyncService::new(storage.clone()),
+            reservation_service: ReservationService::new(storage.clone()),
+            rekey_service: RekeyService::new(storage.clone()),
             tendermint_client,
             transaction_builder,
             block_height_ensure,
+            require_verified_queries: false,
             storage,
         }
     }
 
+    /// Requires query responses the wallet relies on to be verified against the state trusted
+    /// by the light client (i.e. the app hash the wallet is synced to), instead of trusting the
+    /// node
+    pub fn with_verified_queries(mut self, require_verified_queries: bool) -> Self {
+        self.require_verified_queries = require_verified_queries;
+        self
+    }
+
+    /// Spent flags of the outputs of a transaction taken from the wallet history instead of the
+    /// node: spent flags aren't committed to the app hash, but outputs of the wallet can only be
+    /// spent by transactions of the wallet, which are in the history once it's synced (with a
+    /// trusted state) past the block of the transaction
+    fn verified_spent_flags(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tx_info: &TransactionInfo,
+    ) -> Result<Vec<bool>> {
+        self.sync_state_service
+            .get_global_state(name)?
+            .filter(|state| state.trusted && state.last_block_height >= tx_info.block_height)
+            .chain(|| {
+                (
+                    ErrorKind::VerifyError,
+                    "Wallet must be synced past the block of the transaction to verify it",
+                )
+            })?;
+        let txid = tx_info.tx.id();
+        let spent = self
+            .wallet_state_service
+            .get_transaction_history(name, enckey, false, None)?
+            .flat_map(|change| change.inputs)
+            .filter(|input| input.pointer.id == txid)
+            .map(|input| usize::from(input.pointer.index))
+            .collect::<BTreeSet<_>>();
+        Ok((0..tx_info.tx.outputs().len())
+            .map(|index| spent.contains(&index))
+            .collect())
+    }
+
+    /// Unspent transactions which can be selected as inputs: frozen outputs and outputs covering
+    /// the reserved balance are withheld
+    fn spendable_transactions(&self, name: &str, enckey: &SecKey) -> Result<UnspentTransactions> {
+        let unspent_transactions = self.unspent_transactions(name, enckey)?;
+        let frozen_utxos = self.wallet_state_service.get_frozen_utxos(name, enckey)?;
+        let reserved = self.reservation_service.reserved_amount(name, enckey)?;
+        Ok(UnspentTransactions::new(withhold_reserved(
+            unspent_transactions
+                .unwrap()
+                .into_iter()
+                .filter(|(input, _)| !frozen_utxos.contains(input))
+                .collect(),
+            reserved,
+        )))
+    }
+
+    /// Spendable transactions of given addresses (all of them if `only_from_addresses` isn't set)
+    fn spendable_transactions_from(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        only_from_addresses: Option<&[ExtendedAddr]>,
+    ) -> Result<UnspentTransactions> {
+        let unspent_transactions = self.spendable_transactions(name, enckey)?;
+        Ok(match only_from_addresses {
+            Some(addresses) => UnspentTransactions::new(
+                unspent_transactions
+                    .unwrap()
+                    .into_iter()
+                    .filter(|(_, output)| addresses.contains(&output.address))
+                    .collect(),
+            ),
+            None => unspent_transactions,
+        })
+    }
+
     fn is_tx_exist(&self, name: &str, enckey: &SecKey, txid: TxId) -> Result<bool> {
         let tx_change = self
             .wallet_state_service
-            .get_transaction_history(name, enckey, false)?
+            .get_transaction_history(name, enckey, false, None)?
             .filter(|change| BalanceChange::NoChange != change.balance_change)
             .find(|tx_change| tx_change.transaction_id == txid)
             .chain(|| {
@@ -119,6 +228,191 @@ where
             });
         Ok(tx_change.is_ok())
     }
+
+    /// Returns attributes of a transfer transaction readable by the wallet and given view keys
+    fn transfer_attributes(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        view_keys: &mut BTreeSet<PublicKey>,
+        network_id: u8,
+    ) -> Result<TxAttributes> {
+        let view_key = self.view_key(name, enckey)?;
+
+        view_keys.insert(view_key);
+
+        AttributesBuilder::with_network_id(network_id)
+            .view_keys(view_keys.iter().cloned())
+            .build()
+    }
+
+    /// Annotates a transfer between two local wallets in the history of one of them
+    fn annotate_internal_transfer(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: TxId,
+        counterparty: &str,
+    ) -> Result<()> {
+        let annotation = TxAnnotation {
+            category: INTERNAL_TRANSFER_CATEGORY.to_owned(),
+            counterparty: counterparty.to_owned(),
+            ..Default::default()
+        };
+        self.annotation_service
+            .set_annotation(name, enckey, transaction_id, annotation.clone())?;
+        self.device_sync_service.record_event(
+            name,
+            enckey,
+            WalletEvent::Annotation {
+                transaction_id,
+                annotation,
+            },
+        )
+    }
+
+    /// Orders unspent transactions to be selected with given strategy
+    fn apply_input_selection_strategy(
+        &self,
+        unspent_transactions: &mut UnspentTransactions,
+        input_selection_strategy: InputSelectionStrategy,
+        outputs: &[TxOut],
+        attributes: &TxAttributes,
+    ) -> Result<()> {
+        unspent_transactions.apply_all(input_selection_strategy.as_ref());
+        if input_selection_strategy == InputSelectionStrategy::BranchAndBound {
+            // falls back to highest value first if there's no exact match
+            self.transaction_builder.select_exact_match(
+                unspent_transactions,
+                outputs,
+                attributes,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Derives the encryption key of a wallet from its passphrase with the key derivation
+    /// parameters of the wallet (an interrupted re-encryption of the wallet is rolled back first)
+    fn derive_wallet_enckey(&self, name: &str, passphrase: &SecUtf8) -> Result<SecKey> {
+        self.rekey_service.recover(name)?;
+        let kdf_config = load_kdf_config(&self.storage, name)?;
+        derive_enckey_with(passphrase, name, &kdf_config).err_kind(ErrorKind::InvalidInput, || {
+            "unable to derive encryption key from passphrase"
+        })
+    }
+
+    /// Verifies enckey derived from a passphrase
+    ///
+    /// Failed attempts are recorded by the `PassphraseLockout` middleware
+    fn verify_enckey(&self, name: &str, enckey: &SecKey) -> Result<()> {
+        self.wallet_service.view_key(name, enckey).map(|_| ())
+    }
+
+    /// Rejects creating or signing transactions with the auth token of a wallet whose spends
+    /// need approval
+    fn check_spend_without_approval(&self, name: &str) -> Result<()> {
+        if self.approval_service.is_required(name)? {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "Spends of wallet {} need approval, transfers can only be requested",
+                    name
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Builds an unsigned transfer and queues it for approval, inputs of the transfers already
+    /// pending approval aren't selected again
+    #[allow(clippy::too_many_arguments)]
+    fn request_approval(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        only_from_addresses: Option<&[ExtendedAddr]>,
+        return_address: ExtendedAddr,
+        block_height: u64,
+    ) -> Result<TxId> {
+        let pendings = self.approval_service.get_pending(name, enckey)?;
+        let reserved_inputs = pendings
+            .iter()
+            .flat_map(|pending| pending.transaction.inputs.iter())
+            .collect::<BTreeSet<_>>();
+        let mut unspent_transactions = UnspentTransactions::new(
+            self.spendable_transactions_from(name, enckey, only_from_addresses)?
+                .unwrap()
+                .into_iter()
+                .filter(|(input, _)| !reserved_inputs.contains(input))
+                .collect(),
+        );
+        unspent_transactions.apply_all(InputSelectionStrategy::default().as_ref());
+
+        let (transaction, spent_outputs, return_amount) = self
+            .transaction_builder
+            .plan_transfer_tx(unspent_transactions, outputs, return_address, attributes)?;
+        let transaction_id = transaction.id();
+        self.approval_service.add_pending(
+            name,
+            enckey,
+            PendingApproval {
+                transaction_id: hex::encode(transaction_id),
+                transaction,
+                spent_outputs,
+                return_amount,
+                block_height,
+            },
+        )?;
+        Ok(transaction_id)
+    }
+
+    /// Applies a wallet event of a primary device
+    fn apply_wallet_event(&self, name: &str, enckey: &SecKey, event: &WalletEvent) -> Result<()> {
+        match event {
+            WalletEvent::TransferKey { public_key } => {
+                if !self
+                    .wallet_service
+                    .public_keys(name, enckey)?
+                    .contains(public_key)
+                {
+                    self.wallet_service
+                        .add_public_key(name, enckey, public_key)?;
+                    self.new_watch_transfer_address(name, enckey, public_key)?;
+                }
+            }
+            WalletEvent::StakingKey { public_key } => {
+                if !self.staking_keys(name, enckey)?.contains(public_key) {
+                    self.wallet_service
+                        .add_staking_key(name, enckey, public_key)?;
+                }
+            }
+            WalletEvent::Annotation {
+                transaction_id,
+                annotation,
+            } => {
+                self.annotation_service.set_annotation(
+                    name,
+                    enckey,
+                    *transaction_id,
+                    annotation.clone(),
+                )?;
+            }
+            WalletEvent::PendingTransaction {
+                transaction_id,
+                pending,
+            } => {
+                let mut wallet_state_memento = WalletStateMemento::default();
+                wallet_state_memento.add_pending_transaction(*transaction_id, pending.clone());
+                self.wallet_state_service
+                    .apply_memento(name, enckey, &wallet_state_memento)?;
+            }
+            // synced height of the primary device is tracked by `DeviceSyncService`
+            WalletEvent::SyncCheckpoint { .. } => {}
+        }
+        Ok(())
+    }
 }
 
 impl<S> DefaultWalletClient<S, UnauthorizedClient, UnauthorizedWalletTransactionBuilder>
@@ -144,7 +438,9 @@ where
     C: Client,
     T: WalletTransactionBuilder,
 {
-    // new_address: transfer address in TxOut, it will check whether it belongs with 20 window, then it will create 20 new addresses
+    // new_address: transfer address in TxOut, it will check whether it belongs with the next
+    // `gap_limit` addresses, then it will create the addresses up to the matching one, so that the
+    // next ones are checked afterwards
     // return: true means new addresses are generated, so need to refresh current wallet state to bring new addresses
     // return: false mean no new addresses, don't need to refresh wallet state
     fn recover_addresses(
@@ -153,6 +449,7 @@ where
         name: &str,
         enckey: &SecKey,
         _wallet: &mut Wallet,
+        gap_limit: u32,
     ) -> Result<bool> {
         let is_exist = self
             .wallet_service
@@ -163,38 +460,99 @@ where
             return Ok(false);
         }
 
-        let index = self
-            .hd_key_service
-            .get_latest_transfer_index(name, enckey)?;
-        let mut found = false;
-        let count = 20;
-        for i in index..(index + count) {
-            let publickey = self.hd_key_service.peek_pubkey(name, enckey, i)?;
-            let (h256, _multisigaddr) = RootHashService::<S>::peek_new_root_hash(
-                vec![publickey.clone()],
-                publickey.clone(),
-                1,
-            )?;
-            assert!(32 == h256.len());
-            let extended_addr_from_hash = ExtendedAddr::OrTree(h256);
-            if extended_addr == &extended_addr_from_hash {
-                found = true;
-                break;
+        let found = self.find_in_recovery_window(
+            name,
+            enckey,
+            HDAccountType::Transfer,
+            gap_limit,
+            |publickey| {
+                let (h256, _multisigaddr) = RootHashService::<S>::peek_new_root_hash(
+                    vec![publickey.clone()],
+                    publickey.clone(),
+                    1,
+                )?;
+                Ok(extended_addr == &ExtendedAddr::OrTree(h256))
+            },
+        )?;
+
+        match found {
+            Some(count) => {
+                for _i in 0..count {
+                    self.new_transfer_address(name, enckey)?;
+                }
+                Ok(true)
             }
+            None => Ok(false),
         }
+    }
 
-        if !found {
+    // staking_address: staking address of DepositStakeTransaction, it's recovered like the
+    // transfer addresses
+    fn recover_staking_address(
+        &mut self,
+        staking_address: &StakedStateAddress,
+        name: &str,
+        enckey: &SecKey,
+        gap_limit: u32,
+    ) -> Result<bool> {
+        let StakedStateAddress::BasicRedeem(redeem_address) = staking_address;
+        let is_exist = self
+            .wallet_service
+            .find_staking_key(name, enckey, redeem_address)?
+            .is_some();
+        if is_exist {
             return Ok(false);
         }
 
-        let count = count;
-        for _i in 0..count {
-            let _newaddress: ExtendedAddr = self
-                .new_transfer_address(name, enckey)
-                .expect("get new transfer address");
+        let found = self.find_in_recovery_window(
+            name,
+            enckey,
+            HDAccountType::Staking,
+            gap_limit,
+            |publickey| Ok(redeem_address == &RedeemAddress::from(publickey)),
+        )?;
+
+        match found {
+            Some(count) => {
+                for _i in 0..count {
+                    self.new_staking_address(name, enckey)?;
+                }
+                Ok(true)
+            }
+            None => Ok(false),
         }
+    }
+}
 
-        Ok(true)
+impl<S, C, T> DefaultWalletClient<S, C, T>
+where
+    S: Storage + 'static,
+    C: Client,
+    T: WalletTransactionBuilder,
+{
+    /// Checks the next `gap_limit` keys of the account, returns the number of keys to derive to
+    /// reach the matching one
+    fn find_in_recovery_window<F>(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        account_type: HDAccountType,
+        gap_limit: u32,
+        matches: F,
+    ) -> Result<Option<u32>>
+    where
+        F: Fn(&PublicKey) -> Result<bool>,
+    {
+        let window =
+            self.hd_key_service
+                .peek_recovery_window(name, enckey, account_type, gap_limit)?;
+        // the window starts right after the last derived key
+        for (position, (_index, publickey)) in window.iter().enumerate() {
+            if matches(publickey)? {
+                return Ok(Some(position as u32 + 1));
+            }
+        }
+        Ok(None)
     }
 }
 
@@ -226,6 +584,15 @@ where
                 let ledger_service = LedgerService::new(true)?;
                 HwKeyService::Ledger(ledger_service)
             }
+            #[cfg(feature = "pkcs11")]
+            HardwareKind::Pkcs11 => HwKeyService::Pkcs11(Pkcs11Service::from_env()?),
+            #[cfg(not(feature = "pkcs11"))]
+            HardwareKind::Pkcs11 => {
+                return Err(Error::new(
+                    ErrorKind::InitializationError,
+                    "PKCS#11 support is not enabled, rebuild with `pkcs11` feature",
+                ))
+            }
         };
         self.hw_key_service = hw_key_service;
         Ok(())
@@ -249,28 +616,34 @@ where
         address: ExtendedAddr,
         view_keys: &mut BTreeSet<PublicKey>,
         network_id: u8,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+        only_from_addresses: Option<&[ExtendedAddr]>,
     ) -> Result<TxId> {
         let current_block_height = self.get_current_block_height()?;
         let tx_out = TxOut::new(address, amount);
-
-        let view_key = self.view_key(name, enckey)?;
-
-        view_keys.insert(view_key);
-
-        let access_policies: BTreeSet<_> = view_keys
-            .iter()
-            .map(|key| TxAccessPolicy {
-                view_key: key.into(),
-                access: TxAccess::AllData,
-            })
-            .collect();
-
-        let attributes =
-            TxAttributes::new_with_access(network_id, access_policies.into_iter().collect());
+        let attributes = self.transfer_attributes(name, enckey, view_keys, network_id)?;
 
         let return_address = self.new_transfer_address(name, enckey)?;
-        let (transaction, selected_inputs, return_amount) =
-            self.create_transaction(name, enckey, vec![tx_out], attributes, None, return_address)?;
+        if self.approval_service.is_required(name)? {
+            return self.request_approval(
+                name,
+                enckey,
+                vec![tx_out],
+                attributes,
+                only_from_addresses,
+                return_address,
+                current_block_height,
+            );
+        }
+        let (transaction, reference, selected_inputs, return_amount) = self.create_transaction(
+            name,
+            enckey,
+            vec![tx_out],
+            attributes,
+            input_selection_strategy,
+            only_from_addresses,
+            return_address,
+        )?;
 
         self.broadcast_transaction(&transaction)?;
         //update the wallet state
@@ -280,20 +653,8 @@ where
             return_amount,
         };
 
-        self.update_tx_pending_state(name, enckey, transaction.tx_id(), tx_pending)?;
-
-        if let TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
-            payload: TxObfuscated { txid, .. },
-            ..
-        }) = transaction
-        {
-            Ok(txid)
-        } else {
-            Err(Error::new(
-                ErrorKind::IllegalInput,
-                "Transaction is not transfer transaction",
-            ))
-        }
+        self.update_tx_pending_state(name, enckey, reference.txid, tx_pending)?;
+        Ok(reference.txid)
     }
 
     /// broadcast transaction and waiting it confiremed
@@ -306,7 +667,10 @@ where
         view_keys: &mut BTreeSet<PublicKey>,
         network_id: u8,
     ) -> Result<TxId> {
-        let tx_id = self.send_to_address(name, enckey, amount, address, view_keys, network_id)?;
+        self.check_spend_without_approval(name)?;
+        let tx_id = self.send_to_address(
+            name, enckey, amount, address, view_keys, network_id, None, None,
+        )?;
         let block_height = self.get_current_block_height()?;
         loop {
             // query tx_id from tendermint
@@ -415,9 +779,7 @@ where
             ));
         }
         check_passphrase_strength(name, passphrase)?;
-        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
-            "unable to derive encryption key from passphrase"
-        })?;
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
         let view_key = PublicKey::from(&wallet_info.private_key);
         if view_key != wallet_info.wallet.view_key {
             return Err(Error::new(ErrorKind::InvalidInput, "public key not match"));
@@ -495,9 +857,7 @@ where
     ) -> Result<(SecKey, Option<Mnemonic>)> {
         check_passphrase_strength(name, passphrase)?;
 
-        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
-            "unable to derive encryption key from passphrase"
-        })?;
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
 
         match wallet_kind {
             WalletKind::Basic => {
@@ -509,6 +869,7 @@ where
 
                 self.wallet_service
                     .create(name, &enckey, view_key, wallet_kind, hardware_kind)?;
+                mark_brand_new(&self.storage, name)?;
 
                 Ok((enckey, None))
             }
@@ -532,11 +893,13 @@ where
                     wallet_kind,
                     hardware_kind,
                 )?;
+                mark_brand_new(&self.storage, name)?;
 
                 Ok((enckey, Some(mnemonic)))
             }
             WalletKind::HW => {
                 // the view-key pair is the local key pair, not come from the hardware wallet.
+                // the keys of the device may have history, so it's not marked as brand-new
                 let private_key = PrivateKey::new()?;
                 let view_key = PublicKey::from(&private_key);
                 self.hd_key_service.add_mnemonic(name, None, &enckey)?;
@@ -548,6 +911,10 @@ where
 
                 Ok((enckey, None))
             }
+            WalletKind::WatchOnly => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Watch-only wallets are created from public keys with new_watch_only_wallet",
+            )),
         }
     }
 
@@ -559,9 +926,7 @@ where
     ) -> Result<SecKey> {
         check_passphrase_strength(name, passphrase)?;
 
-        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
-            "unable to derive encryption key from passphrase"
-        })?;
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
 
         self.hd_key_service
             .add_mnemonic(name, Some(mnemonic), &enckey)?;
@@ -591,9 +956,7 @@ where
     ) -> Result<SecKey> {
         check_passphrase_strength(name, passphrase)?;
 
-        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
-            "unable to derive encryption key from passphrase"
-        })?;
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
 
         let view_key = PublicKey::from(view_key_priv);
         self.key_service
@@ -608,33 +971,66 @@ where
         Ok(enckey)
     }
 
-    fn delete_wallet(&self, name: &str, passphrase: &SecUtf8) -> Result<()> {
-        // remove from wallet/sync_state/wallet_state/key_service
+    fn new_watch_only_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        view_key: &PublicKey,
+        transfer_public_keys: &[PublicKey],
+        staking_public_keys: &[PublicKey],
+    ) -> Result<SecKey> {
+        check_passphrase_strength(name, passphrase)?;
 
-        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
-            "unable to derive encryption key from passphrase"
-        })?;
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
+
+        self.wallet_service.create(
+            name,
+            &enckey,
+            view_key.clone(),
+            WalletKind::WatchOnly,
+            HardwareKind::LocalOnly,
+        )?;
+        for public_key in transfer_public_keys {
+            self.new_watch_transfer_address(name, &enckey, public_key)?;
+        }
+        for public_key in staking_public_keys {
+            self.new_watch_staking_address(name, &enckey, public_key)?;
+        }
+        Ok(enckey)
+    }
+
+    fn delete_wallet(&self, name: &str, passphrase: &SecUtf8) -> Result<()> {
+        // an interrupted re-encryption is rolled back (and its journal removed) first, so that
+        // the passphrase is checked against consistent records
+        self.rekey_service.recover(name)?;
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
 
         // the passphrase is verified here.
+        self.verify_enckey(name, &enckey)?;
+
+        // everything which can fail on reading is done before deleting anything
+        #[cfg(feature = "experimental")]
+        let session_ids = self.multi_sig_session_service.session_ids(&enckey)?;
+
+        // the wallet disappears at once by removing its record, the rest is removed afterwards
+        // from the same keyspaces `StorageMaintenanceService` checks (if that fails, the
+        // remaining records are reported as orphans)
         self.wallet_service.delete(name, &enckey)?;
-        self.sync_state_service.delete_global_state(name)?;
-        self.wallet_state_service
-            .delete_wallet_state(name, &enckey)?;
-        if self.hd_key_service.has_wallet(name)? {
-            self.hd_key_service.delete_wallet(name, &enckey)?;
+        delete_wallet_records(&self.storage, name)?;
+        self.passphrase_attempt_service.record_success(name)?;
+        #[cfg(feature = "experimental")]
+        for session_id in session_ids.iter() {
+            self.multi_sig_session_service.delete_session(session_id)?;
         }
-        self.key_service.delete_wallet_private_key(name, &enckey)?;
 
         Ok(())
     }
 
     fn auth_token(&self, name: &str, passphrase: &SecUtf8) -> Result<SecKey> {
-        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
-            "unable to derive encryption key from passphrase"
-        })?;
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
 
         // test validity of enckey
-        self.view_key(name, &enckey)?;
+        self.verify_enckey(name, &enckey)?;
         Ok(enckey)
     }
 
@@ -719,6 +1115,7 @@ where
         enckey: &SecKey,
         wallet_kind: WalletKind,
     ) -> Result<Option<PrivateKey>> {
+        wallet_kind.ensure_can_sign()?;
         if wallet_kind != WalletKind::HW {
             let k = self.key_service.wallet_private_key(name, enckey)?;
             Ok(k)
@@ -733,7 +1130,9 @@ where
         enckey: &SecKey,
         public_key: &PublicKey,
     ) -> Result<Box<dyn PrivateKeyAction>> {
+        self.check_spend_without_approval(name)?;
         let wallet = self.wallet_service.get_wallet_info(name, enckey)?;
+        wallet.wallet_kind.ensure_can_sign()?;
         match wallet.wallet_kind {
             WalletKind::HW => {
                 let chain_path = self
@@ -770,6 +1169,7 @@ where
         public_key: &PublicKey,
     ) -> Result<Option<PrivateKey>> {
         let wallet = self.wallet_service.get_wallet_info(name, enckey)?;
+        wallet.wallet_kind.ensure_can_sign()?;
         match wallet.wallet_kind {
             WalletKind::HW => unreachable!("can not get private key in hw wallet"),
             _ => self
@@ -830,6 +1230,7 @@ where
                     .add_key_path(name, enckey, &public_key, &hd_path)?;
                 Ok(public_key)
             }
+            WalletKind::WatchOnly => Err(watch_only_key_generation_error()),
         }
     }
 
@@ -839,6 +1240,7 @@ where
             .chain(|| (ErrorKind::IoError, "Unable to flush sled"))?;
         Ok(())
     }
+
     fn new_staking_address(&self, name: &str, enckey: &SecKey) -> Result<StakedStateAddress> {
         let wallet = self.wallet_service.get_wallet_info(name, enckey)?;
         let public_key = match wallet.wallet_kind {
@@ -868,10 +1270,18 @@ where
                     .add_key_path(name, enckey, &public_key, &hd_path)?;
                 public_key
             }
+            WalletKind::WatchOnly => return Err(watch_only_key_generation_error()),
         };
 
         self.wallet_service
             .add_staking_key(name, enckey, &public_key)?;
+        self.device_sync_service.record_event(
+            name,
+            enckey,
+            WalletEvent::StakingKey {
+                public_key: public_key.clone(),
+            },
+        )?;
 
         Ok(StakedStateAddress::BasicRedeem(RedeemAddress::from(
             &public_key,
@@ -907,9 +1317,17 @@ where
                     .add_key_path(name, enckey, &public_key, &hd_path)?;
                 public_key
             }
+            WalletKind::WatchOnly => return Err(watch_only_key_generation_error()),
         };
         self.wallet_service
             .add_public_key(name, enckey, &public_key)?;
+        self.device_sync_service.record_event(
+            name,
+            enckey,
+            WalletEvent::TransferKey {
+                public_key: public_key.clone(),
+            },
+        )?;
 
         let ret = self.new_multisig_transfer_address(
             name,
@@ -1028,29 +1446,43 @@ where
     fn balance(&self, name: &str, enckey: &SecKey) -> Result<WalletBalance> {
         // Check if wallet exists
         self.wallet_service.view_key(name, enckey)?;
-        self.wallet_state_service.get_balance(name, enckey)
+        let mut balance = self.wallet_state_service.get_balance(name, enckey)?;
+        balance.reserved = self.reservation_service.reserved_amount(name, enckey)?;
+        balance.available = (balance.available - balance.reserved).unwrap_or_default();
+        Ok(balance)
     }
 
     fn history(
         &self,
         name: &str,
         enckey: &SecKey,
+        filter: TransactionHistoryFilter,
         offset: usize,
         limit: usize,
         reversed: bool,
-    ) -> Result<Vec<TransactionChange>> {
+    ) -> Result<TransactionHistory> {
         // Check if wallet exists
         self.wallet_service.view_key(name, enckey)?;
 
-        let history = self
-            .wallet_state_service
-            .get_transaction_history(name, enckey, reversed)?
-            .filter(|change| BalanceChange::NoChange != change.balance_change)
-            .skip(offset)
-            .take(limit)
-            .collect::<Vec<_>>();
+        // the filter also skips transactions without balance change
+        let mut total = 0;
+        let mut transactions = Vec::new();
+        for change in self.wallet_state_service.get_transaction_history(
+            name,
+            enckey,
+            reversed,
+            Some(filter),
+        )? {
+            if total >= offset && transactions.len() < limit {
+                transactions.push(change);
+            }
+            total += 1;
+        }
 
-        Ok(history)
+        Ok(TransactionHistory {
+            total,
+            transactions,
+        })
     }
 
     #[inline]
@@ -1125,19 +1557,78 @@ where
         outputs: Vec<TxOut>,
         attributes: TxAttributes,
         input_selection_strategy: Option<InputSelectionStrategy>,
+        only_from_addresses: Option<&[ExtendedAddr]>,
         return_address: ExtendedAddr,
-    ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
-        let mut unspent_transactions = self.unspent_transactions(name, enckey)?;
-        unspent_transactions.apply_all(input_selection_strategy.unwrap_or_default().as_ref());
+    ) -> Result<(TxAux, TransactionReference, Vec<TxoPointer>, Coin)> {
+        self.check_spend_without_approval(name)?;
+        let mut unspent_transactions =
+            self.spendable_transactions_from(name, enckey, only_from_addresses)?;
+        self.apply_input_selection_strategy(
+            &mut unspent_transactions,
+            input_selection_strategy.unwrap_or_default(),
+            &outputs,
+            &attributes,
+        )?;
 
-        self.transaction_builder.build_transfer_tx(
-            name,
-            enckey,
+        let (transaction, selected_inputs, return_amount) =
+            self.transaction_builder.build_transfer_tx(
+                name,
+                enckey,
+                unspent_transactions,
+                outputs,
+                return_address,
+                attributes,
+            )?;
+        let reference = TransactionReference::new(&transaction);
+        Ok((transaction, reference, selected_inputs, return_amount))
+    }
+
+    fn estimate_fee(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+    ) -> Result<Coin> {
+        let mut unspent_transactions = self.spendable_transactions(name, enckey)?;
+        self.apply_input_selection_strategy(
+            &mut unspent_transactions,
+            input_selection_strategy.unwrap_or_default(),
+            &outputs,
+            &attributes,
+        )?;
+
+        // the change output only affects the fee by its size, which is the same for all transfer
+        // addresses, so no new address is generated for a dry run
+        let return_address = ExtendedAddr::OrTree([0; 32]);
+        let (transaction, spent_outputs, _) = self.transaction_builder.plan_transfer_tx(
             unspent_transactions,
             outputs,
             return_address,
             attributes,
-        )
+        )?;
+
+        let input_value =
+            sum_coins(spent_outputs.iter().map(|output| output.value)).chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Sum of input values exceeds maximum",
+                )
+            })?;
+        let output_value =
+            sum_coins(transaction.outputs.iter().map(|output| output.value)).chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Sum of output values exceeds maximum",
+                )
+            })?;
+        (input_value - output_value).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Sum of output values exceeds sum of input values",
+            )
+        })
     }
 
     #[inline]
@@ -1146,13 +1637,22 @@ where
             .broadcast_transaction(&tx_aux.encode())
     }
 
-    fn export_plain_tx(&self, name: &str, enckey: &SecKey, txid: &str) -> Result<TransactionInfo> {
-        let txid = str2txid(txid).chain(|| (ErrorKind::InvalidInput, "invalid transaction id"))?;
-        let tx = self.get_transaction(name, enckey, txid)?;
+    fn broadcast_transactions(
+        &self,
+        tx_auxs: &[TxAux],
+    ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+        let transactions = tx_auxs.iter().map(Encode::encode).collect::<Vec<_>>();
+        self.tendermint_client
+            .broadcast_transaction_batch(transactions.iter().map(Vec::as_slice))
+    }
+
+    fn export_plain_tx(&self, name: &str, enckey: &SecKey, txid: &str) -> Result<TransactionInfo> {
+        let txid = str2txid(txid).chain(|| (ErrorKind::InvalidInput, "invalid transaction id"))?;
+        let tx = self.get_transaction(name, enckey, txid)?;
         // get the block height
         let tx_change = self
             .wallet_state_service
-            .get_transaction_history(name, enckey, false)?
+            .get_transaction_history(name, enckey, false, None)?
             .filter(|change| BalanceChange::NoChange != change.balance_change)
             .find(|tx_change| tx_change.transaction_id == tx.id())
             .chain(|| {
@@ -1182,22 +1682,26 @@ where
         }
 
         // check if the output is spent or not
-        let v = self
-            .tendermint_client
-            .query("meta", &tx_info.tx.id().to_vec(), None, false)?
-            .bytes();
-        let bit_flag = BitVec::from_bytes(&v);
-        let spent_flags: Result<Vec<bool>> = tx_info
-            .tx
-            .outputs()
-            .iter()
-            .enumerate()
-            .map(|(index, _output)| {
-                bit_flag
-                    .get(index)
-                    .chain(|| (ErrorKind::InvalidInput, "check failed in enclave"))
-            })
-            .collect();
+        let spent_flags = if self.require_verified_queries {
+            self.verified_spent_flags(name, enckey, &tx_info)?
+        } else {
+            let v = self
+                .tendermint_client
+                .query("meta", &tx_info.tx.id().to_vec(), None, false)?
+                .bytes();
+            let bit_flag = BitVec::from_bytes(&v);
+            tx_info
+                .tx
+                .outputs()
+                .iter()
+                .enumerate()
+                .map(|(index, _output)| {
+                    bit_flag
+                        .get(index)
+                        .chain(|| (ErrorKind::InvalidInput, "check failed in enclave"))
+                })
+                .collect::<Result<Vec<bool>>>()?
+        };
         let mut memento = WalletStateMemento::default();
         // check if tx belongs to the block
         let block = self.tendermint_client.block(tx_info.block_height)?;
@@ -1221,7 +1725,7 @@ where
             &tx_info,
             *paid_fee.expect("tx fee checked above"),
             block.header.time,
-            spent_flags?,
+            spent_flags,
         )
         .chain(|| (ErrorKind::InvalidInput, "import error"))?;
 
@@ -1241,6 +1745,36 @@ where
         Ok(current_block_height)
     }
 
+    fn mempool_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        limit: u64,
+    ) -> Result<Vec<MempoolTransaction>> {
+        let unspent_transactions = self.unspent_transactions(name, enckey)?;
+        let staking_addresses = self.staking_addresses(name, enckey, 0, 0, false)?;
+
+        let transactions = self
+            .tendermint_client
+            .unconfirmed_txs(limit)?
+            .transactions()
+            .iter()
+            .filter_map(|tx_aux| {
+                MempoolTransaction::for_wallet(
+                    tx_aux,
+                    |pointer| {
+                        unspent_transactions
+                            .iter()
+                            .find(|(unspent, _)| unspent == pointer)
+                            .map(|(_, output)| output.clone())
+                    },
+                    |address| staking_addresses.contains(address),
+                )
+            })
+            .collect();
+        Ok(transactions)
+    }
+
     fn update_tx_pending_state(
         &self,
         name: &str,
@@ -1249,9 +1783,17 @@ where
         tx_pending: TransactionPending,
     ) -> Result<()> {
         let mut wallet_state_memento = WalletStateMemento::default();
-        wallet_state_memento.add_pending_transaction(tx_id, tx_pending);
+        wallet_state_memento.add_pending_transaction(tx_id, tx_pending.clone());
         self.wallet_state_service
-            .apply_memento(name, enckey, &wallet_state_memento)
+            .apply_memento(name, enckey, &wallet_state_memento)?;
+        self.device_sync_service.record_event(
+            name,
+            enckey,
+            WalletEvent::PendingTransaction {
+                transaction_id: tx_id,
+                pending: tx_pending,
+            },
+        )
     }
 
     fn build_raw_transfer_tx(
@@ -1263,7 +1805,7 @@ where
         view_keys: Vec<PublicKey>,
         network_id: u8,
     ) -> Result<UnsignedTransferTransaction> {
-        let unspent_transactions = self.unspent_transactions(name, enckey)?;
+        let unspent_transactions = self.spendable_transactions(name, enckey)?;
         let return_address = self.new_transfer_address(name, enckey)?;
         let unsigned = UnsignedTransferTransaction {
             unspent_transactions,
@@ -1282,22 +1824,12 @@ where
         enckey: &SecKey,
         unsigned_tx: UnsignedTransferTransaction,
     ) -> Result<SignedTransferTransaction> {
+        self.check_spend_without_approval(name)?;
         let tx_out = TxOut::new(unsigned_tx.to_address, unsigned_tx.amount);
-        let view_key = self.view_key(name, enckey)?;
-        let mut view_keys = unsigned_tx.view_keys;
-        view_keys.push(view_key);
-        let access_policies: BTreeSet<_> = view_keys
-            .iter()
-            .map(|key| TxAccessPolicy {
-                view_key: key.into(),
-                access: TxAccess::AllData,
-            })
-            .collect();
-
-        let attributes = TxAttributes::new_with_access(
-            unsigned_tx.network_id,
-            access_policies.into_iter().collect(),
-        );
+        let attributes = AttributesBuilder::with_network_id(unsigned_tx.network_id)
+            .view_keys(unsigned_tx.view_keys)
+            .wallet_view_key(self, name, enckey)?
+            .build()?;
 
         let return_address = unsigned_tx.return_address.clone();
 
@@ -1324,6 +1856,7 @@ where
         enckey: &SecKey,
         signed_tx: SignedTransferTransaction,
     ) -> Result<TxId> {
+        self.check_spend_without_approval(name)?;
         let current_block_height = self.get_current_block_height()?;
 
         self.broadcast_transaction(&signed_tx.signed_transaction)?;
@@ -1353,6 +1886,94 @@ where
         }
     }
 
+    fn build_unsigned_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+        return_address: ExtendedAddr,
+    ) -> Result<UnsignedTransferTx> {
+        let mut unspent_transactions = self.spendable_transactions(name, enckey)?;
+        self.apply_input_selection_strategy(
+            &mut unspent_transactions,
+            input_selection_strategy.unwrap_or_default(),
+            &outputs,
+            &attributes,
+        )?;
+
+        let (transaction, spent_outputs, return_amount) = self
+            .transaction_builder
+            .plan_transfer_tx(unspent_transactions, outputs, return_address, attributes)?;
+        Ok(UnsignedTransferTx::new(
+            transaction,
+            spent_outputs,
+            return_amount,
+        ))
+    }
+
+    fn sign_unsigned_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_tx: &UnsignedTransferTx,
+    ) -> Result<TxWitness> {
+        self.check_spend_without_approval(name)?;
+        self.transaction_builder
+            .sign_unsigned_transfer_tx(name, enckey, unsigned_tx)
+    }
+
+    fn sign_raw_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        raw_tx: &PartiallySignedTransferTx,
+    ) -> Result<PartiallySignedTransferTx> {
+        self.check_spend_without_approval(name)?;
+        self.verify_enckey(name, enckey)?;
+        let spent_outputs = raw_tx
+            .transaction
+            .inputs
+            .iter()
+            .map(|input| self.wallet_state_service.get_output(name, enckey, input))
+            .collect::<Result<Vec<_>>>()?;
+        let witnesses = self.transaction_builder.sign_transfer_inputs(
+            name,
+            enckey,
+            &raw_tx.transaction,
+            &spent_outputs,
+        )?;
+
+        let mut signed_tx = raw_tx.clone();
+        for (index, (spent_output, witness)) in spent_outputs.into_iter().zip(witnesses).enumerate()
+        {
+            if let (Some(spent_output), Some(witness)) = (spent_output, witness) {
+                signed_tx.add_signed_input(index, spent_output, witness)?;
+            }
+        }
+        Ok(signed_tx)
+    }
+
+    fn import_transaction_witness(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_tx: &UnsignedTransferTx,
+        witness: TxWitness,
+    ) -> Result<SignedTransferTransaction> {
+        self.verify_enckey(name, enckey)?;
+        unsigned_tx.verify_sighashes()?;
+        let signed_transaction = self
+            .transaction_builder
+            .finalize_unsigned_transfer_tx(unsigned_tx, witness)?;
+        Ok(SignedTransferTransaction {
+            signed_transaction,
+            return_amount: unsigned_tx.return_amount,
+            used_inputs: unsigned_tx.transaction.inputs.clone(),
+        })
+    }
+
     fn get_sync_state(&self, name: &str) -> Result<SyncState> {
         let mstate = self.sync_state_service.get_global_state(name)?;
         let sync_state = if let Some(sync_state) = mstate {
@@ -1364,164 +1985,959 @@ where
     }
 }
 
-#[cfg(feature = "experimental")]
-impl<S, C, T> MultiSigWalletClient for DefaultWalletClient<S, C, T>
+impl<S, C, T> WalletRekeyClient for DefaultWalletClient<S, C, T>
 where
     S: Storage + 'static,
     C: Client,
     T: WalletTransactionBuilder,
 {
-    fn schnorr_signature(
+    fn rekey_wallet(
         &self,
         name: &str,
-        enckey: &SecKey,
-        tx: &Transaction,
-        public_key: &PublicKey,
-    ) -> Result<SchnorrSignature> {
-        // To verify if the enckey is correct or not
-        self.transfer_addresses(name, enckey)?;
-        let wallet = self.wallet_service.get_wallet_info(name, enckey)?;
-        let sign_key = match wallet.wallet_kind {
-            WalletKind::HW => {
-                let chain_path = self
-                    .wallet_service
-                    .find_chain_path(name, enckey, public_key)?
-                    .chain(|| (ErrorKind::InvalidInput, "can not find chain path"))?;
-                self.hw_key_service.get_sign_key(&chain_path)?
-            }
-            _ => {
-                let private_key = self
-                    .wallet_service
-                    .find_private_key(name, enckey, public_key)?
-                    .chain(|| (ErrorKind::InvalidInput, ""))?;
-                Box::new(private_key)
-            }
-        };
-        sign_key.schnorr_sign(tx)
+        old_passphrase: &SecUtf8,
+        new_passphrase: &SecUtf8,
+        kdf_config: KdfConfig,
+    ) -> Result<SecKey> {
+        let old_enckey = self.derive_wallet_enckey(name, old_passphrase)?;
+        self.verify_enckey(name, &old_enckey)?;
+        check_passphrase_strength(name, new_passphrase)?;
+        kdf_config.validate()?;
+
+        let new_enckey = derive_enckey_with(new_passphrase, name, &kdf_config)
+            .err_kind(ErrorKind::InvalidInput, || {
+                "unable to derive encryption key from passphrase"
+            })?;
+        self.rekey_service
+            .rekey(name, &old_enckey, &new_enckey, &kdf_config)?;
+        Ok(new_enckey)
     }
 
-    fn new_multi_sig_session(
+    fn change_passphrase(
+        &self,
+        name: &str,
+        old_passphrase: &SecUtf8,
+        new_passphrase: &SecUtf8,
+    ) -> Result<SecKey> {
+        // rolls back an interrupted re-encryption before the current parameters are loaded
+        self.rekey_service.recover(name)?;
+        let kdf_config = load_kdf_config(&self.storage, name)?;
+        self.rekey_wallet(name, old_passphrase, new_passphrase, kdf_config)
+    }
+
+    #[inline]
+    fn passphrase_lockout_status(&self, name: &str) -> Result<LockoutStatus> {
+        self.passphrase_attempt_service.status(name)
+    }
+}
+
+impl<S, C, T> WalletMaintenanceClient for DefaultWalletClient<S, C, T>
+where
+    S: Storage + 'static,
+    C: Client,
+    T: WalletTransactionBuilder,
+{
+    fn export_wallet_snapshot(
         &self,
         name: &str,
         enckey: &SecKey,
-        message: H256,
-        signer_public_keys: Vec<PublicKey>,
-        self_public_key: PublicKey,
-    ) -> Result<H256> {
-        // To verify if the enckey is correct or not
-        self.transfer_addresses(name, enckey)?;
-        //        let sign_key = self.sign_key(name, enckey, &self_public_key)?;
+        include_hd_seed: bool,
+    ) -> Result<WalletExport> {
+        let mut info = self.export_wallet(name, enckey)?;
+        if !include_hd_seed {
+            info.hdkey = None;
+        }
+        let wallet_state = load_wallet_state(&self.storage, name, enckey)?.unwrap_or_default();
 
-        let self_private_key = self
-            .private_key(name, enckey, &self_public_key)?
-            .chain(|| {
-                (
-                    ErrorKind::InvalidInput,
-                    format!(
-                        "Self public key ({}) is not owned by current wallet",
-                        self_public_key
-                    ),
-                )
-            })?;
+        Ok(WalletExport {
+            version: WALLET_EXPORT_VERSION,
+            info,
+            wallet_state,
+            sync_state: self.sync_state_service.get_global_state(name)?,
+            birth_height: load_birth_height(&self.storage, name)?,
+        })
+    }
 
-        self.multi_sig_session_service.new_session(
-            message,
-            signer_public_keys,
-            self_public_key,
-            self_private_key,
-            enckey,
-        )
+    fn import_wallet_snapshot(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        mut snapshot: WalletExport,
+    ) -> Result<SecKey> {
+        if snapshot.version > WALLET_EXPORT_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Unsupported wallet export version {} (latest supported version is {})",
+                    snapshot.version, WALLET_EXPORT_VERSION
+                ),
+            ));
+        }
+
+        snapshot.info.name = name.to_owned();
+        let enckey = self.import_wallet(name, passphrase, &mut snapshot.info)?;
+        save_wallet_state(&self.storage, name, &enckey, &snapshot.wallet_state)?;
+        if let Some(sync_state) = snapshot.sync_state {
+            self.sync_state_service
+                .save_global_state(name, &sync_state)?;
+        }
+        if let Some(birth_height) = snapshot.birth_height {
+            save_birth_height(&self.storage, name, birth_height)?;
+        }
+
+        self.storage
+            .flush()
+            .chain(|| (ErrorKind::IoError, "Unable to flush sled"))?;
+        Ok(enckey)
     }
 
-    fn nonce_commitment(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
-        self.multi_sig_session_service
-            .nonce_commitment(session_id, enckey)
+    fn device_pairing(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        device_name: &str,
+    ) -> Result<DevicePairing> {
+        self.verify_enckey(name, enckey)?;
+        self.device_sync_service
+            .device_pairing(name, enckey, device_name)
     }
 
-    fn add_nonce_commitment(
+    fn pair_device(
         &self,
-        session_id: &H256,
+        name: &str,
         enckey: &SecKey,
-        nonce_commitment: H256,
-        public_key: &PublicKey,
+        peer: DevicePairing,
+        role: DeviceRole,
     ) -> Result<()> {
-        self.multi_sig_session_service.add_nonce_commitment(
-            session_id,
-            nonce_commitment,
-            public_key,
-            enckey,
-        )
+        self.verify_enckey(name, enckey)?;
+        self.device_sync_service.pair(name, enckey, peer, role)
     }
 
-    fn nonce(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
-        self.multi_sig_session_service.nonce(session_id, enckey)
+    fn unpair_device(&self, name: &str, enckey: &SecKey, device_name: &str) -> Result<()> {
+        self.verify_enckey(name, enckey)?;
+        self.device_sync_service.unpair(name, enckey, device_name)
     }
 
-    fn add_nonce(
+    fn paired_devices(&self, name: &str, enckey: &SecKey) -> Result<Vec<PairedDevice>> {
+        self.verify_enckey(name, enckey)?;
+        self.device_sync_service.devices(name, enckey)
+    }
+
+    fn device_events(
         &self,
-        session_id: &H256,
+        name: &str,
         enckey: &SecKey,
-        nonce: &H256,
-        public_key: &PublicKey,
-    ) -> Result<()> {
-        self.multi_sig_session_service
-            .add_nonce(session_id, &nonce, public_key, enckey)
+        device_name: &str,
+        after: u64,
+    ) -> Result<Vec<EncryptedWalletEvent>> {
+        self.verify_enckey(name, enckey)?;
+        self.device_sync_service
+            .export_events(name, enckey, device_name, after)
     }
 
-    fn partial_signature(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
-        self.multi_sig_session_service
-            .partial_signature(session_id, enckey)
+    fn import_device_events(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        device_name: &str,
+        events: Vec<EncryptedWalletEvent>,
+    ) -> Result<u64> {
+        self.verify_enckey(name, enckey)?;
+        let last_sequence =
+            self.device_sync_service
+                .import_events(name, enckey, device_name, events, |event| {
+                    self.apply_wallet_event(name, enckey, event)
+                })?;
+        self.storage
+            .flush()
+            .chain(|| (ErrorKind::IoError, "Unable to flush sled"))?;
+        Ok(last_sequence)
     }
+}
 
-    fn add_partial_signature(
+impl<S, C, T> WalletAuditClient for DefaultWalletClient<S, C, T>
+where
+    S: Storage + 'static,
+    C: Client,
+    T: WalletTransactionBuilder,
+{
+    fn export_history(
         &self,
-        session_id: &H256,
+        name: &str,
         enckey: &SecKey,
-        partial_signature: H256,
-        public_key: &PublicKey,
-    ) -> Result<()> {
-        self.multi_sig_session_service.add_partial_signature(
-            session_id,
-            partial_signature,
-            public_key,
-            enckey,
-        )
+        format: HistoryExportFormat,
+        filter: TransactionHistoryFilter,
+    ) -> Result<String> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        let changes = self
+            .wallet_state_service
+            .get_transaction_history(name, enckey, false, Some(filter))?
+            .collect::<Vec<_>>();
+        export_history(format, changes.iter())
     }
 
-    fn signature(&self, session_id: &H256, enckey: &SecKey) -> Result<SchnorrSignature> {
-        self.multi_sig_session_service.signature(session_id, enckey)
+    fn annotations(&self, name: &str, enckey: &SecKey) -> Result<BTreeMap<TxId, TxAnnotation>> {
+        self.verify_enckey(name, enckey)?;
+        self.annotation_service.get_annotations(name, enckey)
     }
 
-    fn transaction(
+    fn set_annotation(
         &self,
         name: &str,
-        session_id: &H256,
         enckey: &SecKey,
-        unsigned_transaction: Tx,
-    ) -> Result<TxAux> {
-        if unsigned_transaction.inputs.len() != 1 {
+        transaction_id: TxId,
+        annotation: TxAnnotation,
+    ) -> Result<()> {
+        self.verify_enckey(name, enckey)?;
+        if self
+            .wallet_state_service
+            .get_transaction_change(name, enckey, &transaction_id)?
+            .is_none()
+        {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
-                "Multi-Sig Signing is only supported for transactions with only one input",
+                format!(
+                    "Transaction ({}) is not in wallet history",
+                    hex::encode(transaction_id)
+                ),
             ));
         }
+        self.annotation_service
+            .set_annotation(name, enckey, transaction_id, annotation.clone())?;
+        self.device_sync_service.record_event(
+            name,
+            enckey,
+            WalletEvent::Annotation {
+                transaction_id,
+                annotation,
+            },
+        )
+    }
 
-        let output_to_spend = self.output(name, enckey, &unsigned_transaction.inputs[0])?;
-        let root_hash = self
-            .wallet_service
-            .find_root_hash(name, enckey, &output_to_spend.address)?
-            .chain(|| {
-                (
-                    ErrorKind::IllegalInput,
-                    "Output address is not owned by current wallet; cannot spend output in given transaction",
-                )
-            })?;
-        let public_keys = self
-            .multi_sig_session_service
-            .public_keys(session_id, enckey)?;
-
-        let proof = self
-            .root_hash_service
+    fn set_transaction_memo(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: TxId,
+        memo: String,
+    ) -> Result<()> {
+        self.verify_enckey(name, enckey)?;
+        self.wallet_state_service
+            .set_transaction_memo(name, enckey, transaction_id, &memo)
+    }
+
+    fn import_annotations(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        entries: Vec<(TxId, TxAnnotation)>,
+        conflict: AnnotationConflict,
+    ) -> Result<AnnotationImportReport> {
+        self.verify_enckey(name, enckey)?;
+        let history = load_wallet_state(&self.storage, name, enckey)?
+            .map(|state| state.transaction_history)
+            .unwrap_or_default();
+
+        let mut unknown_transactions = Vec::new();
+        let entries: Vec<_> = entries
+            .into_iter()
+            .filter(|(transaction_id, _)| {
+                let known = history.contains_key(transaction_id);
+                if !known {
+                    unknown_transactions.push(hex::encode(transaction_id));
+                }
+                known
+            })
+            .collect();
+        let transaction_ids: BTreeSet<_> = entries
+            .iter()
+            .map(|(transaction_id, _)| *transaction_id)
+            .collect();
+
+        let mut report = self
+            .annotation_service
+            .import(name, enckey, entries, conflict)?;
+        report.unknown_transactions = unknown_transactions;
+
+        let mut annotations = self.annotation_service.get_annotations(name, enckey)?;
+        for transaction_id in transaction_ids {
+            let annotation = annotations.remove(&transaction_id).unwrap_or_default();
+            self.device_sync_service.record_event(
+                name,
+                enckey,
+                WalletEvent::Annotation {
+                    transaction_id,
+                    annotation,
+                },
+            )?;
+        }
+        Ok(report)
+    }
+
+    fn address_book(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<BTreeMap<String, AddressMetadata>> {
+        self.wallet_service.address_book(name, enckey)
+    }
+
+    fn get_address_metadata(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &str,
+    ) -> Result<Option<AddressMetadata>> {
+        let address = canonical_address(address)?;
+        self.wallet_service
+            .get_address_metadata(name, enckey, &address)
+    }
+
+    fn set_address_label(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &str,
+        label: &str,
+    ) -> Result<()> {
+        let address = canonical_address(address)?;
+        self.wallet_service
+            .set_address_label(name, enckey, &address, label)
+    }
+
+    #[inline]
+    fn operator_profile(&self, name: &str) -> Result<Option<OperatorProfile>> {
+        self.operator_profile_service.get_profile(name)
+    }
+
+    fn set_operator_profile(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_address: StakedStateAddress,
+    ) -> Result<()> {
+        let public_key = match staking_address {
+            StakedStateAddress::BasicRedeem(ref redeem_address) => self
+                .wallet_service
+                .find_staking_key(name, enckey, redeem_address)?,
+        };
+        if public_key.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Staking address {} is not found in wallet {}",
+                    staking_address, name
+                ),
+            ));
+        }
+
+        self.operator_profile_service
+            .set_profile(name, &OperatorProfile { staking_address })
+    }
+
+    fn alert_rules(&self, name: &str, enckey: &SecKey) -> Result<Vec<AlertRule>> {
+        self.verify_enckey(name, enckey)?;
+        self.alert_service.get_rules(name)
+    }
+
+    fn set_alert_rules(&self, name: &str, enckey: &SecKey, rules: Vec<AlertRule>) -> Result<()> {
+        self.verify_enckey(name, enckey)?;
+        self.alert_service.set_rules(name, rules)
+    }
+
+    fn alerts(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<Alert>> {
+        self.verify_enckey(name, enckey)?;
+        self.alert_service.get_alerts(name, offset, limit)
+    }
+}
+
+impl<S, C, T> WalletSpendControlClient for DefaultWalletClient<S, C, T>
+where
+    S: Storage + 'static,
+    C: Client,
+    T: WalletTransactionBuilder,
+{
+    fn set_spend_approval(&self, name: &str, passphrase: &SecUtf8, required: bool) -> Result<()> {
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
+
+        // the passphrase is verified here.
+        self.verify_enckey(name, &enckey)?;
+        self.approval_service.set_required(name, required)
+    }
+
+    #[inline]
+    fn pending_approvals(&self, name: &str, enckey: &SecKey) -> Result<Vec<PendingApproval>> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+        self.approval_service.get_pending(name, enckey)
+    }
+
+    fn approve_transaction(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        transaction_id: TxId,
+    ) -> Result<TxId> {
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
+
+        // the passphrase is verified here.
+        self.verify_enckey(name, &enckey)?;
+
+        let hex_transaction_id = hex::encode(transaction_id);
+        let pending = self
+            .approval_service
+            .find_pending(name, &enckey, &hex_transaction_id)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "No transfer with transaction id {} is pending approval",
+                        hex_transaction_id
+                    ),
+                )
+            })?;
+        let used_inputs = pending.transaction.inputs.clone();
+        if !self.has_unspent_transactions(name, &enckey, &used_inputs)? {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Inputs of the transfer were spent meanwhile, reject it and request it again",
+            ));
+        }
+
+        let current_block_height = self.get_current_block_height()?;
+        let transaction = self.transaction_builder.sign_planned_transfer_tx(
+            name,
+            &enckey,
+            pending.transaction,
+            pending.spent_outputs,
+        )?;
+        self.broadcast_transaction(&transaction)?;
+        self.approval_service
+            .remove_pending(name, &enckey, &hex_transaction_id)?;
+
+        //update the wallet state
+        let tx_pending = TransactionPending {
+            used_inputs,
+            block_height: current_block_height,
+            return_amount: pending.return_amount,
+        };
+        self.update_tx_pending_state(name, &enckey, transaction_id, tx_pending)?;
+        Ok(transaction_id)
+    }
+
+    fn reject_transaction(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        transaction_id: TxId,
+    ) -> Result<()> {
+        let enckey = self.derive_wallet_enckey(name, passphrase)?;
+
+        // the passphrase is verified here.
+        self.verify_enckey(name, &enckey)?;
+
+        let hex_transaction_id = hex::encode(transaction_id);
+        if !self
+            .approval_service
+            .remove_pending(name, &enckey, &hex_transaction_id)?
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "No transfer with transaction id {} is pending approval",
+                    hex_transaction_id
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn freeze_utxo(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<()> {
+        self.verify_enckey(name, enckey)?;
+        self.wallet_state_service.freeze_utxo(name, enckey, input)
+    }
+
+    fn unfreeze_utxo(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<()> {
+        self.verify_enckey(name, enckey)?;
+        self.wallet_state_service.unfreeze_utxo(name, enckey, input)
+    }
+
+    fn frozen_utxos(&self, name: &str, enckey: &SecKey) -> Result<Vec<TxoPointer>> {
+        self.verify_enckey(name, enckey)?;
+        Ok(self
+            .wallet_state_service
+            .get_frozen_utxos(name, enckey)?
+            .into_iter()
+            .collect())
+    }
+
+    fn reserve_balance(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        ttl: u64,
+        tag: String,
+    ) -> Result<BalanceReservation> {
+        self.verify_enckey(name, enckey)?;
+        let available = self
+            .wallet_state_service
+            .get_balance(name, enckey)?
+            .available;
+        self.reservation_service
+            .reserve(name, enckey, amount, ttl, tag, available)
+    }
+
+    fn reservations(&self, name: &str, enckey: &SecKey) -> Result<Vec<BalanceReservation>> {
+        self.verify_enckey(name, enckey)?;
+        self.reservation_service.get_reservations(name, enckey)
+    }
+
+    fn release_reservation(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        id: u64,
+    ) -> Result<BalanceReservation> {
+        self.verify_enckey(name, enckey)?;
+        self.reservation_service.release(name, enckey, id)
+    }
+
+    fn release_reservations_by_tag(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tag: &str,
+    ) -> Result<Vec<BalanceReservation>> {
+        self.verify_enckey(name, enckey)?;
+        self.reservation_service.release_tag(name, enckey, tag)
+    }
+}
+
+impl<S, C, T> WalletPaymentClient for DefaultWalletClient<S, C, T>
+where
+    S: Storage + 'static,
+    C: Client,
+    T: WalletTransactionBuilder,
+{
+    fn internal_transfer(
+        &self,
+        from_name: &str,
+        from_enckey: &SecKey,
+        to_name: &str,
+        to_enckey: &SecKey,
+        amount: Coin,
+        network_id: u8,
+    ) -> Result<TxId> {
+        if from_name == to_name {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Source and destination wallets should be different",
+            ));
+        }
+        let current_block_height = self.get_current_block_height()?;
+        // the destination wallet has to read the transaction
+        let mut view_keys = BTreeSet::new();
+        view_keys.insert(self.view_key(to_name, to_enckey)?);
+        let attributes =
+            self.transfer_attributes(from_name, from_enckey, &mut view_keys, network_id)?;
+        let to_address = self.new_transfer_address(to_name, to_enckey)?;
+        let return_address = self.new_transfer_address(from_name, from_enckey)?;
+
+        let (transaction, reference, selected_inputs, return_amount) = self.create_transaction(
+            from_name,
+            from_enckey,
+            vec![TxOut::new(to_address, amount)],
+            attributes,
+            None,
+            None,
+            return_address,
+        )?;
+        self.broadcast_transaction(&transaction)?;
+
+        let outgoing = TransactionPending {
+            used_inputs: selected_inputs,
+            block_height: current_block_height,
+            return_amount,
+        };
+        self.update_tx_pending_state(from_name, from_enckey, reference.txid, outgoing)?;
+        // the incoming amount is pending until the destination wallet syncs the transaction
+        let incoming = TransactionPending {
+            used_inputs: Vec::new(),
+            block_height: current_block_height,
+            return_amount: amount,
+        };
+        self.update_tx_pending_state(to_name, to_enckey, reference.txid, incoming)?;
+
+        self.annotate_internal_transfer(from_name, from_enckey, reference.txid, to_name)?;
+        self.annotate_internal_transfer(to_name, to_enckey, reference.txid, from_name)?;
+        Ok(reference.txid)
+    }
+
+    fn send_to_many(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        recipients: Vec<TransferRecipient>,
+        network_id: u8,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+    ) -> Result<Vec<RecipientOutput>> {
+        if recipients.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Transfer has no recipients",
+            ));
+        }
+        // one output is left for the change
+        if recipients.len() >= MAX_TX_OUTPUTS {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Transfer can pay at most {} recipients, send more in a batch",
+                    MAX_TX_OUTPUTS - 1
+                ),
+            ));
+        }
+        let current_block_height = self.get_current_block_height()?;
+        let mut view_keys = recipients
+            .iter()
+            .flat_map(|recipient| recipient.view_keys.iter().cloned())
+            .collect::<BTreeSet<_>>();
+        let attributes = self.transfer_attributes(name, enckey, &mut view_keys, network_id)?;
+        let outputs = recipients
+            .iter()
+            .map(|recipient| TxOut::new(recipient.address.clone(), recipient.amount))
+            .collect::<Vec<_>>();
+
+        let return_address = self.new_transfer_address(name, enckey)?;
+        let transaction_id = if self.approval_service.is_required(name)? {
+            self.request_approval(
+                name,
+                enckey,
+                outputs,
+                attributes,
+                None,
+                return_address,
+                current_block_height,
+            )?
+        } else {
+            let (transaction, reference, selected_inputs, return_amount) = self
+                .create_transaction(
+                    name,
+                    enckey,
+                    outputs,
+                    attributes,
+                    input_selection_strategy,
+                    None,
+                    return_address,
+                )?;
+            self.broadcast_transaction(&transaction)?;
+            let tx_pending = TransactionPending {
+                used_inputs: selected_inputs,
+                block_height: current_block_height,
+                return_amount,
+            };
+            self.update_tx_pending_state(name, enckey, reference.txid, tx_pending)?;
+            reference.txid
+        };
+
+        // the outputs are in the order of the recipients, followed by the change
+        let transaction_id = hex::encode(transaction_id);
+        Ok(recipients
+            .into_iter()
+            .enumerate()
+            .map(|(index, recipient)| RecipientOutput {
+                address: recipient.address,
+                amount: recipient.amount,
+                transaction_id: transaction_id.clone(),
+                output_index: index as TxoSize,
+            })
+            .collect())
+    }
+
+    fn send_batch(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        batch_name: &str,
+        recipients: Vec<(ExtendedAddr, Coin)>,
+        view_keys: &mut BTreeSet<PublicKey>,
+        network_id: u8,
+    ) -> Result<PayoutBatch> {
+        self.check_spend_without_approval(name)?;
+        if recipients.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Payout batch has no recipients",
+            ));
+        }
+        self.payout_batch_service
+            .check_name(name, enckey, batch_name)?;
+
+        let current_block_height = self.get_current_block_height()?;
+        let attributes = self.transfer_attributes(name, enckey, view_keys, network_id)?;
+
+        let mut batch = PayoutBatch {
+            name: batch_name.to_owned(),
+            block_height: current_block_height,
+            transaction_ids: Vec::new(),
+            recipients: Vec::new(),
+        };
+        let mut result = Ok(());
+        for chunk in split_payouts(recipients, MAX_TX_OUTPUTS) {
+            let outputs = chunk
+                .iter()
+                .map(|(address, amount)| TxOut::new(address.clone(), *amount))
+                .collect();
+            let sent = self
+                .new_transfer_address(name, enckey)
+                .and_then(|return_address| {
+                    let (transaction, reference, selected_inputs, return_amount) = self
+                        .create_transaction(
+                            name,
+                            enckey,
+                            outputs,
+                            attributes.clone(),
+                            None,
+                            None,
+                            return_address,
+                        )?;
+                    self.broadcast_transaction(&transaction)?;
+                    let tx_pending = TransactionPending {
+                        used_inputs: selected_inputs,
+                        block_height: current_block_height,
+                        return_amount,
+                    };
+                    self.update_tx_pending_state(name, enckey, reference.txid, tx_pending)?;
+                    Ok(reference.txid)
+                });
+
+            match sent {
+                Ok(tx_id) => {
+                    let transaction_id = hex::encode(tx_id);
+                    batch
+                        .recipients
+                        .extend(chunk.into_iter().map(|(address, amount)| PayoutRecipient {
+                            address,
+                            amount,
+                            transaction_id: transaction_id.clone(),
+                        }));
+                    batch.transaction_ids.push(transaction_id);
+                }
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+
+        if !batch.transaction_ids.is_empty() {
+            self.payout_batch_service
+                .add_batch(name, enckey, batch.clone())?;
+        }
+        result.map_err(|err| {
+            Error::new_with_source(
+                err.kind(),
+                format!(
+                    "Payout batch ({}) failed after sending {} transaction(s)",
+                    batch_name,
+                    batch.transaction_ids.len()
+                ),
+                Box::new(err),
+            )
+        })?;
+        Ok(batch)
+    }
+
+    #[inline]
+    fn payout_batch(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        batch_name: &str,
+    ) -> Result<Option<PayoutBatch>> {
+        self.payout_batch_service
+            .get_batch(name, enckey, batch_name)
+    }
+}
+
+#[cfg(feature = "experimental")]
+impl<S, C, T> MultiSigWalletClient for DefaultWalletClient<S, C, T>
+where
+    S: Storage + 'static,
+    C: Client,
+    T: WalletTransactionBuilder,
+{
+    fn schnorr_signature(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tx: &Transaction,
+        public_key: &PublicKey,
+    ) -> Result<SchnorrSignature> {
+        // To verify if the enckey is correct or not
+        self.transfer_addresses(name, enckey)?;
+        let wallet = self.wallet_service.get_wallet_info(name, enckey)?;
+        let sign_key = match wallet.wallet_kind {
+            WalletKind::HW => {
+                let chain_path = self
+                    .wallet_service
+                    .find_chain_path(name, enckey, public_key)?
+                    .chain(|| (ErrorKind::InvalidInput, "can not find chain path"))?;
+                self.hw_key_service.get_sign_key(&chain_path)?
+            }
+            _ => {
+                let private_key = self
+                    .wallet_service
+                    .find_private_key(name, enckey, public_key)?
+                    .chain(|| (ErrorKind::InvalidInput, ""))?;
+                Box::new(private_key)
+            }
+        };
+        sign_key.schnorr_sign(tx)
+    }
+
+    fn new_multi_sig_session(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        message: H256,
+        signer_public_keys: Vec<PublicKey>,
+        self_public_key: PublicKey,
+    ) -> Result<H256> {
+        // To verify if the enckey is correct or not
+        self.transfer_addresses(name, enckey)?;
+        //        let sign_key = self.sign_key(name, enckey, &self_public_key)?;
+
+        let self_private_key = self
+            .private_key(name, enckey, &self_public_key)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Self public key ({}) is not owned by current wallet",
+                        self_public_key
+                    ),
+                )
+            })?;
+
+        self.multi_sig_session_service.new_session(
+            message,
+            signer_public_keys,
+            self_public_key,
+            self_private_key,
+            enckey,
+        )
+    }
+
+    fn nonce_commitment(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
+        self.multi_sig_session_service
+            .nonce_commitment(session_id, enckey)
+    }
+
+    fn add_nonce_commitment(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        nonce_commitment: H256,
+        public_key: &PublicKey,
+    ) -> Result<()> {
+        self.multi_sig_session_service.add_nonce_commitment(
+            session_id,
+            nonce_commitment,
+            public_key,
+            enckey,
+        )
+    }
+
+    fn nonce(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
+        self.multi_sig_session_service.nonce(session_id, enckey)
+    }
+
+    fn add_nonce(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        nonce: &H256,
+        public_key: &PublicKey,
+    ) -> Result<()> {
+        self.multi_sig_session_service
+            .add_nonce(session_id, &nonce, public_key, enckey)
+    }
+
+    fn partial_signature(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
+        self.multi_sig_session_service
+            .partial_signature(session_id, enckey)
+    }
+
+    fn add_partial_signature(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        partial_signature: H256,
+        public_key: &PublicKey,
+    ) -> Result<()> {
+        self.multi_sig_session_service.add_partial_signature(
+            session_id,
+            partial_signature,
+            public_key,
+            enckey,
+        )
+    }
+
+    fn signature(&self, session_id: &H256, enckey: &SecKey) -> Result<SchnorrSignature> {
+        self.multi_sig_session_service.signature(session_id, enckey)
+    }
+
+    fn session_status(&self, session_id: &H256, enckey: &SecKey) -> Result<Vec<SignerStatus>> {
+        self.multi_sig_session_service
+            .session_status(session_id, enckey)
+    }
+
+    fn delete_session(&self, session_id: &H256, enckey: &SecKey) -> Result<()> {
+        // only sessions of the wallet can be deleted
+        self.multi_sig_session_service
+            .public_keys(session_id, enckey)?;
+        self.multi_sig_session_service.delete_session(session_id)
+    }
+
+    fn transaction(
+        &self,
+        name: &str,
+        session_id: &H256,
+        enckey: &SecKey,
+        unsigned_transaction: Tx,
+    ) -> Result<TxAux> {
+        if unsigned_transaction.inputs.len() != 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Multi-Sig Signing is only supported for transactions with only one input",
+            ));
+        }
+
+        let output_to_spend = self.output(name, enckey, &unsigned_transaction.inputs[0])?;
+        let root_hash = self
+            .wallet_service
+            .find_root_hash(name, enckey, &output_to_spend.address)?
+            .chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Output address is not owned by current wallet; cannot spend output in given transaction",
+                )
+            })?;
+        let public_keys = self
+            .multi_sig_session_service
+            .public_keys(session_id, enckey)?;
+
+        let proof = self
+            .root_hash_service
             .generate_proof(name, &root_hash, public_keys, enckey)?;
         let signature = self.signature(session_id, enckey)?;
 
@@ -1533,6 +2949,30 @@ where
     }
 }
 
+/// Parses a transfer or staking address, so that the address book is keyed by the canonical
+/// encoding of addresses
+fn canonical_address(address: &str) -> Result<String> {
+    if let Ok(address) = address.parse::<ExtendedAddr>() {
+        return Ok(address.to_string());
+    }
+    address
+        .parse::<StakedStateAddress>()
+        .map(|address| address.to_string())
+        .map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid transfer or staking address: {}", address),
+            )
+        })
+}
+
+fn watch_only_key_generation_error() -> Error {
+    Error::new(
+        ErrorKind::PermissionDenied,
+        "Watch-only wallet can't generate keys, add public keys as watch-only addresses instead",
+    )
+}
+
 fn check_passphrase_strength(name: &str, passphrase: &SecUtf8) -> Result<()> {
     // `estimate_password_strength` returns a score between `0-4`. Any score less than 3 should be considered too
     // weak.
@@ -1627,10 +3067,17 @@ fn import_transaction(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::signer::WalletSignerManager;
+    use crate::transaction_builder::DefaultWalletTransactionBuilder;
+    use crate::types::{TransactionInput, TransactionType};
     use crate::Mnemonic;
+    use chain_core::tx::fee::{LinearFee, Milli};
+    use client_common::cipher::TransactionObfuscation;
     use client_common::storage::MemoryStorage;
     use client_common::PublicKey;
+    use client_common::SignedTransaction;
     use std::str::FromStr;
+    use test_common::block_generator::{BlockGenerator, GeneratorClient};
 
     #[test]
     fn check_delete_wallet() {
@@ -1653,6 +3100,120 @@ mod tests {
             .expect("restore wallet");
     }
 
+    #[test]
+    fn check_delete_wallet_removes_all_records() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let enckey = client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+        client
+            .new_staking_address("Default", &enckey)
+            .expect("new staking address");
+        client
+            .set_alert_rules(
+                "Default",
+                &enckey,
+                vec![AlertRule::BalanceBelow {
+                    amount: Coin::new(100).unwrap(),
+                }],
+            )
+            .expect("set alert rules");
+        // journal of an interrupted re-encryption which didn't overwrite anything yet
+        let kdf_config = load_kdf_config(&storage, "Default").unwrap();
+        let records: Vec<(Vec<u8>, Vec<u8>, Vec<u8>)> = Vec::new();
+        storage
+            .set(
+                REKEY_JOURNAL_KEYSPACE,
+                "Default",
+                (kdf_config, records).encode(),
+            )
+            .unwrap();
+
+        client
+            .delete_wallet("Default", &passphrase)
+            .expect("delete wallet");
+        for keyspace in storage.keyspaces().unwrap() {
+            assert!(
+                storage.keys(&keyspace).unwrap().is_empty(),
+                "records left in {}",
+                String::from_utf8_lossy(&keyspace)
+            );
+        }
+    }
+
+    #[test]
+    fn check_rekey_wallet() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let new_passphrase = SecUtf8::from("654321");
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let enckey = client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        let view_key = client.view_key("Default", &enckey).unwrap();
+        let address = client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+
+        let kdf_config = KdfConfig::argon2id(8192, 1, 1);
+        assert!(client
+            .rekey_wallet("Default", &new_passphrase, &new_passphrase, kdf_config)
+            .is_err());
+        let new_enckey = client
+            .rekey_wallet("Default", &passphrase, &new_passphrase, kdf_config)
+            .expect("rekey wallet");
+
+        assert!(client.auth_token("Default", &passphrase).is_err());
+        assert_eq!(
+            new_enckey,
+            client.auth_token("Default", &new_passphrase).unwrap()
+        );
+        assert_eq!(view_key, client.view_key("Default", &new_enckey).unwrap());
+        assert!(client
+            .transfer_addresses("Default", &new_enckey, 0, 0, false)
+            .unwrap()
+            .contains(&address));
+        assert!(client.view_key("Default", &enckey).is_err());
+    }
+
+    #[test]
+    fn check_change_passphrase_keeps_kdf_config() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let new_passphrase = SecUtf8::from("654321");
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        let kdf_config = KdfConfig::argon2id(8192, 1, 1);
+        let enckey = client
+            .rekey_wallet("Default", &passphrase, &passphrase, kdf_config)
+            .expect("rekey wallet");
+        let view_key = client.view_key("Default", &enckey).unwrap();
+
+        assert!(client
+            .change_passphrase("Default", &new_passphrase, &new_passphrase)
+            .is_err());
+        let new_enckey = client
+            .change_passphrase("Default", &passphrase, &new_passphrase)
+            .expect("change passphrase");
+
+        assert_eq!(kdf_config, load_kdf_config(&storage, "Default").unwrap());
+        assert_eq!(
+            new_enckey,
+            derive_enckey_with(&new_passphrase, "Default", &kdf_config).unwrap()
+        );
+        assert_eq!(view_key, client.view_key("Default", &new_enckey).unwrap());
+        assert!(client.auth_token("Default", &passphrase).is_err());
+    }
+
     #[test]
     fn check_restore_wallet_twice() {
         let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
@@ -1718,6 +3279,7 @@ mod tests {
                     &name1,
                     &enckey1,
                     &mut dummy_wallet,
+                    20,
                 )
                 .unwrap(),
             true
@@ -1732,12 +3294,87 @@ mod tests {
                     &name1,
                     &enckey1,
                     &mut dummy_wallet,
+                    20,
                 )
                 .unwrap(),
             false
         );
     }
 
+    #[test]
+    fn check_address_recover_gap_limit() {
+        let name = "Default";
+        let passphrase = SecUtf8::from("123456");
+        let mut client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let enckey = client
+            .restore_wallet(name, &passphrase, &Mnemonic::new(24).unwrap())
+            .expect("restore wallet");
+        let mut wallet = client.wallet_service.get_wallet(name, &enckey).unwrap();
+
+        let transfer_address = |index: u32| {
+            let public_key = client
+                .hd_key_service
+                .peek_account_pubkey(name, &enckey, HDAccountType::Transfer, index)
+                .unwrap();
+            let (root_hash, _) = RootHashService::<MemoryStorage>::peek_new_root_hash(
+                vec![public_key.clone()],
+                public_key,
+                1,
+            )
+            .unwrap();
+            ExtendedAddr::OrTree(root_hash)
+        };
+        let staking_address = |index: u32| {
+            let public_key = client
+                .hd_key_service
+                .peek_account_pubkey(name, &enckey, HDAccountType::Staking, index)
+                .unwrap();
+            StakedStateAddress::BasicRedeem(RedeemAddress::from(&public_key))
+        };
+        let address3 = transfer_address(3);
+        let address5 = transfer_address(5);
+        let staking_address2 = staking_address(2);
+
+        // beyond the gap limit
+        assert!(!client
+            .recover_addresses(&address5, name, &enckey, &mut wallet, 3)
+            .unwrap());
+
+        // the addresses up to the matching one are created
+        assert!(client
+            .recover_addresses(&address3, name, &enckey, &mut wallet, 3)
+            .unwrap());
+        let addresses = client
+            .transfer_addresses(name, &enckey, 0, 0, false)
+            .unwrap();
+        assert_eq!(3, addresses.len());
+        assert!(addresses.contains(&address3));
+
+        // the window slides after the recovered addresses
+        assert!(client
+            .recover_addresses(&address5, name, &enckey, &mut wallet, 3)
+            .unwrap());
+        assert_eq!(
+            5,
+            client
+                .transfer_addresses(name, &enckey, 0, 0, false)
+                .unwrap()
+                .len()
+        );
+
+        assert!(client
+            .recover_staking_address(&staking_address2, name, &enckey, 3)
+            .unwrap());
+        assert!(!client
+            .recover_staking_address(&staking_address2, name, &enckey, 3)
+            .unwrap());
+        let addresses = client
+            .staking_addresses(name, &enckey, 0, 0, false)
+            .unwrap();
+        assert_eq!(2, addresses.len());
+        assert!(addresses.contains(&staking_address2));
+    }
+
     #[test]
     fn check_restore_basic_wallet() {
         let private_key =
@@ -1755,4 +3392,599 @@ mod tests {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn check_watch_only_wallet() {
+        let public_key = |byte: u8| {
+            PublicKey::from(&PrivateKey::deserialize_from(&[byte; 32]).expect("valid private key"))
+        };
+
+        let name = "Default";
+        let passphrase = SecUtf8::from("123456");
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let enckey = client
+            .new_watch_only_wallet(
+                name,
+                &passphrase,
+                &public_key(1),
+                &[public_key(2)],
+                &[public_key(3)],
+            )
+            .expect("new watch-only wallet");
+
+        assert_eq!(
+            1,
+            client
+                .transfer_addresses(name, &enckey, 0, 0, false)
+                .unwrap()
+                .len()
+        );
+        assert_eq!(
+            1,
+            client
+                .staking_addresses(name, &enckey, 0, 0, false)
+                .unwrap()
+                .len()
+        );
+
+        let permission_denied = |result: Result<()>| {
+            assert_eq!(ErrorKind::PermissionDenied, result.unwrap_err().kind());
+        };
+        permission_denied(client.new_transfer_address(name, &enckey).map(|_| ()));
+        permission_denied(client.new_staking_address(name, &enckey).map(|_| ()));
+        permission_denied(client.sign_key(name, &enckey, &public_key(2)).map(|_| ()));
+        permission_denied(
+            client
+                .wallet_private_key(name, &enckey, WalletKind::WatchOnly)
+                .map(|_| ()),
+        );
+
+        assert!(client
+            .new_wallet(
+                "Other",
+                &passphrase,
+                WalletKind::WatchOnly,
+                HardwareKind::LocalOnly,
+                None
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn check_spend_approval() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let enckey = client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        let return_address = client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+        client
+            .new_staking_address("Default", &enckey)
+            .expect("new staking address");
+        let staking_key = client.staking_keys("Default", &enckey).unwrap()[0].clone();
+
+        assert!(client
+            .set_spend_approval("Default", &SecUtf8::from("123457"), true)
+            .is_err());
+        client
+            .set_spend_approval("Default", &passphrase, true)
+            .expect("set spend approval");
+
+        let error = client
+            .create_transaction(
+                "Default",
+                &enckey,
+                vec![],
+                TxAttributes::default(),
+                None,
+                None,
+                return_address,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::PermissionDenied, error.kind());
+        let error = client
+            .sign_key("Default", &enckey, &staking_key)
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(ErrorKind::PermissionDenied, error.kind());
+        assert!(client
+            .pending_approvals("Default", &enckey)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            client
+                .reject_transaction("Default", &passphrase, [0; 32])
+                .unwrap_err()
+                .kind()
+        );
+
+        client
+            .set_spend_approval("Default", &passphrase, false)
+            .expect("set spend approval");
+        assert!(client.sign_key("Default", &enckey, &staking_key).is_ok());
+    }
+
+    #[test]
+    fn check_wallet_snapshot() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let enckey = client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        let address = client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            TxoPointer::new([0; 32], 0),
+            TxOut::new(address, Coin::new(1000).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("Default", &enckey, &memento)
+            .unwrap();
+        let mut sync_state = SyncState::genesis([0; 32]);
+        sync_state.last_block_height = 10;
+        save_sync_state(&storage, "Default", &sync_state).unwrap();
+
+        let snapshot = client
+            .export_wallet_snapshot("Default", &enckey, false)
+            .expect("export wallet snapshot");
+        assert!(snapshot.info.hdkey.is_none());
+        let snapshot: WalletExport =
+            serde_json::from_str(&serde_json::to_string(&snapshot).unwrap()).unwrap();
+
+        let other = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let imported_enckey = other
+            .import_wallet_snapshot("Imported", &passphrase, snapshot)
+            .expect("import wallet snapshot");
+        assert_eq!(
+            client
+                .transfer_addresses("Default", &enckey, 0, 0, false)
+                .unwrap(),
+            other
+                .transfer_addresses("Imported", &imported_enckey, 0, 0, false)
+                .unwrap()
+        );
+        assert_eq!(
+            Coin::new(1000).unwrap(),
+            other.balance("Imported", &imported_enckey).unwrap().total
+        );
+        assert_eq!(
+            10,
+            other.get_sync_state("Imported").unwrap().last_block_height
+        );
+
+        let mut snapshot = client
+            .export_wallet_snapshot("Default", &enckey, true)
+            .unwrap();
+        assert!(snapshot.info.hdkey.is_some());
+        snapshot.version = WALLET_EXPORT_VERSION + 1;
+        assert!(other
+            .import_wallet_snapshot("Newer", &passphrase, snapshot)
+            .is_err());
+    }
+
+    #[test]
+    fn check_device_sync() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let primary = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let secondary = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let enckey = primary
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        secondary
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+
+        let primary_pairing = primary
+            .device_pairing("Default", &enckey, "desktop")
+            .unwrap();
+        let secondary_pairing = secondary
+            .device_pairing("Default", &enckey, "phone")
+            .unwrap();
+        primary
+            .pair_device("Default", &enckey, secondary_pairing, DeviceRole::Secondary)
+            .unwrap();
+        secondary
+            .pair_device("Default", &enckey, primary_pairing, DeviceRole::Primary)
+            .unwrap();
+
+        primary
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+        primary
+            .new_staking_address("Default", &enckey)
+            .expect("new staking address");
+
+        let events = primary
+            .device_events("Default", &enckey, "phone", 0)
+            .unwrap();
+        assert_eq!(2, events.len());
+        let last_sequence = secondary
+            .import_device_events("Default", &enckey, "desktop", events)
+            .unwrap();
+        assert_eq!(2, last_sequence);
+        assert_eq!(
+            primary.public_keys("Default", &enckey).unwrap(),
+            secondary.public_keys("Default", &enckey).unwrap()
+        );
+        assert_eq!(
+            primary.root_hashes("Default", &enckey).unwrap(),
+            secondary.root_hashes("Default", &enckey).unwrap()
+        );
+        assert_eq!(
+            primary.staking_keys("Default", &enckey).unwrap(),
+            secondary.staking_keys("Default", &enckey).unwrap()
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct MockTransactionCipher;
+
+    impl TransactionObfuscation for MockTransactionCipher {
+        fn decrypt(
+            &self,
+            _transaction_ids: &[TxId],
+            _private_key: &PrivateKey,
+        ) -> Result<Vec<Transaction>> {
+            unreachable!()
+        }
+
+        fn encrypt(&self, _transaction: SignedTransaction) -> Result<TxAux> {
+            unreachable!("fee estimation must not obfuscate transactions")
+        }
+    }
+
+    #[test]
+    fn check_verified_spent_flags() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default())
+            .with_verified_queries(true);
+        let enckey = client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        let address = ExtendedAddr::OrTree([1; 32]);
+        let output = TxOut::new(address, Coin::new(100).unwrap());
+        let tx = Tx::new_with(
+            vec![],
+            vec![output.clone(), output.clone()],
+            TxAttributes::default(),
+        );
+        let txid = tx.id();
+        let tx_info = TransactionInfo {
+            tx: Transaction::TransferTransaction(tx),
+            block_height: 5,
+        };
+
+        // the wallet isn't synced to a trusted state past the block yet
+        let err = client
+            .import_plain_tx("Default", &enckey, &tx_info.encode().unwrap())
+            .unwrap_err();
+        assert_eq!(ErrorKind::VerifyError, err.kind());
+
+        let mut sync_state = SyncState::genesis([0; 32]);
+        sync_state.last_block_height = 5;
+        client
+            .sync_state_service
+            .save_global_state("Default", &sync_state)
+            .unwrap();
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(TransactionChange {
+            transaction_id: [2; 32],
+            inputs: vec![TransactionInput {
+                pointer: TxoPointer::new(txid, 1),
+                output: Some(output),
+            }],
+            outputs: vec![],
+            fee_paid: Fee::new(Coin::new(100).unwrap()),
+            balance_change: BalanceChange::Outgoing {
+                value: Coin::new(100).unwrap(),
+            },
+            transaction_type: TransactionType::Transfer,
+            block_height: 6,
+            block_time: Time::unix_epoch(),
+            memo: None,
+        });
+        client
+            .wallet_state_service
+            .apply_memento("Default", &enckey, &memento)
+            .unwrap();
+        assert_eq!(
+            vec![false, true],
+            client
+                .verified_spent_flags("Default", &enckey, &tx_info)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn check_estimate_fee() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let storage = MemoryStorage::default();
+        let fee_algorithm =
+            LinearFee::new(Milli::try_new(1, 1).unwrap(), Milli::try_new(1, 1).unwrap());
+        let transaction_builder = DefaultWalletTransactionBuilder::new(
+            WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+        let client = DefaultWalletClient::new(
+            storage,
+            UnauthorizedClient,
+            transaction_builder,
+            None,
+            HwKeyService::default(),
+        );
+        let enckey = client
+            .restore_wallet("Default", &SecUtf8::from("123456"), &words)
+            .expect("restore wallet");
+        let address = client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            TxoPointer::new([0; 32], 0),
+            TxOut::new(address.clone(), Coin::new(1000).unwrap()),
+        );
+        memento.add_unspent_transaction(
+            TxoPointer::new([1; 32], 0),
+            TxOut::new(address.clone(), Coin::new(500).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("Default", &enckey, &memento)
+            .unwrap();
+
+        let outputs = vec![TxOut::new(address.clone(), Coin::new(600).unwrap())];
+        let fee = client
+            .estimate_fee(
+                "Default",
+                &enckey,
+                outputs.clone(),
+                TxAttributes::new(171),
+                None,
+            )
+            .expect("estimate fee");
+        assert!(fee > Coin::zero());
+        assert!((fee + Coin::new(600).unwrap()).unwrap() <= Coin::new(1000).unwrap());
+
+        // dry run leaves the wallet untouched
+        assert_eq!(
+            2,
+            client
+                .unspent_transactions("Default", &enckey)
+                .unwrap()
+                .len()
+        );
+        assert_eq!(
+            Coin::new(1500).unwrap(),
+            client.balance("Default", &enckey).unwrap().available
+        );
+
+        let outputs = vec![TxOut::new(address, Coin::new(1500).unwrap())];
+        assert!(client
+            .estimate_fee("Default", &enckey, outputs, TxAttributes::new(171), None)
+            .is_err());
+    }
+
+    #[test]
+    fn check_coin_control() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let storage = MemoryStorage::default();
+        let fee_algorithm =
+            LinearFee::new(Milli::try_new(1, 1).unwrap(), Milli::try_new(1, 1).unwrap());
+        let transaction_builder = DefaultWalletTransactionBuilder::new(
+            WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+        let client = DefaultWalletClient::new(
+            storage,
+            UnauthorizedClient,
+            transaction_builder,
+            None,
+            HwKeyService::default(),
+        );
+        let enckey = client
+            .restore_wallet("Default", &SecUtf8::from("123456"), &words)
+            .expect("restore wallet");
+        let address_1 = client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+        let address_2 = client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+
+        let input_1 = TxoPointer::new([0; 32], 0);
+        let input_2 = TxoPointer::new([1; 32], 0);
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            input_1.clone(),
+            TxOut::new(address_1.clone(), Coin::new(1000).unwrap()),
+        );
+        memento.add_unspent_transaction(
+            input_2.clone(),
+            TxOut::new(address_2.clone(), Coin::new(1000).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("Default", &enckey, &memento)
+            .unwrap();
+
+        let create_transaction = |amount: u64, only_from_addresses: Option<&[ExtendedAddr]>| {
+            client.create_transaction(
+                "Default",
+                &enckey,
+                vec![TxOut::new(address_1.clone(), Coin::new(amount).unwrap())],
+                TxAttributes::new(171),
+                None,
+                only_from_addresses,
+                address_1.clone(),
+            )
+        };
+
+        let (_, _, selected_inputs, _) =
+            create_transaction(100, Some(&[address_2.clone()])).unwrap();
+        assert_eq!(vec![input_2.clone()], selected_inputs);
+        assert!(create_transaction(1500, Some(&[address_2.clone()])).is_err());
+
+        client.freeze_utxo("Default", &enckey, &input_1).unwrap();
+        assert_eq!(
+            vec![input_1.clone()],
+            client.frozen_utxos("Default", &enckey).unwrap()
+        );
+        let (_, _, selected_inputs, _) = create_transaction(100, None).unwrap();
+        assert_eq!(vec![input_2], selected_inputs);
+        assert!(create_transaction(100, Some(&[address_1.clone()])).is_err());
+        // frozen outputs are still in the balance
+        assert_eq!(
+            Coin::new(2000).unwrap(),
+            client.balance("Default", &enckey).unwrap().available
+        );
+
+        client.unfreeze_utxo("Default", &enckey, &input_1).unwrap();
+        let (_, _, selected_inputs, _) = create_transaction(100, Some(&[address_1])).unwrap();
+        assert_eq!(vec![input_1], selected_inputs);
+    }
+
+    #[test]
+    fn check_send_to_many_recipients() {
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let (enckey, _) = client
+            .new_wallet(
+                "Default",
+                &SecUtf8::from("123456"),
+                WalletKind::Basic,
+                HardwareKind::LocalOnly,
+                None,
+            )
+            .unwrap();
+        let address = client.new_transfer_address("Default", &enckey).unwrap();
+        let recipient = TransferRecipient {
+            address,
+            amount: Coin::new(100).unwrap(),
+            view_keys: BTreeSet::new(),
+        };
+
+        let error = client
+            .send_to_many("Default", &enckey, vec![], 171, None)
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        let error = client
+            .send_to_many(
+                "Default",
+                &enckey,
+                vec![recipient; MAX_TX_OUTPUTS],
+                171,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+    }
+
+    #[test]
+    fn check_send_to_many_outputs() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let storage = MemoryStorage::default();
+        let fee_algorithm =
+            LinearFee::new(Milli::try_new(1, 1).unwrap(), Milli::try_new(1, 1).unwrap());
+        let transaction_builder = DefaultWalletTransactionBuilder::new(
+            WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+        let client = DefaultWalletClient::new(
+            storage,
+            GeneratorClient::new(BlockGenerator::one_node()),
+            transaction_builder,
+            None,
+            HwKeyService::default(),
+        );
+        let enckey = client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        let address = client
+            .new_transfer_address("Default", &enckey)
+            .expect("new transfer address");
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            TxoPointer::new([0; 32], 0),
+            TxOut::new(address, Coin::new(1000).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("Default", &enckey, &memento)
+            .unwrap();
+        // the transaction is kept unsigned for approval, so that it can be inspected
+        client
+            .set_spend_approval("Default", &passphrase, true)
+            .expect("set spend approval");
+
+        let view_key = |byte: u8| {
+            PublicKey::from(&PrivateKey::deserialize_from(&[byte; 32]).expect("valid private key"))
+        };
+        let recipients = vec![
+            TransferRecipient {
+                address: ExtendedAddr::OrTree([2; 32]),
+                amount: Coin::new(200).unwrap(),
+                view_keys: vec![view_key(2)].into_iter().collect(),
+            },
+            TransferRecipient {
+                address: ExtendedAddr::OrTree([1; 32]),
+                amount: Coin::new(100).unwrap(),
+                view_keys: vec![view_key(1), view_key(2)].into_iter().collect(),
+            },
+        ];
+        let recipient_outputs = client
+            .send_to_many("Default", &enckey, recipients.clone(), 171, None)
+            .expect("send to many");
+
+        let pending = client.pending_approvals("Default", &enckey).unwrap();
+        assert_eq!(1, pending.len());
+        let transaction = &pending[0].transaction;
+        // outputs follow the order of the recipients, the change comes last
+        assert_eq!(recipients.len() + 1, transaction.outputs.len());
+        for (index, (recipient, output)) in recipients.iter().zip(recipient_outputs).enumerate() {
+            assert_eq!(index as TxoSize, output.output_index);
+            assert_eq!(pending[0].transaction_id, output.transaction_id);
+            assert_eq!(recipient.address, output.address);
+            assert_eq!(recipient.amount, output.amount);
+            assert_eq!(
+                TxOut::new(recipient.address.clone(), recipient.amount),
+                transaction.outputs[index]
+            );
+        }
+        assert_eq!(
+            pending[0].return_amount,
+            transaction.outputs[recipients.len()].value
+        );
+
+        // every recipient and the wallet itself can view the transaction
+        let allowed_view = transaction
+            .attributes
+            .allowed_view
+            .iter()
+            .map(|policy| PublicKey::from(policy.view_key))
+            .collect::<BTreeSet<_>>();
+        let expected = vec![
+            view_key(1),
+            view_key(2),
+            client.view_key("Default", &enckey).unwrap(),
+        ]
+        .into_iter()
+        .collect::<BTreeSet<_>>();
+        assert_eq!(expected, allowed_view);
+        assert_eq!(171, transaction.attributes.chain_hex_id);
+    }
 }
@@ -0,0 +1,1169 @@
+//! A middleware observes and gates wallet operations: `before` runs before the operation and can
+//! reject it, `after` runs with the outcome of the operation. Middlewares are stacked by nesting
+//! `Layered` clients or by combining them in a tuple, e.g.
+//!
+//! ```ignore
+//! let client = Layered::new(
+//!     DefaultWalletClient::new_read_only(storage.clone()),
+//!     (Logging, PassphraseLockout::new(storage)),
+//! );
+//! ```
+//!
+//! Middlewares don't see the typed results of operations; caching of results is left to
+//! wrappers of the concrete operations.
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+use indexmap::IndexSet;
+use secstr::SecUtf8;
+
+use chain_core::common::{Proof, H256};
+use chain_core::init::address::RedeemAddress;
+use chain_core::init::coin::Coin;
+use chain_core::state::account::StakedStateAddress;
+use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::data::attribute::TxAttributes;
+use chain_core::tx::data::input::TxoPointer;
+use chain_core::tx::data::output::TxOut;
+use chain_core::tx::data::TxId;
+use chain_core::tx::witness::tree::RawXOnlyPubkey;
+use chain_core::tx::witness::TxWitness;
+use chain_core::tx::TxAux;
+use client_common::seckey::KdfConfig;
+use client_common::tendermint::types::BroadcastTxResponse;
+use client_common::{
+    Error, ErrorKind, MultiSigAddress, PrivateKey, PrivateKeyAction, PublicKey, Result, SecKey,
+    Storage, Transaction, TransactionInfo,
+};
+
+use crate::hd_wallet::HardwareKind;
+use crate::service::{
+    LockoutStatus, OperatorProfile, PassphraseAttemptService, SyncState, WalletExport, WalletInfo,
+};
+use crate::transaction_builder::{
+    PartiallySignedTransferTx, SignedTransferTransaction, UnsignedTransferTransaction,
+    UnsignedTransferTx,
+};
+use crate::types::{
+    AddressMetadata, AddressType, Alert, AlertRule, AnnotationConflict, AnnotationImportReport,
+    BalanceReservation, DevicePairing, DeviceRole, EncryptedWalletEvent, HistoryExportFormat,
+    MempoolTransaction, PairedDevice, PayoutBatch, PendingApproval, RecipientOutput,
+    TransactionChange, TransactionHistory, TransactionHistoryFilter, TransactionPending,
+    TransactionReference, TransferRecipient, TxAnnotation, WalletBalance, WalletKind,
+};
+use crate::{
+    InputSelectionStrategy, Mnemonic, UnspentTransactions, WalletAuditClient, WalletClient,
+    WalletMaintenanceClient, WalletPaymentClient, WalletRekeyClient, WalletSpendControlClient,
+};
+
+/// Kind of access needed by a wallet operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// only reads wallet data
+    Read,
+    /// changes wallet data, signs or broadcasts transactions
+    Write,
+    /// reveals private keys of the wallet
+    Secret,
+}
+
+/// Wallet operation passed through middlewares
+#[derive(Debug, Clone, Copy)]
+pub struct WalletOperation<'a> {
+    /// name of the wallet client method
+    pub method: &'static str,
+    /// name of the wallet (if the operation is on a single wallet)
+    pub name: Option<&'a str>,
+    /// kind of access
+    pub access: Access,
+}
+
+impl<'a> WalletOperation<'a> {
+    fn new(method: &'static str, name: Option<&'a str>, access: Access) -> Self {
+        WalletOperation {
+            method,
+            name,
+            access,
+        }
+    }
+}
+
+/// Middleware invoked around wallet operations
+pub trait WalletMiddleware: Send + Sync {
+    /// Called before the operation, an error rejects the operation (and `after` isn't called)
+    fn before(&self, _op: &WalletOperation) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called with the outcome of the operation, an error replaces the outcome
+    fn after(&self, _op: &WalletOperation, _result: std::result::Result<(), &Error>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Two middlewares applied in order: `before` of the first runs first, `after` of the first
+/// runs last (also when the second one rejected the operation)
+impl<A: WalletMiddleware, B: WalletMiddleware> WalletMiddleware for (A, B) {
+    fn before(&self, op: &WalletOperation) -> Result<()> {
+        self.0.before(op)?;
+        if let Err(err) = self.1.before(op) {
+            // the operation got rejected by the second middleware
+            self.0.after(op, Err(&err))?;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    fn after(&self, op: &WalletOperation, result: std::result::Result<(), &Error>) -> Result<()> {
+        let inner = self.1.after(op, result);
+        let result = match &inner {
+            Ok(()) => result,
+            Err(err) => Err(err),
+        };
+        self.0.after(op, result)?;
+        inner
+    }
+}
+
+/// Logs wallet operations
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Logging;
+
+impl WalletMiddleware for Logging {
+    fn before(&self, op: &WalletOperation) -> Result<()> {
+        log::debug!("wallet operation {} on {:?}", op.method, op.name);
+        Ok(())
+    }
+
+    fn after(&self, op: &WalletOperation, result: std::result::Result<(), &Error>) -> Result<()> {
+        if let Err(err) = result {
+            log::warn!(
+                "wallet operation {} on {:?} failed: {}",
+                op.method,
+                op.name,
+                err
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Number of calls and failures of a wallet operation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OperationMetrics {
+    /// number of calls
+    pub calls: u64,
+    /// number of failed calls
+    pub errors: u64,
+}
+
+/// Counts calls and failures of wallet operations, clones share the counters
+#[derive(Debug, Default, Clone)]
+pub struct Metrics {
+    operations: Arc<Mutex<BTreeMap<&'static str, OperationMetrics>>>,
+}
+
+impl Metrics {
+    /// Returns counters of all called operations
+    pub fn snapshot(&self) -> BTreeMap<&'static str, OperationMetrics> {
+        self.operations.lock().expect("lock metrics").clone()
+    }
+}
+
+impl WalletMiddleware for Metrics {
+    fn after(&self, op: &WalletOperation, result: std::result::Result<(), &Error>) -> Result<()> {
+        let mut operations = self.operations.lock().expect("lock metrics");
+        let metrics = operations.entry(op.method).or_default();
+        metrics.calls += 1;
+        if result.is_err() {
+            metrics.errors += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Rejects operations which change wallet data, sign or broadcast transactions or reveal private
+/// keys
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReadOnlyGuard;
+
+impl WalletMiddleware for ReadOnlyGuard {
+    fn before(&self, op: &WalletOperation) -> Result<()> {
+        match op.access {
+            Access::Read => Ok(()),
+            Access::Write | Access::Secret => Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!("Wallet client is read-only, {} is not allowed", op.method),
+            )),
+        }
+    }
+}
+
+/// Operations which verify the passphrase of an existing wallet
+const PASSPHRASE_OPERATIONS: [&str; 7] = [
+    "auth_token",
+    "delete_wallet",
+    "rekey_wallet",
+    "change_passphrase",
+    "set_spend_approval",
+    "approve_transaction",
+    "reject_transaction",
+];
+
+/// Brute-force protection for wallet passphrases: rejects passphrase verification while the
+/// wallet is locked out and records the outcome of each verification
+#[derive(Debug, Default, Clone)]
+pub struct PassphraseLockout<S: Storage> {
+    service: PassphraseAttemptService<S>,
+}
+
+impl<S: Storage> PassphraseLockout<S> {
+    /// Creates a new instance of passphrase lockout policy
+    pub fn new(storage: S) -> Self {
+        Self::with_service(PassphraseAttemptService::new(storage))
+    }
+
+    /// Creates a new instance of passphrase lockout policy with given attempt service
+    pub fn with_service(service: PassphraseAttemptService<S>) -> Self {
+        PassphraseLockout { service }
+    }
+}
+
+impl<S: Storage> WalletMiddleware for PassphraseLockout<S> {
+    fn before(&self, op: &WalletOperation) -> Result<()> {
+        match op.name {
+            Some(name) if PASSPHRASE_OPERATIONS.contains(&op.method) => self.service.check(name),
+            _ => Ok(()),
+        }
+    }
+
+    fn after(&self, op: &WalletOperation, result: std::result::Result<(), &Error>) -> Result<()> {
+        match op.name {
+            Some(name) if PASSPHRASE_OPERATIONS.contains(&op.method) => match result {
+                Ok(()) => self.service.record_success(name),
+                Err(err) if err.kind() == ErrorKind::DecryptionError => {
+                    self.service.record_failure(name)
+                }
+                Err(_) => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `WalletClient` with a middleware invoked around each operation of the inner client
+#[derive(Debug, Clone)]
+pub struct Layered<W, M> {
+    inner: W,
+    middleware: M,
+}
+
+impl<W, M> Layered<W, M>
+where
+    W: WalletClient,
+    M: WalletMiddleware,
+{
+    /// Wraps `inner` with `middleware`
+    pub fn new(inner: W, middleware: M) -> Self {
+        Layered { inner, middleware }
+    }
+
+    /// Returns the inner client
+    pub fn inner(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the middleware
+    pub fn middleware(&self) -> &M {
+        &self.middleware
+    }
+
+    fn call<T, F>(&self, op: WalletOperation, f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        self.middleware.before(&op)?;
+        let result = f();
+        self.middleware.after(&op, result.as_ref().map(|_| ()))?;
+        result
+    }
+}
+
+/// Implements the listed methods by forwarding them to the inner client through the middleware,
+/// each method declares its access class and the wallet it operates on
+macro_rules! layered_operations {
+    ($(
+        $access:ident($wallet:expr)
+        fn $method:ident(&self $(, $arg:ident: $ty:ty)* $(,)?) -> $ret:ty;
+    )*) => {
+        $(
+            fn $method(&self $(, $arg: $ty)*) -> $ret {
+                self.call(
+                    WalletOperation::new(stringify!($method), $wallet, Access::$access),
+                    || self.inner.$method($($arg),*),
+                )
+            }
+        )*
+    };
+}
+
+impl<W, M> WalletClient for Layered<W, M>
+where
+    W: WalletClient,
+    M: WalletMiddleware,
+{
+    fn update_hw_service(&mut self, hardware_type: HardwareKind) -> Result<()> {
+        let op = WalletOperation::new("update_hw_service", None, Access::Write);
+        self.middleware.before(&op)?;
+        let result = self.inner.update_hw_service(hardware_type);
+        self.middleware.after(&op, result.as_ref().map(|_| ()))?;
+        result
+    }
+
+    layered_operations! {
+        Read(Some(name)) fn get_transaction(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            txid: TxId,
+        ) -> Result<Transaction>;
+
+        Read(Some(name)) fn get_wallet_kind(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<WalletKind>;
+
+        Read(Some(name)) fn get_hardware_kind(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<HardwareKind>;
+
+        Write(Some(name)) fn send_to_address(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            amount: Coin,
+            address: ExtendedAddr,
+            view_keys: &mut BTreeSet<PublicKey>,
+            network_id: u8,
+            input_selection_strategy: Option<InputSelectionStrategy>,
+            only_from_addresses: Option<&[ExtendedAddr]>,
+        ) -> Result<TxId>;
+
+        Write(Some(name)) fn send_to_address_commit(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            amount: Coin,
+            address: ExtendedAddr,
+            view_keys: &mut BTreeSet<PublicKey>,
+            network_id: u8,
+        ) -> Result<TxId>;
+
+        Read(None) fn wallets(&self) -> Result<Vec<String>>;
+
+        Write(Some(name)) fn new_wallet(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            wallet_kind: WalletKind,
+            hardware_kind: HardwareKind,
+            mnemonics_word_count: Option<u32>,
+        ) -> Result<(SecKey, Option<Mnemonic>)>;
+
+        Secret(Some(name)) fn export_wallet(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<WalletInfo>;
+
+        Write(Some(name)) fn import_wallet(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            wallet_info: &mut WalletInfo,
+        ) -> Result<SecKey>;
+
+        Write(Some(name)) fn restore_wallet(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            mnemonic: &Mnemonic,
+        ) -> Result<SecKey>;
+
+        Write(Some(name)) fn restore_basic_wallet(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            view_key: &PrivateKey,
+        ) -> Result<SecKey>;
+
+        Write(Some(name)) fn new_watch_only_wallet(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            view_key: &PublicKey,
+            transfer_public_keys: &[PublicKey],
+            staking_public_keys: &[PublicKey],
+        ) -> Result<SecKey>;
+
+        Write(Some(name)) fn delete_wallet(&self, name: &str, passphrase: &SecUtf8) -> Result<()>;
+
+        Read(Some(name)) fn auth_token(&self, name: &str, passphrase: &SecUtf8) -> Result<SecKey>;
+
+        Read(Some(name)) fn view_key(&self, name: &str, enckey: &SecKey) -> Result<PublicKey>;
+
+        Secret(Some(name)) fn view_key_private(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<PrivateKey>;
+
+        Read(Some(name)) fn public_keys(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<IndexSet<PublicKey>>;
+
+        Read(Some(name)) fn staking_keys(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<IndexSet<PublicKey>>;
+
+        Read(Some(name)) fn root_hashes(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<IndexSet<H256>>;
+
+        Read(Some(name)) fn staking_addresses(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            offset: u64,
+            limit: u64,
+            reversed: bool,
+        ) -> Result<IndexSet<StakedStateAddress>>;
+
+        Read(Some(name)) fn transfer_addresses(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            offset: u64,
+            limit: u64,
+            reversed: bool,
+        ) -> Result<IndexSet<ExtendedAddr>>;
+
+        Read(Some(name)) fn find_staking_key(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            redeem_address: &RedeemAddress,
+        ) -> Result<Option<PublicKey>>;
+
+        Read(Some(name)) fn find_root_hash(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            address: &ExtendedAddr,
+        ) -> Result<Option<H256>>;
+
+        Secret(Some(name)) fn wallet_private_key(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            wallet_kind: WalletKind,
+        ) -> Result<Option<PrivateKey>>;
+
+        Write(Some(name)) fn sign_key(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            public_key: &PublicKey,
+        ) -> Result<Box<dyn PrivateKeyAction>>;
+
+        Secret(Some(name)) fn private_key(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            public_key: &PublicKey,
+        ) -> Result<Option<PrivateKey>>;
+
+        Write(Some(name)) fn new_public_key(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            address_type: Option<AddressType>,
+        ) -> Result<PublicKey>;
+
+        Write(Some(name)) fn new_staking_address(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<StakedStateAddress>;
+
+        Write(Some(name)) fn new_transfer_address(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<ExtendedAddr>;
+
+        Write(Some(name)) fn new_watch_staking_address(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            public_key: &PublicKey,
+        ) -> Result<StakedStateAddress>;
+
+        Write(Some(name)) fn new_watch_transfer_address(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            public_key: &PublicKey,
+        ) -> Result<ExtendedAddr>;
+
+        Write(Some(name)) fn new_multisig_transfer_address(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            public_keys: Vec<PublicKey>,
+            self_public_key: PublicKey,
+            m: usize,
+        ) -> Result<ExtendedAddr>;
+
+        Read(Some(name)) fn get_multisig_addresses(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<Vec<MultiSigAddress>>;
+
+        Read(Some(name)) fn generate_proof(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            address: &ExtendedAddr,
+            public_keys: Vec<PublicKey>,
+        ) -> Result<Proof<RawXOnlyPubkey>>;
+
+        Read(Some(name)) fn required_cosigners(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            root_hash: &H256,
+        ) -> Result<usize>;
+
+        Read(Some(name)) fn balance(&self, name: &str, enckey: &SecKey) -> Result<WalletBalance>;
+
+        Read(Some(name)) fn history(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            filter: TransactionHistoryFilter,
+            offset: usize,
+            limit: usize,
+            reversed: bool,
+        ) -> Result<TransactionHistory>;
+
+        Read(Some(name)) fn get_transaction_change(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            transaction_id: &TxId,
+        ) -> Result<Option<TransactionChange>>;
+
+        Read(Some(name)) fn unspent_transactions(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<UnspentTransactions>;
+
+        Read(Some(name)) fn has_unspent_transactions(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            inputs: &[TxoPointer],
+        ) -> Result<bool>;
+
+        Read(Some(name)) fn are_inputs_unspent(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            inputs: Vec<TxoPointer>,
+        ) -> Result<Vec<(TxoPointer, bool)>>;
+
+        Read(Some(name)) fn output(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            input: &TxoPointer,
+        ) -> Result<TxOut>;
+
+        Write(Some(name)) fn create_transaction(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            outputs: Vec<TxOut>,
+            attributes: TxAttributes,
+            input_selection_strategy: Option<InputSelectionStrategy>,
+            only_from_addresses: Option<&[ExtendedAddr]>,
+            return_address: ExtendedAddr,
+        ) -> Result<(TxAux, TransactionReference, Vec<TxoPointer>, Coin)>;
+
+        Read(Some(name)) fn estimate_fee(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            outputs: Vec<TxOut>,
+            attributes: TxAttributes,
+            input_selection_strategy: Option<InputSelectionStrategy>,
+        ) -> Result<Coin>;
+
+        Write(None) fn broadcast_transaction(&self, tx_aux: &TxAux) -> Result<BroadcastTxResponse>;
+
+        Write(None) fn broadcast_transactions(
+            &self,
+            tx_auxs: &[TxAux],
+        ) -> Result<Vec<Result<BroadcastTxResponse>>>;
+
+        Read(Some(name)) fn export_plain_tx(
+            &self,
+            name: &str,
+            passphras: &SecKey,
+            txid: &str,
+        ) -> Result<TransactionInfo>;
+
+        Write(Some(name)) fn import_plain_tx(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            tx_str: &str,
+        ) -> Result<Coin>;
+
+        Read(None) fn get_current_block_height(&self) -> Result<u64>;
+
+        Read(Some(name)) fn mempool_transactions(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            limit: u64,
+        ) -> Result<Vec<MempoolTransaction>>;
+
+        Write(Some(name)) fn update_tx_pending_state(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            tx_id: TxId,
+            tx_pending: TransactionPending,
+        ) -> Result<()>;
+
+        Read(Some(name)) fn build_raw_transfer_tx(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            to_address: ExtendedAddr,
+            amount: Coin,
+            view_keys: Vec<PublicKey>,
+            network_id: u8,
+        ) -> Result<UnsignedTransferTransaction>;
+
+        Write(Some(name)) fn sign_raw_transfer_tx(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            unsigned_tx: UnsignedTransferTransaction,
+        ) -> Result<SignedTransferTransaction>;
+
+        Write(Some(name)) fn broadcast_signed_transfer_tx(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            signed_tx: SignedTransferTransaction,
+        ) -> Result<TxId>;
+
+        Read(Some(name)) fn build_unsigned_transaction(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            outputs: Vec<TxOut>,
+            attributes: TxAttributes,
+            input_selection_strategy: Option<InputSelectionStrategy>,
+            return_address: ExtendedAddr,
+        ) -> Result<UnsignedTransferTx>;
+
+        Write(Some(name)) fn sign_unsigned_transaction(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            unsigned_tx: &UnsignedTransferTx,
+        ) -> Result<TxWitness>;
+
+        Write(Some(name)) fn sign_raw_transaction(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            raw_tx: &PartiallySignedTransferTx,
+        ) -> Result<PartiallySignedTransferTx>;
+
+        Write(Some(name)) fn import_transaction_witness(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            unsigned_tx: &UnsignedTransferTx,
+            witness: TxWitness,
+        ) -> Result<SignedTransferTransaction>;
+
+        Read(Some(name)) fn get_sync_state(&self, name: &str) -> Result<SyncState>;
+
+        Read(None) fn flush_database(&self) -> Result<()>;
+    }
+}
+
+impl<W, M> WalletRekeyClient for Layered<W, M>
+where
+    W: WalletRekeyClient,
+    M: WalletMiddleware,
+{
+    layered_operations! {
+        Write(Some(name)) fn rekey_wallet(
+            &self,
+            name: &str,
+            old_passphrase: &SecUtf8,
+            new_passphrase: &SecUtf8,
+            kdf_config: KdfConfig,
+        ) -> Result<SecKey>;
+
+        Write(Some(name)) fn change_passphrase(
+            &self,
+            name: &str,
+            old_passphrase: &SecUtf8,
+            new_passphrase: &SecUtf8,
+        ) -> Result<SecKey>;
+
+        Read(Some(name)) fn passphrase_lockout_status(&self, name: &str) -> Result<LockoutStatus>;
+    }
+}
+
+impl<W, M> WalletMaintenanceClient for Layered<W, M>
+where
+    W: WalletMaintenanceClient,
+    M: WalletMiddleware,
+{
+    layered_operations! {
+        Secret(Some(name)) fn export_wallet_snapshot(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            include_hd_seed: bool,
+        ) -> Result<WalletExport>;
+
+        Write(Some(name)) fn import_wallet_snapshot(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            snapshot: WalletExport,
+        ) -> Result<SecKey>;
+
+        Write(Some(name)) fn device_pairing(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            device_name: &str,
+        ) -> Result<DevicePairing>;
+
+        Write(Some(name)) fn pair_device(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            peer: DevicePairing,
+            role: DeviceRole,
+        ) -> Result<()>;
+
+        Write(Some(name)) fn unpair_device(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            device_name: &str,
+        ) -> Result<()>;
+
+        Read(Some(name)) fn paired_devices(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<Vec<PairedDevice>>;
+
+        Write(Some(name)) fn device_events(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            device_name: &str,
+            after: u64,
+        ) -> Result<Vec<EncryptedWalletEvent>>;
+
+        Write(Some(name)) fn import_device_events(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            device_name: &str,
+            events: Vec<EncryptedWalletEvent>,
+        ) -> Result<u64>;
+    }
+}
+
+impl<W, M> WalletAuditClient for Layered<W, M>
+where
+    W: WalletAuditClient,
+    M: WalletMiddleware,
+{
+    layered_operations! {
+        Read(Some(name)) fn export_history(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            format: HistoryExportFormat,
+            filter: TransactionHistoryFilter,
+        ) -> Result<String>;
+
+        Read(Some(name)) fn annotations(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<BTreeMap<TxId, TxAnnotation>>;
+
+        Write(Some(name)) fn set_annotation(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            transaction_id: TxId,
+            annotation: TxAnnotation,
+        ) -> Result<()>;
+
+        Write(Some(name)) fn set_transaction_memo(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            transaction_id: TxId,
+            memo: String,
+        ) -> Result<()>;
+
+        Write(Some(name)) fn import_annotations(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            entries: Vec<(TxId, TxAnnotation)>,
+            conflict: AnnotationConflict,
+        ) -> Result<AnnotationImportReport>;
+
+        Read(Some(name)) fn address_book(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<BTreeMap<String, AddressMetadata>>;
+
+        Read(Some(name)) fn get_address_metadata(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            address: &str,
+        ) -> Result<Option<AddressMetadata>>;
+
+        Write(Some(name)) fn set_address_label(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            address: &str,
+            label: &str,
+        ) -> Result<()>;
+
+        Read(Some(name)) fn operator_profile(&self, name: &str) -> Result<Option<OperatorProfile>>;
+
+        Write(Some(name)) fn set_operator_profile(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            staking_address: StakedStateAddress,
+        ) -> Result<()>;
+
+        Read(Some(name)) fn alert_rules(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<Vec<AlertRule>>;
+
+        Write(Some(name)) fn set_alert_rules(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            rules: Vec<AlertRule>,
+        ) -> Result<()>;
+
+        Read(Some(name)) fn alerts(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            offset: usize,
+            limit: usize,
+        ) -> Result<Vec<Alert>>;
+    }
+}
+
+impl<W, M> WalletSpendControlClient for Layered<W, M>
+where
+    W: WalletSpendControlClient,
+    M: WalletMiddleware,
+{
+    layered_operations! {
+        Write(Some(name)) fn set_spend_approval(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            required: bool,
+        ) -> Result<()>;
+
+        Read(Some(name)) fn pending_approvals(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<Vec<PendingApproval>>;
+
+        Write(Some(name)) fn approve_transaction(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            transaction_id: TxId,
+        ) -> Result<TxId>;
+
+        Write(Some(name)) fn reject_transaction(
+            &self,
+            name: &str,
+            passphrase: &SecUtf8,
+            transaction_id: TxId,
+        ) -> Result<()>;
+
+        Write(Some(name)) fn freeze_utxo(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            input: &TxoPointer,
+        ) -> Result<()>;
+
+        Write(Some(name)) fn unfreeze_utxo(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            input: &TxoPointer,
+        ) -> Result<()>;
+
+        Read(Some(name)) fn frozen_utxos(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<Vec<TxoPointer>>;
+
+        Write(Some(name)) fn reserve_balance(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            amount: Coin,
+            ttl: u64,
+            tag: String,
+        ) -> Result<BalanceReservation>;
+
+        Read(Some(name)) fn reservations(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+        ) -> Result<Vec<BalanceReservation>>;
+
+        Write(Some(name)) fn release_reservation(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            id: u64,
+        ) -> Result<BalanceReservation>;
+
+        Write(Some(name)) fn release_reservations_by_tag(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            tag: &str,
+        ) -> Result<Vec<BalanceReservation>>;
+    }
+}
+
+impl<W, M> WalletPaymentClient for Layered<W, M>
+where
+    W: WalletPaymentClient,
+    M: WalletMiddleware,
+{
+    layered_operations! {
+        Write(Some(from_name)) fn internal_transfer(
+            &self,
+            from_name: &str,
+            from_enckey: &SecKey,
+            to_name: &str,
+            to_enckey: &SecKey,
+            amount: Coin,
+            network_id: u8,
+        ) -> Result<TxId>;
+
+        Write(Some(name)) fn send_to_many(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            recipients: Vec<TransferRecipient>,
+            network_id: u8,
+            input_selection_strategy: Option<InputSelectionStrategy>,
+        ) -> Result<Vec<RecipientOutput>>;
+
+        Write(Some(name)) fn send_batch(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            batch_name: &str,
+            recipients: Vec<(ExtendedAddr, Coin)>,
+            view_keys: &mut BTreeSet<PublicKey>,
+            network_id: u8,
+        ) -> Result<PayoutBatch>;
+
+        Read(Some(name)) fn payout_batch(
+            &self,
+            name: &str,
+            enckey: &SecKey,
+            batch_name: &str,
+        ) -> Result<Option<PayoutBatch>>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::storage::MemoryStorage;
+
+    use crate::wallet::DefaultWalletClient;
+
+    #[test]
+    fn check_passphrase_lockout() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let wrong_passphrase = SecUtf8::from("123457");
+        let storage = MemoryStorage::default();
+        let client = Layered::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            PassphraseLockout::new(storage),
+        );
+        client
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+
+        for _ in 0..3 {
+            assert_eq!(
+                ErrorKind::DecryptionError,
+                client
+                    .auth_token("Default", &wrong_passphrase)
+                    .unwrap_err()
+                    .kind()
+            );
+        }
+        let status = client.passphrase_lockout_status("Default").unwrap();
+        assert!(status.locked);
+        assert_eq!(3, status.failed_attempts);
+
+        // even the correct passphrase is rejected during lockout
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .auth_token("Default", &passphrase)
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .delete_wallet("Default", &passphrase)
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_read_only_guard_and_metrics() {
+        let metrics = Metrics::default();
+        let client = Layered::new(
+            DefaultWalletClient::new_read_only(MemoryStorage::default()),
+            (metrics.clone(), ReadOnlyGuard),
+        );
+        let passphrase = SecUtf8::from("123456");
+
+        assert!(client.wallets().unwrap().is_empty());
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .new_wallet(
+                    "Default",
+                    &passphrase,
+                    WalletKind::Basic,
+                    HardwareKind::LocalOnly,
+                    None
+                )
+                .unwrap_err()
+                .kind()
+        );
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            OperationMetrics {
+                calls: 1,
+                errors: 0
+            },
+            snapshot["wallets"]
+        );
+        assert_eq!(
+            OperationMetrics {
+                calls: 1,
+                errors: 1
+            },
+            snapshot["new_wallet"]
+        );
+    }
+
+    #[test]
+    fn check_read_only_guard_rejects_secrets() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let storage = MemoryStorage::default();
+        let enckey = DefaultWalletClient::new_read_only(storage.clone())
+            .restore_wallet("Default", &passphrase, &words)
+            .expect("restore wallet");
+        let client = Layered::new(DefaultWalletClient::new_read_only(storage), ReadOnlyGuard);
+
+        let view_key = client.view_key("Default", &enckey).expect("view key");
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .view_key_private("Default", &enckey)
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .private_key("Default", &enckey, &view_key)
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .wallet_private_key("Default", &enckey, WalletKind::HD)
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client.export_wallet("Default", &enckey).unwrap_err().kind()
+        );
+    }
+}
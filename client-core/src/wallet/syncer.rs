@@ -1,20 +1,22 @@
 #![allow(missing_docs)]
 use indexmap::IndexMap;
 use itertools::{izip, Itertools};
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::iter;
 use std::path::Path;
 use std::result;
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 use std::thread;
 use std::time::Duration;
 use tendermint_light_client::peer_list::PeerListBuilder;
 pub use tendermint_light_client::supervisor::Handle;
 use tendermint_light_client::{
     components::{
-        clock::SystemClock,
+        clock::Clock as LightClientClock,
         io::{AtHeight, Io, ProdIo},
         scheduler,
         verifier::ProdVerifier,
@@ -29,8 +31,9 @@ use tendermint_light_client::{
     types::{LightBlock, PeerId, Status, TrustThreshold},
 };
 
-use chain_core::common::H256;
-use chain_core::state::account::StakedStateAddress;
+use chain_core::common::{Timespec, H256};
+use chain_core::state::account::{StakedState, StakedStateAddress};
+use chain_core::state::compact_block::CompactBlock;
 use chain_core::state::ChainState;
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::TxId;
@@ -42,22 +45,38 @@ use client_common::tendermint::types::{
     Block, BlockExt, BlockResults, BlockResultsResponse, Genesis, Time,
 };
 use client_common::tendermint::Client;
+use client_common::{Clock, SystemClock};
 use client_common::{
     Error, ErrorKind, PrivateKey, Result, ResultExt, SecKey, SecureStorage, Transaction,
     TransactionObfuscation,
 };
+use parity_scale_codec::{Decode, Encode, Error as CodecError, Input, Output};
 
 use super::syncer_logic::handle_blocks;
 use crate::service;
-use crate::service::{KeyService, SyncState, Wallet, WalletState, WalletStateMemento};
+use crate::service::{
+    AlertService, DeviceSyncService, KeyService, SyncJournalEntry, SyncState, Wallet, WalletState,
+    WalletStateMemento,
+};
+use crate::types::{Alert, TransactionChange, WalletBalance, WalletEvent, WalletKind};
 use std::sync::Mutex;
 type BlockConfirmFunc = Arc<Mutex<Box<dyn Fn(u64, String) -> bool>>>; // height, blockhash
 
 pub trait LightClientHandle: Handle + Send + Sync + Clone {}
 impl<T: Handle + Send + Sync + Clone> LightClientHandle for T {}
 
+/// Default number of not yet derived addresses scanned by the address recovery
+pub const DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT: u32 = 20;
+
+/// Number of block batches fetched ahead of the one being processed when syncing
+const SYNC_PREFETCH_BATCHES: usize = 4;
+
+/// Blocks of a batch with their results and chain states
+type BlockData = (Vec<Block>, Vec<BlockResultsResponse>, Vec<ChainState>);
+
 pub trait AddressRecovery: Clone + Send + Sync {
     // new_address: transfer address in TxOut
+    // gap_limit: number of not yet derived addresses to check
     // return: true, new addresses are generated
     fn recover_addresses(
         &mut self,
@@ -65,6 +84,18 @@ pub trait AddressRecovery: Clone + Send + Sync {
         name: &str,
         enckey: &SecKey,
         wallet: &mut Wallet,
+        gap_limit: u32,
+    ) -> Result<bool>;
+
+    // staking_address: staking address which stake is deposited to
+    // gap_limit: number of not yet derived addresses to check
+    // return: true, new addresses are generated
+    fn recover_staking_address(
+        &mut self,
+        staking_address: &StakedStateAddress,
+        name: &str,
+        enckey: &SecKey,
+        gap_limit: u32,
     ) -> Result<bool>;
 }
 
@@ -87,7 +118,7 @@ where
 #[derive(Clone)]
 pub struct TxObfuscationDecryptor<O: TransactionObfuscation> {
     obfuscation: O,
-    private_key: PrivateKey,
+    private_key: Option<PrivateKey>,
 }
 
 impl<O: TransactionObfuscation> TxObfuscationDecryptor<O> {
@@ -95,14 +126,26 @@ impl<O: TransactionObfuscation> TxObfuscationDecryptor<O> {
     pub fn new(obfuscation: O, private_key: PrivateKey) -> TxObfuscationDecryptor<O> {
         TxObfuscationDecryptor {
             obfuscation,
-            private_key,
+            private_key: Some(private_key),
+        }
+    }
+
+    /// Construct TxObfuscationDecryptor for a watch-only wallet without private view key, which
+    /// can't decrypt any transaction
+    pub fn watch_only(obfuscation: O) -> TxObfuscationDecryptor<O> {
+        TxObfuscationDecryptor {
+            obfuscation,
+            private_key: None,
         }
     }
 }
 
 impl<O: TransactionObfuscation> TxDecryptor for TxObfuscationDecryptor<O> {
     fn decrypt_tx(&self, txids: &[TxId]) -> Result<Vec<Transaction>> {
-        self.obfuscation.decrypt(&txids, &self.private_key)
+        match &self.private_key {
+            Some(private_key) => self.obfuscation.decrypt(&txids, private_key),
+            None => Ok(vec![]),
+        }
     }
 }
 
@@ -112,12 +155,18 @@ pub struct SyncerOptions {
     pub enable_fast_forward: bool,
     pub disable_light_client: bool,
     pub enable_address_recovery: bool,
+    /// Number of not yet derived addresses checked against the transactions when recovering
+    /// addresses
+    pub address_recovery_gap_limit: u32,
     pub batch_size: usize,
     pub block_height_ensure: u64,
     pub light_client_peers: String,
     pub light_client_trusting_period_seconds: u64,
     pub light_client_trusting_height: u64,
     pub light_client_trusting_blockhash: String,
+    /// Start brand-new wallets from current verified height instead of genesis
+    /// (only generated wallets are brand-new, restored or imported ones sync from genesis)
+    pub sync_from_now: bool,
 }
 
 /// Common configs for wallet syncer with `TransactionObfuscation`
@@ -228,18 +277,150 @@ where
         Ok(())
     }
 
+    /// Delete wallet state (unspent transactions, history) and rewind sync state to the block
+    /// before `from_height`, keys and addresses are kept.
+    ///
+    /// Next `sync` replays blocks from `from_height`, e.g. to pick up transactions of imported
+    /// keys or recovered addresses.
+    pub fn rescan_state(&self, from_height: u64) -> Result<()> {
+        let wallet = service::load_wallet(&self.storage, &self.name, &self.enckey)?
+            .err_kind(ErrorKind::InvalidInput, || {
+                format!("wallet not found: {}", self.name)
+            })?;
+
+        let sync_state = if from_height <= 1 {
+            let enable_genesis_fingerprint_check = !self.options.enable_fast_forward;
+            get_genesis_sync_state(&self.client, enable_genesis_fingerprint_check)?
+        } else {
+            let height = from_height - 1;
+            let block = self.client.block(height)?;
+            let block_result = self.client.block_results(height)?;
+            let state = self
+                .client
+                .query_state_batch(iter::once(height))?
+                .pop()
+                .err_kind(ErrorKind::TendermintRpcError, || {
+                    format!("chain state not found at height: {}", height)
+                })?;
+            let block = FilteredBlock::from_block(
+                &wallet,
+                &WalletState::default(),
+                &block,
+                &block_result,
+                &state,
+            )?;
+            SyncState {
+                last_block_height: block.block_height,
+                last_app_hash: block.app_hash,
+                last_block_hash: block.block_hash,
+                staking_root: block.staking_root,
+                trusted: false,
+            }
+        };
+
+        service::delete_wallet_state(&self.storage, &self.name)?;
+        self.storage
+            .delete(service::SYNC_CHECKPOINT_KEYSPACE, &self.name)?;
+        self.storage
+            .delete(service::SYNC_JOURNAL_KEYSPACE, &self.name)?;
+        service::save_sync_state(&self.storage, &self.name, &sync_state)?;
+        self.storage.flush()
+    }
+
+    /// Returns height, app hash and block hash of the latest block to synchronize to
+    fn sync_target(&self) -> Result<(u64, String, String)> {
+        let status = self.client.status()?;
+        if status.sync_info.catching_up {
+            return Err(Error::new(
+                ErrorKind::TendermintRpcError,
+                "Tendermint node is catching up with full node (retry after some time)",
+            ));
+        }
+
+        if self.options.enable_fast_forward || self.options.disable_light_client {
+            Ok((
+                /* use Tendermint RPC directly */
+                status.sync_info.latest_block_height.value(),
+                status
+                    .sync_info
+                    .latest_app_hash
+                    .map(|hash| hash.to_string())
+                    .unwrap_or_default(),
+                status
+                    .sync_info
+                    .latest_block_hash
+                    .map(|hash| hash.to_string())
+                    .unwrap_or_default(),
+            ))
+        } else {
+            /* use light client */
+            let light_block = self
+                .light_client
+                .as_ref()
+                .expect("get light client")
+                .verify_to_highest()
+                .map_err(|e| Error::new(ErrorKind::VerifyError, format!("{}", e)))?;
+
+            let target_height = light_block.signed_header.header.height.value();
+            let target_app_hash = hex::encode_upper(&light_block.signed_header.header.app_hash);
+            let target_block_hash = ProdHasher {}
+                .hash_header(&light_block.signed_header.header)
+                .to_string();
+            {
+                // wait for the target block results to become available
+                let mut success = false;
+                for _ in 0..10 {
+                    if self.client.block_results(target_height).is_ok() {
+                        success = true;
+                        break;
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                if !success {
+                    return Err(Error::new(
+                        ErrorKind::TendermintRpcError,
+                        "block result for highest light block is not available",
+                    ));
+                }
+            }
+            Ok((target_height, target_app_hash, target_block_hash))
+        }
+    }
+
     /// Load wallet state in memory, sync it to most recent latest, then drop the memory cache.
-    pub fn sync<F: FnMut(ProgressReport) -> bool>(&mut self, callback: F) -> Result<()> {
-        WalletSyncerImpl::new(self, callback)?.sync()
+    pub fn sync<F: FnMut(ProgressReport) -> bool>(&mut self, callback: F) -> Result<()>
+    where
+        C: 'static,
+    {
+        self.sync_with_reporter(callback)
+    }
+
+    /// Same as `sync`, reporting progress to given reporter
+    pub fn sync_with_reporter<R: SyncProgressReporter>(&mut self, reporter: R) -> Result<()>
+    where
+        C: 'static,
+    {
+        WalletSyncerImpl::new(self, reporter)?.sync()
     }
 }
 
-fn load_view_key<S: SecureStorage>(storage: &S, name: &str, enckey: &SecKey) -> Result<PrivateKey> {
-    KeyService::new(storage.clone())
-        .wallet_private_key(name, enckey)?
-        .err_kind(ErrorKind::InvalidInput, || {
-            format!("wallet private view key not found: {}", name)
-        })
+/// Returns `None` for watch-only wallets without private view key
+fn load_view_key<S: SecureStorage + 'static>(
+    storage: &S,
+    name: &str,
+    enckey: &SecKey,
+) -> Result<Option<PrivateKey>> {
+    let private_key = KeyService::new(storage.clone()).wallet_private_key(name, enckey)?;
+    let watch_only = service::load_wallet(storage, name, enckey)?
+        .map(|wallet| wallet.wallet_kind == WalletKind::WatchOnly)
+        .unwrap_or_default();
+    if private_key.is_none() && !watch_only {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("wallet private view key not found: {}", name),
+        ));
+    }
+    Ok(private_key)
 }
 
 impl<S, C, O, T, L> WalletSyncer<S, C, TxObfuscationDecryptor<O>, T, L>
@@ -260,8 +441,10 @@ where
     where
         O: TransactionObfuscation,
     {
-        let private_key = load_view_key(&config.storage, &name, &enckey)?;
-        let decryptor = TxObfuscationDecryptor::new(config.obfuscation, private_key);
+        let decryptor = match load_view_key(&config.storage, &name, &enckey)? {
+            Some(private_key) => TxObfuscationDecryptor::new(config.obfuscation, private_key),
+            None => TxObfuscationDecryptor::watch_only(config.obfuscation),
+        };
         Ok(Self::with_config(
             SyncerConfig {
                 storage: config.storage,
@@ -283,96 +466,256 @@ struct WalletSyncerImpl<
     S: SecureStorage,
     C: Client,
     D: TxDecryptor,
-    F: FnMut(ProgressReport) -> bool,
+    R: SyncProgressReporter,
     T: AddressRecovery,
     L: LightClientHandle,
 > {
     env: &'a mut WalletSyncer<S, C, D, T, L>,
-    progress_callback: F,
+    progress_reporter: R,
 
     // cached state
     wallet: Wallet,
     sync_state: SyncState,
     wallet_state: WalletState,
+    /// sync state as of the last commit (`sync_state` runs ahead while a batch is verified)
+    committed_sync_state: SyncState,
 }
 
 impl<
         'a,
         S: SecureStorage + 'static,
-        C: Client,
+        C: Client + 'static,
         D: TxDecryptor,
-        F: FnMut(ProgressReport) -> bool,
+        R: SyncProgressReporter,
         T: AddressRecovery,
         L: LightClientHandle,
-    > WalletSyncerImpl<'a, S, C, D, F, T, L>
+    > WalletSyncerImpl<'a, S, C, D, R, T, L>
 {
-    fn new(env: &'a mut WalletSyncer<S, C, D, T, L>, progress_callback: F) -> Result<Self> {
+    fn new(env: &'a mut WalletSyncer<S, C, D, T, L>, progress_reporter: R) -> Result<Self> {
         let wallet = service::load_wallet(&env.storage, &env.name, &env.enckey)?
             .err_kind(ErrorKind::InvalidInput, || {
                 format!("wallet not found: {}", env.name)
             })?;
 
         let mstate = service::load_sync_state(&env.storage, &env.name)?;
+        let mwallet_state = service::load_wallet_state(&env.storage, &env.name, &env.enckey)?;
         let sync_state = if let Some(sync_state) = mstate {
             sync_state
+        } else if env.options.sync_from_now && service::is_brand_new(&env.storage, &env.name)? {
+            // brand-new wallet can't have any history before current height, restored or
+            // imported wallets are never marked as brand-new and always sync from genesis
+            get_birth_sync_state(env)?
         } else {
             // if fast-forward, don't check genesis fingerprint
             let enable_genesis_fingerprint_check = !env.options.enable_fast_forward;
             get_genesis_sync_state(&env.client, enable_genesis_fingerprint_check)?
         };
 
-        let wallet_state =
-            service::load_wallet_state(&env.storage, &env.name, &env.enckey)?.unwrap_or_default();
+        let wallet_state = mwallet_state.unwrap_or_default();
 
         Ok(Self {
             env,
-            progress_callback,
+            progress_reporter,
             wallet,
+            committed_sync_state: sync_state.clone(),
             sync_state,
             wallet_state,
         })
     }
 
     fn init_progress(&mut self, height: u64) -> bool {
-        (self.progress_callback)(ProgressReport::Init {
-            wallet_name: self.env.name.clone(),
-            start_block_height: self.sync_state.last_block_height,
-            finish_block_height: height,
-        })
-    }
-
-    fn update_progress(&mut self, height: u64) -> bool {
-        (self.progress_callback)(ProgressReport::Update {
-            wallet_name: self.env.name.clone(),
-            current_block_height: height,
-        })
+        self.progress_reporter
+            .on_start(&self.env.name, self.sync_state.last_block_height, height)
     }
 
-    fn update_state(&mut self, memento: &WalletStateMemento) -> Result<()> {
+    /// Applies the memento to the wallet state, returns the memento which reverts it
+    fn update_state(&mut self, memento: &WalletStateMemento) -> Result<WalletStateMemento> {
+        let undo = RefCell::new(WalletStateMemento::default());
         // if there is a job, then fetch & update, if not skip
         if !memento.is_empty() {
             self.wallet_state = service::modify_wallet_state(
                 &self.env.storage,
                 &self.env.name,
                 &self.env.enckey,
-                |state| state.apply_memento(memento),
+                |state| {
+                    *undo.borrow_mut() = state.apply_memento_with_undo(memento)?;
+                    Ok(())
+                },
             )?;
         }
-        Ok(())
+        Ok(undo.into_inner())
     }
 
     fn save(&mut self, memento: &WalletStateMemento) -> Result<()> {
-        service::save_sync_state(&self.env.storage, &self.env.name, &self.sync_state)?;
+        // wallet state goes first, so the sync state never runs ahead of the applied changes
         self.update_state(memento)?;
+        self.commit_sync_state()
+    }
+
+    /// Same as `save`, the changes are journaled so that the batch can be unwound if the chain
+    /// is reorganized
+    fn save_batch(&mut self, memento: &WalletStateMemento) -> Result<()> {
+        let undo = self.update_state(memento)?;
+        let mut journal =
+            service::load_sync_journal(&self.env.storage, &self.env.name, &self.env.enckey)?;
+        journal.push(SyncJournalEntry {
+            previous: self.committed_sync_state.clone(),
+            undo,
+        });
+        service::save_sync_journal(
+            &self.env.storage,
+            &self.env.name,
+            &self.env.enckey,
+            &journal,
+        )?;
+        self.commit_sync_state()
+    }
+
+    fn commit_sync_state(&mut self) -> Result<()> {
+        service::save_sync_state(&self.env.storage, &self.env.name, &self.sync_state)?;
+        DeviceSyncService::new(self.env.storage.clone()).record_event(
+            &self.env.name,
+            &self.env.enckey,
+            WalletEvent::sync_checkpoint(&self.sync_state),
+        )?;
         self.env.storage.flush()?;
+        self.committed_sync_state = self.sync_state.clone();
+        Ok(())
+    }
+
+    /// Checks if the last block of given sync state is on the chain served by the node
+    fn is_on_chain(&self, state: &SyncState, target_height: u64) -> Result<bool> {
+        if state.last_block_hash.is_empty() {
+            // nothing synchronized yet
+            return Ok(true);
+        }
+        if state.last_block_height > target_height {
+            return Ok(false);
+        }
+        let block = self.env.client.block(state.last_block_height)?;
+        Ok(ProdHasher {}.hash_header(&block.header).to_string() == state.last_block_hash)
+    }
+
+    /// Unwinds the batches synchronized past the fork point if the node serves a different chain
+    /// than the one synchronized (e.g. its data was wiped), so that the new chain is synchronized
+    /// from there.
+    fn handle_reorg(&mut self, target_height: u64) -> Result<()> {
+        let mut journal =
+            service::load_sync_journal(&self.env.storage, &self.env.name, &self.env.enckey)?;
+
+        // find the fork point before changing anything
+        let mut depth = 0;
+        let mut state = &self.sync_state;
+        while !self.is_on_chain(state, target_height)? {
+            state = journal
+                .len()
+                .checked_sub(depth + 1)
+                .map(|index| &journal[index].previous)
+                .err_kind(ErrorKind::VerifyError, || {
+                    format!(
+                        "Chain reorganization is deeper than the sync journal of wallet {}, the \
+                         wallet needs to be rescanned",
+                        self.env.name
+                    )
+                })?;
+            depth += 1;
+        }
+        if depth == 0 {
+            return Ok(());
+        }
+
+        log::warn!(
+            "block {} synchronized by wallet {} is not on the chain anymore, rolling back to \
+             block {}",
+            self.sync_state.last_block_height,
+            self.env.name,
+            state.last_block_height
+        );
+        let unwound = journal.split_off(journal.len() - depth);
+        for entry in unwound.into_iter().rev() {
+            self.update_state(&entry.undo)?;
+            self.sync_state = entry.previous;
+        }
+        self.sync_state.trusted = false;
+        service::save_sync_journal(
+            &self.env.storage,
+            &self.env.name,
+            &self.env.enckey,
+            &journal,
+        )?;
+        self.delete_checkpoint()?;
+        self.commit_sync_state()
+    }
+
+    fn save_checkpoint(
+        &self,
+        blocks: &NonEmpty<FilteredBlock>,
+        memento: Option<&WalletStateMemento>,
+    ) -> Result<()> {
+        // same encoding as `SyncCheckpoint`, without copying the blocks
+        let checkpoint = (blocks.iter().collect::<Vec<_>>(), memento);
+        self.env.storage.save_secure(
+            service::SYNC_CHECKPOINT_KEYSPACE,
+            &self.env.name,
+            &self.env.enckey,
+            &checkpoint,
+        )?;
+        self.env.storage.flush()
+    }
+
+    fn delete_checkpoint(&self) -> Result<()> {
+        self.env
+            .storage
+            .delete(service::SYNC_CHECKPOINT_KEYSPACE, &self.env.name)?;
         Ok(())
     }
 
+    /// Finishes the batch interrupted by the last run (e.g. the process was killed), if any.
+    ///
+    /// Blocks of the checkpoint are not fetched again, and if its memento was computed, the
+    /// blocks are not processed again either. Replaying a memento which was already applied is
+    /// harmless, as its operations are idempotent.
+    fn resume_checkpoint(&mut self) -> Result<()> {
+        let checkpoint: SyncCheckpoint = match self.env.storage.load_secure(
+            service::SYNC_CHECKPOINT_KEYSPACE,
+            &self.env.name,
+            &self.env.enckey,
+        )? {
+            Some(checkpoint) => checkpoint,
+            None => return Ok(()),
+        };
+        let resumable = checkpoint
+            .blocks
+            .first()
+            .map(|block| {
+                block.block_height == self.sync_state.last_block_height + 1
+                    && (self.sync_state.last_app_hash.is_empty()
+                        || self.sync_state.last_app_hash == block.last_app_hash)
+                    && (self.sync_state.last_block_hash.is_empty()
+                        || self.sync_state.last_block_hash == block.last_block_hash)
+            })
+            .unwrap_or(false);
+
+        match NonEmpty::new(checkpoint.blocks) {
+            Some(blocks) if resumable => {
+                log::info!(
+                    "resume interrupted sync of wallet {} from block {}",
+                    self.env.name,
+                    blocks.first().block_height
+                );
+                self.handle_batch_with_memento(blocks, checkpoint.memento)
+            }
+            // the batch was committed already, or the sync state was reset since then
+            _ => self.delete_checkpoint(),
+        }
+    }
+
     pub fn handle_recover_addresses_for_transaction(
         &mut self,
         transaction: &Transaction,
     ) -> Result<bool> {
         let mut refetch = false;
+        let gap_limit = self.env.options.address_recovery_gap_limit;
 
         let outputs = transaction.outputs().to_vec();
 
@@ -383,6 +726,7 @@ impl<
                 &self.env.name,
                 &self.env.enckey,
                 &mut self.wallet,
+                gap_limit,
             )?;
 
             if tmp_refetch {
@@ -390,9 +734,22 @@ impl<
             }
         }
 
+        if let Transaction::DepositStakeTransaction(tx) = transaction {
+            if self.env.recover_address.recover_staking_address(
+                &tx.to_staked_account,
+                &self.env.name,
+                &self.env.enckey,
+                gap_limit,
+            )? {
+                refetch = true;
+            }
+        }
+
         Ok(refetch)
     }
 
+    /// Recovers the addresses used by the transactions in the blocks, the wallet is reloaded if
+    /// new addresses are generated, so that their transactions are handled too.
     fn handle_recover_addresses(&mut self, blocks: &[FilteredBlock]) -> Result<()> {
         let enclave_txids = blocks
             .iter()
@@ -404,21 +761,74 @@ impl<
             .map(|tx| (tx.id(), tx))
             .collect::<HashMap<_, _>>();
 
+        let mut refetch = false;
         for block in blocks {
             for txid in block.enclave_transaction_ids.iter() {
                 if let (Some(tx), Some(_fee)) = (
                     enclave_transactions.get(txid),
                     block.valid_transaction_fees.get(txid),
                 ) {
-                    self.handle_recover_addresses_for_transaction(&tx)?;
+                    if self.handle_recover_addresses_for_transaction(&tx)? {
+                        refetch = true;
+                    }
                 }
             }
         }
 
+        if refetch {
+            self.wallet =
+                service::load_wallet(&self.env.storage, &self.env.name, &self.env.enckey)?
+                    .err_kind(ErrorKind::InvalidInput, || {
+                        format!("wallet not found: {}", self.env.name)
+                    })?;
+        }
+
         Ok(())
     }
 
     fn handle_batch(&mut self, blocks: NonEmpty<FilteredBlock>) -> Result<()> {
+        self.handle_batch_with_memento(blocks, None)
+    }
+
+    /// Commits a batch of blocks, `memento` is the already computed changes of the batch.
+    ///
+    /// Progress is checkpointed after fetching and after processing the blocks, the checkpoint is
+    /// deleted once the batch is committed.
+    fn handle_batch_with_memento(
+        &mut self,
+        blocks: NonEmpty<FilteredBlock>,
+        memento: Option<WalletStateMemento>,
+    ) -> Result<()> {
+        let memento = match memento {
+            Some(memento) => memento,
+            None => {
+                self.save_checkpoint(&blocks, None)?;
+                let memento = self.process_batch(&blocks)?;
+                self.save_checkpoint(&blocks, Some(&memento))?;
+                memento
+            }
+        };
+
+        let block = blocks.last();
+        self.sync_state.last_block_height = block.block_height;
+        self.sync_state.last_app_hash = block.app_hash.clone();
+        self.sync_state.last_block_hash = block.block_hash.clone();
+        self.sync_state.staking_root = block.staking_root;
+        self.save_batch(&memento)?;
+        self.delete_checkpoint()?;
+        self.report_changes(block, &memento)?;
+
+        if !self
+            .progress_reporter
+            .on_batch_committed(&self.env.name, block.block_height)
+        {
+            return Err(Error::new(ErrorKind::InvalidInput, "Cancelled by user"));
+        }
+
+        Ok(())
+    }
+
+    fn process_batch(&mut self, blocks: &NonEmpty<FilteredBlock>) -> Result<WalletStateMemento> {
         let enclave_txids = blocks
             .iter()
             .flat_map(|block| block.enclave_transaction_ids.iter().copied())
@@ -438,25 +848,60 @@ impl<
             &mut self.wallet_state,
             &blocks,
             &enclave_txs,
-            &mut self.progress_callback,
+            &mut self.progress_reporter,
         )
         .map_err(|err| Error::new(ErrorKind::InvalidInput, err.to_string()))?;
         log::debug!(
             "syncer handle_blocks time {} micro-seconds",
             handle_blocks_time.elapsed().as_micros()
         );
+        Ok(memento)
+    }
 
-        let block = blocks.last();
-        self.sync_state.last_block_height = block.block_height;
-        self.sync_state.last_app_hash = block.app_hash.clone();
-        self.sync_state.last_block_hash = block.block_hash.clone();
-        self.sync_state.staking_root = block.staking_root;
-        self.save(&memento)?;
-
-        if !self.update_progress(block.block_height) {
-            return Err(Error::new(ErrorKind::InvalidInput, "Cancelled by user"));
+    /// Reports the transactions of a saved batch and the new balance to the progress reporter,
+    /// and evaluates alert rules of the wallet, triggered alerts are recorded in the alert log and
+    /// reported to the progress reporter
+    fn report_changes(
+        &mut self,
+        block: &FilteredBlock,
+        memento: &WalletStateMemento,
+    ) -> Result<()> {
+        let changes = memento.transaction_changes().collect::<Vec<_>>();
+        let balance = self
+            .wallet_state
+            .get_balance()
+            .chain(|| (ErrorKind::IllegalInput, "Unable to compute wallet balance"))?;
+        if !changes.is_empty() {
+            self.progress_reporter
+                .on_state_changed(&self.env.name, &changes, &balance);
         }
+        let client = &self.env.client;
+        let alerts = AlertService::new(self.env.storage.clone()).evaluate(
+            &self.env.name,
+            block.block_height,
+            to_timespec(block.block_time),
+            &changes,
+            &balance,
+            |address| {
+                let bytes = client
+                    .query(
+                        "staking",
+                        address.as_ref(),
+                        Some(block.block_height.into()),
+                        false,
+                    )?
+                    .bytes();
+                <Option<StakedState>>::decode(&mut bytes.as_slice())
+                    .err_kind(ErrorKind::DeserializationError, || {
+                        format!("Cannot deserialize staked state for address: {}", address)
+                    })
+            },
+        )?;
 
+        for alert in alerts {
+            log::warn!("alert of wallet {}: {}", self.env.name, alert.message);
+            self.progress_reporter.on_alert(&self.env.name, alert);
+        }
         Ok(())
     }
 
@@ -468,82 +913,17 @@ impl<
             &self.wallet_state,
         )?;
 
-        let status = self.env.client.status()?;
-        if status.sync_info.catching_up {
-            return Err(Error::new(
-                ErrorKind::TendermintRpcError,
-                "Tendermint node is catching up with full node (retry after some time)",
-            ));
-        }
-
-        let (target_height, target_app_hash, target_block_hash) =
-            if self.env.options.enable_fast_forward || self.env.options.disable_light_client {
-                (
-                    /* use Tendermint RPC directly */
-                    status.sync_info.latest_block_height.value(),
-                    status
-                        .sync_info
-                        .latest_app_hash
-                        .map(|hash| hash.to_string())
-                        .unwrap_or_default(),
-                    status
-                        .sync_info
-                        .latest_block_hash
-                        .map(|hash| hash.to_string())
-                        .unwrap_or_default(),
-                )
-            } else {
-                /* use light client */
-                let light_block = self
-                    .env
-                    .light_client
-                    .as_ref()
-                    .expect("get light client")
-                    .verify_to_highest()
-                    .map_err(|e| Error::new(ErrorKind::VerifyError, format!("{}", e)))?;
-
-                let target_height = light_block.signed_header.header.height.value();
-                let target_app_hash = hex::encode_upper(&light_block.signed_header.header.app_hash);
-                let target_block_hash = ProdHasher {}
-                    .hash_header(&light_block.signed_header.header)
-                    .to_string();
-                {
-                    // wait for the target block results to become available
-                    let mut success = false;
-                    for _ in 0..10 {
-                        if self.env.client.block_results(target_height).is_ok() {
-                            success = true;
-                            break;
-                        }
-                        thread::sleep(Duration::from_millis(100));
-                    }
-                    if !success {
-                        return Err(Error::new(
-                            ErrorKind::TendermintRpcError,
-                            "block result for highest light block is not available",
-                        ));
-                    }
-                }
-                (target_height, target_app_hash, target_block_hash)
-            };
+        let (target_height, target_app_hash, target_block_hash) = self.env.sync_target()?;
 
         if !self.init_progress(target_height) {
             return Err(Error::new(ErrorKind::InvalidInput, "Cancelled by user"));
         }
+        self.handle_reorg(target_height)?;
+        self.resume_checkpoint()?;
 
         self.sync_to(target_height, &target_app_hash, &target_block_hash)
     }
 
-    fn get_block_data_tuple_for_sync(
-        &mut self,
-        range: &[u64],
-    ) -> Result<(Vec<Block>, Vec<BlockResultsResponse>, Vec<ChainState>)> {
-        let blocks = self.env.client.block_batch(range.iter())?;
-        let block_results = self.env.client.block_results_batch(range.iter())?;
-        let states = self.env.client.query_state_batch(range.iter().cloned())?;
-        Ok((blocks, block_results, states)) // return tuple
-    }
-
     // recursively sync until all synced
     fn sync_to(
         &mut self,
@@ -554,113 +934,13 @@ impl<
         self.sync_state.trusted = false;
         log::debug!("sync_to block {} ", target_height);
 
-        // Send batch RPC requests to tendermint in chunks of `batch_size` requests per batch call
-        for chunk in ((self.sync_state.last_block_height + 1)..=target_height)
-            .chunks(self.env.options.batch_size)
-            .into_iter()
-        {
-            let mut batch = Vec::with_capacity(self.env.options.batch_size);
-            if self.env.options.enable_fast_forward {
-                if let Some(block) = self.fast_forward_status(&target_app_hash, target_height)? {
-                    // Fast forward to latest state if possible
-                    self.handle_batch((batch, block).into())?;
-                    return Ok(());
-                }
-            }
-
-            let range = chunk.collect::<Vec<u64>>();
-
-            if self.env.options.enable_fast_forward {
-                // Get the last block to check if there are any changes
-                let block = self.env.client.block(range[range.len() - 1])?;
-                if let Some(block) = self.fast_forward_block(&block)? {
-                    // Fast forward batch if possible
-                    self.handle_batch((batch, block).into())?;
-                    continue;
-                }
-            }
-
-            // Fetch batch details if it cannot be fast forwarded
-            let mut blocks: Vec<Block> = vec![];
-            let mut block_results: Vec<BlockResultsResponse> = vec![];
-            let mut states: Vec<ChainState> = vec![];
-            // if any error occurs, do it again
-            let mut succeed = false;
-            for _ in 0..12 {
-                let block_data_tuple = self.get_block_data_tuple_for_sync(&range);
-                if let Ok((tmp_blocks, tmp_block_results, tmp_states)) = block_data_tuple.as_ref() {
-                    blocks = tmp_blocks.to_vec();
-                    block_results = tmp_block_results.to_vec();
-                    states = tmp_states.to_vec();
-                    if blocks.len() == block_results.len() && block_results.len() == states.len() {
-                        assert!(blocks.len() == block_results.len());
-                        assert!(block_results.len() == states.len());
-                        log::debug!(
-                            "correct data blocks  {}  block_results {}  states {}",
-                            tmp_blocks.len(),
-                            tmp_block_results.len(),
-                            tmp_states.len()
-                        );
-                        succeed = true;
-                        break;
-                    } else {
-                        log::info!(
-                            "incorrect data blocks  {}  block_results {}  states {}",
-                            tmp_blocks.len(),
-                            tmp_block_results.len(),
-                            tmp_states.len()
-                        );
-                    }
-                }
-                log::info!("retry fetching block-data");
-                std::thread::sleep(std::time::Duration::from_secs(5));
-            }
-            // succeed?
-            if !succeed {
-                return Err(Error::new(ErrorKind::IoError, "sync fetch-block failed"));
-            }
-
-            for (block, block_result, state) in izip!(
-                blocks.into_iter(),
-                block_results.into_iter(),
-                states.into_iter()
-            ) {
-                let block = FilteredBlock::from_block(
-                    &self.wallet,
-                    &self.wallet_state,
-                    &block,
-                    &block_result,
-                    &state,
-                )?;
-
-                // verify app hash chain
-                if !self.sync_state.last_app_hash.is_empty()
-                    && self.sync_state.last_app_hash != block.last_app_hash
-                {
-                    return Err(Error::new(
-                        ErrorKind::VerifyError,
-                        "last app hash don't match",
-                    ));
-                }
-                self.sync_state.last_app_hash = block.app_hash.clone();
-
-                // verify block hash chain
-                if !self.sync_state.last_block_hash.is_empty()
-                    && self.sync_state.last_block_hash != block.last_block_hash
-                {
-                    return Err(Error::new(
-                        ErrorKind::VerifyError,
-                        "last block hash don't match",
-                    ));
-                }
-                self.sync_state.last_block_hash = block.block_hash.clone();
-
-                log::debug!("fetching block {}", block.block_height);
-                batch.push(block);
-            }
-            if let Some(non_empty_batch) = NonEmpty::new(batch) {
-                self.handle_batch(non_empty_batch)?;
+        let start_height = self.sync_state.last_block_height + 1;
+        if self.env.options.enable_fast_forward {
+            if self.sync_batches_fast_forward(start_height, target_height, target_app_hash)? {
+                return Ok(());
             }
+        } else {
+            self.sync_batches_pipelined(start_height, target_height)?;
         }
 
         match self.sync_state.last_block_height.cmp(&target_height) {
@@ -675,7 +955,10 @@ impl<
                     ));
                 };
                 self.sync_state.trusted = true;
-                service::save_sync_state(&self.env.storage, &self.env.name, &self.sync_state)
+                service::save_sync_state(&self.env.storage, &self.env.name, &self.sync_state)?;
+                self.progress_reporter
+                    .on_finish(&self.env.name, target_height);
+                Ok(())
             }
             Ordering::Greater => {
                 // impossible
@@ -692,6 +975,128 @@ impl<
         }
     }
 
+    /// Syncs blocks in batches, skipping the ones which are not relevant to the wallet, returns
+    /// `true` if fast forwarded to latest state
+    fn sync_batches_fast_forward(
+        &mut self,
+        start_height: u64,
+        target_height: u64,
+        target_app_hash: &str,
+    ) -> Result<bool> {
+        // Send batch RPC requests to tendermint in chunks of `batch_size` requests per batch call
+        for chunk in (start_height..=target_height)
+            .chunks(self.env.options.batch_size)
+            .into_iter()
+        {
+            if let Some(block) = self.fast_forward_status(&target_app_hash, target_height)? {
+                // Fast forward to latest state if possible
+                self.handle_batch((vec![], block).into())?;
+                return Ok(true);
+            }
+
+            let range = chunk.collect::<Vec<u64>>();
+
+            // Get the last block to check if there are any changes
+            let block = self.env.client.block(range[range.len() - 1])?;
+            if let Some(block) = self.fast_forward_block(&block)? {
+                // Fast forward batch if possible
+                self.handle_batch((vec![], block).into())?;
+                continue;
+            }
+
+            // Skip fetching full blocks if none of them is relevant to the wallet
+            if let Some(block) = self.fast_forward_compact(&range)? {
+                self.handle_batch((vec![], block).into())?;
+                continue;
+            }
+
+            // Fetch batch details if it cannot be fast forwarded
+            let data = fetch_block_data(&self.env.client, &range)
+                .err_kind(ErrorKind::IoError, || "sync fetch-block failed")?;
+            self.handle_block_data(data)?;
+        }
+        Ok(false)
+    }
+
+    /// Syncs all blocks in batches, the next batches are fetched by a background thread (at most
+    /// `SYNC_PREFETCH_BATCHES` ahead) while the current one is filtered, decrypted and committed
+    fn sync_batches_pipelined(&mut self, start_height: u64, target_height: u64) -> Result<()> {
+        let (sender, receiver) = mpsc::sync_channel(SYNC_PREFETCH_BATCHES);
+        let client = self.env.client.clone();
+        let batch_size = self.env.options.batch_size;
+        let fetcher = thread::spawn(move || {
+            for chunk in (start_height..=target_height)
+                .chunks(batch_size)
+                .into_iter()
+            {
+                let range = chunk.collect::<Vec<u64>>();
+                let data = fetch_block_data(&client, &range);
+                let failed = data.is_none();
+                // stops when the receiver is dropped because of an error
+                if sender.send(data).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        for data in receiver.iter() {
+            let data = data.err_kind(ErrorKind::IoError, || "sync fetch-block failed")?;
+            self.handle_block_data(data)?;
+        }
+        fetcher
+            .join()
+            .map_err(|_| Error::new(ErrorKind::InternalError, "block fetcher panicked"))
+    }
+
+    /// Filters fetched blocks, verifies the app hash and block hash chains and handles them as a
+    /// batch
+    fn handle_block_data(&mut self, data: BlockData) -> Result<()> {
+        let (blocks, block_results, states) = data;
+        let mut batch = Vec::with_capacity(blocks.len());
+        for (block, block_result, state) in izip!(
+            blocks.into_iter(),
+            block_results.into_iter(),
+            states.into_iter()
+        ) {
+            let block = FilteredBlock::from_block(
+                &self.wallet,
+                &self.wallet_state,
+                &block,
+                &block_result,
+                &state,
+            )?;
+
+            // verify app hash chain
+            if !self.sync_state.last_app_hash.is_empty()
+                && self.sync_state.last_app_hash != block.last_app_hash
+            {
+                return Err(Error::new(
+                    ErrorKind::VerifyError,
+                    "last app hash don't match",
+                ));
+            }
+            self.sync_state.last_app_hash = block.app_hash.clone();
+
+            // verify block hash chain
+            if !self.sync_state.last_block_hash.is_empty()
+                && self.sync_state.last_block_hash != block.last_block_hash
+            {
+                return Err(Error::new(
+                    ErrorKind::VerifyError,
+                    "last block hash don't match",
+                ));
+            }
+            self.sync_state.last_block_hash = block.block_hash.clone();
+
+            log::debug!("fetching block {}", block.block_height);
+            batch.push(block);
+        }
+        if let Some(non_empty_batch) = NonEmpty::new(batch) {
+            self.handle_batch(non_empty_batch)?;
+        }
+        Ok(())
+    }
+
     fn rollback_pending_tx(&mut self, current_block_height: u64) -> Result<()> {
         let mut memento = WalletStateMemento::default();
         let state =
@@ -735,23 +1140,128 @@ impl<
         let current_app_hash = hex::encode(&block.header.app_hash);
 
         if current_app_hash == self.sync_state.last_app_hash {
-            let current_block_height = block.header.height.value();
-            let block_result = self.env.client.block_results(current_block_height)?;
-            let states = self
-                .env
-                .client
-                .query_state_batch(iter::once(current_block_height))?;
-            Ok(Some(FilteredBlock::from_block(
-                &self.wallet,
-                &self.wallet_state,
-                &block,
-                &block_result,
-                &states[0],
-            )?))
+            self.filter_block(block).map(Some)
         } else {
             Ok(None)
         }
     }
+
+    /// Fast forwards state to the last block of given range if compact blocks show that none
+    /// of the blocks in the range are relevant to current wallet
+    ///
+    /// The compact blocks are only trusted if their app hashes match the headers verified by the
+    /// light client, full blocks are fetched otherwise.
+    fn fast_forward_compact(&self, range: &[u64]) -> Result<Option<FilteredBlock>> {
+        let light_client = match &self.env.light_client {
+            Some(light_client) if !self.env.options.disable_light_client => light_client,
+            _ => return Ok(None),
+        };
+        let compact_blocks = match self.compact_blocks(range)? {
+            Some(compact_blocks) => compact_blocks,
+            None => return Ok(None),
+        };
+        for compact_block in compact_blocks.iter() {
+            if self.compact_block_relevant(compact_block)? {
+                return Ok(None);
+            }
+        }
+        // the last block is filtered in full, the app hash after each of the other blocks is
+        // in the header of the next one
+        let last_height = range[range.len() - 1];
+        for compact_block in compact_blocks.iter() {
+            let height = compact_block.height.value();
+            if height == last_height {
+                continue;
+            }
+            let light_block = match light_client.verify_to_target(height + 1) {
+                Ok(light_block) => light_block,
+                Err(e) => {
+                    log::warn!("unable to verify header {}: {}", height + 1, e);
+                    return Ok(None);
+                }
+            };
+            if light_block.signed_header.header.app_hash[..] != compact_block.app_hash[..] {
+                log::warn!(
+                    "app hash of compact block {} doesn't match the verified header",
+                    height
+                );
+                return Ok(None);
+            }
+        }
+        let block = self.env.client.block(last_height)?;
+        self.filter_block(&block).map(Some)
+    }
+
+    /// Queries compact blocks of given heights in a batch (`None` if any of them is not served
+    /// by the node)
+    fn compact_blocks(&self, range: &[u64]) -> Result<Option<Vec<CompactBlock>>> {
+        let rsps = self
+            .env
+            .client
+            .query_batch("compact-block", &[], range.iter().copied())?;
+        if rsps.len() != range.len() {
+            return Ok(None);
+        }
+        let mut compact_blocks = Vec::with_capacity(range.len());
+        for (height, rsp) in range.iter().zip(rsps.into_iter()) {
+            let compact_block = match rsp
+                .ok()
+                .and_then(|rsp| CompactBlock::decode(&mut rsp.bytes().as_slice()).ok())
+            {
+                Some(compact_block) if compact_block.height.value() == *height => compact_block,
+                _ => return Ok(None),
+            };
+            compact_blocks.push(compact_block);
+        }
+        Ok(Some(compact_blocks))
+    }
+
+    /// Checks if a compact block may contain transactions of current wallet
+    fn compact_block_relevant(&self, compact_block: &CompactBlock) -> Result<bool> {
+        if let Some(raw_filter) = &compact_block.block_filter {
+            let block_filter = BlockFilter::try_from(raw_filter.as_slice())
+                .map_err(|err| Error::new(ErrorKind::DeserializationError, err))?;
+            if block_filter.check_view_key(&self.wallet.view_key.clone().into()) {
+                return Ok(true);
+            }
+        }
+        for tx in compact_block.transactions.iter() {
+            if let Some(address) = &tx.staking_address {
+                if self.wallet.staking_addresses_contains(address)? {
+                    return Ok(true);
+                }
+            }
+            for input in tx.inputs.iter() {
+                if self.wallet_state.unspent_transactions.get(input).is_some()
+                    || self
+                        .wallet_state
+                        .pending_transactions
+                        .values()
+                        .any(|tx_pending| tx_pending.used_inputs.contains(input))
+                {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Decode and filter given block for current wallet
+    fn filter_block(&self, block: &Block) -> Result<FilteredBlock> {
+        let current_block_height = block.header.height.value();
+        let block_result = self.env.client.block_results(current_block_height)?;
+        let states = self
+            .env
+            .client
+            .query_state_batch(iter::once(current_block_height))?;
+        FilteredBlock::from_block(
+            &self.wallet,
+            &self.wallet_state,
+            &block,
+            &block_result,
+            &states[0],
+        )
+    }
 }
 
 /// testnet v0.5
@@ -819,6 +1329,73 @@ pub fn get_genesis_sync_state<C: Client>(
     Ok(SyncState::genesis(compute_staking_root(&accounts)))
 }
 
+/// Fetches blocks of given heights with their results and chain states, retrying on errors
+/// or incomplete responses, returns `None` if all attempts failed
+fn fetch_block_data<C: Client>(client: &C, range: &[u64]) -> Option<BlockData> {
+    for _ in 0..12 {
+        match get_block_data(client, range) {
+            Ok((blocks, block_results, states))
+                if blocks.len() == block_results.len() && block_results.len() == states.len() =>
+            {
+                log::debug!(
+                    "correct data blocks  {}  block_results {}  states {}",
+                    blocks.len(),
+                    block_results.len(),
+                    states.len()
+                );
+                return Some((blocks, block_results, states));
+            }
+            Ok((blocks, block_results, states)) => log::info!(
+                "incorrect data blocks  {}  block_results {}  states {}",
+                blocks.len(),
+                block_results.len(),
+                states.len()
+            ),
+            Err(e) => log::info!("fetching block-data failed: {}", e),
+        }
+        log::info!("retry fetching block-data");
+        thread::sleep(Duration::from_secs(5));
+    }
+    None
+}
+
+fn get_block_data<C: Client>(client: &C, range: &[u64]) -> Result<BlockData> {
+    let blocks = client.block_batch(range.iter())?;
+    let block_results = client.block_results_batch(range.iter())?;
+    let states = client.query_state_batch(range.iter().cloned())?;
+    Ok((blocks, block_results, states))
+}
+
+/// Initializes sync state of a brand-new wallet at current verified height, and records it as
+/// the birth height of wallet
+fn get_birth_sync_state<S, C, D, T, L>(env: &WalletSyncer<S, C, D, T, L>) -> Result<SyncState>
+where
+    S: SecureStorage,
+    C: Client,
+    D: TxDecryptor,
+    T: AddressRecovery,
+    L: LightClientHandle,
+{
+    let (height, app_hash, block_hash) = env.sync_target()?;
+    if height == 0 {
+        let enable_genesis_fingerprint_check = !env.options.enable_fast_forward;
+        return get_genesis_sync_state(&env.client, enable_genesis_fingerprint_check);
+    }
+    let state = env
+        .client
+        .query_state_batch(iter::once(height))?
+        .pop()
+        .err_kind(ErrorKind::TendermintRpcError, || {
+            format!("chain state not found at height: {}", height)
+        })?;
+
+    let sync_state = SyncState::birth(height, app_hash, block_hash, state.account_root);
+    service::save_sync_state(&env.storage, &env.name, &sync_state)?;
+    service::save_birth_height(&env.storage, &env.name, height)?;
+    service::clear_brand_new(&env.storage, &env.name)?;
+    Ok(sync_state)
+}
+
 /// A struct for providing progress report for synchronization
 #[derive(Debug, Clone)]
 pub enum ProgressReport {
@@ -838,6 +1415,121 @@ pub enum ProgressReport {
         /// Current synchronized block height
         current_block_height: u64,
     },
+    /// Alert triggered by the alert rules of the wallet
+    Alert {
+        /// Name of wallet
+        wallet_name: String,
+        /// The triggered alert
+        alert: Alert,
+    },
+    /// Report of the transactions of a committed batch
+    StateChanged {
+        /// Name of wallet
+        wallet_name: String,
+        /// New transactions of the wallet
+        transactions: Vec<TransactionChange>,
+        /// Balance of the wallet after the transactions
+        balance: WalletBalance,
+    },
+    /// Final report when the wallet is synchronized
+    Finish {
+        /// Name of wallet
+        wallet_name: String,
+        /// Block height at which synchronization finished
+        block_height: u64,
+    },
+}
+
+/// Receives progress of wallet synchronization, e.g. to render progress bars
+///
+/// Closures receiving `ProgressReport`s are reporters too, each block and each committed batch
+/// is reported to them as an `Update`.
+pub trait SyncProgressReporter {
+    /// Called before fetching blocks with the heights synchronization starts from and finishes
+    /// at, returning false cancels synchronization
+    fn on_start(
+        &mut self,
+        wallet_name: &str,
+        start_block_height: u64,
+        finish_block_height: u64,
+    ) -> bool;
+
+    /// Called for each fetched block when it's applied to the (not yet committed) wallet state
+    fn on_block_fetched(&mut self, wallet_name: &str, block_height: u64);
+
+    /// Called when a batch of blocks up to given height is committed to storage, returning false
+    /// cancels synchronization
+    fn on_batch_committed(&mut self, wallet_name: &str, block_height: u64) -> bool;
+
+    /// Called when a committed batch has transactions of the wallet, with the balance after them
+    fn on_state_changed(
+        &mut self,
+        wallet_name: &str,
+        transactions: &[&TransactionChange],
+        balance: &WalletBalance,
+    );
+
+    /// Called when an alert rule of the wallet is triggered
+    fn on_alert(&mut self, wallet_name: &str, alert: Alert);
+
+    /// Called when the wallet is synchronized up to given height
+    fn on_finish(&mut self, wallet_name: &str, block_height: u64);
+}
+
+impl<F: FnMut(ProgressReport) -> bool> SyncProgressReporter for F {
+    fn on_start(
+        &mut self,
+        wallet_name: &str,
+        start_block_height: u64,
+        finish_block_height: u64,
+    ) -> bool {
+        self(ProgressReport::Init {
+            wallet_name: wallet_name.to_owned(),
+            start_block_height,
+            finish_block_height,
+        })
+    }
+
+    fn on_block_fetched(&mut self, wallet_name: &str, block_height: u64) {
+        self(ProgressReport::Update {
+            wallet_name: wallet_name.to_owned(),
+            current_block_height: block_height,
+        });
+    }
+
+    fn on_batch_committed(&mut self, wallet_name: &str, block_height: u64) -> bool {
+        self(ProgressReport::Update {
+            wallet_name: wallet_name.to_owned(),
+            current_block_height: block_height,
+        })
+    }
+
+    fn on_state_changed(
+        &mut self,
+        wallet_name: &str,
+        transactions: &[&TransactionChange],
+        balance: &WalletBalance,
+    ) {
+        self(ProgressReport::StateChanged {
+            wallet_name: wallet_name.to_owned(),
+            transactions: transactions.iter().map(|&change| change.clone()).collect(),
+            balance: balance.clone(),
+        });
+    }
+
+    fn on_alert(&mut self, wallet_name: &str, alert: Alert) {
+        self(ProgressReport::Alert {
+            wallet_name: wallet_name.to_owned(),
+            alert,
+        });
+    }
+
+    fn on_finish(&mut self, wallet_name: &str, block_height: u64) {
+        self(ProgressReport::Finish {
+            wallet_name: wallet_name.to_owned(),
+            block_height,
+        });
+    }
 }
 
 /// Structure for representing a block header on Crypto.com Chain,
@@ -943,6 +1635,64 @@ impl FilteredBlock {
     }
 }
 
+impl Encode for FilteredBlock {
+    fn encode_to<W: Output>(&self, dest: &mut W) {
+        self.last_app_hash.encode_to(dest);
+        self.app_hash.encode_to(dest);
+        self.block_height.encode_to(dest);
+        self.last_block_hash.encode_to(dest);
+        self.block_hash.encode_to(dest);
+        self.block_time.to_rfc3339().encode_to(dest);
+        self.valid_transaction_fees
+            .iter()
+            .collect::<Vec<_>>()
+            .encode_to(dest);
+        self.block_filter.get_raw().to_vec().encode_to(dest);
+        self.enclave_transaction_ids.encode_to(dest);
+        self.staking_transactions.encode_to(dest);
+        self.staking_root.encode_to(dest);
+    }
+}
+
+impl Decode for FilteredBlock {
+    fn decode<I: Input>(input: &mut I) -> result::Result<Self, CodecError> {
+        let last_app_hash = String::decode(input)?;
+        let app_hash = String::decode(input)?;
+        let block_height = u64::decode(input)?;
+        let last_block_hash = String::decode(input)?;
+        let block_hash = String::decode(input)?;
+        let block_time = Time::from_str(&String::decode(input)?)
+            .map_err(|_| CodecError::from("Unable to parse block time"))?;
+        let valid_transaction_fees = <Vec<(TxId, Fee)>>::decode(input)?.into_iter().collect();
+        let block_filter = BlockFilter::try_from(Vec::<u8>::decode(input)?.as_slice())
+            .map_err(CodecError::from)?;
+        let enclave_transaction_ids = <Vec<TxId>>::decode(input)?;
+        let staking_transactions = <Vec<Transaction>>::decode(input)?;
+        let staking_root = H256::decode(input)?;
+        Ok(FilteredBlock {
+            last_app_hash,
+            app_hash,
+            block_height,
+            last_block_hash,
+            block_hash,
+            block_time,
+            valid_transaction_fees,
+            block_filter,
+            enclave_transaction_ids,
+            staking_transactions,
+            staking_root,
+        })
+    }
+}
+
+/// Blocks of a batch which are fetched but not committed yet, together with the changes to the
+/// wallet state once they are processed
+#[derive(Debug, Decode)]
+struct SyncCheckpoint {
+    blocks: Vec<FilteredBlock>,
+    memento: Option<WalletStateMemento>,
+}
+
 /// find the self outgoing staking transactions in the block
 fn filter_staking_transactions(
     block_results: &BlockResultsResponse,
@@ -989,6 +1739,23 @@ fn filter_incomming_staking_transactions(
     Ok(Default::default())
 }
 
+/// Time source of light client verification backed by a `Clock`
+#[derive(Debug)]
+struct LightClientTime<K: Clock>(K);
+
+impl<K: Clock> LightClientClock for LightClientTime<K> {
+    fn now(&self) -> Time {
+        Time::from(self.0.now())
+    }
+}
+
+fn to_timespec(time: Time) -> Timespec {
+    time.duration_since(Time::unix_epoch())
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[allow(clippy::too_many_arguments)]
 fn make_light_client_instance(
     peer_id: PeerId,
     addr: tendermint::net::Address,
@@ -997,6 +1764,7 @@ fn make_light_client_instance(
     trusting_height: u64,
     trusting_blockhash: String,
     light_client_block_confirm: Option<BlockConfirmFunc>,
+    clock: impl Clock + 'static,
 ) -> Result<Instance> {
     let mut peer_map = HashMap::new();
     peer_map.insert(peer_id, addr);
@@ -1073,7 +1841,7 @@ fn make_light_client_instance(
     };
 
     let verifier = ProdVerifier::default();
-    let clock = SystemClock;
+    let clock = LightClientTime(clock);
     let scheduler = scheduler::basic_bisecting_schedule;
 
     let light_client = LightClient::new(peer_id, options, clock, scheduler, verifier, io);
@@ -1103,9 +1871,10 @@ mod tests {
     use crate::types::WalletKind;
     use crate::wallet::{DefaultWalletClient, WalletClient};
     use chain_core::init::coin::Coin;
+    use chain_core::tx::data::input::TxoPointer;
     use chain_core::tx::data::{address::ExtendedAddr, output::TxOut};
     use chain_core::tx::data::{Tx, TxId};
-    use client_common::PublicKey;
+    use client_common::{PublicKey, Storage};
     use std::str::FromStr;
 
     fn check_wallet_syncer_impl(enable_fast_forward: bool) {
@@ -1144,12 +1913,14 @@ mod tests {
                     enable_fast_forward,
                     disable_light_client: enable_fast_forward,
                     enable_address_recovery: false,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
                     batch_size: 20,
                     block_height_ensure: 50,
                     light_client_peers: "".into(),
                     light_client_trusting_period_seconds: 36000000,
                     light_client_trusting_height: 1,
                     light_client_trusting_blockhash: "".into(),
+                    sync_from_now: false,
                 },
             },
             |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
@@ -1160,7 +1931,272 @@ mod tests {
         let genesis = syncer.client.genesis().unwrap();
         let hash = compute_genesis_fingerprint(&genesis).unwrap();
         std::env::set_var("CRYPTO_GENESIS_FINGERPRINT", hash);
+        let mut reports = vec![];
+        syncer
+            .sync(|report| {
+                reports.push(report);
+                true
+            })
+            .expect("Unable to synchronize");
+        assert!(matches!(reports.first(), Some(ProgressReport::Init { .. })));
+        assert!(matches!(
+            reports.last(),
+            Some(ProgressReport::Finish { .. })
+        ));
+    }
+
+    /// First two blocks of the chain, as fetched and filtered by the syncer
+    fn fetch_first_blocks<S, C, D, R, T, L>(
+        syncimpl: &WalletSyncerImpl<'_, S, C, D, R, T, L>,
+    ) -> NonEmpty<FilteredBlock>
+    where
+        S: SecureStorage + 'static,
+        C: Client,
+        D: TxDecryptor,
+        R: SyncProgressReporter,
+        T: AddressRecovery,
+        L: LightClientHandle,
+    {
+        let client = &syncimpl.env.client;
+        let blocks = (1..=2)
+            .map(|height| {
+                FilteredBlock::from_block(
+                    &syncimpl.wallet,
+                    &syncimpl.wallet_state,
+                    &client.block(height).unwrap(),
+                    &client.block_results(height).unwrap(),
+                    &client.query_state_batch(iter::once(height)).unwrap()[0],
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        NonEmpty::new(blocks).unwrap()
+    }
+
+    #[test]
+    fn check_resume_interrupted_sync() {
+        let storage = MemoryStorage::default();
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+        let wallet = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = wallet
+            .new_wallet(
+                name,
+                &passphrase,
+                WalletKind::Basic,
+                HardwareKind::LocalOnly,
+                None,
+            )
+            .unwrap();
+
+        let client = GeneratorClient::new(BlockGenerator::one_node());
+        {
+            let mut gen = client.gen.write().unwrap();
+            for _ in 0..10 {
+                gen.gen_block(&[]);
+            }
+        }
+        let light_client = Some(client.clone());
+
+        let mut syncer = WalletSyncer::with_config(
+            SyncerConfig {
+                storage: storage.clone(),
+                client,
+                light_client,
+                options: SyncerOptions {
+                    enable_fast_forward: false,
+                    disable_light_client: false,
+                    enable_address_recovery: false,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
+                    batch_size: 4,
+                    block_height_ensure: 50,
+                    light_client_peers: "".into(),
+                    light_client_trusting_period_seconds: 36000000,
+                    light_client_trusting_height: 1,
+                    light_client_trusting_blockhash: "".into(),
+                    sync_from_now: false,
+                },
+            },
+            |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
+            name.to_owned(),
+            enckey.clone(),
+            wallet,
+        );
+        let genesis = syncer.client.genesis().unwrap();
+        let hash = compute_genesis_fingerprint(&genesis).unwrap();
+        std::env::set_var("CRYPTO_GENESIS_FINGERPRINT", hash);
+
+        let input = TxoPointer::new([1; 32], 0);
+        let output = TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::one());
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(input.clone(), output);
+
+        let checkpoint_exists = || {
+            storage
+                .get(service::SYNC_CHECKPOINT_KEYSPACE, name)
+                .unwrap()
+                .is_some()
+        };
+
+        // killed after the batch was fetched, before it was processed
+        {
+            let mut syncimpl = WalletSyncerImpl::new(&mut syncer, |_| true).unwrap();
+            let blocks = fetch_first_blocks(&syncimpl);
+            syncimpl.save_checkpoint(&blocks, None).unwrap();
+        }
+        assert!(checkpoint_exists());
+        {
+            let mut syncimpl = WalletSyncerImpl::new(&mut syncer, |_| true).unwrap();
+            syncimpl.resume_checkpoint().unwrap();
+            assert_eq!(2, syncimpl.sync_state.last_block_height);
+        }
+        assert!(!checkpoint_exists());
+
+        // killed after the batch was processed, before its changes were applied
+        service::delete_sync_state(&storage, name).unwrap();
+        {
+            let mut syncimpl = WalletSyncerImpl::new(&mut syncer, |_| true).unwrap();
+            let blocks = fetch_first_blocks(&syncimpl);
+            syncimpl.save_checkpoint(&blocks, Some(&memento)).unwrap();
+        }
+        {
+            let mut syncimpl = WalletSyncerImpl::new(&mut syncer, |_| true).unwrap();
+            syncimpl.resume_checkpoint().unwrap();
+            assert_eq!(2, syncimpl.sync_state.last_block_height);
+            assert_eq!(1, syncimpl.wallet_state.unspent_transactions.len());
+        }
+        assert!(!checkpoint_exists());
+
+        // killed after the changes were applied, before the sync state was saved
+        service::delete_sync_state(&storage, name).unwrap();
+        {
+            let mut syncimpl = WalletSyncerImpl::new(&mut syncer, |_| true).unwrap();
+            let blocks = fetch_first_blocks(&syncimpl);
+            syncimpl.save_checkpoint(&blocks, Some(&memento)).unwrap();
+        }
+        {
+            let mut syncimpl = WalletSyncerImpl::new(&mut syncer, |_| true).unwrap();
+            assert_eq!(1, syncimpl.wallet_state.unspent_transactions.len());
+            syncimpl.resume_checkpoint().unwrap();
+            assert_eq!(2, syncimpl.sync_state.last_block_height);
+            assert_eq!(1, syncimpl.wallet_state.unspent_transactions.len());
+        }
+
+        // killed after the sync state was saved, before the checkpoint was deleted
+        {
+            let mut syncimpl = WalletSyncerImpl::new(&mut syncer, |_| true).unwrap();
+            let blocks = fetch_first_blocks(&syncimpl);
+            syncimpl.save_checkpoint(&blocks, Some(&memento)).unwrap();
+        }
+        syncer.sync(|_| true).expect("Unable to synchronize");
+        assert!(!checkpoint_exists());
+        let wallet_state = service::load_wallet_state(&storage, name, &enckey)
+            .unwrap()
+            .unwrap();
+        assert!(wallet_state.unspent_transactions.contains_key(&input));
+        assert_eq!(
+            10,
+            service::load_sync_state(&storage, name)
+                .unwrap()
+                .unwrap()
+                .last_block_height
+        );
+    }
+
+    #[test]
+    fn check_sync_after_reorg() {
+        let storage = MemoryStorage::default();
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+        let wallet = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = wallet
+            .new_wallet(
+                name,
+                &passphrase,
+                WalletKind::Basic,
+                HardwareKind::LocalOnly,
+                None,
+            )
+            .unwrap();
+
+        let client = GeneratorClient::new(BlockGenerator::one_node());
+        {
+            let mut gen = client.gen.write().unwrap();
+            for _ in 0..10 {
+                gen.gen_block(&[]);
+            }
+        }
+        let mut syncer = WalletSyncer::with_config(
+            SyncerConfig {
+                storage: storage.clone(),
+                client: client.clone(),
+                light_client: None::<GeneratorClient>,
+                options: SyncerOptions {
+                    enable_fast_forward: false,
+                    disable_light_client: true,
+                    enable_address_recovery: false,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
+                    batch_size: 3,
+                    block_height_ensure: 50,
+                    light_client_peers: "".into(),
+                    light_client_trusting_period_seconds: 36000000,
+                    light_client_trusting_height: 1,
+                    light_client_trusting_blockhash: "".into(),
+                    sync_from_now: false,
+                },
+            },
+            |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
+            name.to_owned(),
+            enckey.clone(),
+            wallet,
+        );
+        let genesis = syncer.client.genesis().unwrap();
+        let hash = compute_genesis_fingerprint(&genesis).unwrap();
+        std::env::set_var("CRYPTO_GENESIS_FINGERPRINT", hash);
+
+        syncer.sync(|_| true).expect("Unable to synchronize");
+        let journal = service::load_sync_journal(&storage, name, &enckey).unwrap();
+        assert_eq!(
+            vec![0, 3, 6, 9],
+            journal
+                .iter()
+                .map(|entry| entry.previous.last_block_height)
+                .collect::<Vec<_>>()
+        );
+
+        // the node serves a different chain from block 5 on
+        {
+            let mut gen = client.gen.write().unwrap();
+            gen.blocks.truncate(4);
+            gen.current_height = Some(Height::from(4u64));
+            gen.gen_block(&[]);
+            gen.blocks[4].block.header.time = Time::unix_epoch();
+            for _ in 0..6 {
+                gen.gen_block(&[]);
+            }
+        }
+        let block_hash = |height| {
+            ProdHasher {}
+                .hash_header(&client.block(height).unwrap().header)
+                .to_string()
+        };
+        let sync_state = service::load_sync_state(&storage, name).unwrap().unwrap();
+        assert_eq!(10, sync_state.last_block_height);
+        assert_ne!(block_hash(10), sync_state.last_block_hash);
+
         syncer.sync(|_| true).expect("Unable to synchronize");
+        let sync_state = service::load_sync_state(&storage, name).unwrap().unwrap();
+        assert_eq!(11, sync_state.last_block_height);
+        assert_eq!(block_hash(11), sync_state.last_block_hash);
+        // rolled back to block 3, then synchronized the new chain
+        let journal = service::load_sync_journal(&storage, name, &enckey).unwrap();
+        assert_eq!(
+            vec![0, 3, 6, 9],
+            journal
+                .iter()
+                .map(|entry| entry.previous.last_block_height)
+                .collect::<Vec<_>>()
+        );
     }
 
     #[test]
@@ -1169,6 +2205,222 @@ mod tests {
         check_wallet_syncer_impl(true);
     }
 
+    #[test]
+    fn check_sync_from_now() {
+        let storage = MemoryStorage::default();
+
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let wallet = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet
+            .new_wallet(
+                name,
+                &passphrase,
+                WalletKind::Basic,
+                HardwareKind::LocalOnly,
+                None,
+            )
+            .unwrap();
+
+        assert!(service::is_brand_new(&storage, name).unwrap());
+
+        let client = GeneratorClient::new(BlockGenerator::one_node());
+        {
+            let mut gen = client.gen.write().unwrap();
+            for _ in 0..10 {
+                gen.gen_block(&[]);
+            }
+        }
+
+        let mut syncer = WalletSyncer::with_config(
+            SyncerConfig {
+                storage: storage.clone(),
+                client: client.clone(),
+                light_client: None::<GeneratorClient>,
+                options: SyncerOptions {
+                    enable_fast_forward: false,
+                    disable_light_client: true,
+                    enable_address_recovery: false,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
+                    batch_size: 20,
+                    block_height_ensure: 50,
+                    light_client_peers: "".into(),
+                    light_client_trusting_period_seconds: 36000000,
+                    light_client_trusting_height: 1,
+                    light_client_trusting_blockhash: "".into(),
+                    sync_from_now: true,
+                },
+            },
+            |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
+            name.to_owned(),
+            enckey,
+            wallet,
+        );
+        syncer.sync(|_| true).expect("Unable to synchronize");
+
+        let sync_state = service::load_sync_state(&storage, name).unwrap().unwrap();
+        assert_eq!(10, sync_state.last_block_height);
+        assert_eq!(
+            Some(10),
+            service::load_birth_height(&storage, name).unwrap()
+        );
+        assert!(!service::is_brand_new(&storage, name).unwrap());
+
+        // wallet is not brand-new anymore, sync continues from last state
+        {
+            let mut gen = client.gen.write().unwrap();
+            gen.gen_block(&[]);
+        }
+        syncer.sync(|_| true).expect("Unable to synchronize");
+        let sync_state = service::load_sync_state(&storage, name).unwrap().unwrap();
+        assert_eq!(11, sync_state.last_block_height);
+        assert_eq!(
+            Some(10),
+            service::load_birth_height(&storage, name).unwrap()
+        );
+
+        // rescan replays blocks from requested height
+        syncer.rescan_state(5).unwrap();
+        let sync_state = service::load_sync_state(&storage, name).unwrap().unwrap();
+        assert_eq!(4, sync_state.last_block_height);
+        assert!(!sync_state.trusted);
+        assert!(service::load_wallet_state(&storage, name, &syncer.enckey)
+            .unwrap()
+            .is_none());
+        syncer.sync(|_| true).expect("Unable to synchronize");
+        let sync_state = service::load_sync_state(&storage, name).unwrap().unwrap();
+        assert_eq!(11, sync_state.last_block_height);
+        assert!(sync_state.trusted);
+    }
+
+    #[test]
+    fn check_sync_from_now_skips_restored_wallet() {
+        let storage = MemoryStorage::default();
+
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let wallet = DefaultWalletClient::new_read_only(storage.clone());
+
+        // a restored wallet has no sync state yet, but may have history
+        let enckey = wallet
+            .restore_basic_wallet(name, &passphrase, &PrivateKey::new().unwrap())
+            .unwrap();
+        assert!(!service::is_brand_new(&storage, name).unwrap());
+
+        let client = GeneratorClient::new(BlockGenerator::one_node());
+        {
+            let mut gen = client.gen.write().unwrap();
+            for _ in 0..10 {
+                gen.gen_block(&[]);
+            }
+        }
+
+        let mut syncer = WalletSyncer::with_config(
+            SyncerConfig {
+                storage: storage.clone(),
+                client: client.clone(),
+                light_client: None::<GeneratorClient>,
+                options: SyncerOptions {
+                    enable_fast_forward: false,
+                    disable_light_client: true,
+                    enable_address_recovery: false,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
+                    batch_size: 20,
+                    block_height_ensure: 50,
+                    light_client_peers: "".into(),
+                    light_client_trusting_period_seconds: 36000000,
+                    light_client_trusting_height: 1,
+                    light_client_trusting_blockhash: "".into(),
+                    sync_from_now: true,
+                },
+            },
+            |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
+            name.to_owned(),
+            enckey,
+            wallet,
+        );
+        syncer.sync(|_| true).expect("Unable to synchronize");
+
+        // synchronized from genesis
+        let sync_state = service::load_sync_state(&storage, name).unwrap().unwrap();
+        assert_eq!(10, sync_state.last_block_height);
+        assert_eq!(None, service::load_birth_height(&storage, name).unwrap());
+    }
+
+    #[test]
+    fn check_fast_forward_compact() {
+        let storage = MemoryStorage::default();
+
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let wallet = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet
+            .new_wallet(
+                name,
+                &passphrase,
+                WalletKind::Basic,
+                HardwareKind::LocalOnly,
+                None,
+            )
+            .unwrap();
+
+        let client = GeneratorClient::new(BlockGenerator::one_node());
+        {
+            let mut gen = client.gen.write().unwrap();
+            for _ in 0..10 {
+                gen.gen_block(&[]);
+            }
+        }
+
+        let mut syncer = WalletSyncer::with_config(
+            SyncerConfig {
+                storage,
+                client: client.clone(),
+                light_client: Some(client.clone()),
+                options: SyncerOptions {
+                    enable_fast_forward: true,
+                    disable_light_client: false,
+                    enable_address_recovery: false,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
+                    batch_size: 20,
+                    block_height_ensure: 50,
+                    light_client_peers: "".into(),
+                    light_client_trusting_period_seconds: 36000000,
+                    light_client_trusting_height: 1,
+                    light_client_trusting_blockhash: "".into(),
+                    sync_from_now: false,
+                },
+            },
+            |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
+            name.to_owned(),
+            enckey,
+            wallet,
+        );
+        let syncimpl = WalletSyncerImpl::new(&mut syncer, |_| true).unwrap();
+        let block = syncimpl
+            .fast_forward_compact(&[1, 2, 3, 4, 5])
+            .unwrap()
+            .expect("no relevant blocks");
+        assert_eq!(5, block.block_height);
+
+        // compact block not matching the verified header of the next block
+        client.gen.write().unwrap().blocks[2].state.last_apphash = [0; 32];
+        assert!(syncimpl
+            .fast_forward_compact(&[1, 2, 3, 4, 5])
+            .unwrap()
+            .is_none());
+        // the last block is fetched in full
+        assert!(syncimpl
+            .fast_forward_compact(&[4, 5, 6, 7, 8])
+            .unwrap()
+            .is_some());
+    }
+
     #[test]
     #[ignore]
     fn check_wallet_syncer_app_hash_on_multiple_tx() {
@@ -1211,6 +2463,17 @@ mod tests {
             fn broadcast_transaction(&self, _transaction: &[u8]) -> Result<BroadcastTxResponse> {
                 unreachable!()
             }
+
+            fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+                &self,
+                _transactions: T,
+            ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+                unreachable!()
+            }
+
+            fn unconfirmed_txs(&self, _limit: u64) -> Result<UnconfirmedTxsResponse> {
+                unreachable!()
+            }
             fn query(
                 &self,
                 _path: &str,
@@ -1277,12 +2540,14 @@ mod tests {
                     enable_fast_forward,
                     disable_light_client: enable_fast_forward,
                     enable_address_recovery: false,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
                     batch_size: 20,
                     block_height_ensure: 50,
                     light_client_peers: "".into(),
                     light_client_trusting_period_seconds: 36000000,
                     light_client_trusting_height: 1,
                     light_client_trusting_blockhash: "".into(),
+                    sync_from_now: false,
                 },
             },
             |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
@@ -1334,12 +2599,14 @@ mod tests {
                     enable_fast_forward: false,
                     disable_light_client: false,
                     enable_address_recovery: true,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
                     batch_size: 20,
                     block_height_ensure: 50,
                     light_client_peers: "".into(),
                     light_client_trusting_period_seconds: 36000000,
                     light_client_trusting_height: 1,
                     light_client_trusting_blockhash: "".into(),
+                    sync_from_now: false,
                 },
             },
             |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
@@ -1398,12 +2665,14 @@ mod tests {
                     enable_fast_forward: false,
                     disable_light_client: false,
                     enable_address_recovery: true,
+                    address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
                     batch_size: 20,
                     block_height_ensure: 50,
                     light_client_peers: "".into(),
                     light_client_trusting_period_seconds: 36000000,
                     light_client_trusting_height: 1,
                     light_client_trusting_blockhash: "".into(),
+                    sync_from_now: false,
                 },
             },
             |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
@@ -1459,7 +2728,8 @@ mod tests {
                     .unwrap(),
                     &name,
                     &enckey,
-                    &mut dummy_wallet
+                    &mut dummy_wallet,
+                    DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
                 )
                 .unwrap()
         );
@@ -1571,6 +2841,7 @@ pub fn spawn_light_client_supervisor(
             light_client_trusting_height,
             light_client_trusting_blockhash.clone(),
             light_client_block_confirm.clone(),
+            SystemClock,
         )?;
         all_peers_map.insert(node_peerid, node_address.clone());
         if 0 == i {
@@ -17,7 +17,7 @@ use client_common::Transaction;
 use super::syncer::FilteredBlock;
 use crate::service::{Wallet, WalletState};
 use crate::types::{BalanceChange, TransactionChange, TransactionInput, TransactionType};
-use crate::wallet::syncer::ProgressReport;
+use crate::wallet::syncer::SyncProgressReporter;
 use crate::WalletStateMemento;
 #[derive(Error, Debug)]
 pub enum SyncerLogicError {
@@ -35,7 +35,7 @@ pub(crate) fn handle_blocks(
     wallet_state: &mut WalletState,
     blocks: &[FilteredBlock],
     enclave_transactions: &[Transaction],
-    callback_progress: &mut dyn FnMut(ProgressReport) -> bool,
+    progress_reporter: &mut dyn SyncProgressReporter,
 ) -> Result<WalletStateMemento, SyncerLogicError> {
     let enclave_transactions = enclave_transactions
         .iter()
@@ -44,10 +44,7 @@ pub(crate) fn handle_blocks(
     let mut memento = WalletStateMemento::default();
 
     for block in blocks {
-        callback_progress(ProgressReport::Update {
-            wallet_name: wallet.name.clone(),
-            current_block_height: block.block_height,
-        });
+        progress_reporter.on_block_fetched(&wallet.name, block.block_height);
 
         for tx in block.staking_transactions.iter() {
             if let Some(fee) = block.valid_transaction_fees.get(&tx.id()) {
@@ -107,6 +104,7 @@ pub fn create_transaction_change(
         transaction_type,
         block_height,
         block_time,
+        memo: None,
     };
     Ok(transaction_change)
 }
@@ -269,6 +267,7 @@ mod tests {
     use crate::hd_wallet::HardwareKind;
     use crate::service::load_wallet;
     use crate::types::{TransactionPending, WalletKind};
+    use crate::wallet::syncer::ProgressReport;
     use crate::wallet::{DefaultWalletClient, WalletClient};
 
     fn create_test_wallet(n: usize) -> Result<Vec<Wallet>> {
@@ -13,7 +13,7 @@ use chain_core::tx::data::output::TxOut;
 use chain_core::tx::TxAux;
 use client_common::tendermint::types::{Genesis, StatusResponse};
 use client_common::{ErrorKind, Result, ResultExt, SecKey};
-use client_core::types::TransactionPending;
+use client_core::types::{ConsensusHealth, OperatorAction, TransactionPending};
 
 /// Interface for performing network operations on Crypto.com Chain
 pub trait NetworkOpsClient: Send + Sync {
@@ -106,9 +106,31 @@ pub trait NetworkOpsClient: Send + Sync {
         verify: bool,
     ) -> Result<Option<StakedState>>;
 
+    /// Returns the latest staked state of any staking address, without verifying it against the
+    /// sync state of a wallet
+    fn query_staked_state(&self, address: &StakedStateAddress) -> Result<Option<StakedState>>;
+
     /// Return genesis of tendermint
     fn get_genesis(&self) -> Result<Genesis>;
 
     /// Return status response
     fn get_status(&self) -> Result<StatusResponse>;
+
+    /// Returns consensus health of the council node with given staking address, missed blocks
+    /// are counted in the last `window` blocks
+    fn get_consensus_health(
+        &self,
+        name: &str,
+        address: &StakedStateAddress,
+        window: u64,
+    ) -> Result<ConsensusHealth>;
+
+    /// Checks if given operator action can be performed on the staking address at latest
+    /// block, returns a descriptive error if it can't
+    fn check_operator_action(
+        &self,
+        name: &str,
+        address: &StakedStateAddress,
+        action: OperatorAction,
+    ) -> Result<()>;
 }
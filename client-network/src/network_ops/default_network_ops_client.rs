@@ -1,13 +1,19 @@
+use std::iter;
+
 use parity_scale_codec::Decode;
+use serde::Deserialize;
 
 use crate::NetworkOpsClient;
 use chain_core::common::Timespec;
 use chain_core::init::coin::{sum_coins, Coin};
+use chain_core::init::params::NetworkParameters;
 use chain_core::state::account::{
-    CouncilNodeMeta, DepositBondTx, NodeMetadata, StakedState, StakedStateAddress,
+    CouncilNodeMeta, DepositBondTx, NodeMetadata, NodeState, StakedState, StakedStateAddress,
     StakedStateOpAttributes, StakedStateOpWitness, UnbondTx, UnjailTx, WithdrawUnbondedTx,
 };
+use chain_core::state::tendermint::TendermintValidatorPubKey;
 use chain_core::state::validator::NodeJoinRequestTx;
+use chain_core::tx::canonical::sort_inputs;
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
@@ -24,8 +30,9 @@ use client_common::{
 };
 use client_core::signer::{DummySigner, Signer, WalletSignerManager};
 use client_core::transaction_builder::WitnessedUTxO;
-use client_core::types::TransactionPending;
+use client_core::types::{ConsensusHealth, OperatorAction, TransactionPending};
 use client_core::{UnspentTransactions, WalletClient};
+use tendermint::block::CommitSig;
 use tendermint::{block::Height, Time};
 
 /// Default implementation of `NetworkOpsClient`
@@ -104,6 +111,135 @@ where
             },
         ))
     }
+
+    fn get_network_params(&self) -> Result<NetworkParameters> {
+        let height = self.client.status()?.sync_info.latest_block_height.value();
+        let state = self
+            .client
+            .query_state_batch(iter::once(height))?
+            .into_iter()
+            .next()
+            .chain(|| {
+                (
+                    ErrorKind::TendermintRpcError,
+                    "Unable to query chain state of latest block",
+                )
+            })?;
+        Ok(state.network_params)
+    }
+
+    /// Active council nodes, in the order of voting power
+    fn get_council_nodes(&self) -> Result<Vec<CouncilNodeAddress>> {
+        let bytes = self
+            .client
+            .query("council-nodes", &[], None, false)?
+            .bytes();
+        serde_json::from_slice(&bytes).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to deserialize council nodes",
+            )
+        })
+    }
+
+    /// Returns rank (starting from 1) of the staking address in the active validator set and
+    /// the size of the set
+    fn get_voting_power_rank(
+        &self,
+        address: &StakedStateAddress,
+    ) -> Result<(Option<usize>, usize)> {
+        let council_nodes = self.get_council_nodes()?;
+        let rank = council_nodes
+            .iter()
+            .position(|node| &node.staking_address == address)
+            .map(|index| index + 1);
+        Ok((rank, council_nodes.len()))
+    }
+
+    /// Checks that the staking account can (re-)join the validator set with given node metadata:
+    /// the bonded amount covers the required council node stake and the consensus public key is
+    /// neither blank nor used by another active council node
+    fn check_node_join(
+        &self,
+        staked_state: &StakedState,
+        node_metadata: &CouncilNodeMeta,
+    ) -> Result<()> {
+        let consensus_pubkey = &node_metadata.consensus_pubkey;
+        let TendermintValidatorPubKey::Ed25519(key) = consensus_pubkey;
+        if key.iter().all(|byte| *byte == 0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Consensus public key must not be blank",
+            ));
+        }
+
+        // joining with a different consensus key than the current one rotates the key
+        let action = match &staked_state.node_meta {
+            Some(NodeState::CouncilNode(validator))
+                if &validator.council_node.consensus_pubkey != consensus_pubkey =>
+            {
+                OperatorAction::RotateKey
+            }
+            _ => OperatorAction::NodeJoin,
+        };
+        let required_stake = self.get_network_params()?.get_required_council_node_stake();
+        let block_time = self.get_last_block_time()?;
+        action.check(staked_state, required_stake, block_time)?;
+
+        if self.get_council_nodes()?.iter().any(|node| {
+            &node.tendermint_pubkey == consensus_pubkey
+                && node.staking_address != staked_state.address
+        }) {
+            return Err(Error::new(
+                ErrorKind::IllegalInput,
+                format!(
+                    "Consensus public key {} is used by another council node",
+                    consensus_pubkey
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Counts blocks in the last `window` blocks whose commit wasn't signed by the validator
+    fn count_missed_blocks(&self, staked_state: &StakedState, window: u64) -> Result<u64> {
+        let validator_address = match &staked_state.node_meta {
+            Some(NodeState::CouncilNode(validator)) if validator.is_active() => {
+                <[u8; 20]>::from(&validator.validator_address())
+            }
+            _ => return Ok(0),
+        };
+
+        let latest_height = self.client.status()?.sync_info.latest_block_height.value();
+        let from_height = latest_height.saturating_sub(window).max(1) + 1;
+        if from_height > latest_height {
+            return Ok(0);
+        }
+        let heights = (from_height..=latest_height).collect::<Vec<u64>>();
+        let blocks = self.client.block_batch(heights.iter())?;
+
+        let missed = blocks
+            .iter()
+            .filter_map(|block| block.last_commit.as_ref())
+            .filter(|commit| {
+                !commit.signatures.iter().any(|signature| match signature {
+                    CommitSig::BlockIDFlagCommit {
+                        validator_address: signer,
+                        ..
+                    } => signer.as_bytes() == &validator_address[..],
+                    _ => false,
+                })
+            })
+            .count();
+        Ok(missed as u64)
+    }
+}
+
+/// Entry of `council-nodes` abci query
+#[derive(Deserialize)]
+struct CouncilNodeAddress {
+    staking_address: StakedStateAddress,
+    tendermint_pubkey: TendermintValidatorPubKey,
 }
 
 impl<W, S, C, F, E> NetworkOpsClient for DefaultNetworkOpsClient<W, S, C, F, E>
@@ -136,7 +272,7 @@ where
         &'a self,
         name: &'a str,
         enckey: &'a SecKey,
-        transactions: Vec<(TxoPointer, TxOut)>,
+        mut transactions: Vec<(TxoPointer, TxOut)>,
         to_address: StakedStateAddress,
         attributes: StakedStateOpAttributes,
         verify_staking: bool,
@@ -149,6 +285,7 @@ where
                 )
             })?;
         }
+        sort_inputs(&mut transactions, |(input, _)| input);
         let inputs = transactions
             .iter()
             .map(|(input, _)| input.clone())
@@ -156,9 +293,7 @@ where
 
         let transaction = DepositBondTx::new(inputs.clone(), to_address, attributes);
         let unspent_transactions = UnspentTransactions::new(transactions);
-        let signer =
-            self.signer_manager
-                .create_signer(name, enckey, &self.signer_manager.hw_key_service);
+        let signer = self.signer_manager.create_signer(name, enckey);
 
         let tx = Transaction::DepositStakeTransaction(transaction.clone());
         let witness = signer.schnorr_sign_transaction(&tx, &unspent_transactions.select_all())?;
@@ -433,6 +568,7 @@ where
                 format!("Failed to validate staking account: {}", e),
             )
         })?;
+        self.check_node_join(&staked_state, &node_metadata)?;
 
         let transaction = NodeJoinRequestTx {
             nonce: staked_state.nonce,
@@ -509,18 +645,22 @@ where
 
             mstaking
         } else {
-            let bytes = self
-                .client
-                .query("staking", address.as_ref(), None, false)?
-                .bytes();
-            <Option<StakedState>>::decode(&mut bytes.as_slice())
-                .err_kind(ErrorKind::DeserializationError, || {
-                    format!("Cannot deserialize staked state for address: {}", address)
-                })?
+            self.query_staked_state(address)?
         };
         Ok(mstaking)
     }
 
+    fn query_staked_state(&self, address: &StakedStateAddress) -> Result<Option<StakedState>> {
+        let bytes = self
+            .client
+            .query("staking", address.as_ref(), None, false)?
+            .bytes();
+        <Option<StakedState>>::decode(&mut bytes.as_slice())
+            .err_kind(ErrorKind::DeserializationError, || {
+                format!("Cannot deserialize staked state for address: {}", address)
+            })
+    }
+
     fn get_genesis(&self) -> Result<Genesis> {
         self.client.genesis()
     }
@@ -528,6 +668,49 @@ where
     fn get_status(&self) -> Result<StatusResponse> {
         self.client.status()
     }
+
+    fn get_consensus_health(
+        &self,
+        name: &str,
+        address: &StakedStateAddress,
+        window: u64,
+    ) -> Result<ConsensusHealth> {
+        let staked_state = self.get_staked_state(name, address, true)?;
+        let required_stake = self.get_network_params()?.get_required_council_node_stake();
+        let (voting_power_rank, validator_count) = self.get_voting_power_rank(address)?;
+        let missed_blocks = self.count_missed_blocks(&staked_state, window)?;
+
+        let validator = match &staked_state.node_meta {
+            Some(NodeState::CouncilNode(validator)) => Some(validator),
+            _ => None,
+        };
+        Ok(ConsensusHealth {
+            staking_address: *address,
+            is_council_node: validator.is_some(),
+            is_active: validator.map(|v| v.is_active()).unwrap_or_default(),
+            jailed_until: validator.and_then(|v| v.jailed_until),
+            bonded: staked_state.bonded,
+            unbonded: staked_state.unbonded,
+            required_stake,
+            voting_power_rank,
+            validator_count,
+            missed_blocks,
+            window,
+            last_slash: staked_state.last_slash,
+        })
+    }
+
+    fn check_operator_action(
+        &self,
+        name: &str,
+        address: &StakedStateAddress,
+        action: OperatorAction,
+    ) -> Result<()> {
+        let staked_state = self.get_staked_state(name, address, true)?;
+        let required_stake = self.get_network_params()?.get_required_council_node_stake();
+        let block_time = self.get_last_block_time()?;
+        action.check(&staked_state, required_stake, block_time)
+    }
 }
 
 fn to_timespec(time: Time) -> Timespec {
@@ -546,7 +729,7 @@ mod tests {
     use chain_core::state::account::{StakedState, StakedStateOpAttributes, Validator};
     use chain_core::state::tendermint::BlockHeight;
     use chain_core::state::tendermint::TendermintValidatorPubKey;
-    use chain_core::state::ChainState;
+    use chain_core::state::{ChainState, RewardsPoolState};
     use chain_core::tx::data::input::TxoSize;
     use chain_core::tx::data::TxId;
     use chain_core::tx::fee::Fee;
@@ -562,7 +745,7 @@ mod tests {
     use client_core::signer::WalletSignerManager;
     use client_core::types::WalletKind;
     use client_core::wallet::DefaultWalletClient;
-    use test_common::chain_env::mock_confidential_init;
+    use test_common::chain_env::{get_init_network_params, mock_confidential_init};
 
     #[derive(Debug, Clone)]
     struct MockTransactionCipher;
@@ -656,6 +839,17 @@ mod tests {
             unreachable!()
         }
 
+        fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+            &self,
+            _transactions: T,
+        ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+            unreachable!()
+        }
+
+        fn unconfirmed_txs(&self, _limit: u64) -> Result<UnconfirmedTxsResponse> {
+            unreachable!()
+        }
+
         fn query(
             &self,
             _path: &str,
@@ -706,7 +900,7 @@ mod tests {
         fn status(&self) -> Result<StatusResponse> {
             Ok(StatusResponse {
                 sync_info: status::SyncInfo {
-                    latest_block_height: Height::default(),
+                    latest_block_height: Height(1),
                     latest_app_hash: None,
                     ..mock::sync_info()
                 },
@@ -737,13 +931,34 @@ mod tests {
             unreachable!()
         }
 
+        fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+            &self,
+            _transactions: T,
+        ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+            unreachable!()
+        }
+
+        fn unconfirmed_txs(&self, _limit: u64) -> Result<UnconfirmedTxsResponse> {
+            unreachable!()
+        }
+
         fn query(
             &self,
-            _path: &str,
+            path: &str,
             _data: &[u8],
             _height: Option<Height>,
             _prove: bool,
         ) -> Result<AbciQuery> {
+            if path == "council-nodes" {
+                let council_nodes = serde_json::json!([{
+                    "staking_address": StakedStateAddress::BasicRedeem(RedeemAddress([1; 20])),
+                    "tendermint_pubkey": TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+                }]);
+                return Ok(AbciQuery {
+                    value: council_nodes.to_string().into_bytes(),
+                    ..Default::default()
+                });
+            }
             let staked_state = StakedState::new(
                 0,
                 Coin::new(1000000).unwrap(),
@@ -761,9 +976,17 @@ mod tests {
 
         fn query_state_batch<T: Iterator<Item = u64>>(
             &self,
-            _heights: T,
+            heights: T,
         ) -> Result<Vec<ChainState>> {
-            unreachable!()
+            Ok(heights
+                .map(|_| ChainState {
+                    account_root: [0; 32],
+                    rewards_pool: RewardsPoolState::new(0, 0),
+                    network_params: NetworkParameters::Genesis(get_init_network_params(
+                        Coin::zero(),
+                    )),
+                })
+                .collect())
         }
     }
 
@@ -1227,5 +1450,24 @@ mod tests {
             }
             _ => unreachable!("`create_node_join_tx()` created invalid transaction"),
         }
+
+        for consensus_pubkey in &[[0; 32], [0xcd; 32]] {
+            let node_metadata = CouncilNodeMeta::new_with_details(
+                "test".to_owned(),
+                None,
+                TendermintValidatorPubKey::Ed25519(*consensus_pubkey),
+                mock_confidential_init(),
+            );
+            assert!(network_ops_client
+                .create_node_join_transaction(
+                    name,
+                    &enckey,
+                    staking_account_address,
+                    StakedStateOpAttributes::new(171),
+                    node_metadata,
+                    false,
+                )
+                .is_err());
+        }
     }
 }
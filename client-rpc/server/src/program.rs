@@ -1,6 +1,8 @@
 use structopt::StructOpt;
 
 use crate::server::Server;
+use chain_core::init::coin::Coin;
+use client_rpc_core::access::parse_rate_limit;
 use std::env;
 
 #[derive(StructOpt, Debug)]
@@ -31,8 +33,20 @@ pub struct Options {
     )]
     pub port: u16,
 
-    #[structopt(name = "chain-id", short, long, help = "Full chain ID")]
-    pub chain_id: String,
+    #[structopt(
+        name = "ws-port",
+        long,
+        help = "JSON-RPC websocket server port (with subscriptions), not started if not set"
+    )]
+    pub ws_port: Option<u16>,
+
+    #[structopt(
+        name = "chain-id",
+        short,
+        long,
+        help = "Full chain ID (the genesis chain ID of the tendermint node if not set)"
+    )]
+    pub chain_id: Option<String>,
 
     #[structopt(
         name = "storage-dir",
@@ -63,6 +77,49 @@ pub struct Options {
         help = "Disable light client, which is not secure when connecting to outside nodes"
     )]
     pub disable_light_client: bool,
+    #[structopt(
+        name = "sync-from-now",
+        long,
+        help = "Start syncing brand-new wallets from current block height instead of genesis"
+    )]
+    pub sync_from_now: bool,
+    #[structopt(
+        name = "serve-remote-state",
+        long,
+        help = "Serve wallet UTxOs and proven staking states to thin clients (e.g. mobile apps)"
+    )]
+    pub serve_remote_state: bool,
+
+    #[structopt(
+        name = "error-messages",
+        long,
+        help = "JSON file with localized templates of user-facing error messages (by message id)"
+    )]
+    pub error_messages: Option<String>,
+
+    #[structopt(
+        name = "api-token-file",
+        long,
+        help = "File with the API token required in the `Authorization: Bearer <token>` header"
+    )]
+    pub api_token_file: Option<String>,
+
+    #[structopt(
+        name = "rate-limit",
+        long,
+        number_of_values = 1,
+        parse(try_from_str = parse_rate_limit),
+        help = "Calls per minute of a method, e.g. `wallet_create=10` (`*` for the other methods)"
+    )]
+    pub rate_limits: Vec<(String, u32)>,
+
+    #[structopt(
+        name = "dust-threshold",
+        long,
+        default_value = "0",
+        help = "Outputs below this amount (in base units) are rejected and change below it is avoided"
+    )]
+    pub dust_threshold: Coin,
 
     #[structopt(
         name = "light client peer",
@@ -102,6 +159,13 @@ pub struct Options {
         help = "Disable address recovery when syncing wallet, which is not necessary, when addresses already exist"
     )]
     pub disable_address_recovery: bool,
+    #[structopt(
+        name = "address-recovery-gap-limit",
+        long,
+        default_value = "20",
+        help = "Number of not yet derived addresses checked when recovering addresses"
+    )]
+    pub address_recovery_gap_limit: u32,
     #[structopt(
         name = "batch-size",
         short,
@@ -144,7 +208,7 @@ pub fn run_electron() {
     log::info!("args={:?}", args);
     let mut options = Options::from_iter(vec![""].iter());
     if let Some(a) = find_string(&args, "--chain-id") {
-        options.chain_id = args[a + 1].clone()
+        options.chain_id = Some(args[a + 1].clone())
     }
     if let Some(a) = find_string(&args, "--storage-dir") {
         options.storage_dir = args[a + 1].clone()
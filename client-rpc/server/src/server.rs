@@ -1,26 +1,48 @@
 use crate::program::Options;
 
+use jsonrpc_http_server::hyper::header::AUTHORIZATION;
+use jsonrpc_http_server::hyper::{Body, Request};
 use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
+use jsonrpc_pubsub::Session;
+use jsonrpc_ws_server::{ws, RequestContext, Server as WsServer, ServerBuilder as WsServerBuilder};
+use secstr::SecUtf8;
+use std::fs;
 use std::net::SocketAddr;
+use std::sync::Arc;
 
+use chain_core::init::coin::Coin;
 use chain_core::init::network::{get_network, get_network_id, init_chain_id};
-use client_common::Result;
+use client_common::tendermint::{Client, WebsocketRpcClient};
 use client_common::{Error, ErrorKind};
+use client_common::{Result, ResultExt};
 use client_core::wallet::syncer::SyncerOptions;
+use client_rpc_core::access::{bearer_token, AccessControl};
+use client_rpc_core::display::{RequestMeta, DISPLAY_OPTIONS_HEADER};
+use client_rpc_core::error_catalog::{set_error_catalog, ErrorCatalog};
+use client_rpc_core::handler::RpcIo;
 use client_rpc_core::RpcHandler;
 pub(crate) struct Server {
     host: String,
     port: u16,
+    ws_port: Option<u16>,
     network_id: u8,
     storage_dir: String,
     websocket_url: String,
+    serve_remote_state: bool,
+    dust_threshold: Coin,
+    access: Option<Arc<AccessControl>>,
 
     sync_options: SyncerOptions,
 }
 
 impl Server {
     pub(crate) fn new(options: Options) -> Result<Server> {
-        init_chain_id(&options.chain_id);
+        let chain_id = match options.chain_id {
+            Some(chain_id) => chain_id,
+            None => node_chain_id(&options.websocket_url)?,
+        };
+        check_chain_id(&chain_id)?;
+        init_chain_id(&chain_id);
         let network_id = get_network_id();
 
         println!("Network type {:?} id {:02X}", get_network(), network_id);
@@ -37,22 +59,56 @@ impl Server {
             }
         }
 
+        if let Some(path) = options.error_messages {
+            set_error_catalog(ErrorCatalog::load(path)?)?;
+        }
+
+        let api_token = match options.api_token_file {
+            Some(path) => {
+                let token = fs::read_to_string(&path)
+                    .chain(|| (ErrorKind::IoError, format!("Unable to read {}", path)))?;
+                let token = token.trim();
+                if token.is_empty() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Empty API token in {}", path),
+                    ));
+                }
+                Some(SecUtf8::from(token))
+            }
+            None => None,
+        };
+        let access = if api_token.is_some() || !options.rate_limits.is_empty() {
+            Some(Arc::new(AccessControl::new(
+                api_token,
+                options.rate_limits.into_iter().collect(),
+            )))
+        } else {
+            None
+        };
+
         Ok(Server {
             host: options.host,
             port: options.port,
+            ws_port: options.ws_port,
             network_id,
             storage_dir: options.storage_dir,
             websocket_url: options.websocket_url,
+            serve_remote_state: options.serve_remote_state,
+            dust_threshold: options.dust_threshold,
+            access,
             sync_options: SyncerOptions {
                 enable_fast_forward: options.enable_fast_forward,
                 disable_light_client: options.disable_light_client,
                 enable_address_recovery: !options.disable_address_recovery,
+                address_recovery_gap_limit: options.address_recovery_gap_limit,
                 batch_size: options.batch_size,
                 block_height_ensure: options.block_height_ensure,
                 light_client_peers,
                 light_client_trusting_period_seconds: options.light_client_trusting_period_seconds,
                 light_client_trusting_height: options.light_client_trusting_height,
                 light_client_trusting_blockhash: options.light_client_trusting_blockhash,
+                sync_from_now: options.sync_from_now,
             },
         })
     }
@@ -67,12 +123,28 @@ impl Server {
             self.network_id,
             self.sync_options.clone(),
             None,
+            self.serve_remote_state,
+            self.dust_threshold,
         )
     }
 
     pub(crate) fn start(&mut self) -> Result<()> {
         let handler = self.create_rpc_handler()?;
-        let server = ServerBuilder::new(handler.io)
+        let _ws_server = self
+            .ws_port
+            .map(|port| self.start_ws(handler.io.clone(), port));
+        let access = self.access.clone();
+        let server =
+            ServerBuilder::with_meta_extractor(handler.io, move |request: &Request<Body>| {
+                let header = |name: &str| {
+                    request
+                        .headers()
+                        .get(name)
+                        .and_then(|header| header.to_str().ok())
+                };
+                RequestMeta::from_display_header(header(DISPLAY_OPTIONS_HEADER))
+                    .with_access(access.clone(), header(AUTHORIZATION.as_str()))
+            })
             // TODO: Either make CORS configurable or make it more strict
             .cors(DomainsValidation::AllowOnly(vec![
                 AccessControlAllowOrigin::Any,
@@ -85,4 +157,69 @@ impl Server {
 
         Ok(())
     }
+
+    /// Starts the websocket server, the API token of a connection is checked by its handshake
+    fn start_ws(&self, io: RpcIo, port: u16) -> WsServer {
+        let access = self.access.clone();
+        let handshake_access = self.access.clone();
+        WsServerBuilder::with_meta_extractor(io, move |context: &RequestContext| {
+            let mut meta =
+                RequestMeta::default().with_session(Arc::new(Session::new(context.sender())));
+            meta.credentials = access
+                .as_ref()
+                .and_then(|access| access.connection_credentials());
+            meta.access = access.clone();
+            meta
+        })
+        .request_middleware(move |request: &ws::Request| {
+            let access = handshake_access.as_ref()?;
+            let credentials = request
+                .header(AUTHORIZATION.as_str())
+                .and_then(|header| std::str::from_utf8(header).ok())
+                .and_then(bearer_token);
+            if access.is_authorized(credentials.as_ref()) {
+                None
+            } else {
+                Some(ws::Response::new(401, "Unauthorized", vec![]))
+            }
+        })
+        .start(&SocketAddr::new(self.host.parse().unwrap(), port))
+        .expect("Unable to start JSON-RPC websocket server")
+    }
+}
+
+/// Returns the genesis chain id of the tendermint node
+fn node_chain_id(websocket_url: &str) -> Result<String> {
+    let genesis = WebsocketRpcClient::new(websocket_url)
+        .and_then(|client| client.genesis())
+        .chain(|| {
+            (
+                ErrorKind::InitializationError,
+                format!(
+                    "Unable to get chain id from tendermint node ({}), set it with --chain-id",
+                    websocket_url
+                ),
+            )
+        })?;
+    Ok(genesis.chain_id.to_string())
+}
+
+/// The network id is the last two hex digits of the chain id
+fn check_chain_id(chain_id: &str) -> Result<()> {
+    let valid = chain_id.len() >= 6
+        && chain_id.is_char_boundary(chain_id.len() - 2)
+        && chain_id[chain_id.len() - 2..]
+            .chars()
+            .all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid chain id ({}), it should end with the two hex digits of the network id",
+                chain_id
+            ),
+        ))
+    }
 }
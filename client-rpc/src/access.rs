@@ -0,0 +1,263 @@
+//! Authentication and rate limiting of RPC calls
+//!
+//! The server attaches its `AccessControl` and the API token presented with a request (the
+//! `Authorization: Bearer <token>` HTTP header) to the request metadata. `AccessMiddleware`
+//! rejects calls without the right token and calls of methods exceeding their rate limit with a
+//! JSON-RPC error; requests without access control (e.g. handled in-process) aren't checked.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use jsonrpc_core::futures::future::{self, Either};
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::middleware::{Middleware, NoopCallFuture, NoopFuture};
+use jsonrpc_core::{Call, ErrorCode, Output};
+use secstr::SecUtf8;
+
+use crate::display::RequestMeta;
+use client_common::{Clock, Error, ErrorKind, Result, SystemClock};
+
+/// JSON-RPC error code of calls without the right API token
+pub const UNAUTHORIZED_ERROR_CODE: i64 = -32001;
+/// JSON-RPC error code of calls exceeding the rate limit of the method
+pub const RATE_LIMITED_ERROR_CODE: i64 = -32005;
+
+/// Method name of the rate limit applied to methods without their own limit
+pub const DEFAULT_RATE_LIMIT_METHOD: &str = "*";
+
+/// Scheme of the API token in the `Authorization` header
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Rate limits are numbers of calls in this window
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+struct RateWindow {
+    started_at: SystemTime,
+    calls: u32,
+}
+
+/// Access control of a RPC server
+#[derive(Debug, Default)]
+pub struct AccessControl<K = SystemClock>
+where
+    K: Clock,
+{
+    api_token: Option<SecUtf8>,
+    /// method => calls per minute
+    rate_limits: HashMap<String, u32>,
+    windows: Mutex<HashMap<String, RateWindow>>,
+    clock: K,
+}
+
+impl AccessControl {
+    /// Requires the API token (if set) and limits the calls per minute of the methods
+    pub fn new(api_token: Option<SecUtf8>, rate_limits: HashMap<String, u32>) -> Self {
+        Self::with_clock(api_token, rate_limits, SystemClock)
+    }
+}
+
+impl<K> AccessControl<K>
+where
+    K: Clock,
+{
+    /// Same as `new`, the rate limit windows are measured by given time source
+    pub fn with_clock(
+        api_token: Option<SecUtf8>,
+        rate_limits: HashMap<String, u32>,
+        clock: K,
+    ) -> Self {
+        AccessControl {
+            api_token,
+            rate_limits,
+            windows: Mutex::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    /// Checks the API token presented with a request or a connection
+    pub fn is_authorized(&self, credentials: Option<&SecUtf8>) -> bool {
+        match self.api_token.as_ref() {
+            // `SecUtf8` is compared in constant time
+            Some(api_token) => credentials == Some(api_token),
+            None => true,
+        }
+    }
+
+    /// Credentials of the requests of a connection authorized when it was established (e.g. by
+    /// the websocket handshake)
+    pub fn connection_credentials(&self) -> Option<SecUtf8> {
+        self.api_token.clone()
+    }
+
+    /// Checks the call of the method and counts it against the rate limit
+    pub fn check(
+        &self,
+        method: &str,
+        credentials: Option<&SecUtf8>,
+    ) -> std::result::Result<(), jsonrpc_core::Error> {
+        if !self.is_authorized(credentials) {
+            return Err(jsonrpc_core::Error {
+                code: ErrorCode::ServerError(UNAUTHORIZED_ERROR_CODE),
+                message: "Unauthorized".to_owned(),
+                data: None,
+            });
+        }
+
+        let (key, limit) = match self.rate_limits.get(method) {
+            Some(limit) => (method, *limit),
+            None => match self.rate_limits.get(DEFAULT_RATE_LIMIT_METHOD) {
+                Some(limit) => (DEFAULT_RATE_LIMIT_METHOD, *limit),
+                None => return Ok(()),
+            },
+        };
+        let now = self.clock.now();
+        let mut windows = self.windows.lock().expect("lock rate limit windows");
+        let window = windows.entry(key.to_owned()).or_insert(RateWindow {
+            started_at: now,
+            calls: 0,
+        });
+        // a clock going backwards doesn't reset the window
+        if now.duration_since(window.started_at).unwrap_or_default() >= RATE_LIMIT_WINDOW {
+            window.started_at = now;
+            window.calls = 0;
+        }
+        if window.calls >= limit {
+            return Err(jsonrpc_core::Error {
+                code: ErrorCode::ServerError(RATE_LIMITED_ERROR_CODE),
+                message: format!("Rate limit of {} exceeded", key),
+                data: None,
+            });
+        }
+        window.calls += 1;
+        Ok(())
+    }
+}
+
+/// Returns the API token of an `Authorization: Bearer <token>` header value
+pub fn bearer_token(authorization: &str) -> Option<SecUtf8> {
+    authorization
+        .strip_prefix(BEARER_PREFIX)
+        .map(|token| SecUtf8::from(token.trim()))
+}
+
+/// Parses a rate limit `<method>=<calls per minute>` (`*` as method sets the default limit)
+pub fn parse_rate_limit(s: &str) -> Result<(String, u32)> {
+    let mut parts = s.splitn(2, '=');
+    match (parts.next(), parts.next().map(str::parse)) {
+        (Some(method), Some(Ok(calls))) if !method.is_empty() => Ok((method.to_owned(), calls)),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid rate limit ({}), expected <method>=<calls per minute>",
+                s
+            ),
+        )),
+    }
+}
+
+/// Rejects unauthorized and rate limited calls
+#[derive(Debug, Clone, Default)]
+pub struct AccessMiddleware;
+
+impl Middleware<RequestMeta> for AccessMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(&self, call: Call, meta: RequestMeta, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, RequestMeta) -> X + Send + Sync,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+    {
+        let access = match meta.access.as_ref() {
+            Some(access) => access,
+            None => return Either::B(next(call, meta)),
+        };
+        let method = match &call {
+            Call::MethodCall(method_call) => &method_call.method,
+            Call::Notification(notification) => &notification.method,
+            Call::Invalid { .. } => return Either::B(next(call, meta)),
+        };
+        match access.check(method, meta.credentials.as_ref()) {
+            Ok(()) => Either::B(next(call, meta)),
+            Err(error) => {
+                let output = match call {
+                    Call::MethodCall(method_call) => Some(Output::from(
+                        Err(error),
+                        method_call.id,
+                        method_call.jsonrpc,
+                    )),
+                    _ => None,
+                };
+                Either::A(Box::new(future::ok(output)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::clock::MockClock;
+
+    #[test]
+    fn check_api_token() {
+        let access = AccessControl::new(Some(SecUtf8::from("secret")), HashMap::new());
+        assert!(access
+            .check("wallet_list", Some(&SecUtf8::from("secret")))
+            .is_ok());
+        let error = access
+            .check("wallet_list", Some(&SecUtf8::from("wrong")))
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::ServerError(UNAUTHORIZED_ERROR_CODE));
+        assert!(access.check("wallet_list", None).is_err());
+
+        let access = AccessControl::new(None, HashMap::new());
+        assert!(access.check("wallet_list", None).is_ok());
+    }
+
+    #[test]
+    fn check_bearer_token() {
+        assert_eq!(bearer_token("Bearer secret"), Some(SecUtf8::from("secret")));
+        assert_eq!(bearer_token("Basic c2VjcmV0"), None);
+    }
+
+    #[test]
+    fn check_rate_limits() {
+        let rate_limits = vec![
+            ("wallet_create".to_owned(), 1),
+            (DEFAULT_RATE_LIMIT_METHOD.to_owned(), 2),
+        ];
+        let clock = MockClock::new(1_000);
+        let access =
+            AccessControl::with_clock(None, rate_limits.into_iter().collect(), clock.clone());
+        assert!(access.check("wallet_create", None).is_ok());
+        let error = access.check("wallet_create", None).unwrap_err();
+        assert_eq!(error.code, ErrorCode::ServerError(RATE_LIMITED_ERROR_CODE));
+
+        // the other methods share the default limit
+        assert!(access.check("wallet_list", None).is_ok());
+        assert!(access.check("wallet_balance", None).is_ok());
+        assert!(access.check("wallet_list", None).is_err());
+
+        // the calls are counted again in the next window
+        clock.advance(59);
+        assert!(access.check("wallet_create", None).is_err());
+        clock.advance(1);
+        assert!(access.check("wallet_create", None).is_ok());
+        assert!(access.check("wallet_create", None).is_err());
+        assert!(access.check("wallet_list", None).is_ok());
+    }
+
+    #[test]
+    fn check_parse_rate_limit() {
+        assert_eq!(
+            parse_rate_limit("wallet_create=10").unwrap(),
+            ("wallet_create".to_owned(), 10)
+        );
+        assert_eq!(parse_rate_limit("*=100").unwrap(), ("*".to_owned(), 100));
+        assert!(parse_rate_limit("wallet_create").is_err());
+        assert!(parse_rate_limit("=10").is_err());
+        assert!(parse_rate_limit("wallet_create=ten").is_err());
+    }
+}
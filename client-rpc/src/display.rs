@@ -0,0 +1,399 @@
+//! Display-ready amounts and timestamps in RPC responses
+//!
+//! Amounts are returned in base units and timestamps in UTC, so every wallet UI used to convert
+//! them on its own. When a request has display options (the `X-Display-Options` HTTP header with
+//! a JSON object, e.g. `{"timezone": "+08:00", "decimals": 2, "group_separator": ","}`), a
+//! `<field>_display` string is added next to each amount and timestamp field of the result, the
+//! raw fields are kept unchanged.
+use chrono::{DateTime, FixedOffset, TimeZone};
+use jsonrpc_core::futures::future::Either;
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::middleware::{Middleware, NoopCallFuture, NoopFuture};
+use jsonrpc_core::{Call, Metadata, Output};
+use jsonrpc_pubsub::{PubSubMetadata, Session};
+use secstr::SecUtf8;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+use crate::access::{bearer_token, AccessControl};
+
+use chain_core::init::MAX_COIN_DECIMALS;
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+/// Name of the HTTP header with the display options of a request
+pub const DISPLAY_OPTIONS_HEADER: &str = "X-Display-Options";
+
+/// Fields holding amounts (in base units)
+const AMOUNT_FIELDS: &[&str] = &[
+    "amount",
+    "available",
+    "bonded",
+    "fee",
+    "max_amount",
+    "min_amount",
+    "pending",
+    "required_stake",
+    "reserved",
+    "return_amount",
+    "total",
+    "unbonded",
+    "value",
+];
+
+/// Fields holding timestamps (RFC 3339 strings or seconds since the unix epoch)
+const TIMESTAMP_FIELDS: &[&str] = &[
+    "block_time",
+    "created_at",
+    "expires_at",
+    "jailed_until",
+    "unbonded_from",
+];
+
+/// Methods returning a bare amount, their result is replaced by
+/// `{"amount": String, "amount_display": String}`
+const AMOUNT_RESULT_METHODS: &[&str] = &["transaction_estimateFee", "wallet_importTransaction"];
+
+/// Unit amounts are displayed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Denomination {
+    /// CRO (10^8 base units)
+    Cro,
+    /// base units
+    Base,
+}
+
+impl Default for Denomination {
+    fn default() -> Self {
+        Denomination::Cro
+    }
+}
+
+/// Formatting options of a request
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayOptions {
+    /// UTC offset of displayed timestamps, e.g. `+08:00` (UTC if not set)
+    pub timezone: Option<String>,
+    /// `strftime` format of displayed timestamps
+    pub time_format: String,
+    /// unit of displayed amounts
+    pub denomination: Denomination,
+    /// fixed number of decimal places of CRO amounts (truncated), trailing zeros are trimmed if
+    /// not set
+    pub decimals: Option<u8>,
+    /// separator of the integer and the decimal part
+    pub decimal_separator: String,
+    /// separator of thousands groups of the integer part (not grouped if not set)
+    pub group_separator: Option<String>,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        DisplayOptions {
+            timezone: None,
+            time_format: "%Y-%m-%d %H:%M:%S %:z".to_owned(),
+            denomination: Denomination::Cro,
+            decimals: None,
+            decimal_separator: ".".to_owned(),
+            group_separator: None,
+        }
+    }
+}
+
+impl DisplayOptions {
+    /// Parses display options from JSON (e.g. the value of the `X-Display-Options` header)
+    pub fn from_json(json: &str) -> Result<DisplayOptions> {
+        let options: DisplayOptions = serde_json::from_str(json)
+            .chain(|| (ErrorKind::InvalidInput, "Invalid display options"))?;
+        options.timezone_offset()?;
+        if options.decimals.unwrap_or(0) as usize > decimal_places() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Display decimals should be at most {}", decimal_places()),
+            ));
+        }
+        Ok(options)
+    }
+
+    fn timezone_offset(&self) -> Result<FixedOffset> {
+        let timezone = match self.timezone.as_deref() {
+            None | Some("Z") | Some("UTC") => return Ok(FixedOffset::east(0)),
+            Some(timezone) => timezone,
+        };
+        // parse the offset as the offset of an RFC 3339 timestamp
+        DateTime::parse_from_rfc3339(&format!("2000-01-01T00:00:00{}", timezone))
+            .map(|time| *time.offset())
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!("Invalid display timezone ({}), e.g. +08:00", timezone),
+                )
+            })
+    }
+
+    /// Formats an amount in base units
+    pub fn format_amount(&self, amount: u64) -> String {
+        let (integer, fraction) = match self.denomination {
+            Denomination::Base => (amount, String::new()),
+            Denomination::Cro => {
+                let fraction = format!(
+                    "{:0width$}",
+                    amount % MAX_COIN_DECIMALS,
+                    width = decimal_places()
+                );
+                let fraction = match self.decimals {
+                    Some(decimals) => fraction[..decimals as usize].to_owned(),
+                    None => fraction.trim_end_matches('0').to_owned(),
+                };
+                (amount / MAX_COIN_DECIMALS, fraction)
+            }
+        };
+
+        let digits = integer.to_string();
+        let mut formatted = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                if let Some(separator) = &self.group_separator {
+                    formatted.push_str(separator);
+                }
+            }
+            formatted.push(digit);
+        }
+        if !fraction.is_empty() {
+            formatted.push_str(&self.decimal_separator);
+            formatted.push_str(&fraction);
+        }
+        formatted
+    }
+
+    /// Formats a timestamp in the timezone of the options
+    pub fn format_time<Tz: TimeZone>(&self, time: DateTime<Tz>) -> String {
+        let offset = self
+            .timezone_offset()
+            .unwrap_or_else(|_| FixedOffset::east(0));
+        time.with_timezone(&offset)
+            .format(&self.time_format)
+            .to_string()
+    }
+
+    fn display_amount(&self, value: &Value) -> Option<String> {
+        value
+            .as_str()
+            .and_then(|amount| amount.parse::<u64>().ok())
+            .map(|amount| self.format_amount(amount))
+    }
+
+    fn display_time(&self, value: &Value) -> Option<String> {
+        match value {
+            Value::String(time) => DateTime::parse_from_rfc3339(time)
+                .ok()
+                .map(|time| self.format_time(time)),
+            Value::Number(seconds) => seconds
+                .as_i64()
+                .map(|seconds| self.format_time(FixedOffset::east(0).timestamp(seconds, 0))),
+            _ => None,
+        }
+    }
+
+    /// Adds display strings of the amount and timestamp fields in a value
+    pub fn shape(&self, value: &mut Value) {
+        match value {
+            Value::Array(values) => values.iter_mut().for_each(|value| self.shape(value)),
+            Value::Object(fields) => self.shape_fields(fields),
+            _ => {}
+        }
+    }
+
+    fn shape_fields(&self, fields: &mut Map<String, Value>) {
+        let mut displayed = Vec::new();
+        for (name, value) in fields.iter_mut() {
+            let display = if AMOUNT_FIELDS.contains(&name.as_str()) {
+                self.display_amount(value)
+            } else if TIMESTAMP_FIELDS.contains(&name.as_str()) {
+                self.display_time(value)
+            } else {
+                None
+            };
+            match display {
+                Some(display) => displayed.push((format!("{}_display", name), display)),
+                None => self.shape(value),
+            }
+        }
+        for (name, display) in displayed {
+            fields.insert(name, Value::String(display));
+        }
+    }
+
+    /// Shapes the result of a method
+    pub fn shape_result(&self, method: &str, result: &mut Value) {
+        if AMOUNT_RESULT_METHODS.contains(&method) {
+            let mut fields = Map::new();
+            fields.insert("amount".to_owned(), result.take());
+            *result = Value::Object(fields);
+        }
+        self.shape(result);
+    }
+}
+
+/// Number of decimal places of CRO
+fn decimal_places() -> usize {
+    MAX_COIN_DECIMALS.to_string().len() - 1
+}
+
+/// Metadata of a RPC request
+#[derive(Debug, Clone, Default)]
+pub struct RequestMeta {
+    /// display options of the request, results are returned as is if not set
+    pub display: Option<DisplayOptions>,
+    /// access control of the server, the request isn't checked if not set
+    pub access: Option<Arc<AccessControl>>,
+    /// API token of the request (the `Authorization: Bearer <token>` header)
+    pub credentials: Option<SecUtf8>,
+    /// websocket connection of the request, subscriptions aren't supported if not set
+    pub session: Option<Arc<Session>>,
+}
+
+impl Metadata for RequestMeta {}
+
+impl PubSubMetadata for RequestMeta {
+    fn session(&self) -> Option<Arc<Session>> {
+        self.session.clone()
+    }
+}
+
+impl RequestMeta {
+    /// Metadata of a request with the value of the `X-Display-Options` header, invalid options
+    /// are ignored
+    pub fn from_display_header(header: Option<&str>) -> RequestMeta {
+        let display = header.and_then(|header| match DisplayOptions::from_json(header) {
+            Ok(options) => Some(options),
+            Err(e) => {
+                log::warn!("{}: {}", DISPLAY_OPTIONS_HEADER, e);
+                None
+            }
+        });
+        RequestMeta {
+            display,
+            ..Default::default()
+        }
+    }
+
+    /// Checks the request with the access control and the value of the `Authorization` header
+    pub fn with_access(
+        mut self,
+        access: Option<Arc<AccessControl>>,
+        authorization: Option<&str>,
+    ) -> RequestMeta {
+        self.access = access;
+        self.credentials = authorization.and_then(bearer_token);
+        self
+    }
+
+    /// Request of a websocket connection, subscriptions push their notifications to it
+    pub fn with_session(mut self, session: Arc<Session>) -> RequestMeta {
+        self.session = Some(session);
+        self
+    }
+}
+
+/// Middleware shaping the results of requests with display options
+#[derive(Debug, Clone, Default)]
+pub struct DisplayMiddleware;
+
+impl Middleware<RequestMeta> for DisplayMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(&self, call: Call, meta: RequestMeta, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, RequestMeta) -> X + Send + Sync,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+    {
+        let (options, method) = match (&meta.display, &call) {
+            (Some(options), Call::MethodCall(method_call)) => {
+                (options.clone(), method_call.method.clone())
+            }
+            _ => return Either::B(next(call, meta)),
+        };
+        Either::A(Box::new(next(call, meta).map(move |output| {
+            output.map(|output| match output {
+                Output::Success(mut success) => {
+                    options.shape_result(&method, &mut success.result);
+                    Output::Success(success)
+                }
+                failure => failure,
+            })
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn check_format_amount() {
+        let options = DisplayOptions::default();
+        assert_eq!("1234.5", options.format_amount(1234_5000_0000));
+        assert_eq!("0.00000001", options.format_amount(1));
+        assert_eq!("0", options.format_amount(0));
+
+        let options = DisplayOptions::from_json(
+            r#"{"decimals": 2, "decimal_separator": ",", "group_separator": "."}"#,
+        )
+        .unwrap();
+        assert_eq!("1.234.567,89", options.format_amount(1_234_567_8999_9999));
+        assert_eq!("123,00", options.format_amount(123_0000_0000));
+
+        let options = DisplayOptions::from_json(r#"{"denomination": "base"}"#).unwrap();
+        assert_eq!("123456789", options.format_amount(123_456_789));
+
+        assert!(DisplayOptions::from_json(r#"{"decimals": 9}"#).is_err());
+        assert!(DisplayOptions::from_json(r#"{"timezone": "Asia/Hong_Kong"}"#).is_err());
+    }
+
+    #[test]
+    fn check_shape() {
+        let options =
+            DisplayOptions::from_json(r#"{"timezone": "+08:00", "group_separator": ","}"#).unwrap();
+        let mut result = json!({
+            "total": 2,
+            "transactions": [{
+                "fee": "1000",
+                "kind": "Incoming",
+                "value": "150000000000",
+                "block_height": 10,
+                "block_time": "2020-08-01T20:00:00Z",
+                "outputs": [{"address": "dcro1", "value": "10"}],
+            }],
+        });
+        options.shape_result("wallet_transactionHistory", &mut result);
+        assert_eq!(
+            json!({
+                "total": 2,
+                "transactions": [{
+                    "fee": "1000",
+                    "fee_display": "0.00001",
+                    "kind": "Incoming",
+                    "value": "150000000000",
+                    "value_display": "1,500",
+                    "block_height": 10,
+                    "block_time": "2020-08-01T20:00:00Z",
+                    "block_time_display": "2020-08-02 04:00:00 +08:00",
+                    "outputs": [{"address": "dcro1", "value": "10", "value_display": "0.0000001"}],
+                }],
+            }),
+            result
+        );
+
+        let mut result = json!("100000000");
+        options.shape_result("transaction_estimateFee", &mut result);
+        assert_eq!(
+            json!({"amount": "100000000", "amount_display": "1"}),
+            result
+        );
+    }
+}
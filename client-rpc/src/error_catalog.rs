@@ -0,0 +1,299 @@
+//! User-facing error messages
+//!
+//! Errors are mapped to stable message ids with parameters, so wallet UIs can show consistent
+//! messages (in their own language) without parsing the raw error strings. The message
+//! templates can be localized with a JSON file mapping message ids to templates, e.g.
+//! `{"wallet_not_found": "Le portefeuille {name} est introuvable"}`.
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+/// Localized templates used by `to_rpc_error` (default English ones if not set)
+static ERROR_CATALOG: OnceCell<ErrorCatalog> = OnceCell::new();
+
+/// User-facing message of an error
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ErrorMessage {
+    /// Stable id of the message
+    pub id: &'static str,
+    /// Parameters interpolated into the message
+    pub params: BTreeMap<String, String>,
+    /// Message in the language of the catalog
+    pub message: String,
+}
+
+struct MessageRule {
+    kind: ErrorKind,
+    /// raw error message, `{param}` captures a parameter
+    pattern: &'static str,
+    id: &'static str,
+    template: &'static str,
+}
+
+const fn rule(
+    kind: ErrorKind,
+    pattern: &'static str,
+    id: &'static str,
+    template: &'static str,
+) -> MessageRule {
+    MessageRule {
+        kind,
+        pattern,
+        id,
+        template,
+    }
+}
+
+/// Known errors, the first rule matching the kind and the message of an error is used
+const MESSAGE_RULES: &[MessageRule] = &[
+    rule(
+        ErrorKind::InvalidInput,
+        "Wallet with name ({name}) not found",
+        "wallet_not_found",
+        "Wallet {name} does not exist",
+    ),
+    rule(
+        ErrorKind::InvalidInput,
+        "Wallet with name ({name}) already exists",
+        "wallet_already_exists",
+        "Wallet {name} already exists",
+    ),
+    rule(
+        ErrorKind::DecryptionError,
+        "Incorrect enckey: Unable to unlock stored values",
+        "incorrect_passphrase",
+        "The passphrase is incorrect",
+    ),
+    rule(
+        ErrorKind::PermissionDenied,
+        "Too many failed passphrase attempts for wallet ({name}), retry after {seconds} seconds",
+        "passphrase_locked_out",
+        "Wallet {name} is locked after too many incorrect passphrases, retry in {seconds} seconds",
+    ),
+    rule(
+        ErrorKind::PermissionDenied,
+        "Wallet client is read-only, {method} is not allowed",
+        "read_only_wallet",
+        "The wallet is read-only",
+    ),
+    rule(
+        ErrorKind::InvalidInput,
+        "Insufficient balance",
+        "insufficient_balance",
+        "The balance is not enough for the transaction",
+    ),
+    rule(
+        ErrorKind::VerifyError,
+        "Insufficient balance",
+        "insufficient_balance",
+        "The balance is not enough for the transaction",
+    ),
+    rule(
+        ErrorKind::VerifyError,
+        "Insufficient fee, need more {amount}",
+        "insufficient_fee",
+        "The fee is not enough, {amount} more is needed",
+    ),
+    rule(
+        ErrorKind::InvalidInput,
+        "Payout batch with name ({name}) already exists",
+        "payout_batch_already_exists",
+        "Payout batch {name} already exists",
+    ),
+];
+
+/// Message id and template of errors without a matching rule
+fn kind_message(kind: ErrorKind) -> (&'static str, &'static str) {
+    match kind {
+        ErrorKind::InitializationError => ("initialization_error", "Initialization failed"),
+        ErrorKind::ConnectionError => ("connection_error", "Unable to connect to the node"),
+        ErrorKind::StorageError => ("storage_error", "Unable to access the wallet storage"),
+        ErrorKind::RngError => ("rng_error", "Unable to generate random numbers"),
+        ErrorKind::EncryptionError => ("encryption_error", "Unable to encrypt data"),
+        ErrorKind::DecryptionError => ("decryption_error", "Unable to decrypt data"),
+        ErrorKind::SerializationError => ("serialization_error", "Unable to serialize data"),
+        ErrorKind::DeserializationError => ("deserialization_error", "Unable to read data"),
+        ErrorKind::InvalidInput => ("invalid_input", "The input is invalid"),
+        ErrorKind::IllegalInput => ("illegal_input", "The input is not allowed"),
+        ErrorKind::PermissionDenied => ("permission_denied", "The operation is not allowed"),
+        ErrorKind::IoError => ("io_error", "Unable to read or write files"),
+        ErrorKind::TendermintRpcError => ("node_rpc_error", "The node returned an error"),
+        ErrorKind::MultiSigError => ("multisig_error", "Multi-signature operation failed"),
+        ErrorKind::InternalError => ("internal_error", "Internal error"),
+        ErrorKind::ValidationError => ("validation_error", "The transaction is invalid"),
+        ErrorKind::VerifyError => ("verify_error", "Verification failed"),
+        ErrorKind::RunEnclaveError => ("enclave_error", "Enclave operation failed"),
+        ErrorKind::LedgerError => ("ledger_error", "Hardware wallet operation failed"),
+    }
+}
+
+/// Matches a raw error message against a pattern, returns the captured parameters
+fn match_pattern(mut pattern: &str, mut message: &str) -> Option<BTreeMap<String, String>> {
+    let mut params = BTreeMap::new();
+    while let Some(start) = pattern.find('{') {
+        let end = start + pattern[start..].find('}')?;
+        let literal = &pattern[..start];
+        if !message.starts_with(literal) {
+            return None;
+        }
+        message = &message[literal.len()..];
+
+        let name = &pattern[start + 1..end];
+        pattern = &pattern[end + 1..];
+        let next_literal = &pattern[..pattern.find('{').unwrap_or_else(|| pattern.len())];
+        let value_len = if next_literal.is_empty() {
+            message.len()
+        } else {
+            message.find(next_literal)?
+        };
+        params.insert(name.to_owned(), message[..value_len].to_owned());
+        message = &message[value_len..];
+    }
+
+    if pattern == message {
+        Some(params)
+    } else {
+        None
+    }
+}
+
+fn interpolate(template: &str, params: &BTreeMap<String, String>) -> String {
+    params
+        .iter()
+        .fold(template.to_owned(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// Localized message templates
+#[derive(Debug, Default)]
+pub struct ErrorCatalog {
+    templates: HashMap<String, String>,
+}
+
+impl ErrorCatalog {
+    /// Creates a catalog with given templates (message id -> template), default English
+    /// templates are used for missing ids
+    pub fn new(templates: HashMap<String, String>) -> Self {
+        ErrorCatalog { templates }
+    }
+
+    /// Loads a catalog from a JSON localization file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).chain(|| {
+            (
+                ErrorKind::IoError,
+                format!("Unable to read error messages from {}", path.display()),
+            )
+        })?;
+        let templates = serde_json::from_str(&content).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                format!("Invalid error messages file {}", path.display()),
+            )
+        })?;
+        Ok(ErrorCatalog::new(templates))
+    }
+
+    /// Returns the user-facing message of an error
+    pub fn message(&self, error: &Error) -> ErrorMessage {
+        let (id, template, params) = MESSAGE_RULES
+            .iter()
+            .filter(|rule| rule.kind == error.kind())
+            .find_map(|rule| {
+                match_pattern(rule.pattern, error.message())
+                    .map(|params| (rule.id, rule.template, params))
+            })
+            .unwrap_or_else(|| {
+                let (id, template) = kind_message(error.kind());
+                (id, template, BTreeMap::new())
+            });
+        let template = self.templates.get(id).map_or(template, String::as_str);
+
+        ErrorMessage {
+            id,
+            message: interpolate(template, &params),
+            params,
+        }
+    }
+}
+
+/// Sets the catalog used for RPC errors, it can only be set once
+pub fn set_error_catalog(catalog: ErrorCatalog) -> Result<()> {
+    ERROR_CATALOG.set(catalog).map_err(|_| {
+        Error::new(
+            ErrorKind::InitializationError,
+            "Error message catalog is already set",
+        )
+    })
+}
+
+/// Returns the user-facing message of an error with the catalog used for RPC errors
+pub fn error_message(error: &Error) -> ErrorMessage {
+    ERROR_CATALOG
+        .get_or_init(ErrorCatalog::default)
+        .message(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_error_message() {
+        let catalog = ErrorCatalog::default();
+
+        let error = Error::new(
+            ErrorKind::PermissionDenied,
+            "Too many failed passphrase attempts for wallet (Default), retry after 30 seconds",
+        );
+        let message = catalog.message(&error);
+        assert_eq!("passphrase_locked_out", message.id);
+        assert_eq!("Default", message.params["name"]);
+        assert_eq!("30", message.params["seconds"]);
+        assert_eq!(
+            "Wallet Default is locked after too many incorrect passphrases, retry in 30 seconds",
+            message.message
+        );
+
+        // same message with another kind falls back to the message of the kind
+        let error = Error::new(ErrorKind::StorageError, "Insufficient balance");
+        let message = catalog.message(&error);
+        assert_eq!("storage_error", message.id);
+        assert!(message.params.is_empty());
+
+        let error = Error::new(ErrorKind::InvalidInput, "Wallet with name (a) not found!");
+        assert_eq!("invalid_input", catalog.message(&error).id);
+    }
+
+    #[test]
+    fn check_localized_error_message() {
+        let mut templates = HashMap::new();
+        templates.insert(
+            "wallet_not_found".to_owned(),
+            "Le portefeuille {name} est introuvable".to_owned(),
+        );
+        let catalog = ErrorCatalog::new(templates);
+
+        let error = Error::new(
+            ErrorKind::InvalidInput,
+            "Wallet with name (Default) not found",
+        );
+        let message = catalog.message(&error);
+        assert_eq!("wallet_not_found", message.id);
+        assert_eq!("Le portefeuille Default est introuvable", message.message);
+
+        // missing templates fall back to English
+        let error = Error::new(ErrorKind::InvalidInput, "Insufficient balance");
+        assert_eq!(
+            "The balance is not enough for the transaction",
+            catalog.message(&error).message
+        );
+    }
+}
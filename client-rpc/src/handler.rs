@@ -1,42 +1,61 @@
-use jsonrpc_core::IoHandler;
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpc_core::MetaIoHandler;
 
 #[cfg(feature = "experimental")]
 use crate::rpc::multisig_rpc::{MultiSigRpc, MultiSigRpcImpl};
+use chain_core::init::coin::Coin;
 use chain_core::tx::fee::FeeAlgorithm;
 use client_common::cipher::TransactionObfuscation;
-use client_common::storage::SledStorage;
+use client_common::storage::{InstrumentedStorage, SledStorage, SlowOperationLogger};
 use client_common::tendermint::{types::GenesisExt, Client, WebsocketRpcClient};
 use client_common::Result;
-use client_common::Storage;
+use client_common::{Storage, SystemClock};
 use client_core::service::HwKeyService;
 use client_core::signer::WalletSignerManager;
 use client_core::transaction_builder::DefaultWalletTransactionBuilder;
+use client_core::wallet::middleware::{Layered, PassphraseLockout};
 use client_core::wallet::syncer::{
     spawn_light_client_supervisor, ObfuscationSyncerConfig, SyncerOptions,
 };
 use client_core::wallet::DefaultWalletClient;
 use client_network::network_ops::DefaultNetworkOpsClient;
 
+use crate::access::AccessMiddleware;
+use crate::display::{DisplayMiddleware, RequestMeta};
 use crate::rpc::{
+    chain_rpc::{ChainRpc, ChainRpcImpl},
     info_rpc::{InfoRpc, InfoRpcImpl},
+    remote_state_rpc::{RemoteStateRpc, RemoteStateRpcImpl},
+    session_rpc::{SessionRpc, SessionRpcImpl},
     staking_rpc::{StakingRpc, StakingRpcImpl},
+    storage_rpc::{StorageRpc, StorageRpcImpl},
+    subscription_rpc::{SubscriptionRpc, SubscriptionRpcImpl, WalletNotifier},
     sync_rpc::{CBindingCore, SyncRpc, SyncRpcImpl},
     transaction_rpc::{TransactionRpc, TransactionRpcImpl},
     wallet_rpc::{WalletRpc, WalletRpcImpl},
 };
+use crate::session::{SessionMiddleware, Sessions};
+
+/// storage operations slower than this are logged
+const SLOW_STORAGE_OPERATION: Duration = Duration::from_millis(500);
+/// interval of dropping the expired wallet sessions
+const SESSION_PURGE_INTERVAL: Duration = Duration::from_secs(1);
 
+type AppStorage = InstrumentedStorage<SledStorage>;
 type AppWalletClient<O, F> = DefaultWalletClient<
-    SledStorage,
+    AppStorage,
     WebsocketRpcClient,
-    DefaultWalletTransactionBuilder<SledStorage, F, O>,
+    DefaultWalletTransactionBuilder<AppStorage, F, O>,
 >;
 type AppOpsClient<O, F> =
-    DefaultNetworkOpsClient<AppWalletClient<O, F>, SledStorage, WebsocketRpcClient, F, O>;
-type AppSyncerConfig<O, L> = ObfuscationSyncerConfig<SledStorage, WebsocketRpcClient, O, L>;
+    DefaultNetworkOpsClient<AppWalletClient<O, F>, AppStorage, WebsocketRpcClient, F, O>;
+type AppSyncerConfig<O, L> = ObfuscationSyncerConfig<AppStorage, WebsocketRpcClient, O, L>;
 
 #[derive(Clone)]
 pub struct RpcHandler {
-    pub io: IoHandler,
+    pub io: MetaIoHandler<RequestMeta, (AccessMiddleware, (SessionMiddleware, DisplayMiddleware))>,
 }
 
 impl RpcHandler {
@@ -46,9 +65,21 @@ impl RpcHandler {
         network_id: u8,
         sync_options: SyncerOptions,
         progress_callback: Option<CBindingCore>,
+        serve_remote_state: bool,
+        dust_threshold: Coin,
     ) -> Result<Self> {
-        let mut io = IoHandler::new();
-        let storage = SledStorage::new(&storage_dir)?;
+        let sessions = Sessions::with_clock(SystemClock);
+        let mut io = MetaIoHandler::with_middleware((
+            AccessMiddleware,
+            (SessionMiddleware::new(sessions.clone()), DisplayMiddleware),
+        ));
+        let expiring_sessions = sessions.clone();
+        std::thread::spawn(move || loop {
+            expiring_sessions.purge_expired();
+            std::thread::sleep(SESSION_PURGE_INTERVAL);
+        });
+        let storage = InstrumentedStorage::new(SledStorage::new(&storage_dir)?)
+            .with_observer(Arc::new(SlowOperationLogger::new(SLOW_STORAGE_OPERATION)));
 
         let polling_storage = storage.clone();
         std::thread::spawn(move || {
@@ -68,12 +99,14 @@ impl RpcHandler {
             tendermint_client.clone(),
             fee_policy.clone(),
             obfuscation.clone(),
+            dust_threshold,
         )?;
         let ops_client = make_ops_client(
             storage.clone(),
             tendermint_client.clone(),
             fee_policy.clone(),
             tendermint_client.clone(),
+            dust_threshold,
         )?;
         let handle = if sync_options.disable_light_client {
             None
@@ -97,18 +130,46 @@ impl RpcHandler {
         );
 
         #[cfg(feature = "experimental")]
-        let multisig_rpc = MultiSigRpcImpl::new(wallet_client.clone());
-        let transaction_rpc = TransactionRpcImpl::new(network_id);
+        let multisig_rpc = MultiSigRpcImpl::new(wallet_client.clone(), network_id);
+        let transaction_rpc = TransactionRpcImpl::new(wallet_client.clone(), network_id);
         let staking_rpc =
             StakingRpcImpl::new(wallet_client.clone(), ops_client.clone(), network_id);
         let info_rpc = InfoRpcImpl::new(ops_client);
+        let chain_rpc = ChainRpcImpl::new(wallet_client.clone());
+        let storage_rpc = StorageRpcImpl::new(storage.clone());
+
+        if serve_remote_state {
+            let remote_state_rpc = RemoteStateRpcImpl::new(
+                wallet_client.clone(),
+                tendermint_client.clone(),
+                handle.clone(),
+            );
+            io.extend_with(remote_state_rpc.to_delegate());
+        }
+
+        let locked_out_wallet_client =
+            Layered::new(wallet_client, PassphraseLockout::new(storage.clone()));
+        let session_rpc = SessionRpcImpl::new(locked_out_wallet_client.clone(), sessions);
+        let notifier = WalletNotifier::default();
+        let subscription_rpc =
+            SubscriptionRpcImpl::new(locked_out_wallet_client.clone(), notifier.clone());
+        let wallet_rpc = WalletRpcImpl::new(locked_out_wallet_client, network_id);
 
-        let sync_wallet_client =
-            make_wallet_client(storage, tendermint_client, fee_policy, obfuscation)?;
+        let sync_wallet_client = make_wallet_client(
+            storage,
+            tendermint_client,
+            fee_policy,
+            obfuscation,
+            dust_threshold,
+        )?;
 
-        let sync_rpc =
-            SyncRpcImpl::new(syncer_config, progress_callback, sync_wallet_client, handle);
-        let wallet_rpc = WalletRpcImpl::new(wallet_client, network_id);
+        let sync_rpc = SyncRpcImpl::new(
+            syncer_config,
+            progress_callback,
+            notifier,
+            sync_wallet_client,
+            handle,
+        );
 
         #[cfg(feature = "experimental")]
         io.extend_with(multisig_rpc.to_delegate());
@@ -116,7 +177,11 @@ impl RpcHandler {
         io.extend_with(staking_rpc.to_delegate());
         io.extend_with(sync_rpc.to_delegate());
         io.extend_with(wallet_rpc.to_delegate());
+        io.extend_with(session_rpc.to_delegate());
+        io.extend_with(subscription_rpc.to_delegate());
         io.extend_with(info_rpc.to_delegate());
+        io.extend_with(chain_rpc.to_delegate());
+        io.extend_with(storage_rpc.to_delegate());
 
         Ok(RpcHandler { io })
     }
@@ -127,6 +192,8 @@ impl RpcHandler {
         network_id: u8,
         sync_options: SyncerOptions,
         progress_callback: Option<CBindingCore>,
+        serve_remote_state: bool,
+        dust_threshold: Coin,
     ) -> Result<Self> {
         Self::new_impl(
             storage_dir,
@@ -134,36 +201,46 @@ impl RpcHandler {
             network_id,
             sync_options,
             progress_callback,
+            serve_remote_state,
+            dust_threshold,
         )
     }
 
     pub fn handle(&self, req: &str) -> Option<String> {
-        self.io.handle_request_sync(req)
+        self.io.handle_request_sync(req, RequestMeta::default())
+    }
+
+    /// Handles a request, the results are shaped by its display options (see `display`)
+    pub fn handle_with_meta(&self, req: &str, meta: RequestMeta) -> Option<String> {
+        self.io.handle_request_sync(req, meta)
     }
 }
 
 fn make_wallet_client<O: TransactionObfuscation, F: FeeAlgorithm>(
-    storage: SledStorage,
+    storage: AppStorage,
     tendermint_client: WebsocketRpcClient,
     fee_policy: F,
     obfuscator: O,
+    dust_threshold: Coin,
 ) -> Result<AppWalletClient<O, F>> {
     let hw_key_service = HwKeyService::default();
     let signer_manager = WalletSignerManager::new(storage.clone(), hw_key_service.clone());
     Ok(DefaultWalletClient::new(
         storage,
         tendermint_client,
-        DefaultWalletTransactionBuilder::new(signer_manager, fee_policy, obfuscator),
+        DefaultWalletTransactionBuilder::new(signer_manager, fee_policy, obfuscator)
+            .with_dust_threshold(dust_threshold),
         Some(50),
         hw_key_service,
     ))
 }
 
 fn make_ops_client<O: TransactionObfuscation, F: FeeAlgorithm>(
-    storage: SledStorage,
+    storage: AppStorage,
     tendermint_client: WebsocketRpcClient,
     fee_policy: F,
     obfuscator: O,
+    dust_threshold: Coin,
 ) -> Result<AppOpsClient<O, F>> {
     let hw_key_service = HwKeyService::default();
     let signer_manager = WalletSignerManager::new(storage.clone(), hw_key_service);
@@ -172,6 +249,7 @@ fn make_ops_client<O: TransactionObfuscation, F: FeeAlgorithm>(
         tendermint_client.clone(),
         fee_policy.clone(),
         obfuscator.clone(),
+        dust_threshold,
     )?;
     Ok(DefaultNetworkOpsClient::new(
         wallet_client,
@@ -1,16 +1,29 @@
+use std::any::Any;
 use std::fmt::Debug;
 
+pub mod access;
+pub mod display;
+pub mod error_catalog;
 pub mod handler;
 pub mod rpc;
+pub mod session;
 
 pub use handler::RpcHandler;
 
-pub fn to_rpc_error<E: ToString + Debug>(error: E) -> jsonrpc_core::Error {
+/// Converts an error to RPC error, the user-facing message of client errors (see
+/// `error_catalog`) is returned in `data` alongside the raw error message
+pub fn to_rpc_error<E: ToString + Debug + 'static>(error: E) -> jsonrpc_core::Error {
     log::error!("{:?}", error);
+    let data = (&error as &dyn Any)
+        .downcast_ref::<client_common::Error>()
+        .map(|error| {
+            serde_json::to_value(error_catalog::error_message(error))
+                .expect("serialize error message")
+        });
     jsonrpc_core::Error {
         code: jsonrpc_core::ErrorCode::InternalError,
         message: error.to_string(),
-        data: None,
+        data,
     }
 }
 
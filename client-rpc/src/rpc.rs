@@ -1,7 +1,12 @@
+pub mod chain_rpc;
 pub mod info_rpc;
 #[cfg(feature = "experimental")]
 pub mod multisig_rpc;
+pub mod remote_state_rpc;
+pub mod session_rpc;
 pub mod staking_rpc;
+pub mod storage_rpc;
+pub mod subscription_rpc;
 pub mod sync_rpc;
 pub mod sync_worker;
 pub mod transaction_rpc;
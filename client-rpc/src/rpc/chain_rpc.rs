@@ -0,0 +1,55 @@
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+
+use crate::to_rpc_error;
+use client_core::types::MempoolTransaction;
+use client_core::wallet::WalletRequest;
+use client_core::WalletClient;
+
+/// Number of mempool transactions inspected if not specified
+const DEFAULT_MEMPOOL_LIMIT: u64 = 100;
+
+#[rpc(server)]
+pub trait ChainRpc: Send + Sync {
+    #[rpc(name = "chain_pendingTransactions")]
+    fn pending_transactions(
+        &self,
+        request: WalletRequest,
+        limit: Option<u64>,
+    ) -> Result<Vec<MempoolTransaction>>;
+}
+
+pub struct ChainRpcImpl<T>
+where
+    T: WalletClient,
+{
+    client: T,
+}
+
+impl<T> ChainRpcImpl<T>
+where
+    T: WalletClient,
+{
+    pub fn new(client: T) -> Self {
+        ChainRpcImpl { client }
+    }
+}
+
+impl<T> ChainRpc for ChainRpcImpl<T>
+where
+    T: WalletClient + 'static,
+{
+    fn pending_transactions(
+        &self,
+        request: WalletRequest,
+        limit: Option<u64>,
+    ) -> Result<Vec<MempoolTransaction>> {
+        self.client
+            .mempool_transactions(
+                &request.name,
+                &request.enckey,
+                limit.unwrap_or(DEFAULT_MEMPOOL_LIMIT),
+            )
+            .map_err(to_rpc_error)
+    }
+}
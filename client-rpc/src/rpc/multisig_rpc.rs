@@ -5,6 +5,7 @@ use jsonrpc_derive::rpc;
 use chain_core::common::{H256, HASH_SIZE_256};
 use chain_core::tx::data::Tx;
 use client_common::{Error, ErrorKind, PublicKey, Result as CommonResult, ResultExt, SecKey};
+use client_core::multi_sig::SignerStatus;
 use client_core::types::AddressType;
 use client_core::wallet::WalletRequest;
 use client_core::{MultiSigWalletClient, WalletClient};
@@ -84,6 +85,17 @@ pub trait MultiSigRpc: Send + Sync {
         session_id: String,
         unsigned_transaction: Tx,
     ) -> Result<String>;
+
+    #[rpc(name = "multiSig_sessionStatus")]
+    fn session_status(&self, session_id: String, enckey: SecKey) -> Result<Vec<SignerStatus>>;
+
+    #[rpc(name = "multiSig_finalize")]
+    fn finalize(
+        &self,
+        request: WalletRequest,
+        session_id: String,
+        unsigned_transaction: Tx,
+    ) -> Result<String>;
 }
 
 pub struct MultiSigRpcImpl<T>
@@ -91,14 +103,15 @@ where
     T: WalletClient,
 {
     client: T,
+    network_id: u8,
 }
 
 impl<T> MultiSigRpcImpl<T>
 where
     T: WalletClient,
 {
-    pub fn new(client: T) -> Self {
-        MultiSigRpcImpl { client }
+    pub fn new(client: T, network_id: u8) -> Self {
+        MultiSigRpcImpl { client, network_id }
     }
 }
 
@@ -271,6 +284,7 @@ where
         unsigned_transaction: Tx,
     ) -> Result<String> {
         let session_id = parse_hash_256(session_id).map_err(to_rpc_error)?;
+        check_network_id(self.network_id, &unsigned_transaction).map_err(to_rpc_error)?;
 
         let tx_aux = self
             .client
@@ -287,6 +301,50 @@ where
             .map(|result| result.data.to_string())
             .map_err(to_rpc_error)
     }
+
+    fn session_status(&self, session_id: String, enckey: SecKey) -> Result<Vec<SignerStatus>> {
+        let session_id = parse_hash_256(session_id).map_err(to_rpc_error)?;
+
+        self.client
+            .session_status(&session_id, &enckey)
+            .map_err(to_rpc_error)
+    }
+
+    fn finalize(
+        &self,
+        request: WalletRequest,
+        session_id: String,
+        unsigned_transaction: Tx,
+    ) -> Result<String> {
+        let transaction_id = self.broadcast_with_signature(
+            request.clone(),
+            session_id.clone(),
+            unsigned_transaction,
+        )?;
+
+        let session_id = parse_hash_256(session_id).map_err(to_rpc_error)?;
+        self.client
+            .delete_session(&session_id, &request.enckey)
+            .map_err(to_rpc_error)?;
+        self.client.flush_database().map_err(to_rpc_error)?;
+
+        Ok(transaction_id)
+    }
+}
+
+fn check_network_id(network_id: u8, transaction: &Tx) -> CommonResult<()> {
+    let chain_hex_id = transaction.attributes.chain_hex_id;
+    if chain_hex_id == network_id {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Transaction is built for network id {:02X}, but the network id is {:02X}",
+                chain_hex_id, network_id
+            ),
+        ))
+    }
 }
 
 fn serialize_hash_256(hash: H256) -> String {
@@ -338,9 +396,11 @@ mod test {
 
     use chain_core::init::coin::CoinError;
     use chain_core::state::ChainState;
+    use chain_core::tx::data::attribute::TxAttributes;
     use chain_core::tx::data::TxId;
     use chain_core::tx::fee::{Fee, FeeAlgorithm};
     use chain_core::tx::TxAux;
+    use client_common::seckey::derive_enckey;
     use client_common::storage::MemoryStorage;
     use client_common::tendermint::types::*;
     use client_common::tendermint::Client;
@@ -404,6 +464,23 @@ mod test {
         );
     }
 
+    #[test]
+    fn broadcast_should_reject_transaction_of_other_network() {
+        let multisig_rpc = setup_multisig_rpc();
+        let wallet_request = WalletRequest {
+            name: "Default".to_owned(),
+            enckey: derive_enckey(&SecUtf8::from("passphrase"), "").unwrap(),
+        };
+        let transaction = Tx::new_with(vec![], vec![], TxAttributes::new(0x42));
+
+        let error = multisig_rpc
+            .broadcast_with_signature(wallet_request, encode([0u8; 32]), transaction)
+            .unwrap_err();
+        assert!(error
+            .message
+            .contains("Transaction is built for network id 42, but the network id is AB"));
+    }
+
     fn make_test_wallet_client(storage: MemoryStorage) -> TestWalletClient {
         let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
         let transaction_builder = DefaultWalletTransactionBuilder::new(
@@ -423,7 +500,7 @@ mod test {
     fn setup_multisig_rpc() -> MultiSigRpcImpl<TestWalletClient> {
         let storage = MemoryStorage::default();
         let wallet_client = make_test_wallet_client(storage);
-        MultiSigRpcImpl::new(wallet_client)
+        MultiSigRpcImpl::new(wallet_client, 0xab)
     }
 
     #[derive(Default, Clone)]
@@ -499,6 +576,17 @@ mod test {
             unreachable!("broadcast_transaction")
         }
 
+        fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+            &self,
+            _transactions: T,
+        ) -> CommonResult<Vec<CommonResult<BroadcastTxResponse>>> {
+            unreachable!("broadcast_transaction_batch")
+        }
+
+        fn unconfirmed_txs(&self, _limit: u64) -> CommonResult<UnconfirmedTxsResponse> {
+            unreachable!("unconfirmed_txs")
+        }
+
         fn query(
             &self,
             _path: &str,
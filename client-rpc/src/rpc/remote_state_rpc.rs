@@ -0,0 +1,258 @@
+//! Wallet state read API for thin clients (e.g. mobile apps) which delegate syncing to this
+//! server.
+//!
+//! Staking states are served together with everything needed to verify them trustlessly:
+//! the jellyfish proof against the account root of the chain state, the chain state and valid
+//! transaction ids to recompute the app hash, and the light block which commits to that app
+//! hash. The device verifies the light block with its own light client starting from a trusted
+//! header (`remote_lightBlock` serves the header chain for that).
+//!
+//! UTxOs are confidential on chain, so they can't be proven without decrypting transactions;
+//! they're served with the height of the block which committed them, so the device can at least
+//! check the transaction ids against the (verified) block results.
+use std::iter;
+
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use parity_scale_codec::Decode;
+use serde::{Deserialize, Serialize};
+
+use crate::to_rpc_error;
+use chain_core::state::account::{StakedState, StakedStateAddress};
+use chain_core::state::proof::SparseMerkleProof;
+use chain_core::state::ChainState;
+use chain_core::tx::data::input::TxoPointer;
+use chain_core::tx::data::output::TxOut;
+use client_common::tendermint::types::{AbciQueryExt, BlockResults};
+use client_common::tendermint::Client;
+use client_common::{Error, ErrorKind, Result as CommonResult, ResultExt};
+use client_core::wallet::syncer::Handle;
+use client_core::wallet::WalletRequest;
+use client_core::WalletClient;
+use tendermint_light_client::types::LightBlock;
+
+/// Unspent output of a wallet
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoteUtxo {
+    /// pointer to the output
+    pub pointer: TxoPointer,
+    /// the output
+    pub output: TxOut,
+    /// height of the block which committed the transaction
+    pub block_height: u64,
+}
+
+/// Unspent outputs of a wallet at the height it's synced to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoteUtxoSet {
+    /// height the wallet is synced to
+    pub height: u64,
+    /// app hash at `height`
+    pub app_hash: String,
+    /// unspent outputs
+    pub utxos: Vec<RemoteUtxo>,
+}
+
+/// Staking state with the data to verify it against a light client header
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProvenStakingState {
+    /// height of the block after which the state is taken
+    pub height: u64,
+    /// the staking state, `None` if the address doesn't exist
+    pub staked_state: Option<StakedState>,
+    /// hex encoded jellyfish proof of `staked_state` against `chain_state.account_root`
+    pub proof: String,
+    /// chain state after block `height`
+    pub chain_state: ChainState,
+    /// ids of valid transactions in block `height` (to recompute the app hash)
+    pub valid_txids: Vec<String>,
+    /// light block `height + 1`, its header commits to the app hash of `chain_state`
+    pub light_block: LightBlock,
+}
+
+#[rpc(server)]
+pub trait RemoteStateRpc: Send + Sync {
+    #[rpc(name = "remote_walletUtxos")]
+    fn wallet_utxos(&self, request: WalletRequest) -> Result<RemoteUtxoSet>;
+
+    #[rpc(name = "remote_stakingState")]
+    fn staking_state(&self, address: StakedStateAddress) -> Result<ProvenStakingState>;
+
+    #[rpc(name = "remote_lightBlock")]
+    fn light_block(&self, height: u64) -> Result<LightBlock>;
+}
+
+pub struct RemoteStateRpcImpl<T, C, L>
+where
+    T: WalletClient,
+    C: Client,
+    L: Handle + Send + Sync,
+{
+    client: T,
+    tendermint_client: C,
+    light_client: Option<L>,
+}
+
+impl<T, C, L> RemoteStateRpcImpl<T, C, L>
+where
+    T: WalletClient,
+    C: Client,
+    L: Handle + Send + Sync,
+{
+    pub fn new(client: T, tendermint_client: C, light_client: Option<L>) -> Self {
+        RemoteStateRpcImpl {
+            client,
+            tendermint_client,
+            light_client,
+        }
+    }
+
+    fn get_light_client(&self) -> CommonResult<&L> {
+        self.light_client.as_ref().chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                "Light client is disabled, remote state can't be proven",
+            )
+        })
+    }
+
+    fn get_wallet_utxos(&self, request: &WalletRequest) -> CommonResult<RemoteUtxoSet> {
+        let sync_state = self.client.get_sync_state(&request.name)?;
+        let unspent_transactions = self
+            .client
+            .unspent_transactions(&request.name, &request.enckey)?;
+
+        let utxos = unspent_transactions
+            .iter()
+            .map(|(pointer, output)| {
+                let block_height = self
+                    .client
+                    .get_transaction_change(&request.name, &request.enckey, &pointer.id)?
+                    .map(|change| change.block_height)
+                    .chain(|| {
+                        (
+                            ErrorKind::InternalError,
+                            "Transaction of unspent output is not found in history",
+                        )
+                    })?;
+                Ok(RemoteUtxo {
+                    pointer: pointer.clone(),
+                    output: output.clone(),
+                    block_height,
+                })
+            })
+            .collect::<CommonResult<Vec<_>>>()?;
+
+        Ok(RemoteUtxoSet {
+            height: sync_state.last_block_height,
+            app_hash: sync_state.last_app_hash,
+            utxos,
+        })
+    }
+
+    fn get_staking_state(&self, address: &StakedStateAddress) -> CommonResult<ProvenStakingState> {
+        let light_block = self
+            .get_light_client()?
+            .verify_to_highest()
+            .map_err(|e| Error::new(ErrorKind::VerifyError, e.to_string()))?;
+        let height = light_block
+            .signed_header
+            .header
+            .height
+            .value()
+            .checked_sub(1)
+            .filter(|height| *height > 0)
+            .chain(|| (ErrorKind::InvalidInput, "No block is committed yet"))?;
+
+        let rsp =
+            self.tendermint_client
+                .query("staking", address.as_ref(), Some(height.into()), true)?;
+        let staked_state =
+            <Option<StakedState>>::decode(&mut rsp.bytes().as_slice()).chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!("Cannot deserialize staked state for address: {}", address),
+                )
+            })?;
+        let proof_bytes = rsp
+            .proof
+            .as_ref()
+            .and_then(|proof| proof.ops.first())
+            .map(|op| op.data.clone())
+            .chain(|| {
+                (
+                    ErrorKind::TendermintRpcError,
+                    format!("There is no proof for address: {}", address),
+                )
+            })?;
+        let proof = SparseMerkleProof::decode(&mut proof_bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                format!(
+                    "Cannot deserialize staked state proof for address: {}",
+                    address
+                ),
+            )
+        })?;
+
+        let chain_state = self
+            .tendermint_client
+            .query_state_batch(iter::once(height))?
+            .into_iter()
+            .next()
+            .chain(|| (ErrorKind::TendermintRpcError, "Unable to query chain state"))?;
+        let valid_txids = self
+            .tendermint_client
+            .block_results(height)?
+            .fees()?
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // check the bundle before serving it, so that a broken node doesn't look like an attack
+        proof
+            .verify(chain_state.account_root, address, staked_state.as_ref())
+            .err_kind(ErrorKind::VerifyError, || "Verify staking state failed")?;
+        let app_hash = chain_state.compute_app_hash(valid_txids.clone());
+        if light_block.signed_header.header.app_hash.as_ref() != &app_hash[..] {
+            return Err(Error::new(
+                ErrorKind::VerifyError,
+                "App hash of chain state doesn't match the light block",
+            ));
+        }
+
+        Ok(ProvenStakingState {
+            height,
+            staked_state,
+            proof: hex::encode(&proof_bytes),
+            chain_state,
+            valid_txids: valid_txids.iter().map(hex::encode).collect(),
+            light_block,
+        })
+    }
+}
+
+impl<T, C, L> RemoteStateRpc for RemoteStateRpcImpl<T, C, L>
+where
+    T: WalletClient + 'static,
+    C: Client + 'static,
+    L: Handle + Send + Sync + 'static,
+{
+    fn wallet_utxos(&self, request: WalletRequest) -> Result<RemoteUtxoSet> {
+        self.get_wallet_utxos(&request).map_err(to_rpc_error)
+    }
+
+    fn staking_state(&self, address: StakedStateAddress) -> Result<ProvenStakingState> {
+        self.get_staking_state(&address).map_err(to_rpc_error)
+    }
+
+    fn light_block(&self, height: u64) -> Result<LightBlock> {
+        self.get_light_client()
+            .and_then(|light_client| {
+                light_client
+                    .verify_to_target(height)
+                    .map_err(|e| Error::new(ErrorKind::VerifyError, e.to_string()))
+            })
+            .map_err(to_rpc_error)
+    }
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+
+use crate::session::Sessions;
+use crate::{rpc_error_from_string, to_rpc_error};
+use client_core::wallet::CreateWalletRequest;
+use client_core::WalletClient;
+
+/// Longest lifetime of a session (one day)
+const MAX_SESSION_TTL_SECONDS: u64 = 24 * 60 * 60;
+
+#[rpc(server)]
+pub trait SessionRpc: Send + Sync {
+    #[rpc(name = "wallet_unlock")]
+    fn unlock(&self, request: CreateWalletRequest, ttl_seconds: u64) -> Result<String>;
+
+    #[rpc(name = "wallet_lock")]
+    fn lock(&self, token: String) -> Result<bool>;
+}
+
+pub struct SessionRpcImpl<T>
+where
+    T: WalletClient,
+{
+    client: T,
+    sessions: Sessions,
+}
+
+impl<T> SessionRpcImpl<T>
+where
+    T: WalletClient,
+{
+    pub fn new(client: T, sessions: Sessions) -> Self {
+        SessionRpcImpl { client, sessions }
+    }
+}
+
+impl<T> SessionRpc for SessionRpcImpl<T>
+where
+    T: WalletClient + 'static,
+{
+    fn unlock(&self, request: CreateWalletRequest, ttl_seconds: u64) -> Result<String> {
+        if ttl_seconds == 0 || ttl_seconds > MAX_SESSION_TTL_SECONDS {
+            return Err(rpc_error_from_string(format!(
+                "Session lifetime should be between 1 and {} seconds",
+                MAX_SESSION_TTL_SECONDS
+            )));
+        }
+        let enckey = self
+            .client
+            .auth_token(&request.name, &request.passphrase)
+            .map_err(to_rpc_error)?;
+        Ok(self
+            .sessions
+            .unlock(&request.name, enckey, Duration::from_secs(ttl_seconds)))
+    }
+
+    fn lock(&self, token: String) -> Result<bool> {
+        Ok(self.sessions.lock(&token))
+    }
+}
@@ -3,25 +3,56 @@ use std::str::FromStr;
 
 use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
+use parity_scale_codec::Encode;
+use serde::{Deserialize, Serialize};
 
 use crate::{rpc_error_from_string, to_rpc_error};
 use chain_core::init::coin::Coin;
 use chain_core::state::account::{
-    ConfidentialInit, CouncilNodeMeta, MLSInit, StakedState, StakedStateAddress,
+    ConfidentialInit, CouncilNodeMeta, MLSInit, NodeState, StakedState, StakedStateAddress,
     StakedStateOpAttributes,
 };
 use chain_core::state::tendermint::TendermintValidatorPubKey;
-use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
-use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
+use chain_core::tx::TxAux;
 use client_common::temporary_mls_init;
 use client_common::{Error, ErrorKind, PublicKey, Result as CommonResult, ResultExt, Transaction};
+use client_core::transaction_builder::AttributesBuilder;
+use client_core::types::TransactionPending;
 use client_core::wallet::WalletRequest;
 use client_core::WalletClient;
 use client_network::NetworkOpsClient;
 
+/// Staking state of an account, with the status of its council node
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StakingAccountState {
+    /// bonded and unbonded amounts, council node metadata and last slashing
+    #[serde(flatten)]
+    pub staked_state: StakedState,
+    /// is the account a council node in the active validator set
+    pub is_active: bool,
+    /// is the account a jailed council node
+    pub is_jailed: bool,
+}
+
+impl From<StakedState> for StakingAccountState {
+    fn from(staked_state: StakedState) -> Self {
+        let (is_active, is_jailed) = match &staked_state.node_meta {
+            Some(NodeState::CouncilNode(validator)) => {
+                (validator.is_active(), validator.is_jailed())
+            }
+            _ => (false, false),
+        };
+        StakingAccountState {
+            staked_state,
+            is_active,
+            is_jailed,
+        }
+    }
+}
+
 #[rpc(server)]
 pub trait StakingRpc: Send + Sync {
     #[rpc(name = "staking_depositStake")]
@@ -43,6 +74,9 @@ pub trait StakingRpc: Send + Sync {
     #[rpc(name = "staking_state")]
     fn state(&self, name: String, address: StakedStateAddress) -> Result<StakedState>;
 
+    #[rpc(name = "staking_accountState")]
+    fn account_state(&self, address: StakedStateAddress) -> Result<Option<StakingAccountState>>;
+
     #[rpc(name = "staking_unbondStake")]
     fn unbond_stake(
         &self,
@@ -60,6 +94,31 @@ pub trait StakingRpc: Send + Sync {
         view_keys: Vec<String>,
     ) -> Result<String>;
 
+    #[rpc(name = "staking_buildDepositStake")]
+    fn build_deposit_stake(
+        &self,
+        request: WalletRequest,
+        to_address: String,
+        inputs: Vec<TxoPointer>,
+    ) -> Result<String>;
+
+    #[rpc(name = "staking_buildUnbondStake")]
+    fn build_unbond_stake(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        amount: Coin,
+    ) -> Result<String>;
+
+    #[rpc(name = "staking_buildWithdrawAllUnbondedStake")]
+    fn build_withdraw_all_unbonded_stake(
+        &self,
+        request: WalletRequest,
+        from_address: String,
+        to_address: String,
+        view_keys: Vec<String>,
+    ) -> Result<String>;
+
     #[rpc(name = "staking_unjail")]
     fn unjail(&self, request: WalletRequest, unjail_address: String) -> Result<String>;
 
@@ -96,20 +155,15 @@ where
             network_id,
         }
     }
-}
 
-impl<T, N> StakingRpc for StakingRpcImpl<T, N>
-where
-    T: WalletClient + 'static,
-    N: NetworkOpsClient + 'static,
-{
-    fn deposit_stake(
+    /// Creates a signed deposit transaction of given unspent outputs
+    fn deposit_stake_transaction(
         &self,
-        request: WalletRequest,
-        to_address: String,
+        request: &WalletRequest,
+        to_address: &str,
         inputs: Vec<TxoPointer>,
-    ) -> Result<String> {
-        let to_address = StakedStateAddress::from_str(&to_address)
+    ) -> Result<(TxAux, TransactionPending)> {
+        let to_address = StakedStateAddress::from_str(to_address)
             .chain(|| {
                 (
                     ErrorKind::DeserializationError,
@@ -139,8 +193,7 @@ where
             .collect::<Result<Vec<(TxoPointer, TxOut)>>>()
             .map_err(to_rpc_error)?;
 
-        let (transaction, tx_pending) = self
-            .ops_client
+        self.ops_client
             .create_deposit_bonded_stake_transaction(
                 &request.name,
                 &request.enckey,
@@ -149,12 +202,99 @@ where
                 attributes,
                 true,
             )
+            .map_err(to_rpc_error)
+    }
+
+    /// Creates a signed unbond transaction, with the nonce of the current staked state
+    fn unbond_stake_transaction(
+        &self,
+        request: &WalletRequest,
+        staking_address: &str,
+        amount: Coin,
+    ) -> Result<TxAux> {
+        let attr = StakedStateOpAttributes::new(self.network_id);
+        let addr = StakedStateAddress::from_str(staking_address)
+            .chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!(
+                        "Unable to deserialize staking address ({})",
+                        staking_address
+                    ),
+                )
+            })
             .map_err(to_rpc_error)?;
 
+        self.ops_client
+            .create_unbond_stake_transaction(
+                &request.name,
+                &request.enckey,
+                addr,
+                amount,
+                attr,
+                true,
+            )
+            .map_err(to_rpc_error)
+    }
+
+    /// Creates a signed transaction withdrawing all unbonded stake to a transfer address
+    fn withdraw_all_unbonded_stake_transaction(
+        &self,
+        request: &WalletRequest,
+        from_address: &str,
+        to_address: &str,
+        view_keys: Vec<String>,
+    ) -> Result<(TxAux, TransactionPending)> {
+        let from_address = StakedStateAddress::from_str(from_address)
+            .chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!("Unable to deserialize from_address ({})", from_address),
+                )
+            })
+            .map_err(to_rpc_error)?;
+        let to_address = ExtendedAddr::from_str(to_address)
+            .chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!("Unable to deserialize to_address ({})", to_address),
+                )
+            })
+            .map_err(to_rpc_error)?;
+        let view_keys = view_keys
+            .iter()
+            .map(|key| PublicKey::from_str(key))
+            .collect::<CommonResult<Vec<PublicKey>>>()
+            .map_err(to_rpc_error)?;
+
+        let attributes = AttributesBuilder::with_network_id(self.network_id)
+            .view_keys(view_keys)
+            .wallet_view_key(&self.client, &request.name, &request.enckey)
+            .and_then(AttributesBuilder::build)
+            .map_err(to_rpc_error)?;
+
+        self.ops_client
+            .create_withdraw_all_unbonded_stake_transaction(
+                &request.name,
+                &request.enckey,
+                &from_address,
+                to_address,
+                attributes,
+                true,
+            )
+            .map_err(to_rpc_error)
+    }
+
+    /// Broadcasts a transaction, then records it as pending in the wallet, returns the tx id
+    fn broadcast_pending_transaction(
+        &self,
+        request: &WalletRequest,
+        transaction: TxAux,
+        tx_pending: TransactionPending,
+    ) -> Result<String> {
         self.client
             .broadcast_transaction(&transaction)
             .map_err(to_rpc_error)?;
-
         // update the wallet pending transaction state
         self.client
             .update_tx_pending_state(
@@ -164,10 +304,46 @@ where
                 tx_pending,
             )
             .map_err(to_rpc_error)?;
-
         Ok(hex::encode(transaction.tx_id()))
     }
 
+    /// Records a transaction broadcast by the caller as pending in the wallet (its inputs aren't
+    /// spent again, they're released if it isn't committed in time), returns the hex encoded
+    /// transaction
+    fn encode_pending_transaction(
+        &self,
+        request: &WalletRequest,
+        transaction: TxAux,
+        tx_pending: TransactionPending,
+    ) -> Result<String> {
+        self.client
+            .update_tx_pending_state(
+                &request.name,
+                &request.enckey,
+                transaction.tx_id(),
+                tx_pending,
+            )
+            .map_err(to_rpc_error)?;
+        Ok(hex::encode(transaction.encode()))
+    }
+}
+
+impl<T, N> StakingRpc for StakingRpcImpl<T, N>
+where
+    T: WalletClient + 'static,
+    N: NetworkOpsClient + 'static,
+{
+    fn deposit_stake(
+        &self,
+        request: WalletRequest,
+        to_address: String,
+        inputs: Vec<TxoPointer>,
+    ) -> Result<String> {
+        let (transaction, tx_pending) =
+            self.deposit_stake_transaction(&request, &to_address, inputs)?;
+        self.broadcast_pending_transaction(&request, transaction, tx_pending)
+    }
+
     /// deposit amount coin to a deposit address
     /// 1. build a transfer transaction to make a UTXO which amount is `deposit_amount + fee`
     /// 2. send to a self created transfer address, waiting it confirmed
@@ -260,36 +436,20 @@ where
             .map_err(to_rpc_error)
     }
 
+    fn account_state(&self, address: StakedStateAddress) -> Result<Option<StakingAccountState>> {
+        self.ops_client
+            .query_staked_state(&address)
+            .map(|mstaking| mstaking.map(StakingAccountState::from))
+            .map_err(to_rpc_error)
+    }
+
     fn unbond_stake(
         &self,
         request: WalletRequest,
         staking_address: String,
         amount: Coin,
     ) -> Result<String> {
-        let attr = StakedStateOpAttributes::new(self.network_id);
-        let addr = StakedStateAddress::from_str(&staking_address)
-            .chain(|| {
-                (
-                    ErrorKind::DeserializationError,
-                    format!(
-                        "Unable to deserialize staking address ({})",
-                        staking_address
-                    ),
-                )
-            })
-            .map_err(to_rpc_error)?;
-
-        let transaction = self
-            .ops_client
-            .create_unbond_stake_transaction(
-                &request.name,
-                &request.enckey,
-                addr,
-                amount,
-                attr,
-                true,
-            )
-            .map_err(to_rpc_error)?;
+        let transaction = self.unbond_stake_transaction(&request, &staking_address, amount)?;
 
         self.client
             .broadcast_transaction(&transaction)
@@ -305,71 +465,50 @@ where
         to_address: String,
         view_keys: Vec<String>,
     ) -> Result<String> {
-        let from_address = StakedStateAddress::from_str(&from_address)
-            .chain(|| {
-                (
-                    ErrorKind::DeserializationError,
-                    format!("Unable to deserialize from_address ({})", from_address),
-                )
-            })
-            .map_err(to_rpc_error)?;
-        let to_address = ExtendedAddr::from_str(&to_address)
-            .chain(|| {
-                (
-                    ErrorKind::DeserializationError,
-                    format!("Unable to deserialize to_address ({})", to_address),
-                )
-            })
-            .map_err(to_rpc_error)?;
-        let mut view_keys = view_keys
-            .iter()
-            .map(|key| PublicKey::from_str(key))
-            .collect::<CommonResult<BTreeSet<PublicKey>>>()
-            .map_err(to_rpc_error)?;
-
-        let view_key = self
-            .client
-            .view_key(&request.name, &request.enckey)
-            .map_err(to_rpc_error)?;
-
-        view_keys.insert(view_key);
-
-        let access_policies: BTreeSet<_> = view_keys
-            .iter()
-            .map(|key| TxAccessPolicy {
-                view_key: key.into(),
-                access: TxAccess::AllData,
-            })
-            .collect();
+        let (transaction, tx_pending) = self.withdraw_all_unbonded_stake_transaction(
+            &request,
+            &from_address,
+            &to_address,
+            view_keys,
+        )?;
+        self.broadcast_pending_transaction(&request, transaction, tx_pending)
+    }
 
-        let attributes =
-            TxAttributes::new_with_access(self.network_id, access_policies.into_iter().collect());
+    fn build_deposit_stake(
+        &self,
+        request: WalletRequest,
+        to_address: String,
+        inputs: Vec<TxoPointer>,
+    ) -> Result<String> {
+        let (transaction, tx_pending) =
+            self.deposit_stake_transaction(&request, &to_address, inputs)?;
+        self.encode_pending_transaction(&request, transaction, tx_pending)
+    }
 
-        let (transaction, tx_pending) = self
-            .ops_client
-            .create_withdraw_all_unbonded_stake_transaction(
-                &request.name,
-                &request.enckey,
-                &from_address,
-                to_address,
-                attributes,
-                true,
-            )
-            .map_err(to_rpc_error)?;
+    fn build_unbond_stake(
+        &self,
+        request: WalletRequest,
+        staking_address: String,
+        amount: Coin,
+    ) -> Result<String> {
+        let transaction = self.unbond_stake_transaction(&request, &staking_address, amount)?;
+        Ok(hex::encode(transaction.encode()))
+    }
 
-        self.client
-            .broadcast_transaction(&transaction)
-            .map_err(to_rpc_error)?;
-        // update the wallet pending transaction state
-        self.client
-            .update_tx_pending_state(
-                &request.name,
-                &request.enckey,
-                transaction.tx_id(),
-                tx_pending,
-            )
-            .map_err(to_rpc_error)?;
-        Ok(hex::encode(transaction.tx_id()))
+    fn build_withdraw_all_unbonded_stake(
+        &self,
+        request: WalletRequest,
+        from_address: String,
+        to_address: String,
+        view_keys: Vec<String>,
+    ) -> Result<String> {
+        let (transaction, tx_pending) = self.withdraw_all_unbonded_stake_transaction(
+            &request,
+            &from_address,
+            &to_address,
+            view_keys,
+        )?;
+        self.encode_pending_transaction(&request, transaction, tx_pending)
     }
 
     fn unjail(&self, request: WalletRequest, unjail_address: String) -> Result<String> {
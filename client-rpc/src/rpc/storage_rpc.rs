@@ -0,0 +1,55 @@
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+
+use crate::to_rpc_error;
+use client_common::Storage;
+use client_core::service::{OrphanRecord, OrphanReport, StorageMaintenanceService, StorageStats};
+
+#[rpc(server)]
+pub trait StorageRpc: Send + Sync {
+    #[rpc(name = "storage_stats")]
+    fn stats(&self) -> Result<StorageStats>;
+
+    #[rpc(name = "storage_scanOrphans")]
+    fn scan_orphans(&self) -> Result<OrphanReport>;
+
+    #[rpc(name = "storage_collectOrphans")]
+    fn collect_orphans(&self, records: Vec<OrphanRecord>) -> Result<OrphanReport>;
+}
+
+pub struct StorageRpcImpl<S>
+where
+    S: Storage,
+{
+    maintenance_service: StorageMaintenanceService<S>,
+}
+
+impl<S> StorageRpcImpl<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S) -> Self {
+        StorageRpcImpl {
+            maintenance_service: StorageMaintenanceService::new(storage),
+        }
+    }
+}
+
+impl<S> StorageRpc for StorageRpcImpl<S>
+where
+    S: Storage + 'static,
+{
+    fn stats(&self) -> Result<StorageStats> {
+        self.maintenance_service.stats().map_err(to_rpc_error)
+    }
+
+    fn scan_orphans(&self) -> Result<OrphanReport> {
+        self.maintenance_service.scan().map_err(to_rpc_error)
+    }
+
+    fn collect_orphans(&self, records: Vec<OrphanRecord>) -> Result<OrphanReport> {
+        self.maintenance_service
+            .collect(&records)
+            .map_err(to_rpc_error)
+    }
+}
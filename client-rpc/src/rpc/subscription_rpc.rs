@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::typed::{Sink, Subscriber};
+use jsonrpc_pubsub::SubscriptionId;
+use serde::Serialize;
+
+use crate::display::RequestMeta;
+use crate::to_rpc_error;
+use client_core::types::{TransactionChange, WalletBalance};
+use client_core::wallet::WalletRequest;
+use client_core::WalletClient;
+
+/// Number of random bytes of a subscription id
+const SUBSCRIPTION_ID_SIZE: usize = 16;
+
+#[rpc(server)]
+pub trait SubscriptionRpc {
+    type Metadata;
+
+    #[pubsub(
+        subscription = "balanceChanged",
+        subscribe,
+        name = "subscribe_balanceChanged"
+    )]
+    fn subscribe_balance(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<WalletBalance>,
+        request: WalletRequest,
+    );
+
+    #[pubsub(
+        subscription = "balanceChanged",
+        unsubscribe,
+        name = "unsubscribe_balanceChanged"
+    )]
+    fn unsubscribe_balance(&self, meta: Option<Self::Metadata>, id: SubscriptionId)
+        -> Result<bool>;
+
+    #[pubsub(
+        subscription = "newTransaction",
+        subscribe,
+        name = "subscribe_newTransaction"
+    )]
+    fn subscribe_transactions(
+        &self,
+        meta: Self::Metadata,
+        subscriber: Subscriber<TransactionChange>,
+        request: WalletRequest,
+    );
+
+    #[pubsub(
+        subscription = "newTransaction",
+        unsubscribe,
+        name = "unsubscribe_newTransaction"
+    )]
+    fn unsubscribe_transactions(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool>;
+}
+
+/// Sinks of the subscriptions by id, with the names of their wallets
+struct Subscriptions<T> {
+    sinks: HashMap<SubscriptionId, (String, Sink<T>)>,
+}
+
+impl<T: Serialize + Clone> Subscriptions<T> {
+    fn add(&mut self, name: String, subscriber: Subscriber<T>) -> Option<Sink<T>> {
+        let id = SubscriptionId::String(hex::encode(rand::random::<[u8; SUBSCRIPTION_ID_SIZE]>()));
+        let sink = subscriber.assign_id(id.clone()).ok()?;
+        self.sinks.insert(id, (name, sink.clone()));
+        Some(sink)
+    }
+
+    /// Sends the value to the subscribers of the wallet, drops the subscriptions of closed
+    /// connections
+    fn notify(&mut self, name: &str, value: &T) {
+        self.sinks.retain(|_, (wallet_name, sink)| {
+            wallet_name != name || sink.notify(Ok(value.clone())).wait().is_ok()
+        });
+    }
+}
+
+impl<T> Default for Subscriptions<T> {
+    fn default() -> Self {
+        Subscriptions {
+            sinks: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Subscribers {
+    balance: Subscriptions<WalletBalance>,
+    transactions: Subscriptions<TransactionChange>,
+}
+
+/// Pushes the changes applied by the wallet syncer to the subscribers, clones share the
+/// subscribers
+#[derive(Clone, Default)]
+pub struct WalletNotifier {
+    subscribers: Arc<Mutex<Subscribers>>,
+}
+
+impl WalletNotifier {
+    /// Notifies the subscribers of the wallet of its new transactions and balance
+    pub fn notify(&self, name: &str, transactions: &[TransactionChange], balance: &WalletBalance) {
+        let mut subscribers = self.subscribers.lock().expect("lock subscribers");
+        for transaction in transactions {
+            subscribers.transactions.notify(name, transaction);
+        }
+        subscribers.balance.notify(name, balance);
+    }
+}
+
+pub struct SubscriptionRpcImpl<T>
+where
+    T: WalletClient,
+{
+    client: T,
+    notifier: WalletNotifier,
+}
+
+impl<T> SubscriptionRpcImpl<T>
+where
+    T: WalletClient,
+{
+    pub fn new(client: T, notifier: WalletNotifier) -> Self {
+        SubscriptionRpcImpl { client, notifier }
+    }
+}
+
+impl<T> SubscriptionRpc for SubscriptionRpcImpl<T>
+where
+    T: WalletClient + 'static,
+{
+    type Metadata = RequestMeta;
+
+    fn subscribe_balance(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<WalletBalance>,
+        request: WalletRequest,
+    ) {
+        // checks the enckey, the current balance is sent right away
+        let balance = match self.client.balance(&request.name, &request.enckey) {
+            Ok(balance) => balance,
+            Err(e) => {
+                let _ = subscriber.reject(to_rpc_error(e));
+                return;
+            }
+        };
+        let mut subscribers = self.notifier.subscribers.lock().expect("lock subscribers");
+        if let Some(sink) = subscribers.balance.add(request.name, subscriber) {
+            let _ = sink.notify(Ok(balance)).wait();
+        }
+    }
+
+    fn unsubscribe_balance(
+        &self,
+        _meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        let mut subscribers = self.notifier.subscribers.lock().expect("lock subscribers");
+        Ok(subscribers.balance.sinks.remove(&id).is_some())
+    }
+
+    fn subscribe_transactions(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<TransactionChange>,
+        request: WalletRequest,
+    ) {
+        // checks the enckey
+        if let Err(e) = self.client.balance(&request.name, &request.enckey) {
+            let _ = subscriber.reject(to_rpc_error(e));
+            return;
+        }
+        let mut subscribers = self.notifier.subscribers.lock().expect("lock subscribers");
+        subscribers.transactions.add(request.name, subscriber);
+    }
+
+    fn unsubscribe_transactions(
+        &self,
+        _meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> Result<bool> {
+        let mut subscribers = self.notifier.subscribers.lock().expect("lock subscribers");
+        Ok(subscribers.transactions.sinks.remove(&id).is_some())
+    }
+}
@@ -1,3 +1,4 @@
+use super::subscription_rpc::WalletNotifier;
 use super::sync_worker::SyncWorker;
 use super::sync_worker::WorkerShared;
 use crate::to_rpc_error;
@@ -73,6 +74,9 @@ pub trait SyncRpc: Send + Sync {
 
     #[rpc(name = "sync_stop")]
     fn sync_stop(&self, request: WalletRequest) -> Result<()>;
+
+    #[rpc(name = "wallet_rescan")]
+    fn rescan(&self, request: WalletRequest, from_height: u64) -> Result<RunSyncResult>;
 }
 
 pub struct SyncRpcImpl<S, C, O, T, L>
@@ -86,6 +90,7 @@ where
     config: ObfuscationSyncerConfig<S, C, O, L>,
 
     progress_callback: Option<CBindingCore>,
+    notifier: WalletNotifier,
     worker: WorkerShared,
     recover_address: T,
     light_client_handle: Option<L>,
@@ -102,7 +107,7 @@ where
     pub fn new(
         config: ObfuscationSyncerConfig<S, C, O, L>,
         progress_callback: Option<CBindingCore>,
-
+        notifier: WalletNotifier,
         recover_address: T,
         light_client_handle: Option<L>,
     ) -> Self {
@@ -110,6 +115,7 @@ where
             config,
 
             progress_callback,
+            notifier,
             worker: Arc::new(Mutex::new(SyncWorker::new())),
 
             recover_address,
@@ -119,19 +125,25 @@ where
 }
 
 fn process_sync<S, C, O, T, L>(
-    config: ObfuscationSyncerConfig<S, C, O, L>,
+    mut config: ObfuscationSyncerConfig<S, C, O, L>,
     request: WalletRequest,
     reset: bool,
+    rescan_from: Option<u64>,
     progress_callback: Option<CBindingCore>,
+    notifier: WalletNotifier,
     recover_address: T,
 ) -> Result<()>
 where
     S: Storage + 'static,
-    C: Client,
+    C: Client + 'static,
     O: TransactionObfuscation,
     T: AddressRecovery,
     L: Handle + Send + Sync + Clone,
 {
+    if reset || rescan_from.is_some() {
+        // history of existing wallet must not be skipped
+        config.options.sync_from_now = false;
+    }
     let mut syncer = WalletSyncer::with_obfuscation_config(
         config,
         request.name,
@@ -142,9 +154,8 @@ where
     if reset {
         syncer.reset_state().map_err(to_rpc_error)?;
     }
-
-    if progress_callback.is_none() {
-        return syncer.sync(|_| true).map_err(to_rpc_error);
+    if let Some(from_height) = rescan_from {
+        syncer.rescan_state(from_height).map_err(to_rpc_error)?;
     }
 
     let mut init_block_height = 0;
@@ -187,6 +198,18 @@ where
                     }
                     true
                 }
+                ProgressReport::StateChanged {
+                    wallet_name,
+                    transactions,
+                    balance,
+                } => {
+                    notifier.notify(&wallet_name, &transactions, &balance);
+                    true
+                }
+                // recorded in the alert log of the wallet by the syncer
+                ProgressReport::Alert { .. } => true,
+                // the worker is marked complete once the sync returns
+                ProgressReport::Finish { .. } => true,
             }
         })
         .map_err(to_rpc_error)
@@ -204,10 +227,12 @@ where
         &self,
         request: WalletRequest,
         reset: bool,
+        rescan_from: Option<u64>,
         do_loop: bool,
     ) -> Result<RunSyncResult> {
         log::info!("run_sync");
         let config = self.config.clone();
+        let notifier = self.notifier.clone();
         let recover_address = self.recover_address.clone();
 
         let name = request.name.clone();
@@ -232,12 +257,15 @@ where
             let node = localworker.lock().expect("get sync worker lock").get(&name);
             let syncnode = node.expect("get progress callback");
             let usercallback = Some(CBindingCore { data: syncnode });
+            let mut rescan_from = rescan_from;
             loop {
                 let result = process_sync(
                     config.clone(),
                     userrequest.clone(),
                     reset,
+                    rescan_from.take(),
                     usercallback.clone(),
+                    notifier.clone(),
                     recover_address.clone(),
                 );
                 log::info!("process_sync finished {} {:?}", name, result);
@@ -264,9 +292,11 @@ where
                     .expect("get sync worker lock")
                     .set_complete(&name);
 
-                // notify
+                // notify (pushed by the new block subscription, or polling interval)
                 log::info!("wait for notification {}", name);
-                std::thread::sleep(std::time::Duration::from_secs(NOTIFICATION_TIME));
+                config
+                    .client
+                    .wait_for_new_block(std::time::Duration::from_secs(NOTIFICATION_TIME));
 
                 if !do_loop {
                     break;
@@ -303,13 +333,15 @@ where
                 self.config.clone(),
                 request,
                 sync_request.reset,
+                None,
                 self.progress_callback.clone(),
+                self.notifier.clone(),
                 self.recover_address.clone(),
             )?;
 
             Ok(RunSyncResult::default())
         } else {
-            self.do_run_sync(request, sync_request.reset, sync_request.do_loop)
+            self.do_run_sync(request, sync_request.reset, None, sync_request.do_loop)
         }
     }
 
@@ -328,6 +360,12 @@ where
             .expect("get sync worker lock")
             .stop(&request.name)
     }
+
+    #[inline]
+    fn rescan(&self, request: WalletRequest, from_height: u64) -> Result<RunSyncResult> {
+        log::info!("rescan {} from {}", request.name, from_height);
+        self.do_run_sync(request, false, Some(from_height), false)
+    }
 }
 
 impl<S, C, O, T, L> Drop for SyncRpcImpl<S, C, O, T, L>
@@ -2,20 +2,26 @@ use jsonrpc_core::Result;
 use jsonrpc_derive::rpc;
 use serde::{Deserialize, Serialize, Serializer};
 
-use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
-use chain_core::tx::data::attribute::TxAttributes;
+use chain_core::init::coin::Coin;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
 use chain_core::tx::data::{Tx, TxId};
 use chain_core::tx::TransactionId;
 use client_common::PublicKey;
-use std::collections::BTreeSet;
+use client_core::input_selection::InputSelectionStrategy;
+use client_core::transaction_builder::{AttributesBuilder, PartiallySignedTransferTx};
+use client_core::wallet::WalletRequest;
+use client_core::WalletClient;
+
+use crate::{rpc_error_from_string, to_rpc_error};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RawTransaction {
     tx: Tx,
     #[serde(serialize_with = "serialize_transaction_id")]
     tx_id: TxId,
+    /// partially signed transaction (base64) to be signed with `transaction_signRaw`
+    raw_tx: String,
 }
 
 fn serialize_transaction_id<S>(
@@ -37,35 +43,59 @@ pub trait TransactionRpc: Send + Sync {
         outputs: Vec<TxOut>,
         view_keys: Vec<PublicKey>,
     ) -> Result<RawTransaction>;
+
+    #[rpc(name = "transaction_signRaw")]
+    fn sign_raw(&self, request: WalletRequest, raw_tx: String) -> Result<String>;
+
+    #[rpc(name = "transaction_combineRaw")]
+    fn combine_raw(&self, raw_txs: Vec<String>) -> Result<String>;
+
+    #[rpc(name = "transaction_broadcastRaw")]
+    fn broadcast_raw(&self, request: WalletRequest, raw_tx: String) -> Result<String>;
+
+    #[rpc(name = "transaction_estimateFee")]
+    fn estimate_fee(
+        &self,
+        request: WalletRequest,
+        outputs: Vec<TxOut>,
+        view_keys: Vec<PublicKey>,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+    ) -> Result<Coin>;
 }
 
-pub struct TransactionRpcImpl {
+pub struct TransactionRpcImpl<T: WalletClient> {
+    client: T,
     network_id: u8,
 }
 
-impl TransactionRpcImpl {
-    pub fn new(network_id: u8) -> Self {
-        TransactionRpcImpl { network_id }
+impl<T> TransactionRpcImpl<T>
+where
+    T: WalletClient,
+{
+    pub fn new(client: T, network_id: u8) -> Self {
+        TransactionRpcImpl { client, network_id }
+    }
+
+    fn attributes(&self) -> AttributesBuilder {
+        AttributesBuilder::with_network_id(self.network_id)
     }
 }
 
-impl TransactionRpc for TransactionRpcImpl {
+impl<T> TransactionRpc for TransactionRpcImpl<T>
+where
+    T: WalletClient + 'static,
+{
     fn create_raw(
         &self,
         inputs: Vec<TxoPointer>,
         outputs: Vec<TxOut>,
         view_keys: Vec<PublicKey>,
     ) -> Result<RawTransaction> {
-        let access_policies: BTreeSet<_> = view_keys
-            .iter()
-            .map(|key| TxAccessPolicy {
-                view_key: key.into(),
-                access: TxAccess::AllData,
-            })
-            .collect();
-
-        let attributes =
-            TxAttributes::new_with_access(self.network_id, access_policies.into_iter().collect());
+        let attributes = self
+            .attributes()
+            .view_keys(view_keys)
+            .build()
+            .map_err(to_rpc_error)?;
 
         let tx = Tx {
             inputs,
@@ -73,8 +103,77 @@ impl TransactionRpc for TransactionRpcImpl {
             attributes,
         };
         let tx_id = tx.id();
+        let raw_tx = PartiallySignedTransferTx::new(tx.clone()).to_string();
+
+        Ok(RawTransaction { tx, tx_id, raw_tx })
+    }
+
+    fn sign_raw(&self, request: WalletRequest, raw_tx: String) -> Result<String> {
+        let raw_tx = raw_tx
+            .parse::<PartiallySignedTransferTx>()
+            .map_err(to_rpc_error)?;
+        self.client
+            .sign_raw_transaction(&request.name, &request.enckey, &raw_tx)
+            .map(|signed_tx| signed_tx.to_string())
+            .map_err(to_rpc_error)
+    }
+
+    fn combine_raw(&self, raw_txs: Vec<String>) -> Result<String> {
+        let mut raw_txs = raw_txs
+            .iter()
+            .map(|raw_tx| raw_tx.parse::<PartiallySignedTransferTx>())
+            .collect::<client_common::Result<Vec<_>>>()
+            .map_err(to_rpc_error)?
+            .into_iter();
+        let mut combined = raw_txs
+            .next()
+            .ok_or_else(|| rpc_error_from_string("No raw transaction to combine".to_owned()))?;
+        for raw_tx in raw_txs {
+            combined.combine(raw_tx).map_err(to_rpc_error)?;
+        }
+        Ok(combined.to_string())
+    }
 
-        Ok(RawTransaction { tx, tx_id })
+    fn broadcast_raw(&self, request: WalletRequest, raw_tx: String) -> Result<String> {
+        let (unsigned_tx, witness) = raw_tx
+            .parse::<PartiallySignedTransferTx>()
+            .and_then(|raw_tx| raw_tx.to_unsigned_with_witness())
+            .map_err(to_rpc_error)?;
+        // obfuscated by the enclave, the inputs of the wallet are marked as pending
+        let signed_tx = self
+            .client
+            .import_transaction_witness(&request.name, &request.enckey, &unsigned_tx, witness)
+            .map_err(to_rpc_error)?;
+        self.client
+            .broadcast_signed_transfer_tx(&request.name, &request.enckey, signed_tx)
+            .map(hex::encode)
+            .map_err(to_rpc_error)
+    }
+
+    fn estimate_fee(
+        &self,
+        request: WalletRequest,
+        outputs: Vec<TxOut>,
+        view_keys: Vec<PublicKey>,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+    ) -> Result<Coin> {
+        // the wallet's own view key is always added when sending, it's part of the fee as well
+        let attributes = self
+            .attributes()
+            .view_keys(view_keys)
+            .wallet_view_key(&self.client, &request.name, &request.enckey)
+            .and_then(AttributesBuilder::build)
+            .map_err(to_rpc_error)?;
+
+        self.client
+            .estimate_fee(
+                &request.name,
+                &request.enckey,
+                outputs,
+                attributes,
+                input_selection_strategy,
+            )
+            .map_err(to_rpc_error)
     }
 }
 
@@ -84,13 +183,17 @@ mod test {
     use chain_core::init::address::CroAddress;
     use chain_core::init::coin::Coin;
     use chain_core::init::network::Network;
+    use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
     use chain_core::tx::data::address::ExtendedAddr;
+    use client_common::storage::MemoryStorage;
     use client_common::PrivateKey;
+    use client_core::wallet::DefaultWalletClient;
 
     #[test]
     fn create_raw_flow() {
         let chain_id = hex::decode("AB").unwrap()[0];
-        let transaction_rpc = TransactionRpcImpl::new(chain_id);
+        let wallet_client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let transaction_rpc = TransactionRpcImpl::new(wallet_client, chain_id);
 
         let inputs = vec![TxoPointer::new([0; 32], 0), TxoPointer::new([1; 32], 0)];
 
@@ -146,5 +249,20 @@ mod test {
                 view_key: view_key_2.into(),
                 access: TxAccess::AllData,
             }));
+
+        let raw_tx = raw_transaction
+            .raw_tx
+            .parse::<PartiallySignedTransferTx>()
+            .expect("Returned raw transaction should be decodable");
+        assert_eq!(raw_tx.transaction, raw_transaction.tx);
+        assert!(!raw_tx.is_completed());
+        let combined = transaction_rpc
+            .combine_raw(vec![
+                raw_transaction.raw_tx.clone(),
+                raw_transaction.raw_tx.clone(),
+            ])
+            .expect("combine_raw does not work for the same transaction");
+        assert_eq!(combined, raw_transaction.raw_tx);
+        assert!(transaction_rpc.combine_raw(vec![]).is_err());
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
 
 use jsonrpc_core::Result;
@@ -7,19 +7,55 @@ use secstr::SecUtf8;
 
 use chain_core::init::coin::Coin;
 use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::data::input::{str2txid, TxoPointer};
 use client_common::{PrivateKey, PublicKey, Result as CommonResult, SecKey};
-use client_core::service::WalletInfo;
+use client_core::service::{LockoutStatus, WalletExport, WalletInfo};
 use client_core::transaction_builder::SignedTransferTransaction;
-use client_core::types::{TransactionChange, WalletBalance, WalletKind};
+use client_core::types::{
+    parse_annotations, AddressMetadata, Alert, AlertRule, AnnotationConflict, AnnotationFormat,
+    AnnotationImportReport, BalanceReservation, DevicePairing, DeviceRole, EncryptedWalletEvent,
+    HistoryExportFormat, PairedDevice, PayoutBatch, PendingApproval, RecipientOutput,
+    TransactionChange, TransactionHistory, TransactionHistoryFilter, TransferRecipient,
+    TxAnnotation, WalletBalance, WalletKind,
+};
 use client_core::wallet::{CreateWalletRequest, WalletRequest};
 #[cfg(feature = "experimental")]
 use client_core::MultiSigWalletClient;
-use client_core::{Mnemonic, UnspentTransactions, WalletClient};
+use client_core::{
+    InputSelectionStrategy, Mnemonic, UnspentTransactions, WalletAuditClient, WalletClient,
+    WalletMaintenanceClient, WalletPaymentClient, WalletRekeyClient, WalletSpendControlClient,
+};
 use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
 
 use crate::{rpc_error_from_string, to_rpc_error};
 use client_core::hd_wallet::HardwareKind;
 
+/// Address returned by the address listing RPCs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ListedAddress {
+    /// the bare address, kept as the default so that existing clients are not affected
+    Address(String),
+    /// the address together with its label from the wallet's address book
+    Labelled {
+        address: String,
+        label: Option<String>,
+    },
+}
+
+/// Recipient of a transfer to multiple recipients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipientRequest {
+    /// transfer address of the recipient
+    pub address: String,
+    /// sent amount
+    pub amount: Coin,
+    /// view keys of the recipient (hex encoded)
+    #[serde(default)]
+    pub view_keys: Vec<String>,
+}
+
 #[rpc(server)]
 pub trait WalletRpc: Send + Sync {
     #[rpc(name = "wallet_balance")]
@@ -39,9 +75,25 @@ pub trait WalletRpc: Send + Sync {
     #[rpc(name = "wallet_restoreBasic")]
     fn restore_basic(&self, request: CreateWalletRequest, view_key: SecUtf8) -> Result<SecKey>;
 
+    #[rpc(name = "wallet_createWatchOnly")]
+    fn create_watch_only(
+        &self,
+        request: CreateWalletRequest,
+        view_key: PublicKey,
+        transfer_public_keys: Vec<PublicKey>,
+        staking_public_keys: Vec<PublicKey>,
+    ) -> Result<SecKey>;
+
     #[rpc(name = "wallet_delete")]
     fn delete(&self, request: CreateWalletRequest) -> Result<()>;
 
+    #[rpc(name = "wallet_changePassphrase")]
+    fn change_passphrase(
+        &self,
+        request: CreateWalletRequest,
+        new_passphrase: SecUtf8,
+    ) -> Result<SecKey>;
+
     #[rpc(name = "wallet_createStakingAddress")]
     fn create_staking_address(&self, request: WalletRequest) -> Result<String>;
 
@@ -84,7 +136,8 @@ pub trait WalletRpc: Send + Sync {
         offset: Option<u64>,
         limit: Option<u64>,
         reversed: Option<bool>,
-    ) -> Result<Vec<String>>;
+        with_labels: Option<bool>,
+    ) -> Result<Vec<ListedAddress>>;
 
     #[rpc(name = "wallet_listTransferAddresses")]
     fn list_transfer_addresses(
@@ -93,11 +146,21 @@ pub trait WalletRpc: Send + Sync {
         offset: Option<u64>,
         limit: Option<u64>,
         reversed: Option<bool>,
-    ) -> Result<Vec<String>>;
+        with_labels: Option<bool>,
+    ) -> Result<Vec<ListedAddress>>;
 
     #[rpc(name = "wallet_listUTxO")]
     fn list_utxo(&self, request: WalletRequest) -> Result<UnspentTransactions>;
 
+    #[rpc(name = "wallet_freezeUTxO")]
+    fn freeze_utxo(&self, request: WalletRequest, utxo: TxoPointer) -> Result<()>;
+
+    #[rpc(name = "wallet_unfreezeUTxO")]
+    fn unfreeze_utxo(&self, request: WalletRequest, utxo: TxoPointer) -> Result<()>;
+
+    #[rpc(name = "wallet_listFrozenUTxO")]
+    fn list_frozen_utxo(&self, request: WalletRequest) -> Result<Vec<TxoPointer>>;
+
     #[rpc(name = "wallet_sendToAddress")]
     fn send_to_address(
         &self,
@@ -105,8 +168,54 @@ pub trait WalletRpc: Send + Sync {
         to_address: String,
         amount: Coin,
         view_keys: Vec<String>,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+        only_from_addresses: Option<Vec<String>>,
+    ) -> Result<String>;
+
+    #[rpc(name = "wallet_sendToMany")]
+    fn send_to_many(
+        &self,
+        request: WalletRequest,
+        recipients: Vec<RecipientRequest>,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+    ) -> Result<Vec<RecipientOutput>>;
+
+    #[rpc(name = "wallet_internalTransfer")]
+    fn internal_transfer(
+        &self,
+        from_request: WalletRequest,
+        to_request: WalletRequest,
+        amount: Coin,
     ) -> Result<String>;
 
+    #[rpc(name = "wallet_sendBatch")]
+    fn send_batch(
+        &self,
+        request: WalletRequest,
+        batch_name: String,
+        recipients: Vec<(String, Coin)>,
+        view_keys: Vec<String>,
+    ) -> Result<PayoutBatch>;
+
+    #[rpc(name = "wallet_payoutBatch")]
+    fn payout_batch(
+        &self,
+        request: WalletRequest,
+        batch_name: String,
+    ) -> Result<Option<PayoutBatch>>;
+
+    #[rpc(name = "wallet_setSpendApproval")]
+    fn set_spend_approval(&self, request: CreateWalletRequest, required: bool) -> Result<()>;
+
+    #[rpc(name = "wallet_pendingApprovals")]
+    fn pending_approvals(&self, request: WalletRequest) -> Result<Vec<PendingApproval>>;
+
+    #[rpc(name = "wallet_approve")]
+    fn approve(&self, request: CreateWalletRequest, txid: String) -> Result<String>;
+
+    #[rpc(name = "wallet_reject")]
+    fn reject(&self, request: CreateWalletRequest, txid: String) -> Result<()>;
+
     #[rpc(name = "wallet_buildRawTransferTx")]
     fn build_raw_transfer_tx(
         &self,
@@ -132,6 +241,24 @@ pub trait WalletRpc: Send + Sync {
         reversed: bool,
     ) -> Result<Vec<TransactionChange>>;
 
+    #[rpc(name = "wallet_exportHistory")]
+    fn export_history(
+        &self,
+        request: WalletRequest,
+        format: HistoryExportFormat,
+        filter: Option<TransactionHistoryFilter>,
+    ) -> Result<String>;
+
+    #[rpc(name = "wallet_transactionHistory")]
+    fn transaction_history(
+        &self,
+        request: WalletRequest,
+        filter: TransactionHistoryFilter,
+        offset: usize,
+        limit: usize,
+        reversed: bool,
+    ) -> Result<TransactionHistory>;
+
     #[rpc(name = "wallet_exportTransaction")]
     fn export_plain_tx(&self, request: WalletRequest, txid: String) -> Result<String>;
 
@@ -141,11 +268,138 @@ pub trait WalletRpc: Send + Sync {
     #[rpc(name = "wallet_getEncKey")]
     fn get_enc_key(&self, request: CreateWalletRequest) -> Result<SecKey>;
 
+    #[rpc(name = "wallet_lockoutStatus")]
+    fn lockout_status(&self, name: String) -> Result<LockoutStatus>;
+
     #[rpc(name = "wallet_export")]
     fn export(&self, request: WalletRequest) -> Result<WalletInfo>;
 
     #[rpc(name = "wallet_import")]
     fn import(&self, request: CreateWalletRequest, wallet_info: WalletInfo) -> Result<SecKey>;
+
+    #[rpc(name = "wallet_exportSnapshot")]
+    fn export_snapshot(
+        &self,
+        request: WalletRequest,
+        include_hd_seed: bool,
+    ) -> Result<WalletExport>;
+
+    #[rpc(name = "wallet_importSnapshot")]
+    fn import_snapshot(
+        &self,
+        request: CreateWalletRequest,
+        snapshot: WalletExport,
+    ) -> Result<SecKey>;
+
+    #[rpc(name = "wallet_setAlertRules")]
+    fn set_alert_rules(&self, request: WalletRequest, rules: Vec<AlertRule>) -> Result<()>;
+
+    #[rpc(name = "wallet_alertRules")]
+    fn alert_rules(&self, request: WalletRequest) -> Result<Vec<AlertRule>>;
+
+    #[rpc(name = "wallet_alerts")]
+    fn alerts(&self, request: WalletRequest, offset: usize, limit: usize) -> Result<Vec<Alert>>;
+
+    #[rpc(name = "wallet_annotations")]
+    fn annotations(&self, request: WalletRequest) -> Result<BTreeMap<String, TxAnnotation>>;
+
+    #[rpc(name = "wallet_setAnnotation")]
+    fn set_annotation(
+        &self,
+        request: WalletRequest,
+        txid: String,
+        annotation: TxAnnotation,
+    ) -> Result<()>;
+
+    #[rpc(name = "wallet_setTransactionMemo")]
+    fn set_transaction_memo(
+        &self,
+        request: WalletRequest,
+        txid: String,
+        memo: String,
+    ) -> Result<()>;
+
+    #[rpc(name = "wallet_importAnnotations")]
+    fn import_annotations(
+        &self,
+        request: WalletRequest,
+        format: AnnotationFormat,
+        content: String,
+        conflict: AnnotationConflict,
+    ) -> Result<AnnotationImportReport>;
+
+    #[rpc(name = "wallet_addressBook")]
+    fn address_book(&self, request: WalletRequest) -> Result<BTreeMap<String, AddressMetadata>>;
+
+    #[rpc(name = "wallet_getAddressMetadata")]
+    fn get_address_metadata(
+        &self,
+        request: WalletRequest,
+        address: String,
+    ) -> Result<Option<AddressMetadata>>;
+
+    #[rpc(name = "wallet_setAddressLabel")]
+    fn set_address_label(
+        &self,
+        request: WalletRequest,
+        address: String,
+        label: String,
+    ) -> Result<()>;
+
+    #[rpc(name = "wallet_devicePairing")]
+    fn device_pairing(&self, request: WalletRequest, device_name: String) -> Result<DevicePairing>;
+
+    #[rpc(name = "wallet_pairDevice")]
+    fn pair_device(
+        &self,
+        request: WalletRequest,
+        pairing: DevicePairing,
+        role: DeviceRole,
+    ) -> Result<()>;
+
+    #[rpc(name = "wallet_unpairDevice")]
+    fn unpair_device(&self, request: WalletRequest, device_name: String) -> Result<()>;
+
+    #[rpc(name = "wallet_pairedDevices")]
+    fn paired_devices(&self, request: WalletRequest) -> Result<Vec<PairedDevice>>;
+
+    #[rpc(name = "wallet_deviceEvents")]
+    fn device_events(
+        &self,
+        request: WalletRequest,
+        device_name: String,
+        after: u64,
+    ) -> Result<Vec<EncryptedWalletEvent>>;
+
+    #[rpc(name = "wallet_importDeviceEvents")]
+    fn import_device_events(
+        &self,
+        request: WalletRequest,
+        device_name: String,
+        events: Vec<EncryptedWalletEvent>,
+    ) -> Result<u64>;
+
+    #[rpc(name = "wallet_reserveBalance")]
+    fn reserve_balance(
+        &self,
+        request: WalletRequest,
+        amount: Coin,
+        ttl: u64,
+        tag: String,
+    ) -> Result<BalanceReservation>;
+
+    #[rpc(name = "wallet_reservations")]
+    fn reservations(&self, request: WalletRequest) -> Result<Vec<BalanceReservation>>;
+
+    #[rpc(name = "wallet_releaseReservation")]
+    fn release_reservation(&self, request: WalletRequest, id: u64) -> Result<BalanceReservation>;
+
+    #[rpc(name = "wallet_releaseReservations")]
+    fn release_reservations(
+        &self,
+        request: WalletRequest,
+        tag: String,
+    ) -> Result<Vec<BalanceReservation>>;
 }
 
 pub struct WalletRpcImpl<T>
@@ -158,16 +412,48 @@ where
 
 impl<T> WalletRpcImpl<T>
 where
-    T: WalletClient,
+    T: WalletAuditClient,
 {
     pub fn new(client: T, network_id: u8) -> Self {
         WalletRpcImpl { client, network_id }
     }
+
+    fn list_addresses<A: ToString>(
+        &self,
+        request: &WalletRequest,
+        addresses: impl IntoIterator<Item = A>,
+        with_labels: bool,
+    ) -> Result<Vec<ListedAddress>> {
+        if !with_labels {
+            return Ok(addresses
+                .into_iter()
+                .map(|address| ListedAddress::Address(address.to_string()))
+                .collect());
+        }
+        let mut address_book = self
+            .client
+            .address_book(&request.name, &request.enckey)
+            .map_err(to_rpc_error)?;
+        Ok(addresses
+            .into_iter()
+            .map(|address| {
+                let address = address.to_string();
+                let label = address_book.remove(&address).map(|metadata| metadata.label);
+                ListedAddress::Labelled { address, label }
+            })
+            .collect())
+    }
 }
 
 impl<T> WalletRpc for WalletRpcImpl<T>
 where
-    T: WalletClient + 'static,
+    T: WalletClient
+        + WalletAuditClient
+        + WalletMaintenanceClient
+        + WalletPaymentClient
+        + WalletRekeyClient
+        + WalletSpendControlClient
+        + 'static,
 {
     fn balance(&self, request: WalletRequest) -> Result<WalletBalance> {
         self.client
@@ -242,6 +528,27 @@ where
         Ok(enckey)
     }
 
+    fn create_watch_only(
+        &self,
+        request: CreateWalletRequest,
+        view_key: PublicKey,
+        transfer_public_keys: Vec<PublicKey>,
+        staking_public_keys: Vec<PublicKey>,
+    ) -> Result<SecKey> {
+        let enckey = self
+            .client
+            .new_watch_only_wallet(
+                &request.name,
+                &request.passphrase,
+                &view_key,
+                &transfer_public_keys,
+                &staking_public_keys,
+            )
+            .map_err(to_rpc_error)?;
+        self.client.flush_database().map_err(to_rpc_error)?;
+        Ok(enckey)
+    }
+
     fn delete(&self, request: CreateWalletRequest) -> Result<()> {
         let ret = self
             .client
@@ -251,6 +558,19 @@ where
         ret
     }
 
+    fn change_passphrase(
+        &self,
+        request: CreateWalletRequest,
+        new_passphrase: SecUtf8,
+    ) -> Result<SecKey> {
+        let ret = self
+            .client
+            .change_passphrase(&request.name, &request.passphrase, &new_passphrase)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
     fn create_staking_address(&self, request: WalletRequest) -> Result<String> {
         let ret = self
             .client
@@ -377,8 +697,10 @@ where
         offset: Option<u64>,
         limit: Option<u64>,
         reversed: Option<bool>,
-    ) -> Result<Vec<String>> {
-        self.client
+        with_labels: Option<bool>,
+    ) -> Result<Vec<ListedAddress>> {
+        let addresses = self
+            .client
             .staking_addresses(
                 &request.name,
                 &request.enckey,
@@ -386,8 +708,8 @@ where
                 std::cmp::max(1, std::cmp::min(limit.unwrap_or(1000), 10000)),
                 reversed.unwrap_or(false),
             )
-            .map(|addresses| addresses.iter().map(ToString::to_string).collect())
-            .map_err(to_rpc_error)
+            .map_err(to_rpc_error)?;
+        self.list_addresses(&request, addresses, with_labels.unwrap_or(false))
     }
 
     fn list_transfer_addresses(
@@ -396,8 +718,10 @@ where
         offset: Option<u64>,
         limit: Option<u64>,
         reversed: Option<bool>,
-    ) -> Result<Vec<String>> {
-        self.client
+        with_labels: Option<bool>,
+    ) -> Result<Vec<ListedAddress>> {
+        let addresses = self
+            .client
             .transfer_addresses(
                 &request.name,
                 &request.enckey,
@@ -405,8 +729,8 @@ where
                 std::cmp::max(1, std::cmp::min(limit.unwrap_or(1000), 10000)),
                 reversed.unwrap_or(false),
             )
-            .map(|addresses| addresses.iter().map(ToString::to_string).collect())
-            .map_err(to_rpc_error)
+            .map_err(to_rpc_error)?;
+        self.list_addresses(&request, addresses, with_labels.unwrap_or(false))
     }
 
     fn list_utxo(&self, request: WalletRequest) -> Result<UnspentTransactions> {
@@ -415,16 +739,51 @@ where
             .map_err(to_rpc_error)
     }
 
+    fn freeze_utxo(&self, request: WalletRequest, utxo: TxoPointer) -> Result<()> {
+        let ret = self
+            .client
+            .freeze_utxo(&request.name, &request.enckey, &utxo)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn unfreeze_utxo(&self, request: WalletRequest, utxo: TxoPointer) -> Result<()> {
+        let ret = self
+            .client
+            .unfreeze_utxo(&request.name, &request.enckey, &utxo)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn list_frozen_utxo(&self, request: WalletRequest) -> Result<Vec<TxoPointer>> {
+        self.client
+            .frozen_utxos(&request.name, &request.enckey)
+            .map_err(to_rpc_error)
+    }
+
     fn send_to_address(
         &self,
         request: WalletRequest,
         to_address: String,
         amount: Coin,
         view_keys: Vec<String>,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+        only_from_addresses: Option<Vec<String>>,
     ) -> Result<String> {
         let address = to_address
             .parse::<ExtendedAddr>()
             .map_err(|err| rpc_error_from_string(format!("{}", err)))?;
+        let only_from_addresses = only_from_addresses
+            .map(|addresses| {
+                addresses
+                    .iter()
+                    .map(|address| address.parse::<ExtendedAddr>())
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|err| rpc_error_from_string(format!("{}", err)))
+            })
+            .transpose()?;
         let mut view_keys = view_keys
             .iter()
             .map(|view_key| PublicKey::from_str(view_key))
@@ -439,12 +798,156 @@ where
                 address,
                 &mut view_keys,
                 self.network_id,
+                input_selection_strategy,
+                only_from_addresses.as_deref(),
+            )
+            .map_err(to_rpc_error)?;
+        self.client.flush_database().map_err(to_rpc_error)?;
+        Ok(hex::encode(tx_id))
+    }
+
+    fn send_to_many(
+        &self,
+        request: WalletRequest,
+        recipients: Vec<RecipientRequest>,
+        input_selection_strategy: Option<InputSelectionStrategy>,
+    ) -> Result<Vec<RecipientOutput>> {
+        let recipients = recipients
+            .into_iter()
+            .map(|recipient| {
+                let address = recipient
+                    .address
+                    .parse::<ExtendedAddr>()
+                    .map_err(|err| rpc_error_from_string(format!("{}", err)))?;
+                let view_keys = recipient
+                    .view_keys
+                    .iter()
+                    .map(|view_key| PublicKey::from_str(view_key))
+                    .collect::<CommonResult<BTreeSet<PublicKey>>>()
+                    .map_err(to_rpc_error)?;
+                Ok(TransferRecipient {
+                    address,
+                    amount: recipient.amount,
+                    view_keys,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let ret = self
+            .client
+            .send_to_many(
+                &request.name,
+                &request.enckey,
+                recipients,
+                self.network_id,
+                input_selection_strategy,
+            )
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn internal_transfer(
+        &self,
+        from_request: WalletRequest,
+        to_request: WalletRequest,
+        amount: Coin,
+    ) -> Result<String> {
+        let tx_id = self
+            .client
+            .internal_transfer(
+                &from_request.name,
+                &from_request.enckey,
+                &to_request.name,
+                &to_request.enckey,
+                amount,
+                self.network_id,
             )
             .map_err(to_rpc_error)?;
         self.client.flush_database().map_err(to_rpc_error)?;
         Ok(hex::encode(tx_id))
     }
 
+    fn send_batch(
+        &self,
+        request: WalletRequest,
+        batch_name: String,
+        recipients: Vec<(String, Coin)>,
+        view_keys: Vec<String>,
+    ) -> Result<PayoutBatch> {
+        let recipients = recipients
+            .into_iter()
+            .map(|(address, amount)| {
+                address
+                    .parse::<ExtendedAddr>()
+                    .map(|address| (address, amount))
+                    .map_err(|err| rpc_error_from_string(format!("{}", err)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut view_keys = view_keys
+            .iter()
+            .map(|view_key| PublicKey::from_str(view_key))
+            .collect::<CommonResult<BTreeSet<PublicKey>>>()
+            .map_err(to_rpc_error)?;
+        let ret = self
+            .client
+            .send_batch(
+                &request.name,
+                &request.enckey,
+                &batch_name,
+                recipients,
+                &mut view_keys,
+                self.network_id,
+            )
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn payout_batch(
+        &self,
+        request: WalletRequest,
+        batch_name: String,
+    ) -> Result<Option<PayoutBatch>> {
+        self.client
+            .payout_batch(&request.name, &request.enckey, &batch_name)
+            .map_err(to_rpc_error)
+    }
+
+    fn set_spend_approval(&self, request: CreateWalletRequest, required: bool) -> Result<()> {
+        let ret = self
+            .client
+            .set_spend_approval(&request.name, &request.passphrase, required)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn pending_approvals(&self, request: WalletRequest) -> Result<Vec<PendingApproval>> {
+        self.client
+            .pending_approvals(&request.name, &request.enckey)
+            .map_err(to_rpc_error)
+    }
+
+    fn approve(&self, request: CreateWalletRequest, txid: String) -> Result<String> {
+        let txid = str2txid(&txid).map_err(|err| rpc_error_from_string(format!("{}", err)))?;
+        let tx_id = self
+            .client
+            .approve_transaction(&request.name, &request.passphrase, txid)
+            .map_err(to_rpc_error)?;
+        self.client.flush_database().map_err(to_rpc_error)?;
+        Ok(hex::encode(tx_id))
+    }
+
+    fn reject(&self, request: CreateWalletRequest, txid: String) -> Result<()> {
+        let txid = str2txid(&txid).map_err(|err| rpc_error_from_string(format!("{}", err)))?;
+        let ret = self
+            .client
+            .reject_transaction(&request.name, &request.passphrase, txid)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
     fn build_raw_transfer_tx(
         &self,
         request: WalletRequest,
@@ -518,8 +1021,43 @@ where
         limit: usize,
         reversed: bool,
     ) -> Result<Vec<TransactionChange>> {
+        self.transaction_history(request, Default::default(), offset, limit, reversed)
+            .map(|history| history.transactions)
+    }
+
+    fn export_history(
+        &self,
+        request: WalletRequest,
+        format: HistoryExportFormat,
+        filter: Option<TransactionHistoryFilter>,
+    ) -> Result<String> {
+        self.client
+            .export_history(
+                &request.name,
+                &request.enckey,
+                format,
+                filter.unwrap_or_default(),
+            )
+            .map_err(to_rpc_error)
+    }
+
+    fn transaction_history(
+        &self,
+        request: WalletRequest,
+        filter: TransactionHistoryFilter,
+        offset: usize,
+        limit: usize,
+        reversed: bool,
+    ) -> Result<TransactionHistory> {
         self.client
-            .history(&request.name, &request.enckey, offset, limit, reversed)
+            .history(
+                &request.name,
+                &request.enckey,
+                filter,
+                offset,
+                limit,
+                reversed,
+            )
             .map_err(to_rpc_error)
     }
 
@@ -529,6 +1067,12 @@ where
             .map_err(to_rpc_error)
     }
 
+    fn lockout_status(&self, name: String) -> Result<LockoutStatus> {
+        self.client
+            .passphrase_lockout_status(&name)
+            .map_err(to_rpc_error)
+    }
+
     fn export(&self, request: WalletRequest) -> Result<WalletInfo> {
         let wallet_info = self
             .client
@@ -547,6 +1091,240 @@ where
         self.client.flush_database().map_err(to_rpc_error)?;
         ret
     }
+
+    fn export_snapshot(
+        &self,
+        request: WalletRequest,
+        include_hd_seed: bool,
+    ) -> Result<WalletExport> {
+        self.client
+            .export_wallet_snapshot(&request.name, &request.enckey, include_hd_seed)
+            .map_err(to_rpc_error)
+    }
+
+    fn import_snapshot(
+        &self,
+        request: CreateWalletRequest,
+        snapshot: WalletExport,
+    ) -> Result<SecKey> {
+        self.client
+            .import_wallet_snapshot(&request.name, &request.passphrase, snapshot)
+            .map_err(to_rpc_error)
+    }
+
+    fn set_alert_rules(&self, request: WalletRequest, rules: Vec<AlertRule>) -> Result<()> {
+        let ret = self
+            .client
+            .set_alert_rules(&request.name, &request.enckey, rules)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn alert_rules(&self, request: WalletRequest) -> Result<Vec<AlertRule>> {
+        self.client
+            .alert_rules(&request.name, &request.enckey)
+            .map_err(to_rpc_error)
+    }
+
+    fn alerts(&self, request: WalletRequest, offset: usize, limit: usize) -> Result<Vec<Alert>> {
+        self.client
+            .alerts(&request.name, &request.enckey, offset, limit)
+            .map_err(to_rpc_error)
+    }
+
+    fn annotations(&self, request: WalletRequest) -> Result<BTreeMap<String, TxAnnotation>> {
+        let annotations = self
+            .client
+            .annotations(&request.name, &request.enckey)
+            .map_err(to_rpc_error)?;
+        Ok(annotations
+            .into_iter()
+            .map(|(txid, annotation)| (hex::encode(txid), annotation))
+            .collect())
+    }
+
+    fn set_annotation(
+        &self,
+        request: WalletRequest,
+        txid: String,
+        annotation: TxAnnotation,
+    ) -> Result<()> {
+        let txid = str2txid(&txid).map_err(|err| rpc_error_from_string(format!("{}", err)))?;
+        let ret = self
+            .client
+            .set_annotation(&request.name, &request.enckey, txid, annotation)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn set_transaction_memo(
+        &self,
+        request: WalletRequest,
+        txid: String,
+        memo: String,
+    ) -> Result<()> {
+        let txid = str2txid(&txid).map_err(|err| rpc_error_from_string(format!("{}", err)))?;
+        let ret = self
+            .client
+            .set_transaction_memo(&request.name, &request.enckey, txid, memo)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn import_annotations(
+        &self,
+        request: WalletRequest,
+        format: AnnotationFormat,
+        content: String,
+        conflict: AnnotationConflict,
+    ) -> Result<AnnotationImportReport> {
+        let entries = parse_annotations(format, &content).map_err(to_rpc_error)?;
+        let ret = self
+            .client
+            .import_annotations(&request.name, &request.enckey, entries, conflict)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn address_book(&self, request: WalletRequest) -> Result<BTreeMap<String, AddressMetadata>> {
+        self.client
+            .address_book(&request.name, &request.enckey)
+            .map_err(to_rpc_error)
+    }
+
+    fn get_address_metadata(
+        &self,
+        request: WalletRequest,
+        address: String,
+    ) -> Result<Option<AddressMetadata>> {
+        self.client
+            .get_address_metadata(&request.name, &request.enckey, &address)
+            .map_err(to_rpc_error)
+    }
+
+    fn set_address_label(
+        &self,
+        request: WalletRequest,
+        address: String,
+        label: String,
+    ) -> Result<()> {
+        let ret = self
+            .client
+            .set_address_label(&request.name, &request.enckey, &address, &label)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn device_pairing(&self, request: WalletRequest, device_name: String) -> Result<DevicePairing> {
+        let ret = self
+            .client
+            .device_pairing(&request.name, &request.enckey, &device_name)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn pair_device(
+        &self,
+        request: WalletRequest,
+        pairing: DevicePairing,
+        role: DeviceRole,
+    ) -> Result<()> {
+        let ret = self
+            .client
+            .pair_device(&request.name, &request.enckey, pairing, role)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn unpair_device(&self, request: WalletRequest, device_name: String) -> Result<()> {
+        let ret = self
+            .client
+            .unpair_device(&request.name, &request.enckey, &device_name)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn paired_devices(&self, request: WalletRequest) -> Result<Vec<PairedDevice>> {
+        self.client
+            .paired_devices(&request.name, &request.enckey)
+            .map_err(to_rpc_error)
+    }
+
+    fn device_events(
+        &self,
+        request: WalletRequest,
+        device_name: String,
+        after: u64,
+    ) -> Result<Vec<EncryptedWalletEvent>> {
+        let ret = self
+            .client
+            .device_events(&request.name, &request.enckey, &device_name, after)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn import_device_events(
+        &self,
+        request: WalletRequest,
+        device_name: String,
+        events: Vec<EncryptedWalletEvent>,
+    ) -> Result<u64> {
+        self.client
+            .import_device_events(&request.name, &request.enckey, &device_name, events)
+            .map_err(to_rpc_error)
+    }
+
+    fn reserve_balance(
+        &self,
+        request: WalletRequest,
+        amount: Coin,
+        ttl: u64,
+        tag: String,
+    ) -> Result<BalanceReservation> {
+        let ret = self
+            .client
+            .reserve_balance(&request.name, &request.enckey, amount, ttl, tag)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn reservations(&self, request: WalletRequest) -> Result<Vec<BalanceReservation>> {
+        self.client
+            .reservations(&request.name, &request.enckey)
+            .map_err(to_rpc_error)
+    }
+
+    fn release_reservation(&self, request: WalletRequest, id: u64) -> Result<BalanceReservation> {
+        let ret = self
+            .client
+            .release_reservation(&request.name, &request.enckey, id)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
+
+    fn release_reservations(
+        &self,
+        request: WalletRequest,
+        tag: String,
+    ) -> Result<Vec<BalanceReservation>> {
+        let ret = self
+            .client
+            .release_reservations_by_tag(&request.name, &request.enckey, &tag)
+            .map_err(to_rpc_error);
+        self.client.flush_database().map_err(to_rpc_error)?;
+        ret
+    }
 }
 
 #[cfg(test)]
@@ -576,6 +1354,7 @@ pub mod tests {
     use client_core::service::HwKeyService;
     use client_core::signer::WalletSignerManager;
     use client_core::transaction_builder::DefaultWalletTransactionBuilder;
+    use client_core::types::HISTORY_EXPORT_COLUMNS;
     use client_core::wallet::DefaultWalletClient;
 
     #[derive(Default, Clone)]
@@ -741,6 +1520,17 @@ pub mod tests {
             unreachable!("broadcast_transaction")
         }
 
+        fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+            &self,
+            _transactions: T,
+        ) -> CommonResult<Vec<CommonResult<BroadcastTxResponse>>> {
+            unreachable!("broadcast_transaction_batch")
+        }
+
+        fn unconfirmed_txs(&self, _limit: u64) -> CommonResult<UnconfirmedTxsResponse> {
+            unreachable!("unconfirmed_txs")
+        }
+
         fn query(
             &self,
             _path: &str,
@@ -823,14 +1613,14 @@ pub mod tests {
             assert_eq!(
                 1,
                 wallet_rpc
-                    .list_transfer_addresses(wallet_request.clone(), None, None, None)
+                    .list_transfer_addresses(wallet_request.clone(), None, None, None, None)
                     .unwrap()
                     .len()
             );
             assert_eq!(
                 1,
                 wallet_rpc
-                    .list_staking_addresses(wallet_request.clone(), None, None, None)
+                    .list_staking_addresses(wallet_request.clone(), None, None, None, None)
                     .unwrap()
                     .len()
             );
@@ -848,7 +1638,7 @@ pub mod tests {
         assert_eq!(
             1,
             wallet_rpc
-                .list_staking_addresses(wallet_request.clone(), None, None, None)
+                .list_staking_addresses(wallet_request.clone(), None, None, None, None)
                 .unwrap()
                 .len()
         );
@@ -860,12 +1650,61 @@ pub mod tests {
         assert_eq!(
             2,
             wallet_rpc
-                .list_staking_addresses(wallet_request.clone(), None, None, None)
+                .list_staking_addresses(wallet_request.clone(), None, None, None, None)
                 .unwrap()
                 .len()
         );
     }
 
+    #[test]
+    fn change_passphrase_should_work() {
+        let wallet_rpc = setup_wallet_rpc();
+        let (create_request, wallet_request) = create_wallet_request("Default", "123456");
+        let (new_create_request, new_wallet_request) = create_wallet_request("Default", "654321");
+
+        wallet_rpc
+            .create(create_request.clone(), WalletKind::Basic, None)
+            .unwrap();
+        let view_key = wallet_rpc
+            .get_view_key(wallet_request.clone(), false)
+            .unwrap();
+
+        assert!(wallet_rpc
+            .change_passphrase(new_create_request.clone(), SecUtf8::from("654321"))
+            .is_err());
+        let enckey = wallet_rpc
+            .change_passphrase(create_request, SecUtf8::from("654321"))
+            .unwrap();
+
+        assert_eq!(new_wallet_request.enckey, enckey);
+        assert!(wallet_rpc.get_view_key(wallet_request, false).is_err());
+        assert_eq!(
+            view_key,
+            wallet_rpc.get_view_key(new_wallet_request, false).unwrap()
+        );
+    }
+
+    #[test]
+    fn export_history_of_new_wallet_should_be_empty() {
+        let wallet_rpc = setup_wallet_rpc();
+        let (create_request, wallet_request) = create_wallet_request("Default", "123456");
+
+        wallet_rpc
+            .create(create_request, WalletKind::Basic, None)
+            .unwrap();
+
+        let csv = wallet_rpc
+            .export_history(wallet_request.clone(), HistoryExportFormat::Csv, None)
+            .unwrap();
+        assert_eq!(format!("{}\r\n", HISTORY_EXPORT_COLUMNS.join(",")), csv);
+        assert_eq!(
+            "",
+            wallet_rpc
+                .export_history(wallet_request, HistoryExportFormat::JsonLines, None)
+                .unwrap()
+        );
+    }
+
     #[test]
     fn create_transfer_address_should_work() {
         let wallet_rpc = setup_wallet_rpc();
@@ -878,7 +1717,7 @@ pub mod tests {
         assert_eq!(
             1,
             wallet_rpc
-                .list_transfer_addresses(wallet_request.clone(), None, None, None)
+                .list_transfer_addresses(wallet_request.clone(), None, None, None, None)
                 .unwrap()
                 .len()
         );
@@ -890,12 +1729,65 @@ pub mod tests {
         assert_eq!(
             2,
             wallet_rpc
-                .list_transfer_addresses(wallet_request.clone(), None, None, None)
+                .list_transfer_addresses(wallet_request.clone(), None, None, None, None)
                 .unwrap()
                 .len()
         );
     }
 
+    #[test]
+    fn set_address_label_should_be_listed() {
+        let wallet_rpc = setup_wallet_rpc();
+        let (create_request, wallet_request) = create_wallet_request("Default", "123456");
+
+        wallet_rpc
+            .create(create_request, WalletKind::Basic, None)
+            .unwrap();
+        let address = match wallet_rpc
+            .list_transfer_addresses(wallet_request.clone(), None, None, None, None)
+            .unwrap()
+            .remove(0)
+        {
+            ListedAddress::Address(address) => address,
+            other => panic!("unexpected listed address: {:?}", other),
+        };
+
+        assert!(wallet_rpc
+            .set_address_label(wallet_request.clone(), "invalid".to_owned(), "x".to_owned())
+            .is_err());
+        wallet_rpc
+            .set_address_label(
+                wallet_request.clone(),
+                address.clone(),
+                "savings".to_owned(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            Some("savings".to_owned()),
+            wallet_rpc
+                .get_address_metadata(wallet_request.clone(), address.clone())
+                .unwrap()
+                .map(|metadata| metadata.label)
+        );
+        assert_eq!(
+            vec![ListedAddress::Labelled {
+                address,
+                label: Some("savings".to_owned()),
+            }],
+            wallet_rpc
+                .list_transfer_addresses(wallet_request.clone(), None, None, None, Some(true))
+                .unwrap()
+        );
+        match &wallet_rpc
+            .list_staking_addresses(wallet_request, None, None, None, Some(true))
+            .unwrap()[0]
+        {
+            ListedAddress::Labelled { label, .. } => assert_eq!(&None, label),
+            other => panic!("unexpected listed address: {:?}", other),
+        }
+    }
+
     #[test]
     fn get_view_key_should_return_public_key() {
         let wallet_rpc = setup_wallet_rpc();
@@ -922,12 +1814,12 @@ pub mod tests {
             .create(create_request.clone(), WalletKind::Basic, None)
             .unwrap();
         let old_staking_address = wallet_rpc
-            .list_staking_addresses(wallet_request.clone(), None, None, None)
+            .list_staking_addresses(wallet_request.clone(), None, None, None, None)
             .unwrap()[0]
             .clone();
 
         let old_transfer_address = wallet_rpc
-            .list_transfer_addresses(wallet_request.clone(), None, None, None)
+            .list_transfer_addresses(wallet_request.clone(), None, None, None, None)
             .unwrap()[0]
             .clone();
         let old_enckey = wallet_rpc.get_enc_key(create_request.clone()).unwrap();
@@ -940,11 +1832,11 @@ pub mod tests {
             .unwrap();
 
         let new_staking_address = wallet_rpc
-            .list_staking_addresses(wallet_request.clone(), None, None, None)
+            .list_staking_addresses(wallet_request.clone(), None, None, None, None)
             .unwrap()[0]
             .clone();
         let new_transfer_address = wallet_rpc
-            .list_transfer_addresses(wallet_request.clone(), None, None, None)
+            .list_transfer_addresses(wallet_request.clone(), None, None, None, None)
             .unwrap()[0]
             .clone();
         assert_eq!(old_transfer_address, new_transfer_address);
@@ -1103,7 +1995,45 @@ pub mod tests {
             addrs[0].clone(),
             Coin::from(1_0000u32),
             vec![viewkey],
+            None,
+            None,
         );
         assert!(send_result.is_err());
     }
+
+    #[test]
+    fn send_to_many_should_reject_invalid_recipients() {
+        let wallet_rpc = setup_wallet_rpc();
+        let (create_request, wallet_request) = create_wallet_request("Default", "123456");
+
+        wallet_rpc
+            .create(create_request, WalletKind::Basic, None)
+            .unwrap();
+        let address = wallet_rpc
+            .create_transfer_address(wallet_request.clone())
+            .unwrap();
+
+        let recipient = |address: &str, view_keys: Vec<String>| RecipientRequest {
+            address: address.to_owned(),
+            amount: Coin::from(1_0000u32),
+            view_keys,
+        };
+        assert!(wallet_rpc
+            .send_to_many(wallet_request.clone(), vec![], None)
+            .is_err());
+        assert!(wallet_rpc
+            .send_to_many(
+                wallet_request.clone(),
+                vec![recipient("dcro1invalid", vec![])],
+                None
+            )
+            .is_err());
+        assert!(wallet_rpc
+            .send_to_many(
+                wallet_request,
+                vec![recipient(&address, vec!["invalid".to_owned()])],
+                None
+            )
+            .is_err());
+    }
 }
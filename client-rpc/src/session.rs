@@ -0,0 +1,211 @@
+//! Wallet sessions
+//!
+//! `wallet_unlock` verifies the passphrase of a wallet once and returns a session token, which is
+//! accepted in place of the enckey (the `enckey` / `auth_token` field of a wallet request) until
+//! the session expires or is ended by `wallet_lock`. The enckeys of the sessions are only kept in
+//! memory (`SecKey` zeroes them on drop); `SessionMiddleware` substitutes them for the tokens
+//! before the requests are dispatched.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use jsonrpc_core::futures::future::Either;
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::middleware::{Middleware, NoopCallFuture, NoopFuture};
+use jsonrpc_core::{Call, Metadata, Output, Params};
+use serde_json::Value;
+
+use client_common::{Clock, SecKey, SystemClock};
+
+/// Fields of wallet requests holding the enckey
+const ENCKEY_FIELDS: &[&str] = &["enckey", "auth_token"];
+
+/// Number of random bytes of a session token
+const TOKEN_SIZE: usize = 32;
+
+struct Session {
+    name: String,
+    enckey: SecKey,
+    expires_at: SystemTime,
+}
+
+/// Unlocked wallet sessions, clones share the sessions
+#[derive(Clone, Default)]
+pub struct Sessions<K = SystemClock>
+where
+    K: Clock,
+{
+    sessions: Arc<Mutex<HashMap<String, Session>>>,
+    clock: K,
+}
+
+impl<K> Sessions<K>
+where
+    K: Clock,
+{
+    /// Creates an empty set of sessions expiring according to given time source
+    pub fn with_clock(clock: K) -> Self {
+        Sessions {
+            sessions: Default::default(),
+            clock,
+        }
+    }
+
+    /// Starts a session of the wallet lasting `ttl`, returns its token
+    pub fn unlock(&self, name: &str, enckey: SecKey, ttl: Duration) -> String {
+        let token = hex::encode(rand::random::<[u8; TOKEN_SIZE]>());
+        let session = Session {
+            name: name.to_owned(),
+            enckey,
+            expires_at: self.clock.now() + ttl,
+        };
+        let mut sessions = self.sessions.lock().expect("lock sessions");
+        sessions.insert(token.clone(), session);
+        token
+    }
+
+    /// Ends the session, returns false if it doesn't exist (or already expired)
+    pub fn lock(&self, token: &str) -> bool {
+        let mut sessions = self.sessions.lock().expect("lock sessions");
+        match sessions.remove(token) {
+            Some(session) => session.expires_at > self.clock.now(),
+            None => false,
+        }
+    }
+
+    /// Returns the enckey of the session of the wallet
+    pub fn resolve(&self, name: &str, token: &str) -> Option<SecKey> {
+        let sessions = self.sessions.lock().expect("lock sessions");
+        sessions
+            .get(token)
+            .filter(|session| session.name == name && session.expires_at > self.clock.now())
+            .map(|session| session.enckey.clone())
+    }
+
+    /// Drops the expired sessions (and their enckeys)
+    pub fn purge_expired(&self) {
+        let now = self.clock.now();
+        let mut sessions = self.sessions.lock().expect("lock sessions");
+        sessions.retain(|_, session| session.expires_at > now);
+    }
+
+    /// Replaces session tokens in the wallet requests of the parameters by their enckeys
+    fn substitute_tokens(&self, params: &mut Params) {
+        let values = match params {
+            Params::Array(values) => values,
+            _ => return,
+        };
+        for value in values.iter_mut() {
+            let request = match value {
+                Value::Object(request) => request,
+                _ => continue,
+            };
+            let name = match request.get("name") {
+                Some(Value::String(name)) => name.clone(),
+                _ => continue,
+            };
+            for field in ENCKEY_FIELDS {
+                if let Some(Value::String(token)) = request.get_mut(*field) {
+                    if let Some(enckey) = self.resolve(&name, token) {
+                        *token = hex::encode(enckey.unsecure());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Substitutes the enckeys of unlocked sessions for their tokens in wallet requests
+#[derive(Clone, Default)]
+pub struct SessionMiddleware {
+    sessions: Sessions,
+}
+
+impl SessionMiddleware {
+    pub fn new(sessions: Sessions) -> Self {
+        SessionMiddleware { sessions }
+    }
+}
+
+impl<M: Metadata> Middleware<M> for SessionMiddleware {
+    type Future = NoopFuture;
+    type CallFuture = NoopCallFuture;
+
+    fn on_call<F, X>(&self, mut call: Call, meta: M, next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, M) -> X + Send + Sync,
+        X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+    {
+        if let Call::MethodCall(method_call) = &mut call {
+            self.sessions.substitute_tokens(&mut method_call.params);
+        }
+        Either::B(next(call, meta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client_common::clock::MockClock;
+    use serde_json::json;
+
+    fn enckey(byte: u8) -> SecKey {
+        hex::encode([byte; 32]).parse().unwrap()
+    }
+
+    #[test]
+    fn check_session_lifetime() {
+        let clock = MockClock::new(1_000);
+        let sessions = Sessions::with_clock(clock.clone());
+        let token = sessions.unlock("Default", enckey(1), Duration::from_secs(60));
+        assert_eq!(sessions.resolve("Default", &token), Some(enckey(1)));
+        // the token is bound to the wallet
+        assert_eq!(sessions.resolve("Other", &token), None);
+
+        assert!(sessions.lock(&token));
+        assert_eq!(sessions.resolve("Default", &token), None);
+        assert!(!sessions.lock(&token));
+
+        let token = sessions.unlock("Default", enckey(1), Duration::from_secs(60));
+        let other_token = sessions.unlock("Default", enckey(1), Duration::from_secs(120));
+        clock.advance(59);
+        assert_eq!(sessions.resolve("Default", &token), Some(enckey(1)));
+        clock.advance(1);
+        assert_eq!(sessions.resolve("Default", &token), None);
+        assert_eq!(sessions.resolve("Default", &other_token), Some(enckey(1)));
+
+        sessions.purge_expired();
+        assert_eq!(sessions.sessions.lock().unwrap().len(), 1);
+        // an expired session can't be ended anymore
+        clock.advance(60);
+        assert!(!sessions.lock(&other_token));
+        assert!(sessions.sessions.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn check_substitute_tokens() {
+        let sessions = Sessions::with_clock(MockClock::default());
+        let token = sessions.unlock("Default", enckey(1), Duration::from_secs(60));
+        let plain_enckey = hex::encode([2; 32]);
+        let mut params = Params::Array(vec![
+            json!({"name": "Default", "enckey": token}),
+            json!({"name": "Default", "auth_token": token}),
+            json!({"name": "Other", "enckey": token}),
+            json!({"name": "Default", "enckey": plain_enckey}),
+            json!(token),
+        ]);
+        sessions.substitute_tokens(&mut params);
+
+        let expected = hex::encode([1; 32]);
+        assert_eq!(
+            params,
+            Params::Array(vec![
+                json!({"name": "Default", "enckey": expected}),
+                json!({"name": "Default", "auth_token": expected}),
+                json!({"name": "Other", "enckey": token}),
+                json!({"name": "Default", "enckey": plain_enckey}),
+                json!(token),
+            ])
+        );
+    }
+}
@@ -4,8 +4,9 @@ use std::ptr;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+use chain_core::init::coin::Coin;
 use client_common::Result;
-use client_core::wallet::syncer::SyncerOptions;
+use client_core::wallet::syncer::{SyncerOptions, DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT};
 use client_rpc_core::{
     rpc::sync_rpc::{CBindingCallback, CBindingCore},
     RpcHandler,
@@ -218,6 +219,7 @@ unsafe fn create_rpc(
         enable_fast_forward: false,
         disable_light_client: true,
         enable_address_recovery: true,
+        address_recovery_gap_limit: DEFAULT_ADDRESS_RECOVERY_GAP_LIMIT,
         batch_size: 50,
         block_height_ensure: 50,
         light_client_peers: "0000000000000000000000000000000000000000@127.0.0.1:26657,1000000000000000000000000000000000000000@127.0.0.1:26657"
@@ -225,6 +227,7 @@ unsafe fn create_rpc(
         light_client_trusting_period_seconds:3_600_000_000_000,
         light_client_trusting_height: 1,
         light_client_trusting_blockhash: "".into(),
+        sync_from_now: false,
     };
     let handler = RpcHandler::new(
         &storage_dir,
@@ -232,6 +235,8 @@ unsafe fn create_rpc(
         network_id,
         options,
         cbindingcallback.clone(),
+        false,
+        Coin::zero(),
     )?;
 
     Ok(CroJsonRpc {
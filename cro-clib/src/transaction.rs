@@ -7,9 +7,7 @@ use chain_core::state::account::{
     StakedState, StakedStateAddress, StakedStateOpAttributes, StakedStateOpWitness, UnbondTx,
     WithdrawUnbondedTx,
 };
-use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
-use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::output::TxOut;
 use chain_core::tx::{TxAux, TxPublicAux};
 use client_common::cipher::DefaultTransactionObfuscation;
@@ -18,9 +16,9 @@ use client_common::tendermint::{Client, WebsocketRpcClient};
 use client_common::TransactionObfuscation;
 use client_common::{ErrorKind, Result, ResultExt, Transaction};
 use client_common::{PrivateKeyAction, PublicKey, SignedTransaction};
+use client_core::transaction_builder::AttributesBuilder;
 use parity_scale_codec::Decode;
 use parity_scale_codec::Encode;
-use std::collections::BTreeSet;
 use std::os::raw::c_char;
 use std::ptr;
 use std::str::FromStr;
@@ -123,18 +121,13 @@ fn create_encoded_signed_withdraw(
     let staked_state = mstaking.err_kind(ErrorKind::InvalidInput, || {
         "staking address not found, sync to the latest and check staking address is correct"
     })?;
-    let access_policies = viewkeys
+    let view_keys = viewkeys
         .iter()
-        .map(|s| {
-            let view_key = s.trim();
-            let public_key = PublicKey::from_str(view_key)?;
-            Ok(TxAccessPolicy {
-                view_key: public_key.into(),
-                access: TxAccess::AllData,
-            })
-        })
-        .collect::<Result<BTreeSet<_>>>()?;
-    let attributes = TxAttributes::new_with_access(network, access_policies.into_iter().collect());
+        .map(|s| PublicKey::from_str(s.trim()))
+        .collect::<Result<Vec<_>>>()?;
+    let attributes = AttributesBuilder::with_network_id(network)
+        .view_keys(view_keys)
+        .build()?;
     let nonce = staked_state.nonce;
     let amount = staked_state.unbonded;
     let outputs = vec![TxOut::new_with_timelock(
@@ -191,6 +191,12 @@ class Wallet:
     def delete(self, name=DEFAULT_WALLET, passphrase=None):
         return self.client.call('wallet_delete', [name, passphrase or get_passphrase()])
 
+    def change_passphrase(self, new_passphrase, name=DEFAULT_WALLET, passphrase=None):
+        return self.client.call(
+            'wallet_changePassphrase',
+            [name, passphrase or get_passphrase()], new_passphrase
+        )
+
     def view_key(self, name=DEFAULT_WALLET, private=False, enckey=None):
         return self.client.call(
             'wallet_getViewKey',
@@ -221,6 +227,9 @@ class Wallet:
     def sync_stop(self, name=DEFAULT_WALLET, enckey=None):
         return self.client.call('sync_stop', [name, enckey or get_enckey()])
 
+    def rescan(self, from_height, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call('wallet_rescan', [name, enckey or get_enckey()], from_height)
+
     def build_raw_transfer_tx(self, to_address, amount, name=DEFAULT_WALLET,  enckey=None, viewkeys=[]):
         """
         build a raw transfer tx on watch-only wallet
@@ -249,6 +258,9 @@ class Staking:
     def state(self, address, name=DEFAULT_WALLET):
         return self.client.call('staking_state', name, fix_address(address))
 
+    def account_state(self, address):
+        return self.client.call('staking_accountState', fix_address(address))
+
     def unbond(self, address, amount, name=DEFAULT_WALLET, enckey=None):
         return self.client.call('staking_unbondStake', [name, enckey or get_enckey()], fix_address(address), str(amount))
 
@@ -259,6 +271,19 @@ class Staking:
             fix_address(from_address), to_address, view_keys or []
         )
 
+    def build_deposit(self, to_address, inputs, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call('staking_buildDepositStake', [name, enckey or get_enckey()], fix_address(to_address), inputs)
+
+    def build_unbond(self, address, amount, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call('staking_buildUnbondStake', [name, enckey or get_enckey()], fix_address(address), str(amount))
+
+    def build_withdraw_all_unbonded(self, from_address, to_address, view_keys=None, name=DEFAULT_WALLET, enckey=None):
+        return self.client.call(
+            'staking_buildWithdrawAllUnbondedStake',
+            [name, enckey or get_enckey()],
+            fix_address(from_address), to_address, view_keys or []
+        )
+
     def unjail(self, address, name=DEFAULT_WALLET, enckey=None):
         return self.client.call('staking_unjail', [name, enckey or get_enckey()], fix_address(address))
 
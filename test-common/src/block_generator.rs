@@ -5,6 +5,7 @@ use std::sync::{Arc, RwLock};
 use std::time::{Duration, UNIX_EPOCH};
 
 use client_common::tendermint::types::BlockResultsResponse;
+use parity_scale_codec::Encode;
 use secstr::SecUtf8;
 use signatory::ed25519;
 use signatory::public_key::PublicKeyed;
@@ -30,6 +31,7 @@ use chain_core::init::{
     address::RedeemAddress, coin::Coin, config::InitConfig, network::Network, params,
 };
 use chain_core::state::account::{CouncilNodeMeta, StakedStateAddress, StakedStateDestination};
+use chain_core::state::compact_block::CompactBlock;
 use chain_core::state::tendermint::{
     TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
 };
@@ -38,7 +40,9 @@ use chain_core::tx::fee::{LinearFee, Milli};
 use chain_core::tx::TxAux;
 use chain_storage::buffer::MemStore;
 use chain_storage::jellyfish::{put_stakings, StakingGetter};
-use client_common::tendermint::types::{AbciQuery, BroadcastTxResponse, Genesis};
+use client_common::tendermint::types::{
+    AbciQuery, BroadcastTxResponse, Genesis, UnconfirmedTxsResponse,
+};
 use client_common::tendermint::Client;
 use client_common::Result;
 use client_core::wallet::syncer::Handle;
@@ -272,6 +276,7 @@ impl TestnetSpec {
                 self.expansion_cap,
             ),
             council_nodes,
+            app_version: chain_core::APP_VERSION,
         }
     }
 
@@ -279,6 +284,7 @@ impl TestnetSpec {
         let mut store = MemStore::new();
 
         let config = self.init_config();
+        let app_version = config.app_version;
         let genesis_seconds = self
             .genesis_time
             .duration_since(Time::unix_epoch())
@@ -349,6 +355,7 @@ impl TestnetSpec {
             network_params,
             staking_table,
             genesis_state.isv_svn,
+            app_version,
         );
 
         (genesis, state)
@@ -573,14 +580,38 @@ impl Client for GeneratorClient {
         unreachable!();
     }
 
+    fn broadcast_transaction_batch<'a, T: Iterator<Item = &'a [u8]>>(
+        &self,
+        _transactions: T,
+    ) -> Result<Vec<Result<BroadcastTxResponse>>> {
+        unreachable!();
+    }
+
+    fn unconfirmed_txs(&self, _limit: u64) -> Result<UnconfirmedTxsResponse> {
+        unreachable!();
+    }
+
     fn query(
         &self,
-        _path: &str,
+        path: &str,
         _data: &[u8],
-        _height: Option<Height>,
+        height: Option<Height>,
         _prove: bool,
     ) -> Result<AbciQuery> {
-        unreachable!();
+        match path {
+            "compact-block" => {
+                let height = height.expect("compact block height").value();
+                let gen = self.gen.read().unwrap();
+                let state = &gen.blocks[(height as usize).checked_sub(1).unwrap()].state;
+                // generated blocks don't contain any transactions
+                let compact_block = CompactBlock::new(height.into(), state.last_apphash, None, &[]);
+                Ok(AbciQuery {
+                    value: compact_block.encode(),
+                    ..Default::default()
+                })
+            }
+            _ => unreachable!(),
+        }
     }
 
     fn query_state_batch<T: Iterator<Item = u64>>(&self, heights: T) -> Result<Vec<ChainState>> {
@@ -599,21 +630,32 @@ impl Client for GeneratorClient {
     }
 }
 
-impl Handle for GeneratorClient {
-    fn verify_to_highest(&self) -> result::Result<LightBlock, Error> {
-        Ok(LightBlock {
-            signed_header: self
-                .gen
-                .read()
-                .unwrap()
-                .blocks
-                .last()
-                .unwrap()
-                .signed_header(),
+impl GeneratorClient {
+    fn light_block(&self, signed_header: SignedHeader) -> LightBlock {
+        LightBlock {
+            signed_header,
             validators: validator::Set::new(vec![]),
             next_validators: validator::Set::new(vec![]),
             provider: "BADFADAD0BEFEEDC0C0ADEADBEEFC0FFEEFACADE".parse().unwrap(),
-        })
+        }
+    }
+}
+
+impl Handle for GeneratorClient {
+    fn verify_to_highest(&self) -> result::Result<LightBlock, Error> {
+        let signed_header = self
+            .gen
+            .read()
+            .unwrap()
+            .blocks
+            .last()
+            .unwrap()
+            .signed_header();
+        Ok(self.light_block(signed_header))
+    }
+    fn verify_to_target(&self, height: u64) -> result::Result<LightBlock, Error> {
+        let signed_header = self.gen.read().unwrap().signed_header(Height::from(height));
+        Ok(self.light_block(signed_header))
     }
     fn terminate(&self) -> result::Result<(), Error> {
         Ok(())
@@ -19,17 +19,17 @@ use chain_core::init::address::RedeemAddress;
 use chain_core::init::coin::Coin;
 use chain_core::init::config::{
     InitConfig, InitNetworkParameters, JailingParameters, NetworkParameters, RewardsParameters,
-    SlashRatio, SlashingParameters,
+    ScheduledParametersUpdate, SlashRatio, SlashingParameters,
 };
 use chain_core::state::account::{
     ConfidentialInit, CouncilNodeMeta, MLSInit, NodeMetadata, NodeName, NodeSecurityContact,
-    NodeState, StakedState, StakedStateAddress, StakedStateDestination, StakedStateOpAttributes,
-    StakedStateOpWitness, UnbondTx, Validator as ChainValidator,
+    NodeState, Nonce, StakedState, StakedStateAddress, StakedStateDestination,
+    StakedStateOpAttributes, StakedStateOpWitness, UnbondTx, Validator as ChainValidator,
 };
 use chain_core::state::tendermint::{
     TendermintValidatorAddress, TendermintValidatorPubKey, TendermintVotePower,
 };
-use chain_core::state::validator::NodeJoinRequestTx;
+use chain_core::state::validator::{NetworkParamsUpdateTx, NodeJoinRequestTx};
 use chain_core::tx::fee::{LinearFee, Milli};
 use chain_core::tx::witness::EcdsaSignature;
 use chain_core::tx::{data::TxId, TransactionId, TxAux, TxPublicAux};
@@ -378,6 +378,27 @@ impl ChainEnv {
         TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, witness))
     }
 
+    pub fn params_update_tx(
+        &self,
+        update: ScheduledParametersUpdate,
+        nonce: Nonce,
+        account_indexes: &[usize],
+    ) -> TxAux {
+        let tx = NetworkParamsUpdateTx::new(update, nonce, StakedStateOpAttributes::new(0));
+        let secp = secp256k1::SECP256K1;
+        let witnesses = account_indexes
+            .iter()
+            .map(|index| {
+                StakedStateOpWitness::new(get_ecdsa_witness(
+                    &secp,
+                    &tx.id(),
+                    &self.accounts[*index].secret_key,
+                ))
+            })
+            .collect();
+        TxAux::PublicTx(TxPublicAux::NetworkParamsUpdateTx(tx, witnesses))
+    }
+
     pub fn req_init_chain(&self) -> RequestInitChain {
         let share = Coin::new(u64::from(self.dist_coin) / self.accounts.len() as u64).unwrap();
         let validators = self